@@ -3,11 +3,15 @@
 use crate::dirs::{KnownPeersPath, PlatformPath};
 use clap::Args;
 use reth_discv4::bootnodes::mainnet_nodes;
+use reth_discv5::{Discv5Config, Enr, DEFAULT_DISCOVERY_V5_PORT};
 use reth_net_nat::NatResolver;
-use reth_network::NetworkConfigBuilder;
+use reth_network::{BandwidthLimits, NetworkConfigBuilder};
 use reth_primitives::{ChainSpec, NodeRecord};
 use reth_staged_sync::Config;
-use std::path::PathBuf;
+use std::{
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::PathBuf,
+};
 
 /// Parameters for configuring the network more granularity via CLI
 #[derive(Debug, Args)]
@@ -32,8 +36,9 @@ pub struct NetworkArgs {
     #[arg(long, value_delimiter = ',')]
     pub bootnodes: Option<Vec<NodeRecord>>,
 
-    /// The path to the known peers file. Connected peers are
-    /// dumped to this file on node shutdown, and read on startup.
+    /// The path to the known peers file. Known peers and their
+    /// reputation are saved to this file periodically and on node
+    /// shutdown, and read on startup.
     /// Cannot be used with --no-persist-peers
     #[arg(long, value_name = "FILE", verbatim_doc_comment, default_value_t)]
     pub peers_file: PlatformPath<KnownPeersPath>,
@@ -42,9 +47,29 @@ pub struct NetworkArgs {
     #[arg(long, verbatim_doc_comment, conflicts_with = "peers_file")]
     pub no_persist_peers: bool,
 
-    /// NAT resolution method.
+    /// NAT resolution method (any|none|upnp|natpmp[:<gateway>]|publicip|extip:<IP>).
+    ///
+    /// With upnp, natpmp and any the discovery and RLPx ports are also mapped on the gateway.
     #[arg(long, default_value = "any")]
     pub nat: NatResolver,
+
+    /// Maximum upload rate of all peers in KiB/s.
+    ///
+    /// Serving requests of peers is throttled first, so that gossip can still be sent.
+    #[arg(long, value_name = "KIB_PER_SEC")]
+    pub max_upload: Option<u64>,
+
+    /// Maximum download rate of all peers in KiB/s.
+    #[arg(long, value_name = "KIB_PER_SEC")]
+    pub max_download: Option<u64>,
+
+    /// Maximum upload rate of a single peer in KiB/s.
+    #[arg(long, value_name = "KIB_PER_SEC")]
+    pub max_upload_per_peer: Option<u64>,
+
+    /// Maximum download rate of a single peer in KiB/s.
+    #[arg(long, value_name = "KIB_PER_SEC")]
+    pub max_download_per_peer: Option<u64>,
 }
 
 impl NetworkArgs {
@@ -55,6 +80,7 @@ impl NetworkArgs {
         let network_config_builder = config
             .network_config(self.nat, peers_file.map(|f| f.as_ref().to_path_buf()))
             .boot_nodes(self.bootnodes.clone().unwrap_or_else(mainnet_nodes))
+            .bandwidth_limits(self.bandwidth_limits())
             .chain_spec(chain_spec);
 
         self.discovery.apply_to_builder(network_config_builder)
@@ -71,6 +97,25 @@ impl NetworkArgs {
         }
         Some(self.peers_file.clone().into())
     }
+
+    /// Returns the configured [`BandwidthLimits`] in bytes per second.
+    pub fn bandwidth_limits(&self) -> BandwidthLimits {
+        let bytes_per_sec = |kib: u64| kib.saturating_mul(1024);
+        let mut limits = BandwidthLimits::default();
+        if let Some(kib) = self.max_upload {
+            limits = limits.with_max_upload(bytes_per_sec(kib));
+        }
+        if let Some(kib) = self.max_download {
+            limits = limits.with_max_download(bytes_per_sec(kib));
+        }
+        if let Some(kib) = self.max_upload_per_peer {
+            limits = limits.with_max_upload_per_peer(bytes_per_sec(kib));
+        }
+        if let Some(kib) = self.max_download_per_peer {
+            limits = limits.with_max_download_per_peer(bytes_per_sec(kib));
+        }
+        limits
+    }
 }
 
 /// Arguments to setup discovery
@@ -87,6 +132,19 @@ pub struct DiscoveryArgs {
     /// Disable Discv4 discovery.
     #[arg(long, conflicts_with = "disable_discovery")]
     disable_discv4_discovery: bool,
+
+    /// Enable Discv5 discovery, which runs next to Discv4 on a separate UDP port.
+    #[arg(long, conflicts_with = "disable_discovery")]
+    enable_discv5_discovery: bool,
+
+    /// The UDP port to use for Discv5 discovery.
+    #[arg(long, value_name = "PORT", default_value_t = DEFAULT_DISCOVERY_V5_PORT)]
+    discv5_port: u16,
+
+    /// ENRs of the nodes to bootstrap Discv5 discovery from.
+    /// --discv5-bootnodes enr:-IS4QHCYrYZbAKWC...
+    #[arg(long, value_delimiter = ',', verbatim_doc_comment)]
+    discv5_bootnodes: Vec<Enr>,
 }
 
 impl DiscoveryArgs {
@@ -102,6 +160,17 @@ impl DiscoveryArgs {
         if self.disable_discovery || self.disable_discv4_discovery {
             network_config_builder = network_config_builder.disable_discv4_discovery();
         }
+
+        if self.enable_discv5_discovery {
+            let mut discv5 = Discv5Config::builder();
+            discv5.add_boot_nodes(self.discv5_bootnodes.clone());
+            network_config_builder = network_config_builder
+                .discovery_v5(discv5)
+                .discovery_v5_addr(SocketAddr::V4(SocketAddrV4::new(
+                    Ipv4Addr::UNSPECIFIED,
+                    self.discv5_port,
+                )));
+        }
         network_config_builder
     }
 }
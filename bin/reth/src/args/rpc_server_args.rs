@@ -7,8 +7,8 @@ use reth_network_api::{NetworkInfo, Peers};
 use reth_provider::{BlockProvider, EvmEnvProvider, HeaderProvider, StateProviderFactory};
 use reth_rpc::{JwtError, JwtSecret};
 use reth_rpc_builder::{
-    constants, IpcServerBuilder, RethRpcModule, RpcModuleSelection, RpcServerConfig,
-    RpcServerHandle, ServerBuilder, TransportRpcModuleConfig,
+    constants, EthConfig, IpcServerBuilder, RethRpcModule, RpcModuleConfig, RpcModuleSelection,
+    RpcServerConfig, RpcServerHandle, ServerBuilder, TransportRpcModuleConfig,
 };
 use reth_rpc_engine_api::EngineApiHandle;
 use reth_tasks::TaskSpawner;
@@ -110,6 +110,7 @@ impl RpcServerArgs {
         pool: Pool,
         network: Network,
         executor: Tasks,
+        eth_config: EthConfig,
     ) -> Result<RpcServerHandle, RpcError>
     where
         Client: BlockProvider
@@ -127,7 +128,8 @@ impl RpcServerArgs {
             client,
             pool,
             network,
-            self.transport_rpc_module_config(),
+            self.transport_rpc_module_config()
+                .with_config(RpcModuleConfig::builder().eth(eth_config).build()),
             self.rpc_server_config(),
             executor,
         )
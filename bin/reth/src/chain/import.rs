@@ -144,6 +144,7 @@ impl ImportCommand {
 
         let mut pipeline = Pipeline::builder()
             .with_sync_state_updater(file_client)
+            .with_commit_policy(config.stages.commit.into())
             .add_stages(
                 DefaultStages::new(
                     consensus.clone(),
@@ -1,6 +1,6 @@
 //! Database debugging tool
 use crate::dirs::{DbPath, PlatformPath};
-use clap::{Parser, Subcommand};
+use clap::{Parser, Subcommand, ValueEnum};
 use comfy_table::{Cell, Row, Table as ComfyTable};
 use eyre::{Result, WrapErr};
 use human_bytes::human_bytes;
@@ -8,13 +8,18 @@ use reth_db::{
     cursor::{DbCursorRO, Walker},
     database::Database,
     table::Table,
-    tables,
-    transaction::DbTx,
+    tables::{self, codecs::dictionary::train_dictionary},
+    transaction::{DbTx, DbTxMut},
 };
 use reth_interfaces::test_utils::generators::random_block_range;
-use reth_provider::insert_canonical_block;
-use std::collections::BTreeMap;
-use tracing::{error, info};
+use reth_primitives::H256;
+use reth_provider::{account_bytecode_hash, insert_canonical_block};
+use reth_stages::stages::EXECUTION;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::PathBuf,
+};
+use tracing::{error, info, warn};
 
 /// DB List TUI
 mod tui;
@@ -53,6 +58,15 @@ pub enum Subcommands {
     },
     /// Deletes all database entries
     Drop,
+    /// Recomputes the reference counts of the contract bytecodes from the accounts and compares
+    /// them to the stored counts
+    VerifyBytecodes {
+        /// Rewrite the stored reference counts if they do not match
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Trains a zstd dictionary on values sampled evenly from a table
+    TrainDictionary(TrainDictionaryArgs),
 }
 
 #[derive(Parser, Debug)]
@@ -68,6 +82,29 @@ pub struct ListArgs {
     len: usize,
 }
 
+#[derive(Parser, Debug)]
+/// The arguments for the `reth db train-dictionary` command
+pub struct TrainDictionaryArgs {
+    /// The table to sample
+    #[arg(value_enum)]
+    table: DictionaryTable,
+    /// The file to write the dictionary to
+    output: PathBuf,
+    /// How many values to sample
+    #[arg(long, default_value = "100000")]
+    samples: usize,
+    /// The maximum size of the dictionary in bytes
+    #[arg(long, default_value = "16384")]
+    max_size: usize,
+}
+
+/// The tables whose values are compressed with a dictionary.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, ValueEnum)]
+enum DictionaryTable {
+    Transactions,
+    Receipts,
+}
+
 impl Command {
     /// Execute `db` command
     pub async fn execute(&self) -> eyre::Result<()> {
@@ -178,12 +215,54 @@ impl Command {
                     BlockTransitionIndex,
                     TxTransitionIndex,
                     SyncStage,
+                    SyncStageProgress,
+                    PruneCheckpoints,
+                    BytecodeRefCounts,
+                    UnreferencedBytecodes,
                     Transactions
                 ]);
             }
             Subcommands::Drop => {
                 tool.drop(&self.db)?;
             }
+            Subcommands::VerifyBytecodes { fix } => {
+                tool.verify_bytecodes(*fix)?;
+            }
+            Subcommands::TrainDictionary(args) => {
+                let table = match args.table {
+                    DictionaryTable::Transactions => tables::Transactions::NAME,
+                    DictionaryTable::Receipts => tables::Receipts::NAME,
+                };
+                let entries = tool.db.view(|tx| {
+                    let table_db = tx.inner.open_db(Some(table)).wrap_err("Could not open db.")?;
+                    let stats = tx
+                        .inner
+                        .db_stat(&table_db)
+                        .wrap_err(format!("Could not find table: {table}"))?;
+                    Ok::<_, eyre::Report>(stats.entries())
+                })??;
+                // Every `step`th value, so the samples cover the whole history.
+                let step = (entries / args.samples.max(1)).max(1);
+
+                let dictionary = match args.table {
+                    DictionaryTable::Transactions => train_dictionary(
+                        tool.sample::<tables::Transactions>(step, args.samples)?,
+                        args.max_size,
+                    ),
+                    DictionaryTable::Receipts => train_dictionary(
+                        tool.sample::<tables::Receipts>(step, args.samples)?,
+                        args.max_size,
+                    ),
+                }
+                .wrap_err("Training the dictionary failed")?;
+                std::fs::write(&args.output, &dictionary)?;
+                info!(
+                    target: "reth::cli",
+                    size = dictionary.len(),
+                    output = %args.output.display(),
+                    "Dictionary written"
+                );
+            }
         }
 
         Ok(())
@@ -235,9 +314,127 @@ impl<'a, DB: Database> DbTool<'a, DB> {
             .map_err(|e| eyre::eyre!(e))
     }
 
+    /// Takes every `step`th value of the table, up to `count` values.
+    fn sample<T: Table>(&mut self, step: usize, count: usize) -> Result<Vec<T::Value>> {
+        self.db.view(|tx| {
+            tx.cursor_read::<T>()?
+                .walk(None)?
+                .step_by(step)
+                .take(count)
+                .map(|entry| entry.map(|(_, value)| value).map_err(Into::into))
+                .collect::<Result<Vec<_>>>()
+        })?
+    }
+
+    /// Recomputes the reference counts of the bytecodes from [tables::PlainAccountState] and
+    /// reports the entries of [tables::BytecodeRefCounts] that do not match, the bytecodes that
+    /// are neither used nor in [tables::UnreferencedBytecodes], as well as the bytecodes that are
+    /// used but missing.
+    ///
+    /// With `fix`, the reference counts are rewritten and every bytecode that is not used by any
+    /// account is scheduled for removal at the block of the last execution.
+    fn verify_bytecodes(&mut self, fix: bool) -> Result<()> {
+        let report = self.db.view(|tx| {
+            let mut expected = BTreeMap::<H256, u64>::new();
+            for entry in tx.cursor_read::<tables::PlainAccountState>()?.walk(None)? {
+                let (_, account) = entry?;
+                if let Some(hash) = account_bytecode_hash(&account) {
+                    *expected.entry(hash).or_default() += 1;
+                }
+            }
+
+            let stored = tx
+                .cursor_read::<tables::BytecodeRefCounts>()?
+                .walk(None)?
+                .collect::<Result<BTreeMap<_, _>, _>>()?;
+            let unreferenced = tx
+                .cursor_read::<tables::UnreferencedBytecodes>()?
+                .walk(None)?
+                .map(|entry| entry.map(|(_, hash)| hash))
+                .collect::<Result<BTreeSet<_>, _>>()?;
+
+            let mut unused = Vec::new();
+            for entry in tx.cursor_read::<tables::Bytecodes>()?.walk(None)? {
+                let (hash, _) = entry?;
+                if !expected.contains_key(&hash) && !unreferenced.contains(&hash) {
+                    unused.push(hash);
+                }
+            }
+
+            let mut missing = Vec::new();
+            for hash in expected.keys() {
+                if tx.get::<tables::Bytecodes>(*hash)?.is_none() {
+                    missing.push(*hash);
+                }
+            }
+
+            Ok::<_, eyre::Report>(BytecodeReport { expected, stored, unused, missing })
+        })??;
+
+        let mismatches = report
+            .expected
+            .keys()
+            .chain(report.stored.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter(|hash| report.expected.get(hash) != report.stored.get(hash))
+            .collect::<Vec<_>>();
+        for hash in &mismatches {
+            warn!(
+                target: "reth::cli",
+                ?hash,
+                expected = report.expected.get(hash).copied().unwrap_or_default(),
+                stored = report.stored.get(hash).copied().unwrap_or_default(),
+                "Bytecode reference count mismatch"
+            );
+        }
+        for hash in &report.missing {
+            error!(target: "reth::cli", ?hash, "Bytecode used by an account is missing");
+        }
+
+        info!(
+            target: "reth::cli",
+            bytecodes = report.expected.len(),
+            mismatches = mismatches.len(),
+            unused = report.unused.len(),
+            missing = report.missing.len(),
+            "Verified bytecode reference counts"
+        );
+
+        if fix && (!mismatches.is_empty() || !report.unused.is_empty()) {
+            self.db.update(|tx| {
+                let block = EXECUTION.get_progress(tx)?.unwrap_or_default();
+
+                tx.clear::<tables::BytecodeRefCounts>()?;
+                for (hash, count) in &report.expected {
+                    tx.put::<tables::BytecodeRefCounts>(*hash, *count)?;
+                }
+                for hash in &report.unused {
+                    tx.put::<tables::UnreferencedBytecodes>(block, *hash)?;
+                }
+                Ok::<_, eyre::Report>(())
+            })??;
+            info!(target: "reth::cli", "Bytecode reference counts rewritten");
+        }
+
+        Ok(())
+    }
+
     fn drop(&mut self, path: &PlatformPath<DbPath>) -> Result<()> {
         info!(target: "reth::cli", "Dropping db at {}", path);
         std::fs::remove_dir_all(path).wrap_err("Dropping the database failed")?;
         Ok(())
     }
 }
+
+/// The result of recomputing the bytecode reference counts, see [DbTool::verify_bytecodes].
+struct BytecodeReport {
+    /// The reference counts computed from the accounts.
+    expected: BTreeMap<H256, u64>,
+    /// The stored reference counts.
+    stored: BTreeMap<H256, u64>,
+    /// The bytecodes that are neither used nor scheduled for removal.
+    unused: Vec<H256>,
+    /// The bytecodes that are used by an account but missing.
+    missing: Vec<H256>,
+}
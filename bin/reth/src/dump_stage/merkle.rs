@@ -88,11 +88,19 @@ async fn unwind_and_copy<DB: Database>(
         .await?;
 
     // Bring hashes to TO
-    AccountHashingStage { clean_threshold: u64::MAX, commit_threshold: u64::MAX }
+    AccountHashingStage {
+        clean_threshold: u64::MAX,
+        commit_threshold: u64::MAX,
+        ..Default::default()
+    }
         .execute(&mut unwind_tx, execute_input)
         .await
         .unwrap();
-    StorageHashingStage { clean_threshold: u64::MAX, commit_threshold: u64::MAX }
+    StorageHashingStage {
+        clean_threshold: u64::MAX,
+        commit_threshold: u64::MAX,
+        ..Default::default()
+    }
         .execute(&mut unwind_tx, execute_input)
         .await
         .unwrap();
@@ -124,6 +132,7 @@ async fn run(
 
     MerkleStage::Execution {
         clean_threshold: u64::MAX, // Forces updating the root instead of calculating from scratch
+        commit_threshold: u64::MAX,
     }
     .execute(
         &mut tx,
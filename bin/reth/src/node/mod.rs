@@ -13,7 +13,8 @@ use events::NodeEvent;
 use eyre::Context;
 use fdlimit::raise_fd_limit;
 use futures::{pin_mut, stream::select as stream_select, Stream, StreamExt};
-use reth_consensus::beacon::BeaconConsensus;
+use reth_auto_seal_consensus::{AutoSealBuilder, MiningMode};
+use reth_consensus::chain::ChainConsensus;
 use reth_db::{
     database::Database,
     mdbx::{Env, WriteMap},
@@ -33,11 +34,18 @@ use reth_interfaces::{
     sync::SyncStateUpdater,
 };
 use reth_network::{
-    error::NetworkError, FetchClient, NetworkConfig, NetworkHandle, NetworkManager,
+    error::NetworkError, snap_requests::SnapFetchClient, FetchClient, NetworkConfig, NetworkHandle,
+    NetworkManager,
 };
 use reth_network_api::NetworkInfo;
-use reth_primitives::{BlockHashOrNumber, ChainSpec, Head, H256};
-use reth_provider::{BlockProvider, HeaderProvider, ShareableDatabase};
+use reth_primitives::{
+    eip4844::load_trusted_setup, BlockHashOrNumber, ChainSpec, Head, DEV, H256,
+};
+use reth_provider::{
+    BlockProvider, HeaderProvider, ShareableDatabase, StateCache, StateProviderFactory,
+    StateRangeProvider,
+};
+use reth_rpc_builder::EthConfig;
 use reth_rpc_engine_api::{EngineApi, EngineApiHandle};
 use reth_staged_sync::{
     utils::{
@@ -49,10 +57,21 @@ use reth_staged_sync::{
 };
 use reth_stages::{
     prelude::*,
-    stages::{ExecutionStage, SenderRecoveryStage, TotalDifficultyStage, FINISH},
+    stages::{
+        AccountHashingStage, ExecutionStage, PruneStage, SenderRecoveryStage, StaticFileStage,
+        StorageHashingStage, TotalDifficultyStage, FINISH,
+    },
+    WorkerPool,
 };
+use reth_static_files::StaticFiles;
 use reth_tasks::TaskExecutor;
-use std::{net::SocketAddr, path::PathBuf, sync::Arc};
+use reth_transaction_pool::{CostOrdering, EthTransactionValidator, Pool, PooledTransaction};
+use std::{
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
 use tokio::sync::{mpsc::unbounded_channel, watch};
 use tracing::*;
 
@@ -83,6 +102,7 @@ pub struct Command {
     /// - mainnet
     /// - goerli
     /// - sepolia
+    /// - dev
     #[arg(
         long,
         value_name = "CHAIN_OR_PATH",
@@ -101,6 +121,14 @@ pub struct Command {
     #[clap(flatten)]
     network: NetworkArgs,
 
+    /// Download the state of a recent block from peers with the `snap` protocol, instead of
+    /// executing all blocks up to it.
+    ///
+    /// This only has an effect if no blocks were executed yet. The history before the downloaded
+    /// block is not available afterwards.
+    #[arg(long = "snap-sync")]
+    snap_sync: bool,
+
     /// Set the chain tip manually for testing purposes.
     ///
     /// NOTE: This is a temporary flag
@@ -115,16 +143,46 @@ pub struct Command {
     #[arg(long = "debug.terminate", help_heading = "Debug")]
     terminate: bool,
 
+    /// Verify the receipts of pre-Byzantium blocks, which commit to the state root after every
+    /// transaction.
+    ///
+    /// The state trie is then updated after every 100 blocks until Byzantium.
+    #[arg(long = "debug.verify-pre-byzantium-receipts", help_heading = "Debug")]
+    verify_pre_byzantium_receipts: bool,
+
     #[clap(flatten)]
     rpc: RpcServerArgs,
+
+    /// Start the node in dev mode.
+    ///
+    /// Instead of syncing from the network, blocks are sealed locally as soon as transactions are
+    /// ready, or every `--dev.block-time` seconds. This uses the `dev` chain with 10 prefunded
+    /// accounts, whose keys are made available to the `eth` namespace.
+    #[arg(long = "dev", help_heading = "Dev")]
+    dev: bool,
+
+    /// Interval in seconds between sealed blocks in dev mode.
+    #[arg(long = "dev.block-time", value_name = "SECONDS", requires = "dev", help_heading = "Dev")]
+    block_time: Option<u64>,
+
+    /// The KZG trusted setup used to verify blob transactions, in the format of the c-kzg-4844
+    /// `trusted_setup.txt`.
+    ///
+    /// Blob transactions are rejected by the transaction pool if this is not set.
+    #[arg(long = "kzg-trusted-setup", value_name = "FILE", verbatim_doc_comment)]
+    kzg_trusted_setup: Option<PathBuf>,
 }
 
 impl Command {
     /// Execute `node` command
     // TODO: RPC
-    pub async fn execute(self, ctx: CliContext) -> eyre::Result<()> {
+    pub async fn execute(mut self, ctx: CliContext) -> eyre::Result<()> {
         info!(target: "reth::cli", "reth {} starting", crate_version!());
 
+        if self.dev {
+            self.chain = DEV.clone();
+        }
+
         // Raise the fd limit of the process.
         // Does not do anything on windows.
         raise_fd_limit();
@@ -134,7 +192,8 @@ impl Command {
 
         info!(target: "reth::cli", path = %self.db, "Opening database");
         let db = Arc::new(init_db(&self.db)?);
-        let shareable_db = ShareableDatabase::new(Arc::clone(&db), self.chain.clone());
+        let static_files = self.open_static_files(&config)?;
+        let shareable_db = self.shareable_db(Arc::clone(&db), static_files.clone());
         info!(target: "reth::cli", "Database opened");
 
         self.start_metrics_endpoint()?;
@@ -143,17 +202,26 @@ impl Command {
 
         init_genesis(db.clone(), self.chain.clone())?;
 
-        let (consensus, forkchoice_state_tx) = self.init_consensus()?;
-        info!(target: "reth::cli", "Consensus engine initialized");
-
         self.init_trusted_nodes(&mut config);
 
         info!(target: "reth::cli", "Connecting to P2P network");
-        let network_config =
-            self.load_network_config(&config, Arc::clone(&db), ctx.task_executor.clone());
-        let network = self.start_network(network_config, &ctx.task_executor, ()).await?;
+        let network_config = self.load_network_config(
+            &config,
+            Arc::clone(&db),
+            static_files.clone(),
+            ctx.task_executor.clone(),
+        );
+        let (network, snap_client) =
+            self.start_network(network_config, &ctx.task_executor, ()).await?;
         info!(target: "reth::cli", peer_id = %network.peer_id(), local_addr = %network.local_addr(), "Connected to P2P network");
 
+        if self.dev {
+            return self.run_dev(ctx, db, shareable_db, network).await
+        }
+
+        let (consensus, forkchoice_state_tx) = self.init_consensus()?;
+        info!(target: "reth::cli", "Consensus engine initialized");
+
         let test_transaction_pool = reth_transaction_pool::test_utils::testing_pool();
         info!(target: "reth::cli", "Test transaction pool initialized");
 
@@ -164,12 +232,18 @@ impl Command {
                 test_transaction_pool.clone(),
                 network.clone(),
                 ctx.task_executor.clone(),
+                EthConfig::default(),
             )
             .await?;
         info!(target: "reth::cli", "Started RPC server");
 
-        let engine_api_handle =
-            self.init_engine_api(Arc::clone(&db), forkchoice_state_tx, &ctx.task_executor);
+        let engine_api_handle = self.init_engine_api(
+            Arc::clone(&db),
+            static_files.clone(),
+            Arc::clone(&consensus),
+            forkchoice_state_tx,
+            &ctx.task_executor,
+        );
         info!(target: "reth::cli", "Engine API handler initialized");
 
         let _auth_server = self
@@ -188,8 +262,10 @@ impl Command {
             .build_networked_pipeline(
                 &mut config,
                 network.clone(),
+                snap_client,
                 &consensus,
                 db.clone(),
+                static_files,
                 &ctx.task_executor,
             )
             .await?;
@@ -221,8 +297,10 @@ impl Command {
         &self,
         config: &mut Config,
         network: NetworkHandle,
+        snap_client: SnapFetchClient,
         consensus: &Arc<dyn Consensus>,
         db: Arc<Env<WriteMap>>,
+        static_files: Option<Arc<StaticFiles>>,
         task_executor: &TaskExecutor,
     ) -> eyre::Result<(Pipeline<Env<WriteMap>, impl SyncStateUpdater>, impl Stream<Item = NodeEvent>)>
     {
@@ -251,9 +329,11 @@ impl Command {
                 config,
                 header_downloader,
                 body_downloader,
+                self.snap_sync.then_some(snap_client),
                 network.clone(),
                 consensus,
                 max_block,
+                static_files,
             )
             .await?;
 
@@ -264,10 +344,82 @@ impl Command {
         Ok((pipeline, events))
     }
 
+    /// Runs the node in dev mode.
+    ///
+    /// Blocks are sealed locally by the auto seal [MiningTask](reth_auto_seal_consensus::MiningTask)
+    /// so neither the pipeline nor the Engine API are started.
+    async fn run_dev(
+        self,
+        ctx: CliContext,
+        db: Arc<Env<WriteMap>>,
+        shareable_db: ShareableDatabase<Arc<Env<WriteMap>>>,
+        network: NetworkHandle,
+    ) -> eyre::Result<()> {
+        let mut validator = EthTransactionValidator::new(shareable_db.clone());
+        if let Some(path) = &self.kzg_trusted_setup {
+            let kzg_settings = load_trusted_setup(path).map_err(|err| {
+                eyre::eyre!("failed to load KZG trusted setup from {}: {err:?}", path.display())
+            })?;
+            validator = validator.with_kzg_settings(Arc::new(kzg_settings));
+        }
+        let transaction_pool: Pool<_, CostOrdering<PooledTransaction>> = Pool::new(
+            Arc::new(validator),
+            Arc::new(CostOrdering::default()),
+            Default::default(),
+        );
+        info!(target: "reth::cli", "Transaction pool initialized");
+
+        let mut builder =
+            AutoSealBuilder::new(Arc::new(self.chain.clone()), db, transaction_pool.clone());
+        if let Some(block_time) = self.block_time {
+            builder = builder.mode(MiningMode::interval(Duration::from_secs(block_time)));
+        }
+        let (_consensus, mining_task) = builder.build();
+        ctx.task_executor.spawn_critical("auto seal mining task", mining_task);
+        info!(target: "reth::cli", "Auto seal consensus initialized");
+
+        let _rpc_server = self
+            .rpc
+            .start_rpc_server(
+                shareable_db,
+                transaction_pool,
+                network,
+                ctx.task_executor.clone(),
+                EthConfig { dev_accounts: true, ..Default::default() },
+            )
+            .await?;
+        info!(target: "reth::cli", "Started RPC server");
+
+        futures::future::pending().await
+    }
+
     fn load_config(&self) -> eyre::Result<Config> {
         confy::load_path::<Config>(&self.config).wrap_err("Could not load config")
     }
 
+    /// Opens the static files next to the database, if they are enabled.
+    fn open_static_files(&self, config: &Config) -> eyre::Result<Option<Arc<StaticFiles>>> {
+        let Some(static_files_conf) = config.static_files else { return Ok(None) };
+        let path = self.db.as_ref().join("static_files");
+        info!(target: "reth::cli", path = %path.display(), "Opening static files");
+        let static_files = StaticFiles::open(path, static_files_conf.compression)
+            .wrap_err("Could not open static files")?;
+        Ok(Some(Arc::new(static_files)))
+    }
+
+    /// Creates a provider over the database that also reads the static files, if there are any.
+    fn shareable_db(
+        &self,
+        db: Arc<Env<WriteMap>>,
+        static_files: Option<Arc<StaticFiles>>,
+    ) -> ShareableDatabase<Arc<Env<WriteMap>>> {
+        let shareable_db = ShareableDatabase::new(db, self.chain.clone());
+        match static_files {
+            Some(static_files) => shareable_db.with_static_files(static_files),
+            None => shareable_db,
+        }
+    }
+
     fn init_trusted_nodes(&self, config: &mut Config) {
         config.peers.connect_trusted_nodes_only = self.network.trusted_only;
 
@@ -289,7 +441,7 @@ impl Command {
     }
 
     fn init_consensus(&self) -> eyre::Result<(Arc<dyn Consensus>, watch::Sender<ForkchoiceState>)> {
-        let (consensus, notifier) = BeaconConsensus::builder().build(self.chain.clone());
+        let (consensus, notifier) = ChainConsensus::builder().build(self.chain.clone());
 
         if let Some(tip) = self.tip {
             debug!(target: "reth::cli", %tip, "Tip manually set");
@@ -311,13 +463,16 @@ impl Command {
     fn init_engine_api(
         &self,
         db: Arc<Env<WriteMap>>,
+        static_files: Option<Arc<StaticFiles>>,
+        consensus: Arc<dyn Consensus>,
         forkchoice_state_tx: watch::Sender<ForkchoiceState>,
         task_executor: &TaskExecutor,
     ) -> EngineApiHandle {
         let (message_tx, message_rx) = unbounded_channel();
         let engine_api = EngineApi::new(
-            ShareableDatabase::new(db, self.chain.clone()),
+            self.shareable_db(db, static_files),
             self.chain.clone(),
+            consensus,
             message_rx,
             forkchoice_state_tx,
         );
@@ -326,20 +481,28 @@ impl Command {
     }
 
     /// Spawns the configured network and associated tasks and returns the [NetworkHandle] connected
-    /// to that network.
+    /// to that network, together with a client that downloads the state of its peers.
     async fn start_network<C>(
         &self,
         config: NetworkConfig<C>,
         task_executor: &TaskExecutor,
         // TODO: integrate pool
         _pool: (),
-    ) -> Result<NetworkHandle, NetworkError>
+    ) -> Result<(NetworkHandle, SnapFetchClient), NetworkError>
     where
-        C: BlockProvider + HeaderProvider + Clone + Unpin + 'static,
+        C: BlockProvider
+            + HeaderProvider
+            + StateRangeProvider
+            + StateProviderFactory
+            + Clone
+            + Unpin
+            + 'static,
     {
         let client = config.client.clone();
-        let (handle, network, _txpool, eth) =
-            NetworkManager::builder(config).await?.request_handler(client).split_with_handle();
+        let (builder, snap) =
+            NetworkManager::builder(config).await?.snap_request_handler(client.clone());
+        let (handle, network, _txpool, eth) = builder.request_handler(client).split_with_handle();
+        let snap_client = snap.fetch_client(handle.peers_handle().clone());
 
         let known_peers_file = self.network.persistent_peers_file();
         task_executor.spawn_critical_with_signal("p2p network task", |shutdown| async move {
@@ -347,10 +510,11 @@ impl Command {
         });
 
         task_executor.spawn_critical("p2p eth request handler", async move { eth.await });
+        task_executor.spawn_critical("p2p snap request handler", async move { snap.await });
 
         // TODO spawn pool
 
-        Ok(handle)
+        Ok((handle, snap_client))
     }
 
     fn lookup_head(&self, db: Arc<Env<WriteMap>>) -> Result<Head, reth_interfaces::db::Error> {
@@ -409,6 +573,7 @@ impl Command {
         &self,
         config: &Config,
         db: Arc<Env<WriteMap>>,
+        static_files: Option<Arc<StaticFiles>>,
         executor: TaskExecutor,
     ) -> NetworkConfig<ShareableDatabase<Arc<Env<WriteMap>>>> {
         let head = self.lookup_head(Arc::clone(&db)).expect("the head block is missing");
@@ -417,7 +582,7 @@ impl Command {
             .network_config(config, self.chain.clone())
             .with_task_executor(Box::new(executor))
             .set_head(head)
-            .build(ShareableDatabase::new(db, self.chain.clone()))
+            .build(self.shareable_db(db, static_files))
     }
 
     async fn build_pipeline<H, B, U>(
@@ -425,9 +590,11 @@ impl Command {
         config: &Config,
         header_downloader: H,
         body_downloader: B,
+        snap_client: Option<SnapFetchClient>,
         updater: U,
         consensus: &Arc<dyn Consensus>,
         max_block: Option<u64>,
+        static_files: Option<Arc<StaticFiles>>,
     ) -> eyre::Result<Pipeline<Env<WriteMap>, U>>
     where
         H: HeaderDownloader + 'static,
@@ -443,34 +610,93 @@ impl Command {
             builder = builder.with_max_block(max_block)
         }
 
+        let pool = match stage_conf.worker_threads {
+            Some(threads) => WorkerPool::new(threads)?,
+            None => WorkerPool::default(),
+        };
+        debug!(target: "reth::cli", threads = pool.threads(), "Configuring stage worker pool");
+
+        let execution_conf = stage_conf.execution;
+        let state_cache = Arc::new(StateCache::new(
+            execution_conf.max_cached_accounts,
+            execution_conf.max_cached_storage_slots,
+            execution_conf.max_cached_bytecodes,
+        ));
+        debug!(target: "reth::cli", ?state_cache, prefetch_threads = execution_conf.prefetch_threads, "Configuring execution state cache");
+
         let factory = reth_executor::Factory::new(Arc::new(self.chain.clone()));
-        let pipeline = builder
-            .with_sync_state_updater(updater.clone())
-            .add_stages(
-                DefaultStages::new(
+        let stages = match snap_client {
+            Some(snap_client) => {
+                debug!(target: "reth::cli", "Configuring builder to snap sync the state");
+                SnapSyncStages::new(
                     consensus.clone(),
                     header_downloader,
                     body_downloader,
-                    updater,
+                    snap_client,
+                    updater.clone(),
                     factory.clone(),
                 )
-                .set(TotalDifficultyStage {
-                    chain_spec: self.chain.clone(),
-                    commit_threshold: stage_conf.total_difficulty.commit_threshold,
-                })
-                .set(SenderRecoveryStage {
-                    commit_threshold: stage_conf.sender_recovery.commit_threshold,
-                })
-                .set(ExecutionStage::new(factory, stage_conf.execution.commit_threshold)),
+                .builder()
+            }
+            None => DefaultStages::new(
+                consensus.clone(),
+                header_downloader,
+                body_downloader,
+                updater.clone(),
+                factory.clone(),
             )
-            .build();
+            .builder(),
+        };
+        let mut builder = builder
+            .with_sync_state_updater(updater)
+            .with_commit_policy(stage_conf.commit.into())
+            .add_stages(
+                stages
+                    .set(TotalDifficultyStage {
+                        chain_spec: self.chain.clone(),
+                        commit_threshold: stage_conf.total_difficulty.commit_threshold,
+                    })
+                    .set(SenderRecoveryStage {
+                        commit_threshold: stage_conf.sender_recovery.commit_threshold,
+                        pool: pool.clone(),
+                    })
+                    .set(
+                        ExecutionStage::new(factory, execution_conf.commit_threshold)
+                            .with_state_cache(state_cache)
+                            .with_prefetch_threads(execution_conf.prefetch_threads)
+                            .with_pre_byzantium_receipt_verification(
+                                self.verify_pre_byzantium_receipts,
+                            ),
+                    )
+                    .set(AccountHashingStage { pool: pool.clone(), ..Default::default() })
+                    .set(StorageHashingStage { pool, ..Default::default() }),
+            );
 
-        Ok(pipeline)
+        if let Some(prune_conf) = config.prune {
+            debug!(target: "reth::cli", parts = ?prune_conf.parts, "Configuring builder to prune history");
+            let mut stage = PruneStage::new(prune_conf.parts, prune_conf.commit_threshold);
+            if let Some(static_files) = &static_files {
+                stage = stage.with_static_files(Arc::clone(static_files));
+            }
+            builder = builder.add_stage(stage);
+        }
+
+        if let (Some(static_files_conf), Some(static_files)) = (config.static_files, static_files) {
+            debug!(target: "reth::cli", blocks_per_file = static_files_conf.blocks_per_file, "Configuring builder to move ancient blocks to static files");
+            builder = builder
+                .add_stage(StaticFileStage::new(static_files, static_files_conf.blocks_per_file));
+        }
+
+        Ok(builder.build())
     }
 }
 
+/// How often the known peers are written to the persistent peers file.
+const PERSIST_PEERS_INTERVAL: Duration = Duration::from_secs(60 * 5);
+
 /// Drives the [NetworkManager] future until a [Shutdown](reth_tasks::shutdown::Shutdown) signal is
-/// received. If configured, this writes known peers to `persistent_peers_file` afterwards.
+/// received. If configured, this writes known peers to `persistent_peers_file` periodically and
+/// afterwards.
 async fn run_network_until_shutdown<C>(
     shutdown: reth_tasks::shutdown::Shutdown,
     network: NetworkManager<C>,
@@ -480,22 +706,49 @@ async fn run_network_until_shutdown<C>(
 {
     pin_mut!(network, shutdown);
 
-    tokio::select! {
-        _ = &mut network => {},
-        _ = shutdown => {},
+    let mut persist_interval = tokio::time::interval_at(
+        tokio::time::Instant::now() + PERSIST_PEERS_INTERVAL,
+        PERSIST_PEERS_INTERVAL,
+    );
+
+    loop {
+        tokio::select! {
+            _ = &mut network => break,
+            _ = &mut shutdown => break,
+            _ = persist_interval.tick(), if persistent_peers_file.is_some() => {
+                if let Some(file_path) = &persistent_peers_file {
+                    write_peers_to_file(&network, file_path);
+                }
+            }
+        }
     }
 
     if let Some(file_path) = persistent_peers_file {
-        let known_peers = network.all_peers().collect::<Vec<_>>();
-        if let Ok(known_peers) = serde_json::to_string_pretty(&known_peers) {
-            trace!(target : "reth::cli", peers_file =?file_path, num_peers=%known_peers.len(), "Saving current peers");
-            match std::fs::write(&file_path, known_peers) {
-                Ok(_) => {
-                    info!(target: "reth::cli", peers_file=?file_path, "Wrote network peers to file");
-                }
-                Err(err) => {
-                    warn!(target: "reth::cli", ?err, peers_file=?file_path, "Failed to write network peers to file");
-                }
+        write_peers_to_file(&network, &file_path);
+    }
+}
+
+/// Writes the known peers and their state to the given file.
+///
+/// The peers are written to a temporary file first that replaces the file, so the file is never
+/// left half written.
+fn write_peers_to_file<C>(network: &NetworkManager<C>, file_path: &Path)
+where
+    C: BlockProvider,
+{
+    let known_peers = network.persisted_peers();
+    if let Ok(known_peers) = serde_json::to_string_pretty(&known_peers) {
+        trace!(target : "reth::cli", peers_file =?file_path, num_peers=%known_peers.len(), "Saving current peers");
+        let mut tmp_path = file_path.as_os_str().to_owned();
+        tmp_path.push(".tmp");
+        match std::fs::write(&tmp_path, known_peers)
+            .and_then(|_| std::fs::rename(&tmp_path, file_path))
+        {
+            Ok(_) => {
+                info!(target: "reth::cli", peers_file=?file_path, "Wrote network peers to file");
+            }
+            Err(err) => {
+                warn!(target: "reth::cli", ?err, peers_file=?file_path, "Failed to write network peers to file");
             }
         }
     }
@@ -513,9 +766,18 @@ mod tests {
 
     #[test]
     fn parse_common_node_command_chain_args() {
-        for chain in ["mainnet", "sepolia", "goerli"] {
+        for chain in ["mainnet", "sepolia", "goerli", "dev"] {
             let args: Command = Command::parse_from(["reth", "--chain", chain]);
             assert_eq!(args.chain.chain, chain.parse().unwrap());
         }
     }
+
+    #[test]
+    fn parse_dev_node_command() {
+        let args: Command = Command::parse_from(["reth", "--dev", "--dev.block-time", "2"]);
+        assert!(args.dev);
+        assert_eq!(args.block_time, Some(2));
+
+        assert!(Command::try_parse_from(["reth", "--dev.block-time", "2"]).is_err());
+    }
 }
@@ -0,0 +1,206 @@
+//! Command that builds an [EIP-1459](https://eips.ethereum.org/EIPS/eip-1459) node list that can be
+//! published via DNS.
+use clap::{Parser, ValueEnum};
+use futures::StreamExt;
+use reth_discv4::{bootnodes::mainnet_nodes, DiscoveryUpdate, Discv4, Discv4Config};
+use reth_dns_discovery::{
+    tree::{LinkEntry, NodeEntry},
+    DnsTreeBuilder,
+};
+use reth_network::config::{rng_secret_key, SecretKey};
+use reth_primitives::{BlockNumber, ChainSpec, ForkCondition, ForkId, Head, NodeRecord};
+use reth_rlp::Decodable;
+use std::{
+    collections::HashMap,
+    net::{Ipv4Addr, SocketAddr, SocketAddrV4},
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use tracing::info;
+
+/// The output formats of the tree.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum DnsTreeFormat {
+    /// A zone file with the TXT records of the tree.
+    Zone,
+    /// A JSON object that maps the names of the TXT records to their content.
+    Json,
+}
+
+/// The arguments for the `reth p2p dns-tree` command
+#[derive(Debug, Parser)]
+pub struct DnsTreeArgs {
+    /// The domain the tree is published under.
+    #[arg(long)]
+    domain: String,
+
+    /// The file with the hex encoded secret key that signs the tree.
+    ///
+    /// A new key is generated and written to the file if it does not exist.
+    #[arg(long, value_name = "FILE")]
+    key: PathBuf,
+
+    /// How many seconds to crawl discv4 for live nodes, `0` disables crawling.
+    #[arg(long, value_name = "SECONDS", default_value = "0")]
+    crawl_time: u64,
+
+    /// The number of the current head block of the chain.
+    ///
+    /// Only crawled nodes with a fork id that is valid at the head are included. Defaults to the
+    /// block of the last block based fork of the chain, timestamp based forks are checked against
+    /// the current time.
+    #[arg(long, value_name = "BLOCK")]
+    head: Option<BlockNumber>,
+
+    /// Bootnodes to start crawling from.
+    ///
+    /// Will fall back to the mainnet bootnodes if not specified.
+    #[arg(long, value_delimiter = ',')]
+    bootnodes: Option<Vec<NodeRecord>>,
+
+    /// ENRs of nodes to include in the tree in addition to the crawled nodes.
+    #[arg(long, value_delimiter = ',')]
+    enr: Vec<NodeEntry<SecretKey>>,
+
+    /// Links to other trees to include in the tree.
+    #[arg(long, value_delimiter = ',')]
+    link: Vec<LinkEntry>,
+
+    /// The sequence number of the tree, defaults to the current unix timestamp.
+    #[arg(long)]
+    seq: Option<u64>,
+
+    /// The output format.
+    #[arg(long, value_enum, default_value_t = DnsTreeFormat::Zone)]
+    format: DnsTreeFormat,
+
+    /// The TTL of the records in the zone file.
+    #[arg(long, default_value = "300")]
+    ttl: u32,
+
+    /// The file to write the tree to, defaults to stdout.
+    #[arg(long, short, value_name = "FILE")]
+    output: Option<PathBuf>,
+}
+
+impl DnsTreeArgs {
+    /// Execute the `p2p dns-tree` command
+    pub async fn execute(&self, chain: &ChainSpec) -> eyre::Result<()> {
+        let secret_key = load_or_create_key(&self.key)?;
+
+        let mut nodes = self.enr.iter().map(|entry| entry.enr.clone()).collect::<Vec<_>>();
+        if self.crawl_time > 0 {
+            nodes.extend(self.crawl(chain).await?.into_iter().map(|entry| entry.enr));
+        }
+
+        let sequence_number = match self.seq {
+            Some(seq) => seq,
+            None => SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+        };
+
+        let tree = DnsTreeBuilder::new()
+            .sequence_number(sequence_number)
+            .add_nodes(nodes)
+            .add_links(self.link.clone())
+            .build(&secret_key)
+            .map_err(|err| eyre::eyre!("Failed to sign the tree: {err:?}"))?;
+
+        let content = match self.format {
+            DnsTreeFormat::Zone => tree.zone_file(&self.domain, self.ttl),
+            DnsTreeFormat::Json => serde_json::to_string_pretty(&tree.txt_records(&self.domain))?,
+        };
+
+        match &self.output {
+            Some(path) => std::fs::write(path, content)?,
+            None => println!("{content}"),
+        }
+
+        info!(target: "reth::cli", link = %tree.link(&self.domain), "Built DNS tree");
+
+        Ok(())
+    }
+
+    /// Crawls discv4 and returns the live nodes with a fork id that is compatible with the chain.
+    async fn crawl(&self, chain: &ChainSpec) -> eyre::Result<Vec<NodeEntry<SecretKey>>> {
+        let secret_key = rng_secret_key();
+        let local_addr = SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, 0));
+        let local_record = NodeRecord::from_secret_key(local_addr, &secret_key);
+        let config = Discv4Config::builder()
+            .add_boot_nodes(self.bootnodes.clone().unwrap_or_else(mainnet_nodes))
+            .external_ip_resolver(None)
+            .build();
+
+        let (_discv4, mut service) =
+            Discv4::bind(local_addr, local_record, secret_key, config).await?;
+        let mut updates = service.update_stream();
+        let _handle = service.spawn();
+
+        let head = self.head(chain)?;
+        let fork_filter = chain.fork_filter(head);
+
+        info!(target: "reth::cli", crawl_time = self.crawl_time, head = head.number, "Crawling discv4");
+
+        let mut nodes = HashMap::new();
+        let crawl = async {
+            while let Some(update) = updates.next().await {
+                match update {
+                    DiscoveryUpdate::Enr(record, enr) => {
+                        let fork_id = enr
+                            .get(b"eth")
+                            .and_then(|mut fork_id| ForkId::decode(&mut fork_id).ok());
+                        if fork_id.map_or(false, |fork_id| fork_filter.validate(fork_id).is_ok()) {
+                            nodes.insert(record.id, NodeEntry { enr });
+                        }
+                    }
+                    DiscoveryUpdate::Removed(id) => {
+                        nodes.remove(&id);
+                    }
+                    _ => {}
+                }
+            }
+        };
+        let _ = tokio::time::timeout(Duration::from_secs(self.crawl_time), crawl).await;
+
+        info!(target: "reth::cli", nodes = nodes.len(), "Finished crawling discv4");
+
+        Ok(nodes.into_values().collect())
+    }
+
+    /// Returns the head the fork ids of the crawled nodes are validated against.
+    fn head(&self, chain: &ChainSpec) -> eyre::Result<Head> {
+        let number = self.head.unwrap_or_else(|| {
+            chain
+                .forks_iter()
+                .filter_map(|(_, condition)| match condition {
+                    ForkCondition::Block(block) => Some(block),
+                    ForkCondition::TTD { fork_block, .. } => fork_block,
+                    _ => None,
+                })
+                .max()
+                .unwrap_or_default()
+        });
+        Ok(Head {
+            number,
+            timestamp: SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs(),
+            ..Default::default()
+        })
+    }
+}
+
+/// Reads the hex encoded secret key from the file, or generates a new key and writes it to the file
+/// if it does not exist.
+fn load_or_create_key(path: &Path) -> eyre::Result<SecretKey> {
+    if path.exists() {
+        let hex = std::fs::read_to_string(path)?;
+        return Ok(hex.trim().trim_start_matches("0x").parse()?)
+    }
+
+    let secret_key = rng_secret_key();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(path, secret_key.display_secret().to_string())?;
+    info!(target: "reth::cli", path = %path.display(), "Generated a new key for the DNS tree");
+
+    Ok(secret_key)
+}
@@ -17,6 +17,9 @@ use reth_staged_sync::{
 };
 use std::sync::Arc;
 
+mod dns_tree;
+pub use dns_tree::{DnsTreeArgs, DnsTreeFormat};
+
 /// `reth p2p` command
 #[derive(Debug, Parser)]
 pub struct Command {
@@ -79,10 +82,18 @@ pub enum Subcommands {
         #[arg(value_parser = hash_or_num_value_parser)]
         id: BlockHashOrNumber,
     },
+    /// Build an EIP-1459 node list that can be published via DNS, optionally from nodes crawled
+    /// via discv4
+    DnsTree(DnsTreeArgs),
 }
 impl Command {
     /// Execute `p2p` command
     pub async fn execute(&self) -> eyre::Result<()> {
+        if let Subcommands::DnsTree(args) = &self.command {
+            // building the tree does not require the network
+            return args.execute(&self.chain).await
+        }
+
         let tempdir = tempfile::TempDir::new()?;
         let noop_db = Arc::new(Env::<WriteMap>::open(&tempdir.into_path(), EnvKind::RW)?);
 
@@ -152,6 +163,7 @@ impl Command {
                 let body = result.into_iter().next().unwrap();
                 println!("Successfully downloaded body: {body:?}")
             }
+            Subcommands::DnsTree(_) => unreachable!("handled before starting the network"),
         }
 
         Ok(())
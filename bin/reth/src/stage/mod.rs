@@ -7,7 +7,7 @@ use crate::{
     prometheus_exporter,
 };
 use clap::{Parser, ValueEnum};
-use reth_consensus::beacon::BeaconConsensus;
+use reth_consensus::chain::ChainConsensus;
 use reth_downloaders::bodies::bodies::BodiesDownloaderBuilder;
 use reth_primitives::ChainSpec;
 use reth_provider::{ShareableDatabase, Transaction};
@@ -123,7 +123,7 @@ impl Command {
 
         match self.stage {
             StageEnum::Bodies => {
-                let (consensus, _) = BeaconConsensus::builder().build(self.chain.clone());
+                let (consensus, _) = ChainConsensus::builder().build(self.chain.clone());
 
                 let mut config = config;
                 config.peers.connect_trusted_nodes_only = self.network.trusted_only;
@@ -0,0 +1,123 @@
+#![warn(missing_docs, unreachable_pub, unused_crate_dependencies)]
+#![deny(unused_must_use, rust_2018_idioms)]
+#![doc(test(
+    no_crate_inject,
+    attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
+))]
+//! A [Consensus] implementation for local testing purposes that automatically seals blocks.
+//!
+//! This is the equivalent of `geth --dev` or anvil: there is no consensus client involved, instead
+//! the [MiningTask] polls a [MiningMode] for transactions that are ready to be mined, executes them
+//! in a new block on top of the current canonical head and commits the block to the database.
+
+use reth_db::database::Database;
+use reth_interfaces::consensus::{Consensus, Error, ForkchoiceState};
+use reth_primitives::{ChainSpec, SealedBlock, SealedHeader, U256};
+use reth_transaction_pool::TransactionPool;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+mod mode;
+mod task;
+
+pub use crate::{
+    mode::{FixedBlockTimeMiner, MiningMode, ReadyTransactionMiner},
+    task::{AutoSealError, MiningTask},
+};
+
+/// A consensus implementation intended for local development and testing purposes.
+///
+/// All blocks are produced locally by the [MiningTask], hence they are considered valid.
+#[derive(Debug)]
+pub struct AutoSealConsensus {
+    /// Watcher over the forkchoice state
+    forkchoice_state_rx: watch::Receiver<ForkchoiceState>,
+    /// Configuration
+    chain_spec: Arc<ChainSpec>,
+}
+
+impl AutoSealConsensus {
+    /// Create a new instance of [AutoSealConsensus]
+    pub fn new(
+        chain_spec: Arc<ChainSpec>,
+        forkchoice_state_rx: watch::Receiver<ForkchoiceState>,
+    ) -> Self {
+        Self { chain_spec, forkchoice_state_rx }
+    }
+
+    /// Returns the chain spec this consensus was configured with.
+    pub fn chain_spec(&self) -> &ChainSpec {
+        &self.chain_spec
+    }
+}
+
+impl Consensus for AutoSealConsensus {
+    fn fork_choice_state(&self) -> watch::Receiver<ForkchoiceState> {
+        self.forkchoice_state_rx.clone()
+    }
+
+    fn pre_validate_header(
+        &self,
+        _header: &SealedHeader,
+        _parent: &SealedHeader,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn validate_header(
+        &self,
+        _header: &SealedHeader,
+        _total_difficulty: U256,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn pre_validate_block(&self, _block: &SealedBlock) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn has_block_reward(&self, _total_difficulty: U256, _difficulty: U256) -> bool {
+        false
+    }
+}
+
+/// Builder type for configuring the setup
+#[derive(Debug)]
+pub struct AutoSealBuilder<DB, Pool> {
+    chain_spec: Arc<ChainSpec>,
+    db: Arc<DB>,
+    pool: Pool,
+    mode: MiningMode,
+}
+
+// === impl AutoSealBuilder ===
+
+impl<DB, Pool> AutoSealBuilder<DB, Pool>
+where
+    DB: Database + 'static,
+    Pool: TransactionPool + 'static,
+{
+    /// Creates a new builder instance to configure all parts.
+    ///
+    /// By default a new block is sealed as soon as a transaction is ready in the pool.
+    pub fn new(chain_spec: Arc<ChainSpec>, db: Arc<DB>, pool: Pool) -> Self {
+        let mode = MiningMode::instant(usize::MAX, pool.pending_transactions_listener());
+        Self { chain_spec, db, pool, mode }
+    }
+
+    /// Sets the [MiningMode] it operates in, default is [MiningMode::Auto]
+    pub fn mode(mut self, mode: MiningMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Consumes the type and returns all components
+    pub fn build(self) -> (Arc<AutoSealConsensus>, MiningTask<DB, Pool>) {
+        let Self { chain_spec, db, pool, mode } = self;
+        let (forkchoice_state_tx, forkchoice_state_rx) = watch::channel(ForkchoiceState::default());
+        let consensus =
+            Arc::new(AutoSealConsensus::new(Arc::clone(&chain_spec), forkchoice_state_rx));
+        let task = MiningTask::new(chain_spec, db, pool, mode, forkchoice_state_tx);
+        (consensus, task)
+    }
+}
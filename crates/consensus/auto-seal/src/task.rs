@@ -0,0 +1,436 @@
+//! The task that drives the [MiningMode] and seals new blocks.
+
+use crate::mode::MiningMode;
+use futures_util::{future::BoxFuture, FutureExt};
+use reth_consensus::validation::calculate_next_block_base_fee;
+use reth_db::database::Database;
+use reth_executor::Factory;
+use reth_interfaces::consensus::ForkchoiceState;
+use reth_primitives::{
+    constants::{EIP1559_INITIAL_BASE_FEE, EMPTY_OMMER_ROOT, EMPTY_WITHDRAWALS},
+    eip4844::calculate_blob_gasprice,
+    proofs, Block, Bloom, ChainSpec, Hardfork, Header, IntoRecoveredTransaction, SealedBlock,
+    TransactionSigned, TransactionSignedEcRecovered, H256, U256,
+};
+use reth_provider::{
+    insert_canonical_block, trie::DBTrieLoader, ExecutorFactory, LatestStateProviderRef,
+    Transaction, TransactionError,
+};
+use reth_stages::stages::{
+    ACCOUNT_HASHING, BODIES, EXECUTION, FINISH, HEADERS, INDEX_ACCOUNT_HISTORY,
+    INDEX_STORAGE_HISTORY, MERKLE_EXECUTION, MERKLE_UNWIND, SENDER_RECOVERY, STORAGE_HASHING,
+    TOTAL_DIFFICULTY, TRANSACTION_LOOKUP,
+};
+use reth_transaction_pool::{OnNewBlockEvent, StateDiff, TransactionPool, ValidPoolTransaction};
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt,
+    future::Future,
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tokio::sync::watch;
+use tracing::{debug, trace, warn};
+
+/// Errors that can occur while sealing a new block.
+#[derive(Debug, thiserror::Error)]
+pub enum AutoSealError {
+    /// Thrown if a database operation failed.
+    #[error(transparent)]
+    Database(#[from] reth_interfaces::db::Error),
+    /// Thrown if a database transaction failed.
+    #[error(transparent)]
+    Transaction(#[from] TransactionError),
+    /// Thrown if the transactions could not be executed.
+    #[error(transparent)]
+    Execution(#[from] reth_interfaces::executor::Error),
+    /// Thrown if inserting the block failed.
+    #[error(transparent)]
+    Interface(#[from] reth_interfaces::Error),
+}
+
+/// Number of times a transaction may be part of a block that fails to be sealed before it is
+/// removed from the pool.
+const MAX_SEAL_ATTEMPTS: usize = 3;
+
+/// The outcome of a sealed block.
+#[derive(Debug)]
+struct SealedOutcome {
+    /// Hash of the new canonical head.
+    hash: H256,
+    /// Base fee of the next block.
+    pending_block_base_fee: u64,
+    /// Blob gas price of the next block, if Cancun is active.
+    pending_block_blob_fee: Option<u128>,
+    /// Hashes of the transactions that were included in the block.
+    mined_transactions: Vec<H256>,
+    /// Hashes of the transactions that failed to execute and were left out of the block.
+    invalid_transactions: Vec<H256>,
+}
+
+/// A Future that listens for new ready transactions and puts new blocks into storage
+pub struct MiningTask<DB, Pool: TransactionPool> {
+    /// The configured chain spec
+    chain_spec: Arc<ChainSpec>,
+    /// Creates the executor for the new blocks
+    factory: Factory,
+    /// The database new blocks are committed to
+    db: Arc<DB>,
+    /// The active miner
+    miner: MiningMode,
+    /// Single active future that inserts a new block into `storage`, resolves to the hashes of
+    /// the transactions of the block if sealing failed
+    insert_task: Option<BoxFuture<'static, Option<Vec<H256>>>>,
+    /// Number of failed attempts to seal a block, per transaction of the failed blocks
+    failed_seals: HashMap<H256, usize>,
+    /// Pool where transactions are stored
+    pool: Pool,
+    /// backlog of sets of transactions ready to be mined
+    queued: VecDeque<Vec<Arc<ValidPoolTransaction<<Pool as TransactionPool>::Transaction>>>>,
+    /// Used to notify consumers of new blocks
+    forkchoice_state_tx: Arc<watch::Sender<ForkchoiceState>>,
+}
+
+// === impl MiningTask ===
+
+impl<DB, Pool: TransactionPool> MiningTask<DB, Pool> {
+    /// Creates a new instance of the task
+    pub(crate) fn new(
+        chain_spec: Arc<ChainSpec>,
+        db: Arc<DB>,
+        pool: Pool,
+        miner: MiningMode,
+        forkchoice_state_tx: watch::Sender<ForkchoiceState>,
+    ) -> Self {
+        Self {
+            factory: Factory::new(Arc::clone(&chain_spec)),
+            chain_spec,
+            db,
+            miner,
+            insert_task: None,
+            failed_seals: Default::default(),
+            pool,
+            queued: Default::default(),
+            forkchoice_state_tx: Arc::new(forkchoice_state_tx),
+        }
+    }
+
+    /// Records a failed attempt to seal a block with the given transactions.
+    ///
+    /// The transactions stay in the pool and are mined again, unless they were part of
+    /// [MAX_SEAL_ATTEMPTS] failed blocks, in which case they are removed from the pool so that
+    /// the same set of transactions is not retried forever.
+    fn on_failed_seal(&mut self, hashes: Vec<H256>) {
+        let mut evicted = Vec::new();
+        for hash in hashes {
+            let attempts = self.failed_seals.entry(hash).or_default();
+            *attempts += 1;
+            if *attempts >= MAX_SEAL_ATTEMPTS {
+                self.failed_seals.remove(&hash);
+                evicted.push(hash);
+            }
+        }
+
+        if !evicted.is_empty() {
+            warn!(target: "consensus::auto", evicted = evicted.len(), "removing transactions that repeatedly failed to seal");
+            self.pool.remove_invalid(evicted);
+        }
+    }
+}
+
+impl<DB, Pool> Future for MiningTask<DB, Pool>
+where
+    DB: Database + 'static,
+    Pool: TransactionPool + Unpin + 'static,
+    <Pool as TransactionPool>::Transaction: IntoRecoveredTransaction,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        // this drives block production and queues new blocks for insertion
+        loop {
+            // the miner is only polled while no block is being sealed, otherwise it would collect
+            // the transactions that are currently being mined again
+            if this.insert_task.is_none() {
+                if let Poll::Ready(transactions) = this.miner.poll(&this.pool, cx) {
+                    // miner returned a set of transaction that we feed to the producer
+                    this.queued.push_back(transactions);
+                }
+            }
+
+            if this.insert_task.is_none() {
+                if this.queued.is_empty() {
+                    // nothing to insert
+                    break
+                }
+
+                // ready to queue in new insert task
+                let transactions = this.queued.pop_front().expect("not empty");
+                let chain_spec = Arc::clone(&this.chain_spec);
+                let factory = this.factory.clone();
+                let db = Arc::clone(&this.db);
+                let pool = this.pool.clone();
+                let forkchoice_state_tx = Arc::clone(&this.forkchoice_state_tx);
+
+                this.insert_task = Some(Box::pin(async move {
+                    let recovered = transactions
+                        .iter()
+                        .map(|tx| tx.transaction.to_recovered_transaction())
+                        .collect::<Vec<_>>();
+                    let hashes = recovered.iter().map(|tx| tx.hash()).collect::<Vec<_>>();
+
+                    let res = tokio::task::spawn_blocking(move || {
+                        seal_block(&chain_spec, &factory, &db, recovered)
+                    })
+                    .await;
+
+                    match res {
+                        Ok(Ok(SealedOutcome {
+                            hash,
+                            pending_block_base_fee,
+                            pending_block_blob_fee,
+                            mined_transactions,
+                            invalid_transactions,
+                        })) => {
+                            debug!(target: "consensus::auto", ?hash, "sealed new block");
+
+                            if !invalid_transactions.is_empty() {
+                                warn!(target: "consensus::auto", invalid = invalid_transactions.len(), "removing transactions that failed to execute");
+                                pool.remove_invalid(invalid_transactions);
+                            }
+
+                            pool.on_new_block(OnNewBlockEvent {
+                                hash,
+                                pending_block_base_fee: pending_block_base_fee as u128,
+                                pending_block_blob_fee,
+                                state_changes: StateDiff {},
+                                mined_transactions,
+                            });
+
+                            let state = ForkchoiceState {
+                                head_block_hash: hash,
+                                safe_block_hash: hash,
+                                finalized_block_hash: hash,
+                            };
+                            let _ = forkchoice_state_tx.send(state);
+                            None
+                        }
+                        Ok(Err(err)) => {
+                            warn!(target: "consensus::auto", ?err, "failed to seal block");
+                            Some(hashes)
+                        }
+                        Err(err) => {
+                            warn!(target: "consensus::auto", ?err, "sealing task failed");
+                            Some(hashes)
+                        }
+                    }
+                }));
+            }
+
+            if let Some(mut fut) = this.insert_task.take() {
+                match fut.poll_unpin(cx) {
+                    Poll::Ready(None) => this.failed_seals.clear(),
+                    Poll::Ready(Some(hashes)) => this.on_failed_seal(hashes),
+                    Poll::Pending => {
+                        this.insert_task = Some(fut);
+                        break
+                    }
+                }
+            }
+        }
+
+        Poll::Pending
+    }
+}
+
+impl<DB, Pool: TransactionPool> fmt::Debug for MiningTask<DB, Pool> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MiningTask").finish_non_exhaustive()
+    }
+}
+
+/// Executes the given transactions in a new block on top of the current canonical head and commits
+/// the block to the database.
+///
+/// This performs the work of all stages of the pipeline for this single block and advances the
+/// progress of every stage, so the node remains consistent with a regularly synced node.
+fn seal_block<DB: Database>(
+    chain_spec: &ChainSpec,
+    factory: &Factory,
+    db: &DB,
+    transactions: Vec<TransactionSignedEcRecovered>,
+) -> Result<SealedOutcome, AutoSealError> {
+    let mut tx = Transaction::new(db)?;
+
+    let parent_number = FINISH.get_progress(&**tx)?.unwrap_or_default();
+    let parent = tx.get_header(parent_number)?;
+    let parent_hash = tx.get_block_hash(parent_number)?;
+    let parent_td = tx.get_td(parent_number)?;
+    let number = parent_number + 1;
+
+    let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+
+    let base_fee_per_gas = chain_spec.fork(Hardfork::London).active_at_block(number).then(|| {
+        parent
+            .base_fee_per_gas
+            .map(|base_fee| {
+                calculate_next_block_base_fee(parent.gas_used, parent.gas_limit, base_fee)
+            })
+            .unwrap_or(EIP1559_INITIAL_BASE_FEE)
+    });
+
+    let timestamp = now.max(parent.timestamp + 1);
+    let withdrawals =
+        chain_spec.fork(Hardfork::Shanghai).active_at_timestamp(timestamp).then(Vec::new);
+
+    // the blob gas fields are derived from the parent, the parent beacon block root is unknown
+    // without a beacon chain
+    let is_cancun = chain_spec.fork(Hardfork::Cancun).active_at_timestamp(timestamp);
+    let blob_gas_used = is_cancun.then_some(0);
+    let excess_blob_gas =
+        is_cancun.then(|| parent.next_block_excess_blob_gas().unwrap_or_default());
+    let parent_beacon_block_root = is_cancun.then(H256::zero);
+
+    let header = Header {
+        parent_hash,
+        ommers_hash: EMPTY_OMMER_ROOT,
+        beneficiary: Default::default(),
+        state_root: Default::default(),
+        transactions_root: Default::default(),
+        receipts_root: Default::default(),
+        withdrawals_root: withdrawals.as_ref().map(|_| EMPTY_WITHDRAWALS),
+        logs_bloom: Default::default(),
+        difficulty: U256::ZERO,
+        number,
+        gas_limit: parent.gas_limit,
+        gas_used: 0,
+        timestamp,
+        mix_hash: Default::default(),
+        nonce: 0,
+        base_fee_per_gas,
+        blob_gas_used,
+        excess_blob_gas,
+        parent_beacon_block_root,
+        extra_data: Default::default(),
+    };
+
+    let (senders, body): (Vec<_>, Vec<TransactionSigned>) =
+        transactions.into_iter().map(|tx| (tx.signer(), tx.into_signed())).unzip();
+    let mut block = Block { header, body, ommers: vec![], withdrawals };
+
+    // transactions that fail to execute, e.g. because of a nonce gap or insufficient funds, are
+    // left out of the block instead of failing the entire block
+    let (result, invalid) = factory
+        .with_sp(LatestStateProviderRef::new(&**tx))
+        .execute_skipping_invalid(&block, parent_td, Some(senders))?;
+
+    let mut invalid_transactions = Vec::with_capacity(invalid.len());
+    for idx in invalid.into_iter().rev() {
+        invalid_transactions.push(block.body.remove(idx).hash());
+    }
+    let mined_transactions = block.body.iter().map(|tx| tx.hash()).collect();
+
+    // the header fields that depend on the executed transactions are only known now
+    let gas_used =
+        result.tx_changesets.last().map(|c| c.receipt.cumulative_gas_used).unwrap_or_default();
+    block.header.gas_used = gas_used;
+    block.header.transactions_root = proofs::calculate_transaction_root(block.body.iter());
+    if let Some(blob_gas_used) = block.header.blob_gas_used.as_mut() {
+        *blob_gas_used = block.body.iter().map(|tx| tx.blob_gas_used()).sum();
+    }
+    block.header.receipts_root =
+        proofs::calculate_receipt_root(result.tx_changesets.iter().map(|c| &c.receipt));
+    block.header.logs_bloom =
+        result.tx_changesets.iter().fold(Bloom::zero(), |bloom, c| bloom | c.receipt.bloom);
+
+    trace!(target: "consensus::auto", number, gas_used, txs = block.body.len(), "executed block");
+
+    // execution stage
+    // the beacon root update is written with the post block transition
+    let has_block_changes =
+        !result.block_changesets.is_empty() || block.parent_beacon_block_root.is_some();
+    tx.insert_execution_result(vec![result], chain_spec, parent_number)?;
+
+    let from = tx.get_block_transition(parent_number)?;
+    let to = from + block.body.len() as u64 + has_block_changes as u64;
+
+    // storage hashing stage
+    {
+        let lists = tx.get_addresses_and_keys_of_changed_storages(from, to)?;
+        let storages = tx.get_plainstate_storages(lists.into_iter())?;
+        tx.insert_storage_for_hashing(storages.into_iter())?;
+    }
+
+    // account hashing stage
+    {
+        let lists = tx.get_addresses_of_changed_accounts(from, to)?;
+        let accounts = tx.get_plainstate_accounts(lists.into_iter())?;
+        tx.insert_account_for_hashing(accounts.into_iter())?;
+    }
+
+    // merkle tree
+    block.header.state_root =
+        DBTrieLoader::default().update_root(&tx, from..to).map_err(TransactionError::from)?;
+
+    let sealed = SealedBlock {
+        header: block.header.seal_slow(),
+        body: block.body,
+        ommers: vec![],
+        withdrawals: block.withdrawals,
+    };
+    let hash = sealed.hash();
+
+    // headers, bodies, sender recovery, total difficulty and transaction lookup stages
+    insert_canonical_block(&**tx, &sealed, has_block_changes)?;
+
+    // account history stage
+    {
+        let indices = tx.get_account_transition_ids_from_changeset(from, to)?;
+        tx.insert_account_history_index(indices)?;
+    }
+
+    // storage history stage
+    {
+        let indices = tx.get_storage_transition_ids_from_changeset(from, to)?;
+        tx.insert_storage_history_index(indices)?;
+    }
+
+    for stage in [
+        HEADERS,
+        TOTAL_DIFFICULTY,
+        BODIES,
+        SENDER_RECOVERY,
+        EXECUTION,
+        MERKLE_UNWIND,
+        ACCOUNT_HASHING,
+        STORAGE_HASHING,
+        MERKLE_EXECUTION,
+        TRANSACTION_LOOKUP,
+        INDEX_STORAGE_HISTORY,
+        INDEX_ACCOUNT_HISTORY,
+        FINISH,
+    ] {
+        stage.save_progress(&**tx, number)?;
+    }
+
+    tx.commit()?;
+
+    let pending_block_base_fee = base_fee_per_gas
+        .map(|base_fee| calculate_next_block_base_fee(gas_used, sealed.header.gas_limit, base_fee))
+        .unwrap_or_default();
+
+    let pending_block_blob_fee =
+        sealed.header.next_block_excess_blob_gas().map(calculate_blob_gasprice);
+
+    Ok(SealedOutcome {
+        hash,
+        pending_block_base_fee,
+        pending_block_blob_fee,
+        mined_transactions,
+        invalid_transactions,
+    })
+}
@@ -0,0 +1,22 @@
+use super::ChainConsensus;
+use reth_interfaces::consensus::ForkchoiceState;
+use reth_primitives::ChainSpec;
+use std::sync::Arc;
+use tokio::sync::watch;
+
+/// Builder for [ChainConsensus].
+#[derive(Debug, Default)]
+pub struct ChainConsensusBuilder;
+
+impl ChainConsensusBuilder {
+    /// Create new instance of [ChainConsensus] and forkchoice notifier. Internally, creates a
+    /// [watch::channel] for updating the forkchoice state.
+    pub fn build(
+        self,
+        chain_spec: ChainSpec,
+    ) -> (Arc<ChainConsensus>, watch::Sender<ForkchoiceState>) {
+        let (forkchoice_state_tx, forkchoice_state_rx) = watch::channel(ForkchoiceState::default());
+        let inner = Arc::new(ChainConsensus::new(chain_spec, forkchoice_state_rx));
+        (inner, forkchoice_state_tx)
+    }
+}
@@ -0,0 +1,199 @@
+//! Consensus that dispatches to the validator of the chain's engine.
+use super::ChainConsensusBuilder;
+use crate::{beacon::BeaconConsensus, clique::CliqueValidator, ethash::EthashValidator};
+use reth_interfaces::consensus::{Consensus, Error, ForkchoiceState};
+use reth_primitives::{ChainSpec, ConsensusEngine, Hardfork, SealedBlock, SealedHeader, U256};
+use tokio::sync::watch;
+
+/// The validator for blocks before the merge.
+#[derive(Debug)]
+enum PreMergeValidator {
+    Ethash(EthashValidator),
+    Clique(CliqueValidator),
+}
+
+/// Consensus for the whole history of a chain.
+///
+/// Headers before the merge are validated by the engine configured in the [ChainSpec], either
+/// ethash or clique. Headers after the merge are handed to [BeaconConsensus].
+///
+/// Whether a header is past the merge is decided by the [Hardfork::Paris] activation block if it
+/// is known. Otherwise a zero difficulty marks a proof-of-stake header, which is confirmed against
+/// the terminal total difficulty once it is known in [Consensus::validate_header].
+///
+/// NOTE: Neither the proof-of-work seal nor the authorization of clique signers is verified, see
+/// [EthashValidator] and [CliqueValidator]. Pre-merge headers are trusted by virtue of leading to
+/// the tip, so this must not be used to validate blocks from an untrusted source without a tip,
+/// e.g. when importing a chain file.
+#[derive(Debug)]
+pub struct ChainConsensus {
+    /// Validator for proof-of-stake blocks
+    beacon: BeaconConsensus,
+    /// Validator for blocks before the merge
+    pre_merge: PreMergeValidator,
+    /// Configuration
+    chain_spec: ChainSpec,
+}
+
+impl ChainConsensus {
+    /// Create a new instance of [ChainConsensus]
+    pub fn new(
+        chain_spec: ChainSpec,
+        forkchoice_state_rx: watch::Receiver<ForkchoiceState>,
+    ) -> Self {
+        let pre_merge = match chain_spec.engine {
+            ConsensusEngine::Ethash => {
+                PreMergeValidator::Ethash(EthashValidator::new(chain_spec.clone()))
+            }
+            ConsensusEngine::Clique { period, epoch } => {
+                PreMergeValidator::Clique(CliqueValidator::new(chain_spec.clone(), period, epoch))
+            }
+        };
+        let beacon = BeaconConsensus::new(chain_spec.clone(), forkchoice_state_rx);
+        Self { beacon, pre_merge, chain_spec }
+    }
+
+    /// Create new [ChainConsensusBuilder].
+    pub fn builder() -> ChainConsensusBuilder {
+        ChainConsensusBuilder::default()
+    }
+
+    /// Returns true if the header is sealed by proof-of-stake.
+    fn is_post_merge(&self, header: &SealedHeader) -> bool {
+        let paris = self.chain_spec.fork(Hardfork::Paris);
+        paris.active_at_block(header.number) ||
+            (paris.ttd().is_some() && header.difficulty == U256::ZERO)
+    }
+}
+
+impl Consensus for ChainConsensus {
+    fn fork_choice_state(&self) -> watch::Receiver<ForkchoiceState> {
+        self.beacon.fork_choice_state()
+    }
+
+    fn pre_validate_header(
+        &self,
+        header: &SealedHeader,
+        parent: &SealedHeader,
+    ) -> Result<(), Error> {
+        if self.is_post_merge(header) {
+            return self.beacon.pre_validate_header(header, parent)
+        }
+
+        match &self.pre_merge {
+            PreMergeValidator::Ethash(ethash) => ethash.pre_validate_header(header, parent),
+            PreMergeValidator::Clique(clique) => clique.pre_validate_header(header, parent),
+        }
+    }
+
+    fn validate_header(&self, header: &SealedHeader, total_difficulty: U256) -> Result<(), Error> {
+        if self.chain_spec.fork(Hardfork::Paris).active_at_ttd(total_difficulty, header.difficulty)
+        {
+            return self.beacon.validate_header(header, total_difficulty)
+        }
+        if header.difficulty == U256::ZERO {
+            return Err(Error::PreMergeDifficultyIsZero)
+        }
+
+        match &self.pre_merge {
+            PreMergeValidator::Ethash(ethash) => ethash.validate_header(header, total_difficulty),
+            PreMergeValidator::Clique(clique) => clique.validate_header(header, total_difficulty),
+        }
+    }
+
+    fn pre_validate_block(&self, block: &SealedBlock) -> Result<(), Error> {
+        if self.is_post_merge(&block.header) {
+            return self.beacon.pre_validate_block(block)
+        }
+
+        match &self.pre_merge {
+            PreMergeValidator::Ethash(ethash) => ethash.pre_validate_block(block),
+            PreMergeValidator::Clique(clique) => clique.pre_validate_block(block),
+        }
+    }
+
+    fn has_block_reward(&self, total_difficulty: U256, difficulty: U256) -> bool {
+        if !self.beacon.has_block_reward(total_difficulty, difficulty) {
+            return false
+        }
+
+        match &self.pre_merge {
+            PreMergeValidator::Ethash(ethash) => ethash.has_block_reward(),
+            PreMergeValidator::Clique(clique) => clique.has_block_reward(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ethash::calculate_difficulty;
+    use reth_primitives::{ChainSpecBuilder, ForkCondition, Header, GOERLI, MAINNET};
+
+    #[test]
+    fn selects_engine_from_chain_spec() {
+        let (consensus, _) = ChainConsensus::builder().build(MAINNET.clone());
+        assert!(matches!(consensus.pre_merge, PreMergeValidator::Ethash(_)));
+
+        let (consensus, _) = ChainConsensus::builder().build(GOERLI.clone());
+        assert!(matches!(consensus.pre_merge, PreMergeValidator::Clique(_)));
+    }
+
+    #[test]
+    fn dispatches_across_the_merge() {
+        let chain_spec = ChainSpecBuilder::mainnet()
+            .with_fork(
+                Hardfork::Paris,
+                ForkCondition::TTD { fork_block: None, total_difficulty: U256::from(1_000_000) },
+            )
+            .build();
+        let (consensus, _) = ChainConsensus::builder().build(chain_spec.clone());
+
+        let parent = Header {
+            number: 1,
+            timestamp: 100,
+            gas_limit: 5000,
+            difficulty: U256::from(1_000_000),
+            ..Default::default()
+        };
+        let mut header = Header {
+            number: 2,
+            parent_hash: parent.hash_slow(),
+            timestamp: 105,
+            gas_limit: 5000,
+            ..Default::default()
+        };
+        let parent = parent.seal_slow();
+
+        // proof-of-work header with a difficulty that does not match the ethash rules
+        header.difficulty = U256::from(1);
+        assert!(matches!(
+            consensus.pre_validate_header(&header.clone().seal_slow(), &parent),
+            Err(Error::DifficultyDiff { .. })
+        ));
+
+        header.difficulty = calculate_difficulty(&parent, 2, 105, &chain_spec);
+        assert_eq!(consensus.pre_validate_header(&header.clone().seal_slow(), &parent), Ok(()));
+
+        // proof-of-stake header, which skips the difficulty checks of the pre-merge engine
+        header.difficulty = U256::ZERO;
+        let header = header.seal_slow();
+        assert_eq!(consensus.pre_validate_header(&header, &parent), Ok(()));
+        assert_eq!(consensus.validate_header(&header, U256::from(1_000_000)), Ok(()));
+
+        // but only once the terminal total difficulty is reached
+        assert_eq!(
+            consensus.validate_header(&header, U256::from(999_999)),
+            Err(Error::PreMergeDifficultyIsZero)
+        );
+    }
+
+    #[test]
+    fn block_reward_depends_on_engine() {
+        let (consensus, _) = ChainConsensus::builder().build(MAINNET.clone());
+        assert!(consensus.has_block_reward(U256::ZERO, U256::from(1)));
+
+        let (consensus, _) = ChainConsensus::builder().build(GOERLI.clone());
+        assert!(!consensus.has_block_reward(U256::ZERO, U256::from(1)));
+    }
+}
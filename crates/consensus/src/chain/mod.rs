@@ -0,0 +1,7 @@
+//! Consensus selected by the chain specification.
+
+mod builder;
+mod chain_consensus;
+
+pub use builder::ChainConsensusBuilder;
+pub use chain_consensus::ChainConsensus;
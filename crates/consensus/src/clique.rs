@@ -0,0 +1,249 @@
+//! Validation of proof-of-authority blocks, see [EIP-225](https://eips.ethereum.org/EIPS/eip-225).
+use crate::validation;
+use reth_interfaces::consensus::Error;
+use reth_primitives::{
+    Address, ChainSpec, Header, SealedBlock, SealedHeader, Signature, EMPTY_OMMER_ROOT, H256, U256,
+};
+
+/// Number of extra data prefix bytes reserved for signer vanity.
+pub const EXTRA_VANITY: usize = 32;
+
+/// Number of extra data suffix bytes reserved for the signer seal.
+pub const EXTRA_SEAL: usize = 65;
+
+/// Block difficulty for in-turn signatures.
+pub const DIFF_IN_TURN: U256 = U256::from_limbs([2, 0, 0, 0]);
+
+/// Block difficulty for out-of-turn signatures.
+pub const DIFF_NO_TURN: U256 = U256::from_limbs([1, 0, 0, 0]);
+
+/// Nonce of a block voting to add a signer.
+const NONCE_AUTH_VOTE: u64 = u64::MAX;
+
+/// Nonce of a block voting to remove a signer.
+const NONCE_DROP_VOTE: u64 = 0;
+
+/// Validates headers sealed by clique before the merge.
+///
+/// Checks the header layout mandated by EIP-225 and that the signer can be recovered from the
+/// seal.
+///
+/// NOTE: Whether the recovered signer is authorized, and whether it signed in turn, requires
+/// replaying the signer votes since the last checkpoint and is not checked. Headers are only as
+/// trustworthy as the tip they lead to.
+#[derive(Debug)]
+pub struct CliqueValidator {
+    chain_spec: ChainSpec,
+    /// Minimum number of seconds between two consecutive blocks.
+    period: u64,
+    /// Number of blocks after which to checkpoint and reset the pending votes.
+    epoch: u64,
+}
+
+impl CliqueValidator {
+    /// Create a new instance of [CliqueValidator]
+    pub fn new(chain_spec: ChainSpec, period: u64, epoch: u64) -> Self {
+        Self { chain_spec, period, epoch }
+    }
+
+    /// Validates a proof-of-authority header against its parent.
+    pub fn pre_validate_header(
+        &self,
+        header: &SealedHeader,
+        parent: &SealedHeader,
+    ) -> Result<(), Error> {
+        validation::validate_header_standalone(header, &self.chain_spec)?;
+        validation::validate_header_regarding_parent(parent, header, &self.chain_spec)?;
+
+        let checkpoint =
+            header.number.checked_rem(self.epoch).ok_or(Error::CliqueEpochIsZero)? == 0;
+
+        // Checkpoint blocks must not vote.
+        if checkpoint && (header.beneficiary != Address::zero() || header.nonce != NONCE_DROP_VOTE)
+        {
+            return Err(Error::CliqueCheckpointVote)
+        }
+        if header.nonce != NONCE_AUTH_VOTE && header.nonce != NONCE_DROP_VOTE {
+            return Err(Error::CliqueVoteNonceInvalid { nonce: header.nonce })
+        }
+
+        // The extra data holds the vanity, the signer list on checkpoint blocks and the seal.
+        let len = header.extra_data.len();
+        let signers_len = len.checked_sub(EXTRA_VANITY + EXTRA_SEAL);
+        let valid_extra_data = match signers_len {
+            Some(signers_len) if checkpoint => signers_len % Address::len_bytes() == 0,
+            Some(signers_len) => signers_len == 0,
+            None => false,
+        };
+        if !valid_extra_data {
+            return Err(Error::CliqueExtraDataInvalid { len })
+        }
+
+        if header.mix_hash != H256::zero() {
+            return Err(Error::CliqueMixHashIsNotZero)
+        }
+        if header.ommers_hash != EMPTY_OMMER_ROOT {
+            return Err(Error::CliqueOmmerRootIsNotEmpty)
+        }
+        if header.difficulty != DIFF_IN_TURN && header.difficulty != DIFF_NO_TURN {
+            return Err(Error::CliqueDifficultyInvalid { difficulty: header.difficulty })
+        }
+
+        if parent.timestamp.checked_add(self.period).map_or(true, |min| header.timestamp < min) {
+            return Err(Error::CliqueBlockPeriod {
+                parent_timestamp: parent.timestamp,
+                timestamp: header.timestamp,
+                period: self.period,
+            })
+        }
+
+        recover_signer(header).ok_or(Error::CliqueSignerRecovery)?;
+
+        Ok(())
+    }
+
+    /// Validates a proof-of-authority header against the total difficulty of the chain.
+    ///
+    /// The layout and seal are checked in [CliqueValidator::pre_validate_header] and the signer
+    /// is not authorized, so there is nothing left to check.
+    pub fn validate_header(
+        &self,
+        _header: &SealedHeader,
+        _total_difficulty: U256,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Validates the body of a proof-of-authority block against its header.
+    pub fn pre_validate_block(&self, block: &SealedBlock) -> Result<(), Error> {
+        if !block.ommers.is_empty() {
+            return Err(Error::CliqueOmmerRootIsNotEmpty)
+        }
+        validation::validate_block_standalone(block, &self.chain_spec)
+    }
+
+    /// Clique does not issue block rewards, the signer only receives the transaction fees.
+    pub fn has_block_reward(&self) -> bool {
+        false
+    }
+}
+
+/// Recovers the address that sealed the header.
+///
+/// The seal signs the hash of the header with the seal stripped from the extra data. Returns
+/// `None` if the extra data is too short to hold a seal or the signature is invalid.
+pub fn recover_signer(header: &Header) -> Option<Address> {
+    let seal_start = header.extra_data.len().checked_sub(EXTRA_SEAL)?;
+    let seal = &header.extra_data[seal_start..];
+
+    let mut unsealed = header.clone();
+    unsealed.extra_data = header.extra_data[..seal_start].to_vec().into();
+
+    let signature = Signature {
+        r: U256::try_from_be_slice(&seal[..32])?,
+        s: U256::try_from_be_slice(&seal[32..64])?,
+        odd_y_parity: seal[64] == 1,
+    };
+    signature.recover_signer(unsealed.hash_slow())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{ChainSpecBuilder, ConsensusEngine, CLIQUE_DEFAULT_EPOCH};
+
+    fn validator() -> CliqueValidator {
+        let chain_spec = ChainSpecBuilder::mainnet()
+            .engine(ConsensusEngine::Clique { period: 15, epoch: CLIQUE_DEFAULT_EPOCH })
+            .build();
+        CliqueValidator::new(chain_spec, 15, CLIQUE_DEFAULT_EPOCH)
+    }
+
+    fn headers() -> (SealedHeader, Header) {
+        let parent = Header { number: 1, timestamp: 100, gas_limit: 5000, ..Default::default() };
+        let header = Header {
+            number: 2,
+            parent_hash: parent.hash_slow(),
+            timestamp: 115,
+            gas_limit: 5000,
+            difficulty: DIFF_IN_TURN,
+            extra_data: vec![0; EXTRA_VANITY + EXTRA_SEAL].into(),
+            ..Default::default()
+        };
+        (parent.seal_slow(), header)
+    }
+
+    #[test]
+    fn reject_invalid_layout() {
+        let validator = validator();
+        let (parent, header) = headers();
+
+        let mut short_extra_data = header.clone();
+        short_extra_data.extra_data = vec![0; EXTRA_VANITY].into();
+        assert_eq!(
+            validator.pre_validate_header(&short_extra_data.seal_slow(), &parent),
+            Err(Error::CliqueExtraDataInvalid { len: EXTRA_VANITY })
+        );
+
+        let mut signers_outside_checkpoint = header.clone();
+        signers_outside_checkpoint.extra_data = vec![0; EXTRA_VANITY + 20 + EXTRA_SEAL].into();
+        assert_eq!(
+            validator.pre_validate_header(&signers_outside_checkpoint.seal_slow(), &parent),
+            Err(Error::CliqueExtraDataInvalid { len: EXTRA_VANITY + 20 + EXTRA_SEAL })
+        );
+
+        let mut invalid_difficulty = header.clone();
+        invalid_difficulty.difficulty = U256::from(3);
+        assert_eq!(
+            validator.pre_validate_header(&invalid_difficulty.seal_slow(), &parent),
+            Err(Error::CliqueDifficultyInvalid { difficulty: U256::from(3) })
+        );
+
+        let mut invalid_nonce = header.clone();
+        invalid_nonce.nonce = 1;
+        assert_eq!(
+            validator.pre_validate_header(&invalid_nonce.seal_slow(), &parent),
+            Err(Error::CliqueVoteNonceInvalid { nonce: 1 })
+        );
+
+        let mut too_early = header;
+        too_early.timestamp = 110;
+        assert_eq!(
+            validator.pre_validate_header(&too_early.seal_slow(), &parent),
+            Err(Error::CliqueBlockPeriod { parent_timestamp: 100, timestamp: 110, period: 15 })
+        );
+    }
+
+    #[test]
+    fn reject_invalid_config() {
+        let (parent, header) = headers();
+
+        let validator = CliqueValidator::new(validator().chain_spec, 15, 0);
+        assert_eq!(
+            validator.pre_validate_header(&header.clone().seal_slow(), &parent),
+            Err(Error::CliqueEpochIsZero)
+        );
+
+        let validator = CliqueValidator::new(validator.chain_spec, u64::MAX, CLIQUE_DEFAULT_EPOCH);
+        assert_eq!(
+            validator.pre_validate_header(&header.seal_slow(), &parent),
+            Err(Error::CliqueBlockPeriod {
+                parent_timestamp: 100,
+                timestamp: 115,
+                period: u64::MAX
+            })
+        );
+    }
+
+    #[test]
+    fn reject_invalid_seal() {
+        let validator = validator();
+        let (parent, header) = headers();
+
+        // An all-zero seal is not a valid signature.
+        assert_eq!(
+            validator.pre_validate_header(&header.seal_slow(), &parent),
+            Err(Error::CliqueSignerRecovery)
+        );
+    }
+}
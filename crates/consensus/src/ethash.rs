@@ -0,0 +1,186 @@
+//! Validation of proof-of-work blocks.
+use crate::validation;
+use reth_interfaces::consensus::Error;
+use reth_primitives::{
+    ChainSpec, Hardfork, Header, SealedBlock, SealedHeader, EMPTY_OMMER_ROOT, U256,
+};
+
+/// The lower bound of the block difficulty.
+pub const MINIMUM_DIFFICULTY: U256 = U256::from_limbs([131_072, 0, 0, 0]);
+
+/// The divisor of the parent difficulty used to bound the difficulty adjustment.
+const DIFFICULTY_BOUND_DIVISOR: u64 = 2048;
+
+/// The number of blocks between two doublings of the difficulty bomb.
+const EXP_DIFFICULTY_PERIOD: u64 = 100_000;
+
+/// The number of blocks the difficulty bomb was delayed by, in activation order.
+const BOMB_DELAYS: [(Hardfork, u64); 6] = [
+    (Hardfork::GrayGlacier, 11_400_000),
+    (Hardfork::ArrowGlacier, 10_700_000),
+    (Hardfork::London, 9_700_000),
+    (Hardfork::MuirGlacier, 9_000_000),
+    (Hardfork::Constantinople, 5_000_000),
+    (Hardfork::Byzantium, 3_000_000),
+];
+
+/// Validates headers sealed by ethash before the merge.
+///
+/// The difficulty of every header is checked against the difficulty adjustment algorithm of the
+/// active hardfork.
+///
+/// NOTE: The proof-of-work seal itself (`mix_hash` and `nonce`) is not verified, since that
+/// requires the ethash cache of the header's epoch. Headers are only as trustworthy as the tip
+/// they lead to.
+#[derive(Debug)]
+pub struct EthashValidator {
+    chain_spec: ChainSpec,
+}
+
+impl EthashValidator {
+    /// Create a new instance of [EthashValidator]
+    pub fn new(chain_spec: ChainSpec) -> Self {
+        Self { chain_spec }
+    }
+
+    /// Validates a proof-of-work header against its parent.
+    pub fn pre_validate_header(
+        &self,
+        header: &SealedHeader,
+        parent: &SealedHeader,
+    ) -> Result<(), Error> {
+        validation::validate_header_standalone(header, &self.chain_spec)?;
+        validation::validate_header_regarding_parent(parent, header, &self.chain_spec)?;
+
+        let expected =
+            calculate_difficulty(parent, header.number, header.timestamp, &self.chain_spec);
+        if header.difficulty != expected {
+            return Err(Error::DifficultyDiff { got: header.difficulty, expected })
+        }
+
+        Ok(())
+    }
+
+    /// Validates a proof-of-work header against the total difficulty of the chain.
+    ///
+    /// The difficulty itself is checked in [EthashValidator::pre_validate_header] and the seal is
+    /// not verified, so there is nothing left to check.
+    pub fn validate_header(
+        &self,
+        _header: &SealedHeader,
+        _total_difficulty: U256,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Validates the body of a proof-of-work block against its header.
+    pub fn pre_validate_block(&self, block: &SealedBlock) -> Result<(), Error> {
+        validation::validate_block_standalone(block, &self.chain_spec)
+    }
+
+    /// Proof-of-work blocks reward the miner and the ommers.
+    pub fn has_block_reward(&self) -> bool {
+        true
+    }
+}
+
+/// Calculates the difficulty of a block with the given number and timestamp on top of `parent`.
+///
+/// See the Yellow Paper section 4.3.4 "Block Header Validity", [EIP-2](https://eips.ethereum.org/EIPS/eip-2)
+/// and [EIP-100](https://eips.ethereum.org/EIPS/eip-100).
+pub fn calculate_difficulty(
+    parent: &Header,
+    number: u64,
+    timestamp: u64,
+    chain_spec: &ChainSpec,
+) -> U256 {
+    let elapsed = timestamp.saturating_sub(parent.timestamp);
+
+    // The adjustment factor, bounded to -99 from Homestead on.
+    let adjustment = if chain_spec.fork(Hardfork::Byzantium).active_at_block(number) {
+        let uncles = if parent.ommers_hash == EMPTY_OMMER_ROOT { 1 } else { 2 };
+        (uncles - (elapsed / 9) as i64).max(-99)
+    } else if chain_spec.fork(Hardfork::Homestead).active_at_block(number) {
+        (1 - (elapsed / 10) as i64).max(-99)
+    } else if elapsed < 13 {
+        1
+    } else {
+        -1
+    };
+
+    let step = parent.difficulty / U256::from(DIFFICULTY_BOUND_DIVISOR);
+    let step = step * U256::from(adjustment.unsigned_abs());
+    let mut difficulty = if adjustment >= 0 {
+        parent.difficulty.saturating_add(step)
+    } else {
+        parent.difficulty.saturating_sub(step)
+    };
+    difficulty = difficulty.max(MINIMUM_DIFFICULTY);
+
+    // The difficulty bomb, which later hardforks delayed by pretending to be at an earlier block.
+    let bomb_delay = BOMB_DELAYS
+        .iter()
+        .find(|(fork, _)| chain_spec.fork(*fork).active_at_block(number))
+        .map_or(0, |(_, delay)| *delay);
+    let periods = number.saturating_sub(bomb_delay) / EXP_DIFFICULTY_PERIOD;
+    if periods > 1 {
+        difficulty = difficulty.saturating_add(U256::from(1) << (periods - 2) as usize);
+    }
+
+    difficulty
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::MAINNET;
+
+    #[test]
+    fn mainnet_frontier_difficulty() {
+        // Mainnet block 1 on top of genesis
+        let genesis = MAINNET.genesis_header();
+        assert_eq!(
+            calculate_difficulty(&genesis, 1, 1438269988, &MAINNET),
+            U256::from(17_171_480_576u64)
+        );
+    }
+
+    #[test]
+    fn difficulty_is_bounded() {
+        let parent = Header { difficulty: MINIMUM_DIFFICULTY, ..Default::default() };
+        assert_eq!(calculate_difficulty(&parent, 1, 1000, &MAINNET), MINIMUM_DIFFICULTY);
+    }
+
+    #[test]
+    fn reject_wrong_difficulty() {
+        let validator = EthashValidator::new(MAINNET.clone());
+        let parent = Header {
+            number: 1,
+            timestamp: 100,
+            gas_limit: 5000,
+            difficulty: U256::from(17_171_480_576u64),
+            ..Default::default()
+        };
+        let mut header = Header {
+            number: 2,
+            parent_hash: parent.hash_slow(),
+            timestamp: 105,
+            gas_limit: 5000,
+            ..Default::default()
+        };
+        header.difficulty =
+            calculate_difficulty(&parent, header.number, header.timestamp, &MAINNET);
+
+        let parent = parent.seal_slow();
+        assert_eq!(validator.pre_validate_header(&header.clone().seal_slow(), &parent), Ok(()));
+
+        header.difficulty += U256::from(1);
+        assert_eq!(
+            validator.pre_validate_header(&header.clone().seal_slow(), &parent),
+            Err(Error::DifficultyDiff {
+                got: header.difficulty,
+                expected: header.difficulty - U256::from(1)
+            })
+        );
+    }
+}
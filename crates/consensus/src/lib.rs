@@ -9,5 +9,14 @@
 /// Beacon consensus implementation.
 pub mod beacon;
 
+/// Consensus selected by the chain specification.
+pub mod chain;
+
+/// Clique proof-of-authority validation.
+pub mod clique;
+
+/// Ethash proof-of-work validation.
+pub mod ethash;
+
 /// Collection of consensus validation methods.
 pub mod validation;
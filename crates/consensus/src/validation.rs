@@ -1,8 +1,11 @@
 //! Collection of methods for block validation.
 use reth_interfaces::{consensus::Error, Result as RethResult};
 use reth_primitives::{
-    BlockNumber, ChainSpec, Hardfork, Header, SealedBlock, SealedHeader, Transaction,
-    TransactionSignedEcRecovered, TxEip1559, TxEip2930, TxLegacy,
+    constants::{BLOB_GAS_PER_BLOB, MAX_BLOB_GAS_PER_BLOCK, VERSIONED_HASH_VERSION_KZG},
+    eip4844::calculate_excess_blob_gas,
+    BlockNumber, ChainSpec, ConsensusEngine, Hardfork, Header, SealedBlock, SealedHeader,
+    Transaction, TransactionKind, TransactionSignedEcRecovered, TxEip1559, TxEip2930, TxEip4844,
+    TxLegacy, U256,
 };
 use reth_provider::{AccountProvider, HeaderProvider};
 use std::{
@@ -34,7 +37,11 @@ pub fn validate_header_standalone(
 
     // From yellow paper: extraData: An arbitrary byte array containing data
     // relevant to this block. This must be 32 bytes or fewer; formally Hx.
-    if header.extra_data.len() > 32 {
+    // Clique sealed blocks carry the signer list and seal in the extra data instead, which is
+    // validated by the clique engine.
+    let is_clique_sealed = matches!(chain_spec.engine, ConsensusEngine::Clique { .. }) &&
+        header.difficulty != U256::ZERO;
+    if !is_clique_sealed && header.extra_data.len() > 32 {
         return Err(Error::ExtraDataExceedsMax { len: header.extra_data.len() })
     }
 
@@ -56,17 +63,51 @@ pub fn validate_header_standalone(
         return Err(Error::WithdrawalsRootUnexpected)
     }
 
+    // EIP-4844: Shard Blob Transactions
+    // EIP-4788: Beacon block root in the EVM
+    if chain_spec.fork(Hardfork::Cancun).active_at_timestamp(header.timestamp) {
+        let blob_gas_used = header.blob_gas_used.ok_or(Error::BlobGasUsedMissing)?;
+        if header.excess_blob_gas.is_none() {
+            return Err(Error::ExcessBlobGasMissing)
+        }
+        if header.parent_beacon_block_root.is_none() {
+            return Err(Error::ParentBeaconBlockRootMissing)
+        }
+
+        if blob_gas_used > MAX_BLOB_GAS_PER_BLOCK {
+            return Err(Error::BlobGasUsedExceedsMaxBlobGasPerBlock {
+                blob_gas_used,
+                max_blob_gas_per_block: MAX_BLOB_GAS_PER_BLOCK,
+            })
+        }
+        if blob_gas_used % BLOB_GAS_PER_BLOB != 0 {
+            return Err(Error::BlobGasUsedNotMultipleOfBlobGasPerBlob {
+                blob_gas_used,
+                blob_gas_per_blob: BLOB_GAS_PER_BLOB,
+            })
+        }
+    } else if header.blob_gas_used.is_some() {
+        return Err(Error::BlobGasUsedUnexpected)
+    } else if header.excess_blob_gas.is_some() {
+        return Err(Error::ExcessBlobGasUnexpected)
+    } else if header.parent_beacon_block_root.is_some() {
+        return Err(Error::ParentBeaconBlockRootUnexpected)
+    }
+
     Ok(())
 }
 
 /// Validate a transaction in regards to a block header.
 ///
-/// The only parameter from the header that affects the transaction is `base_fee`.
+/// The only parameters from the header that affect the transaction are `base_fee` and
+/// `blob_gasprice`.
 pub fn validate_transaction_regarding_header(
     transaction: &Transaction,
     chain_spec: &ChainSpec,
     at_block_number: BlockNumber,
+    at_timestamp: u64,
     base_fee: Option<u64>,
+    blob_gasprice: Option<u128>,
 ) -> Result<(), Error> {
     let chain_id = match transaction {
         Transaction::Legacy(TxLegacy { chain_id, .. }) => {
@@ -102,6 +143,37 @@ pub fn validate_transaction_regarding_header(
                 return Err(Error::TransactionPriorityFeeMoreThenMaxFee)
             }
 
+            Some(*chain_id)
+        }
+        Transaction::Eip4844(TxEip4844 {
+            chain_id,
+            max_fee_per_gas,
+            max_priority_fee_per_gas,
+            to,
+            blob_versioned_hashes,
+            ..
+        }) => {
+            // EIP-4844: Shard Blob Transactions https://eips.ethereum.org/EIPS/eip-4844
+            if !chain_spec.fork(Hardfork::Cancun).active_at_timestamp(at_timestamp) {
+                return Err(Error::TransactionEip4844Disabled)
+            }
+
+            if max_priority_fee_per_gas > max_fee_per_gas {
+                return Err(Error::TransactionPriorityFeeMoreThenMaxFee)
+            }
+
+            // Blob transactions are not allowed to create contracts.
+            if matches!(to, TransactionKind::Create) {
+                return Err(Error::TransactionEip4844Create)
+            }
+
+            if blob_versioned_hashes.is_empty() {
+                return Err(Error::TransactionEip4844NoBlobs)
+            }
+            if blob_versioned_hashes.iter().any(|hash| hash[0] != VERSIONED_HASH_VERSION_KZG) {
+                return Err(Error::TransactionEip4844InvalidVersionedHash)
+            }
+
             Some(*chain_id)
         }
     };
@@ -117,6 +189,15 @@ pub fn validate_transaction_regarding_header(
             return Err(Error::TransactionMaxFeeLessThenBaseFee)
         }
     }
+    // Check that the transaction can pay for its blobs.
+    // https://eips.ethereum.org/EIPS/eip-4844#execution-layer-validation
+    if let (Some(max_fee_per_blob_gas), Some(blob_gasprice)) =
+        (transaction.max_fee_per_blob_gas(), blob_gasprice)
+    {
+        if max_fee_per_blob_gas < blob_gasprice {
+            return Err(Error::TransactionMaxFeePerBlobGasLessThenBlobGasPrice)
+        }
+    }
 
     Ok(())
 }
@@ -139,7 +220,9 @@ pub fn validate_all_transaction_regarding_block_and_nonces<
             transaction,
             chain_spec,
             header.number,
+            header.timestamp,
             header.base_fee_per_gas,
+            header.blob_gasprice(),
         )?;
 
         // Get nonce, if there is previous transaction from same sender we need
@@ -227,6 +310,20 @@ pub fn validate_block_standalone(block: &SealedBlock, chain_spec: &ChainSpec) ->
         }
     }
 
+    // EIP-4844: Shard Blob Transactions
+    if chain_spec.fork(Hardfork::Cancun).active_at_timestamp(block.timestamp) {
+        // Check that the blob gas used in the header matches the sum of the blob gas used by each
+        // blob tx
+        let header_blob_gas_used = block.blob_gas_used.ok_or(Error::BlobGasUsedMissing)?;
+        let total_blob_gas: u64 = block.body.iter().map(|tx| tx.blob_gas_used()).sum();
+        if total_blob_gas != header_blob_gas_used {
+            return Err(Error::BlobGasUsedDiff {
+                got: header_blob_gas_used,
+                expected: total_blob_gas,
+            })
+        }
+    }
+
     Ok(())
 }
 
@@ -327,6 +424,23 @@ pub fn validate_header_regarding_parent(
         }
     }
 
+    // EIP-4844 check excess blob gas
+    if chain_spec.fork(Hardfork::Cancun).active_at_timestamp(child.timestamp) {
+        let excess_blob_gas = child.excess_blob_gas.ok_or(Error::ExcessBlobGasMissing)?;
+
+        // The parent fields are treated as zero on the fork block.
+        let expected_excess_blob_gas = calculate_excess_blob_gas(
+            parent.excess_blob_gas.unwrap_or_default(),
+            parent.blob_gas_used.unwrap_or_default(),
+        );
+        if expected_excess_blob_gas != excess_blob_gas {
+            return Err(Error::ExcessBlobGasDiff {
+                got: excess_blob_gas,
+                expected: expected_excess_blob_gas,
+            })
+        }
+    }
+
     Ok(())
 }
 
@@ -391,7 +505,7 @@ mod tests {
     use reth_interfaces::Result;
     use reth_primitives::{
         hex_literal::hex, proofs, Account, Address, BlockHash, Bytes, ChainSpecBuilder, Header,
-        Signature, TransactionKind, TransactionSigned, Withdrawal, MAINNET, U256,
+        Signature, TransactionKind, TransactionSigned, Withdrawal, H256, MAINNET, U256,
     };
     use std::ops::RangeBounds;
 
@@ -512,7 +626,10 @@ mod tests {
             mix_hash: hex!("0000000000000000000000000000000000000000000000000000000000000000").into(),
             nonce: 0x0000000000000000,
             base_fee_per_gas: 0x28f0001df.into(),
-            withdrawals_root: None
+            withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
         };
         // size: 0x9b5
 
@@ -660,4 +777,131 @@ mod tests {
 
         assert_eq!(validate_header_standalone(&header, &chain_spec), Ok(()));
     }
+
+    #[test]
+    fn cancun_header_fields() {
+        let chain_spec = ChainSpecBuilder::mainnet().cancun_activated().build();
+
+        let header = Header {
+            base_fee_per_gas: Some(1337u64),
+            withdrawals_root: Some(proofs::calculate_withdrawals_root(&[])),
+            blob_gas_used: Some(BLOB_GAS_PER_BLOB),
+            excess_blob_gas: Some(0),
+            parent_beacon_block_root: Some(H256::zero()),
+            ..Default::default()
+        };
+        assert_eq!(validate_header_standalone(&header.clone().seal_slow(), &chain_spec), Ok(()));
+
+        let missing = Header { blob_gas_used: None, ..header.clone() }.seal_slow();
+        assert_eq!(
+            validate_header_standalone(&missing, &chain_spec),
+            Err(Error::BlobGasUsedMissing)
+        );
+
+        let too_much = Header {
+            blob_gas_used: Some(MAX_BLOB_GAS_PER_BLOCK + BLOB_GAS_PER_BLOB),
+            ..header.clone()
+        }
+        .seal_slow();
+        assert_matches!(
+            validate_header_standalone(&too_much, &chain_spec),
+            Err(Error::BlobGasUsedExceedsMaxBlobGasPerBlock { .. })
+        );
+
+        let not_multiple = Header { blob_gas_used: Some(1), ..header.clone() }.seal_slow();
+        assert_matches!(
+            validate_header_standalone(&not_multiple, &chain_spec),
+            Err(Error::BlobGasUsedNotMultipleOfBlobGasPerBlob { .. })
+        );
+
+        // cancun fields are not allowed before the fork
+        let chain_spec = ChainSpecBuilder::mainnet().shanghai_activated().build();
+        assert_eq!(
+            validate_header_standalone(&header.seal_slow(), &chain_spec),
+            Err(Error::BlobGasUsedUnexpected)
+        );
+    }
+
+    #[test]
+    fn cancun_excess_blob_gas() {
+        let chain_spec = ChainSpecBuilder::mainnet().cancun_activated().build();
+
+        let parent = Header {
+            gas_limit: 30_000_000,
+            base_fee_per_gas: Some(7),
+            blob_gas_used: Some(4 * BLOB_GAS_PER_BLOB),
+            excess_blob_gas: Some(0),
+            ..Default::default()
+        };
+        let child = Header {
+            number: 1,
+            excess_blob_gas: Some(BLOB_GAS_PER_BLOB),
+            base_fee_per_gas: Some(calculate_next_block_base_fee(0, 30_000_000, 7)),
+            ..parent.clone()
+        };
+        assert_eq!(
+            validate_header_regarding_parent(
+                &parent.clone().seal_slow(),
+                &child.clone().seal_slow(),
+                &chain_spec
+            ),
+            Ok(())
+        );
+
+        let child = Header { excess_blob_gas: Some(0), ..child };
+        assert_eq!(
+            validate_header_regarding_parent(&parent.seal_slow(), &child.seal_slow(), &chain_spec),
+            Err(Error::ExcessBlobGasDiff { got: 0, expected: BLOB_GAS_PER_BLOB })
+        );
+    }
+
+    #[test]
+    fn cancun_blob_transaction() {
+        let mut versioned_hash = H256::zero();
+        versioned_hash[0] = VERSIONED_HASH_VERSION_KZG;
+        let blob_tx = Transaction::Eip4844(TxEip4844 {
+            chain_id: 1,
+            max_fee_per_gas: 10,
+            max_fee_per_blob_gas: 1,
+            to: TransactionKind::Call(Address::default()),
+            blob_versioned_hashes: vec![versioned_hash],
+            ..Default::default()
+        });
+
+        let shanghai = ChainSpecBuilder::mainnet().shanghai_activated().build();
+        assert_eq!(
+            validate_transaction_regarding_header(&blob_tx, &shanghai, 1, 1, Some(1), None),
+            Err(Error::TransactionEip4844Disabled)
+        );
+
+        let cancun = ChainSpecBuilder::mainnet().cancun_activated().build();
+        assert_eq!(
+            validate_transaction_regarding_header(&blob_tx, &cancun, 1, 1, Some(1), Some(1)),
+            Ok(())
+        );
+        assert_eq!(
+            validate_transaction_regarding_header(&blob_tx, &cancun, 1, 1, Some(1), Some(2)),
+            Err(Error::TransactionMaxFeePerBlobGasLessThenBlobGasPrice)
+        );
+
+        // the blob gas used in the header must match the transactions
+        let tx = TransactionSigned::from_transaction_and_signature(blob_tx, Signature::default());
+        let body = vec![tx];
+        let header = Header {
+            transactions_root: proofs::calculate_transaction_root(body.iter()),
+            withdrawals_root: Some(proofs::calculate_withdrawals_root(&[])),
+            blob_gas_used: Some(0),
+            ..Default::default()
+        };
+        let block = SealedBlock {
+            header: header.seal_slow(),
+            body,
+            withdrawals: Some(vec![]),
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_block_standalone(&block, &cancun),
+            Err(Error::BlobGasUsedDiff { got: 0, expected: BLOB_GAS_PER_BLOB })
+        );
+    }
 }
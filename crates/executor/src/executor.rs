@@ -3,8 +3,10 @@ use crate::execution_result::{
 };
 use reth_interfaces::executor::Error;
 use reth_primitives::{
-    bloom::logs_bloom, Account, Address, Block, Bloom, ChainSpec, Hardfork, Header, Log, Receipt,
-    TransactionSigned, H256, U256,
+    bloom::logs_bloom,
+    constants::{BEACON_ROOTS_ADDRESS, SYSTEM_ADDRESS, SYSTEM_CALL_GAS_LIMIT},
+    Account, Address, Block, Bloom, ChainSpec, ConsensusEngine, Hardfork, Header, Log, Receipt,
+    Transaction, TransactionSigned, H256, U256,
 };
 use reth_provider::{BlockExecutor, StateProvider};
 use reth_revm::{
@@ -18,7 +20,7 @@ use revm::{
     db::AccountState,
     primitives::{
         hash_map::{self, Entry},
-        Account as RevmAccount, AccountInfo, Bytecode, ResultAndState,
+        Account as RevmAccount, AccountInfo, Bytecode, ResultAndState, TransactTo,
     },
     EVM,
 };
@@ -277,8 +279,11 @@ where
     ///
     /// NOTE: Related to Ethereum reward change, for other network this is probably going to be
     /// moved to config.
+    ///
+    /// Clique sealed blocks carry no reward.
     fn get_block_reward(&self, header: &Header, total_difficulty: U256) -> Option<u128> {
-        if self.chain_spec.fork(Hardfork::Paris).active_at_ttd(total_difficulty, header.difficulty)
+        if self.chain_spec.fork(Hardfork::Paris).active_at_ttd(total_difficulty, header.difficulty) ||
+            matches!(self.chain_spec.engine, ConsensusEngine::Clique { .. })
         {
             None
         } else if self.chain_spec.fork(Hardfork::Petersburg).active_at_block(header.number) {
@@ -364,6 +369,96 @@ where
         }
     }
 
+    /// Calls the [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788) beacon roots contract to
+    /// store the parent beacon block root of the block, and commits its state.
+    ///
+    /// Returns an empty changeset before Cancun and for the genesis block.
+    ///
+    /// Assumes the block environment has been filled via `init_env`.
+    fn apply_beacon_root_contract_call(
+        &mut self,
+        header: &Header,
+    ) -> Result<BTreeMap<Address, AccountChangeSet>, Error> {
+        if !self.chain_spec.fork(Hardfork::Cancun).active_at_timestamp(header.timestamp) ||
+            header.number == 0
+        {
+            return Ok(BTreeMap::new())
+        }
+        let parent_beacon_block_root =
+            header.parent_beacon_block_root.ok_or(Error::MissingParentBeaconBlockRoot)?;
+
+        // The system call is not subject to the fee market and the gas limit of the block.
+        let previous_tx = std::mem::take(&mut self.evm.env.tx);
+        let previous_basefee = std::mem::replace(&mut self.evm.env.block.basefee, U256::ZERO);
+        let previous_gas_limit =
+            std::mem::replace(&mut self.evm.env.block.gas_limit, U256::from(SYSTEM_CALL_GAS_LIMIT));
+        let tx = &mut self.evm.env.tx;
+        tx.caller = SYSTEM_ADDRESS;
+        tx.transact_to = TransactTo::Call(BEACON_ROOTS_ADDRESS);
+        tx.data = parent_beacon_block_root.0.to_vec().into();
+        tx.gas_limit = SYSTEM_CALL_GAS_LIMIT;
+        tx.gas_price = U256::ZERO;
+        tx.value = U256::ZERO;
+        // No nonce checks for the system address.
+        tx.nonce = None;
+
+        let out = self.evm.transact();
+
+        self.evm.env.tx = previous_tx;
+        self.evm.env.block.basefee = previous_basefee;
+        self.evm.env.block.gas_limit = previous_gas_limit;
+
+        let ResultAndState { mut state, .. } = out.map_err(|e| Error::EVM(format!("{e:?}")))?;
+
+        // Only the storage of the beacon roots contract is changed, the system address and the
+        // beneficiary are merely touched by the call.
+        state.remove(&SYSTEM_ADDRESS);
+        state.remove(&header.beneficiary);
+
+        let (changeset, _) = self.commit_changes(state);
+        Ok(changeset)
+    }
+
+    /// Charges the blob fee of an [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+    /// transaction from the sender's balance before it is executed.
+    ///
+    /// revm is not aware of blob gas, so the fee cap and the max cost including the blob gas are
+    /// checked here. The blob fee is burned. Returns the charged fee, `None` for other
+    /// transaction types.
+    fn charge_blob_fee(
+        &mut self,
+        header: &Header,
+        transaction: &TransactionSigned,
+        sender: Address,
+    ) -> Result<Option<U256>, Error> {
+        let Transaction::Eip4844(tx) = transaction.as_ref() else { return Ok(None) };
+
+        let blob_gasprice = header.blob_gasprice().ok_or(Error::MissingExcessBlobGas)?;
+        if tx.max_fee_per_blob_gas < blob_gasprice {
+            return Err(Error::BlobFeeCapTooLow {
+                max_fee_per_blob_gas: tx.max_fee_per_blob_gas,
+                blob_gasprice,
+            })
+        }
+
+        let blob_gas = U256::from(tx.blob_gas());
+        let max_cost = U256::from(tx.gas_limit) * U256::from(tx.max_fee_per_gas) +
+            U256::from(tx.value) +
+            blob_gas * U256::from(tx.max_fee_per_blob_gas);
+
+        let account = self.db().load_account(sender).map_err(|_| Error::ProviderError)?;
+        if account.info.balance < max_cost {
+            return Err(Error::InsufficientFundsForBlobTransaction {
+                balance: account.info.balance,
+                max_cost,
+            })
+        }
+
+        let blob_fee = blob_gas * U256::from(blob_gasprice);
+        account.info.balance -= blob_fee;
+        Ok(Some(blob_fee))
+    }
+
     /// Runs a single transaction in the configured environment and proceeds
     /// to return the result and state diff (without applying it).
     ///
@@ -410,65 +505,136 @@ where
         let mut tx_changesets = Vec::with_capacity(block.body.len());
 
         for (transaction, sender) in block.body.iter().zip(senders.into_iter()) {
-            // The sum of the transaction’s gas limit, Tg, and the gas utilised in this block prior,
-            // must be no greater than the block’s gasLimit.
-            let block_available_gas = block.header.gas_limit - cumulative_gas_used;
-            if transaction.gas_limit() > block_available_gas {
-                return Err(Error::TransactionGasLimitMoreThenAvailableBlockGas {
-                    transaction_gas_limit: transaction.gas_limit(),
-                    block_available_gas,
-                })
-            }
-            // Execute transaction.
-            let ResultAndState { result, state } = self.transact(transaction, sender)?;
-
-            // commit changes
-            let (changeset, new_bytecodes) = self.commit_changes(state);
-
-            // append gas used
-            cumulative_gas_used += result.gas_used();
-
-            // cast revm logs to reth logs
-            let logs: Vec<Log> = result.logs().into_iter().map(into_reth_log).collect();
-
-            // Push transaction changeset and calculate header bloom filter for receipt.
-            tx_changesets.push(TransactionChangeSet {
-                receipt: Receipt {
-                    tx_type: transaction.tx_type(),
-                    // Success flag was added in `EIP-658: Embedding transaction status code in
-                    // receipts`.
-                    success: result.is_success(),
-                    cumulative_gas_used,
-                    bloom: logs_bloom(logs.iter()),
-                    logs,
-                },
-                changeset,
-                new_bytecodes,
-            });
+            let tx_changeset =
+                self.execute_transaction(&block.header, transaction, sender, cumulative_gas_used)?;
+            cumulative_gas_used = tx_changeset.receipt.cumulative_gas_used;
+            tx_changesets.push(tx_changeset);
         }
 
         Ok((tx_changesets, cumulative_gas_used))
     }
-}
 
-impl<DB> BlockExecutor<DB> for Executor<DB>
-where
-    DB: StateProvider,
-{
-    fn execute(
+    /// Executes the block like [BlockExecutor::execute], but leaves out the transactions that fail
+    /// to execute, e.g. because of a nonce gap or insufficient funds, instead of failing the block.
+    ///
+    /// The gas used of the block is not checked. Returns the result together with the indices of
+    /// the transactions that were left out.
+    pub fn execute_skipping_invalid(
         &mut self,
         block: &Block,
         total_difficulty: U256,
         senders: Option<Vec<Address>>,
-    ) -> Result<ExecutionResult, Error> {
-        let (tx_changesets, cumulative_gas_used) =
-            self.execute_transactions(block, total_difficulty, senders)?;
+    ) -> Result<(ExecutionResult, Vec<usize>), Error> {
+        let senders = self.recover_senders(&block.body, senders)?;
 
-        // Check if gas used matches the value set in header.
-        if block.gas_used != cumulative_gas_used {
-            return Err(Error::BlockGasUsed { got: cumulative_gas_used, expected: block.gas_used })
+        self.init_env(&block.header, total_difficulty);
+        let pre_block_changesets = self.apply_beacon_root_contract_call(&block.header)?;
+
+        let mut cumulative_gas_used = 0;
+        let mut tx_changesets = Vec::with_capacity(block.body.len());
+        let mut invalid = Vec::new();
+        for (idx, (transaction, sender)) in block.body.iter().zip(senders).enumerate() {
+            match self.execute_transaction(&block.header, transaction, sender, cumulative_gas_used)
+            {
+                Ok(tx_changeset) => {
+                    cumulative_gas_used = tx_changeset.receipt.cumulative_gas_used;
+                    tx_changesets.push(tx_changeset);
+                }
+                Err(err) => {
+                    tracing::trace!(
+                        target: "evm",
+                        ?err, hash = ?transaction.hash(),
+                        "Skipping transaction that failed to execute"
+                    );
+                    invalid.push(idx);
+                }
+            }
+        }
+
+        let block_changesets = self.post_block_changesets(block, total_difficulty)?;
+        Ok((ExecutionResult { tx_changesets, pre_block_changesets, block_changesets }, invalid))
+    }
+
+    /// Executes a single transaction of the block and commits its state, given the gas used by the
+    /// previous transactions of the block.
+    ///
+    /// The state is left unchanged if the transaction fails to execute.
+    fn execute_transaction(
+        &mut self,
+        header: &Header,
+        transaction: &TransactionSigned,
+        sender: Address,
+        cumulative_gas_used: u64,
+    ) -> Result<TransactionChangeSet, Error> {
+        // The sum of the transaction’s gas limit, Tg, and the gas utilised in this block prior,
+        // must be no greater than the block’s gasLimit.
+        let block_available_gas = header.gas_limit - cumulative_gas_used;
+        if transaction.gas_limit() > block_available_gas {
+            return Err(Error::TransactionGasLimitMoreThenAvailableBlockGas {
+                transaction_gas_limit: transaction.gas_limit(),
+                block_available_gas,
+            })
+        }
+        let blob_fee = self.charge_blob_fee(header, transaction, sender)?;
+
+        // Execute transaction.
+        let ResultAndState { result, state } = match self.transact(transaction, sender) {
+            Ok(out) => out,
+            Err(err) => {
+                if let Some(blob_fee) = blob_fee {
+                    // Refund the blob fee, the transaction is not part of the state.
+                    let account =
+                        self.db().load_account(sender).map_err(|_| Error::ProviderError)?;
+                    account.info.balance += blob_fee;
+                }
+                return Err(err)
+            }
+        };
+
+        // commit changes
+        let (mut changeset, new_bytecodes) = self.commit_changes(state);
+
+        // The blob fee was charged before the execution, the balance prior to the transaction
+        // includes it.
+        if let Some(blob_fee) = blob_fee {
+            if let Some(AccountChangeSet {
+                account: AccountInfoChangeSet::Changed { old, .. },
+                ..
+            }) = changeset.get_mut(&sender)
+            {
+                old.balance += blob_fee;
+            }
         }
 
+        // append gas used
+        let cumulative_gas_used = cumulative_gas_used + result.gas_used();
+
+        // cast revm logs to reth logs
+        let logs: Vec<Log> = result.logs().into_iter().map(into_reth_log).collect();
+
+        // Return transaction changeset and calculate header bloom filter for receipt.
+        Ok(TransactionChangeSet {
+            receipt: Receipt {
+                tx_type: transaction.tx_type(),
+                // Success flag was added in `EIP-658: Embedding transaction status code in
+                // receipts`.
+                success: result.is_success(),
+                cumulative_gas_used,
+                bloom: logs_bloom(logs.iter()),
+                logs,
+            },
+            changeset,
+            new_bytecodes,
+        })
+    }
+
+    /// Returns the account changes after the transactions of the block, like block rewards,
+    /// withdrawals or irregular state changes (DAO fork), and commits them.
+    fn post_block_changesets(
+        &mut self,
+        block: &Block,
+        total_difficulty: U256,
+    ) -> Result<BTreeMap<Address, AccountInfoChangeSet>, Error> {
         let mut block_changesets = BTreeMap::default();
         let balance_increments = self.post_block_balance_increments(block, total_difficulty)?;
         for (address, increment) in balance_increments {
@@ -483,7 +649,33 @@ where
             }
         }
 
-        Ok(ExecutionResult { tx_changesets, block_changesets })
+        Ok(block_changesets)
+    }
+}
+
+impl<DB> BlockExecutor<DB> for Executor<DB>
+where
+    DB: StateProvider,
+{
+    fn execute(
+        &mut self,
+        block: &Block,
+        total_difficulty: U256,
+        senders: Option<Vec<Address>>,
+    ) -> Result<ExecutionResult, Error> {
+        self.init_env(&block.header, total_difficulty);
+        let pre_block_changesets = self.apply_beacon_root_contract_call(&block.header)?;
+
+        let (tx_changesets, cumulative_gas_used) =
+            self.execute_transactions(block, total_difficulty, senders)?;
+
+        // Check if gas used matches the value set in header.
+        if block.gas_used != cumulative_gas_used {
+            return Err(Error::BlockGasUsed { got: cumulative_gas_used, expected: block.gas_used })
+        }
+
+        let block_changesets = self.post_block_changesets(block, total_difficulty)?;
+        Ok(ExecutionResult { tx_changesets, pre_block_changesets, block_changesets })
     }
 
     fn execute_and_verify_receipt(
@@ -501,9 +693,9 @@ where
             verify_receipt(block.header.receipts_root, block.header.logs_bloom, receipts_iter)?;
         }
 
-        // TODO Before Byzantium, receipts contained state root that would mean that expensive
-        // operation as hashing that is needed for state root got calculated in every
-        // transaction This was replaced with is_success flag.
+        // Before Byzantium, receipts contained the state root after each transaction instead of
+        // the status code, which needs the state trie to compute. The execution stage verifies
+        // them if enabled.
         // See more about EIP here: https://eips.ethereum.org/EIPS/eip-658
 
         Ok(execution_result)
@@ -537,8 +729,9 @@ pub fn verify_receipt<'a>(
 mod tests {
     use super::*;
     use reth_primitives::{
-        hex_literal::hex, keccak256, Account, Address, Bytecode, Bytes, ChainSpecBuilder,
-        ForkCondition, StorageKey, H256, MAINNET, U256,
+        constants::BLOB_GAS_PER_BLOB, hex_literal::hex, keccak256, Account, Address, Bytecode,
+        Bytes, ChainSpecBuilder, ForkCondition, Signature, StorageKey, TransactionKind, TxEip4844,
+        H256, MAINNET, U256,
     };
     use reth_provider::{AccountProvider, BlockHashProvider, StateProvider};
     use reth_revm::database::State;
@@ -1001,4 +1194,74 @@ mod tests {
         let account = db.load_account(account).unwrap();
         assert_eq!(account.account_state, AccountState::StorageCleared);
     }
+
+    #[test]
+    fn blob_fee_is_charged() {
+        let sender = Address::from_str("a94f5374fce5edbc8e2a8697c15331677e6ebf0b").unwrap();
+        let recipient = Address::from_str("1000000000000000000000000000000000000000").unwrap();
+        let balance = U256::from(0x3635c9adc5dea00000u128);
+
+        let mut db = StateProviderTest::default();
+        db.insert_account(
+            sender,
+            Account { balance, nonce: 0x00, bytecode_hash: None },
+            None,
+            HashMap::new(),
+        );
+
+        let transaction = TransactionSigned::from_transaction_and_signature(
+            Transaction::Eip4844(TxEip4844 {
+                chain_id: 1,
+                gas_limit: 21_000,
+                max_fee_per_gas: 10,
+                to: TransactionKind::Call(recipient),
+                blob_versioned_hashes: vec![H256([1; 32]); 2],
+                max_fee_per_blob_gas: 1,
+                ..Default::default()
+            }),
+            Signature::default(),
+        );
+        let block = Block {
+            header: Header {
+                number: 1,
+                timestamp: 1,
+                gas_limit: 30_000_000,
+                gas_used: 21_000,
+                base_fee_per_gas: Some(7),
+                blob_gas_used: Some(2 * BLOB_GAS_PER_BLOB),
+                excess_blob_gas: Some(0),
+                parent_beacon_block_root: Some(H256([2; 32])),
+                ..Default::default()
+            },
+            body: vec![transaction],
+            ommers: vec![],
+            withdrawals: Some(vec![]),
+        };
+
+        let chain_spec = Arc::new(ChainSpecBuilder::mainnet().cancun_activated().build());
+        let mut executor = Executor::new(chain_spec.clone(), SubState::new(State::new(db.clone())));
+        let out = executor.execute(&block, U256::ZERO, Some(vec![sender])).unwrap();
+
+        // the blob gas price is 1 without excess blob gas
+        let expected = balance - U256::from(21_000 * 7) - U256::from(2 * BLOB_GAS_PER_BLOB);
+        assert_eq!(executor.db().accounts.get(&sender).unwrap().info.balance, expected);
+        assert_eq!(
+            out.tx_changesets[0].changeset.get(&sender).unwrap().account,
+            AccountInfoChangeSet::Changed {
+                old: Account { balance, nonce: 0, bytecode_hash: None },
+                new: Account { balance: expected, nonce: 1, bytecode_hash: None },
+            }
+        );
+
+        // the fee cap is checked against the blob gas price of the block
+        let mut block = block;
+        block.header.excess_blob_gas = Some(20 * BLOB_GAS_PER_BLOB);
+        let blob_gasprice = block.header.blob_gasprice().unwrap();
+        assert!(blob_gasprice > 1);
+        let mut executor = Executor::new(chain_spec, SubState::new(State::new(db)));
+        assert_eq!(
+            executor.execute(&block, U256::ZERO, Some(vec![sender])).unwrap_err(),
+            Error::BlobFeeCapTooLow { max_fee_per_blob_gas: 1, blob_gasprice }
+        );
+    }
 }
@@ -130,4 +130,60 @@ pub enum Error {
     WithdrawalIndexInvalid { got: u64, expected: u64 },
     #[error("Missing withdrawals")]
     BodyWithdrawalsMissing,
+    #[error("Missing blob gas used")]
+    BlobGasUsedMissing,
+    #[error("Unexpected blob gas used")]
+    BlobGasUsedUnexpected,
+    #[error("Missing excess blob gas")]
+    ExcessBlobGasMissing,
+    #[error("Unexpected excess blob gas")]
+    ExcessBlobGasUnexpected,
+    #[error("Missing parent beacon block root")]
+    ParentBeaconBlockRootMissing,
+    #[error("Unexpected parent beacon block root")]
+    ParentBeaconBlockRootUnexpected,
+    #[error("Blob gas used {blob_gas_used} exceeds maximum allowance {max_blob_gas_per_block}")]
+    BlobGasUsedExceedsMaxBlobGasPerBlock { blob_gas_used: u64, max_blob_gas_per_block: u64 },
+    #[error(
+        "Blob gas used {blob_gas_used} is not a multiple of blob gas per blob {blob_gas_per_blob}"
+    )]
+    BlobGasUsedNotMultipleOfBlobGasPerBlob { blob_gas_used: u64, blob_gas_per_blob: u64 },
+    #[error("Block blob gas used ({got}) is different from expected ({expected})")]
+    BlobGasUsedDiff { got: u64, expected: u64 },
+    #[error("Block excess blob gas ({got}) is different from expected ({expected})")]
+    ExcessBlobGasDiff { got: u64, expected: u64 },
+    #[error("Eip4844 transaction is enabled after cancun hardfork.")]
+    TransactionEip4844Disabled,
+    #[error("Eip4844 transaction can not be a contract creation.")]
+    TransactionEip4844Create,
+    #[error("Eip4844 transaction does not reference any blobs.")]
+    TransactionEip4844NoBlobs,
+    #[error("Eip4844 transaction has a blob versioned hash with an unsupported version.")]
+    TransactionEip4844InvalidVersionedHash,
+    #[error("Transaction max fee per blob gas is less then block blob gas price.")]
+    TransactionMaxFeePerBlobGasLessThenBlobGasPrice,
+    #[error("Difficulty is zero before the merge")]
+    PreMergeDifficultyIsZero,
+    #[error("Block difficulty ({got}) is different from expected ({expected})")]
+    DifficultyDiff { got: U256, expected: U256 },
+    #[error(
+        "Clique extra data of length {len} does not contain a valid vanity, signer list and seal"
+    )]
+    CliqueExtraDataInvalid { len: usize },
+    #[error("Clique epoch length is zero")]
+    CliqueEpochIsZero,
+    #[error("Clique checkpoint block casts a vote")]
+    CliqueCheckpointVote,
+    #[error("Clique vote nonce {nonce:#x} is invalid")]
+    CliqueVoteNonceInvalid { nonce: u64 },
+    #[error("Clique mix hash is not zero")]
+    CliqueMixHashIsNotZero,
+    #[error("Clique ommer root is not empty")]
+    CliqueOmmerRootIsNotEmpty,
+    #[error("Clique difficulty {difficulty} is neither in-turn nor out-of-turn")]
+    CliqueDifficultyInvalid { difficulty: U256 },
+    #[error("Clique block timestamp {timestamp} is less than {period}s after parent timestamp {parent_timestamp}")]
+    CliqueBlockPeriod { parent_timestamp: u64, timestamp: u64, period: u64 },
+    #[error("Failed to recover the clique signer from the seal")]
+    CliqueSignerRecovery,
 }
@@ -1,4 +1,4 @@
-use reth_primitives::{Bloom, H256};
+use reth_primitives::{Bloom, H256, U256};
 use thiserror::Error;
 
 /// BlockExecutor Errors
@@ -34,4 +34,18 @@ pub enum Error {
     BlockGasUsed { got: u64, expected: u64 },
     #[error("Provider error")]
     ProviderError,
+    #[error("Failed to calculate intermediate state root: {0}")]
+    IntermediateStateRoot(String),
+    #[error("Block has blob transactions but no excess blob gas.")]
+    MissingExcessBlobGas,
+    #[error(
+        "Blob fee cap {max_fee_per_blob_gas} is lower than the blob gas price {blob_gasprice}."
+    )]
+    BlobFeeCapTooLow { max_fee_per_blob_gas: u128, blob_gasprice: u128 },
+    #[error(
+        "Sender balance {balance} is lower than the max cost {max_cost} of the blob transaction."
+    )]
+    InsufficientFundsForBlobTransaction { balance: U256, max_cost: U256 },
+    #[error("Block is missing the parent beacon block root.")]
+    MissingParentBeaconBlockRoot,
 }
@@ -12,6 +12,10 @@ pub mod bodies;
 /// [`HeadersClient`]: crate::p2p::headers::client::HeadersClient
 pub mod headers;
 
+/// Traits for implementing P2P clients of the `snap` protocol, which download the state at a
+/// recent block instead of the blocks that led to it.
+pub mod snap;
+
 /// Error types broadly used by p2p interfaces for any operation which may produce an error when
 /// interacting with the network implementation
 pub mod error;
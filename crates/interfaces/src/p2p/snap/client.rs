@@ -0,0 +1,31 @@
+use std::pin::Pin;
+
+use crate::p2p::{download::DownloadClient, error::PeerRequestResult};
+use futures::Future;
+use reth_eth_wire::snap::{
+    AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges, GetTrieNodes,
+    StorageRanges, TrieNodes,
+};
+
+/// The future of a `snap` request.
+pub type SnapFut<T> = Pin<Box<dyn Future<Output = PeerRequestResult<T>> + Send + Sync>>;
+
+/// A client capable of downloading the state of peers with the `snap` protocol.
+///
+/// The client assigns the request ids, the ids of the given requests are ignored. Peers answer
+/// requests for state they do not serve, e.g. because the root is too old, with empty responses.
+#[auto_impl::auto_impl(&, Arc, Box)]
+pub trait SnapClient: DownloadClient {
+    /// Fetches a range of accounts of the state trie, with the proof of its boundaries.
+    fn get_account_range(&self, request: GetAccountRange) -> SnapFut<AccountRange>;
+
+    /// Fetches the storage slots of the given accounts, with the proof of the last range if it is
+    /// cut off.
+    fn get_storage_ranges(&self, request: GetStorageRanges) -> SnapFut<StorageRanges>;
+
+    /// Fetches bytecodes by their hash.
+    fn get_byte_codes(&self, request: GetByteCodes) -> SnapFut<ByteCodes>;
+
+    /// Fetches trie nodes of the state trie or the storage tries by their path.
+    fn get_trie_nodes(&self, request: GetTrieNodes) -> SnapFut<TrieNodes>;
+}
@@ -1,4 +1,4 @@
-use reth_primitives::{Address, BlockHash, BlockNumber, TransitionId, TxNumber, H256};
+use reth_primitives::{Address, BlockHash, BlockNumber, PrunePart, TransitionId, TxNumber, H256};
 
 /// Bundled errors variants thrown by various providers.
 #[allow(missing_docs)]
@@ -41,6 +41,16 @@ pub enum ProviderError {
         /// The account address
         address: Address,
     },
+    /// The history needed to serve a request for a block was pruned.
+    #[error("{part:?} of block {block_number:?} was pruned, it is available from block #{available_from}")]
+    HistoryPruned {
+        /// The pruned part of the history
+        part: PrunePart,
+        /// The requested block number, if known
+        block_number: Option<BlockNumber>,
+        /// The lowest block number whose history is still available
+        available_from: BlockNumber,
+    },
     /// The total difficulty for a block is missing.
     #[error("Total difficulty not found for block #{number}")]
     TotalDifficulty { number: BlockNumber },
@@ -71,6 +81,9 @@ pub enum ProviderError {
     /// Thrown when required header related data was not found but was required.
     #[error("requested data not found")]
     HeaderNotFound,
+    /// Reading from the static files failed.
+    #[error("Static file error: {0}")]
+    StaticFile(String),
     /// Thrown when the cache service task dropped
     #[error("cache service task stopped")]
     CacheServiceUnavailable,
@@ -102,6 +102,13 @@ impl BanList {
         self.banned_peers.contains_key(peer_id)
     }
 
+    /// Returns when the ban of the peer expires.
+    ///
+    /// Returns `None` if the peer is not banned and `Some(None)` if it is banned indefinitely.
+    pub fn peer_banned_until(&self, peer_id: &PeerId) -> Option<Option<Instant>> {
+        self.banned_peers.get(peer_id).copied()
+    }
+
     /// Unbans the ip address
     pub fn unban_ip(&mut self, ip: &IpAddr) {
         self.banned_ips.remove(ip);
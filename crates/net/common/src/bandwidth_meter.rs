@@ -47,6 +47,8 @@ struct BandwidthMeterInner {
     inbound: AtomicU64,
     /// Measures the number of outbound packets
     outbound: AtomicU64,
+    /// The meter all bytes of this meter are counted towards as well.
+    parent: Option<BandwidthMeter>,
 }
 
 /// Public shareable struct used for getting bandwidth metering info
@@ -56,6 +58,36 @@ pub struct BandwidthMeter {
 }
 
 impl BandwidthMeter {
+    /// Creates a new [`BandwidthMeter`] that counts all bytes towards this meter as well.
+    ///
+    /// This can be used to meter the bandwidth of a single stream in addition to the total
+    /// bandwidth of all streams.
+    pub fn child(&self) -> Self {
+        Self {
+            inner: Arc::new(BandwidthMeterInner {
+                inbound: AtomicU64::new(0),
+                outbound: AtomicU64::new(0),
+                parent: Some(self.clone()),
+            }),
+        }
+    }
+
+    /// Counts the downloaded bytes, also towards the parent meter.
+    fn add_inbound(&self, num_bytes: u64) {
+        self.inner.inbound.fetch_add(num_bytes, Ordering::Relaxed);
+        if let Some(parent) = &self.inner.parent {
+            parent.add_inbound(num_bytes);
+        }
+    }
+
+    /// Counts the uploaded bytes, also towards the parent meter.
+    fn add_outbound(&self, num_bytes: u64) {
+        self.inner.outbound.fetch_add(num_bytes, Ordering::Relaxed);
+        if let Some(parent) = &self.inner.parent {
+            parent.add_outbound(num_bytes);
+        }
+    }
+
     /// Returns the total number of bytes that have been downloaded on all the streams.
     ///
     /// > **Note**: This method is by design subject to race conditions. The returned value should
@@ -79,6 +111,7 @@ impl Default for BandwidthMeter {
             inner: Arc::new(BandwidthMeterInner {
                 inbound: AtomicU64::new(0),
                 outbound: AtomicU64::new(0),
+                parent: None,
             }),
         }
     }
@@ -127,10 +160,7 @@ impl<Stream: AsyncRead> AsyncRead for MeteredStream<Stream> {
             ready!(this.inner.poll_read(cx, buf))?;
             buf.filled().len() - init_num_bytes
         };
-        this.meter
-            .inner
-            .inbound
-            .fetch_add(u64::try_from(num_bytes).unwrap_or(u64::max_value()), Ordering::Relaxed);
+        this.meter.add_inbound(u64::try_from(num_bytes).unwrap_or(u64::max_value()));
         Poll::Ready(Ok(()))
     }
 }
@@ -143,10 +173,7 @@ impl<Stream: AsyncWrite> AsyncWrite for MeteredStream<Stream> {
     ) -> Poll<io::Result<usize>> {
         let this = self.project();
         let num_bytes = ready!(this.inner.poll_write(cx, buf))?;
-        this.meter
-            .inner
-            .outbound
-            .fetch_add(u64::try_from(num_bytes).unwrap_or(u64::max_value()), Ordering::Relaxed);
+        this.meter.add_outbound(u64::try_from(num_bytes).unwrap_or(u64::max_value()));
         Poll::Ready(Ok(num_bytes))
     }
 
@@ -270,4 +297,27 @@ mod tests {
         assert_bandwidth_counts(&shared_client_bandwidth_meter, 8, 8);
         assert_bandwidth_counts(&shared_server_bandwidth_meter, 8, 8);
     }
+
+    #[tokio::test]
+    async fn test_child_meters() {
+        let (client_1, server_1) = duplex(64);
+        let (client_2, server_2) = duplex(64);
+
+        let total_client_meter = BandwidthMeter::default();
+        let client_1_meter = total_client_meter.child();
+        let client_2_meter = total_client_meter.child();
+
+        let mut metered_client_1 = MeteredStream::new_with_meter(client_1, client_1_meter.clone());
+        let mut metered_server_1 = MeteredStream::new(server_1);
+        let mut metered_client_2 = MeteredStream::new_with_meter(client_2, client_2_meter.clone());
+        let mut metered_server_2 = MeteredStream::new(server_2);
+
+        duplex_stream_ping_pong(&mut metered_client_1, &mut metered_server_1).await;
+        duplex_stream_ping_pong(&mut metered_client_2, &mut metered_server_2).await;
+        duplex_stream_ping_pong(&mut metered_client_2, &mut metered_server_2).await;
+
+        assert_bandwidth_counts(&client_1_meter, 4, 4);
+        assert_bandwidth_counts(&client_2_meter, 8, 8);
+        assert_bandwidth_counts(&total_client_meter, 12, 12);
+    }
 }
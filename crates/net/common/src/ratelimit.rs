@@ -3,6 +3,7 @@
 use std::{
     future::{poll_fn, Future},
     pin::Pin,
+    sync::{Arc, Mutex},
     task::{Context, Poll},
     time::Duration,
 };
@@ -105,15 +106,128 @@ impl Rate {
         Rate { limit, duration }
     }
 
-    fn limit(&self) -> u64 {
+    /// Returns the number of calls allowed per period.
+    pub fn limit(&self) -> u64 {
         self.limit
     }
 
-    fn duration(&self) -> Duration {
+    /// Returns the period.
+    pub fn duration(&self) -> Duration {
         self.duration
     }
 }
 
+/// A rate limit for weighted calls, e.g. the number of bytes sent, that is shared by all its
+/// clones.
+///
+/// Unlike [RateLimit], a call may consume more than the remaining budget of the current period, in
+/// which case the limit stays exhausted for as many additional periods as the excess requires.
+#[derive(Debug)]
+pub struct SharedRateLimit {
+    rate: Rate,
+    state: Arc<Mutex<SharedState>>,
+    /// The timer of this clone to wait for the end of an exhausted period.
+    sleep: Pin<Box<Sleep>>,
+}
+
+// === impl SharedRateLimit ===
+
+impl SharedRateLimit {
+    /// Create a new rate limiter
+    pub fn new(rate: Rate) -> Self {
+        let until = tokio::time::Instant::now();
+        let state = SharedState { until, remaining: rate.limit() };
+        Self {
+            rate,
+            state: Arc::new(Mutex::new(state)),
+            sleep: Box::pin(tokio::time::sleep_until(until)),
+        }
+    }
+
+    /// Returns the [Rate] of this limit.
+    pub fn rate(&self) -> Rate {
+        self.rate
+    }
+
+    /// Checks if the [SharedRateLimit] has any budget left in the current period.
+    pub fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.poll_ready_with_reserve(cx, 0)
+    }
+
+    /// Checks if the [SharedRateLimit] has more than `reserve` budget left in the current period.
+    ///
+    /// This can be used to exhaust the limit for less important calls first, so the reserve is
+    /// left for more important calls that only check [SharedRateLimit::poll_ready].
+    pub fn poll_ready_with_reserve(&mut self, cx: &mut Context<'_>, reserve: u64) -> Poll<()> {
+        let until = {
+            let mut state = self.state.lock().expect("not poisoned");
+            state.refresh(&self.rate);
+            if state.remaining > reserve {
+                return Poll::Ready(())
+            }
+            state.until
+        };
+
+        if self.sleep.deadline() != until {
+            self.sleep.as_mut().reset(until);
+        }
+        self.sleep.as_mut().poll(cx)
+    }
+
+    /// Wait until the [SharedRateLimit] is ready.
+    pub async fn wait(&mut self) {
+        poll_fn(|cx| self.poll_ready(cx)).await
+    }
+
+    /// Consumes `amount` of the budget.
+    pub fn tick_by(&mut self, amount: u64) {
+        let mut state = self.state.lock().expect("not poisoned");
+        state.refresh(&self.rate);
+
+        if amount < state.remaining {
+            state.remaining -= amount;
+        } else {
+            // limited until the end of the current period, plus the periods the excess requires
+            let excess = amount - state.remaining;
+            let periods = excess / self.rate.limit().max(1);
+            let extension =
+                self.rate.duration().saturating_mul(periods.try_into().unwrap_or(u32::MAX));
+            state.until = state.until.checked_add(extension).unwrap_or(state.until);
+            state.remaining = 0;
+        }
+    }
+}
+
+impl Clone for SharedRateLimit {
+    fn clone(&self) -> Self {
+        Self {
+            rate: self.rate,
+            state: Arc::clone(&self.state),
+            sleep: Box::pin(tokio::time::sleep_until(tokio::time::Instant::now())),
+        }
+    }
+}
+
+/// The budget of a [SharedRateLimit] in its current period.
+#[derive(Debug)]
+struct SharedState {
+    /// The end of the current period.
+    until: tokio::time::Instant,
+    /// The budget left in the current period.
+    remaining: u64,
+}
+
+impl SharedState {
+    /// Starts a new period if the current one has elapsed.
+    fn refresh(&mut self, rate: &Rate) {
+        let now = tokio::time::Instant::now();
+        if now >= self.until {
+            self.until = now + rate.duration();
+            self.remaining = rate.limit();
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,4 +266,53 @@ mod tests {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_shared_rate_limit() {
+        let mut limit = SharedRateLimit::new(Rate::new(100, Duration::from_millis(200)));
+        let mut other = limit.clone();
+
+        poll_fn(|cx| {
+            assert!(limit.poll_ready(cx).is_ready());
+            Poll::Ready(())
+        })
+        .await;
+
+        limit.tick_by(60);
+
+        // the budget is shared by all clones
+        poll_fn(|cx| {
+            assert!(other.poll_ready(cx).is_ready());
+            assert!(other.poll_ready_with_reserve(cx, 50).is_pending());
+            Poll::Ready(())
+        })
+        .await;
+
+        // exceeds the budget of this and the next period
+        other.tick_by(200);
+
+        poll_fn(|cx| {
+            assert!(limit.poll_ready(cx).is_pending());
+            assert!(other.poll_ready(cx).is_pending());
+            Poll::Ready(())
+        })
+        .await;
+
+        tokio::time::sleep(Duration::from_millis(200)).await;
+
+        poll_fn(|cx| {
+            assert!(limit.poll_ready(cx).is_pending());
+            Poll::Ready(())
+        })
+        .await;
+
+        limit.wait().await;
+
+        poll_fn(|cx| {
+            assert!(limit.poll_ready(cx).is_ready());
+            assert!(other.poll_ready_with_reserve(cx, 50).is_ready());
+            Poll::Ready(())
+        })
+        .await;
+    }
 }
@@ -4,7 +4,7 @@
 //! <https://github.com/sigp/discv5>
 
 use reth_net_common::ban_list::BanList;
-use reth_net_nat::{NatResolver, ResolveNatInterval};
+use reth_net_nat::{NatResolver, PortMapping, ResolveNatInterval};
 use reth_primitives::{
     bytes::{Bytes, BytesMut},
     NodeRecord,
@@ -69,6 +69,13 @@ pub struct Discv4Config {
     /// If configured and a `external_ip_resolver` is configured, try to resolve the external ip
     /// using this interval.
     pub resolve_external_ip_interval: Option<Duration>,
+    /// Whether to map the discovery and the RLPx port on the gateway when resolving the external
+    /// ip, so the node is reachable from outside the NAT. Default: true.
+    pub enable_port_mapping: bool,
+    /// Additional local ports to map on the gateway if port mapping is enabled, like the port of
+    /// discv5.
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub additional_port_mappings: Vec<PortMapping>,
 }
 
 impl Discv4Config {
@@ -90,6 +97,12 @@ impl Discv4Config {
         self
     }
 
+    /// Add another local port to map on the gateway
+    pub fn add_port_mapping(&mut self, mapping: PortMapping) -> &mut Self {
+        self.additional_port_mappings.push(mapping);
+        self
+    }
+
     /// Extend additional key value pairs to include in the ENR
     pub fn extend_eip868_rlp_pairs(
         &mut self,
@@ -108,6 +121,26 @@ impl Discv4Config {
         let interval = self.resolve_external_ip_interval?;
         Some(ResolveNatInterval::interval(resolver, interval))
     }
+
+    /// Returns the [`ResolveNatInterval`] like [`Self::resolve_external_ip_interval`] that also
+    /// maps the given ports and the [additional ports](Self::additional_port_mappings) on the
+    /// gateway, if port mapping is enabled.
+    pub fn resolve_external_addr_interval(
+        &self,
+        udp_port: u16,
+        tcp_port: u16,
+    ) -> Option<ResolveNatInterval> {
+        let interval = self.resolve_external_ip_interval()?;
+        if !self.enable_port_mapping {
+            return Some(interval)
+        }
+        // the discovery port is mapped first, it's the one announced in the node record
+        let mappings = [PortMapping::udp(udp_port), PortMapping::tcp(tcp_port)]
+            .into_iter()
+            .chain(self.additional_port_mappings.iter().copied())
+            .filter(|mapping| mapping.port != 0);
+        Some(interval.with_port_mappings(mappings))
+    }
 }
 
 impl Default for Discv4Config {
@@ -140,6 +173,8 @@ impl Default for Discv4Config {
             external_ip_resolver: Some(Default::default()),
             /// By default retry public IP using a 5min interval
             resolve_external_ip_interval: Some(Duration::from_secs(60 * 5)),
+            enable_port_mapping: true,
+            additional_port_mappings: Vec::new(),
         }
     }
 }
@@ -299,6 +334,18 @@ impl Discv4ConfigBuilder {
         self
     }
 
+    /// Whether to map the discovery and the RLPx port on the gateway.
+    pub fn enable_port_mapping(&mut self, enable_port_mapping: bool) -> &mut Self {
+        self.config.enable_port_mapping = enable_port_mapping;
+        self
+    }
+
+    /// Adds another local port to map on the gateway.
+    pub fn add_port_mapping(&mut self, mapping: PortMapping) -> &mut Self {
+        self.config.additional_port_mappings.push(mapping);
+        self
+    }
+
     /// Returns the configured [`Discv4Config`]
     pub fn build(&self) -> Discv4Config {
         self.config.clone()
@@ -72,9 +72,9 @@ pub use reth_primitives::NodeRecord;
 #[cfg(any(test, feature = "test-utils"))]
 pub mod test_utils;
 
-use reth_net_nat::ResolveNatInterval;
 /// reexport to get public ip.
-pub use reth_net_nat::{external_ip, NatResolver};
+pub use reth_net_nat::{external_ip, NatResolver, PortMapping};
+use reth_net_nat::{ExternalAddr, PortMappingProtocol, ResolveNatInterval};
 
 /// The default port for discv4 via UDP
 ///
@@ -478,7 +478,8 @@ impl Discv4Service {
             ping_interval,
             evict_expired_requests_interval,
             lookup_rotator,
-            resolve_external_ip_interval: config.resolve_external_ip_interval(),
+            resolve_external_ip_interval: config
+                .resolve_external_addr_interval(local_address.port(), local_node_record.tcp_port),
             config,
             queued_events: Default::default(),
         }
@@ -508,6 +509,41 @@ impl Discv4Service {
         }
     }
 
+    /// Sets the external address resolved by the [ResolveNatInterval] in the node record announced
+    /// in discovery, including the external ports if the local ports were mapped on the gateway.
+    pub fn set_external_addr(&mut self, external_addr: ExternalAddr) {
+        self.set_external_ip_addr(external_addr.ip);
+
+        if let Some(port) = external_addr.external_port(PortMappingProtocol::Udp) {
+            if self.local_node_record.udp_port != port {
+                info!(target : "discv4", %port, "Updating external udp port");
+                self.local_node_record.udp_port = port;
+                if self.local_node_record.address.is_ipv4() {
+                    let _ = self.local_eip_868_enr.set_udp4(port, &self.secret_key);
+                } else {
+                    let _ = self.local_eip_868_enr.set_udp6(port, &self.secret_key);
+                }
+            }
+        }
+
+        if let Some(port) = external_addr.external_port(PortMappingProtocol::Tcp) {
+            if self.local_node_record.tcp_port != port {
+                info!(target : "discv4", %port, "Updating external tcp port");
+                self.set_tcp_port(port);
+            }
+        }
+    }
+
+    /// Sets the tcp port in the node record announced in discovery.
+    fn set_tcp_port(&mut self, port: u16) {
+        self.local_node_record.tcp_port = port;
+        if self.local_node_record.address.is_ipv4() {
+            let _ = self.local_eip_868_enr.set_tcp4(port, &self.secret_key);
+        } else {
+            let _ = self.local_eip_868_enr.set_tcp6(port, &self.secret_key);
+        }
+    }
+
     /// Returns the [PeerId] that identifies this node
     pub fn local_peer_id(&self) -> &PeerId {
         &self.local_node_record.id
@@ -1104,6 +1140,7 @@ impl Discv4Service {
                     (Some(new), None) => self.notify(DiscoveryUpdate::EnrForkId(record, new)),
                     _ => {}
                 }
+                self.notify(DiscoveryUpdate::Enr(record, msg.enr));
             }
         }
     }
@@ -1406,10 +1443,10 @@ impl Discv4Service {
                 self.re_ping_oldest();
             }
 
-            if let Some(Poll::Ready(Some(ip))) =
+            if let Some(Poll::Ready(Some(addr))) =
                 self.resolve_external_ip_interval.as_mut().map(|r| r.poll_tick(cx))
             {
-                self.set_external_ip_addr(ip);
+                self.set_external_addr(addr);
             }
 
             // process all incoming commands
@@ -1451,11 +1488,14 @@ impl Discv4Service {
                             }
                             Discv4Command::SetTcpPort(port) => {
                                 debug!(target: "discv4", %port, "Update tcp port");
-                                self.local_node_record.tcp_port = port;
-                                if self.local_node_record.address.is_ipv4() {
-                                    let _ = self.local_eip_868_enr.set_tcp4(port, &self.secret_key);
-                                } else {
-                                    let _ = self.local_eip_868_enr.set_tcp6(port, &self.secret_key);
+                                self.set_tcp_port(port);
+                                if self.config.enable_port_mapping {
+                                    // map the new port on the gateway
+                                    self.resolve_external_ip_interval =
+                                        self.config.resolve_external_addr_interval(
+                                            self.local_address.port(),
+                                            port,
+                                        );
                                 }
                             }
                         }
@@ -1933,6 +1973,8 @@ pub enum DiscoveryUpdate {
     DiscoveredAtCapacity(NodeRecord),
     /// Received a [`ForkId`] via EIP-868 for the given [`NodeRecord`].
     EnrForkId(NodeRecord, ForkId),
+    /// Received the [`Enr`] of the given [`NodeRecord`] via EIP-868.
+    Enr(NodeRecord, Enr<SecretKey>),
     /// Node that was removed from the table
     Removed(PeerId),
     /// A series of updates
@@ -1947,6 +1989,7 @@ mod tests {
         test_utils::{create_discv4, create_discv4_with_config, rng_endpoint, rng_record},
     };
     use rand::{thread_rng, Rng};
+    use reth_net_nat::MappedPort;
     use reth_primitives::{hex_literal::hex, ForkHash};
     use std::{future::poll_fn, net::Ipv4Addr};
 
@@ -2054,6 +2097,40 @@ mod tests {
         };
     }
 
+    #[tokio::test]
+    async fn test_set_external_addr() {
+        let (_discv4, mut service) = create_discv4().await;
+        let seq = service.local_eip_868_enr.seq();
+
+        let ip = IpAddr::from(Ipv4Addr::new(203, 0, 113, 7));
+        let mapped_ports = vec![
+            MappedPort {
+                protocol: PortMappingProtocol::Udp,
+                internal_port: service.local_addr().port(),
+                external_port: 40404,
+                lifetime: Duration::from_secs(600),
+            },
+            MappedPort {
+                protocol: PortMappingProtocol::Tcp,
+                internal_port: service.local_enr().tcp_port,
+                external_port: 40405,
+                lifetime: Duration::from_secs(600),
+            },
+        ];
+        service.set_external_addr(ExternalAddr { ip, mapped_ports });
+
+        let record = service.local_enr();
+        assert_eq!(record.address, ip);
+        assert_eq!(record.udp_port, 40404);
+        assert_eq!(record.tcp_port, 40405);
+
+        let enr = &service.local_eip_868_enr;
+        assert_eq!(enr.ip4(), Some(Ipv4Addr::new(203, 0, 113, 7)));
+        assert_eq!(enr.udp4(), Some(40404));
+        assert_eq!(enr.tcp4(), Some(40405));
+        assert!(enr.seq() > seq);
+    }
+
     #[tokio::test]
     async fn test_respect_ping_expiration() {
         reth_tracing::init_test_tracing();
@@ -0,0 +1,110 @@
+//! A set of configuration parameters to tune the discv5 discovery service.
+
+use discv5::{Discv5ConfigBuilder as InnerConfigBuilder, Enr};
+use reth_primitives::bytes::{Bytes, BytesMut};
+use reth_rlp::Encodable;
+use std::{collections::HashMap, time::Duration};
+
+/// Configuration parameters of the discv5 discovery service.
+#[derive(Clone, Debug)]
+pub struct Discv5Config {
+    /// The configuration of the underlying [`discv5::Discv5`] instance.
+    pub discv5: discv5::Discv5Config,
+    /// Nodes to boot from.
+    pub bootstrap_nodes: Vec<Enr>,
+    /// The rate at which lookups for random targets should be triggered.
+    pub lookup_interval: Duration,
+    /// Additional pairs to include in the local [`Enr`], like the EIP-868 pairs of discv4.
+    pub additional_eip868_rlp_pairs: HashMap<Vec<u8>, Bytes>,
+}
+
+impl Discv5Config {
+    /// Returns a new default builder instance
+    pub fn builder() -> Discv5ConfigBuilder {
+        Default::default()
+    }
+
+    /// Add another key value pair to include in the ENR
+    pub fn add_eip868_pair(&mut self, key: impl AsRef<[u8]>, value: impl Encodable) -> &mut Self {
+        let mut buf = BytesMut::new();
+        value.encode(&mut buf);
+        self.add_eip868_rlp_pair(key, buf.freeze())
+    }
+
+    /// Add another key value pair to include in the ENR
+    pub fn add_eip868_rlp_pair(&mut self, key: impl AsRef<[u8]>, rlp: Bytes) -> &mut Self {
+        self.additional_eip868_rlp_pairs.insert(key.as_ref().to_vec(), rlp);
+        self
+    }
+
+    /// Extend additional key value pairs to include in the ENR
+    pub fn extend_eip868_rlp_pairs(
+        &mut self,
+        pairs: impl IntoIterator<Item = (impl AsRef<[u8]>, Bytes)>,
+    ) -> &mut Self {
+        for (k, v) in pairs.into_iter() {
+            self.add_eip868_rlp_pair(k, v);
+        }
+        self
+    }
+}
+
+impl Default for Discv5Config {
+    fn default() -> Self {
+        Self {
+            discv5: InnerConfigBuilder::new().build(),
+            bootstrap_nodes: Default::default(),
+            lookup_interval: Duration::from_secs(20),
+            additional_eip868_rlp_pairs: Default::default(),
+        }
+    }
+}
+
+/// Builder type for [`Discv5Config`]
+#[derive(Clone, Debug, Default)]
+pub struct Discv5ConfigBuilder {
+    config: Discv5Config,
+}
+
+impl Discv5ConfigBuilder {
+    /// Sets the configuration of the underlying [`discv5::Discv5`] instance.
+    pub fn discv5_config(&mut self, discv5: discv5::Discv5Config) -> &mut Self {
+        self.config.discv5 = discv5;
+        self
+    }
+
+    /// Sets the rate at which lookups for random targets are triggered.
+    pub fn lookup_interval(&mut self, lookup_interval: Duration) -> &mut Self {
+        self.config.lookup_interval = lookup_interval;
+        self
+    }
+
+    /// Add another key value pair to include in the ENR
+    pub fn add_eip868_pair(&mut self, key: impl AsRef<[u8]>, value: impl Encodable) -> &mut Self {
+        self.config.add_eip868_pair(key, value);
+        self
+    }
+
+    /// Add another key value pair to include in the ENR
+    pub fn add_eip868_rlp_pair(&mut self, key: impl AsRef<[u8]>, rlp: Bytes) -> &mut Self {
+        self.config.add_eip868_rlp_pair(key, rlp);
+        self
+    }
+
+    /// Adds a boot node
+    pub fn add_boot_node(&mut self, node: Enr) -> &mut Self {
+        self.config.bootstrap_nodes.push(node);
+        self
+    }
+
+    /// Adds multiple boot nodes
+    pub fn add_boot_nodes(&mut self, nodes: impl IntoIterator<Item = Enr>) -> &mut Self {
+        self.config.bootstrap_nodes.extend(nodes);
+        self
+    }
+
+    /// Returns the configured [`Discv5Config`]
+    pub fn build(&self) -> Discv5Config {
+        self.config.clone()
+    }
+}
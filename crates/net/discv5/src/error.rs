@@ -0,0 +1,15 @@
+//! Error types that can occur in this crate.
+
+/// Errors that can occur when starting the discv5 service.
+#[derive(Debug, thiserror::Error)]
+pub enum Discv5Error {
+    /// Failed to build the local [`Enr`](discv5::Enr).
+    #[error("Failed to build the local ENR: {0}")]
+    Enr(String),
+    /// Failed to create the discv5 instance.
+    #[error("Failed to create discv5: {0}")]
+    Init(&'static str),
+    /// Failed to bind the socket or to start the discv5 service.
+    #[error("Failed to start discv5: {0:?}")]
+    Start(discv5::Discv5Error),
+}
@@ -0,0 +1,442 @@
+#![warn(missing_docs, unused_crate_dependencies)]
+#![deny(unused_must_use, rust_2018_idioms)]
+#![doc(test(
+    no_crate_inject,
+    attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
+))]
+
+//! Discovery v5 support: <https://github.com/ethereum/devp2p/blob/master/discv5/discv5.md>
+//!
+//! This runs the [discv5](https://github.com/sigp/discv5) implementation next to discv4. The local
+//! ENR carries the same key value pairs that discv4 advertises via EIP-868, most importantly the
+//! `eth` [`ForkId`].
+//!
+//! This consists of a [`Discv5`] and [`Discv5Service`] pair. The service drives the random walk
+//! of the DHT and converts all discovered ENRs into [`DiscoveredNode`]s that listeners will
+//! receive. Only nodes that advertise an `eth` fork id and a TCP port are reported, all other
+//! nodes, like consensus layer nodes, can't be used as execution layer peers.
+use crate::error::Discv5Error;
+use discv5::{
+    enr::{CombinedKey, EnrBuilder, EnrPublicKey, NodeId},
+    Discv5Event, QueryError,
+};
+use futures::{stream::FuturesUnordered, Future};
+use reth_primitives::{keccak256, ForkId, NodeRecord, PeerId};
+use reth_rlp::{Decodable, Encodable};
+use secp256k1::SecretKey;
+use std::{
+    collections::VecDeque,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    sync::Arc,
+    task::{Context, Poll},
+};
+use tokio::{
+    sync::{mpsc, mpsc::error::TrySendError},
+    task::JoinHandle,
+    time::Interval,
+};
+use tokio_stream::{wrappers::ReceiverStream, Stream, StreamExt};
+use tracing::{debug, trace};
+
+pub mod error;
+
+mod config;
+pub use config::{Discv5Config, Discv5ConfigBuilder};
+
+// reexport the ENR type of discv5
+pub use discv5::Enr;
+
+/// The default port for discv5 via UDP.
+///
+/// Discv5 can't share the UDP socket of discv4, so it runs on a separate port.
+pub const DEFAULT_DISCOVERY_V5_PORT: u16 = 9200;
+
+/// The future of a pending lookup.
+type LookupFuture = Pin<Box<dyn Future<Output = Result<Vec<Enr>, QueryError>> + Send>>;
+
+/// The Discv5 frontend
+#[derive(Clone)]
+pub struct Discv5 {
+    /// The address of the udp socket
+    local_addr: SocketAddr,
+    /// The discv5 instance that is shared with the service.
+    discv5: Arc<discv5::Discv5>,
+}
+
+// === impl Discv5 ===
+
+impl Discv5 {
+    /// Starts discv5 on the given address and returns the frontend and the service that drives
+    /// the lookups.
+    ///
+    /// The local ENR is derived from the `local_node_record` and the `secret_key`, the UDP port
+    /// is set to the port of the `local_address`.
+    ///
+    /// NOTE: The service needs to be spawned or polled in order to discover nodes.
+    pub async fn start(
+        local_address: SocketAddr,
+        local_node_record: NodeRecord,
+        secret_key: SecretKey,
+        config: Discv5Config,
+    ) -> Result<(Self, Discv5Service), Discv5Error> {
+        let mut secret_bytes = secret_key.secret_bytes();
+        let enr_key = CombinedKey::secp256k1_from_bytes(&mut secret_bytes)
+            .map_err(|err| Discv5Error::Enr(err.to_string()))?;
+
+        let local_enr = {
+            let mut builder = EnrBuilder::new("v4");
+            builder.ip(local_node_record.address);
+            if local_node_record.address.is_ipv4() {
+                builder.udp4(local_address.port());
+                builder.tcp4(local_node_record.tcp_port);
+            } else {
+                builder.udp6(local_address.port());
+                builder.tcp6(local_node_record.tcp_port);
+            }
+
+            for (key, val) in config.additional_eip868_rlp_pairs.iter() {
+                builder.add_value_rlp(key, val.clone());
+            }
+
+            builder.build(&enr_key).map_err(|err| Discv5Error::Enr(err.to_string()))?
+        };
+
+        let mut discv5 = discv5::Discv5::new(local_enr, enr_key, config.discv5.clone())
+            .map_err(Discv5Error::Init)?;
+        discv5.start(local_address).await.map_err(Discv5Error::Start)?;
+        let events = discv5.event_stream().await.map_err(Discv5Error::Start)?;
+
+        let discv5 = Arc::new(discv5);
+        let service = Discv5Service {
+            discv5: Arc::clone(&discv5),
+            events: ReceiverStream::new(events),
+            lookup_interval: tokio::time::interval(config.lookup_interval),
+            pending_lookup: FuturesUnordered::new(),
+            update_listeners: Vec::with_capacity(1),
+            queued_nodes: Default::default(),
+            config,
+        };
+
+        Ok((Self { local_addr: local_address, discv5 }, service))
+    }
+
+    /// Returns the address of the UDP socket.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the current local ENR.
+    pub fn local_enr(&self) -> Enr {
+        self.discv5.local_enr()
+    }
+
+    /// Sets the pair in the local [`Enr`] of the node.
+    ///
+    /// If the key already exists, this will update it and increase the sequence number of the
+    /// ENR, so peers request the new ENR.
+    pub fn set_eip868_rlp(&self, key: &str, value: impl Encodable) {
+        let mut buf = Vec::new();
+        value.encode(&mut buf);
+        if let Err(err) = self.discv5.enr_insert(key, &buf) {
+            debug!(target : "discv5", key, ?err, "failed to update local ENR");
+        }
+    }
+
+    /// Removes the peer from the table, if it exists.
+    pub fn remove_peer(&self, peer_id: PeerId) {
+        self.discv5.remove_node(&node_id(peer_id));
+    }
+
+    /// Adds the peer and id to the ban list.
+    ///
+    /// This will prevent any future inclusion in the table
+    pub fn ban(&self, peer_id: PeerId, ip: IpAddr) {
+        self.ban_node(peer_id);
+        self.ban_ip(ip);
+    }
+
+    /// Adds the ip to the ban list.
+    ///
+    /// This will prevent any future inclusion in the table
+    pub fn ban_ip(&self, ip: IpAddr) {
+        self.discv5.ban_ip(ip, None);
+    }
+
+    /// Adds the peer to the ban list.
+    ///
+    /// This will prevent any future inclusion in the table
+    pub fn ban_node(&self, peer_id: PeerId) {
+        self.discv5.ban_node(&node_id(peer_id), None);
+    }
+}
+
+impl std::fmt::Debug for Discv5 {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Discv5").field("local_addr", &self.local_addr).finish_non_exhaustive()
+    }
+}
+
+/// Drives the lookups of discv5 and reports discovered nodes.
+#[must_use = "Stream does nothing unless polled"]
+pub struct Discv5Service {
+    /// The discv5 instance that is shared with the frontend.
+    discv5: Arc<discv5::Discv5>,
+    /// Events emitted by discv5.
+    events: ReceiverStream<Discv5Event>,
+    /// The interval at which lookups for random targets are triggered.
+    lookup_interval: Interval,
+    /// The currently active lookup, if any.
+    pending_lookup: FuturesUnordered<LookupFuture>,
+    /// All subscribers for discovered nodes.
+    update_listeners: Vec<mpsc::Sender<DiscoveredNode>>,
+    /// Discovered nodes that are yet to be returned.
+    queued_nodes: VecDeque<DiscoveredNode>,
+    /// The configuration of the service.
+    config: Discv5Config,
+}
+
+// === impl Discv5Service ===
+
+impl Discv5Service {
+    /// Adds all configured boot nodes to the table.
+    pub fn bootstrap(&mut self) {
+        for enr in self.config.bootstrap_nodes.clone() {
+            debug!(target : "discv5", ?enr, "adding boot node");
+            if let Err(err) = self.discv5.add_enr(enr) {
+                debug!(target : "discv5", %err, "failed to add boot node");
+            }
+        }
+    }
+
+    /// Spawns this services onto a new task
+    ///
+    /// Note: requires a running runtime
+    pub fn spawn(mut self) -> JoinHandle<()> {
+        tokio::task::spawn(async move {
+            self.bootstrap();
+
+            while let Some(node) = self.next().await {
+                trace!(target : "discv5", ?node, "discovered node");
+            }
+        })
+    }
+
+    /// Creates a new channel for [`DiscoveredNode`]s
+    pub fn update_stream(&mut self) -> ReceiverStream<DiscoveredNode> {
+        let (tx, rx) = mpsc::channel(512);
+        self.update_listeners.push(tx);
+        ReceiverStream::new(rx)
+    }
+
+    /// Starts a lookup for the closest nodes of the target.
+    pub fn lookup(&mut self, target: PeerId) {
+        self.lookup_node_id(node_id(target))
+    }
+
+    fn lookup_node_id(&mut self, target: NodeId) {
+        trace!(target : "discv5", ?target, "starting lookup");
+        self.pending_lookup.push(Box::pin(self.discv5.find_node(target)));
+    }
+
+    /// Queues the node of the ENR, if it can be used as a peer.
+    fn on_enr(&mut self, enr: &Enr) {
+        if let Some(node) = convert_enr_node_record(enr) {
+            self.queued_nodes.push_back(node);
+        }
+    }
+
+    /// Sends the node to all listeners.
+    ///
+    /// Remove channels that got closed.
+    fn notify(&mut self, node: DiscoveredNode) {
+        self.update_listeners.retain_mut(|listener| match listener.try_send(node.clone()) {
+            Ok(()) => true,
+            Err(err) => match err {
+                TrySendError::Full(_) => true,
+                TrySendError::Closed(_) => false,
+            },
+        });
+    }
+
+    /// Advances the state of the service.
+    pub(crate) fn poll(&mut self, cx: &mut Context<'_>) -> Poll<Option<DiscoveredNode>> {
+        loop {
+            // drain buffered nodes first
+            if let Some(node) = self.queued_nodes.pop_front() {
+                self.notify(node.clone());
+                return Poll::Ready(Some(node))
+            }
+
+            // trigger a lookup for a random target, if none is active
+            if self.lookup_interval.poll_tick(cx).is_ready() && self.pending_lookup.is_empty() {
+                self.lookup_node_id(NodeId::random());
+            }
+
+            while let Poll::Ready(Some(result)) = self.pending_lookup.poll_next_unpin(cx) {
+                match result {
+                    Ok(enrs) => {
+                        trace!(target : "discv5", found=enrs.len(), "lookup finished");
+                        for enr in enrs.iter() {
+                            self.on_enr(enr);
+                        }
+                    }
+                    Err(err) => {
+                        debug!(target : "discv5", ?err, "lookup failed");
+                    }
+                }
+            }
+
+            while let Poll::Ready(event) = self.events.poll_next_unpin(cx) {
+                match event {
+                    Some(Discv5Event::Discovered(enr)) |
+                    Some(Discv5Event::SessionEstablished(enr, _)) => self.on_enr(&enr),
+                    Some(event) => {
+                        trace!(target : "discv5", ?event, "unhandled event");
+                    }
+                    // discv5 was shut down
+                    None => return Poll::Ready(None),
+                }
+            }
+
+            if self.queued_nodes.is_empty() {
+                return Poll::Pending
+            }
+        }
+    }
+}
+
+impl Stream for Discv5Service {
+    type Item = DiscoveredNode;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().poll(cx)
+    }
+}
+
+impl std::fmt::Debug for Discv5Service {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Discv5Service").field("config", &self.config).finish_non_exhaustive()
+    }
+}
+
+/// A node discovered via discv5 that can be used as an execution layer peer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiscoveredNode {
+    /// The node and its addresses.
+    pub node_record: NodeRecord,
+    /// The `eth` fork id of the node's ENR.
+    pub fork_id: ForkId,
+}
+
+/// Returns the discv5 [`NodeId`] of the peer, which is the hash of its public key.
+fn node_id(peer_id: PeerId) -> NodeId {
+    NodeId::new(&keccak256(peer_id).0)
+}
+
+/// Converts an [`Enr`] into a [`DiscoveredNode`].
+///
+/// Returns `None` if the ENR has no secp256k1 key, no TCP port or no `eth` fork id.
+fn convert_enr_node_record(enr: &Enr) -> Option<DiscoveredNode> {
+    let public_key = enr.public_key().encode_uncompressed();
+    if public_key.len() != 64 {
+        return None
+    }
+
+    let node_record = NodeRecord {
+        address: enr.ip4().map(IpAddr::from).or_else(|| enr.ip6().map(IpAddr::from))?,
+        tcp_port: enr.tcp4().or_else(|| enr.tcp6())?,
+        udp_port: enr.udp4().or_else(|| enr.udp6())?,
+        id: PeerId::from_slice(&public_key),
+    }
+    .into_ipv4_mapped();
+
+    let mut maybe_fork_id = enr.get(b"eth")?;
+    let fork_id = ForkId::decode(&mut maybe_fork_id).ok()?;
+
+    Some(DiscoveredNode { node_record, fork_id })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{Hardfork, MAINNET};
+    use secp256k1::{rand::thread_rng, SECP256K1};
+    use std::net::{Ipv4Addr, UdpSocket};
+
+    /// Returns a random local node with an unused UDP port for discv5.
+    fn local_node() -> (SocketAddr, NodeRecord, SecretKey) {
+        let addr = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).unwrap().local_addr().unwrap();
+        let secret_key = SecretKey::new(&mut thread_rng());
+        let mut record = NodeRecord::from_secret_key(addr, &secret_key);
+        record.tcp_port = 30303;
+        (addr, record, secret_key)
+    }
+
+    #[test]
+    fn convert_enr() {
+        let (addr, record, secret_key) = local_node();
+        let mut secret_bytes = secret_key.secret_bytes();
+        let key = CombinedKey::secp256k1_from_bytes(&mut secret_bytes).unwrap();
+        let fork_id = Hardfork::Frontier.fork_id(&MAINNET).unwrap();
+
+        let mut builder = EnrBuilder::new("v4");
+        builder.ip(addr.ip()).udp4(addr.port()).tcp4(record.tcp_port);
+        let enr = builder.build(&key).unwrap();
+        // nodes without a fork id are skipped
+        assert_eq!(convert_enr_node_record(&enr), None);
+
+        let mut buf = Vec::new();
+        reth_rlp::Encodable::encode(&fork_id, &mut buf);
+        builder.add_value_rlp("eth", buf.into());
+        let enr = builder.build(&key).unwrap();
+        assert_eq!(
+            convert_enr_node_record(&enr),
+            Some(DiscoveredNode { node_record: record, fork_id })
+        );
+        assert_eq!(
+            record.id,
+            PeerId::from_slice(&secret_key.public_key(SECP256K1).serialize_uncompressed()[1..])
+        );
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn updates_fork_id() {
+        let (addr, record, secret_key) = local_node();
+        let mut config = Discv5Config::builder();
+        config.add_eip868_pair("eth", Hardfork::Frontier.fork_id(&MAINNET).unwrap());
+        let (discv5, _service) =
+            Discv5::start(addr, record, secret_key, config.build()).await.unwrap();
+        let seq = discv5.local_enr().seq();
+
+        let fork_id = Hardfork::London.fork_id(&MAINNET).unwrap();
+        discv5.set_eip868_rlp("eth", fork_id);
+        let enr = discv5.local_enr();
+        assert!(enr.seq() > seq);
+        assert_eq!(convert_enr_node_record(&enr).unwrap().fork_id, fork_id);
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn discovers_boot_node() {
+        reth_tracing::init_test_tracing();
+        let fork_id = Hardfork::Frontier.fork_id(&MAINNET).unwrap();
+
+        let (addr, record, secret_key) = local_node();
+        let mut config = Discv5Config::builder();
+        config.add_eip868_pair("eth", fork_id);
+        let (boot_node, boot_node_service) =
+            Discv5::start(addr, record, secret_key, config.build()).await.unwrap();
+        let _boot_node_service = boot_node_service.spawn();
+
+        let (addr, record, secret_key) = local_node();
+        let config = Discv5Config::builder().add_boot_node(boot_node.local_enr()).build();
+        let (_discv5, mut service) = Discv5::start(addr, record, secret_key, config).await.unwrap();
+        let mut updates = service.update_stream();
+        let _service = service.spawn();
+
+        let node = updates.next().await.unwrap();
+        assert_eq!(node.node_record.tcp_port, 30303);
+        assert_eq!(node.node_record.udp_port, boot_node.local_addr().port());
+        assert_eq!(node.fork_id, fork_id);
+    }
+}
@@ -0,0 +1,328 @@
+//! Support for building [EIP-1459](https://eips.ethereum.org/EIPS/eip-1459) trees that can be
+//! published via DNS.
+//!
+//! This mirrors geth's `dnsdisc.MakeTree`: the nodes, sorted by their node id, and the links are
+//! each grouped into subtrees of at most [`MAX_CHILDREN`] entries, so that every branch entry fits
+//! into a single TXT record.
+
+use crate::tree::{BranchEntry, DnsEntry, LinkEntry, NodeEntry, TreeRootEntry};
+use data_encoding::BASE32_NOPAD;
+use enr::{Enr, EnrError, EnrKey, EnrKeyUnambiguous};
+use reth_primitives::{bytes::Bytes, keccak256};
+use secp256k1::SecretKey;
+use std::{
+    collections::{BTreeMap, HashSet},
+    fmt::Write,
+};
+
+/// The maximum number of children of a branch entry.
+///
+/// A child is a 16 byte hash in base32 (26 chars) plus the delimiter, at most 13 of them fit into
+/// the 370 bytes of a TXT record that are usable in practice.
+pub const MAX_CHILDREN: usize = 370 / (1 + 16 * 13 / 8);
+
+/// The maximum length of a single character string of a TXT record.
+const MAX_TXT_STRING_LEN: usize = 255;
+
+/// Builds a signed [`DnsTree`] of nodes and links to other trees.
+#[derive(Debug, Clone)]
+pub struct DnsTreeBuilder<K: EnrKeyUnambiguous = SecretKey> {
+    nodes: Vec<Enr<K>>,
+    links: Vec<LinkEntry<K>>,
+    sequence_number: u64,
+}
+
+// === impl DnsTreeBuilder ===
+
+impl<K: EnrKeyUnambiguous> DnsTreeBuilder<K> {
+    /// Creates a builder for an empty tree with sequence number `0`.
+    pub fn new() -> Self {
+        Self { nodes: Vec::new(), links: Vec::new(), sequence_number: 0 }
+    }
+
+    /// Sets the sequence number of the tree.
+    ///
+    /// Clients only update their copy of the tree if the sequence number increased.
+    pub fn sequence_number(mut self, sequence_number: u64) -> Self {
+        self.sequence_number = sequence_number;
+        self
+    }
+
+    /// Adds a node to the tree.
+    pub fn add_node(mut self, enr: Enr<K>) -> Self {
+        self.nodes.push(enr);
+        self
+    }
+
+    /// Adds multiple nodes to the tree.
+    pub fn add_nodes(mut self, enrs: impl IntoIterator<Item = Enr<K>>) -> Self {
+        self.nodes.extend(enrs);
+        self
+    }
+
+    /// Adds a link to another tree.
+    pub fn add_link(mut self, link: LinkEntry<K>) -> Self {
+        self.links.push(link);
+        self
+    }
+
+    /// Adds multiple links to other trees.
+    pub fn add_links(mut self, links: impl IntoIterator<Item = LinkEntry<K>>) -> Self {
+        self.links.extend(links);
+        self
+    }
+
+    /// Builds the tree and signs its root with the given key.
+    ///
+    /// Duplicate nodes and links are only included once.
+    pub fn build(self, key: &K) -> Result<DnsTree<K>, EnrError> {
+        let Self { mut nodes, mut links, sequence_number } = self;
+
+        nodes.sort_by_key(|enr| enr.node_id().raw());
+        nodes.dedup_by_key(|enr| enr.node_id().raw());
+
+        let mut seen = HashSet::new();
+        links.retain(|link| seen.insert(link.to_string()));
+        links.sort_by_key(|link| link.to_string());
+
+        let mut entries = BTreeMap::new();
+
+        let enr_root = build_subtree(
+            &mut entries,
+            nodes.into_iter().map(|enr| DnsEntry::Node(NodeEntry { enr })).collect(),
+        );
+        let enr_root = insert_entry(&mut entries, enr_root);
+
+        let link_root =
+            build_subtree(&mut entries, links.into_iter().map(DnsEntry::Link).collect());
+        let link_root = insert_entry(&mut entries, link_root);
+
+        let mut root =
+            TreeRootEntry { enr_root, link_root, sequence_number, signature: Bytes::new() };
+        root.sign(key)?;
+
+        Ok(DnsTree { root, entries, public_key: key.public() })
+    }
+}
+
+impl<K: EnrKeyUnambiguous> Default for DnsTreeBuilder<K> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// A signed tree of nodes and links, see [`DnsTreeBuilder`].
+#[derive(Debug, Clone)]
+pub struct DnsTree<K: EnrKeyUnambiguous = SecretKey> {
+    /// The signed root of the tree.
+    root: TreeRootEntry,
+    /// All entries below the root, by their hash.
+    entries: BTreeMap<String, DnsEntry<K>>,
+    /// The public key of the key that signed the root.
+    public_key: K::PublicKey,
+}
+
+// === impl DnsTree ===
+
+impl<K: EnrKeyUnambiguous> DnsTree<K> {
+    /// Returns the signed root of the tree.
+    pub fn root(&self) -> &TreeRootEntry {
+        &self.root
+    }
+
+    /// Returns all entries below the root, by their hash.
+    ///
+    /// The hash is the subdomain under which the entry is published.
+    pub fn entries(&self) -> &BTreeMap<String, DnsEntry<K>> {
+        &self.entries
+    }
+
+    /// Returns the link to the tree if it's published under the given domain.
+    pub fn link(&self, domain: impl Into<String>) -> LinkEntry<K> {
+        LinkEntry { domain: domain.into(), pubkey: self.public_key.clone() }
+    }
+
+    /// Returns the content of all TXT records of the tree by their fully qualified name, if the
+    /// tree is published under the given domain.
+    ///
+    /// The root is published under the domain itself. If the domain is empty, the names are just
+    /// the hashes of the entries and the name of the root is empty.
+    pub fn txt_records(&self, domain: &str) -> BTreeMap<String, String> {
+        let mut records = BTreeMap::new();
+        records.insert(domain.to_string(), self.root.to_string());
+        for (hash, entry) in self.entries.iter() {
+            let name = if domain.is_empty() { hash.clone() } else { format!("{hash}.{domain}") };
+            records.insert(name, entry.to_string());
+        }
+        records
+    }
+
+    /// Returns the TXT records of the tree as a zone file with the given domain as origin.
+    pub fn zone_file(&self, domain: &str, ttl: u32) -> String {
+        let mut zone = String::new();
+        let _ = writeln!(zone, "$ORIGIN {}.", domain.trim_end_matches('.'));
+        let _ = writeln!(zone, "$TTL {ttl}");
+        let _ = writeln!(zone, "@ IN TXT {}", txt_strings(&self.root.to_string()));
+        for (hash, entry) in self.entries.iter() {
+            let _ = writeln!(zone, "{hash} IN TXT {}", txt_strings(&entry.to_string()));
+        }
+        zone
+    }
+}
+
+/// Builds the subtree of the entries and returns its root.
+///
+/// All entries below the returned root are inserted into `entries`.
+fn build_subtree<K: EnrKeyUnambiguous>(
+    entries: &mut BTreeMap<String, DnsEntry<K>>,
+    mut children: Vec<DnsEntry<K>>,
+) -> DnsEntry<K> {
+    if children.len() == 1 {
+        return children.pop().expect("exists")
+    }
+
+    if children.len() <= MAX_CHILDREN {
+        let children = children.into_iter().map(|child| insert_entry(entries, child)).collect();
+        return DnsEntry::Branch(BranchEntry { children })
+    }
+
+    let mut children = children.into_iter().peekable();
+    let mut subtrees = Vec::new();
+    while children.peek().is_some() {
+        let chunk = children.by_ref().take(MAX_CHILDREN).collect();
+        subtrees.push(build_subtree(entries, chunk));
+    }
+    build_subtree(entries, subtrees)
+}
+
+/// Inserts the entry by its hash and returns the hash.
+fn insert_entry<K: EnrKeyUnambiguous>(
+    entries: &mut BTreeMap<String, DnsEntry<K>>,
+    entry: DnsEntry<K>,
+) -> String {
+    let hash = entry_hash(&entry);
+    entries.insert(hash.clone(), entry);
+    hash
+}
+
+/// Returns the hash of the entry, which is the base32 encoded first 16 bytes of the keccak256
+/// hash of its text.
+fn entry_hash<K: EnrKeyUnambiguous>(entry: &DnsEntry<K>) -> String {
+    BASE32_NOPAD.encode(&keccak256(entry.to_string().as_bytes()).0[..16])
+}
+
+/// Splits the content into quoted strings of at most [`MAX_TXT_STRING_LEN`] chars.
+///
+/// The content of entries consists of ASCII characters only.
+fn txt_strings(content: &str) -> String {
+    content
+        .as_bytes()
+        .chunks(MAX_TXT_STRING_LEN)
+        .map(|chunk| format!("\"{}\"", String::from_utf8_lossy(chunk)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use enr::EnrBuilder;
+    use secp256k1::rand::thread_rng;
+    use std::net::Ipv4Addr;
+
+    fn random_enr(port: u16) -> Enr<SecretKey> {
+        let secret_key = SecretKey::new(&mut thread_rng());
+        EnrBuilder::new("v4")
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp4(port)
+            .tcp4(port)
+            .build(&secret_key)
+            .unwrap()
+    }
+
+    /// Collects all nodes and links below the entry and checks the size of all branches.
+    fn collect(
+        tree: &DnsTree,
+        hash: &str,
+        nodes: &mut Vec<Enr<SecretKey>>,
+        links: &mut Vec<LinkEntry>,
+    ) {
+        match tree.entries().get(hash).unwrap() {
+            DnsEntry::Branch(branch) => {
+                assert!(branch.children.len() <= MAX_CHILDREN);
+                for child in branch.children.iter() {
+                    collect(tree, child, nodes, links);
+                }
+            }
+            DnsEntry::Node(node) => nodes.push(node.enr.clone()),
+            DnsEntry::Link(link) => links.push(link.clone()),
+            DnsEntry::Root(_) => unreachable!("root below root"),
+        }
+    }
+
+    #[test]
+    fn build_tree() {
+        let secret_key = SecretKey::new(&mut thread_rng());
+        let nodes = (0..100).map(|i| random_enr(30303 + i)).collect::<Vec<_>>();
+        let link: LinkEntry =
+            "enrtree://AM5FCQLWIZX2QFPNJAP7VUERCCRNGRHWZG3YYHIUV7BVDQ5FDPRT2@nodes.example.org"
+                .parse()
+                .unwrap();
+
+        let tree = DnsTreeBuilder::new()
+            .sequence_number(3)
+            .add_nodes(nodes.clone())
+            .add_node(nodes[0].clone())
+            .add_link(link.clone())
+            .build(&secret_key)
+            .unwrap();
+
+        let root = tree.root();
+        assert_eq!(root.sequence_number, 3);
+        assert!(root.verify::<SecretKey>(&secret_key.public()));
+
+        let mut found_nodes = Vec::new();
+        let mut found_links = Vec::new();
+        collect(&tree, &root.enr_root, &mut found_nodes, &mut found_links);
+        collect(&tree, &root.link_root, &mut found_nodes, &mut found_links);
+
+        let mut expected = nodes;
+        expected.sort_by_key(|enr| enr.node_id().raw());
+        assert_eq!(found_nodes, expected);
+        assert_eq!(found_links, vec![link]);
+
+        // all entries can be parsed and are published under their hash
+        for (hash, entry) in tree.entries() {
+            let parsed: DnsEntry<SecretKey> = entry.to_string().parse().unwrap();
+            assert_eq!(&entry_hash(&parsed), hash);
+        }
+    }
+
+    #[test]
+    fn build_empty_tree() {
+        let secret_key = SecretKey::new(&mut thread_rng());
+        let tree = DnsTreeBuilder::<SecretKey>::new().build(&secret_key).unwrap();
+
+        // the empty branch is shared by both roots
+        assert_eq!(tree.root().enr_root, tree.root().link_root);
+        assert_eq!(tree.entries().len(), 1);
+        assert!(tree.entries()[&tree.root().enr_root].to_string().parse::<BranchEntry>().is_ok());
+    }
+
+    #[test]
+    fn zone_file() {
+        let secret_key = SecretKey::new(&mut thread_rng());
+        let tree = DnsTreeBuilder::new().add_node(random_enr(30303)).build(&secret_key).unwrap();
+
+        let zone = tree.zone_file("nodes.example.org", 300);
+        let mut lines = zone.lines();
+        assert_eq!(lines.next(), Some("$ORIGIN nodes.example.org."));
+        assert_eq!(lines.next(), Some("$TTL 300"));
+        assert_eq!(lines.next(), Some(format!("@ IN TXT \"{}\"", tree.root()).as_str()));
+        assert_eq!(lines.count(), tree.entries().len());
+
+        let records = tree.txt_records("nodes.example.org");
+        assert_eq!(records["nodes.example.org"], tree.root().to_string());
+        assert_eq!(records.len(), tree.entries().len() + 1);
+    }
+}
@@ -13,6 +13,7 @@ use crate::{
     sync::{ResolveKind, SyncAction},
     tree::{DnsEntry, LinkEntry},
 };
+pub use builder::{DnsTree, DnsTreeBuilder};
 pub use config::DnsDiscoveryConfig;
 use enr::Enr;
 use error::ParseDnsEntryError;
@@ -42,6 +43,7 @@ use tokio_stream::{
 };
 use tracing::{debug, trace, warn};
 
+pub mod builder;
 mod config;
 mod error;
 mod query;
@@ -487,6 +489,38 @@ mod tests {
         task.await.unwrap();
     }
 
+    #[tokio::test]
+    async fn test_sync_built_tree() {
+        reth_tracing::init_test_tracing();
+
+        let secret_key = SecretKey::new(&mut thread_rng());
+        let node_key = SecretKey::new(&mut thread_rng());
+        let enr = EnrBuilder::new("v4")
+            .ip4(Ipv4Addr::LOCALHOST)
+            .udp4(30303)
+            .tcp4(30303)
+            .build(&node_key)
+            .unwrap();
+
+        let tree = DnsTreeBuilder::new().add_node(enr.clone()).build(&secret_key).unwrap();
+        let link = tree.link("nodes.example.org");
+
+        let resolver = MapResolver::default();
+        for (name, record) in tree.txt_records(&link.domain) {
+            resolver.insert(name, record);
+        }
+
+        let mut service = DnsDiscoveryService::new(Arc::new(resolver), Default::default());
+        service.sync_tree_with_link(link);
+
+        let event = poll_fn(|cx| service.poll(cx)).await;
+        match event {
+            DnsDiscoveryEvent::Enr(discovered) => {
+                assert_eq!(discovered, enr);
+            }
+        }
+    }
+
     #[tokio::test]
     async fn test_recheck_tree() {
         reth_tracing::init_test_tracing();
@@ -96,6 +96,25 @@ impl proptest::arbitrary::Arbitrary for Capability {
     }
 }
 
+/// A subprotocol capability together with the number of message IDs the subprotocol uses.
+///
+/// Message IDs of multiplexed subprotocols are assigned in consecutive ranges, so the number of
+/// messages of every shared capability other than `eth` has to be known to determine the offsets.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Protocol {
+    /// The advertised capability.
+    pub cap: Capability,
+    /// The number of message IDs used by the subprotocol.
+    pub messages: u8,
+}
+
+impl Protocol {
+    /// Create a new `Protocol` for the given capability and number of messages.
+    pub fn new(cap: Capability, messages: u8) -> Self {
+        Self { cap, messages }
+    }
+}
+
 /// Represents all capabilities of a node.
 #[derive(Debug, Clone, Eq, PartialEq)]
 pub struct Capabilities {
@@ -181,6 +200,9 @@ pub enum SharedCapability {
     /// The `eth` capability.
     Eth { version: EthVersion, offset: u8 },
 
+    /// A capability of a subprotocol with a known number of messages, see [`Protocol`].
+    Other { name: SmolStr, version: u8, offset: u8, messages: u8 },
+
     /// An unknown capability.
     UnknownCapability { name: SmolStr, version: u8, offset: u8 },
 }
@@ -194,10 +216,27 @@ impl SharedCapability {
         }
     }
 
+    /// Creates a new [`SharedCapability`] for the given subprotocol and offset.
+    pub(crate) fn from_protocol(
+        protocol: &Protocol,
+        offset: u8,
+    ) -> Result<Self, SharedCapabilityError> {
+        match protocol.cap.name.as_str() {
+            "eth" => Self::new("eth", protocol.cap.version as u8, offset),
+            _ => Ok(Self::Other {
+                name: protocol.cap.name.clone(),
+                version: protocol.cap.version as u8,
+                offset,
+                messages: protocol.messages,
+            }),
+        }
+    }
+
     /// Returns the name of the capability.
     pub fn name(&self) -> &str {
         match self {
             SharedCapability::Eth { .. } => "eth",
+            SharedCapability::Other { name, .. } |
             SharedCapability::UnknownCapability { name, .. } => name,
         }
     }
@@ -206,6 +245,7 @@ impl SharedCapability {
     pub fn version(&self) -> u8 {
         match self {
             SharedCapability::Eth { version, .. } => *version as u8,
+            SharedCapability::Other { version, .. } |
             SharedCapability::UnknownCapability { version, .. } => *version,
         }
     }
@@ -214,6 +254,7 @@ impl SharedCapability {
     pub fn offset(&self) -> u8 {
         match self {
             SharedCapability::Eth { offset, .. } => *offset,
+            SharedCapability::Other { offset, .. } |
             SharedCapability::UnknownCapability { offset, .. } => *offset,
         }
     }
@@ -222,6 +263,7 @@ impl SharedCapability {
     pub fn num_messages(&self) -> Result<u8, SharedCapabilityError> {
         match self {
             SharedCapability::Eth { version, .. } => Ok(version.total_messages()),
+            SharedCapability::Other { messages, .. } => Ok(*messages),
             _ => Err(SharedCapabilityError::UnknownCapability),
         }
     }
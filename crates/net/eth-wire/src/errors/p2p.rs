@@ -21,6 +21,8 @@ pub enum P2PStreamError {
     MessageTooBig { message_size: usize, max_size: usize },
     #[error("unknown reserved p2p message id: {0}")]
     UnknownReservedMessageId(u8),
+    #[error("message id {0} does not belong to a shared capability")]
+    UnknownMessageId(u8),
     #[error("empty protocol message received")]
     EmptyProtocolMessage,
     #[error(transparent)]
@@ -35,6 +37,8 @@ pub enum P2PStreamError {
     PingBeforeHandshake,
     #[error("too many messages buffered before sending")]
     SendBufferFull,
+    #[error("too many messages of a subprotocol buffered before handling")]
+    ProtocolBufferFull,
     #[error("disconnected")]
     Disconnected(DisconnectReason),
     #[error("unknown disconnect reason: {0}")]
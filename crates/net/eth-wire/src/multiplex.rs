@@ -0,0 +1,481 @@
+//! Multiplexing of `RLPx` subprotocols over a single [`P2PStream`].
+
+use crate::{
+    capability::SharedCapability, disconnect::CanDisconnect, errors::P2PStreamError,
+    DisconnectReason, P2PStream,
+};
+use futures::{Sink, SinkExt, StreamExt};
+use reth_net_common::ratelimit::SharedRateLimit;
+use reth_primitives::bytes::{Bytes, BytesMut};
+use std::{
+    collections::VecDeque,
+    io,
+    pin::Pin,
+    task::{ready, Context, Poll},
+};
+use tokio::sync::mpsc::{self, error::TrySendError};
+use tokio_stream::Stream;
+
+/// The maximum number of messages of an installed subprotocol that are buffered in either
+/// direction.
+const MAX_PROTOCOL_CAPACITY: usize = 16;
+
+/// Routes the messages of all shared capabilities of a [`P2PStream`] to their subprotocols.
+///
+/// The [`P2PStream`] keeps handling the reserved `p2p` messages, like pings. Messages of the
+/// primary capability, `eth` if it is shared, are returned by this stream and sent through its
+/// sink, so the multiplexer can be used in place of the [`P2PStream`] below an
+/// [`EthStream`](crate::EthStream). Every other shared capability can be installed once to obtain
+/// a [`ProtocolConnection`] for it, messages of capabilities that are not installed are dropped.
+///
+/// The message IDs of all streams are relative to the offset of their capability.
+///
+/// At most [`MAX_PROTOCOL_CAPACITY`] messages are buffered for each subprotocol in either
+/// direction. A peer whose messages are not handled fast enough is disconnected, and
+/// [`ProtocolConnection::send`] fails while its outgoing messages are not sent fast enough.
+///
+/// The messages of the subprotocols are only sent while the
+/// [upload limits](Self::add_upload_limit) have budget left, the messages of the primary
+/// capability are not limited by the multiplexer.
+#[derive(Debug)]
+pub struct RlpxMultiplexer<S> {
+    /// The underlying connection.
+    conn: P2PStream<S>,
+    /// The capability of the messages of this stream.
+    primary: SharedCapability,
+    /// The installed subprotocols.
+    protocols: Vec<ProtocolProxy>,
+    /// Messages of the installed subprotocols waiting to be sent, with IDs relative to the
+    /// [`P2PStream`].
+    outgoing: VecDeque<Bytes>,
+    /// The upload limits of the subprotocols, with the budget of each limit that is kept for
+    /// other messages.
+    upload_limits: Vec<(SharedRateLimit, u64)>,
+}
+
+impl<S> RlpxMultiplexer<S> {
+    /// Creates a new multiplexer for the shared capabilities of the given stream.
+    ///
+    /// The primary capability is `eth` if it is shared, otherwise the capability with the lowest
+    /// offset.
+    pub fn new(conn: P2PStream<S>) -> Self {
+        let primary = conn
+            .shared_capabilities()
+            .iter()
+            .find(|cap| matches!(cap, SharedCapability::Eth { .. }))
+            .unwrap_or_else(|| conn.shared_capability())
+            .clone();
+        Self {
+            conn,
+            primary,
+            protocols: Vec::new(),
+            outgoing: VecDeque::new(),
+            upload_limits: Vec::new(),
+        }
+    }
+
+    /// Returns the underlying [`P2PStream`].
+    pub fn inner(&self) -> &P2PStream<S> {
+        &self.conn
+    }
+
+    /// Returns mutable access to the underlying [`P2PStream`].
+    pub fn inner_mut(&mut self) -> &mut P2PStream<S> {
+        &mut self.conn
+    }
+
+    /// Limits the messages of the subprotocols by the given limit, they are only sent while more
+    /// than `reserve` of its budget is left.
+    ///
+    /// The multiplexer does not consume the budget, the bytes sent are expected to be charged to
+    /// the limit by the owner of the connection.
+    pub fn add_upload_limit(&mut self, limit: SharedRateLimit, reserve: u64) {
+        self.upload_limits.push((limit, reserve));
+    }
+
+    /// Returns the capability of the messages of this stream.
+    pub fn primary_capability(&self) -> &SharedCapability {
+        &self.primary
+    }
+
+    /// Installs the shared capability with the given name and returns the connection of its
+    /// subprotocol.
+    ///
+    /// Returns `None` if the capability is not shared, is the primary capability or has already
+    /// been installed.
+    pub fn install_protocol(&mut self, name: &str) -> Option<ProtocolConnection> {
+        let capability =
+            self.conn.shared_capabilities().iter().find(|cap| cap.name() == name)?.clone();
+        if capability == self.primary ||
+            self.protocols.iter().any(|proxy| proxy.capability == capability)
+        {
+            return None
+        }
+        let messages = capability.num_messages().ok()?;
+        let shift = self.shift(&capability);
+
+        let (to_protocol, from_wire) = mpsc::channel(MAX_PROTOCOL_CAPACITY);
+        let (to_wire, from_protocol) = mpsc::channel(MAX_PROTOCOL_CAPACITY);
+        self.protocols.push(ProtocolProxy {
+            capability: capability.clone(),
+            messages,
+            shift,
+            to_protocol,
+            from_protocol,
+        });
+        Some(ProtocolConnection { capability, from_wire, to_wire })
+    }
+
+    /// Returns the distance between the offset of the capability and the offset the message IDs
+    /// of the [`P2PStream`] are relative to.
+    fn shift(&self, capability: &SharedCapability) -> u8 {
+        capability.offset() - self.conn.shared_capability().offset()
+    }
+}
+
+impl<S> RlpxMultiplexer<S>
+where
+    S: Sink<Bytes, Error = io::Error> + Unpin,
+{
+    /// Moves the queued messages of the installed subprotocols into the [`P2PStream`].
+    ///
+    /// Subprotocols whose connection was dropped are removed.
+    fn poll_protocols(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), P2PStreamError>> {
+        let outgoing = &mut self.outgoing;
+        self.protocols.retain_mut(|proxy| loop {
+            if outgoing.len() >= MAX_PROTOCOL_CAPACITY {
+                // the remaining messages stay in the channels until the stream is ready
+                return true
+            }
+            match proxy.from_protocol.poll_recv(cx) {
+                Poll::Ready(Some(mut msg)) => {
+                    if msg.is_empty() || msg[0] >= proxy.messages {
+                        tracing::debug!(
+                            capability=?proxy.capability,
+                            "dropping outgoing message with invalid id"
+                        );
+                        continue
+                    }
+                    msg[0] += proxy.shift;
+                    outgoing.push_back(msg.freeze());
+                }
+                Poll::Ready(None) => return false,
+                Poll::Pending => return true,
+            }
+        });
+
+        if self.outgoing.is_empty() {
+            return Poll::Ready(Ok(()))
+        }
+        while !self.outgoing.is_empty() {
+            if self.poll_upload_ready(cx).is_pending() {
+                // the messages stay queued until the limits have budget again, this must not
+                // block the messages of the primary capability
+                break
+            }
+            match self.conn.poll_ready_unpin(cx) {
+                Poll::Ready(Ok(())) => {
+                    let msg = self.outgoing.pop_front().expect("is not empty");
+                    self.conn.start_send_unpin(msg)?
+                }
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+        // the session only flushes the stream if it has messages of its own
+        if let Poll::Ready(Err(err)) = self.conn.poll_flush_unpin(cx) {
+            return Poll::Ready(Err(err))
+        }
+        Poll::Ready(Ok(()))
+    }
+
+    /// Returns `Poll::Ready` if all upload limits have more than their reserve left.
+    fn poll_upload_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        for (limit, reserve) in self.upload_limits.iter_mut() {
+            ready!(limit.poll_ready_with_reserve(cx, *reserve));
+        }
+        Poll::Ready(())
+    }
+}
+
+impl<S> Stream for RlpxMultiplexer<S>
+where
+    S: Stream<Item = io::Result<BytesMut>> + Sink<Bytes, Error = io::Error> + Unpin,
+{
+    type Item = Result<BytesMut, P2PStreamError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Poll::Ready(Err(err)) = this.poll_protocols(cx) {
+            return Poll::Ready(Some(Err(err)))
+        }
+
+        loop {
+            let mut msg = match ready!(this.conn.poll_next_unpin(cx)) {
+                Some(Ok(msg)) => msg,
+                res => return Poll::Ready(res),
+            };
+
+            let id = msg[0] + this.conn.shared_capability().offset();
+            let Some(capability) = this.conn.shared_capabilities().iter().find(|cap| {
+                id >= cap.offset() && id - cap.offset() < cap.num_messages().unwrap_or_default()
+            }) else {
+                return Poll::Ready(Some(Err(P2PStreamError::UnknownMessageId(id))))
+            };
+
+            if *capability == this.primary {
+                msg[0] = id - capability.offset();
+                return Poll::Ready(Some(Ok(msg)))
+            }
+
+            match this.protocols.iter().find(|proxy| proxy.capability == *capability) {
+                Some(proxy) => {
+                    msg[0] = id - capability.offset();
+                    match proxy.to_protocol.try_send(msg) {
+                        Ok(()) => {}
+                        Err(TrySendError::Full(_)) => {
+                            return Poll::Ready(Some(Err(P2PStreamError::ProtocolBufferFull)))
+                        }
+                        // the connection was dropped, it is removed on the next poll
+                        Err(TrySendError::Closed(_)) => {}
+                    }
+                }
+                None => {
+                    tracing::trace!(?capability, "dropping message of capability not installed");
+                }
+            }
+        }
+    }
+}
+
+impl<S> Sink<Bytes> for RlpxMultiplexer<S>
+where
+    S: Sink<Bytes, Error = io::Error> + Unpin,
+{
+    type Error = P2PStreamError;
+
+    fn poll_ready(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_protocols(cx))?;
+        this.conn.poll_ready_unpin(cx)
+    }
+
+    fn start_send(self: Pin<&mut Self>, item: Bytes) -> Result<(), Self::Error> {
+        let this = self.get_mut();
+        let shift = this.shift(&this.primary);
+        if shift == 0 {
+            return this.conn.start_send_unpin(item)
+        }
+        if item.is_empty() {
+            return Err(P2PStreamError::EmptyProtocolMessage)
+        }
+        let mut msg = BytesMut::from(&item[..]);
+        msg[0] += shift;
+        this.conn.start_send_unpin(msg.freeze())
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let this = self.get_mut();
+        ready!(this.poll_protocols(cx))?;
+        this.conn.poll_flush_unpin(cx)
+    }
+
+    fn poll_close(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.get_mut().conn.poll_close_unpin(cx)
+    }
+}
+
+#[async_trait::async_trait]
+impl<S> CanDisconnect<Bytes> for RlpxMultiplexer<S>
+where
+    S: Sink<Bytes, Error = io::Error> + Unpin + Send + Sync,
+{
+    async fn disconnect(&mut self, reason: DisconnectReason) -> Result<(), P2PStreamError> {
+        self.conn.disconnect(reason).await
+    }
+}
+
+/// The multiplexer's end of an installed subprotocol.
+#[derive(Debug)]
+struct ProtocolProxy {
+    /// The capability of the subprotocol.
+    capability: SharedCapability,
+    /// The number of messages of the subprotocol.
+    messages: u8,
+    /// See [`RlpxMultiplexer::shift`].
+    shift: u8,
+    /// Sends incoming messages to the [`ProtocolConnection`].
+    to_protocol: mpsc::Sender<BytesMut>,
+    /// Receives the outgoing messages of the [`ProtocolConnection`].
+    from_protocol: mpsc::Receiver<BytesMut>,
+}
+
+/// The connection of a subprotocol installed in a [`RlpxMultiplexer`].
+///
+/// The stream yields the messages of the subprotocol received from the peer and ends once the
+/// session is closed. The message IDs are relative to the offset of the capability.
+#[derive(Debug)]
+pub struct ProtocolConnection {
+    /// The capability of the subprotocol.
+    capability: SharedCapability,
+    /// Receives the incoming messages of the subprotocol.
+    from_wire: mpsc::Receiver<BytesMut>,
+    /// Sends outgoing messages to the multiplexer.
+    to_wire: mpsc::Sender<BytesMut>,
+}
+
+impl ProtocolConnection {
+    /// Returns the shared capability of the subprotocol.
+    pub fn capability(&self) -> &SharedCapability {
+        &self.capability
+    }
+
+    /// Queues a message for sending, the first byte is the message ID relative to the offset of
+    /// the capability.
+    ///
+    /// Returns the message if too many messages are queued already, or if the session has been
+    /// closed.
+    pub fn send(&self, msg: BytesMut) -> Result<(), TrySendError<BytesMut>> {
+        self.to_wire.try_send(msg)
+    }
+}
+
+impl Stream for ProtocolConnection {
+    type Item = BytesMut;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.get_mut().from_wire.poll_recv(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        capability::{Capability, Protocol},
+        EthVersion, HelloMessage, ProtocolVersion, UnauthedP2PStream,
+    };
+    use reth_ecies::util::pk2id;
+    use reth_net_common::ratelimit::Rate;
+    use secp256k1::{SecretKey, SECP256K1};
+    use std::time::Duration;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio_util::codec::Decoder;
+
+    fn snap_protocol() -> Protocol {
+        Protocol::new(Capability::new("snap".into(), 1), 8)
+    }
+
+    fn hello() -> HelloMessage {
+        let key = SecretKey::new(&mut rand::thread_rng());
+        HelloMessage {
+            protocol_version: ProtocolVersion::V5,
+            client_version: "bitcoind/1.0.0".to_string(),
+            capabilities: vec![EthVersion::Eth67.into(), snap_protocol().cap],
+            port: 30303,
+            id: pk2id(&key.public_key(SECP256K1)),
+        }
+    }
+
+    #[tokio::test]
+    async fn routes_messages_by_capability() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_addr = listener.local_addr().unwrap();
+
+        let handle = tokio::spawn(async move {
+            let (incoming, _) = listener.accept().await.unwrap();
+            let stream = crate::PassthroughCodec::default().framed(incoming);
+            let (p2p_stream, _) = UnauthedP2PStream::new(stream)
+                .with_protocols(vec![snap_protocol()])
+                .handshake(hello())
+                .await
+                .unwrap();
+
+            let mut multiplexer = RlpxMultiplexer::new(p2p_stream);
+            let snap = multiplexer.install_protocol("snap").unwrap();
+            assert_eq!(snap.capability().offset(), 0x10 + EthVersion::Eth67.total_messages());
+
+            // the `eth` message is returned by the multiplexer, the `snap` message is routed
+            assert_eq!(&multiplexer.next().await.unwrap().unwrap()[..], &[0x03, 0xc0]);
+            snap.send(BytesMut::from(&[0x01, 0xc0][..])).unwrap();
+            let _ = multiplexer.next().await;
+        });
+
+        let outgoing = TcpStream::connect(local_addr).await.unwrap();
+        let sink = crate::PassthroughCodec::default().framed(outgoing);
+        let (p2p_stream, _) = UnauthedP2PStream::new(sink)
+            .with_protocols(vec![snap_protocol()])
+            .handshake(hello())
+            .await
+            .unwrap();
+        assert_eq!(p2p_stream.shared_capabilities().len(), 2);
+
+        let mut multiplexer = RlpxMultiplexer::new(p2p_stream);
+        assert_eq!(multiplexer.primary_capability().name(), "eth");
+        let mut snap = multiplexer.install_protocol("snap").unwrap();
+        assert!(multiplexer.install_protocol("snap").is_none());
+
+        multiplexer.send(Bytes::from_static(&[0x03, 0xc0])).await.unwrap();
+
+        // drive the multiplexer until the `snap` message of the peer arrives
+        let received = tokio::select! {
+            msg = snap.next() => msg.unwrap(),
+            _ = multiplexer.next() => unreachable!("no `eth` message expected"),
+        };
+        assert_eq!(&received[..], &[0x01, 0xc0]);
+
+        drop(multiplexer);
+        handle.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn holds_protocol_messages_without_upload_budget() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let local_addr = listener.local_addr().unwrap();
+
+        let handle = tokio::spawn(async move {
+            let (incoming, _) = listener.accept().await.unwrap();
+            let stream = crate::PassthroughCodec::default().framed(incoming);
+            let (p2p_stream, _) = UnauthedP2PStream::new(stream)
+                .with_protocols(vec![snap_protocol()])
+                .handshake(hello())
+                .await
+                .unwrap();
+
+            let mut multiplexer = RlpxMultiplexer::new(p2p_stream);
+            let snap = multiplexer.install_protocol("snap").unwrap();
+
+            // exhausted for the rest of the hour
+            let mut limit = SharedRateLimit::new(Rate::new(1, Duration::from_secs(3600)));
+            limit.tick_by(1);
+            multiplexer.add_upload_limit(limit, 0);
+
+            // the `snap` message is held back, the `eth` message is not
+            snap.send(BytesMut::from(&[0x01, 0xc0][..])).unwrap();
+            multiplexer.send(Bytes::from_static(&[0x03, 0xc0])).await.unwrap();
+            let _ = multiplexer.next().await;
+        });
+
+        let outgoing = TcpStream::connect(local_addr).await.unwrap();
+        let sink = crate::PassthroughCodec::default().framed(outgoing);
+        let (p2p_stream, _) = UnauthedP2PStream::new(sink)
+            .with_protocols(vec![snap_protocol()])
+            .handshake(hello())
+            .await
+            .unwrap();
+
+        let mut multiplexer = RlpxMultiplexer::new(p2p_stream);
+        let mut snap = multiplexer.install_protocol("snap").unwrap();
+
+        assert_eq!(&multiplexer.next().await.unwrap().unwrap()[..], &[0x03, 0xc0]);
+        tokio::select! {
+            _ = snap.next() => panic!("no `snap` message expected"),
+            _ = multiplexer.next() => unreachable!("no `eth` message expected"),
+            _ = tokio::time::sleep(Duration::from_millis(200)) => {}
+        }
+
+        drop(multiplexer);
+        handle.await.unwrap();
+    }
+}
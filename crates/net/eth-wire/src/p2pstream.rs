@@ -1,6 +1,6 @@
 #![allow(dead_code, unreachable_pub, missing_docs, unused_variables)]
 use crate::{
-    capability::{Capability, SharedCapability},
+    capability::{Capability, Protocol, SharedCapability},
     disconnect::CanDisconnect,
     errors::{P2PHandshakeError, P2PStreamError},
     pinger::{Pinger, PingerEvent},
@@ -64,12 +64,24 @@ const MAX_P2P_CAPACITY: usize = 64;
 pub struct UnauthedP2PStream<S> {
     #[pin]
     inner: S,
+    /// The locally supported subprotocols other than `eth`.
+    protocols: Vec<Protocol>,
 }
 
 impl<S> UnauthedP2PStream<S> {
     /// Create a new `UnauthedP2PStream` from a type `S` which implements `Stream` and `Sink`.
     pub fn new(inner: S) -> Self {
-        Self { inner }
+        Self { inner, protocols: Vec::new() }
+    }
+
+    /// Sets the locally supported subprotocols other than `eth`.
+    ///
+    /// Their number of messages is required to determine the message ID offsets of shared
+    /// capabilities, capabilities in the `Hello` message without a matching protocol can't be
+    /// shared, unless they're `eth`.
+    pub fn with_protocols(mut self, protocols: Vec<Protocol>) -> Self {
+        self.protocols = protocols;
+        self
     }
 }
 
@@ -141,20 +153,23 @@ where
             })
         }
 
-        // determine shared capabilities (currently returns only one capability)
-        let capability_res =
-            set_capability_offsets(hello.capabilities, their_hello.capabilities.clone());
+        // determine shared capabilities and their offsets
+        let capability_res = set_capability_offsets(
+            hello.capabilities,
+            their_hello.capabilities.clone(),
+            &self.protocols,
+        );
 
-        let shared_capability = match capability_res {
+        let shared_capabilities = match capability_res {
             Err(err) => {
                 // we don't share any capabilities, send a disconnect message
                 self.send_disconnect(DisconnectReason::UselessPeer).await?;
                 Err(err)
             }
-            Ok(cap) => Ok(cap),
+            Ok(caps) => Ok(caps),
         }?;
 
-        let stream = P2PStream::new(self.inner, shared_capability);
+        let stream = P2PStream::new(self.inner, shared_capabilities);
 
         Ok((stream, their_hello))
     }
@@ -206,8 +221,11 @@ pub struct P2PStream<S> {
     /// The state machine used for keeping track of the peer's ping status.
     pinger: Pinger,
 
-    /// The supported capability for this stream.
-    shared_capability: SharedCapability,
+    /// The shared capabilities of this stream, ordered by their offset.
+    ///
+    /// Message IDs of this stream are relative to the offset of the first capability, see
+    /// [`P2PStream::shared_capability`].
+    shared_capabilities: Vec<SharedCapability>,
 
     /// Outgoing messages buffered for sending to the underlying stream.
     outgoing_messages: VecDeque<Bytes>,
@@ -221,21 +239,34 @@ impl<S> P2PStream<S> {
     /// Create a new [`P2PStream`] from the provided stream.
     /// New [`P2PStream`]s are assumed to have completed the `p2p` handshake successfully and are
     /// ready to send and receive subprotocol messages.
-    pub fn new(inner: S, capability: SharedCapability) -> Self {
+    ///
+    /// # Panics
+    ///
+    /// If no shared capabilities are given.
+    pub fn new(inner: S, shared_capabilities: Vec<SharedCapability>) -> Self {
+        assert!(!shared_capabilities.is_empty(), "at least one shared capability is required");
         Self {
             inner,
             encoder: snap::raw::Encoder::new(),
             decoder: snap::raw::Decoder::new(),
             pinger: Pinger::new(PING_INTERVAL, PING_TIMEOUT),
-            shared_capability: capability,
+            shared_capabilities,
             outgoing_messages: VecDeque::new(),
             disconnecting: false,
         }
     }
 
-    /// Returns the shared capability for this stream.
+    /// Returns the shared capability with the lowest offset.
+    ///
+    /// The message IDs of messages sent and received by this stream are relative to the offset of
+    /// this capability.
     pub fn shared_capability(&self) -> &SharedCapability {
-        &self.shared_capability
+        &self.shared_capabilities[0]
+    }
+
+    /// Returns all shared capabilities of this stream, ordered by their offset.
+    pub fn shared_capabilities(&self) -> &[SharedCapability] {
+        &self.shared_capabilities
     }
 
     /// Returns `true` if the connection is about to disconnect.
@@ -421,7 +452,7 @@ where
                     //  * `eth/67` is reserved message IDs 0x10 - 0x19.
                     //  * `qrs/65` is reserved message IDs 0x1a - 0x21.
                     //
-                    decompress_buf[0] = bytes[0] - this.shared_capability.offset();
+                    decompress_buf[0] = bytes[0] - this.shared_capabilities[0].offset();
 
                     return Poll::Ready(Some(Ok(decompress_buf)))
                 }
@@ -500,7 +531,7 @@ where
 
         // all messages sent in this stream are subprotocol messages, so we need to switch the
         // message id based on the offset
-        compressed[0] = item[0] + this.shared_capability.offset();
+        compressed[0] = item[0] + this.shared_capabilities[0].offset();
         this.outgoing_messages.push_back(compressed.freeze());
 
         Ok(())
@@ -536,13 +567,17 @@ where
 /// Determines the offsets for each shared capability between the input list of peer
 /// capabilities and the input list of locally supported capabilities.
 ///
-/// Currently only `eth` versions 66 and 67 are supported.
+/// Besides `eth`, only capabilities of the given `protocols` can be shared, since the number of
+/// messages of a capability is required to determine the offset of the next one.
 /// Additionally, the `p2p` capability version 5 is supported, but is
 /// expected _not_ to be in neither `local_capabilities` or `peer_capabilities`.
+///
+/// Returns the shared capabilities ordered by their offset.
 pub fn set_capability_offsets(
     local_capabilities: Vec<Capability>,
     peer_capabilities: Vec<Capability>,
-) -> Result<SharedCapability, P2PStreamError> {
+    protocols: &[Protocol],
+) -> Result<Vec<SharedCapability>, P2PStreamError> {
     // find intersection of capabilities
     let our_capabilities = local_capabilities.into_iter().collect::<HashSet<_>>();
 
@@ -596,14 +631,22 @@ pub fn set_capability_offsets(
     for name in shared_capability_names {
         let version = shared_capabilities.get(&name).unwrap();
 
-        let shared_capability = SharedCapability::new(&name, *version as u8, offset)?;
+        let protocol = protocols
+            .iter()
+            .find(|protocol| protocol.cap.name == name && protocol.cap.version == *version);
+        let shared_capability = match protocol {
+            Some(protocol) => SharedCapability::from_protocol(protocol, offset)?,
+            None => SharedCapability::new(&name, *version as u8, offset)?,
+        };
 
         match shared_capability {
             SharedCapability::UnknownCapability { .. } => {
-                // Capabilities which are not shared are ignored
+                // The offsets of all following capabilities depend on the number of messages of
+                // this one, so none of them can be shared
                 tracing::debug!("unknown capability: name={:?}, version={}", name, version,);
+                break
             }
-            SharedCapability::Eth { .. } => {
+            SharedCapability::Eth { .. } | SharedCapability::Other { .. } => {
                 // increment the offset if the capability is known
                 offset += shared_capability.num_messages()?;
 
@@ -612,17 +655,11 @@ pub fn set_capability_offsets(
         }
     }
 
-    // TODO: support multiple capabilities - we would need a new Stream type to go on top of
-    // `P2PStream` containing its capability. `P2PStream` would still send pings and handle
-    // pongs, but instead contain a map of capabilities to their respective stream / channel.
-    // Each channel would be responsible for containing the offset for that stream and would
-    // only increment / decrement message IDs.
-    // NOTE: since the `P2PStream` currently only supports one capability, we set the
-    // capability with the lowest offset.
-    Ok(shared_with_offsets
-        .first()
-        .ok_or(P2PStreamError::HandshakeError(P2PHandshakeError::NoSharedCapabilities))?
-        .clone())
+    if shared_with_offsets.is_empty() {
+        return Err(P2PStreamError::HandshakeError(P2PHandshakeError::NoSharedCapabilities))
+    }
+
+    Ok(shared_with_offsets)
 }
 
 /// This represents only the reserved `p2p` subprotocol messages.
@@ -882,7 +919,7 @@ mod tests {
 
             // ensure that the two share a single capability, eth67
             assert_eq!(
-                p2p_stream.shared_capability,
+                *p2p_stream.shared_capability(),
                 SharedCapability::Eth {
                     version: EthVersion::Eth67,
                     offset: MAX_RESERVED_MESSAGE_ID + 1
@@ -900,7 +937,7 @@ mod tests {
 
         // ensure that the two share a single capability, eth67
         assert_eq!(
-            p2p_stream.shared_capability,
+            *p2p_stream.shared_capability(),
             SharedCapability::Eth {
                 version: EthVersion::Eth67,
                 offset: MAX_RESERVED_MESSAGE_ID + 1
@@ -972,15 +1009,15 @@ mod tests {
             vec![EthVersion::Eth66.into(), EthVersion::Eth67.into(), EthVersion::Eth68.into()];
         let peer_capabilities: Vec<Capability> = vec![EthVersion::Eth66.into()];
 
-        let shared_capability =
-            set_capability_offsets(local_capabilities, peer_capabilities).unwrap();
+        let shared_capabilities =
+            set_capability_offsets(local_capabilities, peer_capabilities, &[]).unwrap();
 
         assert_eq!(
-            shared_capability,
-            SharedCapability::Eth {
+            shared_capabilities,
+            vec![SharedCapability::Eth {
                 version: EthVersion::Eth66,
                 offset: MAX_RESERVED_MESSAGE_ID + 1
-            }
+            }]
         )
     }
 
@@ -989,7 +1026,7 @@ mod tests {
         let local_capabilities: Vec<Capability> = vec![EthVersion::Eth67.into()];
         let peer_capabilities: Vec<Capability> = vec![EthVersion::Eth66.into()];
 
-        let shared_capability = set_capability_offsets(local_capabilities, peer_capabilities);
+        let shared_capability = set_capability_offsets(local_capabilities, peer_capabilities, &[]);
 
         assert!(matches!(
             shared_capability,
@@ -1002,7 +1039,7 @@ mod tests {
         let local_capabilities: Vec<Capability> = vec![EthVersion::Eth66.into()];
         let peer_capabilities: Vec<Capability> = vec![EthVersion::Eth67.into()];
 
-        let shared_capability = set_capability_offsets(local_capabilities, peer_capabilities);
+        let shared_capability = set_capability_offsets(local_capabilities, peer_capabilities, &[]);
 
         assert!(matches!(
             shared_capability,
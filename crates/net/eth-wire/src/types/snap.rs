@@ -0,0 +1,479 @@
+//! Implements the `snap/1` protocol messages.
+//!
+//! See also <https://github.com/ethereum/devp2p/blob/master/caps/snap.md>
+use crate::capability::{Capability, Protocol};
+use reth_codecs::derive_arbitrary;
+use reth_primitives::{
+    bytes::{Buf, BufMut},
+    proofs::EMPTY_ROOT,
+    Bytes, H256, KECCAK_EMPTY, U256,
+};
+use reth_rlp::{Decodable, DecodeError, Encodable, RlpDecodable, RlpEncodable};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+/// A request for the accounts of the state trie with the given root, starting at
+/// `starting_hash` and ending with the first account at or after `limit_hash`.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetAccountRange {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The root of the state trie to serve.
+    pub root_hash: H256,
+    /// The hash of the first account to return.
+    pub starting_hash: H256,
+    /// The hash after which no more accounts need to be returned.
+    pub limit_hash: H256,
+    /// The soft limit of the response size in bytes.
+    pub response_bytes: u64,
+}
+
+/// The response to [`GetAccountRange`], containing consecutive accounts and the proof of the
+/// first and the last one.
+///
+/// The proof is empty if the range starts at the zero hash and contains all remaining accounts.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountRange {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The accounts, ordered by their hashed address.
+    pub accounts: Vec<AccountData>,
+    /// The RLP of the trie nodes proving the boundaries of the range.
+    pub proof: Vec<Bytes>,
+}
+
+/// An account of an [`AccountRange`].
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AccountData {
+    /// The hashed address of the account.
+    pub hash: H256,
+    /// The account.
+    pub body: SlimAccount,
+}
+
+/// The slim encoding of a trie account, which leaves out the storage root of accounts without
+/// storage and the code hash of accounts without code.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct SlimAccount {
+    /// The nonce of the account.
+    pub nonce: u64,
+    /// The balance of the account.
+    pub balance: U256,
+    /// The storage root, empty if it is the root of the empty trie.
+    pub storage_root: Bytes,
+    /// The code hash, empty if it is the hash of empty code.
+    pub code_hash: Bytes,
+}
+
+impl SlimAccount {
+    /// Creates the slim encoding of an account.
+    pub fn new(nonce: u64, balance: U256, storage_root: H256, code_hash: H256) -> Self {
+        let slim = |hash: H256, empty: H256| {
+            if hash == empty {
+                Bytes::default()
+            } else {
+                Bytes::from(hash.to_fixed_bytes().to_vec())
+            }
+        };
+        Self {
+            nonce,
+            balance,
+            storage_root: slim(storage_root, EMPTY_ROOT),
+            code_hash: slim(code_hash, KECCAK_EMPTY),
+        }
+    }
+
+    /// Returns the storage root of the account.
+    pub fn storage_root(&self) -> H256 {
+        full_hash(&self.storage_root, EMPTY_ROOT)
+    }
+
+    /// Returns the code hash of the account.
+    pub fn code_hash(&self) -> H256 {
+        full_hash(&self.code_hash, KECCAK_EMPTY)
+    }
+}
+
+/// Returns the hash of a slim encoding, which is `empty` if the encoding is empty.
+fn full_hash(slim: &Bytes, empty: H256) -> H256 {
+    if slim.is_empty() {
+        empty
+    } else {
+        H256::from_slice(slim)
+    }
+}
+
+/// A request for the storage slots of the given accounts in the state trie with the given root.
+///
+/// The slots of the first account start at `starting_hash`, and the slots of the last account end
+/// with the first slot at or after `limit_hash`. Both are empty to request whole storage tries.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetStorageRanges {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The root of the state trie to serve.
+    pub root_hash: H256,
+    /// The hashed addresses of the accounts.
+    pub account_hashes: Vec<H256>,
+    /// The hash of the first slot of the first account to return.
+    pub starting_hash: Bytes,
+    /// The hash after which no more slots of the last account need to be returned.
+    pub limit_hash: Bytes,
+    /// The soft limit of the response size in bytes.
+    pub response_bytes: u64,
+}
+
+/// The response to [`GetStorageRanges`], containing the slots of a prefix of the requested
+/// accounts.
+///
+/// If the slots of the last account are incomplete, the proof of its first and last slot is
+/// attached.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StorageRanges {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The slots of each account, ordered by their hashed key.
+    pub slots: Vec<Vec<StorageData>>,
+    /// The RLP of the trie nodes proving the boundaries of the last range.
+    pub proof: Vec<Bytes>,
+}
+
+/// A storage slot of a [`StorageRanges`].
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct StorageData {
+    /// The hashed key of the slot.
+    pub hash: H256,
+    /// The RLP encoded value of the slot.
+    pub data: Bytes,
+}
+
+/// A request for the contract bytecodes with the given hashes.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetByteCodes {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The code hashes of the bytecodes.
+    pub hashes: Vec<H256>,
+    /// The soft limit of the response size in bytes.
+    pub response_bytes: u64,
+}
+
+/// The response to [`GetByteCodes`], containing the bytecodes in request order.
+///
+/// Bytecodes that are not available are left out.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct ByteCodes {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The bytecodes.
+    pub codes: Vec<Bytes>,
+}
+
+/// A request for trie nodes of the state trie with the given root, or of the storage tries of its
+/// accounts.
+///
+/// Each path set either holds the hex-prefix encoded path of a node in the state trie, or the
+/// hashed address of an account followed by hex-prefix encoded paths in its storage trie.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct GetTrieNodes {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The root of the state trie to serve.
+    pub root_hash: H256,
+    /// The path sets of the requested nodes.
+    pub paths: Vec<Vec<Bytes>>,
+    /// The soft limit of the response size in bytes.
+    pub response_bytes: u64,
+}
+
+/// The response to [`GetTrieNodes`], containing the RLP of the nodes in request order.
+///
+/// The response ends at the first node that is not available.
+#[derive_arbitrary(rlp)]
+#[derive(Clone, Debug, PartialEq, Eq, RlpEncodable, RlpDecodable, Default)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct TrieNodes {
+    /// The id of the request.
+    pub request_id: u64,
+    /// The trie nodes.
+    pub nodes: Vec<Bytes>,
+}
+
+/// A `snap/1` protocol message.
+///
+/// Unlike `eth` messages, every message starts with its request id, so they are not wrapped in a
+/// [`RequestPair`](crate::message::RequestPair).
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(missing_docs)]
+pub enum SnapMessage {
+    GetAccountRange(GetAccountRange),
+    AccountRange(AccountRange),
+    GetStorageRanges(GetStorageRanges),
+    StorageRanges(StorageRanges),
+    GetByteCodes(GetByteCodes),
+    ByteCodes(ByteCodes),
+    GetTrieNodes(GetTrieNodes),
+    TrieNodes(TrieNodes),
+}
+
+impl SnapMessage {
+    /// The name of the `snap` capability.
+    pub const CAPABILITY_NAME: &'static str = "snap";
+
+    /// Returns the `snap/1` subprotocol with its number of messages.
+    pub fn protocol() -> Protocol {
+        Protocol::new(Capability::new(Self::CAPABILITY_NAME.into(), 1), 8)
+    }
+
+    /// Returns the message's ID.
+    pub fn message_id(&self) -> SnapMessageID {
+        match self {
+            SnapMessage::GetAccountRange(_) => SnapMessageID::GetAccountRange,
+            SnapMessage::AccountRange(_) => SnapMessageID::AccountRange,
+            SnapMessage::GetStorageRanges(_) => SnapMessageID::GetStorageRanges,
+            SnapMessage::StorageRanges(_) => SnapMessageID::StorageRanges,
+            SnapMessage::GetByteCodes(_) => SnapMessageID::GetByteCodes,
+            SnapMessage::ByteCodes(_) => SnapMessageID::ByteCodes,
+            SnapMessage::GetTrieNodes(_) => SnapMessageID::GetTrieNodes,
+            SnapMessage::TrieNodes(_) => SnapMessageID::TrieNodes,
+        }
+    }
+
+    /// Returns the id of the request the message belongs to.
+    pub fn request_id(&self) -> u64 {
+        match self {
+            SnapMessage::GetAccountRange(msg) => msg.request_id,
+            SnapMessage::AccountRange(msg) => msg.request_id,
+            SnapMessage::GetStorageRanges(msg) => msg.request_id,
+            SnapMessage::StorageRanges(msg) => msg.request_id,
+            SnapMessage::GetByteCodes(msg) => msg.request_id,
+            SnapMessage::ByteCodes(msg) => msg.request_id,
+            SnapMessage::GetTrieNodes(msg) => msg.request_id,
+            SnapMessage::TrieNodes(msg) => msg.request_id,
+        }
+    }
+
+    /// Sets the id of the request the message belongs to.
+    pub fn set_request_id(&mut self, request_id: u64) {
+        match self {
+            SnapMessage::GetAccountRange(msg) => msg.request_id = request_id,
+            SnapMessage::AccountRange(msg) => msg.request_id = request_id,
+            SnapMessage::GetStorageRanges(msg) => msg.request_id = request_id,
+            SnapMessage::StorageRanges(msg) => msg.request_id = request_id,
+            SnapMessage::GetByteCodes(msg) => msg.request_id = request_id,
+            SnapMessage::ByteCodes(msg) => msg.request_id = request_id,
+            SnapMessage::GetTrieNodes(msg) => msg.request_id = request_id,
+            SnapMessage::TrieNodes(msg) => msg.request_id = request_id,
+        }
+    }
+
+    /// Returns true if the message is a request, false if it is a response.
+    pub fn is_request(&self) -> bool {
+        matches!(
+            self,
+            SnapMessage::GetAccountRange(_) |
+                SnapMessage::GetStorageRanges(_) |
+                SnapMessage::GetByteCodes(_) |
+                SnapMessage::GetTrieNodes(_)
+        )
+    }
+}
+
+/// Encodes the message with its ID prepended as a single byte.
+impl Encodable for SnapMessage {
+    fn encode(&self, out: &mut dyn BufMut) {
+        self.message_id().encode(out);
+        match self {
+            SnapMessage::GetAccountRange(msg) => msg.encode(out),
+            SnapMessage::AccountRange(msg) => msg.encode(out),
+            SnapMessage::GetStorageRanges(msg) => msg.encode(out),
+            SnapMessage::StorageRanges(msg) => msg.encode(out),
+            SnapMessage::GetByteCodes(msg) => msg.encode(out),
+            SnapMessage::ByteCodes(msg) => msg.encode(out),
+            SnapMessage::GetTrieNodes(msg) => msg.encode(out),
+            SnapMessage::TrieNodes(msg) => msg.encode(out),
+        }
+    }
+
+    fn length(&self) -> usize {
+        let payload = match self {
+            SnapMessage::GetAccountRange(msg) => msg.length(),
+            SnapMessage::AccountRange(msg) => msg.length(),
+            SnapMessage::GetStorageRanges(msg) => msg.length(),
+            SnapMessage::StorageRanges(msg) => msg.length(),
+            SnapMessage::GetByteCodes(msg) => msg.length(),
+            SnapMessage::ByteCodes(msg) => msg.length(),
+            SnapMessage::GetTrieNodes(msg) => msg.length(),
+            SnapMessage::TrieNodes(msg) => msg.length(),
+        };
+        self.message_id().length() + payload
+    }
+}
+
+impl Decodable for SnapMessage {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let message = match SnapMessageID::decode(buf)? {
+            SnapMessageID::GetAccountRange => {
+                SnapMessage::GetAccountRange(GetAccountRange::decode(buf)?)
+            }
+            SnapMessageID::AccountRange => SnapMessage::AccountRange(AccountRange::decode(buf)?),
+            SnapMessageID::GetStorageRanges => {
+                SnapMessage::GetStorageRanges(GetStorageRanges::decode(buf)?)
+            }
+            SnapMessageID::StorageRanges => SnapMessage::StorageRanges(StorageRanges::decode(buf)?),
+            SnapMessageID::GetByteCodes => SnapMessage::GetByteCodes(GetByteCodes::decode(buf)?),
+            SnapMessageID::ByteCodes => SnapMessage::ByteCodes(ByteCodes::decode(buf)?),
+            SnapMessageID::GetTrieNodes => SnapMessage::GetTrieNodes(GetTrieNodes::decode(buf)?),
+            SnapMessageID::TrieNodes => SnapMessage::TrieNodes(TrieNodes::decode(buf)?),
+        };
+        Ok(message)
+    }
+}
+
+/// Represents message IDs for `snap/1` protocol messages, relative to the offset of the
+/// capability.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[allow(missing_docs)]
+pub enum SnapMessageID {
+    GetAccountRange = 0x00,
+    AccountRange = 0x01,
+    GetStorageRanges = 0x02,
+    StorageRanges = 0x03,
+    GetByteCodes = 0x04,
+    ByteCodes = 0x05,
+    GetTrieNodes = 0x06,
+    TrieNodes = 0x07,
+}
+
+impl Encodable for SnapMessageID {
+    fn encode(&self, out: &mut dyn BufMut) {
+        out.put_u8(*self as u8);
+    }
+    fn length(&self) -> usize {
+        1
+    }
+}
+
+impl Decodable for SnapMessageID {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let id = buf.first().ok_or(DecodeError::InputTooShort)?;
+        let id = match id {
+            0x00 => SnapMessageID::GetAccountRange,
+            0x01 => SnapMessageID::AccountRange,
+            0x02 => SnapMessageID::GetStorageRanges,
+            0x03 => SnapMessageID::StorageRanges,
+            0x04 => SnapMessageID::GetByteCodes,
+            0x05 => SnapMessageID::ByteCodes,
+            0x06 => SnapMessageID::GetTrieNodes,
+            0x07 => SnapMessageID::TrieNodes,
+            _ => return Err(DecodeError::Custom("Invalid message ID")),
+        };
+        buf.advance(1);
+        Ok(id)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use hex_literal::hex;
+
+    #[test]
+    fn roundtrip_messages() {
+        let messages = [
+            SnapMessage::GetAccountRange(GetAccountRange {
+                request_id: 1,
+                root_hash: H256::repeat_byte(0x11),
+                starting_hash: H256::zero(),
+                limit_hash: H256::repeat_byte(0xff),
+                response_bytes: 512 * 1024,
+            }),
+            SnapMessage::AccountRange(AccountRange {
+                request_id: 1,
+                accounts: vec![AccountData {
+                    hash: H256::repeat_byte(0x22),
+                    body: SlimAccount::new(1, U256::from(2), EMPTY_ROOT, KECCAK_EMPTY),
+                }],
+                proof: vec![Bytes::from(vec![0xc0])],
+            }),
+            SnapMessage::GetStorageRanges(GetStorageRanges {
+                request_id: 2,
+                root_hash: H256::repeat_byte(0x11),
+                account_hashes: vec![H256::repeat_byte(0x22), H256::repeat_byte(0x33)],
+                starting_hash: Bytes::default(),
+                limit_hash: Bytes::default(),
+                response_bytes: 512 * 1024,
+            }),
+            SnapMessage::StorageRanges(StorageRanges {
+                request_id: 2,
+                slots: vec![
+                    vec![StorageData { hash: H256::repeat_byte(0x44), data: vec![0x01].into() }],
+                    vec![],
+                ],
+                proof: vec![],
+            }),
+            SnapMessage::GetByteCodes(GetByteCodes {
+                request_id: 3,
+                hashes: vec![H256::repeat_byte(0x55)],
+                response_bytes: 1024,
+            }),
+            SnapMessage::ByteCodes(ByteCodes { request_id: 3, codes: vec![vec![0x60].into()] }),
+            SnapMessage::GetTrieNodes(GetTrieNodes {
+                request_id: 4,
+                root_hash: H256::repeat_byte(0x11),
+                paths: vec![
+                    vec![Bytes::from(vec![0x00])],
+                    vec![H256::repeat_byte(0x22).as_bytes().to_vec().into(), vec![0x11].into()],
+                ],
+                response_bytes: 1024,
+            }),
+            SnapMessage::TrieNodes(TrieNodes { request_id: 4, nodes: vec![vec![0xc0].into()] }),
+        ];
+
+        for (id, message) in messages.into_iter().enumerate() {
+            let mut encoded = Vec::new();
+            message.encode(&mut encoded);
+            assert_eq!(encoded.len(), message.length());
+            assert_eq!(encoded[0], id as u8);
+            assert_eq!(SnapMessage::decode(&mut &encoded[..]).unwrap(), message);
+        }
+    }
+
+    #[test]
+    fn slim_account_encoding() {
+        let account = SlimAccount::new(1, U256::from(2), EMPTY_ROOT, KECCAK_EMPTY);
+        let mut encoded = Vec::new();
+        account.encode(&mut encoded);
+        assert_eq!(encoded, hex!("c401028080"));
+        assert_eq!(account.storage_root(), EMPTY_ROOT);
+        assert_eq!(account.code_hash(), KECCAK_EMPTY);
+
+        let storage_root = H256::repeat_byte(0x11);
+        let account = SlimAccount::new(1, U256::from(2), storage_root, KECCAK_EMPTY);
+        assert_eq!(account.storage_root(), storage_root);
+    }
+}
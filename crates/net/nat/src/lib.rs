@@ -5,20 +5,23 @@
     attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
 ))]
 
-//! Helpers for resolving the external IP.
+//! Helpers for resolving the external IP and for mapping ports on the gateway.
 
 use igd::aio::search_gateway;
 use pin_project_lite::pin_project;
 use std::{
     fmt,
     future::{poll_fn, Future},
-    net::{AddrParseError, IpAddr},
+    net::{AddrParseError, IpAddr, Ipv4Addr, SocketAddr},
     pin::Pin,
     str::FromStr,
     task::{ready, Context, Poll},
     time::Duration,
 };
-use tracing::warn;
+use tokio::net::UdpSocket;
+use tracing::{debug, warn};
+
+mod pmp;
 
 #[cfg(feature = "serde")]
 use serde_with::{DeserializeFromStr, SerializeDisplay};
@@ -32,6 +35,8 @@ pub enum NatResolver {
     Any,
     /// Resolve via Upnp
     Upnp,
+    /// Resolve via NAT-PMP or PCP, with the given gateway or the guessed default gateway.
+    NatPmp(Option<Ipv4Addr>),
     /// Resolve external IP via [public_ip::Resolver]
     PublicIp,
     /// Use the given [IpAddr]
@@ -54,6 +59,8 @@ impl fmt::Display for NatResolver {
         match self {
             NatResolver::Any => f.write_str("any"),
             NatResolver::Upnp => f.write_str("upnp"),
+            NatResolver::NatPmp(None) => f.write_str("natpmp"),
+            NatResolver::NatPmp(Some(gateway)) => write!(f, "natpmp:{gateway}"),
             NatResolver::PublicIp => f.write_str("publicip"),
             NatResolver::ExternalIp(ip) => write!(f, "extip:{ip}"),
             NatResolver::None => f.write_str("none"),
@@ -79,11 +86,16 @@ impl FromStr for NatResolver {
         let r = match s {
             "any" => NatResolver::Any,
             "upnp" => NatResolver::Upnp,
+            "natpmp" | "pmp" => NatResolver::NatPmp(None),
             "none" => NatResolver::None,
             "publicip" | "public-ip" => NatResolver::PublicIp,
             s => {
                 if let Some(ip) = s.strip_prefix("extip:") {
                     NatResolver::ExternalIp(ip.parse::<IpAddr>()?)
+                } else if let Some(gateway) =
+                    s.strip_prefix("natpmp:").or_else(|| s.strip_prefix("pmp:"))
+                {
+                    NatResolver::NatPmp(Some(gateway.parse::<Ipv4Addr>()?))
                 } else {
                     return Err(ParseNatResolverError::UnknonwVariant(format!(
                         "Unknown Nat Resolver: {s}"
@@ -95,19 +107,107 @@ impl FromStr for NatResolver {
     }
 }
 
+/// The transport protocol of a [PortMapping].
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum PortMappingProtocol {
+    /// TCP
+    Tcp,
+    /// UDP
+    Udp,
+}
+
+/// A local port that should be reachable from outside the NAT.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct PortMapping {
+    /// The transport protocol of the port.
+    pub protocol: PortMappingProtocol,
+    /// The local port.
+    pub port: u16,
+}
+
+// === impl PortMapping ===
+
+impl PortMapping {
+    /// Creates a mapping of the local TCP port.
+    pub fn tcp(port: u16) -> Self {
+        Self { protocol: PortMappingProtocol::Tcp, port }
+    }
+
+    /// Creates a mapping of the local UDP port.
+    pub fn udp(port: u16) -> Self {
+        Self { protocol: PortMappingProtocol::Udp, port }
+    }
+}
+
+/// A local port that has been mapped on the gateway.
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub struct MappedPort {
+    /// The transport protocol of the port.
+    pub protocol: PortMappingProtocol,
+    /// The local port.
+    pub internal_port: u16,
+    /// The port on the external address of the gateway.
+    pub external_port: u16,
+    /// How long the gateway keeps the mapping.
+    pub lifetime: Duration,
+}
+
+/// The external address resolved by [ResolveNatInterval].
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct ExternalAddr {
+    /// The external IP.
+    pub ip: IpAddr,
+    /// The ports that have been mapped on the gateway.
+    pub mapped_ports: Vec<MappedPort>,
+}
+
+// === impl ExternalAddr ===
+
+impl ExternalAddr {
+    /// Returns the external port of the first mapped port with the given protocol.
+    pub fn external_port(&self, protocol: PortMappingProtocol) -> Option<u16> {
+        self.mapped_ports
+            .iter()
+            .find(|mapped| mapped.protocol == protocol)
+            .map(|mapped| mapped.external_port)
+    }
+
+    /// Returns the shortest lifetime of the mapped ports, `None` if no ports were mapped.
+    pub fn lifetime(&self) -> Option<Duration> {
+        self.mapped_ports.iter().map(|mapped| mapped.lifetime).min()
+    }
+}
+
 /// With this type you can resolve the external public IP address on an interval basis.
+///
+/// If [PortMapping]s are configured, the ports are also mapped on the gateway on every tick. The
+/// mappings are requested for twice the period of the interval, but the gateway may grant a
+/// shorter lifetime, so they are also renewed after half of the granted lifetime.
 #[must_use = "Does nothing unless polled"]
 pub struct ResolveNatInterval {
     resolver: NatResolver,
-    future: Option<ResolveFut>,
+    future: Option<ResolveAddrFut>,
     interval: tokio::time::Interval,
+    /// Fires when the mapped ports need to be renewed before the next tick of the interval.
+    renewal: Option<Pin<Box<tokio::time::Sleep>>>,
+    /// The local ports to map on the gateway.
+    port_mappings: Vec<PortMapping>,
+    /// Identifies the PCP mappings of this node, so they can be renewed.
+    nonce: pmp::Nonce,
 }
 
 // === impl ResolveNatInterval ===
 
 impl ResolveNatInterval {
     fn with_interval(resolver: NatResolver, interval: tokio::time::Interval) -> Self {
-        Self { resolver, future: None, interval }
+        Self {
+            resolver,
+            future: None,
+            interval,
+            renewal: None,
+            port_mappings: Vec::new(),
+            nonce: pmp::random_nonce(),
+        }
     }
 
     /// Creates a new [ResolveNatInterval] that attempts to resolve the public IP with interval of
@@ -130,27 +230,53 @@ impl ResolveNatInterval {
         Self::with_interval(resolver, interval)
     }
 
-    /// Completes when the next [IpAddr] in the interval has been reached.
-    pub async fn tick(&mut self) -> Option<IpAddr> {
-        let ip = poll_fn(|cx| self.poll_tick(cx));
-        ip.await
+    /// Sets the local ports that are mapped on the gateway on every tick.
+    ///
+    /// Ports are only mapped by the [NatResolver::Upnp], [NatResolver::NatPmp] and
+    /// [NatResolver::Any] resolvers.
+    pub fn with_port_mappings(mut self, mappings: impl IntoIterator<Item = PortMapping>) -> Self {
+        self.port_mappings = mappings.into_iter().collect();
+        self
+    }
+
+    /// Completes when the next [ExternalAddr] in the interval has been reached.
+    pub async fn tick(&mut self) -> Option<ExternalAddr> {
+        let addr = poll_fn(|cx| self.poll_tick(cx));
+        addr.await
     }
 
-    /// Polls for the next resolved [IpAddr] in the interval to be reached.
+    /// Polls for the next resolved [ExternalAddr] in the interval to be reached.
     ///
     /// This method can return the following values:
     ///
-    ///  * `Poll::Pending` if the next [IpAddr] has not yet been resolved.
-    ///  * `Poll::Ready(Option<IpAddr>)` if the next [IpAddr] has been resolved. This returns `None`
-    ///    if the attempt was unsuccessful.
-    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Option<IpAddr>> {
-        if self.interval.poll_tick(cx).is_ready() {
-            self.future = Some(Box::pin(self.resolver.external_addr()));
+    ///  * `Poll::Pending` if the next [ExternalAddr] has not yet been resolved.
+    ///  * `Poll::Ready(Option<ExternalAddr>)` if the next [ExternalAddr] has been resolved. This
+    ///    returns `None` if the attempt was unsuccessful.
+    pub fn poll_tick(&mut self, cx: &mut Context<'_>) -> Poll<Option<ExternalAddr>> {
+        let mut resolve = self.interval.poll_tick(cx).is_ready();
+        if let Some(renewal) = self.renewal.as_mut() {
+            if renewal.as_mut().poll(cx).is_ready() {
+                self.renewal = None;
+                resolve = true;
+            }
+        }
+
+        if resolve && self.future.is_none() {
+            let lifetime = self.interval.period() * 2;
+            self.future = Some(Box::pin(resolve_addr(
+                self.resolver,
+                self.port_mappings.clone(),
+                lifetime,
+                self.nonce,
+            )));
         }
 
         if let Some(mut fut) = self.future.take() {
             match fut.as_mut().poll(cx) {
-                Poll::Ready(ip) => return Poll::Ready(ip),
+                Poll::Ready(addr) => {
+                    self.schedule_renewal(addr.as_ref());
+                    return Poll::Ready(addr)
+                }
                 Poll::Pending => {
                     self.future = Some(fut);
                 }
@@ -159,8 +285,23 @@ impl ResolveNatInterval {
 
         Poll::Pending
     }
+
+    /// Schedules the renewal of the mapped ports after half of their granted lifetime, if that is
+    /// before the next tick of the interval.
+    fn schedule_renewal(&mut self, addr: Option<&ExternalAddr>) {
+        self.renewal = None;
+        let Some(lifetime) = addr.and_then(ExternalAddr::lifetime) else { return };
+        let delay = (lifetime / 2).max(MIN_RENEWAL_DELAY);
+        if delay < self.interval.period() {
+            self.renewal = Some(Box::pin(tokio::time::sleep(delay)));
+        }
+    }
 }
 
+/// The minimum delay before mapped ports are renewed, in case the gateway grants very short
+/// lifetimes.
+const MIN_RENEWAL_DELAY: Duration = Duration::from_secs(10);
+
 /// Attempts to produce an IP address with all builtin resolvers (best effort).
 pub async fn external_ip() -> Option<IpAddr> {
     external_addr_with(NatResolver::Any).await
@@ -177,14 +318,54 @@ pub async fn external_addr_with(resolver: NatResolver) -> Option<IpAddr> {
             .await
         }
         NatResolver::Upnp => resolve_external_ip_upnp().await,
+        NatResolver::NatPmp(gateway) => resolve_external_ip_pmp(gateway).await,
         NatResolver::PublicIp => resolve_external_ip().await,
         NatResolver::ExternalIp(ip) => Some(ip),
         NatResolver::None => None,
     }
 }
 
+/// Resolves the external address with the given [`NatResolver`] and maps the ports on the gateway
+/// for the given lifetime (best effort).
+async fn resolve_addr(
+    resolver: NatResolver,
+    mappings: Vec<PortMapping>,
+    lifetime: Duration,
+    nonce: pmp::Nonce,
+) -> Option<ExternalAddr> {
+    if mappings.is_empty() {
+        let ip = external_addr_with(resolver).await?;
+        return Some(ExternalAddr { ip, mapped_ports: Vec::new() })
+    }
+
+    match resolver {
+        NatResolver::Any => {
+            if let Some(addr) = resolve_with_port_mappings_upnp(&mappings, lifetime).await {
+                return Some(addr)
+            }
+            if let Some(addr) =
+                resolve_with_port_mappings_pmp(None, &mappings, lifetime, nonce).await
+            {
+                return Some(addr)
+            }
+            let ip = resolve_external_ip().await?;
+            Some(ExternalAddr { ip, mapped_ports: Vec::new() })
+        }
+        NatResolver::Upnp => resolve_with_port_mappings_upnp(&mappings, lifetime).await,
+        NatResolver::NatPmp(gateway) => {
+            resolve_with_port_mappings_pmp(gateway, &mappings, lifetime, nonce).await
+        }
+        resolver => {
+            let ip = external_addr_with(resolver).await?;
+            Some(ExternalAddr { ip, mapped_ports: Vec::new() })
+        }
+    }
+}
+
 type ResolveFut = Pin<Box<dyn Future<Output = Option<IpAddr>> + Send>>;
 
+type ResolveAddrFut = Pin<Box<dyn Future<Output = Option<ExternalAddr>> + Send>>;
+
 pin_project! {
     /// A future that resolves the first ip via all configured resolvers
     struct ResolveAny {
@@ -244,6 +425,88 @@ async fn resolve_external_ip_upnp() -> Option<IpAddr> {
         .ok()
 }
 
+/// Maps the ports via the UPnP gateway, the external ports are the same as the local ports.
+async fn resolve_with_port_mappings_upnp(
+    mappings: &[PortMapping],
+    lifetime: Duration,
+) -> Option<ExternalAddr> {
+    let gateway = search_gateway(Default::default())
+        .await
+        .map_err(|err| {
+            debug!(target: "net::nat", ?err, "failed to find upnp gateway");
+            err
+        })
+        .ok()?;
+    let ip = gateway
+        .get_external_ip()
+        .await
+        .map_err(|err| {
+            warn!(target: "net::nat", ?err, "failed to resolve external ip via upnp gateway");
+            err
+        })
+        .ok()?;
+
+    // the local address of the interface that routes to the gateway
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await.ok()?;
+    socket.connect(gateway.addr).await.ok()?;
+    let local_ip = socket.local_addr().ok()?.ip();
+
+    let lease_duration = lifetime.as_secs().try_into().unwrap_or(u32::MAX);
+    let mut mapped_ports = Vec::with_capacity(mappings.len());
+    for mapping in mappings {
+        let protocol = match mapping.protocol {
+            PortMappingProtocol::Tcp => igd::PortMappingProtocol::TCP,
+            PortMappingProtocol::Udp => igd::PortMappingProtocol::UDP,
+        };
+        let local_addr = SocketAddr::new(local_ip, mapping.port);
+        match gateway.add_port(protocol, mapping.port, local_addr, lease_duration, "reth").await {
+            Ok(()) => mapped_ports.push(MappedPort {
+                protocol: mapping.protocol,
+                internal_port: mapping.port,
+                external_port: mapping.port,
+                lifetime,
+            }),
+            Err(err) => {
+                warn!(target: "net::nat", ?err, ?mapping, "failed to map port via upnp gateway");
+            }
+        }
+    }
+
+    Some(ExternalAddr { ip, mapped_ports })
+}
+
+/// Returns the address of the NAT-PMP server of the gateway, or of the guessed default gateway.
+async fn pmp_server_addr(gateway: Option<Ipv4Addr>) -> Option<SocketAddr> {
+    let gateway = match gateway {
+        Some(gateway) => gateway,
+        None => pmp::guess_gateway().await?,
+    };
+    Some((gateway, pmp::PMP_SERVER_PORT).into())
+}
+
+async fn resolve_external_ip_pmp(gateway: Option<Ipv4Addr>) -> Option<IpAddr> {
+    let gateway = pmp_server_addr(gateway).await?;
+    let client = pmp::PmpClient::connect(gateway, pmp::random_nonce()).await.ok()?;
+    client
+        .external_ip()
+        .await
+        .map_err(|err| {
+            warn!(target: "net::nat", ?err, "failed to resolve external ip via NAT-PMP");
+            err
+        })
+        .ok()
+}
+
+async fn resolve_with_port_mappings_pmp(
+    gateway: Option<Ipv4Addr>,
+    mappings: &[PortMapping],
+    lifetime: Duration,
+    nonce: pmp::Nonce,
+) -> Option<ExternalAddr> {
+    let gateway = pmp_server_addr(gateway).await?;
+    pmp::resolve_with_port_mappings(gateway, mappings, lifetime, nonce).await
+}
+
 async fn resolve_external_ip() -> Option<IpAddr> {
     public_ip::addr().await
 }
@@ -251,7 +514,6 @@ async fn resolve_external_ip() -> Option<IpAddr> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::net::Ipv4Addr;
 
     #[tokio::test]
     #[ignore]
@@ -273,6 +535,32 @@ mod tests {
         dbg!(ip);
     }
 
+    #[tokio::test]
+    async fn renews_at_half_the_granted_lifetime() {
+        let mut interval =
+            ResolveNatInterval::interval(NatResolver::None, Duration::from_secs(300));
+        let addr = |lifetime| ExternalAddr {
+            ip: IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            mapped_ports: vec![MappedPort {
+                protocol: PortMappingProtocol::Udp,
+                internal_port: 30303,
+                external_port: 30303,
+                lifetime: Duration::from_secs(lifetime),
+            }],
+        };
+
+        interval.schedule_renewal(Some(&addr(120)));
+        let renewal = interval.renewal.as_ref().unwrap();
+        assert!(renewal.deadline() <= tokio::time::Instant::now() + Duration::from_secs(60));
+
+        // renewed by the next tick
+        interval.schedule_renewal(Some(&addr(600)));
+        assert!(interval.renewal.is_none());
+
+        interval.schedule_renewal(None);
+        assert!(interval.renewal.is_none());
+    }
+
     #[test]
     fn test_from_str() {
         assert_eq!(NatResolver::Any, "any".parse().unwrap());
@@ -282,5 +570,12 @@ mod tests {
         let s = "extip:0.0.0.0";
         assert_eq!(ip, s.parse().unwrap());
         assert_eq!(ip.to_string().as_str(), s);
+
+        assert_eq!(NatResolver::NatPmp(None), "natpmp".parse().unwrap());
+        let pmp = NatResolver::NatPmp(Some(Ipv4Addr::new(192, 168, 0, 1)));
+        let s = "natpmp:192.168.0.1";
+        assert_eq!(pmp, s.parse().unwrap());
+        assert_eq!(pmp, "pmp:192.168.0.1".parse().unwrap());
+        assert_eq!(pmp.to_string().as_str(), s);
     }
 }
@@ -0,0 +1,455 @@
+//! Port mappings via [PCP](https://www.rfc-editor.org/rfc/rfc6887) and its predecessor
+//! [NAT-PMP](https://www.rfc-editor.org/rfc/rfc6886).
+//!
+//! PCP is preferred, if the gateway only speaks NAT-PMP it answers PCP requests with an
+//! unsupported version error and the client falls back to NAT-PMP.
+
+use crate::{ExternalAddr, MappedPort, PortMapping, PortMappingProtocol};
+use std::{
+    collections::hash_map::RandomState,
+    hash::{BuildHasher, Hasher},
+    io,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    time::Duration,
+};
+use tokio::net::UdpSocket;
+use tracing::{debug, warn};
+
+/// The port NAT-PMP and PCP servers listen on.
+pub(crate) const PMP_SERVER_PORT: u16 = 5351;
+
+/// The version of NAT-PMP requests.
+const NAT_PMP_VERSION: u8 = 0;
+/// The version of PCP requests.
+const PCP_VERSION: u8 = 2;
+/// Opcode of the NAT-PMP external address request.
+const NAT_PMP_OPCODE_EXTERNAL_ADDRESS: u8 = 0;
+/// Opcode of the PCP map request.
+const PCP_OPCODE_MAP: u8 = 1;
+/// Set in the opcode of all responses.
+const RESPONSE_BIT: u8 = 0x80;
+/// Result code of successful requests.
+const RESULT_SUCCESS: u16 = 0;
+/// Result code of requests with a version the server does not support.
+const RESULT_UNSUPPORTED_VERSION: u16 = 1;
+
+/// Length of a PCP map request and response.
+const PCP_MAP_LEN: usize = 60;
+/// Length of a NAT-PMP map request.
+const NAT_PMP_MAP_REQUEST_LEN: usize = 12;
+/// Length of a NAT-PMP map response.
+const NAT_PMP_MAP_RESPONSE_LEN: usize = 16;
+/// Length of a NAT-PMP external address response.
+const NAT_PMP_EXTERNAL_ADDRESS_RESPONSE_LEN: usize = 12;
+
+/// How long to wait for the first response, doubled on every retransmission.
+const INITIAL_TIMEOUT: Duration = Duration::from_millis(250);
+/// How often a request is sent before giving up.
+const MAX_ATTEMPTS: usize = 4;
+
+/// The nonce that identifies the PCP mappings of a client.
+///
+/// Mappings can only be renewed with the nonce they were created with.
+pub(crate) type Nonce = [u8; 12];
+
+/// Returns a random [Nonce].
+pub(crate) fn random_nonce() -> Nonce {
+    let mut nonce = [0u8; 12];
+    nonce[..8].copy_from_slice(&RandomState::new().build_hasher().finish().to_be_bytes());
+    nonce[8..].copy_from_slice(&RandomState::new().build_hasher().finish().to_be_bytes()[..4]);
+    nonce
+}
+
+/// Errors that can occur when talking to a NAT-PMP or PCP server.
+#[derive(Debug, thiserror::Error)]
+pub(crate) enum PmpError {
+    /// Failed to send or receive a message.
+    #[error(transparent)]
+    Io(#[from] io::Error),
+    /// The gateway did not respond.
+    #[error("no response from the gateway")]
+    Timeout,
+    /// The gateway does not support the version of the request.
+    #[error("the gateway does not support the protocol version")]
+    UnsupportedVersion,
+    /// The gateway rejected the request.
+    #[error("the gateway rejected the request with result code {0}")]
+    Rejected(u16),
+    /// The response does not match the request.
+    #[error("invalid response from the gateway")]
+    InvalidResponse,
+}
+
+/// A client of the PCP or NAT-PMP server of a gateway.
+pub(crate) struct PmpClient {
+    /// The socket connected to the server.
+    socket: UdpSocket,
+    /// The local address of the socket, the mappings are created for this address.
+    local_ip: IpAddr,
+    /// Identifies the PCP mappings of this client.
+    nonce: Nonce,
+    /// Whether the server only speaks NAT-PMP.
+    nat_pmp_only: bool,
+}
+
+// === impl PmpClient ===
+
+impl PmpClient {
+    /// Creates a client of the server at the given address.
+    pub(crate) async fn connect(gateway: SocketAddr, nonce: Nonce) -> io::Result<Self> {
+        let local_addr: SocketAddr = if gateway.is_ipv4() {
+            (Ipv4Addr::UNSPECIFIED, 0).into()
+        } else {
+            (Ipv6Addr::UNSPECIFIED, 0).into()
+        };
+        let socket = UdpSocket::bind(local_addr).await?;
+        socket.connect(gateway).await?;
+        let local_ip = socket.local_addr()?.ip();
+        Ok(Self { socket, local_ip, nonce, nat_pmp_only: false })
+    }
+
+    /// Sends the request and waits for the response, the request is retransmitted with an
+    /// exponential backoff.
+    async fn request(&self, request: &[u8], buf: &mut [u8]) -> Result<usize, PmpError> {
+        let mut timeout = INITIAL_TIMEOUT;
+        for _ in 0..MAX_ATTEMPTS {
+            self.socket.send(request).await?;
+            match tokio::time::timeout(timeout, self.socket.recv(buf)).await {
+                Ok(len) => return Ok(len?),
+                Err(_) => timeout *= 2,
+            }
+        }
+        Err(PmpError::Timeout)
+    }
+
+    /// Maps the port on the gateway for the given lifetime.
+    ///
+    /// Returns the mapped port with the lifetime granted by the gateway and, if the gateway speaks
+    /// PCP, the external IP.
+    pub(crate) async fn map_port(
+        &mut self,
+        mapping: PortMapping,
+        lifetime: Duration,
+    ) -> Result<(MappedPort, Option<IpAddr>), PmpError> {
+        if !self.nat_pmp_only {
+            match self.map_port_pcp(mapping, lifetime).await {
+                Err(PmpError::UnsupportedVersion) => {
+                    debug!(target: "net::nat", "gateway does not support PCP, falling back to NAT-PMP");
+                    self.nat_pmp_only = true;
+                }
+                res => return res.map(|(port, ip)| (port, Some(ip))),
+            }
+        }
+        self.map_port_nat_pmp(mapping, lifetime).await.map(|port| (port, None))
+    }
+
+    /// Maps the port via a PCP map request.
+    async fn map_port_pcp(
+        &self,
+        mapping: PortMapping,
+        lifetime: Duration,
+    ) -> Result<(MappedPort, IpAddr), PmpError> {
+        let mut request = [0u8; PCP_MAP_LEN];
+        request[0] = PCP_VERSION;
+        request[1] = PCP_OPCODE_MAP;
+        request[4..8].copy_from_slice(&lifetime_secs(lifetime).to_be_bytes());
+        request[8..24].copy_from_slice(&to_ipv6(self.local_ip).octets());
+        request[24..36].copy_from_slice(&self.nonce);
+        request[36] = match mapping.protocol {
+            PortMappingProtocol::Tcp => 6,
+            PortMappingProtocol::Udp => 17,
+        };
+        request[40..42].copy_from_slice(&mapping.port.to_be_bytes());
+        // suggest the internal port as external port
+        request[42..44].copy_from_slice(&mapping.port.to_be_bytes());
+        // suggest any external address of the same family
+        let any = match self.local_ip {
+            IpAddr::V4(_) => Ipv4Addr::UNSPECIFIED.to_ipv6_mapped(),
+            IpAddr::V6(_) => Ipv6Addr::UNSPECIFIED,
+        };
+        request[44..60].copy_from_slice(&any.octets());
+
+        let mut buf = [0u8; 1100];
+        let len = self.request(&request, &mut buf).await?;
+        let response = &buf[..len];
+
+        // a NAT-PMP server responds with its own version
+        if response.len() < 4 || response[0] != PCP_VERSION {
+            return Err(PmpError::UnsupportedVersion)
+        }
+        if response[1] != RESPONSE_BIT | PCP_OPCODE_MAP {
+            return Err(PmpError::InvalidResponse)
+        }
+        match response[3] as u16 {
+            RESULT_SUCCESS => {}
+            RESULT_UNSUPPORTED_VERSION => return Err(PmpError::UnsupportedVersion),
+            code => return Err(PmpError::Rejected(code)),
+        }
+        if response.len() < PCP_MAP_LEN ||
+            response[24..36] != self.nonce ||
+            response[36] != request[36] ||
+            response[40..42] != request[40..42]
+        {
+            return Err(PmpError::InvalidResponse)
+        }
+
+        let lifetime = granted_lifetime(&response[4..8]);
+        let external_port = u16::from_be_bytes([response[42], response[43]]);
+        let mut external_ip = [0u8; 16];
+        external_ip.copy_from_slice(&response[44..60]);
+        let external_ip = Ipv6Addr::from(external_ip);
+        let external_ip =
+            external_ip.to_ipv4_mapped().map(IpAddr::V4).unwrap_or(IpAddr::V6(external_ip));
+
+        Ok((
+            MappedPort {
+                protocol: mapping.protocol,
+                internal_port: mapping.port,
+                external_port,
+                lifetime,
+            },
+            external_ip,
+        ))
+    }
+
+    /// Maps the port via a NAT-PMP map request.
+    async fn map_port_nat_pmp(
+        &self,
+        mapping: PortMapping,
+        lifetime: Duration,
+    ) -> Result<MappedPort, PmpError> {
+        let opcode = match mapping.protocol {
+            PortMappingProtocol::Udp => 1,
+            PortMappingProtocol::Tcp => 2,
+        };
+        let mut request = [0u8; NAT_PMP_MAP_REQUEST_LEN];
+        request[0] = NAT_PMP_VERSION;
+        request[1] = opcode;
+        request[4..6].copy_from_slice(&mapping.port.to_be_bytes());
+        request[6..8].copy_from_slice(&mapping.port.to_be_bytes());
+        request[8..12].copy_from_slice(&lifetime_secs(lifetime).to_be_bytes());
+
+        let mut buf = [0u8; NAT_PMP_MAP_RESPONSE_LEN];
+        let len = self.request(&request, &mut buf).await?;
+        let response = check_nat_pmp_response(&buf[..len], opcode, NAT_PMP_MAP_RESPONSE_LEN)?;
+        if response[8..10] != request[4..6] {
+            return Err(PmpError::InvalidResponse)
+        }
+
+        let external_port = u16::from_be_bytes([response[10], response[11]]);
+        let lifetime = granted_lifetime(&response[12..16]);
+        Ok(MappedPort {
+            protocol: mapping.protocol,
+            internal_port: mapping.port,
+            external_port,
+            lifetime,
+        })
+    }
+
+    /// Requests the external IP via NAT-PMP.
+    pub(crate) async fn external_ip(&self) -> Result<IpAddr, PmpError> {
+        let request = [NAT_PMP_VERSION, NAT_PMP_OPCODE_EXTERNAL_ADDRESS];
+        let mut buf = [0u8; NAT_PMP_EXTERNAL_ADDRESS_RESPONSE_LEN];
+        let len = self.request(&request, &mut buf).await?;
+        let response = check_nat_pmp_response(
+            &buf[..len],
+            NAT_PMP_OPCODE_EXTERNAL_ADDRESS,
+            NAT_PMP_EXTERNAL_ADDRESS_RESPONSE_LEN,
+        )?;
+        Ok(Ipv4Addr::new(response[8], response[9], response[10], response[11]).into())
+    }
+}
+
+/// Checks the header of a NAT-PMP response to a request with the given opcode.
+fn check_nat_pmp_response(response: &[u8], opcode: u8, len: usize) -> Result<&[u8], PmpError> {
+    if response.len() < 4 || response[0] != NAT_PMP_VERSION {
+        return Err(PmpError::UnsupportedVersion)
+    }
+    if response[1] != RESPONSE_BIT | opcode {
+        return Err(PmpError::InvalidResponse)
+    }
+    match u16::from_be_bytes([response[2], response[3]]) {
+        RESULT_SUCCESS => {}
+        RESULT_UNSUPPORTED_VERSION => return Err(PmpError::UnsupportedVersion),
+        code => return Err(PmpError::Rejected(code)),
+    }
+    if response.len() < len {
+        return Err(PmpError::InvalidResponse)
+    }
+    Ok(response)
+}
+
+/// Returns the lifetime in seconds as it's sent in requests.
+fn lifetime_secs(lifetime: Duration) -> u32 {
+    lifetime.as_secs().try_into().unwrap_or(u32::MAX)
+}
+
+/// Returns the lifetime the gateway granted in a map response.
+fn granted_lifetime(secs: &[u8]) -> Duration {
+    Duration::from_secs(u32::from_be_bytes([secs[0], secs[1], secs[2], secs[3]]) as u64)
+}
+
+/// Returns the IP as it's encoded in PCP messages.
+fn to_ipv6(ip: IpAddr) -> Ipv6Addr {
+    match ip {
+        IpAddr::V4(ip) => ip.to_ipv6_mapped(),
+        IpAddr::V6(ip) => ip,
+    }
+}
+
+/// Maps the ports via the PCP or NAT-PMP server at `gateway` and resolves the external IP.
+pub(crate) async fn resolve_with_port_mappings(
+    gateway: SocketAddr,
+    mappings: &[PortMapping],
+    lifetime: Duration,
+    nonce: Nonce,
+) -> Option<ExternalAddr> {
+    let mut client = PmpClient::connect(gateway, nonce)
+        .await
+        .map_err(|err| {
+            warn!(target: "net::nat", ?err, ?gateway, "failed to connect to NAT-PMP gateway");
+            err
+        })
+        .ok()?;
+
+    let mut ip = None;
+    let mut mapped_ports = Vec::with_capacity(mappings.len());
+    for mapping in mappings {
+        match client.map_port(*mapping, lifetime).await {
+            Ok((port, external_ip)) => {
+                ip = ip.or(external_ip);
+                mapped_ports.push(port);
+            }
+            Err(PmpError::Timeout) => {
+                debug!(target: "net::nat", ?gateway, "no response from NAT-PMP gateway");
+                return None
+            }
+            Err(err) => {
+                warn!(target: "net::nat", ?err, ?mapping, "failed to map port via NAT-PMP/PCP");
+            }
+        }
+    }
+
+    let ip = match ip {
+        Some(ip) => ip,
+        None => client
+            .external_ip()
+            .await
+            .map_err(|err| {
+                warn!(target: "net::nat", ?err, "failed to resolve external ip via NAT-PMP");
+                err
+            })
+            .ok()?,
+    };
+
+    Some(ExternalAddr { ip, mapped_ports })
+}
+
+/// Returns the likely address of the default gateway, which is assumed to be the first address of
+/// the local /24 network.
+pub(crate) async fn guess_gateway() -> Option<Ipv4Addr> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0)).await.ok()?;
+    // this sends no packets, it only selects the local address of the default route
+    socket.connect((Ipv4Addr::new(1, 1, 1, 1), 80)).await.ok()?;
+    match socket.local_addr().ok()?.ip() {
+        IpAddr::V4(ip) if ip.is_private() => {
+            let [a, b, c, _] = ip.octets();
+            Some(Ipv4Addr::new(a, b, c, 1))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXTERNAL_IP: Ipv4Addr = Ipv4Addr::new(203, 0, 113, 7);
+    /// The lifetime the gateway grants, shorter than the requested one.
+    const GRANTED_LIFETIME: u32 = 120;
+
+    /// Spawns a gateway that answers PCP requests, or NAT-PMP requests if `nat_pmp_only` is set.
+    ///
+    /// Ports are mapped to the internal port plus 1000 for [GRANTED_LIFETIME] seconds.
+    async fn spawn_gateway(nat_pmp_only: bool) -> SocketAddr {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = socket.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut buf = [0u8; 1100];
+            loop {
+                let (len, from) = socket.recv_from(&mut buf).await.unwrap();
+                let request = &buf[..len];
+                let response = match (request[0], nat_pmp_only) {
+                    (PCP_VERSION, false) => {
+                        let mut response = request.to_vec();
+                        response[1] |= RESPONSE_BIT;
+                        response[2..4].copy_from_slice(&[0, 0]);
+                        response[4..8].copy_from_slice(&GRANTED_LIFETIME.to_be_bytes());
+                        response[8..24].copy_from_slice(&[0; 16]);
+                        let port = u16::from_be_bytes([request[40], request[41]]) + 1000;
+                        response[42..44].copy_from_slice(&port.to_be_bytes());
+                        response[44..60].copy_from_slice(&EXTERNAL_IP.to_ipv6_mapped().octets());
+                        response
+                    }
+                    (PCP_VERSION, true) => {
+                        vec![NAT_PMP_VERSION, RESPONSE_BIT | request[1], 0, 1, 0, 0, 0, 0]
+                    }
+                    (_, _) if request[1] == NAT_PMP_OPCODE_EXTERNAL_ADDRESS => {
+                        let mut response = vec![NAT_PMP_VERSION, RESPONSE_BIT, 0, 0, 0, 0, 0, 0];
+                        response.extend_from_slice(&EXTERNAL_IP.octets());
+                        response
+                    }
+                    (_, _) => {
+                        let mut response = vec![NAT_PMP_VERSION, RESPONSE_BIT | request[1], 0, 0];
+                        response.extend_from_slice(&[0, 0, 0, 0]);
+                        response.extend_from_slice(&request[4..6]);
+                        let port = u16::from_be_bytes([request[4], request[5]]) + 1000;
+                        response.extend_from_slice(&port.to_be_bytes());
+                        response.extend_from_slice(&GRANTED_LIFETIME.to_be_bytes());
+                        response
+                    }
+                };
+                socket.send_to(&response, from).await.unwrap();
+            }
+        });
+        addr
+    }
+
+    async fn map_ports(gateway: SocketAddr) -> ExternalAddr {
+        let mappings = [PortMapping::tcp(30303), PortMapping::udp(30304)];
+        resolve_with_port_mappings(gateway, &mappings, Duration::from_secs(600), random_nonce())
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn map_ports_pcp() {
+        let gateway = spawn_gateway(false).await;
+        let addr = map_ports(gateway).await;
+        assert_eq!(addr.ip, IpAddr::V4(EXTERNAL_IP));
+        assert_eq!(addr.external_port(PortMappingProtocol::Tcp), Some(31303));
+        assert_eq!(addr.external_port(PortMappingProtocol::Udp), Some(31304));
+        assert_eq!(addr.lifetime(), Some(Duration::from_secs(GRANTED_LIFETIME as u64)));
+    }
+
+    #[tokio::test]
+    async fn map_ports_nat_pmp_fallback() {
+        let gateway = spawn_gateway(true).await;
+        let addr = map_ports(gateway).await;
+        assert_eq!(addr.ip, IpAddr::V4(EXTERNAL_IP));
+        assert_eq!(addr.external_port(PortMappingProtocol::Tcp), Some(31303));
+        assert_eq!(addr.external_port(PortMappingProtocol::Udp), Some(31304));
+        assert_eq!(addr.lifetime(), Some(Duration::from_secs(GRANTED_LIFETIME as u64)));
+    }
+
+    #[tokio::test]
+    async fn no_gateway() {
+        // nothing listens on the socket of the dropped gateway
+        let gateway = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let addr = gateway.local_addr().unwrap();
+        drop(gateway);
+
+        let client = PmpClient::connect(addr, random_nonce()).await.unwrap();
+        assert!(client.external_ip().await.is_err());
+    }
+}
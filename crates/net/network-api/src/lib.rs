@@ -83,6 +83,7 @@ pub trait Peers: PeersInfo {
 
 /// Represents the kind of peer
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub enum PeerKind {
     /// Basic peer kind.
     #[default]
@@ -20,6 +20,8 @@ pub enum ReputationChangeKind {
     FailedToConnect,
     /// Connection dropped by peer.
     Dropped,
+    /// Peer sent more requests than it is allowed to.
+    Spam,
     /// Apply a reputation change by value
     Other(Reputation),
 }
@@ -1,9 +1,13 @@
 //! Builder support for configuring the entire setup.
 
 use crate::{
-    eth_requests::EthRequestHandler, transactions::TransactionsManager, NetworkHandle,
-    NetworkManager,
+    eth_requests::EthRequestHandler,
+    protocol::{ProtocolHandler, RlpxSubProtocol},
+    snap_requests::SnapRequestHandler,
+    transactions::TransactionsManager,
+    NetworkHandle, NetworkManager,
 };
+use reth_eth_wire::{capability::Protocol, SnapMessage};
 use reth_transaction_pool::TransactionPool;
 use tokio::sync::mpsc;
 
@@ -43,6 +47,32 @@ impl<C, Tx, Eth> NetworkBuilder<C, Tx, Eth> {
         NetworkBuilder { network, request_handler, transactions }
     }
 
+    /// Registers a custom RLPx subprotocol with the network.
+    ///
+    /// See also [`NetworkConfigBuilder::add_protocol`](crate::NetworkConfigBuilder::add_protocol).
+    pub fn add_protocol(mut self, protocol: Protocol, handler: impl ProtocolHandler) -> Self {
+        self.network.add_rlpx_sub_protocol(RlpxSubProtocol::new(protocol, handler));
+        self
+    }
+
+    /// Creates a new [`SnapRequestHandler`] and registers the `snap` protocol it serves with the
+    /// network.
+    ///
+    /// Peers that exceed the request limit of the handler are reported to the network, and
+    /// requests are only served within the upload limit of the network, if one is configured.
+    pub fn snap_request_handler<Client>(
+        self,
+        client: Client,
+    ) -> (Self, SnapRequestHandler<Client>) {
+        let (snap_requests, handler) = SnapRequestHandler::new(client);
+        let mut snap_requests =
+            snap_requests.with_peers(self.network.handle().peers_handle().clone());
+        if let Some(limit) = self.network.upload_limit() {
+            snap_requests = snap_requests.with_upload_limit(limit);
+        }
+        (self.add_protocol(SnapMessage::protocol(), handler), snap_requests)
+    }
+
     /// Creates a new [`EthRequestHandler`] and wires it to the network.
     pub fn request_handler<Client>(
         self,
@@ -52,7 +82,10 @@ impl<C, Tx, Eth> NetworkBuilder<C, Tx, Eth> {
         let (tx, rx) = mpsc::unbounded_channel();
         network.set_eth_request_handler(tx);
         let peers = network.handle().peers_handle().clone();
-        let request_handler = EthRequestHandler::new(client, peers, rx);
+        let mut request_handler = EthRequestHandler::new(client, peers, rx);
+        if let Some(limit) = network.upload_limit() {
+            request_handler = request_handler.with_upload_limit(limit);
+        }
         NetworkBuilder { network, request_handler, transactions }
     }
 }
@@ -4,10 +4,12 @@ use crate::{
     error::NetworkError,
     import::{BlockImport, ProofOfStakeBlockImport},
     peers::PeersConfig,
-    session::SessionsConfig,
+    protocol::{ProtocolHandler, RlpxSubProtocol},
+    session::{BandwidthLimits, SessionsConfig},
     NetworkHandle, NetworkManager,
 };
 use reth_discv4::{Discv4Config, Discv4ConfigBuilder, DEFAULT_DISCOVERY_PORT};
+use reth_discv5::{Discv5Config, Discv5ConfigBuilder, DEFAULT_DISCOVERY_V5_PORT};
 use reth_primitives::{ChainSpec, ForkFilter, Head, NodeRecord, PeerId, MAINNET};
 use reth_provider::{BlockProvider, HeaderProvider};
 use reth_tasks::{TaskSpawner, TokioTaskExecutor};
@@ -26,7 +28,7 @@ mod __reexport {
 pub use __reexport::*;
 use reth_dns_discovery::DnsDiscoveryConfig;
 use reth_ecies::util::pk2id;
-use reth_eth_wire::{HelloMessage, Status};
+use reth_eth_wire::{capability::Protocol, HelloMessage, Status};
 
 /// Convenience function to create a new random [`SecretKey`]
 pub fn rng_secret_key() -> SecretKey {
@@ -47,6 +49,10 @@ pub struct NetworkConfig<C> {
     pub discovery_v4_config: Option<Discv4Config>,
     /// Address to use for discovery
     pub discovery_addr: SocketAddr,
+    /// How to set up discovery over discv5, disabled if `None`.
+    pub discovery_v5_config: Option<Discv5Config>,
+    /// Address to use for discv5
+    pub discovery_v5_addr: SocketAddr,
     /// Address to listen for incoming connections
     pub listener_addr: SocketAddr,
     /// How to instantiate peer manager.
@@ -72,6 +78,8 @@ pub struct NetworkConfig<C> {
     pub status: Status,
     /// Sets the hello message for the p2p handshake in RLPx
     pub hello_message: HelloMessage,
+    /// Custom RLPx subprotocols, their capabilities are added to the hello message.
+    pub protocols: Vec<RlpxSubProtocol>,
 }
 
 // === impl NetworkConfig ===
@@ -132,6 +140,11 @@ pub struct NetworkConfigBuilder {
     boot_nodes: HashSet<NodeRecord>,
     /// Address to use for discovery
     discovery_addr: Option<SocketAddr>,
+    /// How to set up discv5, disabled by default.
+    #[serde(skip)]
+    discovery_v5_builder: Option<Discv5ConfigBuilder>,
+    /// Address to use for discv5
+    discovery_v5_addr: Option<SocketAddr>,
     /// Listener for incoming connections
     listener_addr: Option<SocketAddr>,
     /// How to instantiate peer manager.
@@ -149,6 +162,9 @@ pub struct NetworkConfigBuilder {
     hello_message: Option<HelloMessage>,
     /// Head used to start set for the fork filter and status.
     head: Option<Head>,
+    /// Custom RLPx subprotocols.
+    #[serde(skip)]
+    protocols: Vec<RlpxSubProtocol>,
 }
 
 // === impl NetworkConfigBuilder ===
@@ -162,6 +178,8 @@ impl NetworkConfigBuilder {
             discovery_v4_builder: Some(Default::default()),
             boot_nodes: Default::default(),
             discovery_addr: None,
+            discovery_v5_builder: None,
+            discovery_v5_addr: None,
             listener_addr: None,
             peers_config: None,
             sessions_config: None,
@@ -170,6 +188,7 @@ impl NetworkConfigBuilder {
             executor: None,
             hello_message: None,
             head: None,
+            protocols: Vec::new(),
         }
     }
 
@@ -231,6 +250,13 @@ impl NetworkConfigBuilder {
         self
     }
 
+    /// Sets the upload and download limits of the sessions.
+    pub fn bandwidth_limits(mut self, limits: BandwidthLimits) -> Self {
+        self.sessions_config =
+            Some(self.sessions_config.unwrap_or_default().with_bandwidth_limits(limits));
+        self
+    }
+
     /// Sets the socket address the network will listen on
     pub fn listener_addr(mut self, listener_addr: SocketAddr) -> Self {
         self.listener_addr = Some(listener_addr);
@@ -249,6 +275,21 @@ impl NetworkConfigBuilder {
         self
     }
 
+    /// Enables discv5 discovery with the given config.
+    ///
+    /// Discv5 runs next to discv4 and listens on the [discv5
+    /// address](Self::discovery_v5_addr).
+    pub fn discovery_v5(mut self, builder: Discv5ConfigBuilder) -> Self {
+        self.discovery_v5_builder = Some(builder);
+        self
+    }
+
+    /// Sets the socket address discv5 will listen on
+    pub fn discovery_v5_addr(mut self, discovery_v5_addr: SocketAddr) -> Self {
+        self.discovery_v5_addr = Some(discovery_v5_addr);
+        self
+    }
+
     /// Disables Discv4 discovery.
     pub fn no_discv4_discovery(mut self) -> Self {
         self.discovery_v4_builder = None;
@@ -285,6 +326,21 @@ impl NetworkConfigBuilder {
         self
     }
 
+    /// Disable the Discv5 discovery.
+    pub fn disable_discv5_discovery(mut self) -> Self {
+        self.discovery_v5_builder = None;
+        self
+    }
+
+    /// Registers a custom RLPx subprotocol.
+    ///
+    /// The capability is advertised in the hello message and the handler receives a connection
+    /// for every session that negotiates it.
+    pub fn add_protocol(mut self, protocol: Protocol, handler: impl ProtocolHandler) -> Self {
+        self.protocols.push(RlpxSubProtocol::new(protocol, handler));
+        self
+    }
+
     /// Consumes the type and creates the actual [`NetworkConfig`]
     /// for the given client type that can interact with the chain.
     pub fn build<C>(self, client: C) -> NetworkConfig<C> {
@@ -295,6 +351,8 @@ impl NetworkConfigBuilder {
             discovery_v4_builder,
             boot_nodes,
             discovery_addr,
+            discovery_v5_builder,
+            discovery_v5_addr,
             listener_addr,
             peers_config,
             sessions_config,
@@ -303,6 +361,7 @@ impl NetworkConfigBuilder {
             executor,
             hello_message,
             head,
+            protocols,
         } = self;
 
         let listener_addr = listener_addr.unwrap_or_else(|| {
@@ -347,6 +406,10 @@ impl NetworkConfigBuilder {
             discovery_addr: discovery_addr.unwrap_or_else(|| {
                 SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, DEFAULT_DISCOVERY_PORT))
             }),
+            discovery_v5_config: discovery_v5_builder.map(|builder| builder.build()),
+            discovery_v5_addr: discovery_v5_addr.unwrap_or_else(|| {
+                SocketAddr::V4(SocketAddrV4::new(Ipv4Addr::UNSPECIFIED, DEFAULT_DISCOVERY_V5_PORT))
+            }),
             listener_addr,
             peers_config: peers_config.unwrap_or_default(),
             sessions_config: sessions_config.unwrap_or_default(),
@@ -357,6 +420,7 @@ impl NetworkConfigBuilder {
             status,
             hello_message,
             fork_filter,
+            protocols,
         }
     }
 }
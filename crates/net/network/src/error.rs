@@ -1,6 +1,7 @@
 //! Possible errors when interacting with the network.
 
 use crate::session::PendingSessionHandshakeError;
+use reth_discv5::error::Discv5Error;
 use reth_dns_discovery::resolver::ResolveError;
 use reth_eth_wire::{
     errors::{EthHandshakeError, EthStreamError, P2PHandshakeError, P2PStreamError},
@@ -17,6 +18,9 @@ pub enum NetworkError {
     /// IO error when creating the discovery service
     #[error("Failed to launch discovery service: {0}")]
     Discovery(io::Error),
+    /// Error when creating the discv5 service
+    #[error(transparent)]
+    Discv5(#[from] Discv5Error),
     /// Error when setting up the DNS resolver failed
     ///
     /// See also [DnsResolver](reth_dns_discovery::DnsResolver::from_system_conf)
@@ -103,6 +107,7 @@ impl SessionError for EthStreamError {
                             DisconnectReason::ProtocolBreach
                         )) |
                         P2PStreamError::UnknownReservedMessageId(_) |
+                        P2PStreamError::UnknownMessageId(_) |
                         P2PStreamError::EmptyProtocolMessage |
                         P2PStreamError::ParseVersionError(_) |
                         P2PStreamError::Disconnected(DisconnectReason::UselessPeer) |
@@ -148,6 +153,7 @@ impl SessionError for EthStreamError {
             // malformed messages
             EthStreamError::P2PStreamError(P2PStreamError::Rlp(_)) |
             EthStreamError::P2PStreamError(P2PStreamError::UnknownReservedMessageId(_)) |
+            EthStreamError::P2PStreamError(P2PStreamError::UnknownMessageId(_)) |
             EthStreamError::P2PStreamError(P2PStreamError::UnknownDisconnectReason(_)) |
             EthStreamError::P2PStreamError(P2PStreamError::MessageTooBig { .. }) |
             EthStreamError::P2PStreamError(P2PStreamError::EmptyProtocolMessage) |
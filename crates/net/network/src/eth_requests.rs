@@ -1,18 +1,20 @@
 //! Blocks/Headers management for the p2p network.
 
-use crate::peers::PeersHandle;
+use crate::{
+    peers::PeersHandle, request_limit::PeerRequestLimiter, session::gossip_upload_reserve,
+};
 use futures::StreamExt;
 use reth_eth_wire::{
     BlockBodies, BlockBody, BlockHeaders, GetBlockBodies, GetBlockHeaders, GetNodeData,
     GetReceipts, NodeData, Receipts,
 };
 use reth_interfaces::p2p::error::RequestResult;
+use reth_net_common::ratelimit::SharedRateLimit;
 use reth_primitives::{BlockHashOrNumber, Header, HeadersDirection, PeerId, U256};
 use reth_provider::{BlockProvider, HeaderProvider};
+use reth_rlp::Encodable;
 use std::{
-    borrow::Borrow,
     future::Future,
-    hash::Hash,
     pin::Pin,
     task::{Context, Poll},
 };
@@ -42,6 +44,11 @@ const SOFT_RESPONSE_LIMIT: usize = 2 * 1024 * 1024;
 /// Estimated size in bytes of an RLP encoded header.
 const APPROX_HEADER_SIZE: usize = 500;
 
+/// Maximum number of block receipts to serve.
+///
+/// Used to limit lookups.
+const MAX_RECEIPTS_SERVE: usize = 1024;
+
 /// Manages eth related requests on top of the p2p network.
 ///
 /// This can be spawned to another task and is supposed to be run as background service.
@@ -49,12 +56,14 @@ const APPROX_HEADER_SIZE: usize = 500;
 pub struct EthRequestHandler<C> {
     /// The client type that can interact with the chain.
     client: C,
-    /// Used for reporting peers.
-    #[allow(unused)]
-    // TODO use to report spammers
-    peers: PeersHandle,
     /// Incoming request from the [NetworkManager](crate::NetworkManager).
     incoming_requests: UnboundedReceiverStream<IncomingEthRequest>,
+    /// Limits the number of requests of every peer.
+    request_limiter: PeerRequestLimiter,
+    /// The upload limit of the network.
+    ///
+    /// Requests are only served while more than the budget reserved for gossip is left.
+    upload_limit: Option<SharedRateLimit>,
 }
 
 // === impl EthRequestHandler ===
@@ -65,7 +74,19 @@ impl<C> EthRequestHandler<C> {
         peers: PeersHandle,
         incoming: UnboundedReceiver<IncomingEthRequest>,
     ) -> Self {
-        Self { client, peers, incoming_requests: UnboundedReceiverStream::new(incoming) }
+        Self {
+            client,
+            incoming_requests: UnboundedReceiverStream::new(incoming),
+            request_limiter: PeerRequestLimiter::new(Some(peers)),
+            upload_limit: None,
+        }
+    }
+
+    /// Throttles serving requests by the given upload limit of the network, so that the responses
+    /// do not exhaust the budget reserved for gossip.
+    pub fn with_upload_limit(mut self, limit: SharedRateLimit) -> Self {
+        self.upload_limit = Some(limit);
+        self
     }
 }
 
@@ -141,20 +162,30 @@ where
 
     fn on_headers_request(
         &mut self,
-        _peer_id: PeerId,
+        peer_id: PeerId,
         request: GetBlockHeaders,
         response: oneshot::Sender<RequestResult<BlockHeaders>>,
     ) {
+        if self.request_limiter.is_spam(peer_id) {
+            let _ = response.send(Ok(BlockHeaders::default()));
+            return
+        }
+
         let headers = self.get_headers_response(request);
         let _ = response.send(Ok(BlockHeaders(headers)));
     }
 
     fn on_bodies_request(
         &mut self,
-        _peer_id: PeerId,
+        peer_id: PeerId,
         request: GetBlockBodies,
         response: oneshot::Sender<RequestResult<BlockBodies>>,
     ) {
+        if self.request_limiter.is_spam(peer_id) {
+            let _ = response.send(Ok(BlockBodies::default()));
+            return
+        }
+
         let mut bodies = Vec::new();
 
         let mut total_bytes = APPROX_BODY_SIZE;
@@ -185,6 +216,58 @@ where
 
         let _ = response.send(Ok(BlockBodies(bodies)));
     }
+
+    fn on_receipts_request(
+        &mut self,
+        peer_id: PeerId,
+        request: GetReceipts,
+        response: oneshot::Sender<RequestResult<Receipts>>,
+    ) {
+        if self.request_limiter.is_spam(peer_id) {
+            let _ = response.send(Ok(Receipts::default()));
+            return
+        }
+
+        let mut receipts = Vec::new();
+
+        let mut total_bytes = 0;
+
+        for hash in request.0 {
+            if let Some(block_receipts) =
+                self.client.receipts_by_block(hash.into()).unwrap_or_default()
+            {
+                total_bytes += block_receipts.length();
+
+                receipts.push(block_receipts);
+
+                if total_bytes > SOFT_RESPONSE_LIMIT {
+                    break
+                }
+
+                if receipts.len() >= MAX_RECEIPTS_SERVE {
+                    break
+                }
+            } else {
+                break
+            }
+        }
+
+        let _ = response.send(Ok(Receipts(receipts)));
+    }
+
+    /// Answers node data requests with an empty response, since the state is not stored by the
+    /// hashes of its trie nodes.
+    ///
+    /// Only `eth/66` peers can send this request, it was removed in `eth/67`.
+    fn on_node_data_request(
+        &mut self,
+        peer_id: PeerId,
+        _request: GetNodeData,
+        response: oneshot::Sender<RequestResult<NodeData>>,
+    ) {
+        self.request_limiter.is_spam(peer_id);
+        let _ = response.send(Ok(NodeData::default()));
+    }
 }
 
 /// An endless future.
@@ -200,6 +283,13 @@ where
         let this = self.get_mut();
 
         loop {
+            if let Some(limit) = this.upload_limit.as_mut() {
+                let reserve = gossip_upload_reserve(limit);
+                if limit.poll_ready_with_reserve(cx, reserve).is_pending() {
+                    return Poll::Pending
+                }
+            }
+
             match this.incoming_requests.poll_next_unpin(cx) {
                 Poll::Pending => return Poll::Pending,
                 Poll::Ready(None) => return Poll::Ready(()),
@@ -210,30 +300,18 @@ where
                     IncomingEthRequest::GetBlockBodies { peer_id, request, response } => {
                         this.on_bodies_request(peer_id, request, response)
                     }
-                    IncomingEthRequest::GetNodeData { .. } => {}
-                    IncomingEthRequest::GetReceipts { .. } => {}
+                    IncomingEthRequest::GetNodeData { peer_id, request, response } => {
+                        this.on_node_data_request(peer_id, request, response)
+                    }
+                    IncomingEthRequest::GetReceipts { peer_id, request, response } => {
+                        this.on_receipts_request(peer_id, request, response)
+                    }
                 },
             }
         }
     }
 }
 
-/// Represents a handled [`GetBlockHeaders`] requests
-///
-/// This is the key type for spam detection cache. The counter is ignored during `PartialEq` and
-/// `Hash`.
-#[derive(Debug, PartialEq, Hash)]
-#[allow(unused)]
-struct RespondedGetBlockHeaders {
-    req: (PeerId, GetBlockHeaders),
-}
-
-impl Borrow<(PeerId, GetBlockHeaders)> for RespondedGetBlockHeaders {
-    fn borrow(&self) -> &(PeerId, GetBlockHeaders) {
-        &self.req
-    }
-}
-
 /// All `eth` request related to blocks delegated by the network.
 #[derive(Debug)]
 #[allow(missing_docs)]
@@ -130,7 +130,10 @@ mod message;
 mod metrics;
 mod network;
 pub mod peers;
+pub mod protocol;
+mod request_limit;
 mod session;
+pub mod snap_requests;
 mod state;
 mod swarm;
 pub mod transactions;
@@ -142,6 +145,6 @@ pub use manager::{NetworkEvent, NetworkManager};
 pub use message::PeerRequest;
 pub use network::NetworkHandle;
 pub use peers::PeersConfig;
-pub use session::PeerInfo;
+pub use session::{BandwidthLimits, PeerInfo};
 
 pub use reth_eth_wire::DisconnectReason;
@@ -25,7 +25,8 @@ use crate::{
     message::{NewBlockMessage, PeerMessage, PeerRequest, PeerRequestSender},
     metrics::NetworkMetrics,
     network::{NetworkHandle, NetworkHandleMessage},
-    peers::{PeersHandle, PeersManager},
+    peers::{PeersHandle, PeersManager, PersistedPeer},
+    protocol::{ProtocolConnection, RlpxSubProtocol},
     session::SessionManager,
     state::NetworkState,
     swarm::{NetworkConnectionState, Swarm, SwarmEvent},
@@ -34,11 +35,12 @@ use crate::{
 };
 use futures::{Future, StreamExt};
 use parking_lot::Mutex;
+use reth_discv4::PortMapping;
 use reth_eth_wire::{
     capability::{Capabilities, CapabilityMessage},
     DisconnectReason, EthVersion, Status,
 };
-use reth_net_common::bandwidth_meter::BandwidthMeter;
+use reth_net_common::{bandwidth_meter::BandwidthMeter, ratelimit::SharedRateLimit};
 use reth_network_api::{EthProtocolInfo, NetworkStatus, ReputationChangeKind};
 use reth_primitives::{NodeRecord, PeerId, H256};
 use reth_provider::BlockProvider;
@@ -123,6 +125,15 @@ impl<C> NetworkManager<C> {
         self.to_eth_request_handler = Some(tx);
     }
 
+    /// Registers a custom RLPx subprotocol.
+    ///
+    /// The capability is advertised to all peers connected from now on and the handler receives
+    /// a connection for every session that negotiates it, as well as all [`NetworkEvent`]s.
+    pub fn add_rlpx_sub_protocol(&mut self, protocol: RlpxSubProtocol) {
+        self.swarm.sessions_mut().add_protocol(protocol.protocol.clone());
+        self.event_listeners.protocols.push(protocol);
+    }
+
     /// Returns the [`NetworkHandle`] that can be cloned and shared.
     ///
     /// The [`NetworkHandle`] can be used to interact with this [`NetworkManager`]
@@ -135,6 +146,11 @@ impl<C> NetworkManager<C> {
     pub fn bandwidth_meter(&self) -> &BandwidthMeter {
         self.handle.bandwidth_meter()
     }
+
+    /// Returns the upload limit shared by all sessions, if configured.
+    pub(crate) fn upload_limit(&self) -> Option<SharedRateLimit> {
+        self.swarm.sessions().upload_limit()
+    }
 }
 
 impl<C> NetworkManager<C>
@@ -151,6 +167,8 @@ where
             secret_key,
             mut discovery_v4_config,
             discovery_addr,
+            mut discovery_v5_config,
+            discovery_v5_addr,
             listener_addr,
             peers_config,
             sessions_config,
@@ -163,6 +181,7 @@ where
             status,
             fork_filter,
             dns_discovery_config,
+            protocols,
             ..
         } = config;
 
@@ -175,13 +194,27 @@ where
         discovery_v4_config = discovery_v4_config.map(|mut disc_config| {
             // merge configured boot nodes
             disc_config.bootstrap_nodes.extend(boot_nodes.clone());
+            disc_config.add_eip868_pair("eth", status.forkid);
+            if discovery_v5_config.is_some() {
+                // make discv5 reachable from outside the NAT as well
+                disc_config.add_port_mapping(PortMapping::udp(discovery_v5_addr.port()));
+            }
+            disc_config
+        });
+        discovery_v5_config = discovery_v5_config.map(|mut disc_config| {
             disc_config.add_eip868_pair("eth", status.forkid);
             disc_config
         });
 
-        let discovery =
-            Discovery::new(discovery_addr, secret_key, discovery_v4_config, dns_discovery_config)
-                .await?;
+        let discovery = Discovery::new(
+            discovery_addr,
+            secret_key,
+            discovery_v4_config,
+            discovery_v5_addr,
+            discovery_v5_config,
+            dns_discovery_config,
+        )
+        .await?;
         // need to retrieve the addr here since provided port could be `0`
         let local_peer_id = discovery.local_id();
 
@@ -221,7 +254,7 @@ where
             Arc::new(AtomicU64::new(chain_spec.chain.id())),
         );
 
-        Ok(Self {
+        let mut network = Self {
             swarm,
             handle,
             from_handle_rx: UnboundedReceiverStream::new(from_handle_rx),
@@ -231,7 +264,11 @@ where
             to_eth_request_handler: None,
             num_active_peers,
             metrics: Default::default(),
-        })
+        };
+        for protocol in protocols {
+            network.add_rlpx_sub_protocol(protocol);
+        }
+        Ok(network)
     }
 
     /// Create a new [`NetworkManager`] instance and start a [`NetworkBuilder`] to configure all
@@ -299,6 +336,13 @@ where
         self.swarm.state().peers().iter_peers()
     }
 
+    /// Returns the state of all peers in the peer set that should be persisted across restarts.
+    ///
+    /// See also [`PeersConfig::with_persisted_peers`](crate::PeersConfig::with_persisted_peers).
+    pub fn persisted_peers(&self) -> Vec<PersistedPeer> {
+        self.swarm.state().peers().persisted_peers()
+    }
+
     /// Returns a new [`PeersHandle`] that can be cloned and shared.
     ///
     /// The [`PeersHandle`] can be used to interact with the network's peer set.
@@ -636,6 +680,7 @@ where
                             messages,
                             status,
                             direction,
+                            protocols,
                         } => {
                             let total_active =
                                 this.num_active_peers.fetch_add(1, Ordering::Relaxed) + 1;
@@ -661,6 +706,7 @@ where
                                 status,
                                 messages,
                             });
+                            this.event_listeners.on_protocol_connections(peer_id, protocols);
                         }
                         SwarmEvent::PeerAdded(peer_id) => {
                             trace!(target: "net", ?peer_id, "Peer added");
@@ -859,6 +905,8 @@ pub enum NetworkEvent {
 struct NetworkEventListeners {
     /// All listeners for an event
     listeners: Vec<mpsc::UnboundedSender<NetworkEvent>>,
+    /// Custom RLPx subprotocols, their handlers receive all events.
+    protocols: Vec<RlpxSubProtocol>,
 }
 
 // === impl NetworkEventListeners ===
@@ -868,6 +916,9 @@ impl NetworkEventListeners {
     ///
     /// Remove channels that got closed.
     fn send(&mut self, event: NetworkEvent) {
+        for protocol in &self.protocols {
+            protocol.handler.on_network_event(&event);
+        }
         self.listeners.retain(|listener| {
             let open = listener.send(event.clone()).is_ok();
             if !open {
@@ -876,4 +927,25 @@ impl NetworkEventListeners {
             open
         });
     }
+
+    /// Hands the connections of the negotiated subprotocols of a new session to their handlers.
+    fn on_protocol_connections(&self, peer_id: PeerId, connections: Vec<ProtocolConnection>) {
+        for conn in connections {
+            let handler = self
+                .protocols
+                .iter()
+                .find(|protocol| protocol.capability().name == conn.capability().name());
+            match handler {
+                Some(protocol) => protocol.handler.on_connection(peer_id, conn),
+                None => {
+                    trace!(
+                        target : "net",
+                        ?peer_id,
+                        capability=?conn.capability(),
+                        "no handler for subprotocol"
+                    )
+                }
+            }
+        }
+    }
 }
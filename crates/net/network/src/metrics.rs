@@ -40,3 +40,37 @@ pub struct TransactionsManagerMetrics {
     /// Total number of propagated transactions
     pub(crate) propagated_transactions: Counter,
 }
+
+/// Metrics of the `eth` messages of a single type, labeled with the message type and whether the
+/// messages were received or sent
+#[derive(Metrics)]
+#[metrics(scope = "network.eth_messages")]
+pub struct EthMessageMetrics {
+    /// Total number of messages
+    pub(crate) messages: Counter,
+
+    /// Total number of bytes of the RLP encoded messages, before compression
+    pub(crate) bytes: Counter,
+}
+
+/// Bandwidth metrics of a single peer, labeled with the peer id
+#[derive(Metrics)]
+#[metrics(scope = "network.peer")]
+pub struct PeerBandwidthMetrics {
+    /// Total number of bytes received from the peer
+    pub(crate) ingress_bytes: Counter,
+
+    /// Total number of bytes sent to the peer
+    pub(crate) egress_bytes: Counter,
+}
+
+/// Bandwidth metrics of all peers
+#[derive(Metrics)]
+#[metrics(scope = "network")]
+pub struct BandwidthMetrics {
+    /// Total number of bytes received from all peers
+    pub(crate) ingress_bytes: Counter,
+
+    /// Total number of bytes sent to all peers
+    pub(crate) egress_bytes: Counter,
+}
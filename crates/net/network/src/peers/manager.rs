@@ -18,7 +18,7 @@ use std::{
     net::{IpAddr, SocketAddr},
     path::Path,
     task::{Context, Poll},
-    time::Duration,
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 use thiserror::Error;
 use tokio::{
@@ -26,7 +26,7 @@ use tokio::{
     time::{Instant, Interval},
 };
 use tokio_stream::wrappers::UnboundedReceiverStream;
-use tracing::{debug, info, trace};
+use tracing::{debug, info, trace, warn};
 
 /// A communication channel to the [`PeersManager`] to apply manual changes to the peer set.
 #[derive(Clone, Debug)]
@@ -115,12 +115,13 @@ impl PeersManager {
             refill_slots_interval,
             connection_info,
             reputation_weights,
-            ban_list,
+            mut ban_list,
             ban_duration,
             backoff_durations,
             trusted_nodes,
             connect_trusted_nodes_only,
             basic_nodes,
+            persisted_peers,
             ..
         } = config;
         let (manager_tx, handle_rx) = mpsc::unbounded_channel();
@@ -129,12 +130,35 @@ impl PeersManager {
         // We use half of the interval to decrease the max duration to `150%` in worst case
         let unban_interval = ban_duration.min(backoff_durations.low) / 2;
 
-        let mut peers = HashMap::with_capacity(trusted_nodes.len() + basic_nodes.len());
+        let mut peers =
+            HashMap::with_capacity(trusted_nodes.len() + persisted_peers.len() + basic_nodes.len());
 
         for NodeRecord { address, tcp_port, udp_port: _, id } in trusted_nodes {
             peers.entry(id).or_insert_with(|| Peer::trusted(SocketAddr::from((address, tcp_port))));
         }
 
+        for persisted in persisted_peers {
+            let id = persisted.record.id;
+            if let Some(until) = persisted.banned_until() {
+                // the ban may have been lifted in the meantime, in which case the peer is unbanned
+                // on the next tick of the unban interval
+                let remaining = until.duration_since(SystemTime::now()).unwrap_or_default();
+                ban_list.ban_peer_until(id, std::time::Instant::now() + remaining);
+            }
+            match peers.entry(id) {
+                Entry::Occupied(mut entry) => {
+                    // the peer is configured as trusted, so only the learned state is restored
+                    let peer = entry.get_mut();
+                    peer.reputation = persisted.reputation;
+                    peer.backoff_counter = persisted.backoff_counter;
+                    peer.last_seen = persisted.last_seen();
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(Peer::from_persisted(&persisted));
+                }
+            }
+        }
+
         for NodeRecord { address, tcp_port, udp_port: _, id } in basic_nodes {
             peers.entry(id).or_insert_with(|| Peer::new(SocketAddr::from((address, tcp_port))));
         }
@@ -174,6 +198,34 @@ impl PeersManager {
         self.peers.iter().map(|(peer_id, v)| NodeRecord::new(v.addr, *peer_id))
     }
 
+    /// Returns the state of all peers that should be persisted across restarts.
+    ///
+    /// Peers that are currently connected are considered to be seen just now.
+    pub(crate) fn persisted_peers(&self) -> Vec<PersistedPeer> {
+        let now = SystemTime::now();
+        let instant_now = std::time::Instant::now();
+        self.peers
+            .iter()
+            .map(|(peer_id, peer)| {
+                // peers that are banned indefinitely are part of the configured ban list
+                let banned_until = self
+                    .ban_list
+                    .peer_banned_until(peer_id)
+                    .flatten()
+                    .map(|until| now + until.saturating_duration_since(instant_now));
+                let last_seen = if peer.state.is_connected() { now } else { peer.last_seen };
+                PersistedPeer {
+                    record: NodeRecord::new(peer.addr, *peer_id),
+                    kind: peer.kind,
+                    reputation: peer.reputation,
+                    backoff_counter: peer.backoff_counter,
+                    banned_until: banned_until.map(unix_timestamp),
+                    last_seen: unix_timestamp(last_seen),
+                }
+            })
+            .collect()
+    }
+
     /// Invoked when a new _incoming_ tcp connection is accepted.
     ///
     /// returns an error if the inbound ip address is on the ban list or
@@ -256,6 +308,7 @@ impl PeersManager {
                     return
                 }
                 value.state = PeerConnectionState::In;
+                value.last_seen = SystemTime::now();
             }
             Entry::Vacant(entry) => {
                 entry.insert(Peer::with_state(addr, PeerConnectionState::In));
@@ -349,6 +402,7 @@ impl PeersManager {
                     // session to that peer
                     entry.get_mut().backoff_counter = 0;
                     entry.get_mut().state = PeerConnectionState::Idle;
+                    entry.get_mut().last_seen = SystemTime::now();
                     return
                 }
             }
@@ -502,6 +556,7 @@ impl PeersManager {
                 node.kind = kind;
                 node.addr = addr;
                 node.fork_id = fork_id;
+                node.last_seen = SystemTime::now();
                 return
             }
             Entry::Vacant(entry) => {
@@ -768,6 +823,8 @@ pub struct Peer {
     kind: PeerKind,
     /// Counts number of times the peer was backed off   
     backoff_counter: u32,
+    /// When the peer was last discovered or connected.
+    last_seen: SystemTime,
 }
 
 // === impl Peer ===
@@ -790,6 +847,7 @@ impl Peer {
             remove_after_disconnect: false,
             kind: Default::default(),
             backoff_counter: 0,
+            last_seen: SystemTime::now(),
         }
     }
 
@@ -797,6 +855,16 @@ impl Peer {
         Self { kind, ..Self::new(addr) }
     }
 
+    /// Restores a peer from its persisted state.
+    fn from_persisted(persisted: &PersistedPeer) -> Self {
+        Self {
+            reputation: persisted.reputation,
+            backoff_counter: persisted.backoff_counter,
+            last_seen: persisted.last_seen(),
+            ..Self::with_kind(persisted.record.tcp_addr(), persisted.kind)
+        }
+    }
+
     /// Applies a reputation change to the peer and returns what action should be taken.
     fn apply_reputation(&mut self, reputation: i32) -> ReputationChangeOutcome {
         let previous = self.reputation;
@@ -840,6 +908,43 @@ impl Peer {
     }
 }
 
+/// The state of a peer that is persisted across restarts, see [`PeersConfig::persisted_peers`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PersistedPeer {
+    /// The id of the peer and the address it was last seen at.
+    pub record: NodeRecord,
+    /// The kind of peer.
+    pub kind: PeerKind,
+    /// The reputation of the peer.
+    pub reputation: i32,
+    /// Number of times the peer was backed off.
+    pub backoff_counter: u32,
+    /// Unix timestamp in seconds until which the peer is banned or backed off, if at all.
+    pub banned_until: Option<u64>,
+    /// Unix timestamp in seconds when the peer was last discovered or connected.
+    pub last_seen: u64,
+}
+
+// === impl PersistedPeer ===
+
+impl PersistedPeer {
+    /// Returns when the peer was last discovered or connected.
+    pub fn last_seen(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(self.last_seen)
+    }
+
+    /// Returns until when the peer is banned or backed off, if at all.
+    pub fn banned_until(&self) -> Option<SystemTime> {
+        self.banned_until.map(|until| UNIX_EPOCH + Duration::from_secs(until))
+    }
+}
+
+/// Returns the unix timestamp in seconds of the given time.
+fn unix_timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_secs()
+}
+
 /// Outcomes when a reputation change is applied to a peer
 enum ReputationChangeOutcome {
     /// Nothing to do.
@@ -945,6 +1050,7 @@ pub enum PeerAction {
 /// Config type for initiating a [`PeersManager`] instance
 #[derive(Debug, Clone, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
 pub struct PeersConfig {
     /// How often to recheck free slots for outbound connections.
     #[cfg_attr(feature = "serde", serde(with = "humantime_serde"))]
@@ -956,6 +1062,12 @@ pub struct PeersConfig {
     /// Basic nodes to connect to.
     #[cfg_attr(feature = "serde", serde(skip))]
     pub basic_nodes: HashSet<NodeRecord>,
+    /// Peers and their state restored from a previous run, see [`PersistedPeer`].
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub persisted_peers: Vec<PersistedPeer>,
+    /// Persisted peers that were not seen for longer than this are evicted when they're loaded.
+    #[cfg_attr(feature = "serde", serde(with = "humantime_serde"))]
+    pub max_persisted_peer_age: Duration,
     /// How long to ban bad peers.
     #[cfg_attr(feature = "serde", serde(with = "humantime_serde"))]
     pub ban_duration: Duration,
@@ -984,6 +1096,9 @@ impl Default for PeersConfig {
             trusted_nodes: Default::default(),
             connect_trusted_nodes_only: false,
             basic_nodes: Default::default(),
+            persisted_peers: Default::default(),
+            // Forget peers that were not seen for 5 days
+            max_persisted_peer_age: Duration::from_secs(60 * 60 * 24 * 5),
         }
     }
 }
@@ -1052,6 +1167,59 @@ impl PeersConfig {
         let nodes: HashSet<NodeRecord> = serde_json::from_reader(reader)?;
         Ok(self.with_basic_nodes(nodes))
     }
+
+    /// Peers and their state restored from a previous run.
+    ///
+    /// Peers that were not seen within [`Self::max_persisted_peer_age`] and peers with a banned
+    /// reputation whose ban expired are evicted. Trusted peers are always kept.
+    pub fn with_persisted_peers(mut self, peers: Vec<PersistedPeer>) -> Self {
+        let now = SystemTime::now();
+        let min_last_seen = now.checked_sub(self.max_persisted_peer_age).unwrap_or(UNIX_EPOCH);
+        self.persisted_peers = peers
+            .into_iter()
+            .filter(|peer| {
+                if peer.kind == PeerKind::Trusted {
+                    return true
+                }
+                let is_banned = peer.banned_until().map_or(false, |until| until > now);
+                peer.last_seen() >= min_last_seen &&
+                    (is_banned || !is_banned_reputation(peer.reputation))
+            })
+            .collect();
+        self
+    }
+
+    /// Read from file the peers and their state saved by a previous run. Ignored if None.
+    ///
+    /// Files in the older format, which only lists the node records of the peers, are loaded as
+    /// nodes available at launch, see [`Self::with_basic_nodes`].
+    ///
+    /// See also [`Self::with_persisted_peers`].
+    pub fn with_persisted_peers_from_file(
+        self,
+        optional_file: Option<impl AsRef<Path>>,
+    ) -> Result<Self, io::Error> {
+        let Some(file_path) = optional_file else {
+            return Ok(self)
+        };
+        let content = match std::fs::read(file_path.as_ref()) {
+            Ok(content) => content,
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(self),
+            Err(e) => Err(e)?,
+        };
+        info!(target: "net::peers", file = %file_path.as_ref().display(), "Loading persisted peers");
+        match serde_json::from_slice::<Vec<PersistedPeer>>(&content) {
+            Ok(peers) => Ok(self.with_persisted_peers(peers)),
+            Err(err) => {
+                let nodes = match serde_json::from_slice::<HashSet<NodeRecord>>(&content) {
+                    Ok(nodes) => nodes,
+                    Err(_) => return Err(err.into()),
+                };
+                warn!(target: "net::peers", file = %file_path.as_ref().display(), "Loading peers without their state from a file in the old format");
+                Ok(self.with_basic_nodes(nodes))
+            }
+        }
+    }
 }
 
 /// The durations to use when a backoff should be applied to a peer.
@@ -1121,8 +1289,12 @@ mod test {
     use crate::{
         error::BackoffKind,
         peers::{
-            manager::{ConnectionInfo, PeerBackoffDurations, PeerConnectionState},
-            PeerAction,
+            manager::{
+                unix_timestamp, ConnectionInfo, PeerBackoffDurations, PeerConnectionState,
+                PersistedPeer,
+            },
+            reputation::BANNED_REPUTATION,
+            PeerAction, PeerKind,
         },
         session::PendingSessionHandshakeError,
         PeersConfig,
@@ -1860,4 +2032,94 @@ mod test {
         })
         .await;
     }
+
+    #[tokio::test]
+    async fn test_restore_persisted_peers() {
+        let peer = PeerId::random();
+        let banned_peer = PeerId::random();
+        let trusted_peer = PeerId::random();
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let mut peers = PeersManager::default();
+        peers.add_peer(peer, socket_addr, None);
+        peers.add_peer(banned_peer, socket_addr, None);
+        peers.add_trusted_peer(trusted_peer, socket_addr);
+
+        peers.apply_reputation_change(&peer, ReputationChangeKind::BadMessage);
+        peers.apply_reputation_change(&banned_peer, ReputationChangeKind::BadProtocol);
+        assert!(peers.ban_list.is_banned_peer(&banned_peer));
+
+        let persisted = peers.persisted_peers();
+        assert_eq!(persisted.len(), 3);
+
+        let restored = PeersManager::new(PeersConfig::default().with_persisted_peers(persisted));
+        assert_eq!(restored.num_known_peers(), 3);
+
+        let p = restored.peers.get(&peer).unwrap();
+        assert_eq!(p.reputation, peers.peers.get(&peer).unwrap().reputation);
+        assert_eq!(p.addr, socket_addr);
+        assert_eq!(p.kind, PeerKind::Basic);
+
+        let p = restored.peers.get(&banned_peer).unwrap();
+        assert!(p.is_banned());
+        assert!(restored.ban_list.is_banned_peer(&banned_peer));
+
+        let p = restored.peers.get(&trusted_peer).unwrap();
+        assert_eq!(p.kind, PeerKind::Trusted);
+    }
+
+    #[test]
+    fn test_evict_persisted_peers() {
+        let now = unix_timestamp(std::time::SystemTime::now());
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let peer = |last_seen: u64| PersistedPeer {
+            record: NodeRecord::new(socket_addr, PeerId::random()),
+            kind: PeerKind::Basic,
+            reputation: 0,
+            backoff_counter: 0,
+            banned_until: None,
+            last_seen,
+        };
+        let a_month_ago = now - 60 * 60 * 24 * 30;
+
+        let fresh = peer(now);
+        let stale = peer(a_month_ago);
+        let stale_trusted = PersistedPeer { kind: PeerKind::Trusted, ..peer(a_month_ago) };
+        let low_reputation = PersistedPeer { reputation: BANNED_REPUTATION - 1, ..peer(now) };
+        let ban_expired = PersistedPeer {
+            reputation: BANNED_REPUTATION - 1,
+            banned_until: Some(now - 60),
+            ..peer(now)
+        };
+        let banned = PersistedPeer {
+            reputation: BANNED_REPUTATION - 1,
+            banned_until: Some(now + 60 * 60),
+            ..peer(now)
+        };
+
+        let config = PeersConfig::default().with_persisted_peers(vec![
+            fresh.clone(),
+            stale,
+            stale_trusted.clone(),
+            low_reputation,
+            ban_expired,
+            banned.clone(),
+        ]);
+        assert_eq!(config.persisted_peers, vec![fresh, stale_trusted, banned]);
+    }
+
+    #[test]
+    fn test_persisted_peers_from_old_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("known-peers.json");
+        let socket_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 1, 2)), 8008);
+        let record = NodeRecord::new(socket_addr, PeerId::random());
+        std::fs::write(&file, serde_json::to_string(&vec![record]).unwrap()).unwrap();
+
+        let config = PeersConfig::default().with_persisted_peers_from_file(Some(&file)).unwrap();
+        assert!(config.persisted_peers.is_empty());
+        assert_eq!(config.basic_nodes, HashSet::from([record]));
+
+        std::fs::write(&file, "invalid").unwrap();
+        assert!(PeersConfig::default().with_persisted_peers_from_file(Some(&file)).is_err());
+    }
 }
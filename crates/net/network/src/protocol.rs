@@ -0,0 +1,49 @@
+//! Support for custom `RLPx` subprotocols.
+//!
+//! Subprotocols are registered with a [`ProtocolHandler`] via
+//! [`NetworkConfigBuilder::add_protocol`](crate::NetworkConfigBuilder::add_protocol) or
+//! [`NetworkBuilder::add_protocol`](crate::NetworkBuilder::add_protocol). Their capabilities are
+//! advertised in the `Hello` message and every session that negotiates one of them hands a
+//! [`ProtocolConnection`] to its handler.
+
+use crate::NetworkEvent;
+use reth_eth_wire::capability::{Capability, Protocol};
+use reth_primitives::PeerId;
+use std::{fmt::Debug, sync::Arc};
+
+pub use reth_eth_wire::ProtocolConnection;
+
+/// Handles the connections of a custom `RLPx` subprotocol.
+///
+/// The handler is invoked from the network task, so it should only pass the connections and
+/// events on, e.g. to its own task, instead of processing them.
+pub trait ProtocolHandler: Debug + Send + Sync + 'static {
+    /// Invoked when a session with the peer negotiated the capability of the subprotocol.
+    ///
+    /// The connection yields the messages of the peer and ends once the session is closed.
+    fn on_connection(&self, peer_id: PeerId, conn: ProtocolConnection);
+
+    /// Invoked for every [`NetworkEvent`], e.g. when a peer connects or disconnects.
+    fn on_network_event(&self, _event: &NetworkEvent) {}
+}
+
+/// A custom subprotocol and its handler.
+#[derive(Debug, Clone)]
+pub struct RlpxSubProtocol {
+    /// The capability of the subprotocol and its number of messages.
+    pub protocol: Protocol,
+    /// The handler of the subprotocol's connections.
+    pub handler: Arc<dyn ProtocolHandler>,
+}
+
+impl RlpxSubProtocol {
+    /// Creates a new subprotocol with the given handler.
+    pub fn new(protocol: Protocol, handler: impl ProtocolHandler) -> Self {
+        Self { protocol, handler: Arc::new(handler) }
+    }
+
+    /// Returns the capability of the subprotocol.
+    pub fn capability(&self) -> &Capability {
+        &self.protocol.cap
+    }
+}
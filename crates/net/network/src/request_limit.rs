@@ -0,0 +1,88 @@
+//! Limits the number of requests every peer may send to the request handlers.
+
+use crate::peers::PeersHandle;
+use reth_network_api::ReputationChangeKind;
+use reth_primitives::PeerId;
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+use tracing::debug;
+
+/// The interval in which the requests of each peer are counted.
+pub(crate) const REQUEST_COUNT_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Maximum number of requests a peer may send per [`REQUEST_COUNT_INTERVAL`].
+///
+/// A peer that exceeds the limit is reported as a spammer, and its further requests in the
+/// interval are answered with empty responses.
+pub(crate) const MAX_REQUESTS_PER_INTERVAL: usize = 500;
+
+/// Counts the requests of every peer, see [`MAX_REQUESTS_PER_INTERVAL`].
+///
+/// Every request handler has its own limiter, so the limit applies per protocol.
+#[derive(Debug)]
+pub(crate) struct PeerRequestLimiter {
+    /// Used for reporting peers that send too many requests.
+    peers: Option<PeersHandle>,
+    /// The number of requests of each peer in the current interval.
+    peer_requests: HashMap<PeerId, usize>,
+    /// When the current interval of [`Self::peer_requests`] ends.
+    interval_end: Instant,
+}
+
+// === impl PeerRequestLimiter ===
+
+impl PeerRequestLimiter {
+    /// Creates a new limiter that reports peers that exceed the limit via the given
+    /// [`PeersHandle`], if any.
+    pub(crate) fn new(peers: Option<PeersHandle>) -> Self {
+        Self {
+            peers,
+            peer_requests: HashMap::new(),
+            interval_end: Instant::now() + REQUEST_COUNT_INTERVAL,
+        }
+    }
+
+    /// Counts the request of the peer and returns `true` if the peer sent more requests than
+    /// allowed in the current interval.
+    ///
+    /// The peer is reported once per interval in which it exceeds the limit.
+    pub(crate) fn is_spam(&mut self, peer_id: PeerId) -> bool {
+        let now = Instant::now();
+        if now >= self.interval_end {
+            self.peer_requests.clear();
+            self.interval_end = now + REQUEST_COUNT_INTERVAL;
+        }
+
+        let requests = self.peer_requests.entry(peer_id).or_default();
+        *requests += 1;
+        if *requests == MAX_REQUESTS_PER_INTERVAL + 1 {
+            debug!(target: "net", ?peer_id, "Peer exceeded the request limit");
+            if let Some(peers) = &self.peers {
+                peers.reputation_change(peer_id, ReputationChangeKind::Spam);
+            }
+        }
+        *requests > MAX_REQUESTS_PER_INTERVAL
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn limits_requests_per_peer() {
+        let mut limiter = PeerRequestLimiter::new(None);
+        let peer_id = PeerId::random();
+        for _ in 0..MAX_REQUESTS_PER_INTERVAL {
+            assert!(!limiter.is_spam(peer_id));
+        }
+        assert!(limiter.is_spam(peer_id));
+        assert!(!limiter.is_spam(PeerId::random()));
+
+        // the count starts over in the next interval
+        limiter.interval_end = Instant::now();
+        assert!(!limiter.is_spam(peer_id));
+    }
+}
@@ -3,6 +3,7 @@
 use crate::{
     message::{NewBlockMessage, PeerMessage, PeerRequest, PeerResponse, PeerResponseResult},
     session::{
+        bandwidth::SessionBandwidth,
         config::INITIAL_REQUEST_TIMEOUT,
         handle::{ActiveSessionMessage, SessionCommand},
         SessionId,
@@ -16,12 +17,13 @@ use reth_eth_wire::{
     capability::Capabilities,
     errors::{EthHandshakeError, EthStreamError, P2PStreamError},
     message::{EthBroadcastMessage, RequestPair},
-    DisconnectReason, EthMessage, EthStream, P2PStream,
+    DisconnectReason, EthMessage, EthMessageID, EthStream, P2PStream, RlpxMultiplexer,
 };
 use reth_interfaces::p2p::error::RequestError;
 use reth_metrics_common::metered_sender::MeteredSender;
 use reth_net_common::bandwidth_meter::MeteredStream;
 use reth_primitives::PeerId;
+use reth_rlp::Encodable;
 use std::{
     collections::VecDeque,
     future::Future,
@@ -64,7 +66,7 @@ pub(crate) struct ActiveSession {
     /// Keeps track of request ids.
     pub(crate) next_id: u64,
     /// The underlying connection.
-    pub(crate) conn: EthStream<P2PStream<ECIESStream<MeteredStream<TcpStream>>>>,
+    pub(crate) conn: EthStream<RlpxMultiplexer<P2PStream<ECIESStream<MeteredStream<TcpStream>>>>>,
     /// Identifier of the node we're connected to.
     pub(crate) remote_peer_id: PeerId,
     /// The address we're connected to.
@@ -94,12 +96,19 @@ pub(crate) struct ActiveSession {
     /// If an [ActiveSession] does not receive a response at all within this duration then it is
     /// considered a protocol violation and the session will initiate a drop.
     pub(crate) protocol_breach_request_timeout: Duration,
+    /// Accounts the bandwidth of the session and enforces the upload and download limits.
+    pub(crate) bandwidth: SessionBandwidth,
+    /// Buffered responses to requests of the peer.
+    ///
+    /// Responses are only sent if the upload limits leave enough budget for the messages in
+    /// `queued_outgoing`.
+    pub(crate) queued_responses: VecDeque<OutgoingMessage>,
 }
 
 impl ActiveSession {
     /// Returns `true` if the session is currently in the process of disconnecting
     fn is_disconnecting(&self) -> bool {
-        self.conn.inner().is_disconnecting()
+        self.conn.inner().inner().is_disconnecting()
     }
 
     /// Returns the next request id
@@ -280,7 +289,7 @@ impl ActiveSession {
     fn handle_outgoing_response(&mut self, id: u64, resp: PeerResponseResult) {
         match resp.try_into_message(id) {
             Ok(msg) => {
-                self.queued_outgoing.push_back(msg.into());
+                self.queued_responses.push_back(msg.into());
             }
             Err(err) => {
                 error!(target : "net", ?err, "Failed to respond to received request");
@@ -371,6 +380,7 @@ impl ActiveSession {
     /// Starts the disconnect process
     fn start_disconnect(&mut self, reason: DisconnectReason) -> Result<(), EthStreamError> {
         self.conn
+            .inner_mut()
             .inner_mut()
             .start_disconnect(reason)
             .map_err(P2PStreamError::from)
@@ -512,8 +522,25 @@ impl Future for ActiveSession {
 
             // Send messages by advancing the sink and queuing in buffered messages
             while this.conn.poll_ready_unpin(cx).is_ready() {
-                if let Some(msg) = this.queued_outgoing.pop_front() {
+                if this.bandwidth.poll_upload_ready(cx).is_pending() {
+                    // the upload limit is exhausted
+                    break
+                }
+
+                // responses are only sent if there's enough budget left for gossip
+                let msg = match this.queued_outgoing.pop_front() {
+                    Some(msg) => Some(msg),
+                    None if !this.queued_responses.is_empty() &&
+                        this.bandwidth.poll_response_upload_ready(cx).is_ready() =>
+                    {
+                        this.queued_responses.pop_front()
+                    }
+                    None => None,
+                };
+
+                if let Some(msg) = msg {
                     progress = true;
+                    this.bandwidth.on_message_sent(msg.message_id(), msg.length());
                     let res = match msg {
                         OutgoingMessage::Eth(msg) => this.conn.start_send_unpin(msg),
                         OutgoingMessage::Broadcast(msg) => this.conn.start_send_broadcast(msg),
@@ -551,6 +578,11 @@ impl Future for ActiveSession {
                     }
                 }
 
+                if this.bandwidth.poll_download_ready(cx).is_pending() {
+                    // the download limit is exhausted
+                    break 'receive
+                }
+
                 match this.conn.poll_next_unpin(cx) {
                     Poll::Pending => break,
                     Poll::Ready(None) => {
@@ -566,6 +598,7 @@ impl Future for ActiveSession {
                         match res {
                             Ok(msg) => {
                                 trace!(target: "net::session", msg_id=?msg.message_id(), remote_peer_id=?this.remote_peer_id, "received eth message");
+                                this.bandwidth.on_message_received(msg.message_id(), msg.length());
                                 // decode and handle message
                                 match this.on_incoming(msg) {
                                     OnIncomingMessageOutcome::Ok => {
@@ -690,6 +723,26 @@ pub(crate) enum OutgoingMessage {
     Broadcast(EthBroadcastMessage),
 }
 
+// === impl OutgoingMessage ===
+
+impl OutgoingMessage {
+    /// Returns the id of the message.
+    fn message_id(&self) -> EthMessageID {
+        match self {
+            OutgoingMessage::Eth(msg) => msg.message_id(),
+            OutgoingMessage::Broadcast(msg) => msg.message_id(),
+        }
+    }
+
+    /// Returns the length of the RLP encoded message.
+    fn length(&self) -> usize {
+        match self {
+            OutgoingMessage::Eth(msg) => msg.length(),
+            OutgoingMessage::Broadcast(msg) => msg.length(),
+        }
+    }
+}
+
 impl From<EthMessage> for OutgoingMessage {
     fn from(value: EthMessage) -> Self {
         OutgoingMessage::Eth(value)
@@ -794,6 +847,7 @@ mod tests {
                 remote_addr,
                 self.secret_key,
                 self.hello.clone(),
+                Vec::new(),
                 self.status,
                 self.fork_filter.clone(),
             ));
@@ -807,6 +861,7 @@ mod tests {
                     peer_id,
                     capabilities,
                     conn,
+                    bandwidth_meter,
                     ..
                 } => {
                     let (_to_session_tx, messages_rx) = mpsc::channel(10);
@@ -838,6 +893,12 @@ mod tests {
                             INITIAL_REQUEST_TIMEOUT.as_millis() as u64,
                         )),
                         protocol_breach_request_timeout: PROTOCOL_BREACH_REQUEST_TIMEOUT,
+                        bandwidth: SessionBandwidth::new(
+                            peer_id,
+                            bandwidth_meter,
+                            Default::default(),
+                        ),
+                        queued_responses: Default::default(),
                     }
                 }
                 ev => {
@@ -0,0 +1,341 @@
+//! Bandwidth accounting and limits of active sessions.
+
+use crate::metrics::{BandwidthMetrics, EthMessageMetrics, PeerBandwidthMetrics};
+use reth_eth_wire::EthMessageID;
+use reth_net_common::{bandwidth_meter::BandwidthMeter, ratelimit::SharedRateLimit};
+use reth_primitives::PeerId;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    task::{ready, Context, Poll},
+};
+
+/// Serving requests of peers is throttled once less than `1/GOSSIP_UPLOAD_RESERVE_DIVISOR` of an
+/// upload limit is left, the rest is reserved for gossip.
+const GOSSIP_UPLOAD_RESERVE_DIVISOR: u64 = 4;
+
+/// All message types of the `eth` protocol.
+const ETH_MESSAGE_IDS: [EthMessageID; 15] = [
+    EthMessageID::Status,
+    EthMessageID::NewBlockHashes,
+    EthMessageID::Transactions,
+    EthMessageID::GetBlockHeaders,
+    EthMessageID::BlockHeaders,
+    EthMessageID::GetBlockBodies,
+    EthMessageID::BlockBodies,
+    EthMessageID::NewBlock,
+    EthMessageID::NewPooledTransactionHashes,
+    EthMessageID::GetPooledTransactions,
+    EthMessageID::PooledTransactions,
+    EthMessageID::GetNodeData,
+    EthMessageID::NodeData,
+    EthMessageID::GetReceipts,
+    EthMessageID::Receipts,
+];
+
+/// Returns the budget of the upload limit that is reserved for gossip.
+pub(crate) fn gossip_upload_reserve(limit: &SharedRateLimit) -> u64 {
+    limit.rate().limit() / GOSSIP_UPLOAD_RESERVE_DIVISOR
+}
+
+/// Metrics of all `eth` message types, shared by all sessions.
+#[derive(Debug)]
+pub(crate) struct EthMessagesMetrics {
+    /// Metrics of the received messages.
+    ingress: HashMap<EthMessageID, EthMessageMetrics>,
+    /// Metrics of the sent messages.
+    egress: HashMap<EthMessageID, EthMessageMetrics>,
+}
+
+// === impl EthMessagesMetrics ===
+
+impl EthMessagesMetrics {
+    /// Records a message of the peer.
+    fn on_received(&self, id: EthMessageID, bytes: usize) {
+        if let Some(metrics) = self.ingress.get(&id) {
+            metrics.messages.increment(1);
+            metrics.bytes.increment(bytes as u64);
+        }
+    }
+
+    /// Records a message sent to the peer.
+    fn on_sent(&self, id: EthMessageID, bytes: usize) {
+        if let Some(metrics) = self.egress.get(&id) {
+            metrics.messages.increment(1);
+            metrics.bytes.increment(bytes as u64);
+        }
+    }
+}
+
+impl Default for EthMessagesMetrics {
+    fn default() -> Self {
+        let metrics = |direction: &str| {
+            ETH_MESSAGE_IDS
+                .into_iter()
+                .map(|id| {
+                    let labels = [("message", format!("{id:?}")), ("direction", direction.into())];
+                    (id, EthMessageMetrics::new_with_labels(&labels))
+                })
+                .collect()
+        };
+        Self { ingress: metrics("ingress"), egress: metrics("egress") }
+    }
+}
+
+/// Accounts the bandwidth of an active session and enforces the configured limits.
+///
+/// The bytes on the wire are accounted lazily: the limits are charged with everything that was
+/// transferred since the last check, when the session checks whether it may send or receive the
+/// next message.
+#[derive(Debug)]
+pub(crate) struct SessionBandwidth {
+    /// Meters the bytes on the wire of the session, which are also counted towards the meter of
+    /// all sessions.
+    meter: BandwidthMeter,
+    /// The inbound bytes of the meter that have already been accounted.
+    accounted_inbound: u64,
+    /// The outbound bytes of the meter that have already been accounted.
+    accounted_outbound: u64,
+    /// The upload limit shared by all sessions.
+    upload_limit: Option<SharedRateLimit>,
+    /// The download limit shared by all sessions.
+    download_limit: Option<SharedRateLimit>,
+    /// The upload limit of this session.
+    peer_upload_limit: Option<SharedRateLimit>,
+    /// The download limit of this session.
+    peer_download_limit: Option<SharedRateLimit>,
+    /// Bandwidth metrics of the peer.
+    peer_metrics: PeerBandwidthMetrics,
+    /// Bandwidth metrics of all peers.
+    metrics: BandwidthMetrics,
+    /// Metrics of the messages of all sessions.
+    message_metrics: Arc<EthMessagesMetrics>,
+}
+
+// === impl SessionBandwidth ===
+
+impl SessionBandwidth {
+    /// Creates the accounting of the session with the given meter, without any limits.
+    pub(crate) fn new(
+        peer_id: PeerId,
+        meter: BandwidthMeter,
+        message_metrics: Arc<EthMessagesMetrics>,
+    ) -> Self {
+        let accounted_inbound = meter.total_inbound();
+        let accounted_outbound = meter.total_outbound();
+        Self {
+            meter,
+            accounted_inbound,
+            accounted_outbound,
+            upload_limit: None,
+            download_limit: None,
+            peer_upload_limit: None,
+            peer_download_limit: None,
+            peer_metrics: PeerBandwidthMetrics::new_with_labels(&[(
+                "peer_id",
+                format!("{peer_id:?}"),
+            )]),
+            metrics: Default::default(),
+            message_metrics,
+        }
+    }
+
+    /// Sets the upload limit of all sessions and the upload limit of this session.
+    pub(crate) fn with_upload_limits(
+        mut self,
+        limit: Option<SharedRateLimit>,
+        peer_limit: Option<SharedRateLimit>,
+    ) -> Self {
+        self.upload_limit = limit;
+        self.peer_upload_limit = peer_limit;
+        self
+    }
+
+    /// Sets the download limit of all sessions and the download limit of this session.
+    pub(crate) fn with_download_limits(
+        mut self,
+        limit: Option<SharedRateLimit>,
+        peer_limit: Option<SharedRateLimit>,
+    ) -> Self {
+        self.download_limit = limit;
+        self.peer_download_limit = peer_limit;
+        self
+    }
+
+    /// Accounts the bytes that were transferred on the wire since the last call.
+    fn account(&mut self) {
+        let inbound = self.meter.total_inbound();
+        let received = inbound.saturating_sub(self.accounted_inbound);
+        if received > 0 {
+            self.accounted_inbound = inbound;
+            self.peer_metrics.ingress_bytes.increment(received);
+            self.metrics.ingress_bytes.increment(received);
+            for limit in
+                [&mut self.download_limit, &mut self.peer_download_limit].into_iter().flatten()
+            {
+                limit.tick_by(received);
+            }
+        }
+
+        let outbound = self.meter.total_outbound();
+        let sent = outbound.saturating_sub(self.accounted_outbound);
+        if sent > 0 {
+            self.accounted_outbound = outbound;
+            self.peer_metrics.egress_bytes.increment(sent);
+            self.metrics.egress_bytes.increment(sent);
+            for limit in [&mut self.upload_limit, &mut self.peer_upload_limit].into_iter().flatten()
+            {
+                limit.tick_by(sent);
+            }
+        }
+    }
+
+    /// Returns `Poll::Ready` if the upload limits allow sending the next message to the peer.
+    pub(crate) fn poll_upload_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.account();
+        poll_limits(cx, [&mut self.upload_limit, &mut self.peer_upload_limit], false)
+    }
+
+    /// Returns `Poll::Ready` if the upload limits allow sending the next response to a request of
+    /// the peer, which requires that more than the budget reserved for gossip is left.
+    pub(crate) fn poll_response_upload_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.account();
+        poll_limits(cx, [&mut self.upload_limit, &mut self.peer_upload_limit], true)
+    }
+
+    /// Returns `Poll::Ready` if the download limits allow reading the next message of the peer.
+    pub(crate) fn poll_download_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        self.account();
+        poll_limits(cx, [&mut self.download_limit, &mut self.peer_download_limit], false)
+    }
+
+    /// Records a message received from the peer, `bytes` is the length of the RLP encoded message.
+    pub(crate) fn on_message_received(&self, id: EthMessageID, bytes: usize) {
+        self.message_metrics.on_received(id, bytes);
+    }
+
+    /// Records a message sent to the peer, `bytes` is the length of the RLP encoded message.
+    pub(crate) fn on_message_sent(&self, id: EthMessageID, bytes: usize) {
+        self.message_metrics.on_sent(id, bytes);
+    }
+}
+
+impl Drop for SessionBandwidth {
+    fn drop(&mut self) {
+        // account the bytes that were transferred since the last check
+        self.account();
+    }
+}
+
+/// Polls all configured limits, optionally keeping the budget reserved for gossip.
+fn poll_limits(
+    cx: &mut Context<'_>,
+    limits: [&mut Option<SharedRateLimit>; 2],
+    keep_gossip_reserve: bool,
+) -> Poll<()> {
+    for limit in limits.into_iter().flatten() {
+        let reserve = if keep_gossip_reserve { gossip_upload_reserve(limit) } else { 0 };
+        ready!(limit.poll_ready_with_reserve(cx, reserve));
+    }
+    Poll::Ready(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_net_common::{bandwidth_meter::MeteredStream, ratelimit::Rate};
+    use std::{future::poll_fn, time::Duration};
+    use tokio::io::{duplex, AsyncReadExt, AsyncWriteExt};
+
+    fn upload_limit(bytes_per_sec: u64) -> SharedRateLimit {
+        SharedRateLimit::new(Rate::new(bytes_per_sec, Duration::from_secs(1)))
+    }
+
+    #[tokio::test]
+    async fn test_upload_limits() {
+        let (local, mut remote) = duplex(1024);
+        let total = BandwidthMeter::default();
+        let mut stream = MeteredStream::new_with_meter(local, total.child());
+
+        let mut bandwidth = SessionBandwidth::new(
+            PeerId::random(),
+            stream.get_bandwidth_meter().clone(),
+            Default::default(),
+        )
+        .with_upload_limits(Some(upload_limit(1000)), Some(upload_limit(100)));
+
+        poll_fn(|cx| {
+            assert!(bandwidth.poll_upload_ready(cx).is_ready());
+            assert!(bandwidth.poll_response_upload_ready(cx).is_ready());
+            Poll::Ready(())
+        })
+        .await;
+
+        // leaves less than the gossip reserve of the peer limit
+        stream.write_all(&[0u8; 80]).await.unwrap();
+        remote.read_exact(&mut [0u8; 80]).await.unwrap();
+
+        poll_fn(|cx| {
+            assert!(bandwidth.poll_upload_ready(cx).is_ready());
+            assert!(bandwidth.poll_response_upload_ready(cx).is_pending());
+            Poll::Ready(())
+        })
+        .await;
+
+        // exhausts the peer limit
+        stream.write_all(&[0u8; 20]).await.unwrap();
+        remote.read_exact(&mut [0u8; 20]).await.unwrap();
+
+        poll_fn(|cx| {
+            assert!(bandwidth.poll_upload_ready(cx).is_pending());
+            Poll::Ready(())
+        })
+        .await;
+
+        assert_eq!(total.total_outbound(), 100);
+
+        // the peer limit does not affect the download
+        poll_fn(|cx| {
+            assert!(bandwidth.poll_download_ready(cx).is_ready());
+            Poll::Ready(())
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_shared_download_limit() {
+        let download_limit = upload_limit(100);
+        let total = BandwidthMeter::default();
+
+        let (local_1, mut remote_1) = duplex(1024);
+        let (local_2, _remote_2) = duplex(1024);
+        let mut stream_1 = MeteredStream::new_with_meter(local_1, total.child());
+        let stream_2 = MeteredStream::new_with_meter(local_2, total.child());
+
+        let metrics = Arc::new(EthMessagesMetrics::default());
+        let mut bandwidth_1 = SessionBandwidth::new(
+            PeerId::random(),
+            stream_1.get_bandwidth_meter().clone(),
+            Arc::clone(&metrics),
+        )
+        .with_download_limits(Some(download_limit.clone()), None);
+        let mut bandwidth_2 = SessionBandwidth::new(
+            PeerId::random(),
+            stream_2.get_bandwidth_meter().clone(),
+            metrics,
+        )
+        .with_download_limits(Some(download_limit), None);
+
+        remote_1.write_all(&[0u8; 100]).await.unwrap();
+        stream_1.read_exact(&mut [0u8; 100]).await.unwrap();
+
+        // the download of the first session exhausts the limit of all sessions
+        poll_fn(|cx| {
+            assert!(bandwidth_1.poll_download_ready(cx).is_pending());
+            assert!(bandwidth_2.poll_download_ready(cx).is_pending());
+            assert!(bandwidth_2.poll_upload_ready(cx).is_ready());
+            Poll::Ready(())
+        })
+        .await;
+    }
+}
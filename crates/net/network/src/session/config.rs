@@ -4,6 +4,7 @@ use crate::{
     peers::{DEFAULT_MAX_PEERS_INBOUND, DEFAULT_MAX_PEERS_OUTBOUND},
     session::{Direction, ExceedsSessionLimit},
 };
+use reth_net_common::ratelimit::{Rate, SharedRateLimit};
 use std::time::Duration;
 
 /// Default request timeout for a single request.
@@ -37,6 +38,11 @@ pub struct SessionsConfig {
     /// `PROTOCOL_BREACH_REQUEST_TIMEOUT`) this is considered a protocol violation and results in a
     /// dropped session.
     pub protocol_breach_request_timeout: Duration,
+    /// Upload and download limits to enforce.
+    ///
+    /// By default, no limits will be enforced.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub bandwidth_limits: BandwidthLimits,
 }
 
 impl Default for SessionsConfig {
@@ -55,6 +61,7 @@ impl Default for SessionsConfig {
             limits: Default::default(),
             initial_internal_request_timeout: INITIAL_REQUEST_TIMEOUT,
             protocol_breach_request_timeout: PROTOCOL_BREACH_REQUEST_TIMEOUT,
+            bandwidth_limits: Default::default(),
         }
     }
 }
@@ -69,6 +76,12 @@ impl SessionsConfig {
         self.session_event_buffer = n;
         self
     }
+
+    /// Sets the upload and download limits of the sessions.
+    pub fn with_bandwidth_limits(mut self, limits: BandwidthLimits) -> Self {
+        self.bandwidth_limits = limits;
+        self
+    }
 }
 
 /// Limits for sessions.
@@ -109,6 +122,73 @@ impl SessionLimits {
     }
 }
 
+/// Upload and download limits for sessions in bytes per second.
+///
+/// The limits apply to the bytes on the wire. Serving requests of peers is throttled before
+/// gossip: responses are only sent while more than a quarter of the upload limit is left.
+///
+/// By default, no bandwidth limits will be enforced
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+pub struct BandwidthLimits {
+    max_upload: Option<u64>,
+    max_download: Option<u64>,
+    max_upload_per_peer: Option<u64>,
+    max_download_per_peer: Option<u64>,
+}
+
+impl BandwidthLimits {
+    /// Sets the maximum upload of all sessions.
+    pub fn with_max_upload(mut self, bytes_per_sec: u64) -> Self {
+        self.max_upload = Some(bytes_per_sec);
+        self
+    }
+
+    /// Sets the maximum download of all sessions.
+    pub fn with_max_download(mut self, bytes_per_sec: u64) -> Self {
+        self.max_download = Some(bytes_per_sec);
+        self
+    }
+
+    /// Sets the maximum upload of a single session.
+    pub fn with_max_upload_per_peer(mut self, bytes_per_sec: u64) -> Self {
+        self.max_upload_per_peer = Some(bytes_per_sec);
+        self
+    }
+
+    /// Sets the maximum download of a single session.
+    pub fn with_max_download_per_peer(mut self, bytes_per_sec: u64) -> Self {
+        self.max_download_per_peer = Some(bytes_per_sec);
+        self
+    }
+
+    /// Returns the limit of the upload of all sessions.
+    pub(crate) fn upload_limit(&self) -> Option<SharedRateLimit> {
+        self.max_upload.map(bytes_per_sec_limit)
+    }
+
+    /// Returns the limit of the download of all sessions.
+    pub(crate) fn download_limit(&self) -> Option<SharedRateLimit> {
+        self.max_download.map(bytes_per_sec_limit)
+    }
+
+    /// Returns a new limit of the upload of a single session.
+    pub(crate) fn peer_upload_limit(&self) -> Option<SharedRateLimit> {
+        self.max_upload_per_peer.map(bytes_per_sec_limit)
+    }
+
+    /// Returns a new limit of the download of a single session.
+    pub(crate) fn peer_download_limit(&self) -> Option<SharedRateLimit> {
+        self.max_download_per_peer.map(bytes_per_sec_limit)
+    }
+}
+
+/// Returns a [SharedRateLimit] of the given bytes per second.
+fn bytes_per_sec_limit(bytes_per_sec: u64) -> SharedRateLimit {
+    SharedRateLimit::new(Rate::new(bytes_per_sec, Duration::from_secs(1)))
+}
+
 /// Keeps track of all sessions.
 #[derive(Debug, Clone)]
 pub struct SessionCounter {
@@ -7,9 +7,10 @@ use reth_ecies::{stream::ECIESStream, ECIESError};
 use reth_eth_wire::{
     capability::{Capabilities, CapabilityMessage},
     errors::EthStreamError,
-    DisconnectReason, EthStream, EthVersion, P2PStream, Status,
+    DisconnectReason, EthStream, EthVersion, P2PStream, ProtocolConnection, RlpxMultiplexer,
+    Status,
 };
-use reth_net_common::bandwidth_meter::MeteredStream;
+use reth_net_common::bandwidth_meter::{BandwidthMeter, MeteredStream};
 use reth_primitives::PeerId;
 use std::{io, net::SocketAddr, sync::Arc, time::Instant};
 use tokio::{
@@ -65,6 +66,8 @@ pub(crate) struct ActiveSessionHandle {
     pub(crate) client_version: String,
     /// The address we're connected to
     pub(crate) remote_addr: SocketAddr,
+    /// Meters the bandwidth of the session
+    pub(crate) bandwidth_meter: BandwidthMeter,
 }
 
 // === impl ActiveSessionHandle ===
@@ -90,6 +93,8 @@ pub struct PeerInfo {
     pub remote_addr: SocketAddr,
     /// The direction of the session
     pub direction: Direction,
+    /// Meters the bandwidth of the session
+    pub bandwidth_meter: BandwidthMeter,
 }
 
 /// Events a pending session can produce.
@@ -107,9 +112,13 @@ pub(crate) enum PendingSessionEvent {
         peer_id: PeerId,
         capabilities: Arc<Capabilities>,
         status: Status,
-        conn: EthStream<P2PStream<ECIESStream<MeteredStream<TcpStream>>>>,
+        conn: EthStream<RlpxMultiplexer<P2PStream<ECIESStream<MeteredStream<TcpStream>>>>>,
+        /// The connections of the negotiated subprotocols other than `eth`.
+        protocols: Vec<ProtocolConnection>,
         direction: Direction,
         client_id: String,
+        /// Meters the bandwidth of the session
+        bandwidth_meter: BandwidthMeter,
     },
     /// Handshake unsuccessful, session was disconnected.
     Disconnected {
@@ -3,6 +3,7 @@ use crate::{
     message::PeerMessage,
     session::{
         active::ActiveSession,
+        bandwidth::{EthMessagesMetrics, SessionBandwidth},
         config::SessionCounter,
         handle::{
             ActiveSessionHandle, ActiveSessionMessage, PendingSessionEvent, PendingSessionHandle,
@@ -15,13 +16,15 @@ use fnv::FnvHashMap;
 use futures::{future::Either, io, FutureExt, StreamExt};
 use reth_ecies::{stream::ECIESStream, ECIESError};
 use reth_eth_wire::{
-    capability::{Capabilities, CapabilityMessage},
+    capability::{Capabilities, CapabilityMessage, Protocol},
     errors::EthStreamError,
-    DisconnectReason, EthVersion, HelloMessage, Status, UnauthedEthStream, UnauthedP2PStream,
+    DisconnectReason, EthVersion, HelloMessage, ProtocolConnection, RlpxMultiplexer, Status,
+    UnauthedEthStream, UnauthedP2PStream,
 };
 use reth_metrics_common::metered_sender::MeteredSender;
 use reth_net_common::{
     bandwidth_meter::{BandwidthMeter, MeteredStream},
+    ratelimit::SharedRateLimit,
     stream::HasRemoteAddr,
 };
 use reth_primitives::{ForkFilter, ForkId, ForkTransition, Head, PeerId};
@@ -44,9 +47,11 @@ use tokio_stream::wrappers::ReceiverStream;
 use tracing::{instrument, trace};
 
 mod active;
+mod bandwidth;
 mod config;
 mod handle;
-pub use config::SessionsConfig;
+pub(crate) use bandwidth::gossip_upload_reserve;
+pub use config::{BandwidthLimits, SessionsConfig};
 
 /// Internal identifier for active sessions.
 #[derive(Debug, Clone, Copy, PartialOrd, PartialEq, Eq, Hash)]
@@ -72,6 +77,8 @@ pub(crate) struct SessionManager {
     status: Status,
     /// THe `HelloMessage` message to send to peers.
     hello_message: HelloMessage,
+    /// The custom subprotocols advertised in the `HelloMessage`.
+    protocols: Vec<Protocol>,
     /// The [`ForkFilter`] used to validate the peer's `Status` message.
     fork_filter: ForkFilter,
     /// Size of the command buffer per session.
@@ -101,6 +108,14 @@ pub(crate) struct SessionManager {
     active_session_rx: ReceiverStream<ActiveSessionMessage>,
     /// Used to measure inbound & outbound bandwidth across all managed streams
     bandwidth_meter: BandwidthMeter,
+    /// The configured upload and download limits.
+    bandwidth_limits: BandwidthLimits,
+    /// The upload limit shared by all sessions.
+    upload_limit: Option<SharedRateLimit>,
+    /// The download limit shared by all sessions.
+    download_limit: Option<SharedRateLimit>,
+    /// Metrics of the `eth` messages of all sessions.
+    eth_message_metrics: Arc<EthMessagesMetrics>,
 }
 
 // === impl SessionManager ===
@@ -127,6 +142,7 @@ impl SessionManager {
             secret_key,
             status,
             hello_message,
+            protocols: Vec::new(),
             fork_filter,
             session_command_buffer: config.session_command_buffer,
             executor,
@@ -137,9 +153,18 @@ impl SessionManager {
             active_session_tx: MeteredSender::new(active_session_tx, "network_active_session"),
             active_session_rx: ReceiverStream::new(active_session_rx),
             bandwidth_meter,
+            bandwidth_limits: config.bandwidth_limits,
+            upload_limit: config.bandwidth_limits.upload_limit(),
+            download_limit: config.bandwidth_limits.download_limit(),
+            eth_message_metrics: Default::default(),
         }
     }
 
+    /// Returns the upload limit shared by all sessions, if configured.
+    pub(crate) fn upload_limit(&self) -> Option<SharedRateLimit> {
+        self.upload_limit.clone()
+    }
+
     /// Check whether the provided [`ForkId`] is compatible based on the validation rules in
     /// `EIP-2124`.
     pub(crate) fn is_valid_fork_id(&self, fork_id: ForkId) -> bool {
@@ -163,6 +188,16 @@ impl SessionManager {
         self.hello_message.clone()
     }
 
+    /// Adds a custom subprotocol to the `HelloMessage` sent to peers.
+    ///
+    /// Sessions that negotiate its capability get a connection for the subprotocol.
+    pub(crate) fn add_protocol(&mut self, protocol: Protocol) {
+        if !self.hello_message.capabilities.contains(&protocol.cap) {
+            self.hello_message.capabilities.push(protocol.cap.clone());
+        }
+        self.protocols.push(protocol);
+    }
+
     /// Spawns the given future onto a new task that is tracked in the `spawned_tasks`
     /// [`JoinSet`](tokio::task::JoinSet).
     fn spawn<F>(&self, f: F)
@@ -204,9 +239,10 @@ impl SessionManager {
 
         let (disconnect_tx, disconnect_rx) = oneshot::channel();
         let pending_events = self.pending_sessions_tx.clone();
-        let metered_stream = MeteredStream::new_with_meter(stream, self.bandwidth_meter.clone());
+        let metered_stream = MeteredStream::new_with_meter(stream, self.bandwidth_meter.child());
         let secret_key = self.secret_key;
         let hello_message = self.hello_message.clone();
+        let protocols = self.protocols.clone();
         let status = self.status;
         let fork_filter = self.fork_filter.clone();
         self.spawn(async move {
@@ -218,6 +254,7 @@ impl SessionManager {
                 remote_addr,
                 secret_key,
                 hello_message,
+                protocols,
                 status,
                 fork_filter,
             )
@@ -240,9 +277,10 @@ impl SessionManager {
         let pending_events = self.pending_sessions_tx.clone();
         let secret_key = self.secret_key;
         let hello_message = self.hello_message.clone();
+        let protocols = self.protocols.clone();
         let fork_filter = self.fork_filter.clone();
         let status = self.status;
-        let band_with_meter = self.bandwidth_meter.clone();
+        let band_with_meter = self.bandwidth_meter.child();
         self.spawn(async move {
             start_pending_outbound_session(
                 disconnect_rx,
@@ -252,6 +290,7 @@ impl SessionManager {
                 remote_peer_id,
                 secret_key,
                 hello_message,
+                protocols,
                 status,
                 fork_filter,
                 band_with_meter,
@@ -392,10 +431,12 @@ impl SessionManager {
                 remote_addr,
                 peer_id,
                 capabilities,
-                conn,
+                mut conn,
+                protocols,
                 status,
                 direction,
                 client_id,
+                bandwidth_meter,
             } => {
                 // move from pending to established.
                 self.remove_pending_session(&session_id);
@@ -413,8 +454,9 @@ impl SessionManager {
 
                     self.spawn(async move {
                         // send a disconnect message
+                        let mut conn = conn.into_inner();
                         let _ =
-                            conn.into_inner().disconnect(DisconnectReason::AlreadyConnected).await;
+                            conn.inner_mut().disconnect(DisconnectReason::AlreadyConnected).await;
                     });
 
                     return Poll::Ready(SessionEvent::AlreadyConnected {
@@ -437,6 +479,24 @@ impl SessionManager {
                 // negotiated version
                 let version = conn.version();
 
+                let peer_upload_limit = self.bandwidth_limits.peer_upload_limit();
+                // the subprotocols mostly serve requests, so they can't use the budget reserved
+                // for gossip either
+                for limit in [&self.upload_limit, &peer_upload_limit].into_iter().flatten() {
+                    conn.inner_mut().add_upload_limit(limit.clone(), gossip_upload_reserve(limit));
+                }
+
+                let bandwidth = SessionBandwidth::new(
+                    peer_id,
+                    bandwidth_meter.clone(),
+                    Arc::clone(&self.eth_message_metrics),
+                )
+                .with_upload_limits(self.upload_limit.clone(), peer_upload_limit)
+                .with_download_limits(
+                    self.download_limit.clone(),
+                    self.bandwidth_limits.peer_download_limit(),
+                );
+
                 let session = ActiveSession {
                     next_id: 0,
                     remote_peer_id: peer_id,
@@ -456,6 +516,8 @@ impl SessionManager {
                     ),
                     internal_request_timeout: Arc::clone(&timeout),
                     protocol_breach_request_timeout: self.protocol_breach_request_timeout,
+                    bandwidth,
+                    queued_responses: Default::default(),
                 };
 
                 self.spawn(session);
@@ -470,6 +532,7 @@ impl SessionManager {
                     commands_to_session,
                     client_version: client_id,
                     remote_addr,
+                    bandwidth_meter,
                 };
 
                 self.active_sessions.insert(peer_id, handle);
@@ -484,6 +547,7 @@ impl SessionManager {
                     messages,
                     direction,
                     timeout,
+                    protocols,
                 })
             }
             PendingSessionEvent::Disconnected { remote_addr, session_id, direction, error } => {
@@ -567,6 +631,7 @@ impl SessionManager {
                 remote_addr: session.remote_addr,
                 capabilities: session.capabilities.clone(),
                 client_version: session.client_version.clone(),
+                bandwidth_meter: session.bandwidth_meter.clone(),
             })
             .collect()
     }
@@ -581,6 +646,7 @@ impl SessionManager {
             remote_addr: session.remote_addr,
             capabilities: session.capabilities.clone(),
             client_version: session.client_version.clone(),
+            bandwidth_meter: session.bandwidth_meter.clone(),
         })
     }
 }
@@ -601,6 +667,8 @@ pub(crate) enum SessionEvent {
         messages: PeerRequestSender,
         direction: Direction,
         timeout: Arc<AtomicU64>,
+        /// The connections of the negotiated subprotocols other than `eth`.
+        protocols: Vec<ProtocolConnection>,
     },
     AlreadyConnected {
         peer_id: PeerId,
@@ -705,6 +773,7 @@ pub(crate) async fn start_pending_incoming_session(
     remote_addr: SocketAddr,
     secret_key: SecretKey,
     hello: HelloMessage,
+    protocols: Vec<Protocol>,
     status: Status,
     fork_filter: ForkFilter,
 ) {
@@ -717,6 +786,7 @@ pub(crate) async fn start_pending_incoming_session(
         secret_key,
         Direction::Incoming,
         hello,
+        protocols,
         status,
         fork_filter,
     )
@@ -734,6 +804,7 @@ async fn start_pending_outbound_session(
     remote_peer_id: PeerId,
     secret_key: SecretKey,
     hello: HelloMessage,
+    protocols: Vec<Protocol>,
     status: Status,
     fork_filter: ForkFilter,
     bandwidth_meter: BandwidthMeter,
@@ -761,6 +832,7 @@ async fn start_pending_outbound_session(
         secret_key,
         Direction::Outgoing(remote_peer_id),
         hello,
+        protocols,
         status,
         fork_filter,
     )
@@ -778,9 +850,11 @@ async fn authenticate(
     secret_key: SecretKey,
     direction: Direction,
     hello: HelloMessage,
+    protocols: Vec<Protocol>,
     status: Status,
     fork_filter: ForkFilter,
 ) {
+    let bandwidth_meter = stream.get_bandwidth_meter().clone();
     let stream = match get_eciess_stream(stream, secret_key, direction).await {
         Ok(stream) => stream,
         Err(error) => {
@@ -796,7 +870,7 @@ async fn authenticate(
         }
     };
 
-    let unauthed = UnauthedP2PStream::new(stream);
+    let unauthed = UnauthedP2PStream::new(stream).with_protocols(protocols);
 
     let auth = authenticate_stream(
         unauthed,
@@ -806,6 +880,7 @@ async fn authenticate(
         hello,
         status,
         fork_filter,
+        bandwidth_meter,
     )
     .boxed();
 
@@ -853,6 +928,7 @@ async fn authenticate_stream(
     hello: HelloMessage,
     status: Status,
     fork_filter: ForkFilter,
+    bandwidth_meter: BandwidthMeter,
 ) -> PendingSessionEvent {
     // conduct the p2p handshake and return the authenticated stream
     let (p2p_stream, their_hello) = match stream.handshake(hello).await {
@@ -867,11 +943,21 @@ async fn authenticate_stream(
         }
     };
 
+    // all other negotiated subprotocols get their own connection, multiplexed next to `eth`
+    let mut multiplexer = RlpxMultiplexer::new(p2p_stream);
+    let names = multiplexer
+        .inner()
+        .shared_capabilities()
+        .iter()
+        .map(|cap| cap.name().to_string())
+        .collect::<Vec<_>>();
+    let protocols = names.iter().filter_map(|name| multiplexer.install_protocol(name)).collect();
+
     // if the hello handshake was successful we can try status handshake
     //
-    // Before trying status handshake, set up the version to shared_capability
-    let status = Status { version: p2p_stream.shared_capability().version(), ..status };
-    let eth_unauthed = UnauthedEthStream::new(p2p_stream);
+    // Before trying status handshake, set up the version to the shared `eth` capability
+    let status = Status { version: multiplexer.primary_capability().version(), ..status };
+    let eth_unauthed = UnauthedEthStream::new(multiplexer);
     let (eth_stream, their_status) = match eth_unauthed.handshake(status, fork_filter).await {
         Ok(stream_res) => stream_res,
         Err(err) => {
@@ -890,7 +976,9 @@ async fn authenticate_stream(
         capabilities: Arc::new(Capabilities::from(their_hello.capabilities)),
         status: their_status,
         conn: eth_stream,
+        protocols,
         direction,
         client_id: their_hello.client_version,
+        bandwidth_meter,
     }
 }
@@ -0,0 +1,748 @@
+//! Serves the state of the node over the `snap/1` protocol and requests the state of peers.
+
+use crate::{
+    flattened_response::FlattenedResponse,
+    peers::PeersHandle,
+    protocol::{ProtocolConnection, ProtocolHandler},
+    request_limit::PeerRequestLimiter,
+    session::gossip_upload_reserve,
+};
+use futures::{future, StreamExt};
+use reth_eth_wire::snap::{
+    AccountData, AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges,
+    GetTrieNodes, SlimAccount, SnapMessage, SnapMessageID, StorageData, StorageRanges, TrieNodes,
+};
+use reth_interfaces::p2p::{
+    download::DownloadClient,
+    error::{PeerRequestResult, RequestError},
+    snap::client::{SnapClient, SnapFut},
+};
+use reth_net_common::ratelimit::SharedRateLimit;
+use reth_network_api::ReputationChangeKind;
+use reth_primitives::{
+    bytes::BytesMut, trie::Nibbles, Bytes, PeerId, WithPeerId, H256, KECCAK_EMPTY,
+};
+use reth_provider::{StateProvider, StateProviderFactory, StateRangeProvider};
+use reth_rlp::{Decodable, Encodable};
+use std::{
+    collections::VecDeque,
+    future::Future,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{
+    sync::{
+        mpsc::{self, error::TrySendError, UnboundedSender},
+        oneshot,
+    },
+    time::Interval,
+};
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tracing::trace;
+
+// Limits: <https://github.com/ethereum/go-ethereum/blob/master/eth/protocols/snap/handler.go>
+
+/// Maximum number of bytecodes to serve.
+///
+/// Used to limit lookups.
+const MAX_CODE_LOOKUPS: usize = 1024;
+
+/// Maximum number of trie nodes to serve.
+///
+/// Used to limit lookups.
+const MAX_TRIE_NODE_LOOKUPS: usize = 1024;
+
+/// Maximum size of replies to data retrievals, regardless of the size requested by the peer.
+const SOFT_RESPONSE_LIMIT: usize = 2 * 1024 * 1024;
+
+/// Time after which a request sent to a peer is considered timed out.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Passes the `snap` connections of new sessions on to the [`SnapRequestHandler`].
+///
+/// This must be registered with the network for the `snap` protocol, see
+/// [`SnapMessage::protocol`].
+#[derive(Debug)]
+pub struct SnapProtocolHandler {
+    connections: UnboundedSender<(PeerId, ProtocolConnection)>,
+}
+
+impl ProtocolHandler for SnapProtocolHandler {
+    fn on_connection(&self, peer_id: PeerId, conn: ProtocolConnection) {
+        let _ = self.connections.send((peer_id, conn));
+    }
+}
+
+/// Answers the `snap` requests of peers from the stored state, and sends the requests of
+/// [`SnapFetchClient`]s to peers.
+///
+/// Only the state whose trie was computed last is served, requests for any other state root are
+/// answered with empty responses.
+///
+/// Outgoing requests are queued until a peer without an inflight request is available.
+///
+/// Every peer may send at most
+/// [`MAX_REQUESTS_PER_INTERVAL`](crate::request_limit::MAX_REQUESTS_PER_INTERVAL) requests per
+/// [`REQUEST_COUNT_INTERVAL`](crate::request_limit::REQUEST_COUNT_INTERVAL), see
+/// [`SnapRequestHandler::with_peers`].
+///
+/// This can be spawned to another task and is supposed to be run as background service.
+#[must_use = "Manager does nothing unless polled."]
+pub struct SnapRequestHandler<C> {
+    /// The client type that can interact with the chain.
+    client: C,
+    /// The `snap` connections of new sessions.
+    incoming_connections: UnboundedReceiverStream<(PeerId, ProtocolConnection)>,
+    /// The `snap` connections of all active sessions.
+    connections: Vec<SnapConnection>,
+    /// Sender half of the request channel, cloned into every [`SnapFetchClient`].
+    request_tx: UnboundedSender<SnapRequest>,
+    /// The requests of the [`SnapFetchClient`]s.
+    requests: UnboundedReceiverStream<SnapRequest>,
+    /// Requests that wait for an idle peer.
+    queued_requests: VecDeque<SnapRequest>,
+    /// The id of the next request sent to a peer.
+    next_request_id: u64,
+    /// Number of active `snap` connections, shared with the [`SnapFetchClient`]s.
+    num_peers: Arc<AtomicUsize>,
+    /// Interval when to check for timed out requests.
+    timeout_interval: Interval,
+    /// Limits the number of requests of every peer.
+    request_limiter: PeerRequestLimiter,
+    /// The upload limit of the network.
+    ///
+    /// Requests are only served while more than the budget reserved for gossip is left.
+    upload_limit: Option<SharedRateLimit>,
+}
+
+// === impl SnapRequestHandler ===
+
+impl<C> SnapRequestHandler<C> {
+    /// Creates a new instance and the [`SnapProtocolHandler`] that needs to be registered with the
+    /// network.
+    pub fn new(client: C) -> (Self, SnapProtocolHandler) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (request_tx, request_rx) = mpsc::unbounded_channel();
+        let handler = Self {
+            client,
+            incoming_connections: UnboundedReceiverStream::new(rx),
+            connections: Vec::new(),
+            request_tx,
+            requests: UnboundedReceiverStream::new(request_rx),
+            queued_requests: VecDeque::new(),
+            next_request_id: 0,
+            num_peers: Default::default(),
+            timeout_interval: tokio::time::interval(REQUEST_TIMEOUT),
+            request_limiter: PeerRequestLimiter::new(None),
+            upload_limit: None,
+        };
+        (handler, SnapProtocolHandler { connections: tx })
+    }
+
+    /// Reports peers that send more requests than allowed via the given [`PeersHandle`].
+    pub fn with_peers(mut self, peers: PeersHandle) -> Self {
+        self.request_limiter = PeerRequestLimiter::new(Some(peers));
+        self
+    }
+
+    /// Throttles serving requests by the given upload limit of the network, so that the responses
+    /// do not exhaust the budget reserved for gossip.
+    pub fn with_upload_limit(mut self, limit: SharedRateLimit) -> Self {
+        self.upload_limit = Some(limit);
+        self
+    }
+
+    /// Returns a new [`SnapFetchClient`] that sends its requests to the peers of this handler.
+    ///
+    /// Peers that send bad responses are penalized via the given [`PeersHandle`].
+    pub fn fetch_client(&self, peers_handle: PeersHandle) -> SnapFetchClient {
+        SnapFetchClient {
+            request_tx: self.request_tx.clone(),
+            peers_handle,
+            num_peers: Arc::clone(&self.num_peers),
+        }
+    }
+
+    /// Matches a response of the peer to its inflight request.
+    fn on_response(&mut self, idx: usize, response: SnapMessage) {
+        let conn = &mut self.connections[idx];
+        let peer_id = conn.peer_id;
+        match conn.inflight.take() {
+            Some(inflight) if inflight.request_id == response.request_id() => {
+                let result = if response.message_id() == inflight.response_id {
+                    Ok(WithPeerId::from((peer_id, response)))
+                } else {
+                    Err(RequestError::BadResponse)
+                };
+                let _ = inflight.response.send(result);
+            }
+            inflight => {
+                trace!(target: "net::snap", ?peer_id, "Unsolicited snap response");
+                conn.inflight = inflight;
+            }
+        }
+    }
+
+    /// Fails the requests that were not answered in time.
+    fn on_timeout(&mut self) {
+        let now = Instant::now();
+        for conn in &mut self.connections {
+            if conn.inflight.as_ref().map_or(false, |inflight| inflight.deadline <= now) {
+                let inflight = conn.inflight.take().expect("is some");
+                trace!(target: "net::snap", peer_id=?conn.peer_id, "Snap request timed out");
+                let _ = inflight.response.send(Err(RequestError::Timeout));
+            }
+        }
+    }
+
+    /// Returns `Poll::Ready` if the upload limit allows serving the next request.
+    fn poll_upload_ready(&mut self, cx: &mut Context<'_>) -> Poll<()> {
+        match self.upload_limit.as_mut() {
+            Some(limit) => {
+                let reserve = gossip_upload_reserve(limit);
+                limit.poll_ready_with_reserve(cx, reserve)
+            }
+            None => Poll::Ready(()),
+        }
+    }
+
+    /// Sends the response to the peer of the connection.
+    ///
+    /// Returns `false` if the session was closed and the connection has been removed.
+    fn send_response(&mut self, idx: usize, response: SnapMessage) -> bool {
+        let SnapConnection { peer_id, conn, .. } = &self.connections[idx];
+        let peer_id = *peer_id;
+        let mut out = BytesMut::new();
+        response.encode(&mut out);
+        match conn.send(out) {
+            Ok(()) => {}
+            Err(TrySendError::Full(_)) => {
+                trace!(target: "net::snap", ?peer_id, "Too many queued responses, dropping response");
+            }
+            Err(TrySendError::Closed(_)) => {
+                trace!(target: "net::snap", ?peer_id, "Session closed, dropping connection");
+                self.remove_connection(idx);
+                return false
+            }
+        }
+        true
+    }
+
+    /// Removes the connection and fails its inflight request.
+    fn remove_connection(&mut self, idx: usize) {
+        let conn = self.connections.swap_remove(idx);
+        if let Some(inflight) = conn.inflight {
+            let _ = inflight.response.send(Err(RequestError::ConnectionDropped));
+        }
+    }
+
+    /// Sends queued requests to idle peers.
+    ///
+    /// The search for an idle connection starts at a different connection for every request, so
+    /// retried requests are likely sent to a different peer.
+    fn dispatch_requests(&mut self) {
+        while !self.queued_requests.is_empty() {
+            let len = self.connections.len();
+            let start = self.next_request_id as usize % len.max(1);
+            let Some(idx) = (0..len)
+                .map(|offset| (start + offset) % len)
+                .find(|idx| self.connections[*idx].inflight.is_none())
+            else {
+                return
+            };
+            let SnapRequest { mut message, response } =
+                self.queued_requests.pop_front().expect("is not empty");
+            if response.is_closed() {
+                // the request was dropped by the client
+                continue
+            }
+
+            let request_id = self.next_request_id;
+            self.next_request_id = self.next_request_id.wrapping_add(1);
+            message.set_request_id(request_id);
+            let response_id = response_id(message.message_id());
+
+            let mut out = BytesMut::new();
+            message.encode(&mut out);
+            match self.connections[idx].conn.send(out) {
+                Ok(()) => {}
+                Err(TrySendError::Full(_)) => {
+                    // the session is busy sending, retry later
+                    self.queued_requests.push_front(SnapRequest { message, response });
+                    return
+                }
+                Err(TrySendError::Closed(_)) => {
+                    // the session was closed, try the next peer
+                    self.queued_requests.push_front(SnapRequest { message, response });
+                    self.remove_connection(idx);
+                    continue
+                }
+            }
+
+            self.connections[idx].inflight = Some(InflightSnapRequest {
+                request_id,
+                response_id,
+                response,
+                deadline: Instant::now() + REQUEST_TIMEOUT,
+            });
+        }
+    }
+}
+
+impl<C> SnapRequestHandler<C>
+where
+    C: StateRangeProvider + StateProviderFactory,
+{
+    /// Returns the response to the request, or `None` if the message is not a request.
+    fn on_request(&self, request: SnapMessage) -> Option<SnapMessage> {
+        let response = match request {
+            SnapMessage::GetAccountRange(request) => {
+                SnapMessage::AccountRange(self.get_account_range_response(request))
+            }
+            SnapMessage::GetStorageRanges(request) => {
+                SnapMessage::StorageRanges(self.get_storage_ranges_response(request))
+            }
+            SnapMessage::GetByteCodes(request) => {
+                SnapMessage::ByteCodes(self.get_byte_codes_response(request))
+            }
+            SnapMessage::GetTrieNodes(request) => {
+                SnapMessage::TrieNodes(self.get_trie_nodes_response(request))
+            }
+            SnapMessage::AccountRange(_) |
+            SnapMessage::StorageRanges(_) |
+            SnapMessage::ByteCodes(_) |
+            SnapMessage::TrieNodes(_) => return None,
+        };
+        Some(response)
+    }
+
+    fn get_account_range_response(&self, request: GetAccountRange) -> AccountRange {
+        let GetAccountRange { request_id, root_hash, starting_hash, limit_hash, response_bytes } =
+            request;
+
+        let range = self
+            .client
+            .account_range(root_hash, starting_hash, limit_hash, response_limit(response_bytes))
+            .unwrap_or_default()
+            .unwrap_or_default();
+
+        let accounts = range
+            .entries
+            .into_iter()
+            .map(|(hash, account, storage_root)| AccountData {
+                hash,
+                body: SlimAccount::new(
+                    account.nonce,
+                    account.balance,
+                    storage_root,
+                    account.bytecode_hash.unwrap_or(KECCAK_EMPTY),
+                ),
+            })
+            .collect();
+
+        AccountRange { request_id, accounts, proof: range.proof }
+    }
+
+    fn get_storage_ranges_response(&self, request: GetStorageRanges) -> StorageRanges {
+        let GetStorageRanges {
+            request_id,
+            root_hash,
+            account_hashes,
+            starting_hash,
+            limit_hash,
+            response_bytes,
+        } = request;
+
+        let start = bytes_to_hash(&starting_hash).unwrap_or_default();
+        let limit = bytes_to_hash(&limit_hash).unwrap_or_else(|| H256::repeat_byte(0xff));
+
+        let range = self
+            .client
+            .storage_ranges(
+                root_hash,
+                &account_hashes,
+                start,
+                limit,
+                response_limit(response_bytes),
+            )
+            .unwrap_or_default()
+            .unwrap_or_default();
+
+        let slots = range
+            .entries
+            .into_iter()
+            .map(|slots| {
+                slots
+                    .into_iter()
+                    .map(|(hash, value)| {
+                        let mut data = Vec::new();
+                        value.encode(&mut data);
+                        StorageData { hash, data: data.into() }
+                    })
+                    .collect()
+            })
+            .collect();
+
+        StorageRanges { request_id, slots, proof: range.proof }
+    }
+
+    fn get_byte_codes_response(&self, request: GetByteCodes) -> ByteCodes {
+        let GetByteCodes { request_id, hashes, response_bytes } = request;
+        let limit = response_limit(response_bytes);
+
+        let mut codes = Vec::new();
+        let mut total_bytes = 0;
+
+        if let Ok(state) = self.client.latest() {
+            for hash in hashes.into_iter().take(MAX_CODE_LOOKUPS) {
+                if total_bytes >= limit {
+                    break
+                }
+                if let Some(code) = state.bytecode_by_hash(hash).unwrap_or_default() {
+                    let code: Bytes = code.original_bytes().into();
+                    total_bytes += code.len();
+                    codes.push(code);
+                }
+            }
+        }
+
+        ByteCodes { request_id, codes }
+    }
+
+    fn get_trie_nodes_response(&self, request: GetTrieNodes) -> TrieNodes {
+        let GetTrieNodes { request_id, root_hash, paths, response_bytes } = request;
+
+        // Decode the path sets up to the first invalid one.
+        let mut lookups = 0;
+        let mut path_sets = Vec::new();
+        for path_set in paths {
+            let (hashed_address, encoded_paths) = match path_set.as_slice() {
+                [] => break,
+                [path] => (None, std::slice::from_ref(path)),
+                [hashed_address, paths @ ..] => match bytes_to_hash(hashed_address) {
+                    Some(hashed_address) => (Some(hashed_address), paths),
+                    None => break,
+                },
+            };
+
+            let mut decoded = Vec::new();
+            for path in encoded_paths {
+                if lookups >= MAX_TRIE_NODE_LOOKUPS {
+                    break
+                }
+                match Nibbles::decode_path_leaf(path) {
+                    Some((path, _)) => decoded.push(path),
+                    None => break,
+                }
+                lookups += 1;
+            }
+
+            let complete = decoded.len() == encoded_paths.len();
+            path_sets.push((hashed_address, decoded));
+            if !complete {
+                break
+            }
+        }
+
+        let nodes = self
+            .client
+            .trie_nodes(root_hash, &path_sets, response_limit(response_bytes))
+            .unwrap_or_default()
+            .unwrap_or_default();
+
+        TrieNodes { request_id, nodes }
+    }
+}
+
+/// An endless future.
+///
+/// This should be spawned or used as part of `tokio::select!`.
+impl<C> Future for SnapRequestHandler<C>
+where
+    C: StateRangeProvider + StateProviderFactory + Unpin,
+{
+    type Output = ();
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+
+        while let Poll::Ready(Some((peer_id, conn))) = this.incoming_connections.poll_next_unpin(cx)
+        {
+            this.connections.push(SnapConnection {
+                peer_id,
+                conn,
+                inflight: None,
+                requests: VecDeque::new(),
+            });
+        }
+
+        while let Poll::Ready(Some(request)) = this.requests.poll_next_unpin(cx) {
+            this.queued_requests.push_back(request);
+        }
+
+        while this.timeout_interval.poll_tick(cx).is_ready() {
+            this.on_timeout();
+        }
+
+        for idx in (0..this.connections.len()).rev() {
+            loop {
+                let SnapConnection { peer_id, conn, .. } = &mut this.connections[idx];
+                let peer_id = *peer_id;
+                let msg = match conn.poll_next_unpin(cx) {
+                    Poll::Pending => break,
+                    Poll::Ready(Some(msg)) => msg,
+                    Poll::Ready(None) => {
+                        // the session was closed
+                        this.remove_connection(idx);
+                        break
+                    }
+                };
+
+                let msg = match SnapMessage::decode(&mut &msg[..]) {
+                    Ok(msg) => msg,
+                    Err(err) => {
+                        trace!(
+                            target: "net::snap",
+                            ?peer_id,
+                            ?err,
+                            "Invalid snap message, dropping connection"
+                        );
+                        this.remove_connection(idx);
+                        break
+                    }
+                };
+
+                if !msg.is_request() {
+                    this.on_response(idx, msg);
+                    continue
+                }
+
+                if this.request_limiter.is_spam(peer_id) {
+                    if !this.send_response(idx, empty_response(&msg)) {
+                        break
+                    }
+                } else {
+                    this.connections[idx].requests.push_back(msg);
+                }
+            }
+        }
+
+        // serve one request of every peer per round, while the upload limit allows it
+        'serve: loop {
+            let mut served = false;
+            for idx in (0..this.connections.len()).rev() {
+                if this.connections[idx].requests.is_empty() {
+                    continue
+                }
+                if this.poll_upload_ready(cx).is_pending() {
+                    break 'serve
+                }
+                let request = this.connections[idx].requests.pop_front().expect("is not empty");
+                served = true;
+                if let Some(response) = this.on_request(request) {
+                    this.send_response(idx, response);
+                }
+            }
+            if !served {
+                break
+            }
+        }
+
+        this.dispatch_requests();
+        this.num_peers.store(this.connections.len(), Ordering::Relaxed);
+
+        Poll::Pending
+    }
+}
+
+/// The `snap` connection of an active session.
+struct SnapConnection {
+    /// The peer of the session.
+    peer_id: PeerId,
+    /// The connection of the `snap` subprotocol.
+    conn: ProtocolConnection,
+    /// The request sent to the peer that has not been answered yet.
+    inflight: Option<InflightSnapRequest>,
+    /// The requests of the peer that wait for the upload limit.
+    requests: VecDeque<SnapMessage>,
+}
+
+/// A request sent to a peer.
+struct InflightSnapRequest {
+    /// The id of the request.
+    request_id: u64,
+    /// The id of the expected response message.
+    response_id: SnapMessageID,
+    /// Sender half of the response channel.
+    response: oneshot::Sender<PeerRequestResult<SnapMessage>>,
+    /// When the request times out.
+    deadline: Instant,
+}
+
+/// A request of a [`SnapFetchClient`].
+#[derive(Debug)]
+pub(crate) struct SnapRequest {
+    /// The request, its id is assigned when it is sent to a peer.
+    message: SnapMessage,
+    /// Sender half of the response channel.
+    response: oneshot::Sender<PeerRequestResult<SnapMessage>>,
+}
+
+/// Front-end API for fetching state from the peers of a [`SnapRequestHandler`].
+#[derive(Debug, Clone)]
+pub struct SnapFetchClient {
+    /// Sender half of the request channel.
+    request_tx: UnboundedSender<SnapRequest>,
+    /// The handle to the peers
+    peers_handle: PeersHandle,
+    /// Number of active `snap` connections.
+    num_peers: Arc<AtomicUsize>,
+}
+
+impl SnapFetchClient {
+    /// Sends the request and maps the response with `unwrap`, a response of another type is a
+    /// [`RequestError::BadResponse`].
+    fn request<T: Send + 'static>(
+        &self,
+        message: SnapMessage,
+        unwrap: fn(SnapMessage) -> Option<T>,
+    ) -> SnapFut<T> {
+        let (response, rx) = oneshot::channel();
+        if self.request_tx.send(SnapRequest { message, response }).is_err() {
+            return Box::pin(future::err(RequestError::ChannelClosed))
+        }
+        let response = FlattenedResponse::from(rx);
+        Box::pin(async move {
+            let response = response.await?;
+            let peer_id = response.peer_id();
+            unwrap(response.into_data())
+                .map(|msg| WithPeerId::from((peer_id, msg)))
+                .ok_or(RequestError::BadResponse)
+        })
+    }
+}
+
+impl DownloadClient for SnapFetchClient {
+    fn report_bad_message(&self, peer_id: PeerId) {
+        self.peers_handle.reputation_change(peer_id, ReputationChangeKind::BadMessage);
+    }
+
+    fn num_connected_peers(&self) -> usize {
+        self.num_peers.load(Ordering::Relaxed)
+    }
+}
+
+impl SnapClient for SnapFetchClient {
+    fn get_account_range(&self, request: GetAccountRange) -> SnapFut<AccountRange> {
+        self.request(SnapMessage::GetAccountRange(request), |msg| match msg {
+            SnapMessage::AccountRange(msg) => Some(msg),
+            _ => None,
+        })
+    }
+
+    fn get_storage_ranges(&self, request: GetStorageRanges) -> SnapFut<StorageRanges> {
+        self.request(SnapMessage::GetStorageRanges(request), |msg| match msg {
+            SnapMessage::StorageRanges(msg) => Some(msg),
+            _ => None,
+        })
+    }
+
+    fn get_byte_codes(&self, request: GetByteCodes) -> SnapFut<ByteCodes> {
+        self.request(SnapMessage::GetByteCodes(request), |msg| match msg {
+            SnapMessage::ByteCodes(msg) => Some(msg),
+            _ => None,
+        })
+    }
+
+    fn get_trie_nodes(&self, request: GetTrieNodes) -> SnapFut<TrieNodes> {
+        self.request(SnapMessage::GetTrieNodes(request), |msg| match msg {
+            SnapMessage::TrieNodes(msg) => Some(msg),
+            _ => None,
+        })
+    }
+}
+
+/// Returns the id of the response to a request message.
+fn response_id(request_id: SnapMessageID) -> SnapMessageID {
+    match request_id {
+        SnapMessageID::GetAccountRange | SnapMessageID::AccountRange => SnapMessageID::AccountRange,
+        SnapMessageID::GetStorageRanges | SnapMessageID::StorageRanges => {
+            SnapMessageID::StorageRanges
+        }
+        SnapMessageID::GetByteCodes | SnapMessageID::ByteCodes => SnapMessageID::ByteCodes,
+        SnapMessageID::GetTrieNodes | SnapMessageID::TrieNodes => SnapMessageID::TrieNodes,
+    }
+}
+
+/// Returns the empty response to a request.
+fn empty_response(request: &SnapMessage) -> SnapMessage {
+    let request_id = request.request_id();
+    match response_id(request.message_id()) {
+        SnapMessageID::AccountRange => {
+            SnapMessage::AccountRange(AccountRange { request_id, ..Default::default() })
+        }
+        SnapMessageID::StorageRanges => {
+            SnapMessage::StorageRanges(StorageRanges { request_id, ..Default::default() })
+        }
+        SnapMessageID::ByteCodes => {
+            SnapMessage::ByteCodes(ByteCodes { request_id, ..Default::default() })
+        }
+        _ => SnapMessage::TrieNodes(TrieNodes { request_id, ..Default::default() }),
+    }
+}
+
+/// Returns the byte limit of a response, given the limit requested by the peer.
+fn response_limit(response_bytes: u64) -> usize {
+    usize::try_from(response_bytes).unwrap_or(usize::MAX).min(SOFT_RESPONSE_LIMIT)
+}
+
+/// Returns the hash encoded in the given bytes, left padded with zeros, or `None` if the bytes
+/// are empty or longer than a hash.
+fn bytes_to_hash(bytes: &[u8]) -> Option<H256> {
+    if bytes.is_empty() || bytes.len() > H256::len_bytes() {
+        return None
+    }
+    let mut hash = H256::zero();
+    hash.0[H256::len_bytes() - bytes.len()..].copy_from_slice(bytes);
+    Some(hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pads_hashes() {
+        assert_eq!(bytes_to_hash(&[]), None);
+        assert_eq!(bytes_to_hash(&[0x01]), Some(H256::from_low_u64_be(1)));
+        assert_eq!(bytes_to_hash(&[0xff; 32]), Some(H256::repeat_byte(0xff)));
+        assert_eq!(bytes_to_hash(&[0xff; 33]), None);
+    }
+
+    #[test]
+    fn empty_responses_match_requests() {
+        let request =
+            SnapMessage::GetByteCodes(GetByteCodes { request_id: 3, ..Default::default() });
+        assert_eq!(
+            empty_response(&request),
+            SnapMessage::ByteCodes(ByteCodes { request_id: 3, ..Default::default() })
+        );
+        let request =
+            SnapMessage::GetTrieNodes(GetTrieNodes { request_id: 4, ..Default::default() });
+        assert_eq!(
+            empty_response(&request),
+            SnapMessage::TrieNodes(TrieNodes { request_id: 4, ..Default::default() })
+        );
+    }
+
+    #[test]
+    fn caps_response_size() {
+        assert_eq!(response_limit(1024), 1024);
+        assert_eq!(response_limit(u64::MAX), SOFT_RESPONSE_LIMIT);
+    }
+}
@@ -9,7 +9,7 @@ use futures::Stream;
 use reth_eth_wire::{
     capability::{Capabilities, CapabilityMessage},
     errors::EthStreamError,
-    DisconnectReason, EthVersion, Status,
+    DisconnectReason, EthVersion, ProtocolConnection, Status,
 };
 use reth_primitives::PeerId;
 use reth_provider::BlockProvider;
@@ -131,6 +131,7 @@ where
                 messages,
                 direction,
                 timeout,
+                protocols,
             } => {
                 self.state.on_session_activated(
                     peer_id,
@@ -147,6 +148,7 @@ where
                     messages,
                     status,
                     direction,
+                    protocols,
                 })
             }
             SessionEvent::AlreadyConnected { peer_id, remote_addr, direction } => {
@@ -396,6 +398,8 @@ pub(crate) enum SwarmEvent {
         messages: PeerRequestSender,
         status: Status,
         direction: Direction,
+        /// The connections of the negotiated subprotocols other than `eth`.
+        protocols: Vec<ProtocolConnection>,
     },
     SessionClosed {
         peer_id: PeerId,
@@ -15,9 +15,9 @@ use reth_eth_wire::{
 use reth_interfaces::{p2p::error::RequestResult, sync::SyncStateProvider};
 use reth_network_api::{Peers, ReputationChangeKind};
 use reth_primitives::{
-    FromRecoveredTransaction, IntoRecoveredTransaction, PeerId, TransactionSigned, TxHash, H256,
+    BlobTransaction, FromRecoveredTransaction, IntoRecoveredTransaction, PeerId,
+    PooledTransactionsElement, TransactionSigned, TxHash, TxType, EIP4844_TX_TYPE_ID, H256,
 };
-use reth_rlp::Encodable;
 use reth_transaction_pool::{
     error::PoolResult, PoolTransaction, PropagateKind, PropagatedTransactions, TransactionPool,
     ValidPoolTransaction,
@@ -163,15 +163,28 @@ where
         response: oneshot::Sender<RequestResult<PooledTransactions>>,
     ) {
         if let Some(peer) = self.peers.get_mut(&peer_id) {
-            let transactions = self
-                .pool
-                .get_all(request.0)
-                .into_iter()
-                .map(|tx| tx.transaction.to_recovered_transaction().into_signed())
-                .collect::<Vec<_>>();
+            let mut transactions = Vec::new();
+            for tx in self.pool.get_all(request.0) {
+                let tx = tx.transaction.to_recovered_transaction().into_signed();
+                if tx.tx_type() != TxType::EIP4844 {
+                    transactions.push(tx.into());
+                    continue
+                }
+                // blob transactions are only served in their network form, so skip those whose
+                // sidecar is no longer available
+                match self.pool.get_blob(tx.hash) {
+                    Ok(Some(sidecar)) => {
+                        transactions.push(BlobTransaction { transaction: tx, sidecar }.into())
+                    }
+                    Ok(None) => {}
+                    Err(err) => {
+                        trace!(target: "net::tx", ?err, hash=?tx.hash, "failed to load blob sidecar");
+                    }
+                }
+            }
 
             // we sent a response at which point we assume that the peer is aware of the transaction
-            peer.transactions.extend(transactions.iter().map(|tx| tx.hash()));
+            peer.transactions.extend(transactions.iter().map(PooledTransactionsElement::hash));
 
             let resp = PooledTransactions(transactions);
             let _ = response.send(Ok(resp));
@@ -198,14 +211,7 @@ where
         trace!(target: "net::tx", "Start propagating transactions");
 
         let propagated = self.propagate_transactions(
-            self.pool
-                .get_all(hashes)
-                .into_iter()
-                .map(|tx| {
-                    let tx = Arc::new(tx.transaction.to_recovered_transaction().into_signed());
-                    PropagateTransaction::new(tx)
-                })
-                .collect(),
+            self.pool.get_all(hashes).into_iter().map(PropagateTransaction::new).collect(),
         );
 
         // notify pool so events get fired
@@ -216,6 +222,8 @@ where
     ///
     /// The message for new pooled hashes depends on the negotiated version of the stream.
     /// See [NewPooledTransactionHashes](NewPooledTransactionHashes)
+    ///
+    /// Blob transactions are never sent as full objects, they are always announced.
     fn propagate_transactions(
         &mut self,
         to_propagate: Vec<PropagateTransaction>,
@@ -230,36 +238,60 @@ where
         for (peer_idx, (peer_id, peer)) in self.peers.iter_mut().enumerate() {
             // filter all transactions unknown to the peer
             let mut hashes = PooledTransactionsHashesBuilder::new(peer.version);
+            // blob transactions, these are announced even to peers that receive full objects
+            let mut blob_hashes = PooledTransactionsHashesBuilder::new(peer.version);
             let mut full_transactions = Vec::new();
+            let mut num_unknown = 0;
             for tx in to_propagate.iter() {
                 if peer.transactions.insert(tx.hash()) {
+                    num_unknown += 1;
                     hashes.push(tx);
-                    full_transactions.push(Arc::clone(&tx.transaction));
+                    if tx.is_eip4844() {
+                        blob_hashes.push(tx);
+                    } else {
+                        full_transactions.push(Arc::clone(&tx.transaction));
+                    }
                 }
             }
-            let mut new_pooled_hashes = hashes.build();
 
-            if !full_transactions.is_empty() {
-                // determine whether to send full tx objects or hashes.
-                if peer_idx > max_num_full {
-                    // enforce tx soft limit per message for the (unlikely) event the number of
-                    // hashes exceeds it
-                    new_pooled_hashes.truncate(NEW_POOLED_TRANSACTION_HASHES_SOFT_LIMIT);
+            if num_unknown == 0 {
+                continue
+            }
 
-                    for hash in new_pooled_hashes.iter_hashes().copied() {
-                        propagated.0.entry(hash).or_default().push(PropagateKind::Hash(*peer_id));
-                    }
-                    // send hashes of transactions
-                    self.network.send_transactions_hashes(*peer_id, new_pooled_hashes);
-                } else {
+            // determine whether to send full tx objects or hashes.
+            if peer_idx > max_num_full {
+                let mut new_pooled_hashes = hashes.build();
+                // enforce tx soft limit per message for the (unlikely) event the number of
+                // hashes exceeds it
+                new_pooled_hashes.truncate(NEW_POOLED_TRANSACTION_HASHES_SOFT_LIMIT);
+
+                for hash in new_pooled_hashes.iter_hashes().copied() {
+                    propagated.0.entry(hash).or_default().push(PropagateKind::Hash(*peer_id));
+                }
+                // send hashes of transactions
+                self.network.send_transactions_hashes(*peer_id, new_pooled_hashes);
+            } else {
+                if !full_transactions.is_empty() {
                     // TODO ensure max message size
 
+                    for tx in full_transactions.iter() {
+                        propagated
+                            .0
+                            .entry(tx.hash)
+                            .or_default()
+                            .push(PropagateKind::Full(*peer_id));
+                    }
                     // send full transactions
                     self.network.send_transactions(*peer_id, full_transactions);
+                }
 
-                    for hash in new_pooled_hashes.into_iter_hashes() {
-                        propagated.0.entry(hash).or_default().push(PropagateKind::Full(*peer_id));
+                let mut blob_hashes = blob_hashes.build();
+                if blob_hashes.iter_hashes().next().is_some() {
+                    blob_hashes.truncate(NEW_POOLED_TRANSACTION_HASHES_SOFT_LIMIT);
+                    for hash in blob_hashes.iter_hashes().copied() {
+                        propagated.0.entry(hash).or_default().push(PropagateKind::Hash(*peer_id));
                     }
+                    self.network.send_transactions_hashes(*peer_id, blob_hashes);
                 }
             }
         }
@@ -320,7 +352,13 @@ where
     fn on_network_tx_event(&mut self, event: NetworkTransactionEvent) {
         match event {
             NetworkTransactionEvent::IncomingTransactions { peer_id, msg } => {
-                self.import_transactions(peer_id, msg.0, TransactionSource::Broadcast);
+                // blob transactions must not be broadcast, these end up as blob transactions
+                // without sidecar and are rejected
+                self.import_transactions(
+                    peer_id,
+                    msg.0.into_iter().map(Into::into),
+                    TransactionSource::Broadcast,
+                );
             }
             NetworkTransactionEvent::IncomingPooledTransactionHashes { peer_id, msg } => {
                 self.on_new_pooled_transaction_hashes(peer_id, msg)
@@ -388,10 +426,12 @@ where
     }
 
     /// Starts the import process for the given transactions.
+    ///
+    /// Blob transactions are only accepted in their network form that includes the sidecar.
     fn import_transactions(
         &mut self,
         peer_id: PeerId,
-        transactions: Vec<TransactionSigned>,
+        transactions: impl IntoIterator<Item = PooledTransactionsElement>,
         source: TransactionSource,
     ) {
         // If the node is currently syncing, ignore transactions
@@ -405,8 +445,13 @@ where
 
         if let Some(peer) = self.peers.get_mut(&peer_id) {
             for tx in transactions {
+                if tx.is_blob_without_sidecar() {
+                    has_bad_transactions = true;
+                    continue
+                }
+
                 // recover transaction
-                let tx = if let Some(tx) = tx.into_ecrecovered() {
+                let (tx, sidecar) = if let Some(tx) = tx.try_into_ecrecovered() {
                     tx
                 } else {
                     has_bad_transactions = true;
@@ -428,7 +473,10 @@ where
                     }
                     Entry::Vacant(entry) => {
                         // this is a new transaction that should be imported into the pool
-                        let pool_transaction = <Pool::Transaction as FromRecoveredTransaction>::from_recovered_transaction(tx);
+                        let pool_transaction = match sidecar {
+                            Some(sidecar) => <Pool::Transaction as PoolTransaction>::from_recovered_blob_transaction(tx, sidecar),
+                            None => <Pool::Transaction as FromRecoveredTransaction>::from_recovered_transaction(tx),
+                        };
 
                         let pool = self.pool.clone();
                         let import = Box::pin(async move {
@@ -542,6 +590,7 @@ where
 /// A transaction that's about to be propagated to multiple peers.
 struct PropagateTransaction {
     tx_type: u8,
+    /// The length of the encoded transaction, this is the network form for blob transactions.
     length: usize,
     transaction: Arc<TransactionSigned>,
 }
@@ -553,8 +602,17 @@ impl PropagateTransaction {
         self.transaction.hash
     }
 
-    fn new(transaction: Arc<TransactionSigned>) -> Self {
-        Self { tx_type: transaction.tx_type().into(), length: transaction.length(), transaction }
+    fn is_eip4844(&self) -> bool {
+        self.tx_type == EIP4844_TX_TYPE_ID
+    }
+
+    /// Creates a new instance from a pooled transaction
+    fn new<T: PoolTransaction + IntoRecoveredTransaction>(
+        tx: Arc<ValidPoolTransaction<T>>,
+    ) -> Self {
+        let length = tx.encoded_length;
+        let transaction = Arc::new(tx.transaction.to_recovered_transaction().into_signed());
+        Self { tx_type: tx.tx_type(), length, transaction }
     }
 }
 
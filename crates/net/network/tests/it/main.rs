@@ -1,5 +1,6 @@
 mod connect;
 mod requests;
 mod session;
+mod subprotocol;
 
 fn main() {}
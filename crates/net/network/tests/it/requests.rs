@@ -1,19 +1,23 @@
 //! Tests for eth related requests
 
 use rand::Rng;
-use reth_eth_wire::BlockBody;
+use reth_eth_wire::{BlockBody, GetReceipts};
 use reth_interfaces::p2p::{
     bodies::client::BodiesClient,
     headers::client::{HeadersClient, HeadersRequest},
 };
-use reth_network::test_utils::{NetworkEventStream, Testnet};
+use reth_network::{
+    test_utils::{NetworkEventStream, Testnet},
+    PeerRequest,
+};
 use reth_network_api::{NetworkInfo, Peers};
 use reth_primitives::{
-    Block, Bytes, Header, HeadersDirection, Signature, Transaction, TransactionKind,
-    TransactionSigned, TxEip2930, H256, U256,
+    Block, Bytes, Header, HeadersDirection, Receipt, Signature, Transaction, TransactionKind,
+    TransactionSigned, TxEip2930, TxType, H256, U256,
 };
 use reth_provider::test_utils::MockEthProvider;
 use std::sync::Arc;
+use tokio::sync::oneshot;
 
 /// Returns a new [`TransactionSigned`] with some random parameters
 pub fn rng_transaction(rng: &mut impl rand::RngCore) -> TransactionSigned {
@@ -121,3 +125,54 @@ async fn test_get_header() {
         assert_eq!(headers[0], header);
     }
 }
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_get_receipts() {
+    reth_tracing::init_test_tracing();
+    let mut rng = rand::thread_rng();
+    let mock_provider = Arc::new(MockEthProvider::default());
+
+    let mut net = Testnet::create_with(2, mock_provider.clone()).await;
+
+    // install request handlers
+    net.for_each_mut(|peer| peer.install_request_handler());
+
+    let handle0 = net.peers()[0].handle();
+    let mut events0 = NetworkEventStream::new(handle0.event_listener());
+
+    let handle1 = net.peers()[1].handle();
+
+    let _handle = net.spawn();
+
+    handle0.add_peer(*handle1.peer_id(), handle1.local_addr());
+    let connected = events0.next_session_established().await.unwrap();
+    assert_eq!(connected, *handle1.peer_id());
+
+    // request the receipts of some blocks
+    for _ in 0..10 {
+        // Set new random receipts to the mock storage and request them via the network
+        let block_hash = H256::random();
+        let receipts = vec![Receipt {
+            tx_type: TxType::EIP1559,
+            success: true,
+            cumulative_gas_used: rng.gen(),
+            bloom: Default::default(),
+            logs: vec![],
+        }];
+
+        mock_provider.add_receipts(block_hash, receipts.clone());
+
+        let (tx, rx) = oneshot::channel();
+        handle0.send_request(
+            connected,
+            PeerRequest::GetReceipts {
+                request: GetReceipts(vec![block_hash, H256::random()]),
+                response: tx,
+            },
+        );
+
+        // the response ends at the first unknown block
+        let res = rx.await.unwrap();
+        assert_eq!(res.unwrap().0, vec![receipts]);
+    }
+}
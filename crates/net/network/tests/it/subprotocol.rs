@@ -0,0 +1,166 @@
+//! Custom RLPx subprotocol tests
+
+use futures::StreamExt;
+use reth_eth_wire::{
+    capability::{Capability, Protocol},
+    snap::{AccountRange, GetAccountRange},
+    SnapMessage,
+};
+use reth_interfaces::p2p::snap::client::SnapClient;
+use reth_network::{
+    protocol::{ProtocolConnection, ProtocolHandler},
+    snap_requests::SnapRequestHandler,
+    test_utils::unused_tcp_udp,
+    NetworkConfigBuilder, NetworkManager,
+};
+use reth_network_api::{NetworkInfo, Peers};
+use reth_primitives::{bytes::BytesMut, PeerId, H256};
+use reth_provider::test_utils::NoopProvider;
+use reth_rlp::{Decodable, Encodable};
+use secp256k1::SecretKey;
+use tokio::sync::mpsc;
+
+/// Forwards all connections of the subprotocol.
+#[derive(Debug)]
+struct ForwardingHandler(mpsc::UnboundedSender<(PeerId, ProtocolConnection)>);
+
+impl ProtocolHandler for ForwardingHandler {
+    fn on_connection(&self, peer_id: PeerId, conn: ProtocolConnection) {
+        let _ = self.0.send((peer_id, conn));
+    }
+}
+
+async fn spawn_network(
+) -> (reth_network::NetworkHandle, mpsc::UnboundedReceiver<(PeerId, ProtocolConnection)>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let (p2p_addr, disc_addr) = unused_tcp_udp();
+    let config = NetworkConfigBuilder::new(SecretKey::new(&mut rand::thread_rng()))
+        .listener_addr(p2p_addr)
+        .discovery_addr(disc_addr)
+        .disable_discv4_discovery()
+        .disable_dns_discovery()
+        .add_protocol(Protocol::new(Capability::new("ping".into(), 1), 2), ForwardingHandler(tx))
+        .build(NoopProvider::default());
+    let network = NetworkManager::new(config).await.unwrap();
+    let handle = network.handle().clone();
+    tokio::task::spawn(network);
+    (handle, rx)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_subprotocol_connections() {
+    reth_tracing::init_test_tracing();
+
+    let (handle0, mut connections0) = spawn_network().await;
+    let (handle1, mut connections1) = spawn_network().await;
+
+    handle0.add_peer(*handle1.peer_id(), handle1.local_addr());
+
+    let (peer1, conn0) = connections0.recv().await.unwrap();
+    let (peer0, mut conn1) = connections1.recv().await.unwrap();
+    assert_eq!(peer1, *handle1.peer_id());
+    assert_eq!(peer0, *handle0.peer_id());
+    assert_eq!(conn0.capability().name(), "ping");
+
+    conn0.send(BytesMut::from(&[0x01, 0xc0][..])).unwrap();
+    assert_eq!(&conn1.next().await.unwrap()[..], &[0x01, 0xc0]);
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_snap_requests() {
+    reth_tracing::init_test_tracing();
+
+    // the serving peer
+    let (snap_requests, snap_protocol) = SnapRequestHandler::new(NoopProvider::default());
+    let (p2p_addr, disc_addr) = unused_tcp_udp();
+    let config = NetworkConfigBuilder::new(SecretKey::new(&mut rand::thread_rng()))
+        .listener_addr(p2p_addr)
+        .discovery_addr(disc_addr)
+        .disable_discv4_discovery()
+        .disable_dns_discovery()
+        .add_protocol(SnapMessage::protocol(), snap_protocol)
+        .build(NoopProvider::default());
+    let network = NetworkManager::new(config).await.unwrap();
+    let handle0 = network.handle().clone();
+    tokio::task::spawn(network);
+    tokio::task::spawn(snap_requests);
+
+    // the requesting peer
+    let (tx, mut connections) = mpsc::unbounded_channel();
+    let (p2p_addr, disc_addr) = unused_tcp_udp();
+    let config = NetworkConfigBuilder::new(SecretKey::new(&mut rand::thread_rng()))
+        .listener_addr(p2p_addr)
+        .discovery_addr(disc_addr)
+        .disable_discv4_discovery()
+        .disable_dns_discovery()
+        .add_protocol(SnapMessage::protocol(), ForwardingHandler(tx))
+        .build(NoopProvider::default());
+    let network = NetworkManager::new(config).await.unwrap();
+    let handle1 = network.handle().clone();
+    tokio::task::spawn(network);
+
+    handle0.add_peer(*handle1.peer_id(), handle1.local_addr());
+    let (_, mut conn) = connections.recv().await.unwrap();
+
+    let request = SnapMessage::GetAccountRange(GetAccountRange {
+        request_id: 7,
+        root_hash: H256::repeat_byte(0x11),
+        starting_hash: H256::zero(),
+        limit_hash: H256::repeat_byte(0xff),
+        response_bytes: 1024,
+    });
+    let mut out = BytesMut::new();
+    request.encode(&mut out);
+    conn.send(out).unwrap();
+
+    // the state is not available, so the response is empty
+    let response = conn.next().await.unwrap();
+    assert_eq!(
+        SnapMessage::decode(&mut &response[..]).unwrap(),
+        SnapMessage::AccountRange(AccountRange { request_id: 7, ..Default::default() })
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_snap_fetch_client() {
+    reth_tracing::init_test_tracing();
+
+    let mut handles = Vec::new();
+    let mut clients = Vec::new();
+    for _ in 0..2 {
+        let (snap_requests, snap_protocol) = SnapRequestHandler::new(NoopProvider::default());
+        let (p2p_addr, disc_addr) = unused_tcp_udp();
+        let config = NetworkConfigBuilder::new(SecretKey::new(&mut rand::thread_rng()))
+            .listener_addr(p2p_addr)
+            .discovery_addr(disc_addr)
+            .disable_discv4_discovery()
+            .disable_dns_discovery()
+            .add_protocol(SnapMessage::protocol(), snap_protocol)
+            .build(NoopProvider::default());
+        let network = NetworkManager::new(config).await.unwrap();
+        let handle = network.handle().clone();
+        clients.push(snap_requests.fetch_client(handle.peers_handle().clone()));
+        tokio::task::spawn(network);
+        tokio::task::spawn(snap_requests);
+        handles.push(handle);
+    }
+
+    handles[0].add_peer(*handles[1].peer_id(), handles[1].local_addr());
+
+    // the request is queued until the session is established
+    let response = clients[1]
+        .get_account_range(GetAccountRange {
+            request_id: 0,
+            root_hash: H256::repeat_byte(0x11),
+            starting_hash: H256::zero(),
+            limit_hash: H256::repeat_byte(0xff),
+            response_bytes: 1024,
+        })
+        .await
+        .unwrap();
+
+    // the state is not available, so the response is empty
+    assert_eq!(response.peer_id(), *handles[0].peer_id());
+    assert!(response.data().accounts.is_empty());
+    assert!(response.data().proof.is_empty());
+}
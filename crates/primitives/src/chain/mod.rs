@@ -8,7 +8,8 @@ use std::{fmt, str::FromStr};
 // The chain spec module.
 mod spec;
 pub use spec::{
-    AllGenesisFormats, ChainSpec, ChainSpecBuilder, ForkCondition, GOERLI, MAINNET, SEPOLIA,
+    AllGenesisFormats, ChainSpec, ChainSpecBuilder, ConsensusEngine, ForkCondition,
+    CLIQUE_DEFAULT_EPOCH, DEV, DEV_SECRET_KEYS, GOERLI, MAINNET, SEPOLIA,
 };
 
 // The chain info module.
@@ -41,6 +42,11 @@ impl Chain {
         Chain::Named(ethers_core::types::Chain::Sepolia)
     }
 
+    /// Returns the dev chain.
+    pub const fn dev() -> Self {
+        Chain::Named(ethers_core::types::Chain::Dev)
+    }
+
     /// The id of the chain
     pub fn id(&self) -> u64 {
         match self {
@@ -43,6 +43,7 @@ pub static MAINNET: Lazy<ChainSpec> = Lazy::new(|| ChainSpec {
             },
         ),
     ]),
+    engine: ConsensusEngine::Ethash,
 });
 
 /// The Goerli spec
@@ -63,6 +64,7 @@ pub static GOERLI: Lazy<ChainSpec> = Lazy::new(|| ChainSpec {
             ForkCondition::TTD { fork_block: None, total_difficulty: U256::from(10_790_000) },
         ),
     ]),
+    engine: ConsensusEngine::Clique { period: 15, epoch: CLIQUE_DEFAULT_EPOCH },
 });
 
 /// The Sepolia spec
@@ -95,8 +97,53 @@ pub static SEPOLIA: Lazy<ChainSpec> = Lazy::new(|| ChainSpec {
         ),
         (Hardfork::Shanghai, ForkCondition::Timestamp(1677557088)),
     ]),
+    engine: ConsensusEngine::Ethash,
 });
 
+/// Dev testnet specification
+///
+/// Includes 10 prefunded accounts with 1_000_000 ETH each, derived from the well known
+/// `test test test test test test test test test test test junk` mnemonic.
+pub static DEV: Lazy<ChainSpec> = Lazy::new(|| ChainSpec {
+    chain: Chain::dev(),
+    genesis: serde_json::from_str(include_str!("../../res/genesis/dev.json"))
+        .expect("Can't deserialize Dev testnet genesis json"),
+    genesis_hash: None,
+    hardforks: BTreeMap::from([
+        (Hardfork::Frontier, ForkCondition::Block(0)),
+        (Hardfork::Homestead, ForkCondition::Block(0)),
+        (Hardfork::Dao, ForkCondition::Block(0)),
+        (Hardfork::Tangerine, ForkCondition::Block(0)),
+        (Hardfork::SpuriousDragon, ForkCondition::Block(0)),
+        (Hardfork::Byzantium, ForkCondition::Block(0)),
+        (Hardfork::Constantinople, ForkCondition::Block(0)),
+        (Hardfork::Petersburg, ForkCondition::Block(0)),
+        (Hardfork::Istanbul, ForkCondition::Block(0)),
+        (Hardfork::MuirGlacier, ForkCondition::Block(0)),
+        (Hardfork::Berlin, ForkCondition::Block(0)),
+        (Hardfork::London, ForkCondition::Block(0)),
+        (Hardfork::Paris, ForkCondition::TTD { fork_block: Some(0), total_difficulty: U256::ZERO }),
+        (Hardfork::Shanghai, ForkCondition::Timestamp(0)),
+    ]),
+    engine: ConsensusEngine::Ethash,
+});
+
+/// The secret keys of the prefunded accounts of the [DEV] chain.
+///
+/// **These keys are publicly known and must never be used outside of local development.**
+pub const DEV_SECRET_KEYS: [H256; 10] = [
+    H256(hex!("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")),
+    H256(hex!("59c6995e998f97a5a0044966f0945389dc9e86dae88c7a8412f4603b6b78690d")),
+    H256(hex!("5de4111afa1a4b94908f83103eb1f1706367c2e68ca870fc3fb9a804cdab365a")),
+    H256(hex!("7c852118294e51e653712a81e05800f419141751be58f605c371e15141b007a6")),
+    H256(hex!("47e179ec197488593b187f80a00eb0da91f1b9d0b13f8733639f19c30a34926a")),
+    H256(hex!("8b3a350cf5c34c9194ca85829a2df0ec3153be0318b5e2d3348e872092edffba")),
+    H256(hex!("92db14e403b83dfe3df233f83dfa3a0d7096f21ca9b0d6d6b8d88b2b4ec1564e")),
+    H256(hex!("4bbbf85ce3377467afe5d46f804f221813b2bb87f24d81f60f1fcdbf7cbf4356")),
+    H256(hex!("dbda1821b80551c9d65939329250298aa3472ba22feea921c0cf5d620ea67b97")),
+    H256(hex!("2a871d0798f97d79848a013d4936a73bf4cc922c825d33c1cf7073dff6d409c6")),
+];
+
 /// An Ethereum chain specification.
 ///
 /// A chain specification describes:
@@ -121,6 +168,10 @@ pub struct ChainSpec {
 
     /// The active hard forks and their activation conditions
     pub hardforks: BTreeMap<Hardfork, ForkCondition>,
+
+    /// The consensus engine that seals blocks before the merge.
+    #[serde(default)]
+    pub engine: ConsensusEngine,
 }
 
 impl ChainSpec {
@@ -154,6 +205,15 @@ impl ChainSpec {
                 None
             };
 
+        // If cancun is activated at genesis, initialize the header with zero blob gas used and
+        // excess blob gas, and a zero parent beacon block root.
+        let (blob_gas_used, excess_blob_gas, parent_beacon_block_root) =
+            if self.fork(Hardfork::Cancun).active_at_timestamp(self.genesis.timestamp) {
+                (Some(0), Some(0), Some(H256::zero()))
+            } else {
+                (None, None, None)
+            };
+
         Header {
             gas_limit: self.genesis.gas_limit,
             difficulty: self.genesis.difficulty,
@@ -165,6 +225,9 @@ impl ChainSpec {
             beneficiary: self.genesis.coinbase,
             base_fee_per_gas,
             withdrawals_root,
+            blob_gas_used,
+            excess_blob_gas,
+            parent_beacon_block_root,
             ..Default::default()
         }
     }
@@ -301,11 +364,19 @@ impl From<EthersGenesis> for ChainSpec {
 
         hardforks.extend(time_hardforks);
 
+        let engine = genesis.config.clique.map_or(ConsensusEngine::Ethash, |clique| {
+            ConsensusEngine::Clique {
+                period: clique.period.unwrap_or_default(),
+                epoch: clique.epoch.unwrap_or(CLIQUE_DEFAULT_EPOCH),
+            }
+        });
+
         Self {
             chain: genesis.config.chain_id.into(),
             genesis: genesis_block,
             genesis_hash: None,
             hardforks,
+            engine,
         }
     }
 }
@@ -347,6 +418,7 @@ pub struct ChainSpecBuilder {
     chain: Option<Chain>,
     genesis: Option<Genesis>,
     hardforks: BTreeMap<Hardfork, ForkCondition>,
+    engine: ConsensusEngine,
 }
 
 impl ChainSpecBuilder {
@@ -356,6 +428,7 @@ impl ChainSpecBuilder {
             chain: Some(MAINNET.chain),
             genesis: Some(MAINNET.genesis.clone()),
             hardforks: MAINNET.hardforks.clone(),
+            engine: MAINNET.engine,
         }
     }
 
@@ -371,6 +444,12 @@ impl ChainSpecBuilder {
         self
     }
 
+    /// Set the consensus engine used before the merge.
+    pub fn engine(mut self, engine: ConsensusEngine) -> Self {
+        self.engine = engine;
+        self
+    }
+
     /// Add the given fork with the given activation condition to the spec.
     pub fn with_fork(mut self, fork: Hardfork, condition: ForkCondition) -> Self {
         self.hardforks.insert(fork, condition);
@@ -456,6 +535,13 @@ impl ChainSpecBuilder {
         self
     }
 
+    /// Enable Cancun at genesis.
+    pub fn cancun_activated(mut self) -> Self {
+        self = self.shanghai_activated();
+        self.hardforks.insert(Hardfork::Cancun, ForkCondition::Timestamp(0));
+        self
+    }
+
     /// Build the resulting [`ChainSpec`].
     ///
     /// # Panics
@@ -468,6 +554,7 @@ impl ChainSpecBuilder {
             genesis: self.genesis.expect("The genesis is required"),
             genesis_hash: None,
             hardforks: self.hardforks,
+            engine: self.engine,
         }
     }
 }
@@ -478,10 +565,32 @@ impl From<&ChainSpec> for ChainSpecBuilder {
             chain: Some(value.chain),
             genesis: Some(value.genesis.clone()),
             hardforks: value.hardforks.clone(),
+            engine: value.engine,
         }
     }
 }
 
+/// The default number of blocks after which clique checkpoints and resets pending votes.
+pub const CLIQUE_DEFAULT_EPOCH: u64 = 30_000;
+
+/// The consensus engine that seals blocks before the merge.
+///
+/// After the merge (Paris) every chain is secured by proof-of-stake and blocks are validated
+/// according to [EIP-3675](https://eips.ethereum.org/EIPS/eip-3675) regardless of this setting.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+pub enum ConsensusEngine {
+    /// Proof-of-work using ethash.
+    #[default]
+    Ethash,
+    /// Proof-of-authority, see [EIP-225](https://eips.ethereum.org/EIPS/eip-225).
+    Clique {
+        /// Minimum number of seconds between two consecutive blocks.
+        period: u64,
+        /// Number of blocks after which to checkpoint and reset the pending votes.
+        epoch: u64,
+    },
+}
+
 /// The condition at which a fork is activated.
 #[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum ForkCondition {
@@ -667,6 +776,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn cancun_genesis_header() {
+        let spec =
+            ChainSpecBuilder::mainnet().genesis(Genesis::default()).cancun_activated().build();
+        let header = spec.genesis_header();
+        assert_eq!(header.blob_gas_used, Some(0));
+        assert_eq!(header.excess_blob_gas, Some(0));
+        assert_eq!(header.parent_beacon_block_root, Some(H256::zero()));
+
+        let spec =
+            ChainSpecBuilder::mainnet().genesis(Genesis::default()).shanghai_activated().build();
+        let header = spec.genesis_header();
+        assert_eq!(header.blob_gas_used, None);
+        assert_eq!(header.excess_blob_gas, None);
+        assert_eq!(header.parent_beacon_block_root, None);
+    }
+
     #[test]
     fn mainnet_forkids() {
         test_fork_ids(
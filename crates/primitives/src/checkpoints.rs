@@ -0,0 +1,96 @@
+use crate::{trie::HashBuilderState, BlockNumber, H256};
+use bytes::Buf;
+use reth_codecs::{derive_arbitrary, Compact};
+use serde::{Deserialize, Serialize};
+
+/// Saves the progress of a Merkle stage that is rebuilding the state trie from scratch.
+///
+/// The accounts are added to the trie in the order of their hashed address, so the rebuild can
+/// continue after the last added account once the hash builder is restored.
+#[derive_arbitrary(compact)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleCheckpoint {
+    /// The block whose state root is being computed.
+    pub target_block: BlockNumber,
+    /// The hashed address of the last account added to the trie.
+    pub last_account_key: H256,
+    /// The state of the hash builder after the last account was added.
+    pub state: HashBuilderState,
+}
+
+impl Compact for MerkleCheckpoint {
+    fn to_compact(self, buf: &mut impl bytes::BufMut) -> usize {
+        buf.put_u64(self.target_block);
+        buf.put_slice(self.last_account_key.as_bytes());
+        8 + 32 + self.state.to_compact(buf)
+    }
+
+    fn from_compact(mut buf: &[u8], len: usize) -> (Self, &[u8])
+    where
+        Self: Sized,
+    {
+        let target_block = buf.get_u64();
+        let last_account_key = H256::from_slice(&buf[..32]);
+        buf.advance(32);
+        let (state, buf) = HashBuilderState::from_compact(buf, len - 8 - 32);
+        (Self { target_block, last_account_key, state }, buf)
+    }
+}
+
+/// Saves the progress of a snap sync that downloads the state at a pivot block.
+///
+/// The accounts are downloaded in the order of their hashed address, with the storage and
+/// bytecode of each range of accounts, so the download can continue at `next_account`.
+#[derive_arbitrary(compact)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapSyncCheckpoint {
+    /// The block whose state is downloaded.
+    pub pivot: BlockNumber,
+    /// The state root of the pivot block.
+    pub state_root: H256,
+    /// The hashed address at which the download continues, `None` once all accounts have been
+    /// downloaded.
+    pub next_account: Option<H256>,
+}
+
+impl SnapSyncCheckpoint {
+    /// The key the checkpoint is saved under in the stage progress table, which is the id of the
+    /// snap state stage.
+    ///
+    /// The checkpoint exists once a snap sync started, the state is then read from the hashed
+    /// state since the plain state is incomplete.
+    pub const KEY: &'static str = "SnapState";
+}
+
+impl Compact for SnapSyncCheckpoint {
+    fn to_compact(self, buf: &mut impl bytes::BufMut) -> usize {
+        buf.put_u64(self.pivot);
+        buf.put_slice(self.state_root.as_bytes());
+        match self.next_account {
+            Some(next_account) => {
+                buf.put_u8(1);
+                buf.put_slice(next_account.as_bytes());
+                8 + 32 + 1 + 32
+            }
+            None => {
+                buf.put_u8(0);
+                8 + 32 + 1
+            }
+        }
+    }
+
+    fn from_compact(mut buf: &[u8], _len: usize) -> (Self, &[u8])
+    where
+        Self: Sized,
+    {
+        let pivot = buf.get_u64();
+        let state_root = H256::from_slice(&buf[..32]);
+        buf.advance(32);
+        let next_account = (buf.get_u8() == 1).then(|| {
+            let next_account = H256::from_slice(&buf[..32]);
+            buf.advance(32);
+            next_account
+        });
+        (Self { pivot, state_root, next_account }, buf)
+    }
+}
@@ -1,6 +1,6 @@
 //! Ethereum protocol-related constants
 
-use crate::H256;
+use crate::{Address, H160, H256};
 use hex_literal::hex;
 
 /// The first four bytes of the call data for a function call specifies the function to be called.
@@ -41,3 +41,45 @@ pub const EMPTY_OMMER_ROOT: H256 =
 /// Withdrawals root of empty withdrawals set.
 pub const EMPTY_WITHDRAWALS: H256 =
     H256(hex!("56e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421"));
+
+/// Gas consumption of a single data blob as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const BLOB_GAS_PER_BLOB: u64 = 131_072;
+
+/// Target number of blobs per block as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const TARGET_BLOBS_PER_BLOCK: u64 = 3;
+
+/// Max number of blobs per block as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const MAX_BLOBS_PER_BLOCK: u64 = 6;
+
+/// Target consumable blob gas per block as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const TARGET_BLOB_GAS_PER_BLOCK: u64 = TARGET_BLOBS_PER_BLOCK * BLOB_GAS_PER_BLOB;
+
+/// Maximum consumable blob gas per block as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const MAX_BLOB_GAS_PER_BLOCK: u64 = MAX_BLOBS_PER_BLOCK * BLOB_GAS_PER_BLOB;
+
+/// Minimum gas price for a data blob as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const BLOB_TX_MIN_BLOB_GASPRICE: u128 = 1;
+
+/// Controls the maximum rate of change for blob gas price as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const BLOB_GASPRICE_UPDATE_FRACTION: u128 = 3_338_477;
+
+/// Version byte of a KZG commitment's versioned hash as defined in [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)
+pub const VERSIONED_HASH_VERSION_KZG: u8 = 0x01;
+
+/// Number of field elements in a single data blob.
+pub const FIELD_ELEMENTS_PER_BLOB: usize = 4096;
+
+/// Size of a single data blob in bytes.
+pub const BYTES_PER_BLOB: usize = FIELD_ELEMENTS_PER_BLOB * 32;
+
+/// Size of a KZG commitment or proof in bytes.
+pub const BYTES_PER_COMMITMENT: usize = 48;
+
+/// Address of the beacon roots contract as defined in [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788)
+pub const BEACON_ROOTS_ADDRESS: Address = H160(hex!("000f3df6d732807ef1319fb7b8bb8522d0beac02"));
+
+/// Caller of system calls as defined in [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788)
+pub const SYSTEM_ADDRESS: Address = H160(hex!("fffffffffffffffffffffffffffffffffffffffe"));
+
+/// Gas limit of system calls as defined in [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788)
+pub const SYSTEM_CALL_GAS_LIMIT: u64 = 30_000_000;
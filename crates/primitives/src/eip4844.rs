@@ -0,0 +1,128 @@
+//! Helpers for working with [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844) blob data.
+
+use crate::{
+    constants::{
+        BLOB_GASPRICE_UPDATE_FRACTION, BLOB_TX_MIN_BLOB_GASPRICE, TARGET_BLOB_GAS_PER_BLOCK,
+        VERSIONED_HASH_VERSION_KZG,
+    },
+    H256,
+};
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+pub use c_kzg::{Error as KzgError, KzgSettings};
+
+/// Loads the KZG trusted setup from a file in the format of the
+/// [c-kzg-4844](https://github.com/ethereum/c-kzg-4844) `trusted_setup.txt`.
+pub fn load_trusted_setup(path: &Path) -> Result<KzgSettings, KzgError> {
+    KzgSettings::load_trusted_setup_file(path.to_path_buf())
+}
+
+/// Calculates the versioned hash for a KZG commitment.
+///
+/// This is `VERSIONED_HASH_VERSION_KZG || sha256(commitment)[1..]`.
+pub fn kzg_to_versioned_hash(commitment: &[u8]) -> H256 {
+    let mut hash: [u8; 32] = Sha256::digest(commitment).into();
+    hash[0] = VERSIONED_HASH_VERSION_KZG;
+    H256(hash)
+}
+
+/// Calculates the `excess_blob_gas` of a block from its parent's excess blob gas and blob gas
+/// used.
+///
+/// See also [`calc_excess_blob_gas`](https://eips.ethereum.org/EIPS/eip-4844#header-extension)
+pub fn calculate_excess_blob_gas(parent_excess_blob_gas: u64, parent_blob_gas_used: u64) -> u64 {
+    (parent_excess_blob_gas + parent_blob_gas_used).saturating_sub(TARGET_BLOB_GAS_PER_BLOCK)
+}
+
+/// Calculates the blob gas price from the header's excess blob gas field.
+///
+/// See also [`get_blob_gasprice`](https://eips.ethereum.org/EIPS/eip-4844#gas-accounting)
+pub fn calculate_blob_gasprice(excess_blob_gas: u64) -> u128 {
+    fake_exponential(
+        BLOB_TX_MIN_BLOB_GASPRICE,
+        excess_blob_gas as u128,
+        BLOB_GASPRICE_UPDATE_FRACTION,
+    )
+}
+
+/// Approximates `factor * e ** (numerator / denominator)` using Taylor expansion.
+///
+/// See also [`fake_exponential`](https://eips.ethereum.org/EIPS/eip-4844#helpers)
+///
+/// # Panics
+///
+/// Panics if `denominator` is zero.
+pub fn fake_exponential(factor: u128, numerator: u128, denominator: u128) -> u128 {
+    assert_ne!(denominator, 0, "attempt to divide by zero");
+
+    let mut i = 1;
+    let mut output = 0;
+    let mut numerator_accum = factor * denominator;
+    while numerator_accum > 0 {
+        output += numerator_accum;
+
+        // Denominator is asserted as not zero at the start of the function.
+        numerator_accum = (numerator_accum * numerator) / (denominator * i);
+        i += 1;
+    }
+    output / denominator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::BLOB_GAS_PER_BLOB;
+
+    #[test]
+    fn test_calculate_excess_blob_gas() {
+        for (excess, blobs, expected) in [
+            (0, 0, 0),
+            (0, 1, 0),
+            (0, 3, 0),
+            (0, 4, BLOB_GAS_PER_BLOB),
+            (BLOB_GAS_PER_BLOB, 3, BLOB_GAS_PER_BLOB),
+            (BLOB_GAS_PER_BLOB, 2, 0),
+            (TARGET_BLOB_GAS_PER_BLOCK, 6, 2 * TARGET_BLOB_GAS_PER_BLOCK),
+        ] {
+            assert_eq!(calculate_excess_blob_gas(excess, blobs * BLOB_GAS_PER_BLOB), expected);
+        }
+    }
+
+    #[test]
+    fn test_fake_exponential() {
+        // https://github.com/ethereum/go-ethereum/blob/master/consensus/misc/eip4844_test.go
+        for (factor, numerator, denominator, expected) in [
+            (1, 0, 1, 1),
+            (38493, 0, 1000, 38493),
+            (0, 1234, 2345, 0),
+            (1, 2, 1, 6),
+            (1, 4, 2, 6),
+            (1, 3, 1, 16),
+            (1, 6, 2, 18),
+            (1, 4, 1, 49),
+            (1, 8, 2, 50),
+            (10, 8, 2, 542),
+            (11, 8, 2, 596),
+            (1, 5, 1, 136),
+            (1, 5, 2, 11),
+            (2, 5, 2, 23),
+        ] {
+            assert_eq!(fake_exponential(factor, numerator, denominator), expected);
+        }
+    }
+
+    #[test]
+    fn test_blob_gasprice() {
+        assert_eq!(calculate_blob_gasprice(0), 1);
+        assert_eq!(calculate_blob_gasprice(2314057), 1);
+        assert_eq!(calculate_blob_gasprice(2314058), 2);
+        assert_eq!(calculate_blob_gasprice(10 * 1024 * 1024), 23);
+    }
+
+    #[test]
+    fn test_versioned_hash_version() {
+        let hash = kzg_to_versioned_hash(&[0u8; 48]);
+        assert_eq!(hash[0], VERSIONED_HASH_VERSION_KZG);
+    }
+}
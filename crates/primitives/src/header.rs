@@ -1,4 +1,5 @@
 use crate::{
+    eip4844::{calculate_blob_gasprice, calculate_excess_blob_gas},
     keccak256,
     proofs::{EMPTY_LIST_HASH, EMPTY_ROOT},
     BlockHash, BlockNumber, Bloom, Bytes, H160, H256, U256,
@@ -6,7 +7,7 @@ use crate::{
 use bytes::{Buf, BufMut, BytesMut};
 use ethers_core::types::{Block, H256 as EthersH256, H64};
 use reth_codecs::{add_arbitrary_tests, derive_arbitrary, main_codec, Compact};
-use reth_rlp::{length_of_length, Decodable, Encodable, EMPTY_STRING_CODE};
+use reth_rlp::{length_of_length, Decodable, Encodable, EMPTY_LIST_CODE, EMPTY_STRING_CODE};
 use serde::{Deserialize, Serialize};
 use std::ops::Deref;
 
@@ -87,6 +88,19 @@ pub struct Header {
     /// above the gas target, and decreasing when blocks are below the gas target. The base fee per
     /// gas is burned.
     pub base_fee_per_gas: Option<u64>,
+    /// The total amount of blob gas consumed by the transactions within the block, added in
+    /// EIP-4844.
+    pub blob_gas_used: Option<u64>,
+    /// A running total of blob gas consumed in excess of the target, prior to the block. Blocks
+    /// with above-target blob gas consumption increase this value, blocks with below-target blob
+    /// gas consumption decrease it (bounded at 0). This was added in EIP-4844.
+    pub excess_blob_gas: Option<u64>,
+    /// The hash of the parent beacon block's root is included in execution blocks, as proposed by
+    /// EIP-4788.
+    ///
+    /// This enables trust-minimized access to consensus state, supporting staking pools, bridges,
+    /// and more.
+    pub parent_beacon_block_root: Option<H256>,
     /// An arbitrary byte array containing data relevant to this block. This must be 32 bytes or
     /// fewer; formally Hx.
     pub extra_data: Bytes,
@@ -112,6 +126,9 @@ impl Default for Header {
             nonce: 0,
             base_fee_per_gas: None,
             withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
         }
     }
 }
@@ -158,6 +175,24 @@ impl Header {
         self.seal(hash)
     }
 
+    /// Returns `true` if any of the fields introduced in Cancun is set.
+    fn has_cancun_fields(&self) -> bool {
+        self.blob_gas_used.is_some() ||
+            self.excess_blob_gas.is_some() ||
+            self.parent_beacon_block_root.is_some()
+    }
+
+    /// Returns the blob gas price of this block, if the header contains the excess blob gas field.
+    pub fn blob_gasprice(&self) -> Option<u128> {
+        self.excess_blob_gas.map(calculate_blob_gasprice)
+    }
+
+    /// Returns the excess blob gas of the block following this one, if the header contains the
+    /// EIP-4844 fields.
+    pub fn next_block_excess_blob_gas(&self) -> Option<u64> {
+        Some(calculate_excess_blob_gas(self.excess_blob_gas?, self.blob_gas_used?))
+    }
+
     fn header_payload_length(&self) -> usize {
         let mut length = 0;
         length += self.parent_hash.length();
@@ -178,11 +213,26 @@ impl Header {
 
         if let Some(base_fee) = self.base_fee_per_gas {
             length += U256::from(base_fee).length();
-        } else if self.withdrawals_root.is_some() {
+        } else if self.withdrawals_root.is_some() || self.has_cancun_fields() {
             length += 1; // EMTY STRING CODE
         }
         if let Some(root) = self.withdrawals_root {
             length += root.length();
+        } else if self.has_cancun_fields() {
+            length += 1; // EMTY STRING CODE
+        }
+        if let Some(blob_gas_used) = self.blob_gas_used {
+            length += U256::from(blob_gas_used).length();
+        } else if self.excess_blob_gas.is_some() || self.parent_beacon_block_root.is_some() {
+            length += 1; // EMPTY LIST CODE
+        }
+        if let Some(excess_blob_gas) = self.excess_blob_gas {
+            length += U256::from(excess_blob_gas).length();
+        } else if self.parent_beacon_block_root.is_some() {
+            length += 1; // EMPTY LIST CODE
+        }
+        if let Some(root) = self.parent_beacon_block_root {
+            length += root.length();
         }
 
         length
@@ -211,15 +261,41 @@ impl Encodable for Header {
         H64::from_low_u64_be(self.nonce).encode(out);
 
         // Encode base fee. Put empty string if base fee is missing,
-        // but withdrawals root is present.
+        // but withdrawals root or any of the cancun fields are present.
         if let Some(ref base_fee) = self.base_fee_per_gas {
             U256::from(*base_fee).encode(out);
-        } else if self.withdrawals_root.is_some() {
+        } else if self.withdrawals_root.is_some() || self.has_cancun_fields() {
             out.put_u8(EMPTY_STRING_CODE);
         }
 
+        // Encode withdrawals root. Put empty string if withdrawals root is missing,
+        // but any of the cancun fields are present.
         if let Some(ref root) = self.withdrawals_root {
             root.encode(out);
+        } else if self.has_cancun_fields() {
+            out.put_u8(EMPTY_STRING_CODE);
+        }
+
+        // Encode blob gas used. Put empty list if blob gas used is missing,
+        // but excess blob gas or parent beacon block root is present.
+        //
+        // NOTE: an empty string would be ambiguous here, since it is also the encoding of `0`.
+        if let Some(ref blob_gas_used) = self.blob_gas_used {
+            U256::from(*blob_gas_used).encode(out);
+        } else if self.excess_blob_gas.is_some() || self.parent_beacon_block_root.is_some() {
+            out.put_u8(EMPTY_LIST_CODE);
+        }
+
+        // Encode excess blob gas. Put empty list if excess blob gas is missing,
+        // but parent beacon block root is present.
+        if let Some(ref excess_blob_gas) = self.excess_blob_gas {
+            U256::from(*excess_blob_gas).encode(out);
+        } else if self.parent_beacon_block_root.is_some() {
+            out.put_u8(EMPTY_LIST_CODE);
+        }
+
+        if let Some(ref root) = self.parent_beacon_block_root {
+            root.encode(out);
         }
     }
 
@@ -256,6 +332,9 @@ impl Decodable for Header {
             nonce: H64::decode(buf)?.to_low_u64_be(),
             base_fee_per_gas: None,
             withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
         };
         if started_len - buf.len() < rlp_head.payload_length {
             if buf.first().map(|b| *b == EMPTY_STRING_CODE).unwrap_or_default() {
@@ -265,7 +344,28 @@ impl Decodable for Header {
             }
         }
         if started_len - buf.len() < rlp_head.payload_length {
-            this.withdrawals_root = Some(Decodable::decode(buf)?);
+            if buf.first().map(|b| *b == EMPTY_STRING_CODE).unwrap_or_default() {
+                buf.advance(1)
+            } else {
+                this.withdrawals_root = Some(Decodable::decode(buf)?);
+            }
+        }
+        if started_len - buf.len() < rlp_head.payload_length {
+            if buf.first().map(|b| *b == EMPTY_LIST_CODE).unwrap_or_default() {
+                buf.advance(1)
+            } else {
+                this.blob_gas_used = Some(U256::decode(buf)?.to::<u64>());
+            }
+        }
+        if started_len - buf.len() < rlp_head.payload_length {
+            if buf.first().map(|b| *b == EMPTY_LIST_CODE).unwrap_or_default() {
+                buf.advance(1)
+            } else {
+                this.excess_blob_gas = Some(U256::decode(buf)?.to::<u64>());
+            }
+        }
+        if started_len - buf.len() < rlp_head.payload_length {
+            this.parent_beacon_block_root = Some(Decodable::decode(buf)?);
         }
         let consumed = started_len - buf.len();
         if consumed != rlp_head.payload_length {
@@ -344,6 +444,9 @@ impl From<&Block<EthersH256>> for Header {
             ommers_hash: block.uncles_hash.0.into(),
             gas_used: block.gas_used.as_u64(),
             withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
             logs_bloom: block.logs_bloom.unwrap_or_default().0.into(),
         }
     }
@@ -536,6 +639,9 @@ mod tests {
             nonce: 0,
             base_fee_per_gas: Some(0x036b_u64),
             withdrawals_root: None,
+            blob_gas_used: None,
+            excess_blob_gas: None,
+            parent_beacon_block_root: None,
         };
         assert_eq!(header.hash_slow(), expected_hash);
     }
@@ -614,6 +720,31 @@ mod tests {
         assert_eq!(header.hash_slow(), expected_hash);
     }
 
+    #[test]
+    fn test_encode_decode_cancun_header() {
+        let mut header = Header {
+            base_fee_per_gas: Some(0x09),
+            withdrawals_root: Some(H256::random()),
+            blob_gas_used: Some(0x020000),
+            excess_blob_gas: Some(0),
+            parent_beacon_block_root: Some(H256::random()),
+            ..Default::default()
+        };
+        let mut data = vec![];
+        header.encode(&mut data);
+        assert_eq!(header.length(), data.len());
+        assert_eq!(<Header as Decodable>::decode(&mut data.as_slice()).unwrap(), header);
+
+        // missing fields in between must survive the roundtrip, including zero values
+        header.base_fee_per_gas = None;
+        header.withdrawals_root = None;
+        header.blob_gas_used = None;
+        let mut data = vec![];
+        header.encode(&mut data);
+        assert_eq!(header.length(), data.len());
+        assert_eq!(<Header as Decodable>::decode(&mut data.as_slice()).unwrap(), header);
+    }
+
     #[test]
     fn sanity_direction() {
         let reverse = true;
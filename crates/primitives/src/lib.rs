@@ -14,8 +14,10 @@ mod bits;
 mod block;
 pub mod bloom;
 mod chain;
+mod checkpoints;
 pub mod constants;
 pub mod contract;
+pub mod eip4844;
 mod error;
 pub mod filter;
 mod forkid;
@@ -27,9 +29,11 @@ mod integer_list;
 mod log;
 mod net;
 mod peer;
+mod prune;
 mod receipt;
 mod storage;
 mod transaction;
+pub mod trie;
 mod withdrawal;
 
 /// Helper function for calculating Merkle proofs and hashes
@@ -40,9 +44,10 @@ pub use bits::H512;
 pub use block::{Block, BlockHashOrNumber, BlockId, BlockNumberOrTag, SealedBlock};
 pub use bloom::Bloom;
 pub use chain::{
-    AllGenesisFormats, Chain, ChainInfo, ChainSpec, ChainSpecBuilder, ForkCondition, GOERLI,
-    MAINNET, SEPOLIA,
+    AllGenesisFormats, Chain, ChainInfo, ChainSpec, ChainSpecBuilder, ConsensusEngine,
+    ForkCondition, CLIQUE_DEFAULT_EPOCH, DEV, DEV_SECRET_KEYS, GOERLI, MAINNET, SEPOLIA,
 };
+pub use checkpoints::{MerkleCheckpoint, SnapSyncCheckpoint};
 pub use constants::{
     EMPTY_OMMER_ROOT, GOERLI_GENESIS, KECCAK_EMPTY, MAINNET_GENESIS, SEPOLIA_GENESIS,
 };
@@ -55,15 +60,17 @@ pub use integer_list::IntegerList;
 pub use log::Log;
 pub use net::NodeRecord;
 pub use peer::{PeerId, WithPeerId};
+pub use prune::{PruneMode, PruneModes, PrunePart, MINIMUM_PRUNING_DISTANCE};
 pub use receipt::Receipt;
 pub use revm_primitives::JumpMap;
 pub use serde_helper::JsonU256;
 pub use storage::{StorageEntry, StorageTrieEntry};
 pub use transaction::{
-    AccessList, AccessListItem, AccessListWithGasUsed, FromRecoveredTransaction,
-    IntoRecoveredTransaction, Signature, Transaction, TransactionKind, TransactionSigned,
-    TransactionSignedEcRecovered, TxEip1559, TxEip2930, TxLegacy, TxType, EIP1559_TX_TYPE_ID,
-    EIP2930_TX_TYPE_ID, LEGACY_TX_TYPE_ID,
+    AccessList, AccessListItem, AccessListWithGasUsed, BlobTransaction, BlobTransactionSidecar,
+    FromRecoveredTransaction, IntoRecoveredTransaction, PooledTransactionsElement, Signature,
+    Transaction, TransactionKind, TransactionSigned, TransactionSignedEcRecovered, TxEip1559,
+    TxEip2930, TxEip4844, TxLegacy, TxType, EIP1559_TX_TYPE_ID, EIP2930_TX_TYPE_ID,
+    EIP4844_TX_TYPE_ID, LEGACY_TX_TYPE_ID,
 };
 pub use withdrawal::Withdrawal;
 
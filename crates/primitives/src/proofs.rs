@@ -68,6 +68,20 @@ pub fn calculate_receipt_root<'a>(receipts: impl Iterator<Item = &'a Receipt>) -
     }))
 }
 
+/// Calculates the receipt root for a pre-Byzantium header.
+///
+/// Before [EIP-658](https://eips.ethereum.org/EIPS/eip-658) every receipt contained the state root
+/// after its transaction, so each receipt has to be paired with that intermediate root.
+pub fn calculate_receipt_root_with_state_roots<'a>(
+    receipts: impl Iterator<Item = (&'a Receipt, H256)>,
+) -> H256 {
+    ordered_trie_root::<KeccakHasher, _>(receipts.map(|(receipt, state_root)| {
+        let mut receipt_rlp = Vec::new();
+        receipt.encode_with_state_root(state_root, &mut receipt_rlp);
+        receipt_rlp
+    }))
+}
+
 /// Calculates the log root for headers.
 pub fn calculate_log_root<'a>(logs: impl Iterator<Item = &'a Log> + Clone) -> H256 {
     //https://github.com/ethereum/go-ethereum/blob/356bbe343a30789e77bb38f25983c8f2f2bfbb47/cmd/evm/internal/t8ntool/execution.go#L255
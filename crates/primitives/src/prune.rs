@@ -0,0 +1,126 @@
+use crate::BlockNumber;
+use serde::{Deserialize, Serialize};
+
+/// The minimum number of most recent blocks that are never pruned, so that the pipeline can still
+/// unwind them on a reorg.
+pub const MINIMUM_PRUNING_DISTANCE: u64 = 128;
+
+/// Determines which blocks of a part of the history are pruned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PruneMode {
+    /// Keep only the history of the last `N` blocks.
+    Distance(u64),
+    /// Prune the history of all blocks before this one.
+    Before(BlockNumber),
+}
+
+impl PruneMode {
+    /// Returns the highest block whose history is pruned when the chain is at the given tip, or
+    /// `None` if nothing is pruned yet.
+    ///
+    /// The [MINIMUM_PRUNING_DISTANCE] most recent blocks are never pruned.
+    pub fn prune_target_block(&self, tip: BlockNumber) -> Option<BlockNumber> {
+        let target = match *self {
+            PruneMode::Distance(distance) => tip.checked_sub(distance)?,
+            PruneMode::Before(block) => block.checked_sub(1)?,
+        };
+        Some(target.min(tip.checked_sub(MINIMUM_PRUNING_DISTANCE)?))
+    }
+}
+
+/// A part of the history that can be pruned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum PrunePart {
+    /// The transaction senders in `TxSenders`.
+    SenderRecovery = 0,
+    /// The transaction hash to number lookup in `TxHashNumber`.
+    TransactionLookup = 1,
+    /// The transaction receipts in `Receipts`.
+    Receipts = 2,
+    /// The account changesets in `AccountChangeSet` and their index in `AccountHistory`.
+    AccountHistory = 3,
+    /// The storage changesets in `StorageChangeSet` and their index in `StorageHistory`.
+    StorageHistory = 4,
+}
+
+impl PrunePart {
+    /// All parts of the history that can be pruned.
+    pub const ALL: [PrunePart; 5] = [
+        PrunePart::SenderRecovery,
+        PrunePart::TransactionLookup,
+        PrunePart::Receipts,
+        PrunePart::AccountHistory,
+        PrunePart::StorageHistory,
+    ];
+}
+
+impl TryFrom<u8> for PrunePart {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        PrunePart::ALL.get(value as usize).copied().ok_or(value)
+    }
+}
+
+/// The pruning settings of each part of the history.
+///
+/// Parts without a [PruneMode] are kept in full, so the default settings describe an archive
+/// node.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PruneModes {
+    /// Pruning of the transaction senders.
+    pub sender_recovery: Option<PruneMode>,
+    /// Pruning of the transaction hash to number lookup.
+    pub transaction_lookup: Option<PruneMode>,
+    /// Pruning of the transaction receipts.
+    pub receipts: Option<PruneMode>,
+    /// Pruning of the account changesets and history index.
+    pub account_history: Option<PruneMode>,
+    /// Pruning of the storage changesets and history index.
+    pub storage_history: Option<PruneMode>,
+}
+
+impl PruneModes {
+    /// Returns the pruning settings of the given part, if it is pruned at all.
+    pub fn get(&self, part: PrunePart) -> Option<PruneMode> {
+        match part {
+            PrunePart::SenderRecovery => self.sender_recovery,
+            PrunePart::TransactionLookup => self.transaction_lookup,
+            PrunePart::Receipts => self.receipts,
+            PrunePart::AccountHistory => self.account_history,
+            PrunePart::StorageHistory => self.storage_history,
+        }
+    }
+
+    /// Returns true if no part of the history is pruned.
+    pub fn is_archive(&self) -> bool {
+        PrunePart::ALL.iter().all(|part| self.get(*part).is_none())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prune_target_block() {
+        let tip = 1_000;
+        assert_eq!(PruneMode::Distance(200).prune_target_block(tip), Some(800));
+        assert_eq!(PruneMode::Distance(10).prune_target_block(tip), Some(872));
+        assert_eq!(PruneMode::Distance(2_000).prune_target_block(tip), None);
+        assert_eq!(PruneMode::Before(500).prune_target_block(tip), Some(499));
+        assert_eq!(PruneMode::Before(0).prune_target_block(tip), None);
+        assert_eq!(PruneMode::Before(500).prune_target_block(100), None);
+    }
+
+    #[test]
+    fn prune_part_roundtrip() {
+        for part in PrunePart::ALL {
+            assert_eq!(PrunePart::try_from(part as u8), Ok(part));
+        }
+        assert_eq!(PrunePart::try_from(5), Err(5));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{Bloom, Log, TxType};
+use crate::{Bloom, Log, TxType, H256};
 use bytes::{Buf, BufMut, BytesMut};
 use reth_codecs::{main_codec, Compact};
 use reth_rlp::{length_of_length, Decodable, Encodable};
@@ -67,11 +67,33 @@ impl Receipt {
             TxType::EIP1559 => {
                 out.put_u8(0x02);
             }
+            TxType::EIP4844 => {
+                out.put_u8(0x03);
+            }
             _ => unreachable!("legacy handled; qed."),
         }
         out.put_slice(payload.as_ref());
     }
 
+    /// Encodes the receipt in its pre-Byzantium form, which commits to the intermediate state root
+    /// after the transaction instead of the status code.
+    ///
+    /// See [EIP-658](https://eips.ethereum.org/EIPS/eip-658). Typed transactions did not exist
+    /// before Byzantium, so the receipt is always encoded as a plain list.
+    pub fn encode_with_state_root(&self, state_root: H256, out: &mut dyn BufMut) {
+        let mut rlp_head = reth_rlp::Header { list: true, payload_length: 0 };
+        rlp_head.payload_length += state_root.length();
+        rlp_head.payload_length += self.cumulative_gas_used.length();
+        rlp_head.payload_length += self.bloom.length();
+        rlp_head.payload_length += self.logs.length();
+
+        rlp_head.encode(out);
+        state_root.encode(out);
+        self.cumulative_gas_used.encode(out);
+        self.bloom.encode(out);
+        self.logs.encode(out);
+    }
+
     /// Returns the length of the receipt data.
     fn receipt_length(&self) -> usize {
         let rlp_head = self.receipt_rlp_header();
@@ -109,7 +131,7 @@ impl Encodable for Receipt {
     fn length(&self) -> usize {
         let mut payload_len = self.receipt_length();
         // account for eip-2718 type prefix and set the list
-        if matches!(self.tx_type, TxType::EIP1559 | TxType::EIP2930) {
+        if matches!(self.tx_type, TxType::EIP1559 | TxType::EIP2930 | TxType::EIP4844) {
             payload_len += 1;
             // we include a string header for typed receipts, so include the length here
             payload_len += length_of_length(payload_len);
@@ -144,6 +166,9 @@ impl Decodable for Receipt {
                 } else if receipt_type == 0x02 {
                     buf.advance(1);
                     Self::decode_receipt(buf, TxType::EIP1559)
+                } else if receipt_type == 0x03 {
+                    buf.advance(1);
+                    Self::decode_receipt(buf, TxType::EIP4844)
                 } else {
                     Err(reth_rlp::DecodeError::Custom("invalid receipt type"))
                 }
@@ -228,4 +253,37 @@ mod tests {
         let receipt = Receipt::decode(&mut &data[..]).unwrap();
         assert_eq!(receipt, expected);
     }
+
+    #[test]
+    fn encode_receipt_with_state_root() {
+        let expected = hex!("f90186a0d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f054401b9010000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000f85ff85d940000000000000000000000000000000000000011f842a0000000000000000000000000000000000000000000000000000000000000deada0000000000000000000000000000000000000000000000000000000000000beef830100ff");
+
+        let mut data = vec![];
+        let receipt = Receipt {
+            tx_type: TxType::Legacy,
+            bloom: [0; 256].into(),
+            cumulative_gas_used: 0x1u64,
+            logs: vec![Log {
+                address: Address::from_str("0000000000000000000000000000000000000011").unwrap(),
+                topics: vec![
+                    H256::from_str(
+                        "000000000000000000000000000000000000000000000000000000000000dead",
+                    )
+                    .unwrap(),
+                    H256::from_str(
+                        "000000000000000000000000000000000000000000000000000000000000beef",
+                    )
+                    .unwrap(),
+                ],
+                data: Bytes::from_str("0100ff").unwrap().0.into(),
+            }],
+            success: false,
+        };
+        let state_root =
+            H256(hex!("d7f8974fb5ac78d9ac099b9ad5018bedc2ce0a72dad1827a1709da30580f0544"));
+
+        receipt.encode_with_state_root(state_root, &mut data);
+
+        assert_eq!(data, expected);
+    }
 }
@@ -1,4 +1,4 @@
-use super::{H256, U256};
+use super::{trie::StoredNibblesSubKey, H256, U256};
 use reth_codecs::{derive_arbitrary, Compact};
 use serde::{Deserialize, Serialize};
 
@@ -42,10 +42,10 @@ impl Compact for StorageEntry {
 #[derive_arbitrary(compact)]
 #[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
 pub struct StorageTrieEntry {
-    /// Hashed storage key.
+    /// Path of the branch node in the storage trie.
+    pub nibbles: StoredNibblesSubKey,
+    /// Hash of the branch node.
     pub hash: H256,
-    /// Encoded node.
-    pub node: Vec<u8>,
 }
 
 // NOTE: Removing main_codec and manually encode subkey
@@ -53,18 +53,17 @@ pub struct StorageTrieEntry {
 // over whole value (Even SubKey) that would mess up fetching of values with seek_by_key_subkey
 impl Compact for StorageTrieEntry {
     fn to_compact(self, buf: &mut impl bytes::BufMut) -> usize {
-        // for now put full bytes and later compress it.
+        let nibbles_len = self.nibbles.to_compact(buf);
         buf.put_slice(&self.hash.to_fixed_bytes()[..]);
-        buf.put_slice(&self.node[..]);
-        self.node.len() + 32
+        nibbles_len + 32
     }
 
     fn from_compact(buf: &[u8], len: usize) -> (Self, &[u8])
     where
         Self: Sized,
     {
-        let key = H256::from_slice(&buf[..32]);
-        let node = Vec::from(&buf[32..len]);
-        (Self { hash: key, node }, &buf[len..])
+        let (nibbles, buf) = StoredNibblesSubKey::from_compact(buf, len);
+        let hash = H256::from_slice(&buf[..32]);
+        (Self { nibbles, hash }, &buf[32..])
     }
 }
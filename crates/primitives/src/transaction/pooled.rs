@@ -0,0 +1,272 @@
+use crate::{
+    BlobTransaction, BlobTransactionSidecar, Signature, Transaction, TransactionSigned,
+    TransactionSignedEcRecovered, TxHash, EIP4844_TX_TYPE_ID,
+};
+use reth_codecs::add_arbitrary_tests;
+use reth_rlp::{Decodable, DecodeError, Encodable, Header, EMPTY_LIST_CODE};
+use serde::{Deserialize, Serialize};
+
+/// A transaction as it is exchanged via `PooledTransactions` and `eth_sendRawTransaction`.
+///
+/// Blob transactions are only ever exchanged in their network form, which includes the sidecar.
+/// All other transactions are exchanged in their canonical form.
+#[add_arbitrary_tests(rlp)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PooledTransactionsElement {
+    /// A transaction in its canonical form.
+    Transaction(TransactionSigned),
+    /// A blob transaction including its sidecar.
+    BlobTransaction(BlobTransaction),
+}
+
+// === impl PooledTransactionsElement ===
+
+impl PooledTransactionsElement {
+    /// Returns the hash of the transaction.
+    pub fn hash(&self) -> TxHash {
+        self.transaction().hash
+    }
+
+    /// Returns the signature of the transaction.
+    pub fn signature(&self) -> &Signature {
+        &self.transaction().signature
+    }
+
+    /// Returns the canonical transaction.
+    pub fn transaction(&self) -> &TransactionSigned {
+        match self {
+            PooledTransactionsElement::Transaction(tx) => tx,
+            PooledTransactionsElement::BlobTransaction(tx) => &tx.transaction,
+        }
+    }
+
+    /// Returns the sidecar if this is a blob transaction in its network form.
+    pub fn blob_sidecar(&self) -> Option<&BlobTransactionSidecar> {
+        match self {
+            PooledTransactionsElement::Transaction(_) => None,
+            PooledTransactionsElement::BlobTransaction(tx) => Some(&tx.sidecar),
+        }
+    }
+
+    /// Returns `true` if this is an EIP-4844 transaction that is _not_ accompanied by its sidecar.
+    ///
+    /// Blob transactions must always be exchanged in their network form.
+    pub fn is_blob_without_sidecar(&self) -> bool {
+        matches!(
+            self,
+            PooledTransactionsElement::Transaction(TransactionSigned {
+                transaction: Transaction::Eip4844(_),
+                ..
+            })
+        )
+    }
+
+    /// Consumes the type and returns the canonical transaction, dropping the sidecar if any.
+    pub fn into_transaction(self) -> TransactionSigned {
+        match self {
+            PooledTransactionsElement::Transaction(tx) => tx,
+            PooledTransactionsElement::BlobTransaction(tx) => tx.transaction,
+        }
+    }
+
+    /// Recovers the signer of the transaction and returns the recovered transaction together with
+    /// the sidecar, if any.
+    ///
+    /// Returns `None` if the transaction's signature is invalid.
+    pub fn try_into_ecrecovered(
+        self,
+    ) -> Option<(TransactionSignedEcRecovered, Option<BlobTransactionSidecar>)> {
+        let (transaction, sidecar) = match self {
+            PooledTransactionsElement::Transaction(tx) => (tx, None),
+            PooledTransactionsElement::BlobTransaction(tx) => (tx.transaction, Some(tx.sidecar)),
+        };
+        Some((transaction.into_ecrecovered()?, sidecar))
+    }
+
+    /// Encodes the transaction into the "raw" format used by `eth_sendRawTransaction`.
+    pub fn encode_enveloped(&self, out: &mut dyn bytes::BufMut) {
+        match self {
+            PooledTransactionsElement::Transaction(tx) => tx.encode_enveloped(out),
+            PooledTransactionsElement::BlobTransaction(tx) => tx.encode_enveloped(out),
+        }
+    }
+
+    /// Decodes the "raw" format used by `eth_sendRawTransaction`.
+    ///
+    /// Blob transactions are expected in their network form.
+    pub fn decode_enveloped(tx: crate::Bytes) -> Result<Self, DecodeError> {
+        let data = tx.as_ref();
+        if data.first() == Some(&EIP4844_TX_TYPE_ID) {
+            return BlobTransaction::decode_enveloped(&mut &data[..]).map(Into::into)
+        }
+        TransactionSigned::decode_enveloped(tx).map(Into::into)
+    }
+}
+
+impl From<TransactionSigned> for PooledTransactionsElement {
+    fn from(tx: TransactionSigned) -> Self {
+        PooledTransactionsElement::Transaction(tx)
+    }
+}
+
+impl From<BlobTransaction> for PooledTransactionsElement {
+    fn from(tx: BlobTransaction) -> Self {
+        PooledTransactionsElement::BlobTransaction(tx)
+    }
+}
+
+impl From<PooledTransactionsElement> for TransactionSigned {
+    fn from(element: PooledTransactionsElement) -> Self {
+        element.into_transaction()
+    }
+}
+
+impl Default for PooledTransactionsElement {
+    fn default() -> Self {
+        PooledTransactionsElement::Transaction(Default::default())
+    }
+}
+
+impl Encodable for PooledTransactionsElement {
+    fn encode(&self, out: &mut dyn bytes::BufMut) {
+        match self {
+            PooledTransactionsElement::Transaction(tx) => tx.encode(out),
+            PooledTransactionsElement::BlobTransaction(tx) => {
+                // typed transactions are wrapped in an rlp string
+                Header { list: false, payload_length: tx.enveloped_length() }.encode(out);
+                tx.encode_enveloped(out);
+            }
+        }
+    }
+
+    fn length(&self) -> usize {
+        match self {
+            PooledTransactionsElement::Transaction(tx) => tx.length(),
+            PooledTransactionsElement::BlobTransaction(tx) => {
+                let payload_length = tx.enveloped_length();
+                reth_rlp::length_of_length(payload_length) + payload_length
+            }
+        }
+    }
+}
+
+/// Decodes either a canonical transaction or a blob transaction in its network form.
+///
+/// The two forms of a blob transaction can be told apart by the first item of the outer list: in
+/// the network form this is the transaction payload body, which is a list itself.
+impl Decodable for PooledTransactionsElement {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let mut original_encoding = *buf;
+        let header = Header::decode(&mut original_encoding)?;
+        if header.list {
+            return TransactionSigned::decode(buf).map(Into::into)
+        }
+
+        let mut payload =
+            original_encoding.get(..header.payload_length).ok_or(DecodeError::InputTooShort)?;
+        if payload.first() != Some(&EIP4844_TX_TYPE_ID) {
+            return TransactionSigned::decode(buf).map(Into::into)
+        }
+
+        let mut fields = &payload[1..];
+        let outer = Header::decode(&mut fields)?;
+        let is_network_form =
+            outer.list && fields.first().map_or(false, |first| *first >= EMPTY_LIST_CODE);
+        if !is_network_form {
+            return TransactionSigned::decode(buf).map(Into::into)
+        }
+
+        let tx = BlobTransaction::decode_enveloped(&mut payload)?;
+        if !payload.is_empty() {
+            return Err(DecodeError::Custom("trailing bytes after blob transaction"))
+        }
+        *buf = &original_encoding[header.payload_length..];
+        Ok(tx.into())
+    }
+}
+
+#[cfg(any(test, feature = "arbitrary"))]
+impl proptest::arbitrary::Arbitrary for PooledTransactionsElement {
+    type Parameters = ();
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        use proptest::prelude::{any, Strategy};
+
+        any::<TransactionSigned>().prop_map(Self::from_arbitrary_transaction).boxed()
+    }
+
+    type Strategy = proptest::strategy::BoxedStrategy<PooledTransactionsElement>;
+}
+
+#[cfg(any(test, feature = "arbitrary"))]
+impl<'a> arbitrary::Arbitrary<'a> for PooledTransactionsElement {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        Ok(Self::from_arbitrary_transaction(
+            <TransactionSigned as arbitrary::Arbitrary>::arbitrary(u)?,
+        ))
+    }
+}
+
+#[cfg(any(test, feature = "arbitrary"))]
+impl PooledTransactionsElement {
+    /// Blob transactions are wrapped with an empty sidecar so that they are in their network form.
+    fn from_arbitrary_transaction(transaction: TransactionSigned) -> Self {
+        match transaction.transaction {
+            Transaction::Eip4844(_) => {
+                BlobTransaction { transaction, sidecar: Default::default() }.into()
+            }
+            _ => transaction.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::{BYTES_PER_BLOB, BYTES_PER_COMMITMENT},
+        eip4844::kzg_to_versioned_hash,
+        Bytes, TransactionKind, TxEip4844, TxLegacy, H160, U256,
+    };
+
+    fn signed(transaction: Transaction) -> TransactionSigned {
+        let signature = Signature { odd_y_parity: false, r: U256::from(1), s: U256::from(2) };
+        TransactionSigned::from_transaction_and_signature(transaction, signature)
+    }
+
+    fn blob_tx() -> TransactionSigned {
+        signed(Transaction::Eip4844(TxEip4844 {
+            chain_id: 1,
+            to: TransactionKind::Call(H160::random()),
+            blob_versioned_hashes: vec![kzg_to_versioned_hash(&[1u8; BYTES_PER_COMMITMENT])],
+            ..Default::default()
+        }))
+    }
+
+    #[test]
+    fn roundtrip_elements() {
+        let blob = BlobTransaction {
+            transaction: blob_tx(),
+            sidecar: BlobTransactionSidecar {
+                blobs: vec![Bytes::from(vec![0u8; BYTES_PER_BLOB])],
+                commitments: vec![[1u8; BYTES_PER_COMMITMENT]],
+                proofs: vec![[2u8; BYTES_PER_COMMITMENT]],
+            },
+        };
+        let elements: Vec<PooledTransactionsElement> = vec![
+            signed(Transaction::Legacy(TxLegacy { chain_id: Some(1), ..Default::default() }))
+                .into(),
+            blob.into(),
+            // canonical form of a blob transaction
+            blob_tx().into(),
+        ];
+
+        let mut buf = Vec::new();
+        elements.encode(&mut buf);
+        assert_eq!(buf.len(), elements.length());
+
+        let decoded = Vec::<PooledTransactionsElement>::decode(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded, elements);
+        assert!(decoded[1].blob_sidecar().is_some());
+        assert!(decoded[2].is_blob_without_sidecar());
+    }
+}
@@ -0,0 +1,335 @@
+use crate::{
+    constants::{BYTES_PER_BLOB, BYTES_PER_COMMITMENT},
+    eip4844::{kzg_to_versioned_hash, KzgError, KzgSettings},
+    Bytes, Transaction, TransactionSigned, EIP4844_TX_TYPE_ID, H256,
+};
+use bytes::Buf;
+use c_kzg::{Blob, Bytes48, KzgProof};
+use reth_rlp::{Decodable, DecodeError, Encodable, Header};
+use serde::{Deserialize, Serialize};
+
+/// The blobs, KZG commitments and KZG proofs that accompany a blob transaction on the network.
+///
+/// These are not part of the canonical transaction and are never included in blocks.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+pub struct BlobTransactionSidecar {
+    /// The blob data, each entry is exactly [`BYTES_PER_BLOB`] bytes.
+    pub blobs: Vec<Bytes>,
+    /// The blob commitments.
+    pub commitments: Vec<[u8; BYTES_PER_COMMITMENT]>,
+    /// The blob proofs.
+    pub proofs: Vec<[u8; BYTES_PER_COMMITMENT]>,
+}
+
+impl BlobTransactionSidecar {
+    /// Returns the number of blobs in the sidecar.
+    pub fn len(&self) -> usize {
+        self.blobs.len()
+    }
+
+    /// Returns `true` if the sidecar does not contain any blobs.
+    pub fn is_empty(&self) -> bool {
+        self.blobs.is_empty()
+    }
+
+    /// Returns the versioned hashes of all commitments in the sidecar.
+    pub fn versioned_hashes(&self) -> impl Iterator<Item = H256> + '_ {
+        self.commitments.iter().map(|commitment| kzg_to_versioned_hash(commitment))
+    }
+
+    /// Returns `true` if the number of blobs, commitments and proofs match the given versioned
+    /// hashes and every commitment hashes to its versioned hash.
+    ///
+    /// NOTE: this does not verify the KZG proofs, see [`Self::verify_kzg_proofs`].
+    pub fn matches_versioned_hashes(&self, versioned_hashes: &[H256]) -> bool {
+        self.blobs.len() == versioned_hashes.len() &&
+            self.proofs.len() == versioned_hashes.len() &&
+            self.commitments.len() == versioned_hashes.len() &&
+            self.versioned_hashes()
+                .zip(versioned_hashes)
+                .all(|(computed, hash)| computed == *hash)
+    }
+
+    /// Verifies the KZG proofs of all blobs against their commitments in a single batch.
+    ///
+    /// Returns `Ok(false)` if any proof is invalid and an error if a blob, commitment or proof is
+    /// malformed.
+    pub fn verify_kzg_proofs(&self, settings: &KzgSettings) -> Result<bool, KzgError> {
+        let blobs = self
+            .blobs
+            .iter()
+            .map(|blob| Blob::from_bytes(blob.as_ref()))
+            .collect::<Result<Vec<_>, _>>()?;
+        let commitments = self
+            .commitments
+            .iter()
+            .map(|commitment| Bytes48::from_bytes(commitment))
+            .collect::<Result<Vec<_>, _>>()?;
+        let proofs = self
+            .proofs
+            .iter()
+            .map(|proof| Bytes48::from_bytes(proof))
+            .collect::<Result<Vec<_>, _>>()?;
+        KzgProof::verify_blob_kzg_proof_batch(&blobs, &commitments, &proofs, settings)
+    }
+
+    /// Outputs the RLP length of the sidecar fields, without a list header.
+    fn fields_len(&self) -> usize {
+        self.blobs.iter().map(|blob| blob.0.clone()).collect::<Vec<_>>().length() +
+            self.commitments.length() +
+            self.proofs.length()
+    }
+
+    /// Encodes the sidecar fields, without a list header.
+    fn encode_fields(&self, out: &mut dyn bytes::BufMut) {
+        self.blobs.iter().map(|blob| blob.0.clone()).collect::<Vec<_>>().encode(out);
+        self.commitments.encode(out);
+        self.proofs.encode(out);
+    }
+
+    /// Decodes the sidecar fields, without a list header.
+    ///
+    /// Every blob is checked to be exactly [`BYTES_PER_BLOB`] bytes long.
+    fn decode_fields(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let blobs = Vec::<bytes::Bytes>::decode(buf)?;
+        if blobs.iter().any(|blob| blob.len() != BYTES_PER_BLOB) {
+            return Err(DecodeError::Custom("invalid blob length"))
+        }
+        Ok(Self {
+            blobs: blobs.into_iter().map(Bytes).collect(),
+            commitments: Decodable::decode(buf)?,
+            proofs: Decodable::decode(buf)?,
+        })
+    }
+}
+
+/// Encodes the sidecar as `rlp([blobs, commitments, proofs])`.
+impl Encodable for BlobTransactionSidecar {
+    fn encode(&self, out: &mut dyn bytes::BufMut) {
+        Header { list: true, payload_length: self.fields_len() }.encode(out);
+        self.encode_fields(out);
+    }
+
+    fn length(&self) -> usize {
+        let payload_length = self.fields_len();
+        payload_length + reth_rlp::length_of_length(payload_length)
+    }
+}
+
+impl Decodable for BlobTransactionSidecar {
+    fn decode(buf: &mut &[u8]) -> Result<Self, DecodeError> {
+        let header = Header::decode(buf)?;
+        if !header.list {
+            return Err(DecodeError::UnexpectedString)
+        }
+        let started_len = buf.len();
+        let sidecar = Self::decode_fields(buf)?;
+        let consumed = started_len - buf.len();
+        if consumed != header.payload_length {
+            return Err(DecodeError::ListLengthMismatch {
+                expected: header.payload_length,
+                got: consumed,
+            })
+        }
+        Ok(sidecar)
+    }
+}
+
+/// A blob transaction in its network form ([EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)).
+///
+/// This is the form in which blob transactions are gossiped and submitted via
+/// `eth_sendRawTransaction`:
+///
+/// `0x03 || rlp([tx_payload_body, blobs, commitments, proofs])`
+///
+/// where `tx_payload_body` is the list of the signed transaction's fields.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BlobTransaction {
+    /// The signed blob transaction, this is what gets included in blocks.
+    pub transaction: TransactionSigned,
+    /// The sidecar of the transaction.
+    pub sidecar: BlobTransactionSidecar,
+}
+
+impl BlobTransaction {
+    /// Creates a new [`BlobTransaction`] from a signed transaction and its sidecar.
+    ///
+    /// Returns the transaction back if it is not an EIP-4844 transaction.
+    pub fn try_from_signed(
+        transaction: TransactionSigned,
+        sidecar: BlobTransactionSidecar,
+    ) -> Result<Self, TransactionSigned> {
+        if !matches!(transaction.transaction, Transaction::Eip4844(_)) {
+            return Err(transaction)
+        }
+        Ok(Self { transaction, sidecar })
+    }
+
+    /// Consumes the type and returns the canonical transaction, dropping the sidecar.
+    pub fn into_transaction(self) -> TransactionSigned {
+        self.transaction
+    }
+
+    /// Returns `true` if the number of blobs, commitments and proofs match the transaction's
+    /// versioned hashes and every commitment hashes to its versioned hash.
+    ///
+    /// NOTE: this does not verify the KZG proofs, see [`Self::verify_sidecar`].
+    pub fn has_consistent_sidecar(&self) -> bool {
+        self.sidecar
+            .matches_versioned_hashes(self.transaction.blob_versioned_hashes().unwrap_or_default())
+    }
+
+    /// Returns `true` if the sidecar is consistent with the transaction and all KZG proofs are
+    /// valid.
+    pub fn verify_sidecar(&self, settings: &KzgSettings) -> Result<bool, KzgError> {
+        Ok(self.has_consistent_sidecar() && self.sidecar.verify_kzg_proofs(settings)?)
+    }
+
+    /// Outputs the length of the transaction payload body list, including its header.
+    fn tx_payload_body_len(&self) -> usize {
+        let payload_length =
+            self.transaction.transaction.fields_len() + self.transaction.signature.payload_len();
+        payload_length + reth_rlp::length_of_length(payload_length)
+    }
+
+    /// Outputs the RLP payload length of the network form, without the type byte.
+    fn payload_len(&self) -> usize {
+        self.tx_payload_body_len() + self.sidecar.fields_len()
+    }
+
+    /// Encodes the transaction into its network form.
+    pub fn encode_enveloped(&self, out: &mut dyn bytes::BufMut) {
+        out.put_u8(EIP4844_TX_TYPE_ID);
+        Header { list: true, payload_length: self.payload_len() }.encode(out);
+
+        let payload_length =
+            self.transaction.transaction.fields_len() + self.transaction.signature.payload_len();
+        Header { list: true, payload_length }.encode(out);
+        self.transaction.transaction.encode_fields(out);
+        self.transaction.signature.encode(out);
+
+        self.sidecar.encode_fields(out);
+    }
+
+    /// Returns the length of the network form, including the type byte.
+    pub fn enveloped_length(&self) -> usize {
+        let payload_length = self.payload_len();
+        1 + reth_rlp::length_of_length(payload_length) + payload_length
+    }
+
+    /// Decodes the network form of a blob transaction.
+    ///
+    /// CAUTION: this expects that `data` is `[0x03, rlp([tx_payload_body, blobs, commitments,
+    /// proofs])]`
+    pub fn decode_enveloped(data: &mut &[u8]) -> Result<Self, DecodeError> {
+        let tx_type = *data.first().ok_or(DecodeError::InputTooShort)?;
+        if tx_type != EIP4844_TX_TYPE_ID {
+            return Err(DecodeError::Custom("invalid blob transaction type"))
+        }
+        data.advance(1);
+
+        let outer = Header::decode(data)?;
+        if !outer.list {
+            return Err(DecodeError::UnexpectedString)
+        }
+        let outer_start = data.len();
+
+        // the canonical encoding of the transaction is the type byte followed by the payload body
+        let tx_body_start = *data;
+        let inner = Header::decode(data)?;
+        if !inner.list {
+            return Err(DecodeError::UnexpectedString)
+        }
+        if data.len() < inner.payload_length {
+            return Err(DecodeError::InputTooShort)
+        }
+        let body_len = inner.length() + inner.payload_length;
+        let mut canonical = Vec::with_capacity(1 + body_len);
+        canonical.push(EIP4844_TX_TYPE_ID);
+        canonical.extend_from_slice(&tx_body_start[..body_len]);
+        data.advance(inner.payload_length);
+
+        let transaction =
+            TransactionSigned::decode_enveloped_typed_transaction(&mut canonical.as_slice())?;
+        let sidecar = BlobTransactionSidecar::decode_fields(data)?;
+
+        if outer_start - data.len() != outer.payload_length {
+            return Err(DecodeError::ListLengthMismatch {
+                expected: outer.payload_length,
+                got: outer_start - data.len(),
+            })
+        }
+
+        Ok(Self { transaction, sidecar })
+    }
+}
+
+impl From<BlobTransaction> for TransactionSigned {
+    fn from(tx: BlobTransaction) -> Self {
+        tx.transaction
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AccessList, Signature, TransactionKind, TxEip4844, H160, U256};
+
+    fn blob_transaction() -> BlobTransaction {
+        let commitment = [1u8; BYTES_PER_COMMITMENT];
+        let transaction = Transaction::Eip4844(TxEip4844 {
+            chain_id: 1,
+            nonce: 2,
+            gas_limit: 21_000,
+            max_fee_per_gas: 10,
+            max_priority_fee_per_gas: 1,
+            to: TransactionKind::Call(H160::random()),
+            value: 3,
+            access_list: AccessList::default(),
+            blob_versioned_hashes: vec![kzg_to_versioned_hash(&commitment)],
+            max_fee_per_blob_gas: 4,
+            input: Bytes::default(),
+        });
+        let signature = Signature { odd_y_parity: true, r: U256::from(5), s: U256::from(6) };
+        BlobTransaction {
+            transaction: TransactionSigned::from_transaction_and_signature(transaction, signature),
+            sidecar: BlobTransactionSidecar {
+                blobs: vec![Bytes::from(vec![7u8; BYTES_PER_BLOB])],
+                commitments: vec![commitment],
+                proofs: vec![[8u8; BYTES_PER_COMMITMENT]],
+            },
+        }
+    }
+
+    #[test]
+    fn network_encoding_roundtrip() {
+        let tx = blob_transaction();
+        assert!(tx.has_consistent_sidecar());
+
+        let mut buf = Vec::new();
+        tx.encode_enveloped(&mut buf);
+        assert_eq!(buf.len(), tx.enveloped_length());
+
+        let decoded = BlobTransaction::decode_enveloped(&mut buf.as_slice()).unwrap();
+        assert_eq!(decoded, tx);
+        // the hash only covers the canonical transaction, not the sidecar
+        assert_eq!(decoded.transaction.hash, tx.transaction.recalculate_hash());
+    }
+
+    #[test]
+    fn rejects_invalid_blob_length() {
+        let mut tx = blob_transaction();
+        tx.sidecar.blobs = vec![Bytes::from(vec![7u8; 32])];
+
+        let mut buf = Vec::new();
+        tx.encode_enveloped(&mut buf);
+        assert!(BlobTransaction::decode_enveloped(&mut buf.as_slice()).is_err());
+    }
+
+    #[test]
+    fn detects_inconsistent_sidecar() {
+        let mut tx = blob_transaction();
+        tx.sidecar.commitments = vec![[9u8; BYTES_PER_COMMITMENT]];
+        assert!(!tx.has_consistent_sidecar());
+    }
+}
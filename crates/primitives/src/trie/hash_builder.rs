@@ -0,0 +1,111 @@
+use super::Nibbles;
+use crate::H256;
+use bytes::Buf;
+use reth_codecs::{derive_arbitrary, Compact};
+use serde::{Deserialize, Serialize};
+
+/// The value of the item most recently added to a hash builder.
+#[derive_arbitrary]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum HashBuilderValue {
+    /// The value of a leaf.
+    Bytes(Vec<u8>),
+    /// The hash of a branch node standing in for its whole subtree.
+    Hash(H256),
+}
+
+impl Default for HashBuilderValue {
+    fn default() -> Self {
+        Self::Bytes(vec![])
+    }
+}
+
+/// The intermediate state of a hash builder, from which it can continue adding items.
+///
+/// Only the nodes along the path of the most recently added item are kept, so the state stays
+/// small regardless of how many items were already added.
+#[derive_arbitrary(compact)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HashBuilderState {
+    /// Path of the most recently added item.
+    pub key: Nibbles,
+    /// Value of the most recently added item.
+    pub value: HashBuilderValue,
+    /// References to the nodes that are not yet part of their parent.
+    pub stack: Vec<Vec<u8>>,
+    /// Bitmask of the children of the branch node being built at each depth.
+    pub groups: Vec<u16>,
+}
+
+impl Compact for HashBuilderState {
+    fn to_compact(self, buf: &mut impl bytes::BufMut) -> usize {
+        let mut len = put_bytes(buf, &self.key);
+
+        match self.value {
+            HashBuilderValue::Bytes(bytes) => {
+                buf.put_u8(0);
+                len += 1 + put_bytes(buf, &bytes);
+            }
+            HashBuilderValue::Hash(hash) => {
+                buf.put_u8(1);
+                buf.put_slice(hash.as_bytes());
+                len += 1 + 32;
+            }
+        }
+
+        buf.put_u32(self.stack.len() as u32);
+        len += 4;
+        for node_ref in &self.stack {
+            len += put_bytes(buf, node_ref);
+        }
+
+        buf.put_u32(self.groups.len() as u32);
+        len += 4;
+        for group in self.groups {
+            buf.put_u16(group);
+            len += 2;
+        }
+
+        len
+    }
+
+    fn from_compact(mut buf: &[u8], _len: usize) -> (Self, &[u8])
+    where
+        Self: Sized,
+    {
+        let key = Nibbles::from_hex(get_bytes(&mut buf));
+
+        let value = match buf.get_u8() {
+            0 => HashBuilderValue::Bytes(get_bytes(&mut buf)),
+            1 => {
+                let hash = H256::from_slice(&buf[..32]);
+                buf.advance(32);
+                HashBuilderValue::Hash(hash)
+            }
+            _ => unreachable!("invalid hash builder value"),
+        };
+
+        let stack_len = buf.get_u32() as usize;
+        let stack = (0..stack_len).map(|_| get_bytes(&mut buf)).collect();
+
+        let groups_len = buf.get_u32() as usize;
+        let groups = (0..groups_len).map(|_| buf.get_u16()).collect();
+
+        (Self { key, value, stack, groups }, buf)
+    }
+}
+
+/// Writes the bytes prefixed with their length.
+fn put_bytes(buf: &mut impl bytes::BufMut, bytes: &[u8]) -> usize {
+    buf.put_u32(bytes.len() as u32);
+    buf.put_slice(bytes);
+    4 + bytes.len()
+}
+
+/// Reads bytes written by [put_bytes].
+fn get_bytes(buf: &mut &[u8]) -> Vec<u8> {
+    let len = buf.get_u32() as usize;
+    let bytes = buf[..len].to_vec();
+    buf.advance(len);
+    bytes
+}
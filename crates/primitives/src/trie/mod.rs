@@ -0,0 +1,7 @@
+//! Types used to store and address the nodes of Merkle Patricia Tries.
+
+mod hash_builder;
+mod nibbles;
+
+pub use hash_builder::{HashBuilderState, HashBuilderValue};
+pub use nibbles::{Nibbles, StoredNibbles, StoredNibblesSubKey};
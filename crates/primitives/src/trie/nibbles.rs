@@ -0,0 +1,255 @@
+use bytes::Buf;
+use derive_more::{Deref, From};
+use reth_codecs::Compact;
+use serde::{Deserialize, Serialize};
+
+/// The maximum number of nibbles in a trie path, reached by the leaves of keccak-keyed tries.
+const MAX_NIBBLES: usize = 64;
+
+/// A path in a Merkle Patricia Trie, stored as one nibble per byte.
+///
+/// Paths order lexicographically with a prefix ordering before all of its extensions, which is
+/// the order nodes are visited in a depth-first traversal of the trie.
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deref, From, Serialize, Deserialize,
+)]
+pub struct Nibbles(Vec<u8>);
+
+impl Nibbles {
+    /// Creates a path from a vector of nibbles, each of which must be smaller than 16.
+    pub fn from_hex(hex: Vec<u8>) -> Self {
+        debug_assert!(hex.iter().all(|nibble| *nibble < 16), "invalid nibble");
+        Self(hex)
+    }
+
+    /// Creates the path of a key by splitting each byte into two nibbles.
+    pub fn unpack(data: impl AsRef<[u8]>) -> Self {
+        Self(data.as_ref().iter().flat_map(|byte| [byte >> 4, byte & 0x0f]).collect())
+    }
+
+    /// Packs the nibbles into bytes, padding an odd path with a zero nibble.
+    pub fn pack(&self) -> Vec<u8> {
+        self.0.chunks(2).map(|pair| (pair[0] << 4) | pair.get(1).copied().unwrap_or(0)).collect()
+    }
+
+    /// Returns the nibbles as a slice.
+    pub fn as_slice(&self) -> &[u8] {
+        &self.0
+    }
+
+    /// Returns the number of leading nibbles the two paths have in common.
+    pub fn common_prefix_length(&self, other: &[u8]) -> usize {
+        self.0.iter().zip(other).take_while(|(a, b)| a == b).count()
+    }
+
+    /// Returns a path of the nibbles in the given range.
+    pub fn slice(&self, range: impl std::slice::SliceIndex<[u8], Output = [u8]>) -> Self {
+        Self(self.0[range].to_vec())
+    }
+
+    /// Appends a nibble to the path.
+    pub fn push(&mut self, nibble: u8) {
+        debug_assert!(nibble < 16, "invalid nibble");
+        self.0.push(nibble)
+    }
+
+    /// Appends the nibbles of another path.
+    pub fn extend(&mut self, nibbles: &[u8]) {
+        debug_assert!(nibbles.iter().all(|nibble| *nibble < 16), "invalid nibble");
+        self.0.extend_from_slice(nibbles)
+    }
+
+    /// Shortens the path to the first `len` nibbles.
+    pub fn truncate(&mut self, len: usize) {
+        self.0.truncate(len)
+    }
+
+    /// Returns the smallest path that orders after every path starting with this one, or `None`
+    /// if there is none because the path only consists of `0xf` nibbles.
+    pub fn increment(&self) -> Option<Self> {
+        let mut incremented = self.0.clone();
+        while let Some(nibble) = incremented.pop() {
+            if nibble < 0xf {
+                incremented.push(nibble + 1);
+                return Some(Self(incremented))
+            }
+        }
+        None
+    }
+
+    /// Encodes the path with the hex-prefix encoding used in leaf and extension nodes.
+    ///
+    /// See appendix C of the [Yellow Paper](https://ethereum.github.io/yellowpaper/paper.pdf).
+    pub fn encode_path_leaf(&self, is_leaf: bool) -> Vec<u8> {
+        let odd = self.0.len() % 2 != 0;
+        let flag = match (is_leaf, odd) {
+            (false, false) => 0x00,
+            (false, true) => 0x10 | self.0[0],
+            (true, false) => 0x20,
+            (true, true) => 0x30 | self.0[0],
+        };
+
+        let mut encoded = Vec::with_capacity(self.0.len() / 2 + 1);
+        encoded.push(flag);
+        encoded.extend(self.0[odd as usize..].chunks(2).map(|pair| (pair[0] << 4) | pair[1]));
+        encoded
+    }
+
+    /// Decodes a hex-prefix encoded path, returning the path and whether it is the path of a leaf.
+    ///
+    /// Returns `None` if the flag nibble is invalid.
+    pub fn decode_path_leaf(encoded: &[u8]) -> Option<(Self, bool)> {
+        let (&first, rest) = encoded.split_first()?;
+        let (is_leaf, odd) = match first >> 4 {
+            0x0 => (false, false),
+            0x1 => (false, true),
+            0x2 => (true, false),
+            0x3 => (true, true),
+            _ => return None,
+        };
+
+        let mut path = Vec::with_capacity(rest.len() * 2 + odd as usize);
+        if odd {
+            path.push(first & 0x0f);
+        }
+        path.extend(rest.iter().flat_map(|byte| [byte >> 4, byte & 0x0f]));
+        Some((Self(path), is_leaf))
+    }
+}
+
+#[cfg(any(test, feature = "arbitrary"))]
+use proptest::strategy::Strategy;
+#[cfg(any(test, feature = "arbitrary"))]
+impl proptest::prelude::Arbitrary for Nibbles {
+    type Parameters = ();
+    type Strategy = proptest::prelude::BoxedStrategy<Nibbles>;
+
+    fn arbitrary_with(_: Self::Parameters) -> Self::Strategy {
+        proptest::collection::vec(0u8..16, 0..=MAX_NIBBLES).prop_map(Nibbles).boxed()
+    }
+}
+
+#[cfg(any(test, feature = "arbitrary"))]
+impl<'a> arbitrary::Arbitrary<'a> for Nibbles {
+    fn arbitrary(u: &mut arbitrary::Unstructured<'a>) -> arbitrary::Result<Self> {
+        let len = u.int_in_range(0..=MAX_NIBBLES)?;
+        (0..len).map(|_| u.int_in_range(0..=15)).collect::<Result<_, _>>().map(Nibbles)
+    }
+}
+
+/// The path of a node in the accounts trie, as stored in the database key.
+///
+/// One nibble is stored per byte, so the database orders the keys like the paths.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deref, From)]
+pub struct StoredNibbles(pub Nibbles);
+
+/// The path of a node in a storage trie, as stored in the database subkey.
+///
+/// Duplicate values are ordered by their raw bytes, so the path is padded to a fixed length and
+/// followed by its actual length. This keeps the subkey ordering in line with the path ordering.
+#[cfg_attr(
+    any(test, feature = "arbitrary"),
+    derive(proptest_derive::Arbitrary, arbitrary::Arbitrary)
+)]
+#[derive(
+    Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Deref, From, Serialize, Deserialize,
+)]
+pub struct StoredNibblesSubKey(pub Nibbles);
+
+impl Compact for StoredNibblesSubKey {
+    fn to_compact(self, buf: &mut impl bytes::BufMut) -> usize {
+        assert!(self.0.len() <= MAX_NIBBLES, "path too long");
+        let mut padded = [0u8; MAX_NIBBLES];
+        padded[..self.0.len()].copy_from_slice(&self.0);
+        buf.put_slice(&padded);
+        buf.put_u8(self.0.len() as u8);
+        MAX_NIBBLES + 1
+    }
+
+    fn from_compact(mut buf: &[u8], _len: usize) -> (Self, &[u8])
+    where
+        Self: Sized,
+    {
+        let len = buf[MAX_NIBBLES] as usize;
+        let nibbles = Nibbles(buf[..len].to_vec());
+        buf.advance(MAX_NIBBLES + 1);
+        (Self(nibbles), buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hex_prefix_encoding() {
+        // Examples from appendix C of the Yellow Paper.
+        let path = Nibbles::from_hex(vec![1, 2, 3, 4, 5]);
+        assert_eq!(path.encode_path_leaf(false), vec![0x11, 0x23, 0x45]);
+        let path = Nibbles::from_hex(vec![0, 1, 2, 3, 4, 5]);
+        assert_eq!(path.encode_path_leaf(false), vec![0x00, 0x01, 0x23, 0x45]);
+        let path = Nibbles::from_hex(vec![0, 0xf, 1, 0xc, 0xb, 8]);
+        assert_eq!(path.encode_path_leaf(true), vec![0x20, 0x0f, 0x1c, 0xb8]);
+        let path = Nibbles::from_hex(vec![0xf, 1, 0xc, 0xb, 8]);
+        assert_eq!(path.encode_path_leaf(true), vec![0x3f, 0x1c, 0xb8]);
+    }
+
+    #[test]
+    fn hex_prefix_decoding() {
+        for (hex, is_leaf) in [
+            (vec![], false),
+            (vec![1, 2, 3, 4, 5], false),
+            (vec![0, 1, 2, 3, 4, 5], false),
+            (vec![0, 0xf, 1, 0xc, 0xb, 8], true),
+            (vec![0xf, 1, 0xc, 0xb, 8], true),
+        ] {
+            let path = Nibbles::from_hex(hex);
+            let encoded = path.encode_path_leaf(is_leaf);
+            assert_eq!(Nibbles::decode_path_leaf(&encoded), Some((path, is_leaf)));
+        }
+        assert_eq!(Nibbles::decode_path_leaf(&[]), None);
+        assert_eq!(Nibbles::decode_path_leaf(&[0x40]), None);
+    }
+
+    #[test]
+    fn pack_unpack() {
+        let path = Nibbles::unpack([0xab, 0x01]);
+        assert_eq!(path.as_slice(), &[0xa, 0xb, 0x0, 0x1]);
+        assert_eq!(path.pack(), vec![0xab, 0x01]);
+        assert_eq!(path.slice(..3).pack(), vec![0xab, 0x00]);
+    }
+
+    #[test]
+    fn increment() {
+        assert_eq!(Nibbles::from_hex(vec![1, 0xf]).increment(), Some(Nibbles::from_hex(vec![2])));
+        assert_eq!(Nibbles::from_hex(vec![0xf, 0xf]).increment(), None);
+    }
+
+    #[test]
+    fn subkey_ordering_matches_path_ordering() {
+        let mut paths = vec![
+            Nibbles::from_hex(vec![1, 0, 0]),
+            Nibbles::from_hex(vec![1]),
+            Nibbles::from_hex(vec![1, 0]),
+            Nibbles::from_hex(vec![1, 0, 5]),
+            Nibbles::from_hex(vec![]),
+            Nibbles::from_hex(vec![0, 0xf]),
+        ];
+        let mut encoded = paths
+            .iter()
+            .map(|path| {
+                let mut buf = vec![];
+                StoredNibblesSubKey(path.clone()).to_compact(&mut buf);
+                buf
+            })
+            .collect::<Vec<_>>();
+        paths.sort();
+        encoded.sort();
+
+        let decoded = encoded
+            .iter()
+            .map(|buf| StoredNibblesSubKey::from_compact(buf, buf.len()).0 .0)
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, paths);
+    }
+}
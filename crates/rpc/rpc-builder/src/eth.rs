@@ -22,4 +22,9 @@ pub struct EthHandlers<Client, Pool, Network, Events> {
 pub struct EthConfig {
     /// Settings for the caching layer
     pub cache: EthStateCacheConfig,
+    /// Whether the prefunded accounts of the dev chain should be managed by the node.
+    ///
+    /// This is only intended for `--dev` mode.
+    #[serde(default)]
+    pub dev_accounts: bool,
 }
@@ -608,6 +608,9 @@ where
                 self.network.clone(),
                 eth_cache.clone(),
             );
+            if self.config.eth.dev_accounts {
+                api.with_dev_accounts();
+            }
             let filter = EthFilter::new(self.client.clone(), self.pool.clone());
 
             // TODO: install pubsub
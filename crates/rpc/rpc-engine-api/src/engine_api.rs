@@ -1,6 +1,6 @@
 use crate::{message::EngineApiMessageVersion, EngineApiError, EngineApiMessage, EngineApiResult};
 use futures::StreamExt;
-use reth_interfaces::consensus::ForkchoiceState;
+use reth_interfaces::consensus::{Consensus, ForkchoiceState};
 use reth_primitives::{
     proofs::{self, EMPTY_LIST_HASH},
     BlockHash, BlockId, BlockNumber, ChainSpec, Hardfork, Header, SealedBlock, TransactionSigned,
@@ -12,8 +12,9 @@ use reth_provider::{
 };
 use reth_rlp::Decodable;
 use reth_rpc_types::engine::{
-    ExecutionPayload, ExecutionPayloadBodies, ForkchoiceUpdated, PayloadAttributes, PayloadStatus,
-    PayloadStatusEnum, TransitionConfiguration,
+    CancunPayloadFields, ExecutionPayload, ExecutionPayloadBodies, ExecutionPayloadEnvelopeV3,
+    ForkchoiceUpdated, PayloadAttributes, PayloadStatus, PayloadStatusEnum,
+    TransitionConfiguration,
 };
 use std::{
     future::Future,
@@ -40,6 +41,8 @@ pub struct EngineApi<Client> {
     client: Client,
     /// Consensus configuration
     chain_spec: Arc<ChainSpec>,
+    /// Consensus used to validate payload headers
+    consensus: Arc<dyn Consensus>,
     message_rx: UnboundedReceiverStream<EngineApiMessage>,
     forkchoice_state_tx: watch::Sender<ForkchoiceState>,
     // TODO: Placeholder for storing future blocks. Make cache bounded. Use lru
@@ -54,12 +57,14 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
     pub fn new(
         client: Client,
         chain_spec: ChainSpec,
+        consensus: Arc<dyn Consensus>,
         message_rx: mpsc::UnboundedReceiver<EngineApiMessage>,
         forkchoice_state_tx: watch::Sender<ForkchoiceState>,
     ) -> Self {
         Self {
             client,
             chain_spec: Arc::new(chain_spec),
+            consensus,
             message_rx: UnboundedReceiverStream::new(message_rx),
             forkchoice_state_tx,
         }
@@ -70,6 +75,10 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
             EngineApiMessage::GetPayload(payload_id, tx) => {
                 let _ = tx.send(self.get_payload(payload_id).ok_or(EngineApiError::PayloadUnknown));
             }
+            EngineApiMessage::GetPayloadV3(payload_id, tx) => {
+                let _ =
+                    tx.send(self.get_payload_v3(payload_id).ok_or(EngineApiError::PayloadUnknown));
+            }
             EngineApiMessage::GetPayloadBodiesByHash(hashes, tx) => {
                 let _ = tx.send(self.get_payload_bodies_by_hash(hashes));
             }
@@ -77,15 +86,20 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
                 let _ = tx.send(self.get_payload_bodies_by_range(start, count));
             }
             EngineApiMessage::NewPayload(version, payload, tx) => {
-                if let Err(err) = self.validate_withdrawals_presence(
-                    version,
-                    payload.timestamp.as_u64(),
-                    payload.withdrawals.is_some(),
-                ) {
+                if let Err(err) = self.validate_payload_fields_presence(version, &payload) {
+                    let _ = tx.send(Err(err));
+                    return
+                }
+                let _ = tx.send(self.new_payload(payload, None));
+            }
+            EngineApiMessage::NewPayloadV3(payload, cancun_fields, tx) => {
+                if let Err(err) =
+                    self.validate_payload_fields_presence(EngineApiMessageVersion::V3, &payload)
+                {
                     let _ = tx.send(Err(err));
                     return
                 }
-                let _ = tx.send(self.new_payload(payload));
+                let _ = tx.send(self.new_payload(payload, Some(cancun_fields)));
             }
             EngineApiMessage::ForkchoiceUpdated(version, state, attrs, tx) => {
                 if let Some(attributes) = &attrs {
@@ -124,7 +138,7 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
                     return Err(EngineApiError::InvalidParams)
                 }
             }
-            EngineApiMessageVersion::V2 => {
+            EngineApiMessageVersion::V2 | EngineApiMessageVersion::V3 => {
                 let shanghai_with_no_withdrawals = is_shanghai && !has_withdrawals;
                 let not_shanghai_with_withdrawals = !is_shanghai && has_withdrawals;
                 if shanghai_with_no_withdrawals || not_shanghai_with_withdrawals {
@@ -136,6 +150,55 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
         Ok(())
     }
 
+    /// Validates the presence of the blob gas fields according to the payload timestamp and
+    /// ensures the method version matches the fork of the payload.
+    /// After Cancun, only V3 is supported and the blob gas fields must be [Some].
+    /// Before Cancun, V3 is not supported and the blob gas fields must be [None].
+    fn validate_blob_gas_presence(
+        &self,
+        version: EngineApiMessageVersion,
+        timestamp: u64,
+        has_blob_gas_fields: bool,
+    ) -> EngineApiResult<()> {
+        let is_cancun = self.chain_spec.fork(Hardfork::Cancun).active_at_timestamp(timestamp);
+
+        match version {
+            EngineApiMessageVersion::V1 | EngineApiMessageVersion::V2 => {
+                if is_cancun {
+                    return Err(EngineApiError::UnsupportedFork)
+                }
+                if has_blob_gas_fields {
+                    return Err(EngineApiError::InvalidParams)
+                }
+            }
+            EngineApiMessageVersion::V3 => {
+                if !is_cancun {
+                    return Err(EngineApiError::UnsupportedFork)
+                }
+                if !has_blob_gas_fields {
+                    return Err(EngineApiError::InvalidParams)
+                }
+            }
+        };
+
+        Ok(())
+    }
+
+    /// Validates the presence of all version specific fields of the payload.
+    fn validate_payload_fields_presence(
+        &self,
+        version: EngineApiMessageVersion,
+        payload: &ExecutionPayload,
+    ) -> EngineApiResult<()> {
+        let timestamp = payload.timestamp.as_u64();
+        self.validate_withdrawals_presence(version, timestamp, payload.withdrawals.is_some())?;
+        self.validate_blob_gas_presence(
+            version,
+            timestamp,
+            payload.blob_gas_used.is_some() && payload.excess_blob_gas.is_some(),
+        )
+    }
+
     /// Try to construct a block from given payload. Perform addition validation of `extra_data` and
     /// `base_fee_per_gas` fields.
     ///
@@ -144,7 +207,11 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
     /// comparing the value with `payload.block_hash`.
     ///
     /// See <https://github.com/ethereum/go-ethereum/blob/79a478bb6176425c2400e949890e668a3d9a3d05/core/beacon/types.go#L145>
-    fn try_construct_block(&self, payload: ExecutionPayload) -> EngineApiResult<SealedBlock> {
+    fn try_construct_block(
+        &self,
+        payload: ExecutionPayload,
+        parent_beacon_block_root: Option<H256>,
+    ) -> EngineApiResult<SealedBlock> {
         if payload.extra_data.len() > 32 {
             return Err(EngineApiError::PayloadExtraData(payload.extra_data))
         }
@@ -177,6 +244,9 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
             timestamp: payload.timestamp.as_u64(),
             mix_hash: payload.prev_randao,
             base_fee_per_gas: Some(payload.base_fee_per_gas.to::<u64>()),
+            blob_gas_used: payload.blob_gas_used.map(|gas| gas.as_u64()),
+            excess_blob_gas: payload.excess_blob_gas.map(|gas| gas.as_u64()),
+            parent_beacon_block_root,
             extra_data: payload.extra_data,
             // Defaults
             ommers_hash: EMPTY_LIST_HASH,
@@ -209,6 +279,14 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
         None
     }
 
+    /// Called to retrieve a built payload along with the blobs of its transactions.
+    ///
+    /// NOTE: Will always result in `PayloadUnknown` since we don't support block
+    /// building for now.
+    pub fn get_payload_v3(&self, _payload_id: H64) -> Option<ExecutionPayloadEnvelopeV3> {
+        None
+    }
+
     /// Called to retrieve execution payload bodies by range.
     pub fn get_payload_bodies_by_range(
         &self,
@@ -260,8 +338,16 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
     ///
     /// These responses should adhere to the [Engine API Spec for
     /// `engine_newPayload`](https://github.com/ethereum/execution-apis/blob/main/src/engine/paris.md#specification).
-    pub fn new_payload(&mut self, payload: ExecutionPayload) -> EngineApiResult<PayloadStatus> {
-        let block = match self.try_construct_block(payload) {
+    ///
+    /// For `engine_newPayloadV3` the [CancunPayloadFields] are expected to be present, the
+    /// versioned hashes of all blob transactions are checked against the expected ones.
+    pub fn new_payload(
+        &mut self,
+        payload: ExecutionPayload,
+        cancun_fields: Option<CancunPayloadFields>,
+    ) -> EngineApiResult<PayloadStatus> {
+        let parent_beacon_block_root = cancun_fields.as_ref().map(|f| f.parent_beacon_block_root);
+        let block = match self.try_construct_block(payload, parent_beacon_block_root) {
             Ok(b) => b,
             Err(err) => {
                 return Ok(PayloadStatus::from_status(PayloadStatusEnum::InvalidBlockHash {
@@ -269,6 +355,25 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
                 }))
             }
         };
+
+        if let Some(CancunPayloadFields { versioned_hashes, .. }) = cancun_fields {
+            let block_versioned_hashes = block
+                .body
+                .iter()
+                .filter_map(|tx| tx.blob_versioned_hashes())
+                .flatten()
+                .copied()
+                .collect::<Vec<_>>();
+            if block_versioned_hashes != versioned_hashes {
+                return Ok(PayloadStatus::from_status(PayloadStatusEnum::Invalid {
+                    validation_error: EngineApiError::PayloadVersionedHashes {
+                        expected: versioned_hashes,
+                        got: block_versioned_hashes,
+                    }
+                    .to_string(),
+                }))
+            }
+        }
         let block_hash = block.header.hash();
         let parent_hash = block.parent_hash;
 
@@ -278,8 +383,8 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
         }
 
         let Some(parent) = self.client.block_by_hash(parent_hash)? else {
-             // TODO: cache block for storing later
-             return Ok(PayloadStatus::from_status(PayloadStatusEnum::Syncing))
+            // TODO: cache block for storing later
+            return Ok(PayloadStatus::from_status(PayloadStatusEnum::Syncing))
         };
 
         let parent_td = if let Some(parent_td) = self.client.header_td(&block.parent_hash)? {
@@ -320,8 +425,21 @@ impl<Client: HeaderProvider + BlockProvider + StateProviderFactory + EvmEnvProvi
             }))
         }
 
-        let state_provider = self.client.latest()?;
         let total_difficulty = parent_td + block.header.difficulty;
+        let parent_header = parent.header.seal(parent_hash);
+        if let Err(err) = self
+            .consensus
+            .pre_validate_header(&block.header, &parent_header)
+            .and_then(|_| self.consensus.validate_header(&block.header, total_difficulty))
+            .and_then(|_| self.consensus.pre_validate_block(&block))
+        {
+            return Ok(PayloadStatus::new(
+                PayloadStatusEnum::Invalid { validation_error: err.to_string() },
+                parent_hash,
+            ))
+        }
+
+        let state_provider = self.client.latest()?;
 
         let factory = reth_executor::Factory::new(self.chain_spec.clone());
         let mut executor = factory.with_sp(&state_provider);
@@ -448,7 +566,7 @@ where
 mod tests {
     use super::*;
     use assert_matches::assert_matches;
-    use reth_interfaces::test_utils::generators::random_block;
+    use reth_interfaces::test_utils::{generators::random_block, TestConsensus};
     use reth_primitives::{H256, MAINNET};
     use reth_provider::test_utils::MockEthProvider;
     use std::sync::Arc;
@@ -465,6 +583,7 @@ mod tests {
         let api = EngineApi {
             client: client.clone(),
             chain_spec: chain_spec.clone(),
+            consensus: Arc::new(TestConsensus::default()),
             message_rx: UnboundedReceiverStream::new(msg_rx),
             forkchoice_state_tx,
         };
@@ -529,7 +648,10 @@ mod tests {
                 b.header.extra_data = BytesMut::zeroed(32).freeze().into();
                 b
             });
-            assert_matches!(api.try_construct_block(block_with_valid_extra_data.into()), Ok(_));
+            assert_matches!(
+                api.try_construct_block(block_with_valid_extra_data.into(), None),
+                Ok(_)
+            );
 
             // Invalid extra data
             let block_with_invalid_extra_data: Bytes = BytesMut::zeroed(33).freeze();
@@ -538,7 +660,7 @@ mod tests {
                 b
             });
             assert_matches!(
-                api.try_construct_block(invalid_extra_data_block.into()),
+                api.try_construct_block(invalid_extra_data_block.into(), None),
                 Err(EngineApiError::PayloadExtraData(data)) if data == block_with_invalid_extra_data
             );
 
@@ -548,7 +670,7 @@ mod tests {
                 b
             });
             assert_matches!(
-                api.try_construct_block(block_with_zero_base_fee.into()),
+                api.try_construct_block(block_with_zero_base_fee.into(), None),
                 Err(EngineApiError::PayloadBaseFee(val)) if val == U256::ZERO
             );
 
@@ -558,7 +680,7 @@ mod tests {
                 *tx = Bytes::new().into();
             });
             assert_matches!(
-                api.try_construct_block(payload_with_invalid_txs),
+                api.try_construct_block(payload_with_invalid_txs, None),
                 Err(EngineApiError::Decode(DecodeError::InputTooShort))
             );
 
@@ -568,7 +690,7 @@ mod tests {
                 b
             });
             assert_matches!(
-                api.try_construct_block(block_with_ommers.clone().into()),
+                api.try_construct_block(block_with_ommers.clone().into(), None),
                 Err(EngineApiError::PayloadBlockHash { consensus, .. })
                     if consensus == block_with_ommers.hash()
             );
@@ -579,7 +701,7 @@ mod tests {
                 b
             });
             assert_matches!(
-                api.try_construct_block(block_with_difficulty.clone().into()),
+                api.try_construct_block(block_with_difficulty.clone().into(), None),
                 Err(EngineApiError::PayloadBlockHash { consensus, .. })
                     if consensus == block_with_difficulty.hash()
             );
@@ -590,14 +712,14 @@ mod tests {
                 b
             });
             assert_matches!(
-                api.try_construct_block(block_with_nonce.clone().into()),
+                api.try_construct_block(block_with_nonce.clone().into(), None),
                 Err(EngineApiError::PayloadBlockHash { consensus, .. })
                     if consensus == block_with_nonce.hash()
             );
 
             // Valid block
             let valid_block = block;
-            assert_matches!(api.try_construct_block(valid_block.into()), Ok(_));
+            assert_matches!(api.try_construct_block(valid_block.into(), None), Ok(_));
         }
 
         #[tokio::test]
@@ -705,6 +827,53 @@ mod tests {
             assert_matches!( result_rx.await, Ok(Ok(result)) => assert_eq!(result, expected_result));
         }
 
+        #[tokio::test]
+        async fn payload_v3_pre_cancun() {
+            let (handle, api) = setup_engine_api();
+            tokio::spawn(api);
+
+            let block = random_block(100, Some(H256::random()), None, Some(0));
+            let mut payload: ExecutionPayload = block.into();
+            payload.blob_gas_used = Some(0u64.into());
+            payload.excess_blob_gas = Some(0u64.into());
+            let cancun_fields = CancunPayloadFields {
+                versioned_hashes: vec![],
+                parent_beacon_block_root: H256::random(),
+            };
+
+            let (result_tx, result_rx) = oneshot::channel();
+            handle.send_message(EngineApiMessage::NewPayloadV3(payload, cancun_fields, result_tx));
+            assert_matches!(result_rx.await, Ok(Err(EngineApiError::UnsupportedFork)));
+        }
+
+        #[tokio::test]
+        async fn payload_versioned_hashes_mismatch() {
+            let (_, mut api) = setup_engine_api();
+
+            let parent_beacon_block_root = H256::random();
+            let block = transform_block(random_block(100, None, None, Some(0)), |mut b| {
+                b.header.parent_beacon_block_root = Some(parent_beacon_block_root);
+                b
+            });
+            let versioned_hashes = vec![H256::random()];
+            let cancun_fields = CancunPayloadFields {
+                versioned_hashes: versioned_hashes.clone(),
+                parent_beacon_block_root,
+            };
+
+            let expected_result = PayloadStatus::from_status(PayloadStatusEnum::Invalid {
+                validation_error: EngineApiError::PayloadVersionedHashes {
+                    expected: versioned_hashes,
+                    got: vec![],
+                }
+                .to_string(),
+            });
+            assert_matches!(
+                api.new_payload(block.into(), Some(cancun_fields)),
+                Ok(result) => assert_eq!(result, expected_result)
+            );
+        }
+
         // TODO: add execution tests
     }
 
@@ -8,6 +8,8 @@ pub type EngineApiResult<Ok> = Result<Ok, EngineApiError>;
 pub const UNKNOWN_PAYLOAD_CODE: i32 = -38001;
 /// Request too large error code.
 pub const REQUEST_TOO_LARGE_CODE: i32 = -38004;
+/// Unsupported fork error code.
+pub const UNSUPPORTED_FORK_CODE: i32 = -38005;
 
 /// Error returned by [`EngineApi`][crate::EngineApi]
 #[derive(Error, PartialEq, Debug)]
@@ -55,6 +57,17 @@ pub enum EngineApiError {
     /// The params are invalid.
     #[error("Invalid params")]
     InvalidParams,
+    /// The payload timestamp does not fall within the fork supported by the method version.
+    #[error("Unsupported fork")]
+    UnsupportedFork,
+    /// The blob versioned hashes of the payload transactions do not match the provided hashes.
+    #[error("Invalid blob versioned hashes. Expected: {expected:?}. Got: {got:?}")]
+    PayloadVersionedHashes {
+        /// The versioned hashes provided alongside the payload.
+        expected: Vec<H256>,
+        /// The versioned hashes referenced by the payload transactions.
+        got: Vec<H256>,
+    },
     /// Terminal total difficulty mismatch during transition configuration exchange.
     #[error(
         "Invalid transition terminal total difficulty. Execution: {execution}. Consensus: {consensus}"
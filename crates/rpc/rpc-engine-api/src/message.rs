@@ -2,8 +2,8 @@ use crate::EngineApiSender;
 use reth_interfaces::consensus::ForkchoiceState;
 use reth_primitives::{BlockHash, BlockNumber, H64};
 use reth_rpc_types::engine::{
-    ExecutionPayload, ExecutionPayloadBodies, ForkchoiceUpdated, PayloadAttributes, PayloadStatus,
-    TransitionConfiguration,
+    CancunPayloadFields, ExecutionPayload, ExecutionPayloadBodies, ExecutionPayloadEnvelopeV3,
+    ForkchoiceUpdated, PayloadAttributes, PayloadStatus, TransitionConfiguration,
 };
 
 /// Message type for communicating with [`EngineApi`][crate::EngineApi].
@@ -11,8 +11,12 @@ use reth_rpc_types::engine::{
 pub enum EngineApiMessage {
     /// New payload message
     NewPayload(EngineApiMessageVersion, ExecutionPayload, EngineApiSender<PayloadStatus>),
+    /// New payload message with the additional fields introduced in V3
+    NewPayloadV3(ExecutionPayload, CancunPayloadFields, EngineApiSender<PayloadStatus>),
     /// Get payload message
     GetPayload(H64, EngineApiSender<ExecutionPayload>),
+    /// Get payload message for V3, which includes the blobs bundle
+    GetPayloadV3(H64, EngineApiSender<ExecutionPayloadEnvelopeV3>),
     /// Get payload bodies by range message
     GetPayloadBodiesByRange(BlockNumber, u64, EngineApiSender<ExecutionPayloadBodies>),
     /// Get payload bodies by hash message
@@ -38,4 +42,6 @@ pub enum EngineApiMessageVersion {
     V1,
     /// Version 2
     V2,
+    /// Version 3
+    V3,
 }
@@ -240,6 +240,15 @@ pub struct Header {
     pub mix_hash: H256,
     /// Nonce
     pub nonce: Option<H64>,
+    /// Blob gas used
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U256>,
+    /// Excess blob gas
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub excess_blob_gas: Option<U256>,
+    /// Parent beacon block root
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parent_beacon_block_root: Option<H256>,
 }
 
 // === impl Header ===
@@ -267,6 +276,9 @@ impl Header {
             base_fee_per_gas: _,
             extra_data,
             withdrawals_root,
+            blob_gas_used,
+            excess_blob_gas,
+            parent_beacon_block_root,
         } = primitive_header;
 
         Header {
@@ -288,6 +300,9 @@ impl Header {
             difficulty,
             mix_hash,
             nonce: Some(nonce.to_be_bytes().into()),
+            blob_gas_used: blob_gas_used.map(U256::from),
+            excess_blob_gas: excess_blob_gas.map(U256::from),
+            parent_beacon_block_root,
         }
     }
 }
@@ -385,6 +400,9 @@ mod tests {
                 difficulty: U256::from(13),
                 mix_hash: H256::from_low_u64_be(14),
                 nonce: Some(H64::from_low_u64_be(15)),
+                blob_gas_used: None,
+                excess_blob_gas: None,
+                parent_beacon_block_root: None,
             },
             total_difficulty: Some(U256::from(100000)),
             uncles: vec![H256::from_low_u64_be(17)],
@@ -9,14 +9,16 @@ use reth_rlp::Encodable;
 use serde::{Deserialize, Serialize};
 
 /// The list of supported Engine capabilities
-pub const CAPABILITIES: [&str; 9] = [
+pub const CAPABILITIES: [&str; 11] = [
     "engine_forkchoiceUpdatedV1",
     "engine_forkchoiceUpdatedV2",
     "engine_exchangeTransitionConfigurationV1",
     "engine_getPayloadV1",
     "engine_getPayloadV2",
+    "engine_getPayloadV3",
     "engine_newPayloadV1",
     "engine_newPayloadV2",
+    "engine_newPayloadV3",
     "engine_getPayloadBodiesByHashV1",
     "engine_getPayloadBodiesByRangeV1",
 ];
@@ -45,6 +47,15 @@ pub struct ExecutionPayload {
     /// See <https://github.com/ethereum/execution-apis/blob/6709c2a795b707202e93c4f2867fa0bf2640a84f/src/engine/shanghai.md#executionpayloadv2>
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub withdrawals: Option<Vec<Withdrawal>>,
+    /// The total amount of blob gas consumed by the transactions within the block, enabled with
+    /// V3
+    /// See <https://github.com/ethereum/execution-apis/blob/fe8e13c288c592ec154ce25c534e26cb7ce0530d/src/engine/cancun.md#executionpayloadv3>
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub blob_gas_used: Option<U64>,
+    /// The running total of blob gas consumed in excess of the target, enabled with V3
+    /// See <https://github.com/ethereum/execution-apis/blob/fe8e13c288c592ec154ce25c534e26cb7ce0530d/src/engine/cancun.md#executionpayloadv3>
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub excess_blob_gas: Option<U64>,
 }
 
 impl From<SealedBlock> for ExecutionPayload {
@@ -74,10 +85,47 @@ impl From<SealedBlock> for ExecutionPayload {
             block_hash: value.hash(),
             transactions,
             withdrawals: value.withdrawals,
+            blob_gas_used: value.blob_gas_used.map(Into::into),
+            excess_blob_gas: value.excess_blob_gas.map(Into::into),
         }
     }
 }
 
+/// The fields of `engine_newPayloadV3` that are passed alongside the [ExecutionPayload].
+///
+/// See also: <https://github.com/ethereum/execution-apis/blob/fe8e13c288c592ec154ce25c534e26cb7ce0530d/src/engine/cancun.md#engine_newpayloadv3>
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CancunPayloadFields {
+    /// The expected blob versioned hashes of all blob transactions in the payload, in order.
+    pub versioned_hashes: Vec<H256>,
+    /// The root of the parent beacon block.
+    pub parent_beacon_block_root: H256,
+}
+
+/// The blobs, commitments and proofs of all blob transactions in a built payload.
+///
+/// See also: <https://github.com/ethereum/execution-apis/blob/fe8e13c288c592ec154ce25c534e26cb7ce0530d/src/engine/cancun.md#blobsbundlev1>
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlobsBundleV1 {
+    pub commitments: Vec<Bytes>,
+    pub proofs: Vec<Bytes>,
+    pub blobs: Vec<Bytes>,
+}
+
+/// The response of `engine_getPayloadV3`.
+///
+/// See also: <https://github.com/ethereum/execution-apis/blob/fe8e13c288c592ec154ce25c534e26cb7ce0530d/src/engine/cancun.md#response-2>
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExecutionPayloadEnvelopeV3 {
+    pub execution_payload: ExecutionPayload,
+    pub block_value: U256,
+    pub blobs_bundle: BlobsBundleV1,
+    pub should_override_builder: bool,
+}
+
 /// This structure contains a body of an execution payload.
 ///
 /// See also: <https://github.com/ethereum/execution-apis/blob/6452a6b194d7db269bf1dbd087a267251d3cc7f8/src/engine/shanghai.md#executionpayloadbodyv1>
@@ -232,4 +280,20 @@ mod tests {
             assert_eq!(block.withdrawals.unwrap_or_default(), payload_body.withdrawals);
         }
     }
+
+    #[test]
+    fn serde_payload_blob_fields() {
+        let block = random_block_range(0..1, H256::default(), 0..2).remove(0);
+        let mut payload: ExecutionPayload = block.into();
+        let json = serde_json::to_value(&payload).unwrap();
+        assert!(json.get("blobGasUsed").is_none());
+        assert!(json.get("excessBlobGas").is_none());
+
+        payload.blob_gas_used = Some(U64::from(0x20000));
+        payload.excess_blob_gas = Some(U64::from(0));
+        let json = serde_json::to_value(&payload).unwrap();
+        assert_eq!(json["blobGasUsed"], "0x20000");
+        assert_eq!(json["excessBlobGas"], "0x0");
+        assert_eq!(serde_json::from_value::<ExecutionPayload>(json).unwrap(), payload);
+    }
 }
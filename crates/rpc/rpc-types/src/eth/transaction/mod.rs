@@ -102,6 +102,7 @@ impl Transaction {
             TxType::Legacy => (Some(U128::from(signed_tx.max_fee_per_gas())), None),
             TxType::EIP2930 => (None, Some(U128::from(signed_tx.max_fee_per_gas()))),
             TxType::EIP1559 => (None, Some(U128::from(signed_tx.max_fee_per_gas()))),
+            TxType::EIP4844 => (None, Some(U128::from(signed_tx.max_fee_per_gas()))),
         };
 
         let chain_id = signed_tx.chain_id().map(U64::from);
@@ -127,6 +128,16 @@ impl Transaction {
                     })
                     .collect(),
             ),
+            PrimitiveTransaction::Eip4844(tx) => Some(
+                tx.access_list
+                    .0
+                    .iter()
+                    .map(|item| AccessListItem {
+                        address: item.address.0.into(),
+                        storage_keys: item.storage_keys.iter().map(|key| key.0.into()).collect(),
+                    })
+                    .collect(),
+            ),
         };
 
         Self {
@@ -5,15 +5,15 @@ use jsonrpsee::{
     types::error::INVALID_PARAMS_CODE,
 };
 use reth_interfaces::consensus::ForkchoiceState;
-use reth_primitives::{BlockHash, BlockNumber, H64};
+use reth_primitives::{BlockHash, BlockNumber, H256, H64};
 use reth_rpc_api::EngineApiServer;
 use reth_rpc_engine_api::{
     EngineApiError, EngineApiHandle, EngineApiMessage, EngineApiMessageVersion, EngineApiResult,
-    REQUEST_TOO_LARGE_CODE, UNKNOWN_PAYLOAD_CODE,
+    REQUEST_TOO_LARGE_CODE, UNKNOWN_PAYLOAD_CODE, UNSUPPORTED_FORK_CODE,
 };
 use reth_rpc_types::engine::{
-    ExecutionPayload, ExecutionPayloadBodies, ForkchoiceUpdated, PayloadAttributes, PayloadStatus,
-    TransitionConfiguration, CAPABILITIES,
+    CancunPayloadFields, ExecutionPayload, ExecutionPayloadBodies, ExecutionPayloadEnvelopeV3,
+    ForkchoiceUpdated, PayloadAttributes, PayloadStatus, TransitionConfiguration, CAPABILITIES,
 };
 use tokio::sync::oneshot::{self, Receiver};
 
@@ -48,6 +48,7 @@ impl EngineApi {
                 EngineApiError::InvalidParams => INVALID_PARAMS_CODE,
                 EngineApiError::PayloadUnknown => UNKNOWN_PAYLOAD_CODE,
                 EngineApiError::PayloadRequestTooLarge { .. } => REQUEST_TOO_LARGE_CODE,
+                EngineApiError::UnsupportedFork => UNSUPPORTED_FORK_CODE,
                 // Any other server error
                 _ => jsonrpsee::types::error::INTERNAL_ERROR_CODE,
             };
@@ -79,6 +80,18 @@ impl EngineApiServer for EngineApi {
         .await
     }
 
+    /// See also <https://github.com/ethereum/execution-apis/blob/fe8e13c288c592ec154ce25c534e26cb7ce0530d/src/engine/cancun.md#engine_newpayloadv3>
+    async fn new_payload_v3(
+        &self,
+        payload: ExecutionPayload,
+        versioned_hashes: Vec<H256>,
+        parent_beacon_block_root: H256,
+    ) -> Result<PayloadStatus> {
+        let (tx, rx) = oneshot::channel();
+        let cancun_fields = CancunPayloadFields { versioned_hashes, parent_beacon_block_root };
+        self.delegate_request(EngineApiMessage::NewPayloadV3(payload, cancun_fields, tx), rx).await
+    }
+
     /// See also <https://github.com/ethereum/execution-apis/blob/8db51dcd2f4bdfbd9ad6e4a7560aac97010ad063/src/engine/specification.md#engine_forkchoiceUpdatedV1>
     ///
     /// Caution: This should not accept the `withdrawals` field
@@ -133,6 +146,12 @@ impl EngineApiServer for EngineApi {
         self.delegate_request(EngineApiMessage::GetPayload(payload_id, tx), rx).await
     }
 
+    /// See also <https://github.com/ethereum/execution-apis/blob/fe8e13c288c592ec154ce25c534e26cb7ce0530d/src/engine/cancun.md#engine_getpayloadv3>
+    async fn get_payload_v3(&self, payload_id: H64) -> Result<ExecutionPayloadEnvelopeV3> {
+        let (tx, rx) = oneshot::channel();
+        self.delegate_request(EngineApiMessage::GetPayloadV3(payload_id, tx), rx).await
+    }
+
     /// See also <https://github.com/ethereum/execution-apis/blob/6452a6b194d7db269bf1dbd087a267251d3cc7f8/src/engine/shanghai.md#engine_getpayloadbodiesbyhashv1>
     async fn get_payload_bodies_by_hash_v1(
         &self,
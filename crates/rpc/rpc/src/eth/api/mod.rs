@@ -3,8 +3,9 @@
 //! The entire implementation of the namespace is quite large, hence it is divided across several
 //! files.
 
-use crate::eth::signer::EthSigner;
+use crate::eth::signer::{DevSigner, EthSigner};
 use async_trait::async_trait;
+use parking_lot::RwLock;
 use reth_interfaces::Result;
 use reth_network_api::NetworkInfo;
 use reth_primitives::{
@@ -76,6 +77,14 @@ impl<Client, Pool, Network> EthApi<Client, Pool, Network> {
         }
     }
 
+    /// Registers the prefunded accounts of the [DEV](reth_primitives::DEV) chain as accounts that
+    /// are managed by this node.
+    ///
+    /// Used in `--dev` mode.
+    pub fn with_dev_accounts(&self) {
+        self.inner.signers.write().push(Box::new(DevSigner::dev_accounts()));
+    }
+
     /// Returns the state cache frontend
     pub(crate) fn cache(&self) -> &EthStateCache {
         &self.inner.eth_cache
@@ -208,7 +217,7 @@ where
     }
 
     fn accounts(&self) -> Vec<Address> {
-        self.inner.signers.iter().flat_map(|s| s.accounts()).collect()
+        self.inner.signers.read().iter().flat_map(|s| s.accounts()).collect()
     }
 
     async fn transaction_by_hash(&self, hash: H256) -> Result<Option<TransactionSigned>> {
@@ -225,7 +234,7 @@ struct EthApiInner<Client, Pool, Network> {
     /// An interface to interact with the network
     network: Network,
     /// All configured Signers
-    signers: Vec<Box<dyn EthSigner>>,
+    signers: RwLock<Vec<Box<dyn EthSigner>>>,
     /// The async cache frontend for eth related data
     eth_cache: EthStateCache,
 }
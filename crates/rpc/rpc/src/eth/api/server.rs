@@ -234,7 +234,11 @@ where
             return Ok(FeeHistory::default())
         }
 
-        let Some(end_block) = self.inner.client.block_number_for_id(newest_block).to_rpc_result()? else { return Err(EthApiError::UnknownBlockNumber.into())};
+        let Some(end_block) =
+            self.inner.client.block_number_for_id(newest_block).to_rpc_result()?
+        else {
+            return Err(EthApiError::UnknownBlockNumber.into())
+        };
 
         if end_block < block_count {
             return Err(EthApiError::InvalidBlockRange.into())
@@ -350,8 +354,8 @@ where
     }
 
     /// Handler for: `eth_sendTransaction`
-    async fn send_transaction(&self, _request: TransactionRequest) -> Result<H256> {
-        Err(internal_rpc_err("unimplemented"))
+    async fn send_transaction(&self, request: TransactionRequest) -> Result<H256> {
+        Ok(EthApi::send_transaction(self, request).await?)
     }
 
     /// Handler for: `eth_sendRawTransaction`
@@ -360,8 +364,8 @@ where
     }
 
     /// Handler for: `eth_sign`
-    async fn sign(&self, _address: Address, _message: Bytes) -> Result<Bytes> {
-        Err(internal_rpc_err("unimplemented"))
+    async fn sign(&self, address: Address, message: Bytes) -> Result<Bytes> {
+        Ok(EthApi::sign(self, address, &message)?)
     }
 
     /// Handler for: `eth_signTransaction`
@@ -1,14 +1,20 @@
 //! Contains RPC handler implementations specific to transactions
 
 use crate::{
-    eth::error::{EthApiError, EthResult},
+    eth::{
+        api::EthApiSpec,
+        error::{EthApiError, EthResult},
+    },
     EthApi,
 };
-use reth_primitives::{Bytes, FromRecoveredTransaction, TransactionSigned, H256};
+use reth_primitives::{
+    Address, BlobTransaction, BlockId, BlockNumberOrTag, Bytes, FromRecoveredTransaction,
+    PooledTransactionsElement, TransactionSigned, EIP4844_TX_TYPE_ID, H256, U128, U256,
+};
 use reth_provider::{BlockProvider, EvmEnvProvider, StateProviderFactory};
 use reth_rlp::Decodable;
-use reth_rpc_types::TransactionRequest;
-use reth_transaction_pool::{TransactionOrigin, TransactionPool};
+use reth_rpc_types::{CallRequest, TransactionRequest, TypedTransactionRequest};
+use reth_transaction_pool::{PoolTransaction, TransactionOrigin, TransactionPool};
 
 impl<Client, Pool, Network> EthApi<Client, Pool, Network>
 where
@@ -16,12 +22,30 @@ where
     Client: BlockProvider + StateProviderFactory + EvmEnvProvider + 'static,
     Network: 'static,
 {
-    pub(crate) async fn send_transaction(&self, _request: TransactionRequest) -> EthResult<H256> {
-        unimplemented!()
+    /// Signs the message with the given account, as specified by `eth_sign`.
+    ///
+    /// Returns the signature in the `r || s || v` format, with `v` being `27` or `28`.
+    pub(crate) fn sign(&self, account: Address, message: &[u8]) -> EthResult<Bytes> {
+        let signature = self
+            .inner
+            .signers
+            .read()
+            .iter()
+            .find(|signer| signer.is_signer_for(&account))
+            .ok_or(EthApiError::UnknownAccount)?
+            .sign(account, message)?;
+
+        let mut bytes = Vec::with_capacity(65);
+        bytes.extend_from_slice(&signature.r.to_be_bytes::<32>());
+        bytes.extend_from_slice(&signature.s.to_be_bytes::<32>());
+        bytes.push(signature.odd_y_parity as u8 + 27);
+        Ok(bytes.into())
     }
 
     /// Decodes and recovers the transaction and submits it to the pool.
     ///
+    /// Blob transactions are expected in their network form, including the sidecar.
+    ///
     /// Returns the hash of the transaction.
     pub(crate) async fn send_raw_transaction(&self, tx: Bytes) -> EthResult<H256> {
         let mut data = tx.as_ref();
@@ -29,12 +53,104 @@ where
             return Err(EthApiError::EmptyRawTransactionData)
         }
 
-        let transaction = TransactionSigned::decode(&mut data)
-            .map_err(|_| EthApiError::FailedToDecodeSignedTransaction)?;
+        let transaction: PooledTransactionsElement = if data[0] == EIP4844_TX_TYPE_ID {
+            BlobTransaction::decode_enveloped(&mut data).map(Into::into)
+        } else {
+            TransactionSigned::decode(&mut data).map(Into::into)
+        }
+        .map_err(|_| EthApiError::FailedToDecodeSignedTransaction)?;
 
-        let recovered =
-            transaction.into_ecrecovered().ok_or(EthApiError::InvalidTransactionSignature)?;
+        let (recovered, sidecar) =
+            transaction.try_into_ecrecovered().ok_or(EthApiError::InvalidTransactionSignature)?;
+
+        let pool_transaction = match sidecar {
+            Some(sidecar) => {
+                <Pool::Transaction as PoolTransaction>::from_recovered_blob_transaction(
+                    recovered, sidecar,
+                )
+            }
+            None => <Pool::Transaction>::from_recovered_transaction(recovered),
+        };
+
+        // submit the transaction to the pool with a `Local` origin
+        let hash = self.pool().add_transaction(TransactionOrigin::Local, pool_transaction).await?;
+
+        Ok(hash)
+    }
+}
+
+impl<Client, Pool, Network> EthApi<Client, Pool, Network>
+where
+    Self: EthApiSpec,
+    Pool: TransactionPool + 'static,
+    Client: BlockProvider + StateProviderFactory + EvmEnvProvider + 'static,
+    Network: 'static,
+{
+    /// Fills the missing fields of the request, signs it with the account in `from` and submits
+    /// the transaction to the pool.
+    ///
+    /// Returns the hash of the transaction.
+    pub(crate) async fn send_transaction(
+        &self,
+        mut request: TransactionRequest,
+    ) -> EthResult<H256> {
+        let from = request.from.unwrap_or_default();
+        if !self.inner.signers.read().iter().any(|signer| signer.is_signer_for(&from)) {
+            return Err(EthApiError::UnknownAccount)
+        }
 
+        if let (Some(gas_price), Some(max_fee_per_gas)) =
+            (request.gas_price, request.max_fee_per_gas)
+        {
+            return Err(EthApiError::ConflictingRequestGasPrice { gas_price, max_fee_per_gas })
+        }
+
+        if request.nonce.is_none() {
+            request.nonce = Some(U256::from(self.next_nonce(from)?));
+        }
+
+        if request.gas_price.is_none() && request.max_fee_per_gas.is_none() {
+            // pay up to twice the current base fee, so the transaction stays valid if the base
+            // fee rises
+            let best_number = self.client().chain_info()?.best_number;
+            let base_fee = self
+                .client()
+                .header_by_number(best_number)?
+                .and_then(|header| header.base_fee_per_gas)
+                .unwrap_or_default();
+            let priority_fee = request.max_priority_fee_per_gas.unwrap_or_default();
+            request.max_fee_per_gas = Some(U128::from(base_fee.saturating_mul(2)) + priority_fee);
+        }
+
+        if request.gas.is_none() {
+            let call = CallRequest {
+                from: Some(from),
+                to: request.to,
+                gas_price: request.gas_price,
+                max_fee_per_gas: request.max_fee_per_gas,
+                max_priority_fee_per_gas: request.max_priority_fee_per_gas,
+                value: request.value,
+                data: request.data.clone(),
+                nonce: request.nonce,
+                access_list: request.access_list.clone(),
+                ..Default::default()
+            };
+            request.gas =
+                Some(self.estimate_gas_at(call, BlockId::Number(BlockNumberOrTag::Latest)).await?);
+        }
+
+        let chain_id = EthApiSpec::chain_id(self).as_u64();
+        let mut typed =
+            request.into_typed_request().ok_or(EthApiError::InvalidTransactionRequest)?;
+        match typed {
+            TypedTransactionRequest::Legacy(ref mut request) => request.chain_id = Some(chain_id),
+            TypedTransactionRequest::EIP2930(ref mut request) => request.chain_id = chain_id,
+            TypedTransactionRequest::EIP1559(ref mut request) => request.chain_id = chain_id,
+        }
+
+        let signed = self.sign_request(&from, typed)?;
+        let recovered =
+            signed.into_ecrecovered().ok_or(EthApiError::InvalidTransactionSignature)?;
         let pool_transaction = <Pool::Transaction>::from_recovered_transaction(recovered);
 
         // submit the transaction to the pool with a `Local` origin
@@ -42,16 +158,71 @@ where
 
         Ok(hash)
     }
+
+    /// Signs the transaction request with the signer of the given account.
+    fn sign_request(
+        &self,
+        from: &Address,
+        request: TypedTransactionRequest,
+    ) -> EthResult<TransactionSigned> {
+        self.inner
+            .signers
+            .read()
+            .iter()
+            .find(|signer| signer.is_signer_for(from))
+            .ok_or(EthApiError::UnknownAccount)?
+            .sign_transaction(request, from)
+    }
+
+    /// Returns the nonce of the next transaction of the account, which accounts for the
+    /// transactions of the account that are already in the pool.
+    fn next_nonce(&self, account: Address) -> EthResult<u64> {
+        let state_nonce = self.get_transaction_count(account, None)?.to::<u64>();
+        let pool_nonce = self
+            .pool()
+            .pooled_transactions()
+            .iter()
+            .filter(|tx| tx.transaction.sender() == account)
+            .map(|tx| tx.transaction.nonce() + 1)
+            .max()
+            .unwrap_or_default();
+        Ok(state_nonce.max(pool_nonce))
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::eth::cache::EthStateCache;
-    use reth_primitives::{hex_literal::hex, Bytes};
-    use reth_provider::test_utils::NoopProvider;
+    use reth_db::{
+        database::Database,
+        mdbx::{test_utils::create_test_db, EnvKind, WriteMap},
+        tables,
+        transaction::DbTxMut,
+    };
+    use reth_network_api::test_utils::NoopNetwork;
+    use reth_primitives::{hex_literal::hex, Bytes, PrunePart, H256, MAINNET};
+    use reth_provider::{test_utils::NoopProvider, ShareableDatabase};
     use reth_transaction_pool::{test_utils::testing_pool, TransactionPool};
 
-    use crate::EthApi;
+    use crate::{EthApi, EthApiSpec};
+
+    #[tokio::test]
+    async fn transaction_by_hash_with_pruned_lookup() {
+        let db = create_test_db::<WriteMap>(EnvKind::RW);
+        let tx = db.tx_mut().unwrap();
+        tx.put::<tables::PruneCheckpoints>(PrunePart::TransactionLookup, 10).unwrap();
+        tx.commit().unwrap();
+
+        let eth_api = EthApi::new(
+            ShareableDatabase::new(db, MAINNET.clone()),
+            testing_pool(),
+            NoopNetwork::default(),
+            EthStateCache::spawn(NoopProvider::default(), Default::default()),
+        );
+
+        // an unknown hash is not reported as pruned
+        assert_eq!(eth_api.transaction_by_hash(H256::random()).await.unwrap(), None);
+    }
 
     #[tokio::test]
     async fn send_raw_transaction() {
@@ -49,6 +49,13 @@ pub(crate) enum EthApiError {
     /// conflicting `state` and `stateDiff` fields
     #[error("account {0:?} has both 'state' and 'stateDiff'")]
     BothStateAndStateDiffInOverride(Address),
+    /// Thrown when no signer manages the requested account
+    #[error("unknown account")]
+    UnknownAccount,
+    /// Thrown when a transaction request can't be converted into a transaction, e.g. because a
+    /// field exceeds the value range of the transaction
+    #[error("invalid transaction request")]
+    InvalidTransactionRequest,
     /// Other internal error
     #[error(transparent)]
     Internal(#[from] reth_interfaces::Error),
@@ -65,7 +72,9 @@ impl From<EthApiError> for RpcError {
             EthApiError::ConflictingRequestGasPrice { .. } |
             EthApiError::ConflictingRequestGasPriceAndTipSet { .. } |
             EthApiError::RequestLegacyGasPriceAndTipSet { .. } |
-            EthApiError::BothStateAndStateDiffInOverride(_) => {
+            EthApiError::BothStateAndStateDiffInOverride(_) |
+            EthApiError::UnknownAccount |
+            EthApiError::InvalidTransactionRequest => {
                 rpc_err(INVALID_PARAMS_CODE, error.to_string(), None)
             }
             EthApiError::InvalidTransaction(err) => err.into(),
@@ -285,24 +294,23 @@ pub(crate) enum GethTxPoolError {
     NegativeValue,
     #[error("oversized data")]
     OversizedData,
+    #[error("nonce too low")]
+    NonceTooLow,
 }
 
-impl From<PoolError> for GethTxPoolError {
-    fn from(err: PoolError) -> GethTxPoolError {
-        match err {
+impl From<PoolError> for EthApiError {
+    fn from(err: PoolError) -> Self {
+        let err = match err {
             PoolError::ReplacementUnderpriced(_) => GethTxPoolError::ReplaceUnderpriced,
             PoolError::ProtocolFeeCapTooLow(_, _) => GethTxPoolError::Underpriced,
             PoolError::SpammerExceededCapacity(_, _) => GethTxPoolError::TxPoolOverflow,
             PoolError::DiscardedOnInsert(_) => GethTxPoolError::TxPoolOverflow,
             PoolError::TxExceedsGasLimit(_, _, _) => GethTxPoolError::GasLimit,
             PoolError::TxExceedsMaxInitCodeSize(_, _, _) => GethTxPoolError::OversizedData,
-        }
-    }
-}
-
-impl From<PoolError> for EthApiError {
-    fn from(err: PoolError) -> Self {
-        EthApiError::PoolError(GethTxPoolError::from(err))
+            PoolError::NonceTooLow(_, _, _) => GethTxPoolError::NonceTooLow,
+            PoolError::Provider(_, err) => return EthApiError::Internal(err),
+        };
+        EthApiError::PoolError(err)
     }
 }
 
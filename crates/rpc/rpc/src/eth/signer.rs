@@ -1,13 +1,18 @@
 //! An abstraction over ethereum signers.
 
-use jsonrpsee::core::RpcResult as Result;
-use reth_primitives::{Address, Signature, TransactionSigned};
-use reth_rpc_types::TypedTransactionRequest;
-use secp256k1::SecretKey;
+use crate::eth::error::{EthApiError, EthResult};
+use reth_primitives::{
+    keccak256, Address, Signature, Transaction, TransactionKind, TransactionSigned, TxEip1559,
+    TxEip2930, TxLegacy, DEV_SECRET_KEYS, H256, U256,
+};
+use reth_rpc_types::{
+    EIP1559TransactionRequest, EIP2930TransactionRequest, LegacyTransactionRequest,
+    TypedTransactionRequest,
+};
+use secp256k1::{Message, PublicKey, SecretKey, SECP256K1};
 use std::collections::HashMap;
 
 /// An Ethereum Signer used via RPC.
-#[async_trait::async_trait]
 pub(crate) trait EthSigner: Send + Sync {
     /// Returns the available accounts for this signer.
     fn accounts(&self) -> Vec<Address>;
@@ -17,15 +22,15 @@ pub(crate) trait EthSigner: Send + Sync {
         self.accounts().contains(addr)
     }
 
-    /// Returns the signature
-    async fn sign(&self, address: Address, message: &[u8]) -> Result<Signature>;
+    /// Returns the signature of the message, prefixed as specified by `eth_sign`.
+    fn sign(&self, address: Address, message: &[u8]) -> EthResult<Signature>;
 
     /// signs a transaction request using the given account in request
     fn sign_transaction(
         &self,
         request: TypedTransactionRequest,
         address: &Address,
-    ) -> Result<TransactionSigned>;
+    ) -> EthResult<TransactionSigned>;
 }
 
 /// Holds developer keys
@@ -34,7 +39,44 @@ pub(crate) struct DevSigner {
     accounts: HashMap<Address, SecretKey>,
 }
 
-#[async_trait::async_trait]
+// === impl DevSigner ===
+
+impl DevSigner {
+    /// Creates a new signer that manages the accounts of the given secret keys.
+    ///
+    /// The accounts are returned in the same order as the keys.
+    pub(crate) fn new(keys: impl IntoIterator<Item = SecretKey>) -> Self {
+        let mut addresses = Vec::new();
+        let mut accounts = HashMap::new();
+        for key in keys {
+            let address = secret_key_to_address(&key);
+            if accounts.insert(address, key).is_none() {
+                addresses.push(address);
+            }
+        }
+        Self { addresses, accounts }
+    }
+
+    /// Creates a new signer for the prefunded accounts of the [DEV](reth_primitives::DEV) chain.
+    pub(crate) fn dev_accounts() -> Self {
+        Self::new(DEV_SECRET_KEYS.iter().map(|key| {
+            SecretKey::from_slice(key.as_bytes()).expect("dev secret keys are valid; qed")
+        }))
+    }
+
+    /// Signs the hash with the secret key of the account.
+    fn sign_hash(&self, hash: H256, address: Address) -> EthResult<Signature> {
+        let secret = self.accounts.get(&address).ok_or(EthApiError::UnknownAccount)?;
+        let message = Message::from_slice(hash.as_bytes()).expect("hash is 32 bytes; qed");
+        let (rec_id, data) = SECP256K1.sign_ecdsa_recoverable(&message, secret).serialize_compact();
+        Ok(Signature {
+            r: U256::try_from_be_slice(&data[..32]).expect("r is 32 bytes; qed"),
+            s: U256::try_from_be_slice(&data[32..64]).expect("s is 32 bytes; qed"),
+            odd_y_parity: rec_id.to_i32() != 0,
+        })
+    }
+}
+
 impl EthSigner for DevSigner {
     fn accounts(&self) -> Vec<Address> {
         self.addresses.clone()
@@ -44,15 +86,174 @@ impl EthSigner for DevSigner {
         self.accounts.contains_key(addr)
     }
 
-    async fn sign(&self, _address: Address, _message: &[u8]) -> Result<Signature> {
-        todo!()
+    fn sign(&self, address: Address, message: &[u8]) -> EthResult<Signature> {
+        self.sign_hash(eth_message_hash(message), address)
     }
 
     fn sign_transaction(
         &self,
-        _request: TypedTransactionRequest,
-        _address: &Address,
-    ) -> Result<TransactionSigned> {
-        todo!()
+        request: TypedTransactionRequest,
+        address: &Address,
+    ) -> EthResult<TransactionSigned> {
+        let transaction = into_transaction(request)?;
+        let signature = self.sign_hash(transaction.signature_hash(), *address)?;
+        Ok(TransactionSigned::from_transaction_and_signature(transaction, signature))
+    }
+}
+
+/// Returns the hash of the message that is signed by `eth_sign`:
+/// `keccak256("\x19Ethereum Signed Message:\n" + len(message) + message)`
+pub(crate) fn eth_message_hash(message: &[u8]) -> H256 {
+    let mut prefixed = format!("\x19Ethereum Signed Message:\n{}", message.len()).into_bytes();
+    prefixed.extend_from_slice(message);
+    keccak256(prefixed)
+}
+
+/// Converts the transaction request into the unsigned [Transaction].
+fn into_transaction(request: TypedTransactionRequest) -> EthResult<Transaction> {
+    let transaction = match request {
+        TypedTransactionRequest::Legacy(LegacyTransactionRequest {
+            nonce,
+            gas_price,
+            gas_limit,
+            kind,
+            value,
+            input,
+            chain_id,
+        }) => Transaction::Legacy(TxLegacy {
+            chain_id,
+            nonce: to_u64(nonce)?,
+            gas_price: gas_price.to(),
+            gas_limit: to_u64(gas_limit)?,
+            to: into_kind(kind.as_call()),
+            value: to_u128(value)?,
+            input,
+        }),
+        TypedTransactionRequest::EIP2930(EIP2930TransactionRequest {
+            chain_id,
+            nonce,
+            gas_price,
+            gas_limit,
+            kind,
+            value,
+            input,
+            access_list,
+        }) => Transaction::Eip2930(TxEip2930 {
+            chain_id,
+            nonce: to_u64(nonce)?,
+            gas_price: gas_price.to(),
+            gas_limit: to_u64(gas_limit)?,
+            to: into_kind(kind.as_call()),
+            value: to_u128(value)?,
+            access_list,
+            input,
+        }),
+        TypedTransactionRequest::EIP1559(EIP1559TransactionRequest {
+            chain_id,
+            nonce,
+            max_priority_fee_per_gas,
+            max_fee_per_gas,
+            gas_limit,
+            kind,
+            value,
+            input,
+            access_list,
+        }) => Transaction::Eip1559(TxEip1559 {
+            chain_id,
+            nonce: to_u64(nonce)?,
+            gas_limit: to_u64(gas_limit)?,
+            max_fee_per_gas: max_fee_per_gas.to(),
+            max_priority_fee_per_gas: max_priority_fee_per_gas.to(),
+            to: into_kind(kind.as_call()),
+            value: to_u128(value)?,
+            access_list,
+            input,
+        }),
+    };
+    Ok(transaction)
+}
+
+/// Converts the `to` field of the request.
+fn into_kind(to: Option<&Address>) -> TransactionKind {
+    match to {
+        Some(to) => TransactionKind::Call(*to),
+        None => TransactionKind::Create,
+    }
+}
+
+fn to_u64(value: U256) -> EthResult<u64> {
+    u64::try_from(value).map_err(|_| EthApiError::InvalidTransactionRequest)
+}
+
+fn to_u128(value: U256) -> EthResult<u128> {
+    u128::try_from(value).map_err(|_| EthApiError::InvalidTransactionRequest)
+}
+
+/// Returns the address that corresponds to the given secret key.
+fn secret_key_to_address(key: &SecretKey) -> Address {
+    let public = PublicKey::from_secret_key(SECP256K1, key);
+    let hash = keccak256(&public.serialize_uncompressed()[1..]);
+    Address::from_slice(&hash[12..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{DEV, U128};
+    use reth_rpc_types::TransactionRequest;
+    use std::str::FromStr;
+
+    #[test]
+    fn dev_signer_accounts() {
+        let signer = DevSigner::dev_accounts();
+        assert_eq!(signer.accounts().len(), DEV_SECRET_KEYS.len());
+        assert!(signer.accounts().iter().all(|account| DEV.genesis.alloc.contains_key(account)));
+    }
+
+    #[test]
+    fn dev_signer_dedup_accounts() {
+        let key =
+            SecretKey::from_str("ac0974bec39a17e36ba4a6b4d238ff944bacb478cbed5efcae784d7bf4f2ff80")
+                .unwrap();
+        let signer = DevSigner::new([key, key]);
+        let expected: Address = "0xf39Fd6e51aad88F6F4ce6aB8827279cffFb92266".parse().unwrap();
+        assert_eq!(signer.accounts(), vec![expected]);
+        assert!(signer.is_signer_for(&expected));
+    }
+
+    #[test]
+    fn dev_signer_sign_message() {
+        let signer = DevSigner::dev_accounts();
+        let account = signer.accounts()[0];
+        let message = b"hello world";
+
+        let signature = signer.sign(account, message).unwrap();
+        assert_eq!(signature.recover_signer(eth_message_hash(message)), Some(account));
+
+        let unknown = Address::random();
+        assert!(matches!(signer.sign(unknown, message), Err(EthApiError::UnknownAccount)));
+    }
+
+    #[test]
+    fn dev_signer_sign_transaction() {
+        let signer = DevSigner::dev_accounts();
+        let account = signer.accounts()[1];
+        let request = TransactionRequest {
+            to: Some(Address::random()),
+            max_fee_per_gas: Some(U128::from(1_000_000_000u64)),
+            gas: Some(U256::from(21_000)),
+            value: Some(U256::from(1)),
+            nonce: Some(U256::from(1)),
+            ..Default::default()
+        };
+        let mut request = request.into_typed_request().unwrap();
+        if let TypedTransactionRequest::EIP1559(ref mut request) = request {
+            request.chain_id = DEV.chain.id();
+        }
+
+        let signed = signer.sign_transaction(request, &account).unwrap();
+        assert_eq!(signed.recover_signer(), Some(account));
+        assert_eq!(signed.chain_id(), Some(DEV.chain.id()));
+        assert_eq!(signed.nonce(), 1);
     }
 }
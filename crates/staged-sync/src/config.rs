@@ -5,8 +5,17 @@ use reth_downloaders::{
     headers::reverse_headers::ReverseHeadersDownloaderBuilder,
 };
 use reth_network::{config::rng_secret_key, NetworkConfigBuilder, PeersConfig};
+use reth_primitives::PruneModes;
+use reth_stages::{
+    stages::{
+        DEFAULT_MAX_CACHED_ACCOUNTS, DEFAULT_MAX_CACHED_BYTECODES, DEFAULT_MAX_CACHED_STORAGE_SLOTS,
+    },
+    CommitPolicy,
+};
+use reth_static_files::Compression;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::{path::PathBuf, time::Duration};
+use tracing::warn;
 
 /// Configuration for the reth node.
 #[derive(Debug, Clone, Default, Deserialize, PartialEq, Serialize)]
@@ -17,6 +26,11 @@ pub struct Config {
     pub stages: StageConfig,
     /// Configuration for the discovery service.
     pub peers: PeersConfig,
+    /// Configuration for pruning old history. Without it, the node keeps the full history.
+    pub prune: Option<PruneConfig>,
+    /// Configuration for moving ancient blocks to static files. Without it, all blocks are kept
+    /// in the database.
+    pub static_files: Option<StaticFilesConfig>,
 }
 
 impl Config {
@@ -26,11 +40,11 @@ impl Config {
         nat_resolution_method: reth_net_nat::NatResolver,
         peers_file: Option<PathBuf>,
     ) -> NetworkConfigBuilder {
-        let peer_config = self
-            .peers
-            .clone()
-            .with_basic_nodes_from_file(peers_file)
-            .unwrap_or_else(|_| self.peers.clone());
+        let peer_config =
+            self.peers.clone().with_persisted_peers_from_file(peers_file).unwrap_or_else(|err| {
+                warn!(target: "reth::config", ?err, "Failed to load the persisted peers");
+                self.peers.clone()
+            });
         let discv4 =
             Discv4Config::builder().external_ip_resolver(Some(nat_resolution_method)).clone();
         NetworkConfigBuilder::new(rng_secret_key()).peer_config(peer_config).discovery(discv4)
@@ -50,6 +64,73 @@ pub struct StageConfig {
     pub sender_recovery: SenderRecoveryConfig,
     /// Execution stage configuration.
     pub execution: ExecutionConfig,
+    /// When to commit the progress of the stages, applied to all stages.
+    #[serde(default)]
+    pub commit: CommitConfig,
+    /// The number of worker threads of the CPU-bound stages: sender recovery, account hashing and
+    /// storage hashing. Defaults to one thread per logical CPU.
+    #[serde(default)]
+    pub worker_threads: Option<usize>,
+}
+
+/// Configuration of when the pipeline commits the progress of a stage.
+///
+/// The progress is committed as soon as any of the limits is reached, and always once a stage is
+/// done. Without any limit, the progress is committed after every execution of a stage, which is
+/// bounded by the stage's own `commit_threshold`.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct CommitConfig {
+    /// The number of blocks a stage may progress before committing.
+    pub max_blocks: Option<u64>,
+    /// The estimated number of modified bytes in the database transaction before committing.
+    pub max_dirty_bytes: Option<u64>,
+    /// The time since the last commit before committing.
+    #[serde(with = "humantime_serde")]
+    pub max_duration: Option<Duration>,
+}
+
+impl From<CommitConfig> for CommitPolicy {
+    fn from(config: CommitConfig) -> Self {
+        CommitPolicy {
+            max_blocks: config.max_blocks,
+            max_dirty_bytes: config.max_dirty_bytes,
+            max_duration: config.max_duration,
+        }
+    }
+}
+
+/// Pruning configuration.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct PruneConfig {
+    /// The maximum number of transactions, or state transitions for the account and storage
+    /// history, to prune before committing progress to the database.
+    pub commit_threshold: u64,
+    /// The pruning settings of each part of the history.
+    pub parts: PruneModes,
+}
+
+impl Default for PruneConfig {
+    fn default() -> Self {
+        Self { commit_threshold: 10_000, parts: PruneModes::default() }
+    }
+}
+
+/// Static files configuration.
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, Serialize)]
+#[serde(default)]
+pub struct StaticFilesConfig {
+    /// The number of blocks in each static file.
+    pub blocks_per_file: u64,
+    /// The compression of new static files.
+    pub compression: Compression,
+}
+
+impl Default for StaticFilesConfig {
+    fn default() -> Self {
+        Self { blocks_per_file: 500_000, compression: Compression::Snappy }
+    }
 }
 
 /// Header stage configuration.
@@ -145,20 +226,37 @@ impl Default for SenderRecoveryConfig {
 
 /// Execution stage configuration.
 #[derive(Debug, Clone, Copy, Deserialize, PartialEq, Serialize)]
+#[serde(default)]
 pub struct ExecutionConfig {
     /// The maximum number of blocks to execution before committing progress to the database.
     pub commit_threshold: u64,
+    /// The maximum number of accounts in the state cache.
+    pub max_cached_accounts: u32,
+    /// The maximum number of storage slots in the state cache.
+    pub max_cached_storage_slots: u32,
+    /// The maximum number of bytecodes in the state cache.
+    pub max_cached_bytecodes: u32,
+    /// The number of threads prefetching the state of upcoming blocks, `0` disables prefetching.
+    pub prefetch_threads: usize,
 }
 
 impl Default for ExecutionConfig {
     fn default() -> Self {
-        Self { commit_threshold: 5_000 }
+        Self {
+            commit_threshold: 5_000,
+            max_cached_accounts: DEFAULT_MAX_CACHED_ACCOUNTS,
+            max_cached_storage_slots: DEFAULT_MAX_CACHED_STORAGE_SLOTS,
+            max_cached_bytecodes: DEFAULT_MAX_CACHED_BYTECODES,
+            prefetch_threads: 0,
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::Config;
+    use super::{Config, PruneConfig, StaticFilesConfig};
+    use reth_primitives::{PruneMode, PruneModes};
+    use reth_static_files::Compression;
 
     const EXTENSION: &str = "toml";
 
@@ -189,4 +287,42 @@ mod tests {
             assert_eq!(config, loaded_config);
         })
     }
+
+    #[test]
+    fn test_load_prune_config() {
+        with_tempdir("config-prune-test", |config_path| {
+            let config = Config {
+                prune: Some(PruneConfig {
+                    commit_threshold: 1_000,
+                    parts: PruneModes {
+                        receipts: Some(PruneMode::Distance(10_064)),
+                        account_history: Some(PruneMode::Before(1_000_000)),
+                        ..Default::default()
+                    },
+                }),
+                ..Default::default()
+            };
+            confy::store_path(config_path, &config).unwrap();
+
+            let loaded_config: Config = confy::load_path(config_path).unwrap();
+            assert_eq!(config, loaded_config);
+        })
+    }
+
+    #[test]
+    fn test_load_static_files_config() {
+        with_tempdir("config-static-files-test", |config_path| {
+            let config = Config {
+                static_files: Some(StaticFilesConfig {
+                    blocks_per_file: 100_000,
+                    compression: Compression::Uncompressed,
+                }),
+                ..Default::default()
+            };
+            confy::store_path(config_path, &config).unwrap();
+
+            let loaded_config: Config = confy::load_path(config_path).unwrap();
+            assert_eq!(config, loaded_config);
+        })
+    }
 }
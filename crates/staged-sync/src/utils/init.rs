@@ -5,7 +5,7 @@ use reth_db::{
     tables,
     transaction::{DbTx, DbTxMut},
 };
-use reth_primitives::{Account, ChainSpec, H256};
+use reth_primitives::{keccak256, Account, ChainSpec, H256};
 use std::{path::Path, sync::Arc};
 use tracing::debug;
 
@@ -58,16 +58,16 @@ pub fn init_genesis<DB: Database>(
     debug!("Writing genesis block.");
     let tx = db.tx_mut()?;
 
-    // Insert account state
+    // Insert account state, hashed as well so the state trie can be computed on top of it before
+    // the hashing stages ran.
     for (address, account) in &genesis.alloc {
-        tx.put::<tables::PlainAccountState>(
-            *address,
-            Account {
-                nonce: account.nonce.unwrap_or_default(),
-                balance: account.balance,
-                bytecode_hash: None,
-            },
-        )?;
+        let account = Account {
+            nonce: account.nonce.unwrap_or_default(),
+            balance: account.balance,
+            bytecode_hash: None,
+        };
+        tx.put::<tables::PlainAccountState>(*address, account)?;
+        tx.put::<tables::HashedAccount>(keccak256(address), account)?;
     }
 
     // Insert header
@@ -5,9 +5,12 @@ use criterion::{
 use pprof::criterion::{Output, PProfProfiler};
 use reth_db::mdbx::{Env, WriteMap};
 use reth_stages::{
-    stages::{MerkleStage, SenderRecoveryStage, TotalDifficultyStage, TransactionLookupStage},
+    stages::{
+        AccountHashingStage, MerkleStage, SenderRecoveryStage, StorageHashingStage,
+        TotalDifficultyStage, TransactionLookupStage,
+    },
     test_utils::TestTransaction,
-    ExecInput, Stage, StageId, UnwindInput,
+    ExecInput, Stage, StageId, UnwindInput, WorkerPool,
 };
 use std::path::PathBuf;
 
@@ -17,12 +20,15 @@ use setup::StageRange;
 criterion_group! {
     name = benches;
     config = Criterion::default().with_profiler(PProfProfiler::new(1000, Output::Flamegraph(None)));
-    targets = transaction_lookup, account_hashing, senders, total_difficulty, merkle
+    targets = transaction_lookup, account_hashing, storage_hashing, senders, total_difficulty, merkle
 }
 criterion_main!(benches);
 
 const DEFAULT_NUM_BLOCKS: u64 = 10_000;
 
+/// The numbers of worker threads the parallel stages are measured with.
+const WORKER_THREADS: [usize; 4] = [1, 2, 4, 8];
+
 fn account_hashing(c: &mut Criterion) {
     let mut group = c.benchmark_group("Stages");
 
@@ -32,14 +38,38 @@ fn account_hashing(c: &mut Criterion) {
     let num_blocks = 10_000;
     let (path, stage, execution_range) = setup::prepare_account_hashing(num_blocks);
 
-    measure_stage_with_path(
-        path,
-        &mut group,
-        setup::stage_unwind,
-        stage,
-        execution_range,
-        "AccountHashing".to_string(),
-    );
+    for threads in WORKER_THREADS {
+        let stage =
+            AccountHashingStage { pool: WorkerPool::new(threads).unwrap(), ..stage.clone() };
+
+        measure_stage_with_path(
+            path.clone(),
+            &mut group,
+            setup::stage_unwind,
+            stage,
+            execution_range,
+            format!("AccountHashing-threads-{threads}"),
+        );
+    }
+}
+
+fn storage_hashing(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Stages");
+    // don't need to run each stage for that many times
+    group.sample_size(10);
+
+    for threads in WORKER_THREADS {
+        let stage =
+            StorageHashingStage { pool: WorkerPool::new(threads).unwrap(), ..Default::default() };
+
+        measure_stage(
+            &mut group,
+            setup::stage_unwind,
+            stage,
+            0..DEFAULT_NUM_BLOCKS,
+            format!("StorageHashing-threads-{threads}"),
+        );
+    }
 }
 
 fn senders(c: &mut Criterion) {
@@ -47,9 +77,12 @@ fn senders(c: &mut Criterion) {
     // don't need to run each stage for that many times
     group.sample_size(10);
 
-    for batch in [1000usize, 10_000, 100_000, 250_000] {
-        let stage = SenderRecoveryStage { commit_threshold: DEFAULT_NUM_BLOCKS };
-        let label = format!("SendersRecovery-batch-{batch}");
+    for threads in WORKER_THREADS {
+        let stage = SenderRecoveryStage {
+            commit_threshold: DEFAULT_NUM_BLOCKS,
+            pool: WorkerPool::new(threads).unwrap(),
+        };
+        let label = format!("SendersRecovery-threads-{threads}");
 
         measure_stage(&mut group, setup::stage_unwind, stage, 0..DEFAULT_NUM_BLOCKS, label);
     }
@@ -92,7 +125,7 @@ fn merkle(c: &mut Criterion) {
     // don't need to run each stage for that many times
     group.sample_size(10);
 
-    let stage = MerkleStage::Both { clean_threshold: u64::MAX };
+    let stage = MerkleStage::Both { clean_threshold: u64::MAX, commit_threshold: u64::MAX };
     measure_stage(
         &mut group,
         setup::unwind_hashes,
@@ -101,7 +134,7 @@ fn merkle(c: &mut Criterion) {
         "Merkle-incremental".to_string(),
     );
 
-    let stage = MerkleStage::Both { clean_threshold: 0 };
+    let stage = MerkleStage::Both { clean_threshold: 0, commit_threshold: u64::MAX };
     measure_stage(
         &mut group,
         setup::unwind_hashes,
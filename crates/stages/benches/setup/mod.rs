@@ -9,7 +9,7 @@ use reth_interfaces::test_utils::generators::{
     random_block_range, random_contract_account_range, random_eoa_account_range,
     random_transition_range,
 };
-use reth_primitives::{Account, Address, SealedBlock, H256};
+use reth_primitives::{trie::StoredNibbles, Account, Address, SealedBlock, H256};
 use reth_provider::trie::DBTrieLoader;
 use reth_stages::{
     stages::{AccountHashingStage, StorageHashingStage},
@@ -69,8 +69,7 @@ pub(crate) fn unwind_hashes<S: Clone + Stage<Env<WriteMap>>>(
         StorageHashingStage::default().unwind(&mut db_tx, unwind).await.unwrap();
         AccountHashingStage::default().unwind(&mut db_tx, unwind).await.unwrap();
 
-        let target_root = db_tx.get_header(unwind.unwind_to).unwrap().state_root;
-        let _ = db_tx.delete::<tables::AccountsTrie>(target_root, None);
+        let _ = db_tx.delete::<tables::AccountsTrie>(StoredNibbles::default(), None);
 
         // Clear previous run
         stage.unwind(&mut db_tx, unwind).await.unwrap();
@@ -150,7 +149,7 @@ pub(crate) fn txs_testdata(num_blocks: u64) -> PathBuf {
         };
 
         tx.query(|tx| {
-            assert!(tx.get::<tables::AccountsTrie>(root)?.is_some());
+            assert_eq!(tx.get::<tables::AccountsTrie>(StoredNibbles::default())?, Some(root));
             Ok(())
         })
         .unwrap();
@@ -4,6 +4,7 @@ use reth_interfaces::{
 };
 use reth_primitives::BlockNumber;
 use reth_provider::TransactionError;
+use reth_static_files::StaticFileError;
 use thiserror::Error;
 use tokio::sync::mpsc::error::SendError;
 
@@ -44,6 +45,9 @@ pub enum StageError {
     /// The stage encountered an error related to the current database transaction.
     #[error("A database transaction error occurred: {0}")]
     Transaction(#[from] TransactionError),
+    /// The stage failed to read or write static files.
+    #[error("A static file error occurred: {0}")]
+    StaticFile(#[from] StaticFileError),
     /// Invalid download response. Applicable for stages which
     /// rely on external downloaders
     #[error("Invalid download response: {0}")]
@@ -69,6 +73,7 @@ impl StageError {
             StageError::Database(_) |
                 StageError::Download(_) |
                 StageError::DatabaseIntegrity(_) |
+                StageError::StaticFile(_) |
                 StageError::StageProgress(_) |
                 StageError::ExecutionError { .. } |
                 StageError::ChannelClosed |
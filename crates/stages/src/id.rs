@@ -1,6 +1,6 @@
 use crate::stages::{BODIES, HEADERS};
 use reth_db::{
-    tables::SyncStage,
+    tables::{SyncStage, SyncStageProgress},
     transaction::{DbTx, DbTxMut},
     Error as DbError,
 };
@@ -38,6 +38,26 @@ impl StageId {
     ) -> Result<(), DbError> {
         tx.put::<SyncStage>(self.0.as_bytes().to_vec(), block)
     }
+
+    /// Get the last committed intermediate checkpoint of this stage, if it was interrupted.
+    pub fn get_checkpoint<'db>(&self, tx: &impl DbTx<'db>) -> Result<Option<Vec<u8>>, DbError> {
+        tx.get::<SyncStageProgress>(self.0.as_bytes().to_vec())
+    }
+
+    /// Save an intermediate checkpoint of this stage, to resume from if it is interrupted.
+    pub fn save_checkpoint<'db>(
+        &self,
+        tx: &impl DbTxMut<'db>,
+        checkpoint: Vec<u8>,
+    ) -> Result<(), DbError> {
+        tx.put::<SyncStageProgress>(self.0.as_bytes().to_vec(), checkpoint)
+    }
+
+    /// Remove the intermediate checkpoint of this stage once its work is complete.
+    pub fn clear_checkpoint<'db>(&self, tx: &impl DbTxMut<'db>) -> Result<(), DbError> {
+        tx.delete::<SyncStageProgress>(self.0.as_bytes().to_vec(), None)?;
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -54,6 +54,7 @@
 mod error;
 mod id;
 mod pipeline;
+mod pool;
 mod stage;
 mod util;
 
@@ -72,6 +73,7 @@ pub mod sets;
 pub use error::*;
 pub use id::*;
 pub use pipeline::*;
+pub use pool::WorkerPool;
 pub use stage::*;
 
 // NOTE: Needed so the link in the module-level rustdoc works.
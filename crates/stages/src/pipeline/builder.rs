@@ -1,4 +1,4 @@
-use crate::{Pipeline, Stage, StageSet};
+use crate::{CommitPolicy, Pipeline, Stage, StageSet};
 use reth_db::database::Database;
 use reth_interfaces::sync::{NoopSyncStateUpdate, SyncStateUpdater};
 use reth_primitives::BlockNumber;
@@ -56,6 +56,14 @@ where
         self
     }
 
+    /// Set the policy that determines when the progress of a stage is committed.
+    ///
+    /// By default, the progress is committed after every execution of a stage.
+    pub fn with_commit_policy(mut self, policy: CommitPolicy) -> Self {
+        self.pipeline.commit_policy = policy;
+        self
+    }
+
     /// Set a [SyncStateUpdater].
     pub fn with_sync_state_updater(mut self, updater: U) -> Self {
         self.pipeline.sync_state_updater = Some(updater);
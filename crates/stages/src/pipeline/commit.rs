@@ -0,0 +1,139 @@
+use reth_db::Error as DbError;
+use std::time::{Duration, Instant};
+
+/// Determines when the pipeline commits the progress of a stage to the database.
+///
+/// The progress of a stage is always committed once the stage is done, as are intermediate
+/// checkpoints of a stage that did not progress any blocks. Otherwise, the pipeline keeps
+/// executing the stage in the same transaction until one of the configured limits is reached. If
+/// no limit is set, the progress is committed after every execution of the stage.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CommitPolicy {
+    /// Commit once the stage progressed this many blocks since the last commit.
+    pub max_blocks: Option<u64>,
+    /// Commit once the transaction is estimated to have modified this many bytes.
+    pub max_dirty_bytes: Option<u64>,
+    /// Commit once this much time passed since the last commit.
+    pub max_duration: Option<Duration>,
+}
+
+impl CommitPolicy {
+    /// Commit once the stage progressed this many blocks since the last commit.
+    pub fn with_max_blocks(mut self, max_blocks: u64) -> Self {
+        self.max_blocks = Some(max_blocks);
+        self
+    }
+
+    /// Commit once the transaction is estimated to have modified this many bytes.
+    pub fn with_max_dirty_bytes(mut self, max_dirty_bytes: u64) -> Self {
+        self.max_dirty_bytes = Some(max_dirty_bytes);
+        self
+    }
+
+    /// Commit once this much time passed since the last commit.
+    pub fn with_max_duration(mut self, max_duration: Duration) -> Self {
+        self.max_duration = Some(max_duration);
+        self
+    }
+
+    /// Returns true if no limit is set, so every execution of a stage is committed.
+    fn is_unlimited(&self) -> bool {
+        self.max_blocks.is_none() && self.max_dirty_bytes.is_none() && self.max_duration.is_none()
+    }
+}
+
+/// The work of a stage that was not committed yet.
+#[derive(Debug)]
+pub(crate) struct PendingCommit {
+    /// The number of blocks the stage progressed since the last commit.
+    blocks: u64,
+    /// Whether the stage saved an intermediate checkpoint since the last commit.
+    checkpointed: bool,
+    /// The time of the last commit.
+    since: Instant,
+}
+
+impl Default for PendingCommit {
+    fn default() -> Self {
+        Self { blocks: 0, checkpointed: false, since: Instant::now() }
+    }
+}
+
+impl PendingCommit {
+    /// Records an execution of the stage that progressed the given number of blocks.
+    ///
+    /// An execution that is not done without progressing any blocks saved an intermediate
+    /// checkpoint within the block range instead, e.g. the Merkle stage rebuilding the trie. It
+    /// is always committed, since no block limit would ever be reached otherwise.
+    pub(crate) fn record(&mut self, blocks: u64, done: bool) {
+        self.blocks += blocks;
+        self.checkpointed |= !done && blocks == 0;
+    }
+
+    /// Returns true if the pending work should be committed according to the policy.
+    ///
+    /// The number of dirty bytes is only queried if the policy limits it.
+    pub(crate) fn should_commit(
+        &self,
+        policy: &CommitPolicy,
+        dirty_bytes: impl FnOnce() -> Result<u64, DbError>,
+    ) -> Result<bool, DbError> {
+        if policy.is_unlimited() ||
+            self.checkpointed ||
+            policy.max_blocks.map_or(false, |max| self.blocks >= max) ||
+            policy.max_duration.map_or(false, |max| self.since.elapsed() >= max)
+        {
+            return Ok(true)
+        }
+
+        match policy.max_dirty_bytes {
+            Some(max) => Ok(dirty_bytes()? >= max),
+            None => Ok(false),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlimited_policy_always_commits() {
+        let pending = PendingCommit::default();
+        assert!(pending.should_commit(&CommitPolicy::default(), || unreachable!()).unwrap());
+    }
+
+    #[test]
+    fn commits_once_a_limit_is_reached() {
+        let policy = CommitPolicy::default().with_max_blocks(10).with_max_dirty_bytes(1024);
+
+        let mut pending = PendingCommit::default();
+        pending.record(5, false);
+        assert!(!pending.should_commit(&policy, || Ok(512)).unwrap());
+        assert!(pending.should_commit(&policy, || Ok(1024)).unwrap());
+
+        pending.record(5, false);
+        assert!(pending.should_commit(&policy, || unreachable!()).unwrap());
+
+        let policy = CommitPolicy::default().with_max_duration(Duration::ZERO);
+        assert!(PendingCommit::default().should_commit(&policy, || unreachable!()).unwrap());
+    }
+
+    #[test]
+    fn blocks_only_policy_commits_intermediate_checkpoints() {
+        let policy = CommitPolicy::default().with_max_blocks(10);
+
+        let mut pending = PendingCommit::default();
+        pending.record(5, false);
+        assert!(!pending.should_commit(&policy, || unreachable!()).unwrap());
+
+        // The stage did not progress any blocks, but saved an intermediate checkpoint.
+        pending.record(0, false);
+        assert!(pending.should_commit(&policy, || unreachable!()).unwrap());
+
+        // A stage that is done without progressing any blocks has nothing to checkpoint.
+        let mut pending = PendingCommit::default();
+        pending.record(0, true);
+        assert!(!pending.should_commit(&policy, || unreachable!()).unwrap());
+    }
+}
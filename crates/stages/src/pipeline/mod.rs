@@ -1,6 +1,6 @@
 use crate::{error::*, util::opt, ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput};
-use metrics::Gauge;
-use reth_db::database::Database;
+use metrics::{Gauge, Histogram};
+use reth_db::{database::Database, transaction::DbTxMut};
 use reth_interfaces::sync::{SyncState, SyncStateUpdater};
 use reth_metrics_derive::Metrics;
 use reth_primitives::BlockNumber;
@@ -10,16 +10,20 @@ use std::{
     fmt::{Debug, Formatter},
     ops::Deref,
     sync::Arc,
+    time::{Duration, Instant},
 };
 use tokio_stream::wrappers::UnboundedReceiverStream;
 use tracing::*;
 
 mod builder;
+mod commit;
 mod ctrl;
 mod event;
 mod set;
 
 pub use builder::*;
+pub use commit::CommitPolicy;
+use commit::PendingCommit;
 use ctrl::*;
 pub use event::*;
 pub use set::*;
@@ -74,9 +78,15 @@ pub use set::*;
 /// In case of a validation error (as determined by the consensus engine) in one of the stages, the
 /// pipeline will unwind the stages in reverse order of execution. It is also possible to
 /// request an unwind manually (see [Pipeline::unwind]).
+///
+/// # Commits
+///
+/// The progress of each stage is committed to the database according to the pipeline's
+/// [CommitPolicy]. If a stage fails, all of its progress since the last commit is discarded.
 pub struct Pipeline<DB: Database, U: SyncStateUpdater> {
     stages: Vec<BoxedStage<DB>>,
     max_block: Option<BlockNumber>,
+    commit_policy: CommitPolicy,
     listeners: PipelineEventListeners,
     sync_state_updater: Option<U>,
     progress: PipelineProgress,
@@ -88,6 +98,7 @@ impl<DB: Database, U: SyncStateUpdater> Default for Pipeline<DB, U> {
         Self {
             stages: Vec::new(),
             max_block: None,
+            commit_policy: CommitPolicy::default(),
             listeners: PipelineEventListeners::default(),
             sync_state_updater: None,
             progress: PipelineProgress::default(),
@@ -101,6 +112,7 @@ impl<DB: Database, U: SyncStateUpdater> Debug for Pipeline<DB, U> {
         f.debug_struct("Pipeline")
             .field("stages", &self.stages.iter().map(|stage| stage.id()).collect::<Vec<StageId>>())
             .field("max_block", &self.max_block)
+            .field("commit_policy", &self.commit_policy)
             .finish()
     }
 }
@@ -267,9 +279,13 @@ impl<DB: Database, U: SyncStateUpdater> Pipeline<DB, U> {
         let stage = &mut self.stages[stage_index];
         let stage_id = stage.id();
         let mut made_progress = false;
-        loop {
-            let mut tx = Transaction::new(db)?;
 
+        let mut tx = Transaction::new(db)?;
+        let mut pending = PendingCommit::default();
+        // The progress of the stage as of the last commit.
+        let mut committed_progress = stage_id.get_progress(tx.deref())?;
+
+        loop {
             let prev_progress = stage_id.get_progress(tx.deref())?;
 
             let stage_reached_max_block = prev_progress
@@ -281,6 +297,9 @@ impl<DB: Database, U: SyncStateUpdater> Pipeline<DB, U> {
                     stage = %stage_id,
                     "Stage reached maximum block, skipping."
                 );
+                if prev_progress != committed_progress {
+                    self.metrics.stage_commit(stage_id, commit(&mut tx)?);
+                }
                 self.listeners.notify(PipelineEvent::Skipped { stage_id });
 
                 // We reached the maximum block, so we skip the stage
@@ -308,8 +327,15 @@ impl<DB: Database, U: SyncStateUpdater> Pipeline<DB, U> {
 
                     self.listeners.notify(PipelineEvent::Ran { stage_id, result: out.clone() });
 
-                    // TODO: Make the commit interval configurable
-                    tx.commit()?;
+                    pending.record(
+                        stage_progress.saturating_sub(prev_progress.unwrap_or_default()),
+                        done,
+                    );
+                    if done || pending.should_commit(&self.commit_policy, || tx.dirty_bytes())? {
+                        self.metrics.stage_commit(stage_id, commit(&mut tx)?);
+                        committed_progress = Some(stage_progress);
+                        pending = PendingCommit::default();
+                    }
 
                     if done {
                         return Ok(if made_progress {
@@ -322,6 +348,11 @@ impl<DB: Database, U: SyncStateUpdater> Pipeline<DB, U> {
                 Err(err) => {
                     self.listeners.notify(PipelineEvent::Error { stage_id });
 
+                    // Discard the changes of the failed execution, along with any progress that
+                    // was not committed yet.
+                    tx.drop()?;
+                    pending = PendingCommit::default();
+
                     return if let StageError::Validation { block, error } = err {
                         warn!(
                             target: "sync::pipeline",
@@ -334,7 +365,7 @@ impl<DB: Database, U: SyncStateUpdater> Pipeline<DB, U> {
                         // we bail entirely, otherwise we restart the execution loop from the
                         // beginning.
                         Ok(ControlFlow::Unwind {
-                            target: prev_progress.unwrap_or_default(),
+                            target: committed_progress.unwrap_or_default(),
                             bad_block: Some(block),
                         })
                     } else if err.is_fatal() {
@@ -360,11 +391,20 @@ impl<DB: Database, U: SyncStateUpdater> Pipeline<DB, U> {
     }
 }
 
+/// Commits the transaction, returning the time the commit took.
+fn commit<DB: Database>(tx: &mut Transaction<'_, DB>) -> Result<Duration, PipelineError> {
+    let started = Instant::now();
+    tx.commit()?;
+    Ok(started.elapsed())
+}
+
 #[derive(Metrics)]
 #[metrics(scope = "sync")]
 struct StageMetrics {
     /// The block number of the last commit for a stage.
     checkpoint: Gauge,
+    /// The time it took to commit the progress of a stage.
+    commit_duration: Histogram,
 }
 
 #[derive(Default)]
@@ -373,12 +413,18 @@ struct Metrics {
 }
 
 impl Metrics {
-    fn stage_checkpoint(&mut self, stage_id: StageId, progress: u64) {
+    fn stage_metrics(&mut self, stage_id: StageId) -> &mut StageMetrics {
         self.checkpoints
             .entry(stage_id)
             .or_insert_with(|| StageMetrics::new_with_labels(&[("stage", stage_id.to_string())]))
-            .checkpoint
-            .set(progress as f64);
+    }
+
+    fn stage_checkpoint(&mut self, stage_id: StageId, progress: u64) {
+        self.stage_metrics(stage_id).checkpoint.set(progress as f64);
+    }
+
+    fn stage_commit(&mut self, stage_id: StageId, duration: Duration) {
+        self.stage_metrics(stage_id).commit_duration.record(duration);
     }
 }
 
@@ -667,6 +713,98 @@ mod tests {
         );
     }
 
+    /// Checks that progress is only committed according to the commit policy, and that
+    /// uncommitted progress is discarded when the stage fails.
+    #[tokio::test]
+    async fn pipeline_commit_policy() {
+        let db = test_utils::create_test_db::<mdbx::WriteMap>(EnvKind::RW);
+
+        let mut pipeline: Pipeline<_, NoopSyncStateUpdate> = Pipeline::builder()
+            .add_stage(
+                TestStage::new(StageId("A"))
+                    .add_exec(Ok(ExecOutput { stage_progress: 5, done: false }))
+                    .add_exec(Err(StageError::Recoverable(Box::new(std::fmt::Error))))
+                    .add_exec(Ok(ExecOutput { stage_progress: 5, done: false }))
+                    .add_exec(Ok(ExecOutput { stage_progress: 10, done: true })),
+            )
+            .with_commit_policy(CommitPolicy::default().with_max_blocks(10))
+            .with_max_block(10)
+            .build();
+        let events = pipeline.events();
+
+        // Run pipeline
+        tokio::spawn(async move {
+            pipeline.run(db).await.expect("Could not run pipeline");
+        });
+
+        assert_eq!(
+            events.collect::<Vec<PipelineEvent>>().await,
+            vec![
+                PipelineEvent::Running { stage_id: StageId("A"), stage_progress: None },
+                PipelineEvent::Ran {
+                    stage_id: StageId("A"),
+                    result: ExecOutput { stage_progress: 5, done: false },
+                },
+                PipelineEvent::Running { stage_id: StageId("A"), stage_progress: Some(5) },
+                PipelineEvent::Error { stage_id: StageId("A") },
+                // The progress to block 5 was not committed
+                PipelineEvent::Running { stage_id: StageId("A"), stage_progress: None },
+                PipelineEvent::Ran {
+                    stage_id: StageId("A"),
+                    result: ExecOutput { stage_progress: 5, done: false },
+                },
+                PipelineEvent::Running { stage_id: StageId("A"), stage_progress: Some(5) },
+                PipelineEvent::Ran {
+                    stage_id: StageId("A"),
+                    result: ExecOutput { stage_progress: 10, done: true },
+                },
+            ]
+        );
+    }
+
+    /// Checks that intermediate checkpoints of a stage that does not progress any blocks are
+    /// committed regardless of the commit policy.
+    #[tokio::test]
+    async fn pipeline_commits_intermediate_checkpoints() {
+        let db = test_utils::create_test_db::<mdbx::WriteMap>(EnvKind::RW);
+
+        let mut pipeline: Pipeline<_, NoopSyncStateUpdate> = Pipeline::builder()
+            .add_stage(
+                TestStage::new(StageId("A"))
+                    .add_exec(Ok(ExecOutput { stage_progress: 0, done: false }))
+                    .add_exec(Err(StageError::Recoverable(Box::new(std::fmt::Error))))
+                    .add_exec(Ok(ExecOutput { stage_progress: 10, done: true })),
+            )
+            .with_commit_policy(CommitPolicy::default().with_max_blocks(10))
+            .with_max_block(10)
+            .build();
+        let events = pipeline.events();
+
+        // Run pipeline
+        tokio::spawn(async move {
+            pipeline.run(db).await.expect("Could not run pipeline");
+        });
+
+        assert_eq!(
+            events.collect::<Vec<PipelineEvent>>().await,
+            vec![
+                PipelineEvent::Running { stage_id: StageId("A"), stage_progress: None },
+                PipelineEvent::Ran {
+                    stage_id: StageId("A"),
+                    result: ExecOutput { stage_progress: 0, done: false },
+                },
+                PipelineEvent::Running { stage_id: StageId("A"), stage_progress: Some(0) },
+                PipelineEvent::Error { stage_id: StageId("A") },
+                // The intermediate checkpoint was committed
+                PipelineEvent::Running { stage_id: StageId("A"), stage_progress: Some(0) },
+                PipelineEvent::Ran {
+                    stage_id: StageId("A"),
+                    result: ExecOutput { stage_progress: 10, done: true },
+                },
+            ]
+        );
+    }
+
     /// Checks that the pipeline re-runs stages on non-fatal errors and stops on fatal ones.
     #[tokio::test]
     async fn pipeline_error_handling() {
@@ -0,0 +1,73 @@
+use rayon::{prelude::*, ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+use std::sync::Arc;
+
+/// A pool of worker threads for the CPU-bound work of stages, like recovering transaction senders
+/// and hashing the state.
+///
+/// Stages read their input in chunks and fan the work out to the pool, but write the results back
+/// in key order, so the writes to the database stay sequential appends.
+///
+/// Clones of a pool share its threads. The default pool runs on the global rayon thread pool.
+#[derive(Clone, Debug, Default)]
+pub struct WorkerPool {
+    /// The dedicated thread pool, or `None` to use the global rayon thread pool.
+    pool: Option<Arc<ThreadPool>>,
+}
+
+impl WorkerPool {
+    /// Creates a dedicated pool with the given number of threads, or one thread per logical CPU if
+    /// it is `0`.
+    pub fn new(threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        let pool = ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .thread_name(|index| format!("reth-stages-worker-{index}"))
+            .build()?;
+        Ok(Self { pool: Some(Arc::new(pool)) })
+    }
+
+    /// Returns the number of threads of the pool.
+    pub fn threads(&self) -> usize {
+        self.pool
+            .as_ref()
+            .map_or_else(rayon::current_num_threads, |pool| pool.current_num_threads())
+    }
+
+    /// Spawns a job onto the pool, jobs are started in the order they are spawned.
+    pub(crate) fn spawn_fifo(&self, job: impl FnOnce() + Send + 'static) {
+        match &self.pool {
+            Some(pool) => pool.spawn_fifo(job),
+            None => rayon::spawn_fifo(job),
+        }
+    }
+
+    /// Maps the items on the pool and returns the results in the order of the items.
+    pub(crate) fn map<T, R, F>(&self, items: Vec<T>, f: F) -> Vec<R>
+    where
+        T: Send,
+        R: Send,
+        F: Fn(T) -> R + Send + Sync,
+    {
+        let map = || items.into_par_iter().map(f).collect();
+        match &self.pool {
+            Some(pool) => pool.install(map),
+            None => map(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn map_keeps_order() {
+        let items = (0..10_000u64).collect::<Vec<_>>();
+        for pool in [WorkerPool::default(), WorkerPool::new(3).unwrap()] {
+            assert_eq!(
+                pool.map(items.clone(), |item| item * 2),
+                items.iter().map(|item| item * 2).collect::<Vec<_>>()
+            );
+        }
+        assert_eq!(WorkerPool::new(3).unwrap().threads(), 3);
+    }
+}
@@ -4,6 +4,6 @@ pub use crate::{
     pipeline::{Pipeline, PipelineBuilder, PipelineEvent, StageSet, StageSetBuilder},
     sets::{
         DefaultStages, ExecutionStages, HashingStages, HistoryIndexingStages, OfflineStages,
-        OnlineStages,
+        OnlineStages, SnapSyncStages,
     },
 };
@@ -1,7 +1,8 @@
 //! Built-in [`StageSet`]s.
 //!
 //! The easiest set to use is [`DefaultStages`], which provides all stages required to run an
-//! instance of reth.
+//! instance of reth. [`SnapSyncStages`] additionally downloads the state at a recent block from
+//! peers instead of executing all blocks up to it.
 //!
 //! It is also possible to run parts of reth standalone given the required data is present in
 //! the environment, such as [`ExecutionStages`] or [`HashingStages`].
@@ -42,7 +43,8 @@ use crate::{
     stages::{
         AccountHashingStage, BodyStage, ExecutionStage, FinishStage, HeaderStage,
         IndexAccountHistoryStage, IndexStorageHistoryStage, MerkleStage, SenderRecoveryStage,
-        StorageHashingStage, TotalDifficultyStage, TransactionLookupStage,
+        SnapHealStage, SnapStateStage, StorageHashingStage, TotalDifficultyStage,
+        TransactionLookupStage,
     },
     StageSet, StageSetBuilder,
 };
@@ -52,6 +54,7 @@ use reth_interfaces::{
     p2p::{
         bodies::downloader::BodyDownloader,
         headers::{client::StatusUpdater, downloader::HeaderDownloader},
+        snap::client::SnapClient,
     },
 };
 use reth_provider::ExecutorFactory;
@@ -110,6 +113,71 @@ where
     }
 }
 
+/// A set containing all stages to run an instance of reth that snap syncs the state.
+///
+/// A combination of (in order)
+///
+/// - [`OnlineStages`]
+/// - [`SnapStateStage`]
+/// - [`SnapHealStage`]
+/// - [`OfflineStages`]
+/// - [`FinishStage`]
+///
+/// The snap stages download the state at a pivot block close to the tip and only run on an empty
+/// node, afterwards the execution stage continues from the pivot block.
+#[derive(Debug)]
+pub struct SnapSyncStages<H, B, C, S, EF> {
+    /// Configuration for the online stages
+    online: OnlineStages<H, B>,
+    /// The client used to download the state from peers
+    snap_client: C,
+    /// Executor factory needs for execution stage
+    executor_factory: EF,
+    /// Configuration for the [`FinishStage`] stage.
+    status_updater: S,
+}
+
+impl<H, B, C, S, EF> SnapSyncStages<H, B, C, S, EF> {
+    /// Create a new set of snap sync stages with default values.
+    pub fn new(
+        consensus: Arc<dyn Consensus>,
+        header_downloader: H,
+        body_downloader: B,
+        snap_client: C,
+        status_updater: S,
+        executor_factory: EF,
+    ) -> Self
+    where
+        EF: ExecutorFactory,
+    {
+        Self {
+            online: OnlineStages::new(consensus, header_downloader, body_downloader),
+            snap_client,
+            executor_factory,
+            status_updater,
+        }
+    }
+}
+
+impl<DB, H, B, C, S, EF> StageSet<DB> for SnapSyncStages<H, B, C, S, EF>
+where
+    DB: Database,
+    H: HeaderDownloader + 'static,
+    B: BodyDownloader + 'static,
+    C: SnapClient + Clone + 'static,
+    S: StatusUpdater + 'static,
+    EF: ExecutorFactory,
+{
+    fn builder(self) -> StageSetBuilder<DB> {
+        self.online
+            .builder()
+            .add_stage(SnapStateStage::new(self.snap_client.clone()))
+            .add_stage(SnapHealStage::new(self.snap_client))
+            .add_set(OfflineStages::new(self.executor_factory))
+            .add_stage(FinishStage::new(self.status_updater))
+    }
+}
+
 /// A set containing all stages that require network access by default.
 ///
 /// These stages *can* be run without network access if the specified downloaders are
@@ -1,7 +1,9 @@
 use crate::{
-    exec_or_return, ExecAction, ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput,
-    UnwindOutput,
+    exec_or_return,
+    stages::{is_snap_synced, MERKLE_EXECUTION},
+    ExecAction, ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput, UnwindOutput,
 };
+use metrics::{Counter, Gauge};
 use reth_db::{
     cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO},
     database::Database,
@@ -9,14 +11,45 @@ use reth_db::{
     tables,
     transaction::{DbTx, DbTxMut},
 };
-use reth_interfaces::provider::ProviderError;
-use reth_primitives::{Address, Block, U256};
-use reth_provider::{BlockExecutor, ExecutorFactory, LatestStateProviderRef, Transaction};
+use reth_interfaces::{executor::Error as ExecutorError, provider::ProviderError};
+use reth_metrics_derive::Metrics;
+use reth_primitives::{
+    proofs::calculate_receipt_root_with_state_roots, Address, Block, Bloom, Hardfork, PrunePart,
+    Receipt, TransactionKind, H256, U256,
+};
+use reth_provider::{
+    account_bytecode_hash,
+    execution_result::ExecutionResult,
+    trie::{DBTrieLoader, HashedPostState},
+    update_bytecode_references, AccountProvider, BlockExecutor, CachedStateProvider,
+    ExecutorFactory, HashedStateProviderRef, LatestStateProviderRef, PostState, StateCache,
+    StateProvider, Transaction,
+};
+use std::{
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
 use tracing::*;
 
 /// The [`StageId`] of the execution stage.
 pub const EXECUTION: StageId = StageId("Execution");
 
+/// The default maximum number of accounts in the [StateCache] of the execution stage.
+pub const DEFAULT_MAX_CACHED_ACCOUNTS: u32 = 500_000;
+
+/// The default maximum number of storage slots in the [StateCache] of the execution stage.
+pub const DEFAULT_MAX_CACHED_STORAGE_SLOTS: u32 = 1_000_000;
+
+/// The default maximum number of bytecodes in the [StateCache] of the execution stage.
+pub const DEFAULT_MAX_CACHED_BYTECODES: u32 = 10_000;
+
+/// The number of blocks the state prefetcher runs ahead of the executed block at most.
+const PREFETCH_DISTANCE: usize = 64;
+
+/// The maximum number of pre-Byzantium blocks executed at once while their receipts are verified,
+/// which bounds the changes the intermediate state roots are computed with.
+const VERIFIED_RECEIPTS_COMMIT_THRESHOLD: u64 = 100;
+
 /// The execution stage executes all transactions and
 /// update history indexes.
 ///
@@ -33,13 +66,41 @@ pub const EXECUTION: StageId = StageId("Execution");
 /// - [tables::Bytecodes]
 /// - [tables::PlainStorageState]
 ///
+/// After a snap sync the plain state is incomplete, so the state is read through
+/// [HashedStateProviderRef] from [tables::HashedAccount] and [tables::HashedStorage] instead, and
+/// the changes are written to the hashed state as well. While a snap sync is in progress, the
+/// stage does not execute any blocks, the snap sync sets its progress to the pivot block once the
+/// downloaded state matches it.
+///
+/// State reads go through a [StateCache] that is kept across executions of the stage, and is
+/// cleared on unwind or when the stage progress does not match the block the cache was last
+/// updated for. With [ExecutionStage::with_prefetch_threads], background threads read the state
+/// touched by the senders, recipients and access lists of upcoming blocks into the cache ahead of
+/// execution.
+///
+/// The changes of all executed blocks are merged into a [PostState], which is written once per
+/// execution in key order.
+///
+/// Receipts of pre-Byzantium blocks contain the state root after their transaction, and are only
+/// verified with [ExecutionStage::with_pre_byzantium_receipt_verification]. The state roots are
+/// computed from the hashed state and the state trie, see
+/// [DBTrieLoader::state_root_with_changes], which are only updated by the stages after this one.
+/// The stage then executes at most 100 blocks at once, and waits for the
+/// [MerkleStage][crate::stages::MerkleStage] to catch up before it continues.
+///
 /// Tables updated after state finishes execution:
 /// - [tables::PlainAccountState]
 /// - [tables::PlainStorageState]
 /// - [tables::Bytecodes]
+/// - [tables::BytecodeRefCounts]
+/// - [tables::UnreferencedBytecodes]
 /// - [tables::AccountChangeSet]
 /// - [tables::StorageChangeSet]
 ///
+/// Bytecodes that are no longer used by any account are only recorded in
+/// [tables::UnreferencedBytecodes], the [PruneStage][crate::stages::PruneStage] deletes them once
+/// the account history of their block is pruned.
+///
 /// For unwinds we are accessing:
 /// - [tables::BlockBodies] get tx index to know what needs to be unwinded
 /// - [tables::AccountHistory] to remove change set and apply old values to
@@ -52,18 +113,55 @@ pub struct ExecutionStage<EF: ExecutorFactory> {
     pub executor_factory: EF,
     /// Commit threshold
     pub commit_threshold: u64,
+    /// The cache of the latest state, kept across executions.
+    pub state_cache: Arc<StateCache>,
+    /// The number of threads prefetching the state of upcoming blocks, `0` disables prefetching.
+    pub prefetch_threads: usize,
+    /// Whether the receipts of pre-Byzantium blocks are verified.
+    pub verify_pre_byzantium_receipts: bool,
+    /// Metrics of the state cache and the execution throughput.
+    metrics: ExecutionStageMetrics,
 }
 
 impl<EF: ExecutorFactory> ExecutionStage<EF> {
     /// Create new execution stage with specified config.
     pub fn new(executor_factory: EF, commit_threshold: u64) -> Self {
-        Self { executor_factory, commit_threshold }
+        Self {
+            executor_factory,
+            commit_threshold,
+            state_cache: Arc::new(StateCache::new(
+                DEFAULT_MAX_CACHED_ACCOUNTS,
+                DEFAULT_MAX_CACHED_STORAGE_SLOTS,
+                DEFAULT_MAX_CACHED_BYTECODES,
+            )),
+            prefetch_threads: 0,
+            verify_pre_byzantium_receipts: false,
+            metrics: ExecutionStageMetrics::default(),
+        }
     }
 
     /// Create execution stage with executor factory and default commit threshold set to 10_000
     /// blocks
     pub fn new_default_threshold(executor_factory: EF) -> Self {
-        Self { executor_factory, commit_threshold: 10_000 }
+        Self::new(executor_factory, 10_000)
+    }
+
+    /// Set the cache of the latest state.
+    pub fn with_state_cache(mut self, state_cache: Arc<StateCache>) -> Self {
+        self.state_cache = state_cache;
+        self
+    }
+
+    /// Set the number of threads prefetching the state of upcoming blocks.
+    pub fn with_prefetch_threads(mut self, prefetch_threads: usize) -> Self {
+        self.prefetch_threads = prefetch_threads;
+        self
+    }
+
+    /// Set whether the receipts of pre-Byzantium blocks are verified.
+    pub fn with_pre_byzantium_receipt_verification(mut self, verify: bool) -> Self {
+        self.verify_pre_byzantium_receipts = verify;
+        self
     }
 
     /// Execute the stage.
@@ -72,9 +170,40 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
         tx: &mut Transaction<'_, DB>,
         input: ExecInput,
     ) -> Result<ExecOutput, StageError> {
-        let ((start_block, end_block), capped) =
-            exec_or_return!(input, self.commit_threshold, "sync::stages::execution");
         let last_block = input.stage_progress.unwrap_or_default();
+        let verify_receipts = self.verify_pre_byzantium_receipts &&
+            !self
+                .executor_factory
+                .chain_spec()
+                .fork(Hardfork::Byzantium)
+                .active_at_block(last_block + 1);
+        let commit_threshold = if verify_receipts {
+            self.commit_threshold.min(VERIFIED_RECEIPTS_COMMIT_THRESHOLD)
+        } else {
+            self.commit_threshold
+        };
+        let ((start_block, end_block), capped) =
+            exec_or_return!(input, commit_threshold, "sync::stages::execution");
+        let snap_synced = is_snap_synced(&**tx)?;
+        if snap_synced && last_block == 0 {
+            // The downloaded state is incomplete until the snap sync finished.
+            info!(target: "sync::stages::execution", "Snap sync in progress, skipping execution");
+            return Ok(ExecOutput { stage_progress: last_block, done: true })
+        }
+
+        // The intermediate state roots are computed on top of the hashed state and the state
+        // trie, which must hold the state of the last executed block.
+        if verify_receipts && MERKLE_EXECUTION.get_progress(&**tx)?.unwrap_or_default() < last_block
+        {
+            info!(target: "sync::stages::execution", "Waiting for the state trie to verify pre-Byzantium receipts");
+            return Ok(ExecOutput { stage_progress: last_block, done: true })
+        }
+
+        // The cache only holds the latest state if it was updated by the last committed execution.
+        if self.state_cache.block() != input.stage_progress {
+            debug!(target: "sync::stages::execution", cached = ?self.state_cache.block(), stage_progress = ?input.stage_progress, "Clearing state cache");
+            self.state_cache.clear();
+        }
 
         // Get header with canonical hashes.
         let mut headers_cursor = tx.cursor_read::<tables::Headers>()?;
@@ -90,6 +219,7 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
         let mut tx_cursor = tx.cursor_read::<tables::Transactions>()?;
         // Skip sender recovery and load signer from database.
         let mut tx_sender = tx.cursor_read::<tables::TxSenders>()?;
+        let senders_pruned_to = tx.get::<tables::PruneCheckpoints>(PrunePart::SenderRecovery)?;
         // Get block headers and bodies
         let block_batch = headers_cursor
             .walk_range(start_block..=end_block)?
@@ -107,15 +237,10 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        // Create state provider with cached state
-
-        let mut executor = self.executor_factory.with_sp(LatestStateProviderRef::new(&**tx));
-
-        // Fetch transactions, execute them and generate results
-        let mut changesets = Vec::with_capacity(block_batch.len());
+        // Fetch transactions and signers of all blocks, so they can be prefetched.
+        let mut blocks = Vec::with_capacity(block_batch.len());
         for (header, td, body, ommers, withdrawals) in block_batch.into_iter() {
             let block_number = header.number;
-            tracing::trace!(target: "sync::stages::execution", ?block_number, "Execute block.");
 
             // iterate over all transactions
             let mut tx_walker = tx_cursor.walk(Some(body.start_tx_id))?;
@@ -132,6 +257,16 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
             }
 
             // take signers
+            if let Some(pruned_to) =
+                senders_pruned_to.filter(|pruned_to| block_number <= *pruned_to)
+            {
+                return Err(ProviderError::HistoryPruned {
+                    part: PrunePart::SenderRecovery,
+                    block_number: Some(block_number),
+                    available_from: pruned_to + 1,
+                }
+                .into())
+            }
             let mut tx_sender_walker = tx_sender.walk(Some(body.start_tx_id))?;
             let mut signers = Vec::with_capacity(body.tx_count as usize);
             for index in body.tx_id_range() {
@@ -144,28 +279,333 @@ impl<EF: ExecutorFactory> ExecutionStage<EF> {
                 signers.push(tx);
             }
 
-            trace!(target: "sync::stages::execution", number = block_number, txs = transactions.len(), "Executing block");
+            blocks.push((Block { header, body: transactions, ommers, withdrawals }, td, signers));
+        }
+
+        let (hits, misses) = (self.state_cache.hits(), self.state_cache.misses());
+        let started_at = Instant::now();
+
+        // The prefetchers read through their own transactions, which only see the committed
+        // state. Their reads can only be cached if no earlier execution is still uncommitted.
+        let prefetch_into_cache = self.prefetch_threads > 0 &&
+            EXECUTION.get_progress(&tx.inner().tx()?)? == input.stage_progress;
+        let executing = ExecutingBlock::default();
+        let post_state = PostState::new(last_block, tx.get_block_transition(last_block)?);
+        let post_state = std::thread::scope(|scope| {
+            let workers = self.prefetch_threads;
+            for worker in 0..workers {
+                let (db, blocks, executing) = (tx.inner(), &blocks, &executing);
+                let cache = prefetch_into_cache.then_some(&*self.state_cache);
+                scope.spawn(move || {
+                    if let Err(error) =
+                        prefetch_state(db, cache, blocks, worker, workers, executing, snap_synced)
+                    {
+                        debug!(target: "sync::stages::execution", worker, ?error, "State prefetching failed");
+                    }
+                });
+            }
+
+            let receipts = verify_receipts
+                .then(|| PreByzantiumReceipts { tx: &**tx, changes: HashedPostState::default() });
+            let result = if snap_synced {
+                self.execute_blocks(
+                    HashedStateProviderRef::new(&**tx),
+                    &blocks,
+                    post_state,
+                    &executing,
+                    receipts,
+                )
+            } else {
+                self.execute_blocks(
+                    LatestStateProviderRef::new(&**tx),
+                    &blocks,
+                    post_state,
+                    &executing,
+                    receipts,
+                )
+            };
+            executing.finish();
+            result
+        });
+        let post_state = match post_state {
+            Ok(post_state) => post_state,
+            Err(error) => {
+                // The cache may hold state read from the failed batch.
+                self.state_cache.clear();
+                return Err(error)
+            }
+        };
+
+        let gas_used = blocks.iter().map(|(block, ..)| block.header.gas_used).sum::<u64>();
+        self.record_metrics(gas_used, started_at.elapsed(), hits, misses);
+
+        // apply the changes to the cache before they are moved into the database
+        self.state_cache.apply_post_state(&post_state);
+
+        // put the changes of all blocks to the database at once
+        info!(target: "sync::stages::execution", transition_id = post_state.transition_id(), blocks = blocks.len(), "Writing post state");
+        post_state.write_to_db(&**tx)?;
+        if snap_synced {
+            post_state.write_hashed_state(&**tx)?;
+        }
+
+        // The following stages update the state trie before more pre-Byzantium blocks are verified.
+        let done = !capped || verify_receipts;
+        info!(target: "sync::stages::execution", stage_progress = end_block, done, "Sync iteration finished");
+        Ok(ExecOutput { stage_progress: end_block, done })
+    }
+
+    /// Executes the blocks on top of the latest state of the given provider, reading the state
+    /// through the state cache, and adds their changes to the post state.
+    ///
+    /// The receipts of pre-Byzantium blocks are verified if `receipts` is set.
+    fn execute_blocks<'tx, SP: StateProvider, TX: DbTx<'tx>>(
+        &self,
+        provider: SP,
+        blocks: &[(Block, U256, Vec<Address>)],
+        mut post_state: PostState,
+        executing: &ExecutingBlock,
+        mut receipts: Option<PreByzantiumReceipts<'_, TX>>,
+    ) -> Result<PostState, StageError> {
+        let mut executor =
+            self.executor_factory.with_sp(CachedStateProvider::new(provider, &self.state_cache));
+
+        let chain_spec = self.executor_factory.chain_spec();
+        for (index, (block, td, signers)) in blocks.iter().enumerate() {
+            executing.set(index);
+            let block_number = block.header.number;
+            trace!(target: "sync::stages::execution", number = block_number, txs = block.body.len(), "Executing block");
 
             // Configure the executor to use the current state.
             let changeset = executor
-                .execute_and_verify_receipt(
-                    &Block { header, body: transactions, ommers, withdrawals },
-                    td,
-                    Some(signers),
-                )
+                .execute_and_verify_receipt(block, *td, Some(signers.clone()))
                 .map_err(|error| StageError::ExecutionError { block: block_number, error })?;
-            changesets.push(changeset);
+            let has_state_clear_eip =
+                chain_spec.fork(Hardfork::SpuriousDragon).active_at_block(block_number);
+
+            if chain_spec.fork(Hardfork::Byzantium).active_at_block(block_number) {
+                receipts = None;
+            }
+            if let Some(receipts) = receipts.as_mut() {
+                receipts
+                    .verify(block, &changeset, has_state_clear_eip)
+                    .map_err(|error| StageError::ExecutionError { block: block_number, error })?;
+            }
+
+            post_state.add_block(changeset, has_state_clear_eip);
         }
+        Ok(post_state)
+    }
 
-        // put execution results to database
-        tx.insert_execution_result(changesets, self.executor_factory.chain_spec(), last_block)?;
+    /// Records the state cache hits and misses since the given totals and the execution
+    /// throughput.
+    fn record_metrics(&self, gas_used: u64, elapsed: Duration, hits: u64, misses: u64) {
+        let hits = self.state_cache.hits() - hits;
+        let misses = self.state_cache.misses() - misses;
+        self.metrics.state_cache_hits.increment(hits);
+        self.metrics.state_cache_misses.increment(misses);
+        if hits + misses > 0 {
+            self.metrics.state_cache_hit_rate.set(hits as f64 / (hits + misses) as f64);
+        }
 
-        let done = !capped;
-        info!(target: "sync::stages::execution", stage_progress = end_block, done, "Sync iteration finished");
-        Ok(ExecOutput { stage_progress: end_block, done })
+        self.metrics.gas_processed.increment(gas_used);
+        let gas_per_second = gas_used as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        self.metrics.gas_per_second.set(gas_per_second);
+        debug!(target: "sync::stages::execution", gas_used, ?elapsed, gas_per_second, hits, misses, "Executed blocks");
     }
 }
 
+/// Verifies the receipts of pre-Byzantium blocks, which contain the state root after their
+/// transaction instead of a status code, see [EIP-658](https://eips.ethereum.org/EIPS/eip-658).
+struct PreByzantiumReceipts<'a, TX> {
+    /// The transaction with the hashed state and the state trie before the executed blocks.
+    tx: &'a TX,
+    /// The changes of the blocks executed so far.
+    changes: HashedPostState,
+}
+
+impl<'a, 'tx, TX: DbTx<'tx>> PreByzantiumReceipts<'a, TX> {
+    /// Computes the state roots after the transactions of the executed block and verifies its
+    /// receipts with them.
+    fn verify(
+        &mut self,
+        block: &Block,
+        result: &ExecutionResult,
+        has_state_clear_eip: bool,
+    ) -> Result<(), ExecutorError> {
+        self.changes.apply_changeset(&result.pre_block_changesets, has_state_clear_eip);
+        let mut state_roots = Vec::with_capacity(result.tx_changesets.len());
+        for tx_changeset in &result.tx_changesets {
+            self.changes.apply_changeset(&tx_changeset.changeset, has_state_clear_eip);
+            let state_root = DBTrieLoader::default()
+                .state_root_with_changes(self.tx, &self.changes)
+                .map_err(|err| ExecutorError::IntermediateStateRoot(err.to_string()))?;
+            state_roots.push(state_root);
+        }
+        self.changes.apply_account_changes(&result.block_changesets, has_state_clear_eip);
+
+        let receipts = result.tx_changesets.iter().map(|changeset| &changeset.receipt);
+        verify_receipt_with_state_roots(
+            block.header.receipts_root,
+            block.header.logs_bloom,
+            receipts,
+            &state_roots,
+        )
+    }
+}
+
+/// Verifies pre-Byzantium receipts, which commit to the given intermediate state roots.
+fn verify_receipt_with_state_roots<'a>(
+    expected_receipts_root: H256,
+    expected_logs_bloom: Bloom,
+    receipts: impl Iterator<Item = &'a Receipt> + Clone,
+    state_roots: &[H256],
+) -> Result<(), ExecutorError> {
+    let receipts_root =
+        calculate_receipt_root_with_state_roots(receipts.clone().zip(state_roots.iter().copied()));
+    if receipts_root != expected_receipts_root {
+        return Err(ExecutorError::ReceiptRootDiff {
+            got: receipts_root,
+            expected: expected_receipts_root,
+        })
+    }
+
+    let logs_bloom = receipts.fold(Bloom::zero(), |bloom, receipt| bloom | receipt.bloom);
+    if logs_bloom != expected_logs_bloom {
+        return Err(ExecutorError::BloomLogDiff {
+            expected: Box::new(expected_logs_bloom),
+            got: Box::new(logs_bloom),
+        })
+    }
+    Ok(())
+}
+
+/// The index of the block of the batch that is executed, shared with the state prefetchers.
+struct ExecutingBlock {
+    /// The index of the executed block, `None` once the execution finished.
+    index: Mutex<Option<usize>>,
+    /// Notifies the prefetchers of changes of the index.
+    changed: Condvar,
+}
+
+impl ExecutingBlock {
+    /// Marks the block with the given index as being executed.
+    fn set(&self, index: usize) {
+        *self.index.lock().expect("not poisoned") = Some(index);
+        self.changed.notify_all();
+    }
+
+    /// Marks the execution as finished, which stops the prefetchers.
+    fn finish(&self) {
+        *self.index.lock().expect("not poisoned") = None;
+        self.changed.notify_all();
+    }
+
+    /// Waits until the block with the given index is at most [PREFETCH_DISTANCE] blocks ahead of
+    /// the executed block.
+    ///
+    /// Returns false if the block is already executed or the execution finished.
+    fn wait_for(&self, index: usize) -> bool {
+        let executing = self
+            .changed
+            .wait_while(self.index.lock().expect("not poisoned"), |executing| {
+                executing.map_or(false, |executing| index > executing + PREFETCH_DISTANCE)
+            })
+            .expect("not poisoned");
+        executing.map_or(false, |executing| index > executing)
+    }
+}
+
+impl Default for ExecutingBlock {
+    fn default() -> Self {
+        Self { index: Mutex::new(Some(0)), changed: Condvar::new() }
+    }
+}
+
+/// Reads the state touched by the senders, recipients and access lists of the transactions of the
+/// upcoming blocks, so it is cached by the time the blocks are executed.
+///
+/// Every worker handles every `workers`-th block and stays at most [PREFETCH_DISTANCE] blocks ahead
+/// of the executed block. The reads use separate read-only transactions which only see the
+/// committed state. They are inserted into the given cache, which must only be passed if the
+/// committed state is the state the executed blocks start from. Without a cache, the reads only
+/// warm up the database pages.
+fn prefetch_state<DB: Database>(
+    db: &DB,
+    cache: Option<&StateCache>,
+    blocks: &[(Block, U256, Vec<Address>)],
+    worker: usize,
+    workers: usize,
+    executing: &ExecutingBlock,
+    hashed: bool,
+) -> reth_interfaces::Result<()> {
+    for (index, (block, _, signers)) in blocks.iter().enumerate().skip(worker).step_by(workers) {
+        if !executing.wait_for(index) {
+            continue
+        }
+
+        let tx = db.tx()?;
+        match (cache, hashed) {
+            (Some(cache), true) => prefetch_block_state(
+                CachedStateProvider::prefetching(HashedStateProviderRef::new(&tx), cache),
+                block,
+                signers,
+            )?,
+            (Some(cache), false) => prefetch_block_state(
+                CachedStateProvider::prefetching(LatestStateProviderRef::new(&tx), cache),
+                block,
+                signers,
+            )?,
+            (None, true) => prefetch_block_state(HashedStateProviderRef::new(&tx), block, signers)?,
+            (None, false) => {
+                prefetch_block_state(LatestStateProviderRef::new(&tx), block, signers)?
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Reads the state touched by the transactions of the block from the given provider.
+fn prefetch_block_state(
+    provider: impl StateProvider,
+    block: &Block,
+    signers: &[Address],
+) -> reth_interfaces::Result<()> {
+    for (transaction, signer) in block.body.iter().zip(signers) {
+        provider.basic_account(*signer)?;
+        if let TransactionKind::Call(to) = transaction.kind() {
+            if let Some(hash) =
+                provider.basic_account(*to)?.as_ref().and_then(account_bytecode_hash)
+            {
+                provider.bytecode_by_hash(hash)?;
+            }
+        }
+        for item in transaction.access_list().into_iter().flat_map(|list| &list.0) {
+            provider.basic_account(item.address)?;
+            for key in &item.storage_keys {
+                provider.storage(item.address, *key)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Metrics of the execution stage.
+#[derive(Metrics)]
+#[metrics(scope = "sync.execution")]
+struct ExecutionStageMetrics {
+    /// The number of state reads served by the state cache.
+    state_cache_hits: Counter,
+    /// The number of state reads that missed the state cache.
+    state_cache_misses: Counter,
+    /// The share of state reads served by the state cache in the last batch of blocks.
+    state_cache_hit_rate: Gauge,
+    /// The total amount of gas used by the executed blocks.
+    gas_processed: Counter,
+    /// The gas used by the last batch of blocks per second of execution.
+    gas_per_second: Gauge,
+}
+
 #[async_trait::async_trait]
 impl<EF: ExecutorFactory, DB: Database> Stage<DB> for ExecutionStage<EF> {
     /// Return the id of the stage
@@ -204,6 +644,9 @@ impl<EF: ExecutorFactory, DB: Database> Stage<DB> for ExecutionStage<EF> {
     ) -> Result<UnwindOutput, StageError> {
         info!(target: "sync::stages::execution", to_block = input.unwind_to, "Unwinding");
 
+        // The cached state is not reverted.
+        self.state_cache.clear();
+
         // Acquire changeset cursors
         let mut account_changeset = tx.cursor_dup_write::<tables::AccountChangeSet>()?;
         let mut storage_changeset = tx.cursor_dup_write::<tables::StorageChangeSet>()?;
@@ -230,6 +673,13 @@ impl<EF: ExecutorFactory, DB: Database> Stage<DB> for ExecutionStage<EF> {
 
         // revert all changes to PlainState
         for (_, changeset) in account_changeset_batch.into_iter().rev() {
+            let current = tx.get::<tables::PlainAccountState>(changeset.address)?;
+            update_bytecode_references(
+                &**tx,
+                current.as_ref(),
+                changeset.info.as_ref(),
+                input.unwind_to,
+            )?;
             if let Some(account_info) = changeset.info {
                 tx.put::<tables::PlainAccountState>(changeset.address, account_info)?;
             } else {
@@ -286,17 +736,21 @@ impl<EF: ExecutorFactory, DB: Database> Stage<DB> for ExecutionStage<EF> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::test_utils::{TestTransaction, PREV_STAGE_ID};
+    use crate::{
+        stages::SNAP_STATE,
+        test_utils::{TestTransaction, PREV_STAGE_ID},
+    };
+    use assert_matches::assert_matches;
     use reth_db::{
-        mdbx::{test_utils::create_test_db, EnvKind, WriteMap},
+        mdbx::{test_utils::create_test_db, Env, EnvKind, WriteMap},
         models::AccountBeforeTx,
     };
     use reth_executor::Factory;
     use reth_primitives::{
-        hex_literal::hex, keccak256, Account, Bytecode, ChainSpecBuilder, SealedBlock,
+        hex_literal::hex, keccak256, Account, Bytecode, ChainSpecBuilder, Header, SealedBlock,
         StorageEntry, H160, H256, U256,
     };
-    use reth_provider::insert_canonical_block;
+    use reth_provider::{execution_result::AccountInfoChangeSet, insert_canonical_block};
     use reth_rlp::Decodable;
     use std::{
         ops::{Deref, DerefMut},
@@ -309,6 +763,62 @@ mod tests {
         ExecutionStage::new(factory, 100)
     }
 
+    #[test]
+    fn prefetch_into_state_cache() {
+        let db = create_test_db::<WriteMap>(EnvKind::RW);
+        let signer = Address::random();
+        let account = Account { nonce: 1, ..Default::default() };
+        let tx = db.tx_mut().unwrap();
+        tx.put::<tables::PlainAccountState>(signer, account).unwrap();
+        tx.commit().unwrap();
+
+        let block = Block { body: vec![Default::default()], ..Default::default() };
+        let blocks = vec![
+            (block.clone(), U256::ZERO, vec![Address::random()]),
+            (block, U256::ZERO, vec![signer]),
+        ];
+        let cache = StateCache::new(10, 10, 10);
+        let executing = ExecutingBlock::default();
+
+        // The block that is executed is skipped, the next one is read into the cache.
+        prefetch_state(db.as_ref(), Some(&cache), &blocks, 0, 1, &executing, false).unwrap();
+        assert_eq!((cache.hits(), cache.misses()), (0, 0));
+
+        let tx = db.tx().unwrap();
+        let provider = CachedStateProvider::new(LatestStateProviderRef::new(&tx), &cache);
+        assert_eq!(provider.basic_account(blocks[0].2[0]), Ok(None));
+        assert_eq!(provider.basic_account(signer), Ok(Some(account)));
+        assert_eq!((cache.hits(), cache.misses()), (1, 1));
+    }
+
+    #[test]
+    fn prefetchers_wait_for_execution() {
+        let executing = ExecutingBlock::default();
+        std::thread::scope(|scope| {
+            let ahead = scope.spawn(|| executing.wait_for(PREFETCH_DISTANCE + 1));
+            let behind = scope.spawn(|| executing.wait_for(PREFETCH_DISTANCE + 3));
+            executing.set(1);
+            assert!(ahead.join().unwrap());
+            executing.finish();
+            assert!(!behind.join().unwrap());
+        });
+        // Executed blocks are not prefetched.
+        executing.set(5);
+        assert!(!executing.wait_for(5));
+        assert!(executing.wait_for(6));
+    }
+
+    #[tokio::test]
+    async fn waits_for_snap_sync() {
+        let test_tx = TestTransaction::default();
+        let mut tx = test_tx.inner();
+        SNAP_STATE.save_checkpoint(tx.deref(), vec![]).unwrap();
+
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, 10)), stage_progress: None };
+        let output = stage().execute(&mut tx, input).await;
+        assert_matches!(output, Ok(ExecOutput { stage_progress: 0, done: true }));
+    }
+
     #[tokio::test]
     async fn sanity_execution_of_block() {
         // TODO cleanup the setup after https://github.com/paradigmxyz/reth/issues/332
@@ -354,6 +864,7 @@ mod tests {
         let output = execution_stage.execute(&mut tx, input).await.unwrap();
         tx.commit().unwrap();
         assert_eq!(output, ExecOutput { stage_progress: 1, done: true });
+        assert_eq!(execution_stage.state_cache.block(), Some(1));
         let tx = tx.deref_mut();
         // check post state
         let account1 = H160(hex!("1000000000000000000000000000000000000000"));
@@ -397,6 +908,112 @@ mod tests {
         );
     }
 
+    /// The block of `sanity_execution_of_block`, which is a frontier block on mainnet.
+    fn frontier_block() -> Block {
+        let mut block_rlp = hex!("f90262f901f9a075c371ba45999d87f4542326910a11af515897aebce5265d3f6acd1f1161f82fa01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347942adc25665018aa1fe0e6bc666dac8fc2697ff9baa098f2dcd87c8ae4083e7017a05456c14eea4b1db2032126e27b3b1563d57d7cc0a08151d548273f6683169524b66ca9fe338b9ce42bc3540046c828fd939ae23bcba03f4e5c2ec5b2170b711d97ee755c160457bb58d8daa338e835ec02ae6860bbabb901000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000083020000018502540be40082a8798203e800a00000000000000000000000000000000000000000000000000000000000000000880000000000000000f863f861800a8405f5e10094100000000000000000000000000000000000000080801ba07e09e26678ed4fac08a249ebe8ed680bf9051a5e14ad223e4b2b9d26e0208f37a05f6e3f188e3e6eab7d7d3b6568f5eac7d687b08d307d3154ccd8c87b4630509bc0").as_slice();
+        Block::decode(&mut block_rlp).unwrap()
+    }
+
+    /// Inserts the genesis block, the modified [frontier_block] and the pre state of
+    /// `sanity_execution_of_block`, which is hashed as well.
+    fn insert_frontier_block(db: &Env<WriteMap>, modify_header: impl FnOnce(&mut Header)) {
+        let mut tx = Transaction::new(db).unwrap();
+        let mut genesis_rlp = hex!("f901faf901f5a00000000000000000000000000000000000000000000000000000000000000000a01dcc4de8dec75d7aab85b567b6ccd41ad312451b948a7413f0a142fd40d49347942adc25665018aa1fe0e6bc666dac8fc2697ff9baa045571b40ae66ca7480791bbb2887286e4e4c4b1b298b191c889d6959023a32eda056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421a056e81f171bcc55a6ff8345e692c0f86e5b48e01b996cadc001622fb5e363b421b901000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000083020000808502540be400808000a00000000000000000000000000000000000000000000000000000000000000000880000000000000000c0c0").as_slice();
+        let genesis = SealedBlock::decode(&mut genesis_rlp).unwrap();
+        let Block { mut header, body, .. } = frontier_block();
+        modify_header(&mut header);
+        let block =
+            SealedBlock { header: header.seal_slow(), body, ommers: vec![], withdrawals: None };
+        insert_canonical_block(tx.deref_mut(), &genesis, true).unwrap();
+        insert_canonical_block(tx.deref_mut(), &block, true).unwrap();
+
+        let code = hex!("5a465a905090036002900360015500");
+        let code_hash = keccak256(code);
+        let balance = U256::from(0x3635c9adc5dea00000u128);
+        for (address, account) in [
+            (
+                H160(hex!("1000000000000000000000000000000000000000")),
+                Account { nonce: 0, balance: U256::ZERO, bytecode_hash: Some(code_hash) },
+            ),
+            (
+                H160(hex!("a94f5374fce5edbc8e2a8697c15331677e6ebf0b")),
+                Account { nonce: 0, balance, bytecode_hash: None },
+            ),
+        ] {
+            tx.put::<tables::PlainAccountState>(address, account).unwrap();
+            tx.put::<tables::HashedAccount>(keccak256(address), account).unwrap();
+        }
+        tx.put::<tables::Bytecodes>(code_hash, Bytecode::new_raw(code.to_vec().into())).unwrap();
+        tx.commit().unwrap();
+    }
+
+    #[test]
+    fn verifies_pre_byzantium_receipts() {
+        let chain_spec = Arc::new(ChainSpecBuilder::mainnet().build());
+        let execute = |db: &Env<WriteMap>, input| {
+            let mut tx = Transaction::new(db).unwrap();
+            ExecutionStage::new(Factory::new(chain_spec.clone()), 100)
+                .with_pre_byzantium_receipt_verification(true)
+                .execute_inner(&mut tx, input)
+        };
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, 1)), stage_progress: None };
+
+        // The gas costs of the frontier block differ from those it was generated with.
+        let db = create_test_db::<WriteMap>(EnvKind::RW);
+        insert_frontier_block(&db, |_| {});
+        let (tx_changesets, gas_used) = {
+            let tx = db.tx().unwrap();
+            Factory::new(chain_spec.clone())
+                .with_sp(LatestStateProviderRef::new(&tx))
+                .execute_transactions(&frontier_block(), U256::ZERO, None)
+                .unwrap()
+        };
+
+        // Derive the expected receipts root from the state after the transaction, calculated by
+        // rebuilding the state trie.
+        let state_root = {
+            let tx = Transaction::new(db.as_ref()).unwrap();
+            for (address, changeset) in &tx_changesets[0].changeset {
+                if let AccountInfoChangeSet::Changed { new, .. } |
+                AccountInfoChangeSet::Created { new } = changeset.account
+                {
+                    tx.insert_account_for_hashing([(*address, Some(new))]).unwrap();
+                }
+                let storage = changeset
+                    .storage
+                    .iter()
+                    .map(|(key, (_, value))| (H256(key.to_be_bytes()), *value));
+                tx.insert_storage_for_hashing([(*address, storage)]).unwrap();
+            }
+            DBTrieLoader::default().calculate_root(&tx).unwrap()
+        };
+        let receipts_root = calculate_receipt_root_with_state_roots(
+            tx_changesets.iter().map(|changeset| &changeset.receipt).zip([state_root]),
+        );
+
+        // The receipts root of the block was generated for a status code receipt.
+        let db = create_test_db::<WriteMap>(EnvKind::RW);
+        insert_frontier_block(&db, |header| header.gas_used = gas_used);
+        assert_matches!(
+            execute(&db, input),
+            Err(StageError::ExecutionError {
+                block: 1,
+                error: ExecutorError::ReceiptRootDiff { got, expected }
+            }) if got == receipts_root && expected == frontier_block().receipts_root
+        );
+
+        let db = create_test_db::<WriteMap>(EnvKind::RW);
+        insert_frontier_block(&db, |header| {
+            header.gas_used = gas_used;
+            header.receipts_root = receipts_root;
+        });
+        assert_matches!(execute(&db, input), Ok(ExecOutput { stage_progress: 1, done: true }));
+
+        // The state trie does not hold the state of block 1 yet.
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, 2)), stage_progress: Some(1) };
+        assert_matches!(execute(&db, input), Ok(ExecOutput { stage_progress: 1, done: true }));
+    }
+
     #[tokio::test]
     async fn sanity_execute_unwind() {
         // TODO cleanup the setup after https://github.com/paradigmxyz/reth/issues/332
@@ -503,6 +1120,7 @@ mod tests {
         db_tx.put::<tables::PlainAccountState>(caller_address, caller_info).unwrap();
         db_tx.put::<tables::PlainAccountState>(destroyed_address, destroyed_info).unwrap();
         db_tx.put::<tables::Bytecodes>(code_hash, Bytecode::new_raw(code.to_vec().into())).unwrap();
+        db_tx.put::<tables::BytecodeRefCounts>(code_hash, 1).unwrap();
         // set storage to check when account gets destroyed.
         db_tx
             .put::<tables::PlainStorageState>(
@@ -564,6 +1182,12 @@ mod tests {
         );
         assert!(plain_storage.is_empty());
 
+        // the bytecode of the destroyed account is kept until the account history of block 1 is
+        // pruned
+        assert!(test_tx.table::<tables::BytecodeRefCounts>().unwrap().is_empty());
+        assert_eq!(test_tx.table::<tables::UnreferencedBytecodes>().unwrap(), vec![(1, code_hash)]);
+        assert_eq!(test_tx.table::<tables::Bytecodes>().unwrap().len(), 1);
+
         let account_changesets = test_tx.table::<tables::AccountChangeSet>().unwrap();
         let storage_changesets = test_tx.table::<tables::StorageChangeSet>().unwrap();
 
@@ -1,4 +1,7 @@
-use crate::{ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput, UnwindOutput};
+use crate::{
+    stages::is_snap_synced, ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput,
+    UnwindOutput, WorkerPool,
+};
 use reth_db::{
     cursor::{DbCursorRO, DbCursorRW},
     database::Database,
@@ -15,6 +18,11 @@ pub const ACCOUNT_HASHING: StageId = StageId("AccountHashing");
 
 /// Account hashing stage hashes plain account.
 /// This is preparation before generating intermediate hashes and calculating Merkle tree root.
+///
+/// When hashing all accounts, every batch of accounts is hashed in parallel on the [WorkerPool].
+///
+/// After a snap sync the plain state is incomplete, and the stage is skipped since the execution
+/// stage writes the hashed accounts itself.
 #[derive(Clone, Debug)]
 pub struct AccountHashingStage {
     /// The threshold (in number of state transitions) for switching between incremental
@@ -22,11 +30,13 @@ pub struct AccountHashingStage {
     pub clean_threshold: u64,
     /// The maximum number of blocks to process before committing.
     pub commit_threshold: u64,
+    /// The pool that hashes the accounts.
+    pub pool: WorkerPool,
 }
 
 impl Default for AccountHashingStage {
     fn default() -> Self {
-        Self { clean_threshold: 500_000, commit_threshold: 100_000 }
+        Self { clean_threshold: 500_000, commit_threshold: 100_000, pool: WorkerPool::default() }
     }
 }
 
@@ -128,6 +138,12 @@ impl<DB: Database> Stage<DB> for AccountHashingStage {
         let stage_progress = input.stage_progress.unwrap_or_default();
         let previous_stage_progress = input.previous_stage_progress();
 
+        // After a snap sync, the execution stage keeps the hashed state up to date itself.
+        if is_snap_synced(&**tx)? {
+            info!(target: "sync::stages::hashing_account", "Stage skipped after snap sync");
+            return Ok(ExecOutput { stage_progress: previous_stage_progress, done: true })
+        }
+
         // read account changeset, merge it into one changeset and calculate account hashes.
         let from_transition = tx.get_block_transition(stage_progress)?;
         let to_transition = tx.get_block_transition(previous_stage_progress)?;
@@ -146,11 +162,15 @@ impl<DB: Database> Stage<DB> for AccountHashingStage {
                 let next_key = {
                     let mut accounts = tx.cursor_read::<tables::PlainAccountState>()?;
 
-                    let hashed_batch = accounts
+                    let batch = accounts
                         .walk(first_key)?
                         .take(self.commit_threshold as usize)
-                        .map(|res| res.map(|(address, account)| (keccak256(address), account)))
-                        .collect::<Result<BTreeMap<_, _>, _>>()?;
+                        .collect::<Result<Vec<_>, _>>()?;
+                    let hashed_batch = self
+                        .pool
+                        .map(batch, |(address, account)| (keccak256(address), account))
+                        .into_iter()
+                        .collect::<BTreeMap<_, _>>();
 
                     let mut hashed_account_cursor = tx.cursor_write::<tables::HashedAccount>()?;
 
@@ -366,6 +386,7 @@ mod tests {
                 Self::S {
                     commit_threshold: self.commit_threshold,
                     clean_threshold: self.clean_threshold,
+                    pool: WorkerPool::new(2).unwrap(),
                 }
             }
         }
@@ -1,4 +1,7 @@
-use crate::{ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput, UnwindOutput};
+use crate::{
+    stages::is_snap_synced, ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput,
+    UnwindOutput, WorkerPool,
+};
 use num_traits::Zero;
 use reth_db::{
     cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO},
@@ -17,18 +20,26 @@ pub const STORAGE_HASHING: StageId = StageId("StorageHashing");
 
 /// Storage hashing stage hashes plain storage.
 /// This is preparation before generating intermediate hashes and calculating Merkle tree root.
-#[derive(Debug)]
+///
+/// When hashing all storage slots, every batch of slots is hashed in parallel on the
+/// [WorkerPool].
+///
+/// After a snap sync the plain state is incomplete, and the stage is skipped since the execution
+/// stage writes the hashed storage itself.
+#[derive(Clone, Debug)]
 pub struct StorageHashingStage {
     /// The threshold (in number of state transitions) for switching between incremental
     /// hashing and full storage hashing.
     pub clean_threshold: u64,
     /// The maximum number of slots to process before committing.
     pub commit_threshold: u64,
+    /// The pool that hashes the storage slots.
+    pub pool: WorkerPool,
 }
 
 impl Default for StorageHashingStage {
     fn default() -> Self {
-        Self { clean_threshold: 500_000, commit_threshold: 100_000 }
+        Self { clean_threshold: 500_000, commit_threshold: 100_000, pool: WorkerPool::default() }
     }
 }
 
@@ -48,6 +59,12 @@ impl<DB: Database> Stage<DB> for StorageHashingStage {
         let stage_progress = input.stage_progress.unwrap_or_default();
         let previous_stage_progress = input.previous_stage_progress();
 
+        // After a snap sync, the execution stage keeps the hashed state up to date itself.
+        if is_snap_synced(&**tx)? {
+            info!(target: "sync::stages::hashing_storage", "Stage skipped after snap sync");
+            return Ok(ExecOutput { stage_progress: previous_stage_progress, done: true })
+        }
+
         // read storage changeset, merge it into one changeset and calculate storage hashes.
         let from_transition = tx.get_block_transition(stage_progress)?;
         let to_transition = tx.get_block_transition(previous_stage_progress)?;
@@ -62,37 +79,21 @@ impl<DB: Database> Stage<DB> for StorageHashingStage {
 
             let mut current_key = None;
             let mut current_subkey = None;
-            let mut keccak_address = None;
 
             loop {
-                let mut hashed_batch = BTreeMap::new();
+                let mut batch = Vec::new();
                 let mut remaining = self.commit_threshold as usize;
                 {
                     let mut storage = tx.cursor_dup_read::<tables::PlainStorageState>()?;
                     while !remaining.is_zero() {
-                        hashed_batch.extend(
+                        batch.extend(
                             storage
                                 .walk_dup(current_key, current_subkey)?
                                 .take(remaining)
-                                .map(|res| {
-                                    res.map(|(address, slot)| {
-                                        // Address caching for the first iteration when current_key
-                                        // is None
-                                        let keccak_address =
-                                            if let Some(keccak_address) = keccak_address {
-                                                keccak_address
-                                            } else {
-                                                keccak256(address)
-                                            };
-
-                                        // TODO cache map keccak256(slot.key) ?
-                                        ((keccak_address, keccak256(slot.key)), slot.value)
-                                    })
-                                })
-                                .collect::<Result<BTreeMap<_, _>, _>>()?,
+                                .collect::<Result<Vec<_>, _>>()?,
                         );
 
-                        remaining = self.commit_threshold as usize - hashed_batch.len();
+                        remaining = self.commit_threshold as usize - batch.len();
 
                         if let Some((address, slot)) = storage.next_dup()? {
                             // There's still some remaining elements on this key, so we need to save
@@ -106,17 +107,23 @@ impl<DB: Database> Stage<DB> for StorageHashingStage {
                             current_key = storage.next_no_dup()?.map(|(key, _)| key);
                             current_subkey = None;
 
-                            // Cache keccak256(address) for the next key if it exists
-                            if let Some(address) = current_key {
-                                keccak_address = Some(keccak256(address));
-                            } else {
-                                // We have reached the end of table
+                            // We have reached the end of table
+                            if current_key.is_none() {
                                 break
                             }
                         }
                     }
                 }
 
+                // hash the slots on the worker pool and sort them by hashed address and key
+                let hashed_batch = self
+                    .pool
+                    .map(batch, |(address, slot)| {
+                        ((keccak256(address), keccak256(slot.key)), slot.value)
+                    })
+                    .into_iter()
+                    .collect::<BTreeMap<_, _>>();
+
                 // iterate and put presorted hashed slots
                 hashed_batch.into_iter().try_for_each(|((addr, key), value)| {
                     tx.put::<tables::HashedStorage>(addr, StorageEntry { key, value })
@@ -279,6 +286,7 @@ mod tests {
             Self::S {
                 commit_threshold: self.commit_threshold,
                 clean_threshold: self.clean_threshold,
+                pool: WorkerPool::new(2).unwrap(),
             }
         }
     }
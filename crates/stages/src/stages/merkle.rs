@@ -1,8 +1,16 @@
-use crate::{ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput, UnwindOutput};
+use crate::{
+    stages::snap_state::unwinds_below_pivot, ExecInput, ExecOutput, Stage, StageError, StageId,
+    UnwindInput, UnwindOutput,
+};
+use reth_codecs::Compact;
 use reth_db::{database::Database, tables, transaction::DbTx};
 use reth_interfaces::consensus;
-use reth_provider::{trie::DBTrieLoader, Transaction};
-use std::fmt::Debug;
+use reth_primitives::{trie::StoredNibbles, MerkleCheckpoint};
+use reth_provider::{
+    trie::{DBTrieLoader, TrieProgress},
+    Transaction,
+};
+use std::{fmt::Debug, ops::Deref};
 use tracing::*;
 
 /// The [`StageId`] of the merkle hashing execution stage.
@@ -35,6 +43,10 @@ pub const MERKLE_BOTH: StageId = StageId("MerkleBoth");
 /// - [`AccountHashingStage`][crate::stages::AccountHashingStage]
 /// - [`StorageHashingStage`][crate::stages::StorageHashingStage]
 /// - [`MerkleStage::Execution`]
+///
+/// When the trie is rebuilt from scratch, the stage commits after every `commit_threshold`
+/// accounts and saves a [`MerkleCheckpoint`], so an interrupted rebuild continues where it left off
+/// instead of starting over.
 #[derive(Debug, Clone)]
 pub enum MerkleStage {
     /// The execution portion of the merkle stage.
@@ -42,6 +54,8 @@ pub enum MerkleStage {
         /// The threshold for switching from incremental trie building
         /// of changes to whole rebuild. Num of transitions.
         clean_threshold: u64,
+        /// The number of accounts to add to the trie before committing, when rebuilding it.
+        commit_threshold: u64,
     },
     /// The unwind portion of the merkle stage.
     Unwind,
@@ -49,19 +63,48 @@ pub enum MerkleStage {
     /// Able to execute and unwind. Used for tests
     #[cfg(any(test, feature = "test-utils"))]
     #[allow(missing_docs)]
-    Both { clean_threshold: u64 },
+    Both { clean_threshold: u64, commit_threshold: u64 },
 }
 
 impl MerkleStage {
     /// Stage default for the Execution variant.
     pub fn default_execution() -> Self {
-        Self::Execution { clean_threshold: 5_000 }
+        Self::Execution { clean_threshold: 5_000, commit_threshold: 100_000 }
     }
 
     /// Stage default for the Unwind variant.
     pub fn default_unwind() -> Self {
         Self::Unwind
     }
+
+    /// The ID of the stage whose checkpoint holds the progress of an interrupted rebuild.
+    fn checkpoint_id(&self) -> StageId {
+        match self {
+            #[cfg(any(test, feature = "test-utils"))]
+            MerkleStage::Both { .. } => MERKLE_BOTH,
+            _ => MERKLE_EXECUTION,
+        }
+    }
+
+    /// Gets the checkpoint of an interrupted rebuild of the trie, if any.
+    pub fn get_execution_checkpoint<DB: Database>(
+        &self,
+        tx: &Transaction<'_, DB>,
+    ) -> Result<Option<MerkleCheckpoint>, StageError> {
+        let checkpoint = self.checkpoint_id().get_checkpoint(tx.deref())?;
+        Ok(checkpoint.map(|buf| MerkleCheckpoint::from_compact(&buf, buf.len()).0))
+    }
+
+    /// Saves the checkpoint of a rebuild of the trie.
+    pub fn save_execution_checkpoint<DB: Database>(
+        &self,
+        tx: &Transaction<'_, DB>,
+        checkpoint: MerkleCheckpoint,
+    ) -> Result<(), StageError> {
+        let mut buf = vec![];
+        checkpoint.to_compact(&mut buf);
+        Ok(self.checkpoint_id().save_checkpoint(tx.deref(), buf)?)
+    }
 }
 
 #[async_trait::async_trait]
@@ -82,7 +125,7 @@ impl<DB: Database> Stage<DB> for MerkleStage {
         tx: &mut Transaction<'_, DB>,
         input: ExecInput,
     ) -> Result<ExecOutput, StageError> {
-        let threshold = match self {
+        let (threshold, commit_threshold) = match self {
             MerkleStage::Unwind => {
                 info!(target: "sync::stages::merkle::unwind", "Stage is always skipped");
                 return Ok(ExecOutput {
@@ -90,9 +133,13 @@ impl<DB: Database> Stage<DB> for MerkleStage {
                     done: true,
                 })
             }
-            MerkleStage::Execution { clean_threshold } => *clean_threshold,
+            MerkleStage::Execution { clean_threshold, commit_threshold } => {
+                (*clean_threshold, *commit_threshold)
+            }
             #[cfg(any(test, feature = "test-utils"))]
-            MerkleStage::Both { clean_threshold } => *clean_threshold,
+            MerkleStage::Both { clean_threshold, commit_threshold } => {
+                (*clean_threshold, *commit_threshold)
+            }
         };
 
         let stage_progress = input.stage_progress.unwrap_or_default();
@@ -103,23 +150,58 @@ impl<DB: Database> Stage<DB> for MerkleStage {
 
         let block_root = tx.get_header(previous_stage_progress)?.state_root;
 
+        // A checkpoint is only valid for the hashed state it was saved with.
+        let checkpoint = self
+            .get_execution_checkpoint(tx)?
+            .filter(|checkpoint| checkpoint.target_block == previous_stage_progress);
+
         let trie_root = if from_transition == to_transition {
             block_root
-        } else if to_transition - from_transition > threshold || stage_progress == 0 {
-            debug!(target: "sync::stages::merkle::exec", current = ?stage_progress, target = ?previous_stage_progress, "Rebuilding trie");
+        } else if to_transition - from_transition > threshold ||
+            stage_progress == 0 ||
+            checkpoint.is_some()
+        {
             // if there are more blocks than threshold it is faster to rebuild the trie
+            let resume = match checkpoint {
+                Some(MerkleCheckpoint { last_account_key, state, .. }) => {
+                    debug!(target: "sync::stages::merkle::exec", current = ?stage_progress, target = ?previous_stage_progress, ?last_account_key, "Continuing trie rebuild");
+                    Some((last_account_key, state))
+                }
+                None => {
+                    debug!(target: "sync::stages::merkle::exec", current = ?stage_progress, target = ?previous_stage_progress, "Rebuilding trie");
+                    None
+                }
+            };
+
             let loader = DBTrieLoader::default();
-            loader.calculate_root(tx).map_err(|e| StageError::Fatal(Box::new(e)))?
+            match loader
+                .calculate_root_with_progress(tx, resume, commit_threshold)
+                .map_err(|e| StageError::Fatal(Box::new(e)))?
+            {
+                TrieProgress::Complete(root) => root,
+                TrieProgress::InProgress { last_account_key, state } => {
+                    self.save_execution_checkpoint(
+                        tx,
+                        MerkleCheckpoint {
+                            target_block: previous_stage_progress,
+                            last_account_key,
+                            state,
+                        },
+                    )?;
+                    return Ok(ExecOutput { stage_progress, done: false })
+                }
+            }
         } else {
             debug!(target: "sync::stages::merkle::exec", current = ?stage_progress, target = ?previous_stage_progress, "Updating trie");
             // Iterate over changeset (similar to Hashing stages) and take new values
-            let current_root = tx.get_header(stage_progress)?.state_root;
             let loader = DBTrieLoader::default();
             loader
-                .update_root(tx, current_root, from_transition..to_transition)
+                .update_root(tx, from_transition..to_transition)
                 .map_err(|e| StageError::Fatal(Box::new(e)))?
         };
 
+        self.checkpoint_id().clear_checkpoint(tx.deref())?;
+
         if block_root != trie_root {
             warn!(target: "sync::stages::merkle::exec", ?previous_stage_progress, got = ?block_root, expected = ?trie_root, "Block's root state failed verification");
             return Err(StageError::Validation {
@@ -143,23 +225,33 @@ impl<DB: Database> Stage<DB> for MerkleStage {
             return Ok(UnwindOutput { stage_progress: input.unwind_to })
         }
 
+        // The hashed state is about to change, so an interrupted rebuild has to start over. The
+        // intermediate hashes it already stored remain valid and are updated below.
+        self.checkpoint_id().clear_checkpoint(tx.deref())?;
+
+        // The state before the pivot block of a snap sync can not be recreated, the snap state
+        // stage removes the downloaded state instead.
+        if unwinds_below_pivot(tx, input.unwind_to)? {
+            info!(target: "sync::stages::merkle::unwind", "Stage skipped below the snap sync pivot");
+            return Ok(UnwindOutput { stage_progress: input.unwind_to })
+        }
+
         let target_root = tx.get_header(input.unwind_to)?.state_root;
 
         // If the merkle stage fails to execute, the trie changes weren't commited
         // and the root stayed the same
-        if tx.get::<tables::AccountsTrie>(target_root)?.is_some() {
+        if tx.get::<tables::AccountsTrie>(StoredNibbles::default())? == Some(target_root) {
             info!(target: "sync::stages::merkle::unwind", "Stage skipped");
             return Ok(UnwindOutput { stage_progress: input.unwind_to })
         }
 
         let loader = DBTrieLoader::default();
-        let current_root = tx.get_header(input.stage_progress)?.state_root;
 
         let from_transition = tx.get_block_transition(input.unwind_to)?;
         let to_transition = tx.get_block_transition(input.stage_progress)?;
 
         let block_root = loader
-            .update_root(tx, current_root, from_transition..to_transition)
+            .update_root(tx, from_transition..to_transition)
             .map_err(|e| StageError::Fatal(Box::new(e)))?;
 
         if block_root != target_root {
@@ -257,14 +349,62 @@ mod tests {
         assert!(runner.validate_execution(input, result.ok()).is_ok(), "execution validation");
     }
 
+    /// Rebuild the trie over several runs, committing in between
+    #[tokio::test]
+    async fn execute_clean_merkle_with_checkpoints() {
+        let (previous_stage, stage_progress) = (500, 0);
+
+        // Set up the runner
+        let mut runner = MerkleTestRunner::default();
+        runner.commit_threshold = 5;
+        let input = ExecInput {
+            previous_stage: Some((PREV_STAGE_ID, previous_stage)),
+            stage_progress: Some(stage_progress),
+        };
+
+        runner.seed_execution(input).expect("failed to seed execution");
+        let n_accounts = runner.tx.table::<tables::HashedAccount>().unwrap().len() as u64;
+
+        let mut runs = 0;
+        let result = loop {
+            runs += 1;
+            let result = runner.execute(input).await.unwrap();
+            match result {
+                Ok(ExecOutput { done: false, stage_progress: progress }) => {
+                    assert_eq!(progress, stage_progress);
+                    let checkpoint = runner
+                        .stage()
+                        .get_execution_checkpoint(&runner.tx.inner())
+                        .unwrap()
+                        .expect("checkpoint is saved");
+                    assert_eq!(checkpoint.target_block, previous_stage);
+                }
+                result => break result,
+            }
+        };
+
+        // Assert the successful result
+        assert_matches!(
+            result,
+            Ok(ExecOutput { done, stage_progress })
+                if done && stage_progress == previous_stage
+        );
+        assert_eq!(runs, n_accounts / 5 + 1);
+        assert_matches!(runner.stage().get_execution_checkpoint(&runner.tx.inner()), Ok(None));
+
+        // Validate the stage execution
+        assert!(runner.validate_execution(input, result.ok()).is_ok(), "execution validation");
+    }
+
     struct MerkleTestRunner {
         tx: TestTransaction,
         clean_threshold: u64,
+        commit_threshold: u64,
     }
 
     impl Default for MerkleTestRunner {
         fn default() -> Self {
-            Self { tx: TestTransaction::default(), clean_threshold: 10000 }
+            Self { tx: TestTransaction::default(), clean_threshold: 10000, commit_threshold: 10000 }
         }
     }
 
@@ -276,7 +416,10 @@ mod tests {
         }
 
         fn stage(&self) -> Self::S {
-            Self::S::Both { clean_threshold: self.clean_threshold }
+            Self::S::Both {
+                clean_threshold: self.clean_threshold,
+                commit_threshold: self.commit_threshold,
+            }
         }
     }
 
@@ -16,8 +16,16 @@ mod index_account_history;
 mod index_storage_history;
 /// Intermediate hashes and creating merkle root
 mod merkle;
+/// The prune stage.
+mod prune;
 /// The sender recovery stage.
 mod sender_recovery;
+/// The snap heal stage.
+mod snap_heal;
+/// The snap state stage.
+mod snap_state;
+/// The static file stage.
+mod static_file;
 /// Helper types for working with streams.
 mod stream;
 /// The total difficulty stage
@@ -34,6 +42,10 @@ pub use headers::*;
 pub use index_account_history::*;
 pub use index_storage_history::*;
 pub use merkle::*;
+pub use prune::*;
 pub use sender_recovery::*;
+pub use snap_heal::*;
+pub use snap_state::*;
+pub use static_file::*;
 pub use total_difficulty::*;
 pub use tx_lookup::*;
@@ -0,0 +1,391 @@
+use crate::{ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput, UnwindOutput};
+use reth_db::{
+    cursor::DbCursorRO,
+    database::Database,
+    table::Table,
+    tables,
+    transaction::{DbTx, DbTxMut},
+};
+use reth_primitives::{BlockNumber, PruneModes, PrunePart, TransitionId, TxNumber};
+use reth_provider::{remove_unreferenced_bytecodes, Transaction};
+use reth_static_files::StaticFiles;
+use std::{
+    ops::{Range, RangeInclusive},
+    sync::Arc,
+};
+use tracing::*;
+
+/// The [`StageId`] of the prune stage.
+pub const PRUNE: StageId = StageId("Prune");
+
+/// The prune stage.
+///
+/// This stage deletes the history of old blocks for each part of the history that has a
+/// [PruneMode][reth_primitives::PruneMode] in its [PruneModes], turning an archive node into a
+/// full node. The highest pruned block of each part is written to [tables::PruneCheckpoints],
+/// which providers use to tell callers that the history of a block is gone.
+///
+/// Pruning the account history also deletes the bytecodes of [tables::UnreferencedBytecodes]
+/// that are no longer used by the remaining history. Archive nodes keep all bytecodes.
+///
+/// The stage runs after all other stages and deletes at most `commit_threshold` transactions, or
+/// state transitions for the account and storage history, before committing.
+///
+/// Pruned history can not be restored on unwind, which is why the most recent
+/// [MINIMUM_PRUNING_DISTANCE][reth_primitives::MINIMUM_PRUNING_DISTANCE] blocks are never pruned.
+#[derive(Debug, Clone)]
+pub struct PruneStage {
+    /// The pruning settings of each part of the history.
+    modes: PruneModes,
+    /// The maximum number of transactions or state transitions to prune before committing.
+    commit_threshold: u64,
+    /// The static files holding the transactions moved out of the database, if any.
+    static_files: Option<Arc<StaticFiles>>,
+}
+
+impl Default for PruneStage {
+    fn default() -> Self {
+        Self { modes: PruneModes::default(), commit_threshold: 10_000, static_files: None }
+    }
+}
+
+impl PruneStage {
+    /// Create new instance of [PruneStage].
+    pub fn new(modes: PruneModes, commit_threshold: u64) -> Self {
+        Self { modes, commit_threshold, static_files: None }
+    }
+
+    /// Read the transactions moved out of the database from the given static files.
+    pub fn with_static_files(mut self, static_files: Arc<StaticFiles>) -> Self {
+        self.static_files = Some(static_files);
+        self
+    }
+
+    /// Prune a part of the history from `blocks.start()` up to at most `blocks.end()`, limited to
+    /// the blocks whose transactions or state transitions fit within `limit`.
+    ///
+    /// Returns the highest pruned block and the number of pruned transactions or transitions, or
+    /// `None` if there are no blocks to prune.
+    fn prune_part<DB: Database>(
+        &self,
+        tx: &Transaction<'_, DB>,
+        part: PrunePart,
+        blocks: RangeInclusive<BlockNumber>,
+        limit: u64,
+    ) -> Result<Option<(BlockNumber, u64)>, StageError> {
+        let pruned = match part {
+            PrunePart::SenderRecovery => transaction_range(tx, blocks, limit)?
+                .map(|(block, txs)| prune_by_tx_number::<_, tables::TxSenders>(tx, block, txs))
+                .transpose()?,
+            PrunePart::Receipts => transaction_range(tx, blocks, limit)?
+                .map(|(block, txs)| prune_by_tx_number::<_, tables::Receipts>(tx, block, txs))
+                .transpose()?,
+            PrunePart::TransactionLookup => {
+                let Some((block, txs)) = transaction_range(tx, blocks, limit)? else {
+                    return Ok(None)
+                };
+                for tx_number in txs.clone() {
+                    let mut transaction = tx.get::<tables::Transactions>(tx_number)?;
+                    if let (None, Some(static_files)) = (&transaction, &self.static_files) {
+                        transaction = static_files.transaction(tx_number)?;
+                    }
+                    if let Some(transaction) = transaction {
+                        tx.delete::<tables::TxHashNumber>(transaction.hash(), None)?;
+                    }
+                }
+                Some((block, txs.end - txs.start))
+            }
+            PrunePart::AccountHistory => {
+                let Some((block, transitions)) = transition_range(tx, blocks, limit)? else {
+                    return Ok(None)
+                };
+                tx.prune_account_history(transitions.clone())?;
+                // The state of the pruned blocks can no longer be read, so neither can the
+                // bytecodes that became unreferenced up to the highest of them.
+                let removed = remove_unreferenced_bytecodes(&**tx, block + 1)?;
+                if removed > 0 {
+                    debug!(target: "sync::stages::prune", removed, "Removed unreferenced bytecodes");
+                }
+                Some((block, transitions.end - transitions.start))
+            }
+            PrunePart::StorageHistory => {
+                let Some((block, transitions)) = transition_range(tx, blocks, limit)? else {
+                    return Ok(None)
+                };
+                tx.prune_storage_history(transitions.clone())?;
+                Some((block, transitions.end - transitions.start))
+            }
+        };
+        Ok(pruned)
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB: Database> Stage<DB> for PruneStage {
+    /// Return the id of the stage
+    fn id(&self) -> StageId {
+        PRUNE
+    }
+
+    /// Prune the history of all configured parts up to their target blocks.
+    async fn execute(
+        &mut self,
+        tx: &mut Transaction<'_, DB>,
+        input: ExecInput,
+    ) -> Result<ExecOutput, StageError> {
+        let tip = input.previous_stage_progress();
+        let mut remaining = self.commit_threshold;
+        let mut done = true;
+
+        for part in PrunePart::ALL {
+            let target = self.modes.get(part).and_then(|mode| mode.prune_target_block(tip));
+            let Some(target) = target else { continue };
+            let from = tx.get::<tables::PruneCheckpoints>(part)?.map_or(0, |block| block + 1);
+            if from > target {
+                continue
+            }
+            if remaining == 0 {
+                done = false;
+                break
+            }
+
+            let Some((pruned_to, pruned)) = self.prune_part(tx, part, from..=target, remaining)?
+            else {
+                continue
+            };
+            tx.put::<tables::PruneCheckpoints>(part, pruned_to)?;
+            remaining = remaining.saturating_sub(pruned);
+            debug!(target: "sync::stages::prune", ?part, from, pruned_to, target, "Pruned history");
+
+            if pruned_to < target {
+                done = false;
+                break
+            }
+        }
+
+        let stage_progress = if done { tip } else { input.stage_progress.unwrap_or_default() };
+        info!(target: "sync::stages::prune", stage_progress, done, "Sync iteration finished");
+        Ok(ExecOutput { stage_progress, done })
+    }
+
+    /// Unwind the stage.
+    async fn unwind(
+        &mut self,
+        _tx: &mut Transaction<'_, DB>,
+        input: UnwindInput,
+    ) -> Result<UnwindOutput, StageError> {
+        // Pruned history can not be restored. The blocks being unwound are within the minimum
+        // pruning distance, so their history was never pruned.
+        info!(target: "sync::stages::prune", to_block = input.unwind_to, "Unwinding");
+        Ok(UnwindOutput { stage_progress: input.unwind_to })
+    }
+}
+
+/// Returns the highest block of the range whose transactions, together with the transactions of
+/// the blocks before it, do not exceed `limit`, and the numbers of those transactions. The first
+/// block is always included.
+fn transaction_range<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    blocks: RangeInclusive<BlockNumber>,
+    limit: u64,
+) -> Result<Option<(BlockNumber, Range<TxNumber>)>, StageError> {
+    let mut range: Option<(BlockNumber, Range<TxNumber>)> = None;
+    let mut cursor = tx.cursor_read::<tables::BlockBodies>()?;
+    let mut walker = cursor.walk_range(blocks)?;
+    while let Some((number, body)) = walker.next().transpose()? {
+        let start = range.as_ref().map_or(body.start_tx_id, |(_, txs)| txs.start);
+        let end = body.start_tx_id + body.tx_count;
+        if range.is_some() && end - start > limit {
+            break
+        }
+        range = Some((number, start..end));
+    }
+    Ok(range)
+}
+
+/// Returns the highest block of the range whose state transitions, together with the transitions
+/// of the blocks before it, do not exceed `limit`, and the ids of those transitions. The first
+/// block is always included.
+fn transition_range<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    blocks: RangeInclusive<BlockNumber>,
+    limit: u64,
+) -> Result<Option<(BlockNumber, Range<TransitionId>)>, StageError> {
+    let start = match blocks.start().checked_sub(1) {
+        Some(previous) => tx.get_block_transition(previous)?,
+        None => 0,
+    };
+
+    let mut range: Option<(BlockNumber, Range<TransitionId>)> = None;
+    let mut cursor = tx.cursor_read::<tables::BlockTransitionIndex>()?;
+    let mut walker = cursor.walk_range(blocks)?;
+    while let Some((number, end)) = walker.next().transpose()? {
+        if range.is_some() && end - start > limit {
+            break
+        }
+        range = Some((number, start..end));
+    }
+    Ok(range)
+}
+
+/// Delete the entries of the given transactions from a table keyed by transaction number.
+///
+/// Returns the highest pruned block and the number of pruned transactions.
+fn prune_by_tx_number<DB, T>(
+    tx: &Transaction<'_, DB>,
+    block: BlockNumber,
+    txs: Range<TxNumber>,
+) -> Result<(BlockNumber, u64), StageError>
+where
+    DB: Database,
+    T: Table<Key = TxNumber>,
+{
+    for tx_number in txs.clone() {
+        tx.delete::<T>(tx_number, None)?;
+    }
+    Ok((block, txs.end - txs.start))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{TestTransaction, PREV_STAGE_ID};
+    use reth_db::{
+        models::{AccountBeforeTx, ShardedKey},
+        TransitionList,
+    };
+    use reth_interfaces::test_utils::generators::random_block_range;
+    use reth_primitives::{Account, Address, Bytecode, PruneMode, H256};
+
+    /// Run the stage to completion, committing after every execution like the pipeline does.
+    async fn execute_to_completion(test_tx: &TestTransaction, stage: &mut PruneStage, tip: u64) {
+        let mut stage_progress = None;
+        loop {
+            let mut tx = test_tx.inner();
+            let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, tip)), stage_progress };
+            let output = stage.execute(&mut tx, input).await.unwrap();
+            tx.commit().unwrap();
+
+            stage_progress = Some(output.stage_progress);
+            if output.done {
+                assert_eq!(output.stage_progress, tip);
+                break
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn prune_transactions_in_batches() {
+        let test_tx = TestTransaction::default();
+        let tip = 300;
+        let blocks = random_block_range(0..tip + 1, H256::zero(), 0..3);
+        test_tx.insert_blocks(blocks.iter(), None).unwrap();
+        test_tx
+            .commit(|tx| {
+                let mut tx_number = 0;
+                for block in &blocks {
+                    for transaction in &block.body {
+                        tx.put::<tables::TxSenders>(tx_number, Address::random())?;
+                        tx.put::<tables::TxHashNumber>(transaction.hash(), tx_number)?;
+                        tx_number += 1;
+                    }
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        let modes = PruneModes {
+            sender_recovery: Some(PruneMode::Distance(150)),
+            transaction_lookup: Some(PruneMode::Before(100)),
+            ..Default::default()
+        };
+        let mut stage = PruneStage::new(modes, 10);
+        execute_to_completion(&test_tx, &mut stage, tip).await;
+
+        let tx = test_tx.inner();
+        assert_eq!(
+            tx.get::<tables::PruneCheckpoints>(PrunePart::SenderRecovery).unwrap(),
+            Some(150)
+        );
+        assert_eq!(
+            tx.get::<tables::PruneCheckpoints>(PrunePart::TransactionLookup).unwrap(),
+            Some(99)
+        );
+        assert_eq!(tx.get::<tables::PruneCheckpoints>(PrunePart::Receipts).unwrap(), None);
+
+        let mut tx_number = 0;
+        for block in &blocks {
+            for transaction in &block.body {
+                assert_eq!(
+                    tx.get::<tables::TxSenders>(tx_number).unwrap().is_some(),
+                    block.number > 150
+                );
+                assert_eq!(
+                    tx.get::<tables::TxHashNumber>(transaction.hash()).unwrap().is_some(),
+                    block.number >= 100
+                );
+                tx_number += 1;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn prune_account_history() {
+        let test_tx = TestTransaction::default();
+        let tip = 200;
+        // A single transaction per block, so the transitions of block `n` end at `n + 1`.
+        let blocks = random_block_range(0..tip + 1, H256::zero(), 1..2);
+        test_tx.insert_blocks(blocks.iter(), None).unwrap();
+
+        let address = Address::random();
+        let account = |nonce| AccountBeforeTx {
+            address,
+            info: Some(Account { nonce, ..Default::default() }),
+        };
+        let bytecode = Bytecode::new_raw(vec![0x00].into());
+        let (pruned_code, kept_code) = (H256::random(), H256::random());
+        test_tx
+            .commit(|tx| {
+                for transition in [5, 50, 150] {
+                    tx.put::<tables::AccountChangeSet>(transition, account(transition))?;
+                }
+                tx.put::<tables::AccountHistory>(
+                    ShardedKey::new(address, 5),
+                    TransitionList::new([5]).unwrap(),
+                )?;
+                tx.put::<tables::AccountHistory>(
+                    ShardedKey::new(address, u64::MAX),
+                    TransitionList::new([50, 150]).unwrap(),
+                )?;
+                for (block, hash) in [(50, pruned_code), (150, kept_code)] {
+                    tx.put::<tables::Bytecodes>(hash, bytecode.clone())?;
+                    tx.put::<tables::UnreferencedBytecodes>(block, hash)?;
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        let modes =
+            PruneModes { account_history: Some(PruneMode::Before(100)), ..Default::default() };
+        let mut stage = PruneStage::new(modes, 30);
+        execute_to_completion(&test_tx, &mut stage, tip).await;
+
+        let tx = test_tx.inner();
+        assert_eq!(
+            tx.get::<tables::PruneCheckpoints>(PrunePart::AccountHistory).unwrap(),
+            Some(99)
+        );
+        assert_eq!(test_tx.table::<tables::AccountChangeSet>().unwrap(), vec![(150, account(150))]);
+        assert_eq!(
+            test_tx.table::<tables::AccountHistory>().unwrap(),
+            vec![(ShardedKey::new(address, u64::MAX), TransitionList::new([150]).unwrap())]
+        );
+        // Only the bytecode that became unreferenced within the pruned history is deleted.
+        assert_eq!(tx.get::<tables::Bytecodes>(pruned_code).unwrap(), None);
+        assert!(tx.get::<tables::Bytecodes>(kept_code).unwrap().is_some());
+        assert_eq!(
+            test_tx.table::<tables::UnreferencedBytecodes>().unwrap(),
+            vec![(150, kept_code)]
+        );
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     exec_or_return, stages::stream::SequentialPairStream, ExecAction, ExecInput, ExecOutput, Stage,
-    StageError, StageId, UnwindInput, UnwindOutput,
+    StageError, StageId, UnwindInput, UnwindOutput, WorkerPool,
 };
 use futures_util::StreamExt;
 use reth_db::{
@@ -9,7 +9,7 @@ use reth_db::{
     tables,
     transaction::{DbTx, DbTxMut},
 };
-use reth_primitives::TxNumber;
+use reth_primitives::{Address, TransactionSigned, TxNumber};
 use reth_provider::Transaction;
 use std::fmt::Debug;
 use thiserror::Error;
@@ -20,19 +20,26 @@ use tracing::*;
 /// The [`StageId`] of the sender recovery stage.
 pub const SENDER_RECOVERY: StageId = StageId("SenderRecovery");
 
+/// The number of transactions recovered by a single job on the [WorkerPool].
+const RECOVERY_CHUNK_SIZE: usize = 1_000;
+
 /// The sender recovery stage iterates over existing transactions,
 /// recovers the transaction signer and stores them
 /// in [`TxSenders`][reth_db::tables::TxSenders] table.
+///
+/// The transactions are read in chunks, which are recovered in parallel on the [WorkerPool].
 #[derive(Clone, Debug)]
 pub struct SenderRecoveryStage {
     /// The size of inserted items after which the control
     /// flow will be returned to the pipeline for commit
     pub commit_threshold: u64,
+    /// The pool that recovers the senders.
+    pub pool: WorkerPool,
 }
 
 impl Default for SenderRecoveryStage {
     fn default() -> Self {
-        Self { commit_threshold: 10000 }
+        Self { commit_threshold: 10000, pool: WorkerPool::default() }
     }
 }
 
@@ -74,37 +81,43 @@ impl<DB: Database> Stage<DB> for SenderRecoveryStage {
         // Acquire the cursor over the transactions
         let mut tx_cursor = tx.cursor_read::<tables::Transactions>()?;
         // Walk the transactions from start to end index (inclusive)
-        let entries = tx_cursor.walk_range(start_tx_index..=end_tx_index)?;
+        let mut entries = tx_cursor.walk_range(start_tx_index..=end_tx_index)?;
 
         // Iterate over transactions in chunks
-        info!(target: "sync::stages::sender_recovery", start_tx_index, end_tx_index, "Recovering senders");
+        info!(target: "sync::stages::sender_recovery", start_tx_index, end_tx_index, threads = self.pool.threads(), "Recovering senders");
 
-        // a channel to receive results from a rayon job
+        // a channel to receive results from the recovery jobs
         let (tx, rx) = mpsc::unbounded_channel();
 
-        // spawn recovery jobs onto the default rayon threadpool and send the result through the
-        // channel
-        for entry in entries {
-            let (tx_id, transaction) = entry?;
+        // spawn a recovery job for every chunk onto the worker pool and send the result through
+        // the channel
+        let mut chunk_index = 0u64;
+        loop {
+            let chunk =
+                entries.by_ref().take(RECOVERY_CHUNK_SIZE).collect::<Result<Vec<_>, _>>()?;
+            if chunk.is_empty() {
+                break
+            }
+
             let tx = tx.clone();
-            rayon::spawn_fifo(move || {
-                let res = if let Some(signer) = transaction.recover_signer() {
-                    Ok((tx_id, signer))
-                } else {
-                    Err(StageError::from(SenderRecoveryStageError::SenderRecovery { tx: tx_id }))
-                };
+            let index = chunk_index;
+            self.pool.spawn_fifo(move || {
+                let res = recover_senders(chunk).map(|senders| (index, senders));
                 // send the result back
                 let _ = tx.send(res);
             });
+            chunk_index += 1;
         }
         drop(tx);
 
-        let mut recovered_senders =
-            SequentialPairStream::new(start_tx_index, UnboundedReceiverStream::new(rx));
+        // write the senders of the chunks in order, so they are appended to the table
+        let mut recovered_senders = SequentialPairStream::new(0, UnboundedReceiverStream::new(rx));
 
         while let Some(recovered) = recovered_senders.next().await {
-            let (id, sender) = recovered?;
-            senders_cursor.append(id, sender)?;
+            let (_, senders) = recovered?;
+            for (id, sender) in senders {
+                senders_cursor.append(id, sender)?;
+            }
         }
 
         let done = !capped;
@@ -126,6 +139,21 @@ impl<DB: Database> Stage<DB> for SenderRecoveryStage {
     }
 }
 
+/// Recovers the senders of a chunk of transactions.
+fn recover_senders(
+    chunk: Vec<(TxNumber, TransactionSigned)>,
+) -> Result<Vec<(TxNumber, Address)>, StageError> {
+    chunk
+        .into_iter()
+        .map(|(tx_id, transaction)| {
+            let signer = transaction
+                .recover_signer()
+                .ok_or(SenderRecoveryStageError::SenderRecovery { tx: tx_id })?;
+            Ok((tx_id, signer))
+        })
+        .collect()
+}
+
 // TODO(onbjerg): Should unwind
 #[derive(Error, Debug)]
 enum SenderRecoveryStageError {
@@ -275,7 +303,10 @@ mod tests {
         }
 
         fn stage(&self) -> Self::S {
-            SenderRecoveryStage { commit_threshold: self.threshold }
+            SenderRecoveryStage {
+                commit_threshold: self.threshold,
+                pool: WorkerPool::new(2).unwrap(),
+            }
         }
     }
 
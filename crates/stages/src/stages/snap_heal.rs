@@ -0,0 +1,587 @@
+use crate::{
+    stages::{
+        snap_state::{
+            delete_hashed_accounts, delete_hashed_storage, download_bytecodes, fetch,
+            get_snap_checkpoint, key_range, move_pivot, next_key, previous_key, SnapSyncError,
+            DEFAULT_PIVOT_DISTANCE,
+        },
+        ACCOUNT_HASHING, EXECUTION, INDEX_ACCOUNT_HISTORY, INDEX_STORAGE_HISTORY, MERKLE_EXECUTION,
+        MERKLE_UNWIND, SENDER_RECOVERY, STORAGE_HASHING,
+    },
+    ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput, UnwindOutput,
+};
+use reth_db::{
+    cursor::DbCursorRW,
+    database::Database,
+    tables,
+    transaction::{DbTx, DbTxMut},
+};
+use reth_eth_wire::snap::GetTrieNodes;
+use reth_interfaces::p2p::snap::client::SnapClient;
+use reth_primitives::{
+    keccak256, trie::Nibbles, Account, BlockNumber, Bytes, PrunePart, SnapSyncCheckpoint,
+    StorageEntry, EMPTY_ROOT, H256, U256,
+};
+use reth_provider::{
+    trie::{ChildRef, DBTrieLoader, EthAccount, TrieNode},
+    update_bytecode_references, Transaction,
+};
+use reth_rlp::Decodable;
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    ops::Deref,
+};
+use tracing::*;
+
+/// The [`StageId`] of the snap heal stage.
+pub const SNAP_HEAL: StageId = StageId("SnapHeal");
+
+/// The number of bytes requested per response.
+const RESPONSE_BYTES: u64 = 512 * 1024;
+
+/// The maximum number of trie nodes requested at once.
+const MAX_TRIE_NODES: usize = 128;
+
+/// The number of times the state trie is healed before the stage gives up.
+const MAX_HEAL_ROUNDS: usize = 4;
+
+/// The stages that process the blocks up to the pivot block, their progress is set to the pivot
+/// once the snap sync finished.
+const PIVOT_STAGES: [StageId; 8] = [
+    SENDER_RECOVERY,
+    EXECUTION,
+    MERKLE_UNWIND,
+    ACCOUNT_HASHING,
+    STORAGE_HASHING,
+    MERKLE_EXECUTION,
+    INDEX_STORAGE_HISTORY,
+    INDEX_ACCOUNT_HISTORY,
+];
+
+/// The history that is not available below the pivot block.
+const PIVOT_PRUNE_PARTS: [PrunePart; 4] = [
+    PrunePart::SenderRecovery,
+    PrunePart::Receipts,
+    PrunePart::AccountHistory,
+    PrunePart::StorageHistory,
+];
+
+/// The snap heal stage repairs the state downloaded by the
+/// [SnapStateStage][crate::stages::SnapStateStage], so it matches the state root of the pivot
+/// block, and hands over to the execution of the blocks after the pivot.
+///
+/// The downloaded ranges may belong to the state of different pivot blocks. The stage computes
+/// the root of the downloaded state and, if it does not match, walks the state trie of the pivot
+/// block from the root by requesting its nodes from peers. The children of every node are
+/// compared with the local trie, only subtrees whose hash differs are requested, and the leaves
+/// that are not part of the requested trie are deleted. Accounts whose storage root differs are
+/// healed the same way in their storage trie.
+///
+/// Once the state matches, the progress of the stages that process the blocks up to the pivot is
+/// set to the pivot block, and the history below the pivot is marked as pruned, since neither the
+/// senders, the receipts nor the changesets of these blocks are available. Unwinding below the
+/// pivot block starts the snap sync over, see [SnapStateStage][crate::stages::SnapStateStage].
+///
+/// The stage is skipped if no snap sync was started or if its download has not finished.
+///
+/// Tables updated:
+/// - [tables::HashedAccount]
+/// - [tables::HashedStorage]
+/// - [tables::Bytecodes]
+/// - [tables::BytecodeRefCounts]
+/// - [tables::AccountsTrie]
+/// - [tables::StoragesTrie]
+/// - [tables::PruneCheckpoints]
+#[derive(Debug)]
+pub struct SnapHealStage<C> {
+    /// The client that downloads the trie nodes from peers.
+    pub client: C,
+    /// The distance of the pivot block from the tip of the chain.
+    pub pivot_distance: u64,
+}
+
+impl<C> SnapHealStage<C> {
+    /// Create new snap heal stage with the default pivot distance.
+    pub fn new(client: C) -> Self {
+        Self { client, pivot_distance: DEFAULT_PIVOT_DISTANCE }
+    }
+
+    /// Set the distance of the pivot block from the tip of the chain.
+    pub fn with_pivot_distance(mut self, pivot_distance: u64) -> Self {
+        self.pivot_distance = pivot_distance;
+        self
+    }
+}
+
+/// A node of the trie of the pivot block whose subtree differs from the local one.
+#[derive(Debug, Clone)]
+struct HealTask {
+    /// The hashed address of the account if the node is part of its storage trie.
+    account: Option<H256>,
+    /// The path of the node.
+    path: Nibbles,
+    /// The hash of the node.
+    hash: H256,
+}
+
+impl<C: SnapClient> SnapHealStage<C> {
+    /// Walks the trie of the pivot block from the root and replaces the subtrees that differ from
+    /// the local state.
+    async fn heal<DB: Database>(
+        &self,
+        tx: &Transaction<'_, DB>,
+        checkpoint: &SnapSyncCheckpoint,
+    ) -> Result<(), SnapSyncError> {
+        let root = checkpoint.state_root;
+        let mut queue =
+            VecDeque::from([HealTask { account: None, path: Nibbles::default(), hash: root }]);
+        let mut code_hashes = HashSet::new();
+        let mut healed = 0;
+
+        while !queue.is_empty() {
+            let batch = queue.drain(..queue.len().min(MAX_TRIE_NODES)).collect::<Vec<_>>();
+            let paths = batch
+                .iter()
+                .map(|task| {
+                    let path = Bytes::from(task.path.encode_path_leaf(false));
+                    match task.account {
+                        Some(hashed_address) => {
+                            vec![Bytes::from(hashed_address.as_bytes().to_vec()), path]
+                        }
+                        None => vec![path],
+                    }
+                })
+                .collect::<Vec<_>>();
+
+            let nodes = fetch(
+                &self.client,
+                || {
+                    self.client.get_trie_nodes(GetTrieNodes {
+                        request_id: 0,
+                        root_hash: root,
+                        paths: paths.clone(),
+                        response_bytes: RESPONSE_BYTES,
+                    })
+                },
+                |response| {
+                    if response.nodes.is_empty() {
+                        return Err(SnapSyncError::StateUnavailable)
+                    }
+                    if response.nodes.len() > batch.len() {
+                        return Err(SnapSyncError::InvalidResponse("more trie nodes than requested"))
+                    }
+                    batch
+                        .iter()
+                        .zip(response.nodes)
+                        .map(|(task, node)| {
+                            if keccak256(&node) != task.hash {
+                                return Err(SnapSyncError::InvalidResponse("unrequested trie node"))
+                            }
+                            TrieNode::decode(&node)
+                                .map_err(|_| SnapSyncError::InvalidResponse("invalid trie node"))
+                        })
+                        .collect::<Result<Vec<_>, _>>()
+                },
+            )
+            .await?;
+
+            // Request the nodes that were not served again.
+            for task in batch[nodes.len()..].iter().rev() {
+                queue.push_front(task.clone());
+            }
+
+            healed += nodes.len();
+            for (task, node) in batch.into_iter().zip(nodes) {
+                heal_node(
+                    tx,
+                    checkpoint.pivot,
+                    task.account,
+                    task.path,
+                    node,
+                    &mut queue,
+                    &mut code_hashes,
+                )?;
+            }
+            trace!(target: "sync::stages::snap_heal", healed, pending = queue.len(), "Healed trie nodes");
+        }
+
+        debug!(target: "sync::stages::snap_heal", healed, codes = code_hashes.len(), "Healed state trie");
+        download_bytecodes(&self.client, tx, code_hashes).await
+    }
+}
+
+/// Replaces the local subtree at `path` with the children of the given node of the trie of the
+/// pivot block. Children whose hash differs from the local subtree are added to the queue.
+fn heal_node<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    pivot: BlockNumber,
+    account: Option<H256>,
+    path: Nibbles,
+    node: TrieNode,
+    queue: &mut VecDeque<HealTask>,
+    code_hashes: &mut HashSet<H256>,
+) -> Result<(), SnapSyncError> {
+    match node {
+        TrieNode::Branch(children) => {
+            let child_path = |nibble: u8| {
+                let mut child_path = path.clone();
+                child_path.push(nibble);
+                child_path
+            };
+            let hashed_children = (0u8..)
+                .zip(&children)
+                .filter(|(_, child)| matches!(child, Some(ChildRef::Hash(_))))
+                .map(|(nibble, _)| child_path(nibble))
+                .collect::<Vec<_>>();
+            let local = local_hashes(tx, account, &hashed_children)?;
+
+            for (nibble, child) in (0u8..).zip(children) {
+                let child_path = child_path(nibble);
+                match child {
+                    None => {
+                        let (from, to) = key_range(&child_path);
+                        delete_leaves(tx, pivot, account, from, to)?;
+                    }
+                    Some(child) => heal_child(
+                        tx,
+                        pivot,
+                        account,
+                        child_path,
+                        child,
+                        &local,
+                        queue,
+                        code_hashes,
+                    )?,
+                }
+            }
+        }
+        TrieNode::Extension { key, child } => {
+            let mut child_path = path.clone();
+            child_path.extend(&key);
+            let (from, to) = key_range(&path);
+            let (keep_from, keep_to) = key_range(&child_path);
+            delete_leaves_except(tx, pivot, account, (from, to), (keep_from, keep_to))?;
+
+            let local = match child {
+                ChildRef::Hash(_) => local_hashes(tx, account, &[child_path.clone()])?,
+                ChildRef::Embedded(_) => HashMap::new(),
+            };
+            heal_child(tx, pivot, account, child_path, child, &local, queue, code_hashes)?;
+        }
+        TrieNode::Leaf { key, value } => {
+            let mut leaf_path = path.clone();
+            leaf_path.extend(&key);
+            if leaf_path.len() != 64 {
+                return Err(SnapSyncError::InvalidResponse("leaf with an invalid key length"))
+            }
+            let leaf_key = H256::from_slice(&leaf_path.pack());
+            let (from, to) = key_range(&path);
+            delete_leaves_except(tx, pivot, account, (from, to), (leaf_key, leaf_key))?;
+
+            match account {
+                None => {
+                    let account = EthAccount::decode(&mut &value[..])
+                        .map_err(|_| SnapSyncError::InvalidResponse("invalid account"))?;
+                    heal_account(tx, pivot, leaf_key, account, queue, code_hashes)?;
+                }
+                Some(hashed_address) => {
+                    let value = U256::decode(&mut &value[..])
+                        .map_err(|_| SnapSyncError::InvalidResponse("invalid storage value"))?;
+                    delete_hashed_storage(tx.deref(), hashed_address, leaf_key, leaf_key)?;
+                    tx.cursor_dup_write::<tables::HashedStorage>()?
+                        .upsert(hashed_address, StorageEntry { key: leaf_key, value })?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Heals the child of a node, whose local hash is looked up in `local`.
+#[allow(clippy::too_many_arguments)]
+fn heal_child<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    pivot: BlockNumber,
+    account: Option<H256>,
+    path: Nibbles,
+    child: ChildRef,
+    local: &HashMap<Nibbles, H256>,
+    queue: &mut VecDeque<HealTask>,
+    code_hashes: &mut HashSet<H256>,
+) -> Result<(), SnapSyncError> {
+    match child {
+        ChildRef::Hash(hash) => {
+            if local.get(&path) != Some(&hash) {
+                queue.push_back(HealTask { account, path, hash });
+            }
+            Ok(())
+        }
+        ChildRef::Embedded(rlp) => {
+            let node = TrieNode::decode(&rlp)
+                .map_err(|_| SnapSyncError::InvalidResponse("invalid trie node"))?;
+            heal_node(tx, pivot, account, path, node, queue, code_hashes)
+        }
+    }
+}
+
+/// Writes an account of the trie of the pivot block, and adds its storage trie to the queue if
+/// its storage root differs from the local one.
+fn heal_account<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    pivot: BlockNumber,
+    hashed_address: H256,
+    account: EthAccount,
+    queue: &mut VecDeque<HealTask>,
+    code_hashes: &mut HashSet<H256>,
+) -> Result<(), SnapSyncError> {
+    let storage_root = account.storage_root();
+    let account = Account::from(account);
+
+    let old = tx.get::<tables::HashedAccount>(hashed_address)?;
+    update_bytecode_references(tx.deref(), old.as_ref(), Some(&account), pivot)?;
+    tx.put::<tables::HashedAccount>(hashed_address, account)?;
+    code_hashes.extend(account.bytecode_hash);
+
+    let (local_root, _) = DBTrieLoader::default().storage_proof(tx.deref(), hashed_address, &[])?;
+    if local_root != storage_root {
+        if storage_root == EMPTY_ROOT {
+            tx.delete::<tables::HashedStorage>(hashed_address, None)?;
+            tx.delete::<tables::StoragesTrie>(hashed_address, None)?;
+        } else {
+            queue.push_back(HealTask {
+                account: Some(hashed_address),
+                path: Nibbles::default(),
+                hash: storage_root,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Returns the hashes of the local subtrees at the given paths of the state trie, or of the
+/// storage trie of the account. Subtrees that do not exist or are embedded in their parent are
+/// left out.
+fn local_hashes<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    account: Option<H256>,
+    paths: &[Nibbles],
+) -> Result<HashMap<Nibbles, H256>, SnapSyncError> {
+    let loader = DBTrieLoader::default();
+    let (_, nodes) = match account {
+        None => loader.state_proof(tx.deref(), paths)?,
+        Some(hashed_address) => loader.storage_proof(tx.deref(), hashed_address, paths)?,
+    };
+    Ok(nodes
+        .into_iter()
+        .filter(|(path, rlp)| rlp.len() >= 32 && paths.contains(path))
+        .map(|(path, rlp)| (path, keccak256(rlp)))
+        .collect())
+}
+
+/// Deletes the local leaves with keys from `from` to `to` of the state trie, or of the storage
+/// trie of the account.
+fn delete_leaves<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    pivot: BlockNumber,
+    account: Option<H256>,
+    from: H256,
+    to: H256,
+) -> Result<(), SnapSyncError> {
+    match account {
+        None => delete_hashed_accounts(tx.deref(), from, to, pivot)?,
+        Some(hashed_address) => delete_hashed_storage(tx.deref(), hashed_address, from, to)?,
+    }
+    Ok(())
+}
+
+/// Deletes the local leaves in the range `(from, to)`, except for those in the range `keep`.
+fn delete_leaves_except<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    pivot: BlockNumber,
+    account: Option<H256>,
+    (from, to): (H256, H256),
+    (keep_from, keep_to): (H256, H256),
+) -> Result<(), SnapSyncError> {
+    if let Some(before) = previous_key(keep_from).filter(|before| *before >= from) {
+        delete_leaves(tx, pivot, account, from, before)?;
+    }
+    if let Some(after) = next_key(keep_to).filter(|after| *after <= to) {
+        delete_leaves(tx, pivot, account, after, to)?;
+    }
+    Ok(())
+}
+
+/// Sets the progress of the stages that process the blocks up to the pivot block, and marks the
+/// history below it as pruned.
+fn finish_snap_sync<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    pivot: BlockNumber,
+) -> Result<(), StageError> {
+    for stage_id in PIVOT_STAGES {
+        stage_id.save_progress(tx.deref(), pivot)?;
+    }
+
+    for part in PIVOT_PRUNE_PARTS {
+        tx.put::<tables::PruneCheckpoints>(part, pivot)?;
+    }
+    Ok(())
+}
+
+/// Reverts [finish_snap_sync], so the stages start over with a new snap sync.
+pub(crate) fn reset_snap_sync<DB: Database>(tx: &Transaction<'_, DB>) -> Result<(), StageError> {
+    SNAP_HEAL.save_progress(tx.deref(), 0)?;
+    for stage_id in PIVOT_STAGES {
+        stage_id.save_progress(tx.deref(), 0)?;
+    }
+
+    for part in PIVOT_PRUNE_PARTS {
+        tx.delete::<tables::PruneCheckpoints>(part, None)?;
+    }
+    Ok(())
+}
+
+#[async_trait::async_trait]
+impl<DB: Database, C: SnapClient + 'static> Stage<DB> for SnapHealStage<C> {
+    /// Return the id of the stage
+    fn id(&self) -> StageId {
+        SNAP_HEAL
+    }
+
+    /// Execute the stage.
+    async fn execute(
+        &mut self,
+        tx: &mut Transaction<'_, DB>,
+        input: ExecInput,
+    ) -> Result<ExecOutput, StageError> {
+        let tip = input.previous_stage_progress();
+        let stage_progress = input.stage_progress.unwrap_or_default();
+        if stage_progress > 0 {
+            return Ok(ExecOutput { stage_progress: tip, done: true })
+        }
+
+        let mut checkpoint = match get_snap_checkpoint(tx)? {
+            // The state has not been downloaded yet.
+            Some(checkpoint) if checkpoint.next_account.is_some() => {
+                return Ok(ExecOutput { stage_progress, done: true })
+            }
+            Some(checkpoint) => checkpoint,
+            None => {
+                info!(target: "sync::stages::snap_heal", "No snap sync, stage skipped");
+                return Ok(ExecOutput { stage_progress: tip, done: true })
+            }
+        };
+
+        let mut rounds = 0;
+        loop {
+            let root = DBTrieLoader::default()
+                .calculate_root(tx)
+                .map_err(|error| StageError::from(SnapSyncError::from(error)))?;
+            if root == checkpoint.state_root {
+                break
+            }
+            if rounds == MAX_HEAL_ROUNDS {
+                return Err(SnapSyncError::RootMismatch {
+                    got: root,
+                    expected: checkpoint.state_root,
+                }
+                .into())
+            }
+            rounds += 1;
+
+            info!(target: "sync::stages::snap_heal", pivot = checkpoint.pivot, ?root, expected = ?checkpoint.state_root, "Healing state trie");
+            match self.heal(tx, &checkpoint).await {
+                Ok(()) => {}
+                Err(SnapSyncError::StateUnavailable) => {
+                    if !move_pivot(tx, &mut checkpoint, tip, self.pivot_distance)? {
+                        warn!(target: "sync::stages::snap_heal", pivot = checkpoint.pivot, "State of the pivot block is not available, waiting for the tip to move");
+                        return Ok(ExecOutput { stage_progress, done: true })
+                    }
+                    rounds = 0;
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+
+        finish_snap_sync(tx, checkpoint.pivot)?;
+        info!(target: "sync::stages::snap_heal", pivot = checkpoint.pivot, "Stage finished");
+        Ok(ExecOutput { stage_progress: tip, done: true })
+    }
+
+    /// Unwind the stage.
+    async fn unwind(
+        &mut self,
+        _tx: &mut Transaction<'_, DB>,
+        input: UnwindInput,
+    ) -> Result<UnwindOutput, StageError> {
+        info!(target: "sync::stages::snap_heal", to_block = input.unwind_to, "Unwinding");
+        Ok(UnwindOutput { stage_progress: input.unwind_to })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        stages::{
+            snap_state::tests::{insert_headers, serving_node},
+            SnapStateStage,
+        },
+        test_utils::{TestSnapClient, TestTransaction, PREV_STAGE_ID},
+    };
+    use assert_matches::assert_matches;
+    use reth_db::cursor::DbCursorRO;
+
+    #[tokio::test]
+    async fn heals_modified_state() {
+        let (node, root) = serving_node();
+        let tx = TestTransaction::default();
+        insert_headers(&tx, 100, 36, root);
+
+        let client = TestSnapClient::new(node.inner_raw());
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, 100)), stage_progress: None };
+        let mut db = tx.inner();
+        let output = SnapStateStage::new(client.clone()).execute(&mut db, input).await;
+        assert_matches!(output, Ok(ExecOutput { stage_progress: 100, done: true }));
+
+        // Modify the downloaded state as if parts of it belonged to the state of another block.
+        let accounts = node.table::<tables::HashedAccount>().unwrap();
+        let (removed, _) = accounts[5];
+        db.delete::<tables::HashedAccount>(removed, None).unwrap();
+        db.delete::<tables::HashedStorage>(removed, None).unwrap();
+        let (changed, _) =
+            db.cursor_read::<tables::HashedStorage>().unwrap().first().unwrap().unwrap();
+        db.cursor_dup_write::<tables::HashedStorage>()
+            .unwrap()
+            .upsert(changed, StorageEntry { key: H256::repeat_byte(0x42), value: U256::from(1) })
+            .unwrap();
+        db.put::<tables::HashedAccount>(H256::repeat_byte(0x42), Account::default()).unwrap();
+
+        let output = SnapHealStage::new(client.clone()).execute(&mut db, input).await;
+        assert_matches!(output, Ok(ExecOutput { stage_progress: 100, done: true }));
+        assert_eq!(DBTrieLoader::default().calculate_root(&db).unwrap(), root);
+        assert_eq!(EXECUTION.get_progress(db.deref()).unwrap(), Some(36));
+        assert_eq!(MERKLE_EXECUTION.get_progress(db.deref()).unwrap(), Some(36));
+        assert_eq!(db.get::<tables::PruneCheckpoints>(PrunePart::Receipts).unwrap(), Some(36));
+        db.commit().unwrap();
+        drop(db);
+
+        assert_eq!(client.bad_messages(), 0);
+        assert_eq!(tx.table::<tables::HashedAccount>().unwrap(), accounts);
+        assert_eq!(
+            tx.table::<tables::HashedStorage>().unwrap(),
+            node.table::<tables::HashedStorage>().unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_without_snap_sync() {
+        let (node, _) = serving_node();
+        let tx = TestTransaction::default();
+
+        let mut stage = SnapHealStage::new(TestSnapClient::new(node.inner_raw()));
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, 100)), stage_progress: None };
+        let output = stage.execute(&mut tx.inner(), input).await;
+        assert_matches!(output, Ok(ExecOutput { stage_progress: 100, done: true }));
+        assert!(tx.table_is_empty::<tables::AccountsTrie>().unwrap());
+    }
+}
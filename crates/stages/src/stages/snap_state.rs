@@ -0,0 +1,835 @@
+use crate::{
+    stages::{snap_heal::reset_snap_sync, EXECUTION},
+    ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput, UnwindOutput,
+};
+use reth_codecs::Compact;
+use reth_db::{
+    cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO},
+    database::Database,
+    tables,
+    transaction::{DbTx, DbTxMut},
+    Error as DbError,
+};
+use reth_eth_wire::snap::{
+    GetAccountRange, GetByteCodes, GetStorageRanges, SlimAccount, StorageRanges,
+};
+use reth_interfaces::p2p::{
+    error::RequestError,
+    snap::client::{SnapClient, SnapFut},
+};
+use reth_primitives::{
+    keccak256, trie::Nibbles, Account, BlockNumber, Bytecode, Bytes, SnapSyncCheckpoint,
+    StorageEntry, EMPTY_ROOT, H256, U256,
+};
+use reth_provider::{
+    trie::{verify_range_proof, EthAccount, RangeProofError, TrieError},
+    update_bytecode_references, Transaction,
+};
+use reth_rlp::{Decodable, Encodable};
+use std::{collections::HashSet, ops::Deref};
+use tracing::*;
+
+/// The [`StageId`] of the snap state stage.
+pub const SNAP_STATE: StageId = StageId(SnapSyncCheckpoint::KEY);
+
+/// The default distance of the pivot block from the tip of the chain.
+///
+/// Peers only serve the state of recent blocks, so the pivot has to stay close to the tip.
+pub const DEFAULT_PIVOT_DISTANCE: u64 = 64;
+
+/// The number of bytes requested per response.
+const RESPONSE_BYTES: u64 = 512 * 1024;
+
+/// The maximum number of accounts whose storage is requested at once.
+const MAX_STORAGE_ACCOUNTS: usize = 128;
+
+/// The maximum number of bytecodes requested at once.
+const MAX_BYTECODES: usize = 64;
+
+/// The number of times a request is sent before the download gives up.
+const MAX_ATTEMPTS: usize = 5;
+
+/// Errors of the snap sync stages.
+#[derive(Debug, thiserror::Error)]
+pub enum SnapSyncError {
+    /// No peer served the state of the pivot block.
+    #[error("The state of the pivot block is not available")]
+    StateUnavailable,
+    /// A request to a peer failed.
+    #[error(transparent)]
+    Request(#[from] RequestError),
+    /// A peer sent a range of the trie that does not belong to the requested root.
+    #[error("Invalid range proof: {0}")]
+    InvalidProof(#[from] RangeProofError),
+    /// A peer sent an invalid response.
+    #[error("Invalid response: {0}")]
+    InvalidResponse(&'static str),
+    /// The healed state trie does not have the root of the pivot block.
+    #[error("State root mismatch after healing, got {got:?}, expected {expected:?}")]
+    RootMismatch {
+        /// The root of the healed state trie.
+        got: H256,
+        /// The state root of the pivot block.
+        expected: H256,
+    },
+    /// The root or a proof of the stored state trie could not be computed.
+    #[error(transparent)]
+    Trie(#[from] TrieError),
+    /// The downloaded state could not be written.
+    #[error(transparent)]
+    Database(#[from] DbError),
+}
+
+impl SnapSyncError {
+    /// Returns whether the error is caused by the response of a peer that should be reported.
+    fn is_bad_response(&self) -> bool {
+        matches!(self, SnapSyncError::InvalidProof(_) | SnapSyncError::InvalidResponse(_))
+    }
+}
+
+impl From<SnapSyncError> for StageError {
+    fn from(error: SnapSyncError) -> Self {
+        match error {
+            SnapSyncError::Database(error) => StageError::Database(error),
+            error @ (SnapSyncError::RootMismatch { .. } | SnapSyncError::Trie(_)) => {
+                StageError::Fatal(Box::new(error))
+            }
+            error => StageError::Recoverable(Box::new(error)),
+        }
+    }
+}
+
+/// The snap state stage downloads the state of a recent pivot block from peers with the `snap`
+/// protocol, instead of executing all blocks up to it.
+///
+/// The accounts are requested in ranges of hashed addresses, together with the storage and the
+/// bytecodes of the accounts in each range. Every range is verified against the state root of the
+/// pivot block with the proof of its boundaries and replaces the hashed state in the range.
+///
+/// Only the hashed state can be downloaded, so the plain state tables are left as they are. Once
+/// the state was downloaded, the execution stage reads and writes the hashed state instead, see
+/// [is_snap_synced].
+///
+/// The stage only runs on an empty node: it skips the snap sync if any block was executed, or if
+/// the chain is not longer than the pivot distance. The progress is saved in a
+/// [SnapSyncCheckpoint] every `commit_threshold` accounts. If the state of the pivot block is no
+/// longer served by peers, the pivot is moved towards the tip. If it can not be moved, the stage
+/// finishes without progress and continues once the tip moved. The execution stage does not run
+/// until the snap sync finished.
+///
+/// The downloaded ranges may belong to the state of different pivot blocks, the
+/// [SnapHealStage][crate::stages::SnapHealStage] repairs the state trie afterwards.
+///
+/// The state before the pivot block can not be recreated, since the changesets of the earlier
+/// blocks are not available. Unwinding below the pivot block removes the downloaded state together
+/// with the checkpoint, and resets the progress of the later stages, so a new snap sync starts.
+///
+/// Tables updated:
+/// - [tables::HashedAccount]
+/// - [tables::HashedStorage]
+/// - [tables::Bytecodes]
+/// - [tables::BytecodeRefCounts]
+#[derive(Debug)]
+pub struct SnapStateStage<C> {
+    /// The client that downloads the state from peers.
+    pub client: C,
+    /// The distance of the pivot block from the tip of the chain.
+    pub pivot_distance: u64,
+    /// The number of accounts to download before committing.
+    pub commit_threshold: u64,
+}
+
+impl<C> SnapStateStage<C> {
+    /// Create new snap state stage with the default pivot distance and commit threshold.
+    pub fn new(client: C) -> Self {
+        Self { client, pivot_distance: DEFAULT_PIVOT_DISTANCE, commit_threshold: 100_000 }
+    }
+
+    /// Set the distance of the pivot block from the tip of the chain.
+    pub fn with_pivot_distance(mut self, pivot_distance: u64) -> Self {
+        self.pivot_distance = pivot_distance;
+        self
+    }
+
+    /// Set the number of accounts to download before committing.
+    pub fn with_commit_threshold(mut self, commit_threshold: u64) -> Self {
+        self.commit_threshold = commit_threshold;
+        self
+    }
+}
+
+impl<C: SnapClient> SnapStateStage<C> {
+    /// Downloads the accounts starting at `start` with their storage and bytecodes, and replaces
+    /// the hashed state in the range with them.
+    ///
+    /// Returns the number of downloaded accounts and the hashed address the download continues
+    /// at, or `None` if the range reached the end of the trie.
+    async fn download_range<DB: Database>(
+        &self,
+        tx: &Transaction<'_, DB>,
+        checkpoint: &SnapSyncCheckpoint,
+        start: H256,
+    ) -> Result<(usize, Option<H256>), SnapSyncError> {
+        let root = checkpoint.state_root;
+        let (accounts, more) = fetch(
+            &self.client,
+            || {
+                self.client.get_account_range(GetAccountRange {
+                    request_id: 0,
+                    root_hash: root,
+                    starting_hash: start,
+                    limit_hash: H256::repeat_byte(0xff),
+                    response_bytes: RESPONSE_BYTES,
+                })
+            },
+            |range| {
+                if range.accounts.is_empty() && range.proof.is_empty() {
+                    return Err(SnapSyncError::StateUnavailable)
+                }
+                let leaves = range
+                    .accounts
+                    .iter()
+                    .map(|account| {
+                        let mut out = Vec::new();
+                        trie_account(&account.body).encode(&mut out);
+                        (account.hash, out)
+                    })
+                    .collect::<Vec<_>>();
+                let more = verify_range_proof(root, start, &leaves, &range.proof)?;
+                Ok((range.accounts, more))
+            },
+        )
+        .await?;
+
+        let last = accounts.last().map(|account| account.hash);
+        let end = if more {
+            last.expect("a range with more accounts is not empty")
+        } else {
+            H256::repeat_byte(0xff)
+        };
+        debug!(target: "sync::stages::snap_state", ?start, ?end, accounts = accounts.len(), "Downloaded account range");
+
+        delete_hashed_accounts(tx.deref(), start, end, checkpoint.pivot)?;
+
+        let mut storage_roots = Vec::new();
+        let mut code_hashes = HashSet::new();
+        for data in &accounts {
+            let account = trie_account(&data.body);
+            if account.storage_root() != EMPTY_ROOT {
+                storage_roots.push((data.hash, account.storage_root()));
+            }
+            let account = Account::from(account);
+            code_hashes.extend(account.bytecode_hash);
+            update_bytecode_references(tx.deref(), None, Some(&account), checkpoint.pivot)?;
+            tx.put::<tables::HashedAccount>(data.hash, account)?;
+        }
+
+        download_storage(&self.client, tx, root, &storage_roots).await?;
+        download_bytecodes(&self.client, tx, code_hashes).await?;
+
+        let next = if more { last.and_then(next_key) } else { None };
+        Ok((accounts.len(), next))
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB: Database, C: SnapClient + 'static> Stage<DB> for SnapStateStage<C> {
+    /// Return the id of the stage
+    fn id(&self) -> StageId {
+        SNAP_STATE
+    }
+
+    /// Execute the stage.
+    async fn execute(
+        &mut self,
+        tx: &mut Transaction<'_, DB>,
+        input: ExecInput,
+    ) -> Result<ExecOutput, StageError> {
+        let tip = input.previous_stage_progress();
+        let stage_progress = input.stage_progress.unwrap_or_default();
+        if stage_progress > 0 {
+            return Ok(ExecOutput { stage_progress: tip, done: true })
+        }
+
+        let mut checkpoint = match get_snap_checkpoint(tx)? {
+            Some(checkpoint) => checkpoint,
+            None => {
+                let pivot = tip.saturating_sub(self.pivot_distance);
+                if pivot == 0 || EXECUTION.get_progress(tx.deref())?.unwrap_or_default() > 0 {
+                    info!(target: "sync::stages::snap_state", tip, "Skipping snap sync");
+                    return Ok(ExecOutput { stage_progress: tip, done: true })
+                }
+
+                info!(target: "sync::stages::snap_state", pivot, "Starting snap sync");
+                let checkpoint = SnapSyncCheckpoint {
+                    pivot,
+                    state_root: tx.get_header(pivot)?.state_root,
+                    next_account: Some(H256::zero()),
+                };
+                save_snap_checkpoint(tx, checkpoint.clone())?;
+                checkpoint
+            }
+        };
+
+        let mut downloaded = 0;
+        while let Some(start) = checkpoint.next_account {
+            match self.download_range(tx, &checkpoint, start).await {
+                Ok((accounts, next_account)) => {
+                    downloaded += accounts as u64;
+                    checkpoint.next_account = next_account;
+                    save_snap_checkpoint(tx, checkpoint.clone())?;
+                }
+                Err(SnapSyncError::StateUnavailable) => {
+                    if !move_pivot(tx, &mut checkpoint, tip, self.pivot_distance)? {
+                        warn!(target: "sync::stages::snap_state", pivot = checkpoint.pivot, "State of the pivot block is not available, waiting for the tip to move");
+                        return Ok(ExecOutput { stage_progress, done: true })
+                    }
+                    continue
+                }
+                Err(error) => return Err(error.into()),
+            }
+
+            if checkpoint.next_account.is_some() && downloaded >= self.commit_threshold {
+                info!(target: "sync::stages::snap_state", next_account = ?checkpoint.next_account, downloaded, "Saving snap sync checkpoint");
+                return Ok(ExecOutput { stage_progress, done: false })
+            }
+        }
+
+        info!(target: "sync::stages::snap_state", pivot = checkpoint.pivot, "Stage finished");
+        Ok(ExecOutput { stage_progress: tip, done: true })
+    }
+
+    /// Unwind the stage.
+    async fn unwind(
+        &mut self,
+        tx: &mut Transaction<'_, DB>,
+        input: UnwindInput,
+    ) -> Result<UnwindOutput, StageError> {
+        info!(target: "sync::stages::snap_state", to_block = input.unwind_to, "Unwinding");
+        if !unwinds_below_pivot(tx, input.unwind_to)? {
+            return Ok(UnwindOutput { stage_progress: input.unwind_to })
+        }
+
+        warn!(target: "sync::stages::snap_state", to_block = input.unwind_to, "Unwinding below the pivot block, removing the downloaded state");
+        tx.clear::<tables::HashedAccount>()?;
+        tx.clear::<tables::HashedStorage>()?;
+        tx.clear::<tables::AccountsTrie>()?;
+        tx.clear::<tables::StoragesTrie>()?;
+        // The plain state only holds the accounts changed after the pivot block.
+        tx.clear::<tables::PlainAccountState>()?;
+        tx.clear::<tables::PlainStorageState>()?;
+        tx.clear::<tables::Bytecodes>()?;
+        tx.clear::<tables::BytecodeRefCounts>()?;
+        reset_snap_sync(tx)?;
+        SNAP_STATE.clear_checkpoint(tx.deref())?;
+        Ok(UnwindOutput { stage_progress: 0 })
+    }
+}
+
+/// Returns whether the state of the node was downloaded with a snap sync.
+///
+/// The plain state of such a node is incomplete, so the state has to be read from the hashed
+/// state tables instead, which the execution stage keeps up to date.
+///
+/// The state providers of the database check for the same checkpoint.
+pub fn is_snap_synced<'db>(tx: &impl DbTx<'db>) -> Result<bool, DbError> {
+    Ok(SNAP_STATE.get_checkpoint(tx)?.is_some())
+}
+
+/// Returns whether unwinding to the given block removes the pivot block of the snap sync.
+pub(crate) fn unwinds_below_pivot<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    unwind_to: BlockNumber,
+) -> Result<bool, StageError> {
+    Ok(get_snap_checkpoint(tx)?.map_or(false, |checkpoint| unwind_to < checkpoint.pivot))
+}
+
+/// Gets the checkpoint of the snap sync, if it was started.
+pub(crate) fn get_snap_checkpoint<DB: Database>(
+    tx: &Transaction<'_, DB>,
+) -> Result<Option<SnapSyncCheckpoint>, StageError> {
+    let checkpoint = SNAP_STATE.get_checkpoint(tx.deref())?;
+    Ok(checkpoint.map(|buf| SnapSyncCheckpoint::from_compact(&buf, buf.len()).0))
+}
+
+/// Saves the checkpoint of the snap sync.
+pub(crate) fn save_snap_checkpoint<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    checkpoint: SnapSyncCheckpoint,
+) -> Result<(), StageError> {
+    let mut buf = vec![];
+    checkpoint.to_compact(&mut buf);
+    Ok(SNAP_STATE.save_checkpoint(tx.deref(), buf)?)
+}
+
+/// Moves the pivot of the snap sync to the given distance from the tip, returns `false` if it
+/// would not move forward.
+pub(crate) fn move_pivot<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    checkpoint: &mut SnapSyncCheckpoint,
+    tip: BlockNumber,
+    pivot_distance: u64,
+) -> Result<bool, StageError> {
+    let pivot = tip.saturating_sub(pivot_distance);
+    if pivot <= checkpoint.pivot {
+        return Ok(false)
+    }
+
+    info!(target: "sync::stages::snap", from = checkpoint.pivot, to = pivot, "Moving pivot block");
+    checkpoint.pivot = pivot;
+    checkpoint.state_root = tx.get_header(pivot)?.state_root;
+    save_snap_checkpoint(tx, checkpoint.clone())?;
+    Ok(true)
+}
+
+/// Sends a request until a peer answers it with a response that passes `verify`, at most
+/// [MAX_ATTEMPTS] times. Peers that send invalid responses are reported.
+///
+/// Returns [SnapSyncError::StateUnavailable] if any of the peers did not serve the state.
+pub(crate) async fn fetch<C: SnapClient, T, R>(
+    client: &C,
+    mut send: impl FnMut() -> SnapFut<T>,
+    mut verify: impl FnMut(T) -> Result<R, SnapSyncError>,
+) -> Result<R, SnapSyncError> {
+    let mut unavailable = false;
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        let error = match send().await {
+            Ok(response) => {
+                let peer_id = response.peer_id();
+                match verify(response.into_data()) {
+                    Ok(result) => return Ok(result),
+                    Err(error) => {
+                        if error.is_bad_response() {
+                            client.report_bad_message(peer_id);
+                        }
+                        error
+                    }
+                }
+            }
+            Err(error) => error.into(),
+        };
+        unavailable |= matches!(error, SnapSyncError::StateUnavailable);
+        debug!(target: "sync::stages::snap", attempt, %error, "Snap request failed");
+        if attempt == MAX_ATTEMPTS {
+            return Err(if unavailable { SnapSyncError::StateUnavailable } else { error })
+        }
+    }
+}
+
+/// Downloads the storage of the accounts with the given hashed addresses and storage roots, and
+/// adds it to the hashed state.
+pub(crate) async fn download_storage<C: SnapClient, DB: Database>(
+    client: &C,
+    tx: &Transaction<'_, DB>,
+    root: H256,
+    mut accounts: &[(H256, H256)],
+) -> Result<(), SnapSyncError> {
+    while !accounts.is_empty() {
+        let batch = &accounts[..accounts.len().min(MAX_STORAGE_ACCOUNTS)];
+        let account_hashes =
+            batch.iter().map(|(hashed_address, _)| *hashed_address).collect::<Vec<_>>();
+        let (ranges, mut more) = fetch(
+            client,
+            || {
+                client.get_storage_ranges(GetStorageRanges {
+                    request_id: 0,
+                    root_hash: root,
+                    account_hashes: account_hashes.clone(),
+                    starting_hash: Bytes::default(),
+                    limit_hash: Bytes::default(),
+                    response_bytes: RESPONSE_BYTES,
+                })
+            },
+            |response| verify_storage_ranges(batch, H256::zero(), response),
+        )
+        .await?;
+
+        for ((hashed_address, _), slots) in batch.iter().zip(&ranges) {
+            write_storage(tx, *hashed_address, slots)?;
+        }
+
+        // The storage of the last account was cut off, request the rest of it on its own.
+        let (hashed_address, storage_root) = batch[ranges.len() - 1];
+        let mut last_slot = ranges.last().and_then(|slots| slots.last()).map(|(key, _)| *key);
+        while more {
+            let Some(start) = last_slot.and_then(next_key) else { break };
+            let (range, range_more) = fetch(
+                client,
+                || {
+                    client.get_storage_ranges(GetStorageRanges {
+                        request_id: 0,
+                        root_hash: root,
+                        account_hashes: vec![hashed_address],
+                        starting_hash: start.as_bytes().to_vec().into(),
+                        limit_hash: Bytes::default(),
+                        response_bytes: RESPONSE_BYTES,
+                    })
+                },
+                |response| {
+                    verify_storage_ranges(&[(hashed_address, storage_root)], start, response)
+                },
+            )
+            .await?;
+            let slots = range.into_iter().next().unwrap_or_default();
+            write_storage(tx, hashed_address, &slots)?;
+            last_slot = slots.last().map(|(key, _)| *key);
+            more = range_more;
+        }
+
+        accounts = &accounts[ranges.len()..];
+    }
+    Ok(())
+}
+
+/// Verifies the storage ranges of the given accounts, the first of which starts at `start`.
+///
+/// Returns the slots of the served accounts and whether the storage of the last one was cut off.
+fn verify_storage_ranges(
+    accounts: &[(H256, H256)],
+    start: H256,
+    response: StorageRanges,
+) -> Result<(Vec<Vec<(H256, U256)>>, bool), SnapSyncError> {
+    if response.slots.is_empty() {
+        return Err(SnapSyncError::StateUnavailable)
+    }
+    if response.slots.len() > accounts.len() {
+        return Err(SnapSyncError::InvalidResponse("more storage ranges than requested"))
+    }
+
+    let served = response.slots.len();
+    let mut more = false;
+    let mut ranges = Vec::with_capacity(served);
+    for (index, ((_, storage_root), slots)) in accounts.iter().zip(response.slots).enumerate() {
+        let leaves = slots.iter().map(|slot| (slot.hash, slot.data.to_vec())).collect::<Vec<_>>();
+        // Only the last range may be cut off and carry a proof.
+        let start = if index == 0 { start } else { H256::zero() };
+        let proof = if index + 1 == served { &response.proof[..] } else { &[] };
+        more = verify_range_proof(*storage_root, start, &leaves, proof)?;
+
+        let slots = slots
+            .into_iter()
+            .map(|slot| {
+                let value = U256::decode(&mut &slot.data[..])
+                    .map_err(|_| SnapSyncError::InvalidResponse("invalid storage value"))?;
+                Ok((slot.hash, value))
+            })
+            .collect::<Result<Vec<_>, SnapSyncError>>()?;
+        ranges.push(slots);
+    }
+    Ok((ranges, more))
+}
+
+/// Adds the slots to the hashed storage of the account.
+fn write_storage<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    hashed_address: H256,
+    slots: &[(H256, U256)],
+) -> Result<(), DbError> {
+    let mut cursor = tx.cursor_dup_write::<tables::HashedStorage>()?;
+    for (key, value) in slots {
+        if let Some(entry) = cursor.seek_by_key_subkey(hashed_address, *key)? {
+            if entry.key == *key {
+                cursor.delete_current()?;
+            }
+        }
+        if *value != U256::ZERO {
+            cursor.upsert(hashed_address, StorageEntry { key: *key, value: *value })?;
+        }
+    }
+    Ok(())
+}
+
+/// Downloads the bytecodes with the given hashes that are not stored yet.
+pub(crate) async fn download_bytecodes<C: SnapClient, DB: Database>(
+    client: &C,
+    tx: &Transaction<'_, DB>,
+    code_hashes: HashSet<H256>,
+) -> Result<(), SnapSyncError> {
+    let mut missing = Vec::new();
+    for hash in code_hashes {
+        if tx.get::<tables::Bytecodes>(hash)?.is_none() {
+            missing.push(hash);
+        }
+    }
+
+    while !missing.is_empty() {
+        let batch = missing.iter().take(MAX_BYTECODES).copied().collect::<HashSet<_>>();
+        let codes = fetch(
+            client,
+            || {
+                client.get_byte_codes(GetByteCodes {
+                    request_id: 0,
+                    hashes: batch.iter().copied().collect(),
+                    response_bytes: RESPONSE_BYTES,
+                })
+            },
+            |response| {
+                if response.codes.is_empty() {
+                    return Err(SnapSyncError::StateUnavailable)
+                }
+                response
+                    .codes
+                    .into_iter()
+                    .map(|code| {
+                        let hash = keccak256(&code);
+                        if batch.contains(&hash) {
+                            Ok((hash, code))
+                        } else {
+                            Err(SnapSyncError::InvalidResponse("unrequested bytecode"))
+                        }
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+            },
+        )
+        .await?;
+
+        for (hash, code) in codes {
+            tx.put::<tables::Bytecodes>(hash, Bytecode::new_raw(code.0))?;
+            missing.retain(|missing| *missing != hash);
+        }
+    }
+    Ok(())
+}
+
+/// Deletes the hashed accounts from `from` to `to` with their storage and the nodes of their
+/// storage tries, and releases the references to their bytecodes.
+pub(crate) fn delete_hashed_accounts<'a, TX: DbTxMut<'a> + DbTx<'a>>(
+    tx: &TX,
+    from: H256,
+    to: H256,
+    block: BlockNumber,
+) -> Result<(), DbError> {
+    let mut cursor = tx.cursor_write::<tables::HashedAccount>()?;
+    while let Some((hashed_address, account)) = cursor.seek(from)?.filter(|(key, _)| *key <= to) {
+        update_bytecode_references(tx, Some(&account), None, block)?;
+        tx.delete::<tables::HashedStorage>(hashed_address, None)?;
+        tx.delete::<tables::StoragesTrie>(hashed_address, None)?;
+        cursor.delete_current()?;
+    }
+    Ok(())
+}
+
+/// Deletes the hashed storage slots of the account from `from` to `to`.
+pub(crate) fn delete_hashed_storage<'a, TX: DbTxMut<'a> + DbTx<'a>>(
+    tx: &TX,
+    hashed_address: H256,
+    from: H256,
+    to: H256,
+) -> Result<(), DbError> {
+    let mut cursor = tx.cursor_dup_write::<tables::HashedStorage>()?;
+    while cursor.seek_by_key_subkey(hashed_address, from)?.filter(|entry| entry.key <= to).is_some()
+    {
+        cursor.delete_current()?;
+    }
+    Ok(())
+}
+
+/// Returns the trie representation of an account of a `snap` response.
+fn trie_account(account: &SlimAccount) -> EthAccount {
+    EthAccount::from_with_root(
+        Account {
+            nonce: account.nonce,
+            balance: account.balance,
+            bytecode_hash: Some(account.code_hash()),
+        },
+        account.storage_root(),
+    )
+}
+
+/// Returns the first and the last key starting with the given path.
+pub(crate) fn key_range(path: &Nibbles) -> (H256, H256) {
+    let bound = |nibble: u8| {
+        let mut key = path.clone();
+        key.extend(&vec![nibble; 64usize.saturating_sub(path.len())]);
+        H256::from_slice(&key.pack())
+    };
+    (bound(0), bound(0xf))
+}
+
+/// Returns the key after the given one, or `None` if it is the last key.
+pub(crate) fn next_key(key: H256) -> Option<H256> {
+    U256::from_be_bytes(key.0).checked_add(U256::from(1)).map(|key| H256(key.to_be_bytes()))
+}
+
+/// Returns the key before the given one, or `None` if it is the first key.
+pub(crate) fn previous_key(key: H256) -> Option<H256> {
+    U256::from_be_bytes(key.0).checked_sub(U256::from(1)).map(|key| H256(key.to_be_bytes()))
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::test_utils::{TestSnapClient, TestTransaction, PREV_STAGE_ID};
+    use assert_matches::assert_matches;
+    use reth_primitives::{bytes, Address, Header};
+    use reth_provider::trie::DBTrieLoader;
+
+    /// Creates a node with accounts, storage and bytecodes whose state is served to the snap
+    /// sync, returns it with its state root.
+    pub(crate) fn serving_node() -> (TestTransaction, H256) {
+        let node = TestTransaction::default();
+        let code = Bytecode::new_raw(bytes::Bytes::from_static(&[0x60, 0x00, 0x60, 0x00, 0xf3]));
+        let code_hash = keccak256(code.original_bytes());
+
+        node.insert_accounts_and_storages((0..100u64).map(|i| {
+            let account = Account {
+                nonce: i,
+                balance: U256::from(i),
+                bytecode_hash: (i % 10 == 0).then_some(code_hash),
+            };
+            let storage = (0..i % 7 * 10)
+                .map(|slot| StorageEntry {
+                    key: H256::from_low_u64_be(slot),
+                    value: U256::from(slot + 1),
+                })
+                .collect::<Vec<_>>();
+            (Address::from_low_u64_be(i), (account, storage))
+        }))
+        .unwrap();
+
+        let root = {
+            let mut tx = node.inner();
+            tx.put::<tables::Bytecodes>(code_hash, code).unwrap();
+            let root = DBTrieLoader::default().calculate_root(&tx).unwrap();
+            tx.commit().unwrap();
+            root
+        };
+        (node, root)
+    }
+
+    /// Inserts the headers up to the tip, the header of the pivot block with the given state root.
+    pub(crate) fn insert_headers(
+        tx: &TestTransaction,
+        tip: BlockNumber,
+        pivot: BlockNumber,
+        state_root: H256,
+    ) {
+        let headers = (0..=tip)
+            .map(|number| {
+                let state_root = if number == pivot { state_root } else { H256::zero() };
+                Header { number, state_root, ..Default::default() }.seal_slow()
+            })
+            .collect::<Vec<_>>();
+        tx.insert_headers(headers.iter()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn downloads_pivot_state() {
+        let (node, root) = serving_node();
+        let tx = TestTransaction::default();
+        insert_headers(&tx, 100, 36, root);
+
+        let client = TestSnapClient::new(node.inner_raw());
+        let mut stage = SnapStateStage::new(client.clone());
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, 100)), stage_progress: None };
+        let mut db = tx.inner();
+        let output = stage.execute(&mut db, input).await;
+        assert_matches!(output, Ok(ExecOutput { stage_progress: 100, done: true }));
+        assert!(is_snap_synced(db.deref()).unwrap());
+        assert_eq!(
+            get_snap_checkpoint(&db).unwrap(),
+            Some(SnapSyncCheckpoint { pivot: 36, state_root: root, next_account: None })
+        );
+        db.commit().unwrap();
+        drop(db);
+
+        assert_eq!(client.bad_messages(), 0);
+        assert_eq!(
+            tx.table::<tables::HashedAccount>().unwrap(),
+            node.table::<tables::HashedAccount>().unwrap()
+        );
+        assert_eq!(
+            tx.table::<tables::HashedStorage>().unwrap(),
+            node.table::<tables::HashedStorage>().unwrap()
+        );
+        assert_eq!(
+            tx.table::<tables::Bytecodes>().unwrap(),
+            node.table::<tables::Bytecodes>().unwrap()
+        );
+        assert!(tx.table_is_empty::<tables::PlainAccountState>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn unwind_below_pivot_starts_over() {
+        let (node, root) = serving_node();
+        let tx = TestTransaction::default();
+        insert_headers(&tx, 100, 36, root);
+
+        let mut stage = SnapStateStage::new(TestSnapClient::new(node.inner_raw()));
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, 100)), stage_progress: None };
+        let mut db = tx.inner();
+        stage.execute(&mut db, input).await.unwrap();
+
+        // the state of the pivot block is kept
+        let input = UnwindInput { stage_progress: 100, unwind_to: 36, bad_block: None };
+        let output = stage.unwind(&mut db, input).await;
+        assert_matches!(output, Ok(UnwindOutput { stage_progress: 36 }));
+        assert!(is_snap_synced(db.deref()).unwrap());
+
+        EXECUTION.save_progress(db.deref(), 36).unwrap();
+        let input = UnwindInput { stage_progress: 36, unwind_to: 35, bad_block: None };
+        let output = stage.unwind(&mut db, input).await;
+        assert_matches!(output, Ok(UnwindOutput { stage_progress: 0 }));
+        assert!(!is_snap_synced(db.deref()).unwrap());
+        assert_eq!(EXECUTION.get_progress(db.deref()).unwrap(), Some(0));
+        db.commit().unwrap();
+        drop(db);
+
+        assert!(tx.table_is_empty::<tables::HashedAccount>().unwrap());
+        assert!(tx.table_is_empty::<tables::HashedStorage>().unwrap());
+        assert!(tx.table_is_empty::<tables::Bytecodes>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn waits_for_unavailable_state() {
+        let (node, _) = serving_node();
+        let tx = TestTransaction::default();
+        insert_headers(&tx, 100, 36, H256::repeat_byte(0x01));
+
+        let client = TestSnapClient::new(node.inner_raw());
+        let mut stage = SnapStateStage::new(client.clone());
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, 100)), stage_progress: None };
+        let mut db = tx.inner();
+        let output = stage.execute(&mut db, input).await;
+        assert_matches!(output, Ok(ExecOutput { stage_progress: 0, done: true }));
+        assert_matches!(
+            get_snap_checkpoint(&db).unwrap(),
+            Some(SnapSyncCheckpoint { pivot: 36, next_account: Some(_), .. })
+        );
+        assert_eq!(client.bad_messages(), 0);
+        drop(db);
+
+        assert!(tx.table_is_empty::<tables::HashedAccount>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn skips_short_chain() {
+        let (node, root) = serving_node();
+        let tx = TestTransaction::default();
+        insert_headers(&tx, 50, 0, root);
+
+        let mut stage = SnapStateStage::new(TestSnapClient::new(node.inner_raw()));
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, 50)), stage_progress: None };
+        let mut db = tx.inner();
+        let output = stage.execute(&mut db, input).await;
+        assert_matches!(output, Ok(ExecOutput { stage_progress: 50, done: true }));
+        assert!(!is_snap_synced(db.deref()).unwrap());
+    }
+
+    #[test]
+    fn key_bounds() {
+        let mut first = H256::zero();
+        first.0[0] = 0x12;
+        let mut last = H256::repeat_byte(0xff);
+        last.0[0] = 0x12;
+        assert_eq!(key_range(&Nibbles::unpack([0x12])), (first, last));
+        assert_eq!(key_range(&Nibbles::default()), (H256::zero(), H256::repeat_byte(0xff)));
+
+        assert_eq!(next_key(H256::from_low_u64_be(0xff)), Some(H256::from_low_u64_be(0x100)));
+        assert_eq!(next_key(H256::repeat_byte(0xff)), None);
+        assert_eq!(previous_key(H256::from_low_u64_be(0x100)), Some(H256::from_low_u64_be(0xff)));
+        assert_eq!(previous_key(H256::zero()), None);
+    }
+}
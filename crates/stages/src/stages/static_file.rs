@@ -0,0 +1,343 @@
+use crate::{ExecInput, ExecOutput, Stage, StageError, StageId, UnwindInput, UnwindOutput};
+use reth_codecs::Compact;
+use reth_db::{
+    cursor::{DbCursorRO, DbCursorRW},
+    database::Database,
+    table::Table,
+    tables,
+    transaction::{DbTx, DbTxMut},
+};
+use reth_interfaces::provider::ProviderError;
+use reth_primitives::{BlockNumber, TxNumber};
+use reth_provider::Transaction;
+use reth_static_files::{StaticFileSegment, StaticFileWriter, StaticFiles};
+use std::{
+    ops::{Range, RangeInclusive},
+    sync::Arc,
+};
+use tracing::*;
+
+/// The [`StageId`] of the static file stage.
+pub const STATIC_FILES: StageId = StageId("StaticFiles");
+
+/// Blocks within this distance of the tip are never moved to static files. They can still be
+/// unwound, and the EVM reads the hashes of the last 256 blocks from the database.
+pub const STATIC_FILE_DISTANCE: u64 = 256;
+
+/// The static file stage.
+///
+/// This stage moves the ancient headers, transactions and receipts out of the database into
+/// [StaticFiles], which the providers read transparently. Each [StaticFileSegment] is moved in
+/// files of `blocks_per_file` blocks, and only complete files of blocks that are at least
+/// [STATIC_FILE_DISTANCE] blocks behind the tip are written.
+///
+/// The stage writes at most one static file before committing. The file is completed before its
+/// rows are deleted from the database, so an interrupted run leaves a stale copy of the rows in the
+/// database. Every run first deletes the rows of all blocks that are already in the static files.
+///
+/// The hash of the genesis block stays in [tables::CanonicalHeaders], it identifies the chain of
+/// the database on startup.
+#[derive(Debug, Clone)]
+pub struct StaticFileStage {
+    /// The static files to write to.
+    static_files: Arc<StaticFiles>,
+    /// The number of blocks in each static file.
+    blocks_per_file: u64,
+}
+
+impl StaticFileStage {
+    /// Create new instance of [StaticFileStage].
+    pub fn new(static_files: Arc<StaticFiles>, blocks_per_file: u64) -> Self {
+        Self { static_files, blocks_per_file: blocks_per_file.max(1) }
+    }
+
+    /// Delete the rows of the blocks that are already in the static files from the database.
+    ///
+    /// The rows are only left over if the transaction deleting them was not committed after the
+    /// static file was, otherwise this does not find any rows.
+    fn remove_stale_rows<DB: Database>(&self, tx: &Transaction<'_, DB>) -> Result<(), StageError> {
+        if let Some(highest) = self.static_files.highest_block(StaticFileSegment::Headers) {
+            delete_rows::<_, tables::CanonicalHeaders>(tx, 1..highest + 1)?;
+            delete_rows::<_, tables::Headers>(tx, 0..highest + 1)?;
+        }
+        delete_rows::<_, tables::Transactions>(
+            tx,
+            0..self.static_files.next_key(StaticFileSegment::Transactions),
+        )?;
+        delete_rows::<_, tables::Receipts>(
+            tx,
+            0..self.static_files.next_key(StaticFileSegment::Receipts),
+        )?;
+        Ok(())
+    }
+
+    /// Write the static file of a segment for the given blocks and delete its rows from the
+    /// database.
+    fn move_to_static_file<DB: Database>(
+        &self,
+        tx: &Transaction<'_, DB>,
+        segment: StaticFileSegment,
+        blocks: RangeInclusive<BlockNumber>,
+    ) -> Result<(), StageError> {
+        match segment {
+            StaticFileSegment::Headers => {
+                let mut writer =
+                    self.static_files.writer(segment, blocks.clone(), *blocks.start())?;
+                for number in blocks.clone() {
+                    let hash = tx
+                        .get::<tables::CanonicalHeaders>(number)?
+                        .ok_or(ProviderError::CanonicalHeader { block_number: number })?;
+                    let header = tx
+                        .get::<tables::Headers>(number)?
+                        .ok_or(ProviderError::Header { number })?;
+                    writer.append_header(header, hash)?;
+                }
+                self.static_files.commit(writer)?;
+
+                for number in blocks {
+                    if number != 0 {
+                        tx.delete::<tables::CanonicalHeaders>(number, None)?;
+                    }
+                    tx.delete::<tables::Headers>(number, None)?;
+                }
+            }
+            StaticFileSegment::Transactions => {
+                let txs = transaction_range(tx, &blocks)?;
+                let writer = self.static_files.writer(segment, blocks, txs.start)?;
+                self.move_by_tx_number::<_, tables::Transactions>(tx, writer, txs)?;
+            }
+            StaticFileSegment::Receipts => {
+                let txs = transaction_range(tx, &blocks)?;
+                let writer = self.static_files.writer(segment, blocks, txs.start)?;
+                self.move_by_tx_number::<_, tables::Receipts>(tx, writer, txs)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Write the rows of the given transactions from a table keyed by transaction number to a
+    /// static file and delete them from the table. Transactions without a row, e.g. because their
+    /// receipts were pruned, get an empty row.
+    fn move_by_tx_number<DB, T>(
+        &self,
+        tx: &Transaction<'_, DB>,
+        mut writer: StaticFileWriter,
+        txs: Range<TxNumber>,
+    ) -> Result<(), StageError>
+    where
+        DB: Database,
+        T: Table<Key = TxNumber>,
+        T::Value: Compact,
+    {
+        for tx_number in txs.clone() {
+            match tx.get::<T>(tx_number)? {
+                Some(value) => writer.append_value(value)?,
+                None => writer.append_empty()?,
+            }
+        }
+        self.static_files.commit(writer)?;
+
+        for tx_number in txs {
+            tx.delete::<T>(tx_number, None)?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait::async_trait]
+impl<DB: Database> Stage<DB> for StaticFileStage {
+    /// Return the id of the stage
+    fn id(&self) -> StageId {
+        STATIC_FILES
+    }
+
+    /// Move the next complete static file of ancient blocks out of the database.
+    async fn execute(
+        &mut self,
+        tx: &mut Transaction<'_, DB>,
+        input: ExecInput,
+    ) -> Result<ExecOutput, StageError> {
+        let tip = input.previous_stage_progress();
+        self.remove_stale_rows(tx)?;
+
+        if let Some(highest) = tip.checked_sub(STATIC_FILE_DISTANCE) {
+            for segment in StaticFileSegment::ALL {
+                let start = self.static_files.highest_block(segment).map_or(0, |block| block + 1);
+                let end = start + self.blocks_per_file - 1;
+                if end > highest {
+                    continue
+                }
+
+                self.move_to_static_file(tx, segment, start..=end)?;
+                info!(target: "sync::stages::static_file", %segment, start, end, "Moved blocks to static file");
+
+                return Ok(ExecOutput {
+                    stage_progress: input.stage_progress.unwrap_or_default(),
+                    done: false,
+                })
+            }
+        }
+
+        info!(target: "sync::stages::static_file", stage_progress = tip, "Sync iteration finished");
+        Ok(ExecOutput { stage_progress: tip, done: true })
+    }
+
+    /// Unwind the stage.
+    async fn unwind(
+        &mut self,
+        _tx: &mut Transaction<'_, DB>,
+        input: UnwindInput,
+    ) -> Result<UnwindOutput, StageError> {
+        // Static files are immutable. The blocks being unwound are within the static file
+        // distance, so they were never moved.
+        info!(target: "sync::stages::static_file", to_block = input.unwind_to, "Unwinding");
+        Ok(UnwindOutput { stage_progress: input.unwind_to })
+    }
+}
+
+/// Deletes the rows of a table with keys in the given range.
+fn delete_rows<DB: Database, T: Table<Key = u64>>(
+    tx: &Transaction<'_, DB>,
+    keys: Range<u64>,
+) -> Result<(), StageError> {
+    let mut cursor = tx.cursor_write::<T>()?;
+    while cursor.seek(keys.start)?.filter(|(key, _)| *key < keys.end).is_some() {
+        cursor.delete_current()?;
+    }
+    Ok(())
+}
+
+/// Returns the numbers of the transactions in the given blocks.
+fn transaction_range<DB: Database>(
+    tx: &Transaction<'_, DB>,
+    blocks: &RangeInclusive<BlockNumber>,
+) -> Result<Range<TxNumber>, StageError> {
+    let first = tx
+        .get::<tables::BlockBodies>(*blocks.start())?
+        .ok_or(ProviderError::BlockBody { number: *blocks.start() })?;
+    let last = tx
+        .get::<tables::BlockBodies>(*blocks.end())?
+        .ok_or(ProviderError::BlockBody { number: *blocks.end() })?;
+    Ok(first.start_tx_id..last.start_tx_id + last.tx_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{TestTransaction, PREV_STAGE_ID};
+    use reth_interfaces::test_utils::generators::random_block_range;
+    use reth_primitives::{Receipt, H256};
+    use reth_static_files::Compression;
+
+    #[tokio::test]
+    async fn move_complete_files() {
+        let test_tx = TestTransaction::default();
+        let tip = 600;
+        let blocks = random_block_range(0..tip + 1, H256::zero(), 0..3);
+        test_tx.insert_blocks(blocks.iter(), None).unwrap();
+        // Only the receipts of every second transaction are kept.
+        test_tx
+            .commit(|tx| {
+                for tx_number in
+                    (0..blocks.iter().map(|block| block.body.len() as u64).sum()).step_by(2)
+                {
+                    let receipt = Receipt { cumulative_gas_used: tx_number, ..Default::default() };
+                    tx.put::<tables::Receipts>(tx_number, receipt)?;
+                }
+                Ok(())
+            })
+            .unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let static_files = Arc::new(StaticFiles::open(dir.path(), Compression::Snappy).unwrap());
+        let mut stage = StaticFileStage::new(static_files.clone(), 100);
+
+        let mut stage_progress = None;
+        loop {
+            let mut tx = test_tx.inner();
+            let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, tip)), stage_progress };
+            let output = stage.execute(&mut tx, input).await.unwrap();
+            tx.commit().unwrap();
+
+            stage_progress = Some(output.stage_progress);
+            if output.done {
+                assert_eq!(output.stage_progress, tip);
+                break
+            }
+        }
+
+        // Blocks up to 344 are at the static file distance, so only the files up to 299 are
+        // complete.
+        for segment in StaticFileSegment::ALL {
+            assert_eq!(static_files.highest_block(segment), Some(299));
+        }
+
+        let tx = test_tx.inner();
+        let mut tx_number = 0;
+        for block in &blocks {
+            let frozen = block.number < 300;
+            assert_eq!(tx.get::<tables::Headers>(block.number).unwrap().is_some(), !frozen);
+            assert_eq!(
+                tx.get::<tables::CanonicalHeaders>(block.number).unwrap().is_some(),
+                !frozen || block.number == 0
+            );
+            if frozen {
+                assert_eq!(
+                    static_files.header_with_hash(block.number).unwrap(),
+                    Some((block.header.clone().unseal(), block.hash()))
+                );
+            }
+
+            for transaction in &block.body {
+                assert_eq!(tx.get::<tables::Transactions>(tx_number).unwrap().is_some(), !frozen);
+                assert_eq!(
+                    tx.get::<tables::Receipts>(tx_number).unwrap().is_some(),
+                    !frozen && tx_number % 2 == 0
+                );
+                if frozen {
+                    assert_eq!(
+                        static_files.transaction(tx_number).unwrap().as_ref(),
+                        Some(transaction)
+                    );
+                    assert_eq!(
+                        static_files.receipt(tx_number).unwrap().map(|r| r.cumulative_gas_used),
+                        (tx_number % 2 == 0).then_some(tx_number)
+                    );
+                }
+                tx_number += 1;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn remove_stale_rows() {
+        let test_tx = TestTransaction::default();
+        let tip = 400;
+        let blocks = random_block_range(0..tip + 1, H256::zero(), 0..3);
+        test_tx.insert_blocks(blocks.iter(), None).unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let static_files = Arc::new(StaticFiles::open(dir.path(), Compression::Snappy).unwrap());
+        let mut stage = StaticFileStage::new(static_files.clone(), 100);
+        let input = ExecInput { previous_stage: Some((PREV_STAGE_ID, tip)), stage_progress: None };
+
+        // The static file of the headers is written, but the deletion of its rows is not
+        // committed.
+        let mut tx = test_tx.inner();
+        stage.execute(&mut tx, input).await.unwrap();
+        drop(tx);
+        assert_eq!(static_files.highest_block(StaticFileSegment::Headers), Some(99));
+        assert!(test_tx.inner().get::<tables::Headers>(50).unwrap().is_some());
+
+        let mut tx = test_tx.inner();
+        stage.execute(&mut tx, input).await.unwrap();
+        tx.commit().unwrap();
+
+        let tx = test_tx.inner();
+        for number in 0..100 {
+            assert_eq!(tx.get::<tables::Headers>(number).unwrap(), None);
+            assert_eq!(tx.get::<tables::CanonicalHeaders>(number).unwrap().is_some(), number == 0);
+        }
+        assert!(tx.get::<tables::Headers>(100).unwrap().is_some());
+    }
+}
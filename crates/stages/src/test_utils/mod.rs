@@ -3,12 +3,14 @@ use crate::StageId;
 
 mod macros;
 mod runner;
+mod snap;
 mod test_db;
 
 pub(crate) use macros::*;
 pub(crate) use runner::{
     ExecuteStageTestRunner, StageTestRunner, TestRunnerError, UnwindStageTestRunner,
 };
+pub use snap::TestSnapClient;
 pub use test_db::TestTransaction;
 
 /// The previous test stage id mock used for testing
@@ -0,0 +1,171 @@
+use futures_util::future;
+use reth_db::{
+    database::Database,
+    mdbx::{Env, WriteMap},
+    tables,
+    transaction::DbTx,
+};
+use reth_eth_wire::snap::{
+    AccountData, AccountRange, ByteCodes, GetAccountRange, GetByteCodes, GetStorageRanges,
+    GetTrieNodes, SlimAccount, StorageData, StorageRanges, TrieNodes,
+};
+use reth_interfaces::p2p::{
+    download::DownloadClient,
+    snap::client::{SnapClient, SnapFut},
+};
+use reth_primitives::{
+    trie::Nibbles, Bytes, ChainSpecBuilder, PeerId, WithPeerId, H256, KECCAK_EMPTY,
+};
+use reth_provider::{ShareableDatabase, StateRangeProvider};
+use reth_rlp::Encodable;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+
+/// A [SnapClient] that serves the state of a database like a peer with the `snap` protocol.
+///
+/// Like a peer, the client answers requests for a state root other than the root of the stored
+/// state trie with empty responses.
+#[derive(Debug, Clone)]
+pub struct TestSnapClient {
+    /// The database holding the served state.
+    db: Arc<Env<WriteMap>>,
+    /// The provider of the served state.
+    provider: Arc<ShareableDatabase<Arc<Env<WriteMap>>>>,
+    /// The number of responses reported as bad.
+    bad_messages: Arc<AtomicUsize>,
+}
+
+impl TestSnapClient {
+    /// Create a new client serving the state of the database.
+    pub fn new(db: Arc<Env<WriteMap>>) -> Self {
+        Self {
+            provider: Arc::new(ShareableDatabase::new(
+                db.clone(),
+                ChainSpecBuilder::mainnet().build(),
+            )),
+            db,
+            bad_messages: Default::default(),
+        }
+    }
+
+    /// Returns the number of responses reported as bad.
+    pub fn bad_messages(&self) -> usize {
+        self.bad_messages.load(Ordering::Relaxed)
+    }
+
+    /// Returns a future that resolves to the response.
+    fn respond<T: Send + Sync + 'static>(response: T) -> SnapFut<T> {
+        Box::pin(future::ready(Ok(WithPeerId::from((PeerId::zero(), response)))))
+    }
+}
+
+impl DownloadClient for TestSnapClient {
+    fn report_bad_message(&self, _peer_id: PeerId) {
+        self.bad_messages.fetch_add(1, Ordering::Relaxed);
+    }
+
+    fn num_connected_peers(&self) -> usize {
+        1
+    }
+}
+
+impl SnapClient for TestSnapClient {
+    fn get_account_range(&self, request: GetAccountRange) -> SnapFut<AccountRange> {
+        let range = self
+            .provider
+            .account_range(
+                request.root_hash,
+                request.starting_hash,
+                request.limit_hash,
+                request.response_bytes as usize,
+            )
+            .unwrap()
+            .unwrap_or_default();
+        let accounts = range
+            .entries
+            .into_iter()
+            .map(|(hash, account, storage_root)| AccountData {
+                hash,
+                body: SlimAccount::new(
+                    account.nonce,
+                    account.balance,
+                    storage_root,
+                    account.bytecode_hash.unwrap_or(KECCAK_EMPTY),
+                ),
+            })
+            .collect();
+        Self::respond(AccountRange { request_id: request.request_id, accounts, proof: range.proof })
+    }
+
+    fn get_storage_ranges(&self, request: GetStorageRanges) -> SnapFut<StorageRanges> {
+        let to_hash = |bytes: &Bytes, default: H256| {
+            if bytes.is_empty() {
+                default
+            } else {
+                H256::from_slice(bytes)
+            }
+        };
+        let range = self
+            .provider
+            .storage_ranges(
+                request.root_hash,
+                &request.account_hashes,
+                to_hash(&request.starting_hash, H256::zero()),
+                to_hash(&request.limit_hash, H256::repeat_byte(0xff)),
+                request.response_bytes as usize,
+            )
+            .unwrap()
+            .unwrap_or_default();
+        let slots = range
+            .entries
+            .into_iter()
+            .map(|slots| {
+                slots
+                    .into_iter()
+                    .map(|(hash, value)| {
+                        let mut data = Vec::new();
+                        value.encode(&mut data);
+                        StorageData { hash, data: data.into() }
+                    })
+                    .collect()
+            })
+            .collect();
+        Self::respond(StorageRanges { request_id: request.request_id, slots, proof: range.proof })
+    }
+
+    fn get_byte_codes(&self, request: GetByteCodes) -> SnapFut<ByteCodes> {
+        let tx = self.db.tx().unwrap();
+        let codes = request
+            .hashes
+            .into_iter()
+            .filter_map(|hash| tx.get::<tables::Bytecodes>(hash).unwrap())
+            .map(|code| code.original_bytes().into())
+            .collect();
+        Self::respond(ByteCodes { request_id: request.request_id, codes })
+    }
+
+    fn get_trie_nodes(&self, request: GetTrieNodes) -> SnapFut<TrieNodes> {
+        let paths = request
+            .paths
+            .iter()
+            .map(|path_set| {
+                let decode = |path: &Bytes| Nibbles::decode_path_leaf(path).unwrap().0;
+                match path_set.as_slice() {
+                    [path] => (None, vec![decode(path)]),
+                    [hashed_address, paths @ ..] => {
+                        (Some(H256::from_slice(hashed_address)), paths.iter().map(decode).collect())
+                    }
+                    [] => panic!("empty path set"),
+                }
+            })
+            .collect::<Vec<_>>();
+        let nodes = self
+            .provider
+            .trie_nodes(request.root_hash, &paths, request.response_bytes as usize)
+            .unwrap()
+            .unwrap_or_default();
+        Self::respond(TrieNodes { request_id: request.request_id, nodes })
+    }
+}
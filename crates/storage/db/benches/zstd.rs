@@ -0,0 +1,151 @@
+#![allow(dead_code, unused_imports, non_snake_case)]
+
+use criterion::{
+    black_box, criterion_group, criterion_main, measurement::WallTime, BenchmarkGroup, Criterion,
+};
+use pprof::criterion::{Output, PProfProfiler};
+use rand::{thread_rng, Rng};
+use reth_codecs::Compact;
+use reth_db::tables::codecs::dictionary::{
+    zstd_compress, zstd_decompress, ZstdDictionary, RECEIPT_DICTIONARIES, TRANSACTION_DICTIONARIES,
+};
+use reth_primitives::{
+    hex_literal::hex, Bytes, Log, Receipt, Signature, Transaction, TransactionKind,
+    TransactionSigned, TxEip1559, TxType, H160, H256, U256,
+};
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().with_profiler(PProfProfiler::new(100, Output::Flamegraph(None)));
+    targets = zstd
+}
+criterion_main!(benches);
+
+/// Number of values compressed in every iteration.
+const VALUES: usize = 1_000;
+
+/// Compares the size and the compression and decompression latency of [Compact] encoded
+/// transactions and receipts without compression, with zstd and with zstd and the shipped
+/// dictionaries.
+///
+/// The total sizes are printed before the benchmarks run.
+pub fn zstd(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Zstd Table Values");
+    group.sample_size(10);
+
+    let transactions = (0..VALUES).map(|_| compact(erc20_transfer())).collect::<Vec<_>>();
+    measure_values(&mut group, "TransactionSigned", &transactions, TRANSACTION_DICTIONARIES);
+
+    let receipts = (0..VALUES).map(|_| compact(erc20_receipt())).collect::<Vec<_>>();
+    measure_values(&mut group, "Receipt", &receipts, RECEIPT_DICTIONARIES);
+}
+
+fn measure_values(
+    group: &mut BenchmarkGroup<WallTime>,
+    name: &str,
+    values: &[Vec<u8>],
+    dictionaries: &'static [ZstdDictionary],
+) {
+    let plain =
+        values.iter().map(|value| zstd::bulk::compress(value, 0).unwrap()).collect::<Vec<_>>();
+    let with_dictionary =
+        values.iter().map(|value| zstd_compress(dictionaries, value.clone())).collect::<Vec<_>>();
+
+    let size = |values: &[Vec<u8>]| values.iter().map(Vec::len).sum::<usize>();
+    println!(
+        "{name}: {} values, uncompressed {} bytes, zstd {} bytes, zstd with dictionary {} bytes",
+        values.len(),
+        size(values),
+        size(&plain),
+        size(&with_dictionary),
+    );
+
+    group.bench_function(format!("{name}.Compress.Zstd"), |b| {
+        b.iter(|| {
+            for value in values {
+                black_box(zstd::bulk::compress(value, 0).unwrap());
+            }
+        })
+    });
+
+    group.bench_function(format!("{name}.Compress.ZstdDictionary"), |b| {
+        b.iter_with_setup(
+            || values.to_vec(),
+            |values| {
+                for value in values {
+                    black_box(zstd_compress(dictionaries, value));
+                }
+            },
+        )
+    });
+
+    group.bench_function(format!("{name}.Decompress.Zstd"), |b| {
+        b.iter(|| {
+            for (value, compressed) in values.iter().zip(&plain) {
+                black_box(zstd::bulk::decompress(compressed, value.len()).unwrap());
+            }
+        })
+    });
+
+    group.bench_function(format!("{name}.Decompress.ZstdDictionary"), |b| {
+        b.iter(|| {
+            for compressed in &with_dictionary {
+                black_box(zstd_decompress(dictionaries, compressed).unwrap());
+            }
+        })
+    });
+}
+
+fn compact<T: Compact>(value: T) -> Vec<u8> {
+    let mut buf = Vec::new();
+    value.to_compact(&mut buf);
+    buf
+}
+
+fn address_word() -> H256 {
+    let mut word = H256::zero();
+    word.0[12..].copy_from_slice(H160::random().as_bytes());
+    word
+}
+
+fn amount_word() -> H256 {
+    H256::from_low_u64_be(thread_rng().gen_range(1..u64::MAX >> 8))
+}
+
+fn erc20_transfer() -> TransactionSigned {
+    let mut input = hex!("a9059cbb").to_vec();
+    input.extend_from_slice(address_word().as_bytes());
+    input.extend_from_slice(amount_word().as_bytes());
+    TransactionSigned::from_transaction_and_signature(
+        Transaction::Eip1559(TxEip1559 {
+            chain_id: 1,
+            nonce: thread_rng().gen_range(0..10_000),
+            gas_limit: 65_000,
+            max_fee_per_gas: 30_000_000_000,
+            max_priority_fee_per_gas: 1_000_000_000,
+            to: TransactionKind::Call(H160::random()),
+            input: Bytes::from(input),
+            ..Default::default()
+        }),
+        Signature {
+            r: U256::from_be_bytes(H256::random().0),
+            s: U256::from_be_bytes(H256::random().0),
+            odd_y_parity: thread_rng().gen(),
+        },
+    )
+}
+
+fn erc20_receipt() -> Receipt {
+    let transfer = H256(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"));
+    Receipt {
+        tx_type: TxType::EIP1559,
+        success: true,
+        cumulative_gas_used: thread_rng().gen_range(21_000..30_000_000),
+        bloom: Default::default(),
+        logs: vec![Log {
+            address: H160::random(),
+            topics: vec![transfer, address_word(), address_word()],
+            data: Bytes::from(amount_word().as_bytes().to_vec()),
+        }],
+    }
+}
@@ -98,6 +98,10 @@ impl<'a> DbTxMut<'a> for TxMock {
     fn clear<T: Table>(&self) -> Result<(), Error> {
         todo!()
     }
+
+    fn dirty_bytes(&self) -> Result<u64, Error> {
+        todo!()
+    }
 }
 
 impl<'a> TableImporter<'a> for TxMock {}
@@ -61,4 +61,7 @@ pub trait DbTxMut<'tx>: for<'a> DbTxMutGAT<'a> {
     fn cursor_dup_write<T: DupSort>(
         &self,
     ) -> Result<<Self as DbTxMutGAT<'_>>::DupCursorMut<T>, Error>;
+    /// Estimate of the number of bytes modified by the transaction that are not yet written to
+    /// disk.
+    fn dirty_bytes(&self) -> Result<u64, Error>;
 }
@@ -139,4 +139,8 @@ impl<E: EnvironmentKind> DbTxMut<'_> for Tx<'_, RW, E> {
     ) -> Result<<Self as DbTxMutGAT<'_>>::DupCursorMut<T>, Error> {
         self.new_cursor()
     }
+
+    fn dirty_bytes(&self) -> Result<u64, Error> {
+        Ok(self.inner.info(false).map_err(|e| Error::Read(e.into()))?.space_dirty())
+    }
 }
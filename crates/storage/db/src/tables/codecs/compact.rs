@@ -1,6 +1,6 @@
 use crate::{
     table::{Compress, Decompress},
-    tables::models::*,
+    tables::{codecs::dictionary::*, models::*},
     Error,
 };
 use reth_codecs::{main_codec, Compact};
@@ -37,7 +37,6 @@ impl_compression_for_compact!(
     Header,
     Account,
     Log,
-    Receipt,
     TxType,
     StorageEntry,
     StorageTrieEntry,
@@ -46,10 +45,47 @@ impl_compression_for_compact!(
     StoredBlockWithdrawals,
     Bytecode
 );
-impl_compression_for_compact!(AccountBeforeTx, TransactionSigned);
+impl_compression_for_compact!(AccountBeforeTx);
 impl_compression_for_compact!(CompactU256);
 impl_compression_for_compact!(H256, H160);
 
+/// Implements compression for Compact types whose values can be zstd compressed with the given
+/// dictionaries, see [dictionary][crate::tables::codecs::dictionary].
+macro_rules! impl_zstd_compression_for_compact {
+    ($($name:tt => $dictionaries:ident),+) => {
+        $(
+            impl Compress for $name
+            {
+                type Compressed = Vec<u8>;
+
+                fn compress(self) -> Self::Compressed {
+                    let mut buf = vec![];
+                    let _  = Compact::to_compact(self, &mut buf);
+                    if cfg!(feature = "zstd-tables") {
+                        buf = zstd_compress($dictionaries, buf);
+                    }
+                    buf
+                }
+            }
+
+            impl Decompress for $name
+            {
+                fn decompress<B: Into<bytes::Bytes>>(value: B) -> Result<$name, Error> {
+                    let value = value.into();
+                    let value = zstd_decompress($dictionaries, &value)?;
+                    let (obj, _) = Compact::from_compact(&value, value.len());
+                    Ok(obj)
+                }
+            }
+        )+
+    };
+}
+
+impl_zstd_compression_for_compact!(
+    TransactionSigned => TRANSACTION_DICTIONARIES,
+    Receipt => RECEIPT_DICTIONARIES
+);
+
 /// Adds wrapper structs for some primitive types so they can use StructFlags from Compact, when
 /// used as pure table values.
 macro_rules! add_wrapper_struct {
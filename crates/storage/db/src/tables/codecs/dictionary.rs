@@ -0,0 +1,330 @@
+//! Zstd compression with shipped dictionaries for large and repetitive table values.
+//!
+//! Values of the [Transactions][crate::tables::Transactions] and
+//! [Receipts][crate::tables::Receipts] tables are dominated by a few patterns, like ERC-20 calldata
+//! and log topics. Compressing every value on its own with a dictionary of those patterns saves
+//! space without losing random access.
+//!
+//! A compressed value starts with a byte that has [ZSTD_FLAG] set and holds the version of the
+//! dictionary in its other bits, followed by the length of the uncompressed value as a `u32` in
+//! little endian and the zstd frame. The [Compact] encodings of the compressed types never set
+//! [ZSTD_FLAG] in their first byte, so values written without compression stay readable.
+//!
+//! Dictionaries are never changed once shipped, a new dictionary is added with the next version.
+//! Version 1 was trained with [train_dictionary] on generated rows of common transfers, swaps and
+//! their logs. Later versions should be trained on rows of a synced database with
+//! `reth db train-dictionary` and added to the end of the lists.
+//!
+//! Values are only written compressed with the `zstd-tables` feature, while compressed values are
+//! always readable.
+//!
+//! [Compact]: reth_codecs::Compact
+
+use crate::Error;
+use reth_codecs::Compact;
+use std::{
+    borrow::Cow,
+    cell::RefCell,
+    collections::{hash_map::Entry, HashMap},
+};
+use zstd::bulk::{Compressor, Decompressor};
+
+/// The bit of the first byte of a value that marks it as zstd compressed.
+pub const ZSTD_FLAG: u8 = 0x80;
+
+/// The zstd compression level of table values.
+const ZSTD_LEVEL: i32 = 0;
+
+/// The length of the header of a compressed value: flag byte and uncompressed length.
+const HEADER_LEN: usize = 1 + 4;
+
+/// A versioned zstd dictionary for the values of a table.
+#[derive(Debug)]
+pub struct ZstdDictionary {
+    /// The name of the compressed type.
+    pub name: &'static str,
+    /// The version of the dictionary, between 1 and 127.
+    pub version: u8,
+    /// The dictionary.
+    pub bytes: &'static [u8],
+}
+
+/// The dictionaries of the [Transactions][crate::tables::Transactions] table, the last one is used
+/// for new values.
+pub const TRANSACTION_DICTIONARIES: &[ZstdDictionary] = &[ZstdDictionary {
+    name: "TransactionSigned",
+    version: 1,
+    bytes: include_bytes!("dictionaries/transactions-v1.bin"),
+}];
+
+/// The dictionaries of the [Receipts][crate::tables::Receipts] table, the last one is used for new
+/// values.
+pub const RECEIPT_DICTIONARIES: &[ZstdDictionary] = &[ZstdDictionary {
+    name: "Receipt",
+    version: 1,
+    bytes: include_bytes!("dictionaries/receipts-v1.bin"),
+}];
+
+thread_local! {
+    /// Compressors of every used dictionary, loading a dictionary is expensive.
+    static COMPRESSORS: RefCell<HashMap<(&'static str, u8), Compressor<'static>>> =
+        RefCell::new(HashMap::new());
+
+    /// Decompressors of every used dictionary, loading a dictionary is expensive.
+    static DECOMPRESSORS: RefCell<HashMap<(&'static str, u8), Decompressor<'static>>> =
+        RefCell::new(HashMap::new());
+}
+
+/// Compresses a [Compact][reth_codecs::Compact] encoded value with the latest of the given
+/// dictionaries.
+///
+/// Returns the value unchanged if compression does not make it smaller.
+pub fn zstd_compress(dictionaries: &'static [ZstdDictionary], value: Vec<u8>) -> Vec<u8> {
+    debug_assert!(value.first().map_or(true, |flags| flags & ZSTD_FLAG == 0));
+    let dictionary = dictionaries.last().expect("dictionaries are not empty");
+
+    let frame = COMPRESSORS.with(|compressors| {
+        let mut compressors = compressors.borrow_mut();
+        let compressor =
+            compressors.entry((dictionary.name, dictionary.version)).or_insert_with(|| {
+                Compressor::with_dictionary(ZSTD_LEVEL, dictionary.bytes)
+                    .expect("shipped dictionary is valid")
+            });
+        compressor.compress(&value)
+    });
+
+    match frame {
+        Ok(frame) if HEADER_LEN + frame.len() < value.len() => {
+            let mut compressed = Vec::with_capacity(HEADER_LEN + frame.len());
+            compressed.push(ZSTD_FLAG | dictionary.version);
+            compressed.extend_from_slice(&(value.len() as u32).to_le_bytes());
+            compressed.extend_from_slice(&frame);
+            compressed
+        }
+        _ => value,
+    }
+}
+
+/// Trains a dictionary of at most `max_size` bytes on the [Compact] encodings of the given values.
+pub fn train_dictionary<T: Compact>(
+    values: impl IntoIterator<Item = T>,
+    max_size: usize,
+) -> std::io::Result<Vec<u8>> {
+    let samples = values
+        .into_iter()
+        .map(|value| {
+            let mut buf = Vec::new();
+            value.to_compact(&mut buf);
+            buf
+        })
+        .collect::<Vec<_>>();
+    zstd::dict::from_samples(&samples, max_size)
+}
+
+/// Decompresses a value written by [zstd_compress], returning values that are not compressed
+/// unchanged.
+pub fn zstd_decompress<'a>(
+    dictionaries: &'static [ZstdDictionary],
+    value: &'a [u8],
+) -> Result<Cow<'a, [u8]>, Error> {
+    let Some(&flags) = value.first() else { return Ok(Cow::Borrowed(value)) };
+    if flags & ZSTD_FLAG == 0 {
+        return Ok(Cow::Borrowed(value))
+    }
+    if value.len() < HEADER_LEN {
+        return Err(Error::DecodeError)
+    }
+
+    let version = flags & !ZSTD_FLAG;
+    let dictionary = dictionaries
+        .iter()
+        .find(|dictionary| dictionary.version == version)
+        .ok_or(Error::DecodeError)?;
+    let len = u32::from_le_bytes(value[1..HEADER_LEN].try_into().expect("4 bytes")) as usize;
+
+    DECOMPRESSORS.with(|decompressors| {
+        let mut decompressors = decompressors.borrow_mut();
+        let decompressor = match decompressors.entry((dictionary.name, dictionary.version)) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(
+                Decompressor::with_dictionary(dictionary.bytes).map_err(|_| Error::DecodeError)?,
+            ),
+        };
+        let decompressed =
+            decompressor.decompress(&value[HEADER_LEN..], len).map_err(|_| Error::DecodeError)?;
+        if decompressed.len() != len {
+            return Err(Error::DecodeError)
+        }
+        Ok(Cow::Owned(decompressed))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{
+        hex_literal::hex, AccessList, AccessListItem, Bytes, Log, Receipt, Signature, Transaction,
+        TransactionKind, TransactionSigned, TxEip1559, TxEip2930, TxEip4844, TxLegacy, TxType,
+        H160, H256,
+    };
+
+    fn compact<T: Compact>(value: T) -> Vec<u8> {
+        let mut buf = Vec::new();
+        value.to_compact(&mut buf);
+        buf
+    }
+
+    fn address_word() -> H256 {
+        let mut word = H256::zero();
+        word.0[12..].copy_from_slice(H160::random().as_bytes());
+        word
+    }
+
+    fn transfer_input() -> Bytes {
+        let mut input = hex!("a9059cbb").to_vec();
+        input.extend_from_slice(address_word().as_bytes());
+        input.extend_from_slice(H256::from_low_u64_be(1_000_000).as_bytes());
+        Bytes::from(input)
+    }
+
+    fn signed(transaction: Transaction) -> TransactionSigned {
+        TransactionSigned::from_transaction_and_signature(
+            transaction,
+            Signature { r: Default::default(), s: Default::default(), odd_y_parity: true },
+        )
+    }
+
+    fn erc20_transfer() -> TransactionSigned {
+        signed(Transaction::Eip1559(TxEip1559 {
+            chain_id: 1,
+            nonce: 42,
+            gas_limit: 65_000,
+            to: TransactionKind::Call(H160::random()),
+            input: transfer_input(),
+            ..Default::default()
+        }))
+    }
+
+    fn erc20_receipt() -> Receipt {
+        receipt(TxType::EIP1559)
+    }
+
+    fn receipt(tx_type: TxType) -> Receipt {
+        let transfer =
+            H256(hex!("ddf252ad1be2c89b69c2b068fc378daa952ba7f163c4a11628f55a4df523b3ef"));
+        Receipt {
+            tx_type,
+            success: true,
+            cumulative_gas_used: 1_234_567,
+            bloom: Default::default(),
+            logs: vec![Log {
+                address: H160::random(),
+                topics: vec![transfer, address_word(), address_word()],
+                data: Bytes::from(H256::from_low_u64_be(1_000_000).as_bytes().to_vec()),
+            }],
+        }
+    }
+
+    #[test]
+    fn compact_encodings_leave_flag_clear() {
+        let access_list = AccessList(vec![AccessListItem {
+            address: H160::random(),
+            storage_keys: vec![H256::random(), H256::random()],
+        }]);
+        let transactions = [
+            TransactionSigned::default(),
+            signed(Transaction::Legacy(TxLegacy {
+                chain_id: Some(1),
+                nonce: 42,
+                gas_price: 30_000_000_000,
+                gas_limit: 65_000,
+                to: TransactionKind::Call(H160::random()),
+                input: transfer_input(),
+                ..Default::default()
+            })),
+            signed(Transaction::Eip2930(TxEip2930 {
+                chain_id: 1,
+                nonce: 42,
+                gas_price: 30_000_000_000,
+                gas_limit: 65_000,
+                to: TransactionKind::Call(H160::random()),
+                access_list: access_list.clone(),
+                input: transfer_input(),
+                ..Default::default()
+            })),
+            erc20_transfer(),
+            signed(Transaction::Eip4844(TxEip4844 {
+                chain_id: 1,
+                nonce: 42,
+                gas_limit: 65_000,
+                max_fee_per_gas: 30_000_000_000,
+                max_priority_fee_per_gas: 1_000_000_000,
+                to: TransactionKind::Call(H160::random()),
+                access_list,
+                blob_versioned_hashes: vec![H256::random()],
+                max_fee_per_blob_gas: 1,
+                input: transfer_input(),
+                ..Default::default()
+            })),
+        ];
+        for transaction in transactions {
+            assert_eq!(compact(transaction)[0] & ZSTD_FLAG, 0);
+        }
+
+        let receipts = [
+            Receipt::default(),
+            receipt(TxType::Legacy),
+            receipt(TxType::EIP2930),
+            receipt(TxType::EIP1559),
+            receipt(TxType::EIP4844),
+        ];
+        for receipt in receipts {
+            assert_eq!(compact(receipt)[0] & ZSTD_FLAG, 0);
+        }
+    }
+
+    #[test]
+    fn trained_dictionary_roundtrip() {
+        let dictionary = train_dictionary((0..1000).map(|_| erc20_receipt()), 4 * 1024).unwrap();
+        let mut compressor = Compressor::with_dictionary(ZSTD_LEVEL, &dictionary).unwrap();
+        let mut decompressor = Decompressor::with_dictionary(&dictionary).unwrap();
+
+        let receipt = compact(erc20_receipt());
+        let frame = compressor.compress(&receipt).unwrap();
+        assert!(frame.len() < receipt.len());
+        assert_eq!(decompressor.decompress(&frame, receipt.len()).unwrap(), receipt);
+    }
+
+    #[test]
+    fn compress_roundtrip() {
+        let receipt = compact(erc20_receipt());
+        let compressed = zstd_compress(RECEIPT_DICTIONARIES, receipt.clone());
+        assert_ne!(compressed[0] & ZSTD_FLAG, 0);
+        assert!(compressed.len() < receipt.len());
+        assert_eq!(zstd_decompress(RECEIPT_DICTIONARIES, &compressed).unwrap(), receipt);
+
+        let transaction = compact(erc20_transfer());
+        let compressed = zstd_compress(TRANSACTION_DICTIONARIES, transaction.clone());
+        assert_eq!(zstd_decompress(TRANSACTION_DICTIONARIES, &compressed).unwrap(), transaction);
+    }
+
+    #[test]
+    fn uncompressed_values_stay_readable() {
+        let receipt = compact(erc20_receipt());
+        assert_eq!(
+            zstd_decompress(RECEIPT_DICTIONARIES, &receipt).unwrap(),
+            Cow::Borrowed(&receipt[..])
+        );
+
+        // Values that do not shrink are stored as they are.
+        let empty = compact(Receipt::default());
+        assert_eq!(zstd_compress(RECEIPT_DICTIONARIES, empty.clone()), empty);
+    }
+
+    #[test]
+    fn unknown_dictionary_version() {
+        let mut compressed = zstd_compress(RECEIPT_DICTIONARIES, compact(erc20_receipt()));
+        compressed[0] = ZSTD_FLAG | 127;
+        assert_eq!(zstd_decompress(RECEIPT_DICTIONARIES, &compressed), Err(Error::DecodeError));
+    }
+}
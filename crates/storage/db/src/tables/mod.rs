@@ -18,8 +18,9 @@ use crate::{
     },
 };
 use reth_primitives::{
-    Account, Address, BlockHash, BlockNumber, Bytecode, Header, IntegerList, Receipt, StorageEntry,
-    StorageTrieEntry, TransactionSigned, TransitionId, TxHash, TxNumber, H256,
+    trie::{StoredNibbles, StoredNibblesSubKey},
+    Account, Address, BlockHash, BlockNumber, Bytecode, Header, IntegerList, PrunePart, Receipt,
+    StorageEntry, StorageTrieEntry, TransactionSigned, TransitionId, TxHash, TxNumber, H256,
 };
 
 /// Enum for the types of tables present in libmdbx.
@@ -32,7 +33,7 @@ pub enum TableType {
 }
 
 /// Default tables that should be present inside database.
-pub const TABLES: [(TableType, &str); 26] = [
+pub const TABLES: [(TableType, &str); 30] = [
     (TableType::Table, CanonicalHeaders::const_name()),
     (TableType::Table, HeaderTD::const_name()),
     (TableType::Table, HeaderNumbers::const_name()),
@@ -47,6 +48,8 @@ pub const TABLES: [(TableType, &str); 26] = [
     (TableType::Table, PlainAccountState::const_name()),
     (TableType::DupSort, PlainStorageState::const_name()),
     (TableType::Table, Bytecodes::const_name()),
+    (TableType::Table, BytecodeRefCounts::const_name()),
+    (TableType::DupSort, UnreferencedBytecodes::const_name()),
     (TableType::Table, BlockTransitionIndex::const_name()),
     (TableType::Table, TxTransitionIndex::const_name()),
     (TableType::Table, AccountHistory::const_name()),
@@ -59,6 +62,8 @@ pub const TABLES: [(TableType, &str); 26] = [
     (TableType::DupSort, StoragesTrie::const_name()),
     (TableType::Table, TxSenders::const_name()),
     (TableType::Table, SyncStage::const_name()),
+    (TableType::Table, SyncStageProgress::const_name()),
+    (TableType::Table, PruneCheckpoints::const_name()),
 ];
 
 #[macro_export]
@@ -166,13 +171,27 @@ table!(
 );
 
 table!(
-    /// Stores all smart contract bytecodes.
-    /// There will be multiple accounts that have same bytecode
-    /// So we would need to introduce reference counter.
-    /// This will be small optimization on state.
+    /// Stores all smart contract bytecodes, deduplicated by their hash.
+    /// Multiple accounts can share the same bytecode, the number of accounts that use each
+    /// bytecode is kept in [`BytecodeRefCounts`].
     ( Bytecodes ) H256 | Bytecode
 );
 
+table!(
+    /// Stores the number of accounts in [`PlainAccountState`] that use each bytecode.
+    /// Bytecodes that are not used by any account have no entry.
+    ( BytecodeRefCounts ) H256 | u64
+);
+
+dupsort!(
+    /// Stores the bytecodes that are no longer used by any account, keyed by the block at which
+    /// the last account using them was destroyed or unwound.
+    /// They are kept until the account history of that block is pruned and are then removed from
+    /// [`Bytecodes`]. Entries of bytecodes that are used again are left in place and skipped on
+    /// removal.
+    ( UnreferencedBytecodes ) BlockNumber | [H256] H256
+);
+
 table!(
     /// Stores the mapping of block number to state transition id.
     /// The block transition marks the final state at the end of the block.
@@ -272,13 +291,15 @@ dupsort!(
 );
 
 table!(
-    /// Stores the current state's Merkle Patricia Tree.
-    ( AccountsTrie ) H256 | Vec<u8>
+    /// Stores the hashes of the branch nodes of the current state's Merkle Patricia Tree, keyed
+    /// by their path in the trie.
+    ( AccountsTrie ) StoredNibbles | H256
 );
 
 dupsort!(
-    /// Stores the Merkle Patricia Trees of each [`Account`]'s storage.
-    ( StoragesTrie ) H256 | [H256] StorageTrieEntry
+    /// Stores the hashes of the branch nodes of each [`Account`]'s storage Merkle Patricia Tree,
+    /// keyed by the hashed address and their path in the trie.
+    ( StoragesTrie ) H256 | [StoredNibblesSubKey] StorageTrieEntry
 );
 
 table!(
@@ -293,6 +314,17 @@ table!(
     ( SyncStage ) StageId | BlockNumber
 );
 
+table!(
+    /// Stores the intermediate progress of each stage that can resume its work within a block
+    /// range, encoded by the stage itself.
+    ( SyncStageProgress ) StageId | Vec<u8>
+);
+
+table!(
+    /// Stores the highest pruned block number of each part of the history.
+    ( PruneCheckpoints ) PrunePart | BlockNumber
+);
+
 ///
 /// Alias Types
 
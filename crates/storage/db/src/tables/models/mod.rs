@@ -14,7 +14,12 @@ use crate::{
     table::{Decode, Encode},
     Error,
 };
-use reth_primitives::{bytes::Bytes, Address, H256};
+use reth_codecs::Compact;
+use reth_primitives::{
+    bytes::Bytes,
+    trie::{StoredNibbles, StoredNibblesSubKey},
+    Address, PrunePart, H256,
+};
 
 /// Macro that implements [`Encode`] and [`Decode`] for uint types.
 macro_rules! impl_uints {
@@ -83,3 +88,60 @@ impl Decode for H256 {
         Ok(H256::from_slice(&value.into()[..]))
     }
 }
+
+impl Encode for StoredNibbles {
+    type Encoded = Vec<u8>;
+
+    fn encode(self) -> Self::Encoded {
+        self.0.as_slice().to_vec()
+    }
+}
+
+impl Decode for StoredNibbles {
+    fn decode<B: Into<Bytes>>(value: B) -> Result<Self, Error> {
+        let value = value.into();
+        if value.iter().any(|nibble| *nibble > 0x0f) {
+            return Err(Error::DecodeError)
+        }
+        Ok(StoredNibbles(value.to_vec().into()))
+    }
+}
+
+impl Encode for StoredNibblesSubKey {
+    type Encoded = Vec<u8>;
+
+    // Delegate to the Compact implementation
+    fn encode(self) -> Self::Encoded {
+        let mut buf = Vec::with_capacity(65);
+        self.to_compact(&mut buf);
+        buf
+    }
+}
+
+impl Decode for StoredNibblesSubKey {
+    fn decode<B: Into<Bytes>>(value: B) -> Result<Self, Error> {
+        let value = value.into();
+        if value.len() != 65 {
+            return Err(Error::DecodeError)
+        }
+        Ok(Self::from_compact(&value, value.len()).0)
+    }
+}
+
+impl Encode for PrunePart {
+    type Encoded = [u8; 1];
+
+    fn encode(self) -> Self::Encoded {
+        [self as u8]
+    }
+}
+
+impl Decode for PrunePart {
+    fn decode<B: Into<Bytes>>(value: B) -> Result<Self, Error> {
+        let value = value.into();
+        match value.as_ref() {
+            [part] => PrunePart::try_from(*part).map_err(|_| Error::DecodeError),
+            _ => Err(Error::DecodeError),
+        }
+    }
+}
@@ -11,7 +11,7 @@ pub use crate::{
     },
     error::{Error, Result},
     flags::*,
-    transaction::{Transaction, TransactionKind, RO, RW},
+    transaction::{Transaction, TransactionInfo, TransactionKind, RO, RW},
 };
 
 mod codec;
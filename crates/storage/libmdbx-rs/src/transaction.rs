@@ -13,7 +13,7 @@ use std::{
     fmt,
     fmt::Debug,
     marker::PhantomData,
-    mem::size_of,
+    mem::{self, size_of},
     ptr, result, slice,
     sync::{mpsc::sync_channel, Arc},
 };
@@ -211,6 +211,20 @@ where
         }
     }
 
+    /// Retrieves information about this transaction.
+    ///
+    /// If `scan_rlt` is set, the reader lock table is scanned to determine the lag of this
+    /// transaction behind the most recent one, which is more expensive.
+    pub fn info(&self, scan_rlt: bool) -> Result<TransactionInfo> {
+        unsafe {
+            let mut info = TransactionInfo(mem::zeroed());
+            mdbx_result(txn_execute(&self.txn, |txn| {
+                ffi::mdbx_txn_info(txn, &mut info.0, scan_rlt)
+            }))?;
+            Ok(info)
+        }
+    }
+
     /// Open a new cursor on the given database.
     pub fn cursor<'txn>(&'txn self, db: &Database<'txn>) -> Result<Cursor<'txn, K>> {
         Cursor::new(self, db)
@@ -407,6 +421,49 @@ impl<'env> Transaction<'env, RW, NoWriteMap> {
     }
 }
 
+/// Information about a transaction.
+#[repr(transparent)]
+pub struct TransactionInfo(ffi::MDBX_txn_info);
+
+impl TransactionInfo {
+    /// The ID of the transaction.
+    #[inline]
+    pub fn id(&self) -> u64 {
+        self.0.txn_id
+    }
+
+    /// The number of bytes used by the database as of this transaction.
+    #[inline]
+    pub fn space_used(&self) -> u64 {
+        self.0.txn_space_used
+    }
+
+    /// For write transactions, the number of bytes of pages that were modified and are not
+    /// written to disk yet.
+    #[inline]
+    pub fn space_dirty(&self) -> u64 {
+        self.0.txn_space_dirty
+    }
+
+    /// For write transactions, the number of bytes of pages that were retired, i.e. freed by this
+    /// transaction.
+    #[inline]
+    pub fn space_retired(&self) -> u64 {
+        self.0.txn_space_retired
+    }
+}
+
+impl fmt::Debug for TransactionInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> result::Result<(), fmt::Error> {
+        f.debug_struct("TransactionInfo")
+            .field("id", &self.id())
+            .field("space_used", &self.space_used())
+            .field("space_dirty", &self.space_dirty())
+            .field("space_retired", &self.space_retired())
+            .finish()
+    }
+}
+
 impl<'env, K, E> fmt::Debug for Transaction<'env, K, E>
 where
     K: TransactionKind,
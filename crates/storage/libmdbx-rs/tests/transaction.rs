@@ -374,3 +374,20 @@ fn test_stat_dupsort() {
         assert_eq!(stat.entries(), 8);
     }
 }
+
+#[test]
+fn test_info() {
+    let dir = tempdir().unwrap();
+    let env = Environment::new().open(dir.path()).unwrap();
+
+    let txn = env.begin_rw_txn().unwrap();
+    let db = txn.create_db(None, DatabaseFlags::empty()).unwrap();
+    let dirty_before = txn.info(false).unwrap().space_dirty();
+    for i in 0..1000u32 {
+        txn.put(&db, i.to_be_bytes(), [0u8; 64], WriteFlags::empty()).unwrap();
+    }
+    let info = txn.info(false).unwrap();
+    assert_eq!(info.id(), txn.id());
+    assert!(info.space_dirty() > dirty_before);
+    txn.commit().unwrap();
+}
@@ -0,0 +1,151 @@
+use reth_db::{
+    cursor::DbCursorRO,
+    tables,
+    transaction::{DbTx, DbTxMut},
+    Error as DbError,
+};
+use reth_primitives::{Account, BlockNumber, H256, KECCAK_EMPTY};
+use std::collections::HashSet;
+
+/// Returns the hash of the bytecode of an account, or `None` if the account has no bytecode.
+pub fn account_bytecode_hash(account: &Account) -> Option<H256> {
+    account.bytecode_hash.filter(|hash| *hash != KECCAK_EMPTY)
+}
+
+/// Updates the reference counts of the bytecodes in [tables::BytecodeRefCounts] when an account in
+/// [tables::PlainAccountState] changes from `old` to `new`.
+///
+/// A bytecode that is no longer used by any account is added to [tables::UnreferencedBytecodes]
+/// at the given block number. Its entry in [tables::Bytecodes] is only deleted by
+/// [remove_unreferenced_bytecodes], which skips the bytecode if an account uses it again.
+pub fn update_bytecode_references<'a, TX: DbTxMut<'a> + DbTx<'a>>(
+    tx: &TX,
+    old: Option<&Account>,
+    new: Option<&Account>,
+    block_number: BlockNumber,
+) -> Result<(), DbError> {
+    let old = old.and_then(account_bytecode_hash);
+    let new = new.and_then(account_bytecode_hash);
+    if old == new {
+        return Ok(())
+    }
+
+    if let Some(hash) = old {
+        match tx.get::<tables::BytecodeRefCounts>(hash)? {
+            Some(count) if count > 1 => tx.put::<tables::BytecodeRefCounts>(hash, count - 1)?,
+            Some(_) => {
+                tx.delete::<tables::BytecodeRefCounts>(hash, None)?;
+                tx.put::<tables::UnreferencedBytecodes>(block_number, hash)?;
+            }
+            // Bytecodes written before reference counting have no count. They are never
+            // removed until the counts are rebuilt with `reth db verify-bytecodes --fix`.
+            None => {}
+        }
+    }
+
+    if let Some(hash) = new {
+        let count = tx.get::<tables::BytecodeRefCounts>(hash)?.unwrap_or_default();
+        tx.put::<tables::BytecodeRefCounts>(hash, count + 1)?;
+    }
+
+    Ok(())
+}
+
+/// Deletes the bytecodes from [tables::Bytecodes] that became unreferenced before the given
+/// block, and their entries in [tables::UnreferencedBytecodes].
+///
+/// Bytecodes that are used by an account again, or that became unreferenced again at or after the
+/// given block, are kept. The caller must make sure that no state at or before the given block is
+/// read anymore, i.e. that the account history up to it is pruned.
+///
+/// Returns the number of deleted bytecodes.
+pub fn remove_unreferenced_bytecodes<'a, TX: DbTxMut<'a> + DbTx<'a>>(
+    tx: &TX,
+    before: BlockNumber,
+) -> Result<usize, DbError> {
+    let mut cursor = tx.cursor_read::<tables::UnreferencedBytecodes>()?;
+    let unreferenced = cursor.walk_range(..before)?.collect::<Result<Vec<_>, _>>()?;
+    if unreferenced.is_empty() {
+        return Ok(0)
+    }
+    // The few bytecodes that became unreferenced again later are still needed by their history.
+    let later = cursor
+        .walk(Some(before))?
+        .map(|entry| entry.map(|(_, hash)| hash))
+        .collect::<Result<HashSet<_>, _>>()?;
+
+    let mut removed = 0;
+    for (block, hash) in unreferenced {
+        tx.delete::<tables::UnreferencedBytecodes>(block, Some(hash))?;
+        if tx.get::<tables::BytecodeRefCounts>(hash)?.is_none() &&
+            !later.contains(&hash) &&
+            tx.delete::<tables::Bytecodes>(hash, None)?
+        {
+            removed += 1;
+        }
+    }
+
+    Ok(removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_db::{database::Database, mdbx::test_utils::create_test_rw_db};
+    use reth_primitives::{Bytecode, Bytes, U256};
+    use revm_primitives::Bytecode as RevmBytecode;
+
+    #[test]
+    fn bytecode_reference_lifecycle() {
+        let db = create_test_rw_db();
+        let tx = db.tx_mut().unwrap();
+
+        let code = H256::random();
+        let bytecode = Bytecode(RevmBytecode::new_raw(Bytes::from(vec![0x00]).0));
+        tx.put::<tables::Bytecodes>(code, bytecode).unwrap();
+        let contract = Account { balance: U256::ZERO, nonce: 1, bytecode_hash: Some(code) };
+        let eoa = Account { balance: U256::from(1), nonce: 0, bytecode_hash: Some(KECCAK_EMPTY) };
+
+        // Two contracts with the same bytecode are created, accounts without code are ignored.
+        update_bytecode_references(&tx, None, Some(&contract), 1).unwrap();
+        update_bytecode_references(&tx, None, Some(&contract), 1).unwrap();
+        update_bytecode_references(&tx, None, Some(&eoa), 1).unwrap();
+        assert_eq!(tx.get::<tables::BytecodeRefCounts>(code), Ok(Some(2)));
+        assert_eq!(tx.get::<tables::BytecodeRefCounts>(KECCAK_EMPTY), Ok(None));
+
+        // Changing the balance keeps the reference.
+        let funded = Account { balance: U256::from(1), ..contract };
+        update_bytecode_references(&tx, Some(&contract), Some(&funded), 2).unwrap();
+        assert_eq!(tx.get::<tables::BytecodeRefCounts>(code), Ok(Some(2)));
+
+        // Both contracts self-destruct.
+        update_bytecode_references(&tx, Some(&funded), None, 3).unwrap();
+        update_bytecode_references(&tx, Some(&contract), None, 4).unwrap();
+        assert_eq!(tx.get::<tables::BytecodeRefCounts>(code), Ok(None));
+        assert_eq!(tx.get::<tables::UnreferencedBytecodes>(4), Ok(Some(code)));
+
+        // The bytecode is kept while the history of the block can still be read.
+        assert_eq!(remove_unreferenced_bytecodes(&tx, 4), Ok(0));
+        assert!(tx.get::<tables::Bytecodes>(code).unwrap().is_some());
+
+        // An unwind restores the contract, so the bytecode is referenced again.
+        update_bytecode_references(&tx, None, Some(&contract), 3).unwrap();
+        assert_eq!(tx.get::<tables::BytecodeRefCounts>(code), Ok(Some(1)));
+        assert_eq!(remove_unreferenced_bytecodes(&tx, 5), Ok(0));
+        assert!(tx.get::<tables::Bytecodes>(code).unwrap().is_some());
+        assert_eq!(tx.get::<tables::UnreferencedBytecodes>(4), Ok(None));
+
+        // The contract is destroyed, created and destroyed again, the history of the later
+        // blocks still needs the bytecode.
+        update_bytecode_references(&tx, Some(&contract), None, 5).unwrap();
+        update_bytecode_references(&tx, None, Some(&contract), 6).unwrap();
+        update_bytecode_references(&tx, Some(&contract), None, 8).unwrap();
+        assert_eq!(remove_unreferenced_bytecodes(&tx, 7), Ok(0));
+        assert!(tx.get::<tables::Bytecodes>(code).unwrap().is_some());
+        assert_eq!(tx.get::<tables::UnreferencedBytecodes>(5), Ok(None));
+
+        assert_eq!(remove_unreferenced_bytecodes(&tx, 9), Ok(1));
+        assert_eq!(tx.get::<tables::Bytecodes>(code), Ok(None));
+        assert_eq!(tx.get::<tables::UnreferencedBytecodes>(8), Ok(None));
+    }
+}
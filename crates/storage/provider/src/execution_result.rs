@@ -1,7 +1,13 @@
 //! Output of execution.
 
-use reth_db::{models::AccountBeforeTx, tables, transaction::DbTxMut, Error as DbError};
-use reth_primitives::{Account, Address, Receipt, H256, U256};
+use crate::update_bytecode_references;
+use reth_db::{
+    models::AccountBeforeTx,
+    tables,
+    transaction::{DbTx, DbTxMut},
+    Error as DbError,
+};
+use reth_primitives::{Account, Address, BlockNumber, Receipt, H256, U256};
 use revm_primitives::Bytecode;
 use std::collections::BTreeMap;
 
@@ -11,6 +17,12 @@ use std::collections::BTreeMap;
 pub struct ExecutionResult {
     /// Transaction changeset containing [Receipt], changed [Accounts][Account] and Storages.
     pub tx_changesets: Vec<TransactionChangeSet>,
+    /// State changes of the system calls made before the transactions, like the
+    /// [EIP-4788](https://eips.ethereum.org/EIPS/eip-4788) beacon root update.
+    ///
+    /// These are written with the first transition of the block, before the changes of its first
+    /// transaction.
+    pub pre_block_changesets: BTreeMap<Address, AccountChangeSet>,
     /// Post block account changesets. This might include block reward, uncle rewards, withdrawals
     /// or irregular state changes (DAO fork).
     pub block_changesets: BTreeMap<Address, AccountInfoChangeSet>,
@@ -58,11 +70,15 @@ pub enum AccountInfoChangeSet {
 
 impl AccountInfoChangeSet {
     /// Apply the changes from the changeset to a database transaction.
-    pub fn apply_to_db<'a, TX: DbTxMut<'a>>(
+    ///
+    /// The reference counts of the bytecodes of the old and new account are updated as well, see
+    /// [update_bytecode_references].
+    pub fn apply_to_db<'a, TX: DbTxMut<'a> + DbTx<'a>>(
         self,
         tx: &TX,
         address: Address,
         tx_index: u64,
+        block_number: BlockNumber,
         has_state_clear_eip: bool,
     ) -> Result<(), DbError> {
         match self {
@@ -74,6 +90,7 @@ impl AccountInfoChangeSet {
                     AccountBeforeTx { address, info: Some(old) },
                 )?;
                 tx.put::<tables::PlainAccountState>(address, new)?;
+                update_bytecode_references(tx, Some(&old), Some(&new), block_number)?;
             }
             AccountInfoChangeSet::Created { new } => {
                 // Ignore account that are created empty and state clear (SpuriousDragon) hardfork
@@ -86,6 +103,7 @@ impl AccountInfoChangeSet {
                     AccountBeforeTx { address, info: None },
                 )?;
                 tx.put::<tables::PlainAccountState>(address, new)?;
+                update_bytecode_references(tx, None, Some(&new), block_number)?;
             }
             AccountInfoChangeSet::Destroyed { old } => {
                 tx.delete::<tables::PlainAccountState>(address, None)?;
@@ -93,6 +111,7 @@ impl AccountInfoChangeSet {
                     tx_index,
                     AccountBeforeTx { address, info: Some(old) },
                 )?;
+                update_bytecode_references(tx, Some(&old), None, block_number)?;
             }
             AccountInfoChangeSet::NoChange => {
                 // do nothing storage account didn't change
@@ -141,7 +160,7 @@ mod tests {
 
         // check Changed changeset
         AccountInfoChangeSet::Changed { new: acc1, old: acc2 }
-            .apply_to_db(&tx, address, tx_num, true)
+            .apply_to_db(&tx, address, tx_num, 1, true)
             .unwrap();
         assert_eq!(
             tx.get::<tables::AccountChangeSet>(tx_num),
@@ -150,7 +169,7 @@ mod tests {
         assert_eq!(tx.get::<tables::PlainAccountState>(address), Ok(Some(acc1)));
 
         AccountInfoChangeSet::Created { new: acc1 }
-            .apply_to_db(&tx, address, tx_num, true)
+            .apply_to_db(&tx, address, tx_num, 1, true)
             .unwrap();
         assert_eq!(
             tx.get::<tables::AccountChangeSet>(tx_num),
@@ -162,12 +181,15 @@ mod tests {
         tx.delete::<tables::AccountChangeSet>(tx_num, None).unwrap();
 
         AccountInfoChangeSet::Destroyed { old: acc2 }
-            .apply_to_db(&tx, address, tx_num, true)
+            .apply_to_db(&tx, address, tx_num, 1, true)
             .unwrap();
         assert_eq!(tx.get::<tables::PlainAccountState>(address), Ok(None));
         assert_eq!(
             tx.get::<tables::AccountChangeSet>(tx_num),
             Ok(Some(AccountBeforeTx { address, info: Some(acc2) }))
         );
+        // the bytecode of the created account is no longer used
+        assert_eq!(tx.get::<tables::BytecodeRefCounts>(H256::zero()), Ok(None));
+        assert_eq!(tx.get::<tables::UnreferencedBytecodes>(1), Ok(Some(H256::zero())));
     }
 }
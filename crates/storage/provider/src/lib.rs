@@ -12,15 +12,16 @@
 mod traits;
 pub use traits::{
     AccountProvider, BlockExecutor, BlockHashProvider, BlockIdProvider, BlockProvider,
-    EvmEnvProvider, ExecutorFactory, HeaderProvider, StateProvider, StateProviderFactory,
-    TransactionsProvider, WithdrawalsProvider,
+    EvmEnvProvider, ExecutorFactory, HeaderProvider, ReceiptProvider, StateProvider,
+    StateProviderFactory, StateRangeProvider, TransactionsProvider, TrieRange, WithdrawalsProvider,
 };
 
 /// Provider trait implementations.
 pub mod providers;
 pub use providers::{
-    HistoricalStateProvider, HistoricalStateProviderRef, LatestStateProvider,
-    LatestStateProviderRef, ShareableDatabase,
+    CachedStateProvider, HashedStateProviderRef, HistoricalStateProvider,
+    HistoricalStateProviderRef, LatestStateProvider, LatestStateProviderRef, ShareableDatabase,
+    StateCache,
 };
 
 /// Merkle trie
@@ -29,6 +30,16 @@ pub mod trie;
 /// Execution result
 pub mod execution_result;
 
+/// State changes of executed blocks
+pub mod post_state;
+pub use post_state::PostState;
+
+/// Reference counting of contract bytecodes.
+mod bytecode;
+pub use bytecode::{
+    account_bytecode_hash, remove_unreferenced_bytecodes, update_bytecode_references,
+};
+
 /// Helper types for interacting with the database
 mod transaction;
 pub use transaction::{Transaction, TransactionError};
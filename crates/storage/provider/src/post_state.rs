@@ -0,0 +1,627 @@
+//! State changes of many executed blocks, accumulated in memory before they are written to the
+//! database.
+
+use crate::{
+    execution_result::{AccountChangeSet, AccountInfoChangeSet, ExecutionResult},
+    update_bytecode_references,
+};
+use reth_db::{
+    cursor::{DbCursorRO, DbDupCursorRO, DbDupCursorRW},
+    models::{AccountBeforeTx, TransitionIdAddress},
+    tables,
+    transaction::{DbTx, DbTxMut},
+    Error as DbError,
+};
+use reth_primitives::{
+    keccak256, Account, Address, BlockNumber, Bytecode, StorageEntry, TransitionId, H256, U256,
+};
+use reth_tracing::tracing::trace;
+use std::collections::{btree_map::Entry, BTreeMap};
+
+/// The state changes of consecutive executed blocks.
+///
+/// Changes of the same account or storage slot are merged, so the plain state tables are written
+/// once per changed entry. The changesets keep the value before every transition, and the earliest
+/// one if an entry is changed twice in a transition. All tables are written in key order by
+/// [PostState::write_to_db].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PostState {
+    /// The last added block.
+    block_number: BlockNumber,
+    /// The transition of the next change.
+    transition_id: TransitionId,
+    /// The changed accounts.
+    accounts: BTreeMap<Address, ChangedAccount>,
+    /// The changed storage.
+    storage: BTreeMap<Address, Storage>,
+    /// The accounts before every transition that changed them, `None` if they did not exist.
+    account_changes: BTreeMap<TransitionId, BTreeMap<Address, Option<Account>>>,
+    /// The storage changes of every transition.
+    storage_changes: BTreeMap<TransitionId, BTreeMap<Address, StorageTransition>>,
+    /// The created bytecodes.
+    bytecodes: BTreeMap<H256, Bytecode>,
+}
+
+/// An account changed by the blocks of a [PostState].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct ChangedAccount {
+    /// The account before the first change, `None` if it did not exist.
+    original: Option<Account>,
+    /// The account after the last change, `None` if it is destroyed.
+    current: Option<Account>,
+    /// The block of the last change.
+    block_number: BlockNumber,
+}
+
+/// The storage changes of an account in one transition.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+struct StorageTransition {
+    /// Whether the storage was wiped before the slots were changed.
+    wiped: bool,
+    /// The changed slots with their values before and after the transition.
+    slots: BTreeMap<H256, (U256, U256)>,
+}
+
+/// The changed storage of an account.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Storage {
+    /// Whether the storage was wiped, the slots in the database are then replaced by
+    /// [Storage::slots].
+    pub wiped: bool,
+    /// The changed slots with their latest value, zero if the slot is empty.
+    pub slots: BTreeMap<H256, U256>,
+}
+
+impl PostState {
+    /// Creates an empty post state on top of the given block, whose last transition is
+    /// `transition_id`.
+    pub fn new(parent_block_number: BlockNumber, transition_id: TransitionId) -> Self {
+        Self { block_number: parent_block_number, transition_id, ..Default::default() }
+    }
+
+    /// Returns the last added block.
+    pub fn block_number(&self) -> BlockNumber {
+        self.block_number
+    }
+
+    /// Returns the transition of the next change.
+    pub fn transition_id(&self) -> TransitionId {
+        self.transition_id
+    }
+
+    /// Returns the changed accounts with their latest value, `None` if they are destroyed.
+    pub fn accounts(&self) -> impl Iterator<Item = (Address, Option<Account>)> + '_ {
+        self.accounts.iter().map(|(address, account)| (*address, account.current))
+    }
+
+    /// Returns the changed storage of every account.
+    pub fn storage(&self) -> &BTreeMap<Address, Storage> {
+        &self.storage
+    }
+
+    /// Returns the created bytecodes.
+    pub fn bytecodes(&self) -> &BTreeMap<H256, Bytecode> {
+        &self.bytecodes
+    }
+
+    /// Adds the execution result of the next block.
+    ///
+    /// `has_state_clear_eip` tells whether [EIP-158](https://eips.ethereum.org/EIPS/eip-158) is
+    /// active at the block, empty accounts are then not created.
+    pub fn add_block(&mut self, result: ExecutionResult, has_state_clear_eip: bool) {
+        self.block_number += 1;
+        // System calls made before the transactions are part of the first transition of the block,
+        // so its changeset holds the state before the block.
+        for (address, changeset) in result.pre_block_changesets {
+            let AccountChangeSet { account, storage, wipe_storage } = changeset;
+            self.change_account(address, account, has_state_clear_eip);
+            self.change_storage(address, storage, wipe_storage);
+        }
+        for tx_changeset in result.tx_changesets {
+            for (address, changeset) in tx_changeset.changeset {
+                let AccountChangeSet { account, storage, wipe_storage } = changeset;
+                self.change_account(address, account, has_state_clear_eip);
+                self.change_storage(address, storage, wipe_storage);
+            }
+            for (hash, bytecode) in tx_changeset.new_bytecodes {
+                self.bytecodes.insert(hash, Bytecode(bytecode));
+            }
+            self.transition_id += 1;
+        }
+
+        // Post block changes, like block rewards, have a transition of their own.
+        for (address, account) in result.block_changesets {
+            self.change_account(address, account, has_state_clear_eip);
+        }
+        self.transition_id += 1;
+    }
+
+    fn change_account(
+        &mut self,
+        address: Address,
+        change: AccountInfoChangeSet,
+        has_state_clear_eip: bool,
+    ) {
+        let (old, new) = match change {
+            AccountInfoChangeSet::Changed { old, new } => (Some(old), Some(new)),
+            AccountInfoChangeSet::Created { new } => {
+                // Ignore account that are created empty and state clear (SpuriousDragon) hardfork
+                // is activated.
+                if has_state_clear_eip && new.is_empty() {
+                    return
+                }
+                (None, Some(new))
+            }
+            AccountInfoChangeSet::Destroyed { old } => (Some(old), None),
+            AccountInfoChangeSet::NoChange => return,
+        };
+        trace!(target: "provider::post_state", ?address, transition_id = self.transition_id, ?old, ?new, "Changing account");
+
+        self.account_changes.entry(self.transition_id).or_default().entry(address).or_insert(old);
+        let block_number = self.block_number;
+        self.accounts
+            .entry(address)
+            .and_modify(|account| {
+                account.current = new;
+                account.block_number = block_number;
+            })
+            .or_insert(ChangedAccount { original: old, current: new, block_number });
+    }
+
+    fn change_storage(
+        &mut self,
+        address: Address,
+        slots: BTreeMap<U256, (U256, U256)>,
+        wipe: bool,
+    ) {
+        if slots.is_empty() && !wipe {
+            return
+        }
+
+        let transition =
+            self.storage_changes.entry(self.transition_id).or_default().entry(address).or_default();
+        let storage = self.storage.entry(address).or_default();
+        if wipe {
+            transition.wiped = true;
+            storage.wiped = true;
+            storage.slots.clear();
+        }
+        for (key, (old_value, new_value)) in slots {
+            let key = H256(key.to_be_bytes());
+            trace!(target: "provider::post_state", ?address, transition_id = self.transition_id, ?key, ?old_value, ?new_value, "Changing storage");
+            transition.slots.entry(key).or_insert((old_value, new_value)).1 = new_value;
+            storage.slots.insert(key, new_value);
+        }
+    }
+
+    /// Writes the changesets, the plain state, the bytecodes and their reference counts to the
+    /// database.
+    ///
+    /// The changesets are appended, so no changesets of later transitions may exist.
+    pub fn write_to_db<'a, TX: DbTxMut<'a> + DbTx<'a>>(self, tx: &TX) -> Result<(), DbError> {
+        let PostState { accounts, storage, account_changes, storage_changes, bytecodes, .. } = self;
+
+        let mut account_changeset = tx.cursor_dup_write::<tables::AccountChangeSet>()?;
+        for (transition_id, changes) in account_changes {
+            for (address, info) in changes {
+                account_changeset.append_dup(transition_id, AccountBeforeTx { address, info })?;
+            }
+        }
+
+        // The changeset of wiped storage holds every slot before the wipe. The storage of those
+        // accounts is replayed from the database, which is not changed yet.
+        let mut storage_changeset = tx.cursor_dup_write::<tables::StorageChangeSet>()?;
+        let mut replayed = BTreeMap::<Address, BTreeMap<H256, U256>>::new();
+        for (transition_id, changes) in storage_changes {
+            for (address, transition) in changes {
+                let key = TransitionIdAddress((transition_id, address));
+                let mut replayed = if storage.get(&address).map_or(false, |storage| storage.wiped) {
+                    Some(match replayed.entry(address) {
+                        Entry::Occupied(entry) => entry.into_mut(),
+                        Entry::Vacant(entry) => entry.insert(plain_storage(tx, address)?),
+                    })
+                } else {
+                    None
+                };
+
+                if transition.wiped {
+                    let replayed = replayed.as_mut().expect("wiped storage is replayed");
+                    for (slot, value) in std::mem::take(*replayed) {
+                        if value != U256::ZERO {
+                            storage_changeset.append_dup(key, StorageEntry { key: slot, value })?;
+                        }
+                    }
+                } else {
+                    for (slot, (old_value, _)) in &transition.slots {
+                        storage_changeset
+                            .append_dup(key, StorageEntry { key: *slot, value: *old_value })?;
+                    }
+                }
+
+                if let Some(replayed) = replayed {
+                    replayed
+                        .extend(transition.slots.into_iter().map(|(slot, (_, new))| (slot, new)));
+                }
+            }
+        }
+
+        for (address, account) in accounts {
+            match account.current {
+                Some(current) => tx.put::<tables::PlainAccountState>(address, current)?,
+                None => {
+                    tx.delete::<tables::PlainAccountState>(address, None)?;
+                }
+            }
+            update_bytecode_references(
+                tx,
+                account.original.as_ref(),
+                account.current.as_ref(),
+                account.block_number,
+            )?;
+        }
+
+        let mut plain_storage_cursor = tx.cursor_dup_write::<tables::PlainStorageState>()?;
+        for (address, storage) in storage {
+            if storage.wiped {
+                tx.delete::<tables::PlainStorageState>(address, None)?;
+            }
+            for (key, value) in storage.slots {
+                if !storage.wiped {
+                    if let Some(entry) = plain_storage_cursor.seek_by_key_subkey(address, key)? {
+                        if entry.key == key {
+                            plain_storage_cursor.delete_current()?;
+                        }
+                    }
+                }
+                if value != U256::ZERO {
+                    plain_storage_cursor.upsert(address, StorageEntry { key, value })?;
+                }
+            }
+        }
+
+        for (hash, bytecode) in bytecodes {
+            // make different types of bytecode. Checked and maybe even analyzed (needs to
+            // be packed). Currently save only raw bytes.
+            trace!(target: "provider::post_state", ?hash, len = bytecode.0.bytes().len(), "Inserting bytecode");
+            tx.put::<tables::Bytecodes>(hash, bytecode)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes the changed accounts and storage slots to the hashed state, which is otherwise only
+    /// updated from the changesets by the hashing stages.
+    ///
+    /// This keeps the hashed state up to date with every execution, for state that is read from
+    /// it instead of the plain state. The hashed storage of wiped accounts is removed entirely,
+    /// together with the stored nodes of their storage trie, so their storage root is recomputed
+    /// from scratch.
+    pub fn write_hashed_state<'a, TX: DbTxMut<'a> + DbTx<'a>>(
+        &self,
+        tx: &TX,
+    ) -> Result<(), DbError> {
+        let accounts = self
+            .accounts
+            .iter()
+            .map(|(address, account)| (keccak256(address), account.current))
+            .collect::<BTreeMap<_, _>>();
+        for (hashed_address, account) in accounts {
+            match account {
+                Some(account) => tx.put::<tables::HashedAccount>(hashed_address, account)?,
+                None => {
+                    tx.delete::<tables::HashedAccount>(hashed_address, None)?;
+                }
+            }
+        }
+
+        let storage = self
+            .storage
+            .iter()
+            .map(|(address, storage)| (keccak256(address), storage))
+            .collect::<BTreeMap<_, _>>();
+        let mut hashed_storage_cursor = tx.cursor_dup_write::<tables::HashedStorage>()?;
+        for (hashed_address, storage) in storage {
+            if storage.wiped {
+                tx.delete::<tables::HashedStorage>(hashed_address, None)?;
+                tx.delete::<tables::StoragesTrie>(hashed_address, None)?;
+            }
+            let slots = storage
+                .slots
+                .iter()
+                .map(|(key, value)| (keccak256(key), *value))
+                .collect::<BTreeMap<_, _>>();
+            for (key, value) in slots {
+                if !storage.wiped {
+                    if let Some(entry) =
+                        hashed_storage_cursor.seek_by_key_subkey(hashed_address, key)?
+                    {
+                        if entry.key == key {
+                            hashed_storage_cursor.delete_current()?;
+                        }
+                    }
+                }
+                if value != U256::ZERO {
+                    hashed_storage_cursor.upsert(hashed_address, StorageEntry { key, value })?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Reads the storage of an account from [tables::PlainStorageState].
+fn plain_storage<'a, TX: DbTx<'a>>(
+    tx: &TX,
+    address: Address,
+) -> Result<BTreeMap<H256, U256>, DbError> {
+    let mut cursor = tx.cursor_dup_read::<tables::PlainStorageState>()?;
+    let storage = cursor
+        .walk_dup(Some(address), None)?
+        .map(|entry| entry.map(|(_, entry)| (entry.key, entry.value)))
+        .collect();
+    storage
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution_result::TransactionChangeSet;
+    use reth_db::{database::Database, mdbx::test_utils::create_test_rw_db};
+    use reth_primitives::Receipt;
+
+    fn tx_changeset(
+        address: Address,
+        account: AccountInfoChangeSet,
+        storage: impl IntoIterator<Item = (u64, u64, u64)>,
+        wipe_storage: bool,
+    ) -> TransactionChangeSet {
+        let storage = storage
+            .into_iter()
+            .map(|(key, old, new)| (U256::from(key), (U256::from(old), U256::from(new))))
+            .collect();
+        TransactionChangeSet {
+            receipt: Receipt::default(),
+            changeset: BTreeMap::from([(
+                address,
+                AccountChangeSet { account, storage, wipe_storage },
+            )]),
+            new_bytecodes: BTreeMap::new(),
+        }
+    }
+
+    fn slot(key: u64) -> H256 {
+        H256::from_low_u64_be(key)
+    }
+
+    #[test]
+    fn merge_and_write_blocks() {
+        let db = create_test_rw_db();
+        let tx = db.tx_mut().unwrap();
+
+        let address = Address::random();
+        let original = Account { nonce: 1, ..Default::default() };
+        tx.put::<tables::PlainAccountState>(address, original).unwrap();
+        for (key, value) in [(1, 1), (2, 2)] {
+            tx.put::<tables::PlainStorageState>(
+                address,
+                StorageEntry { key: slot(key), value: U256::from(value) },
+            )
+            .unwrap();
+        }
+
+        let first = Account { nonce: 2, ..original };
+        let second = Account { nonce: 3, ..original };
+        let mut post_state = PostState::new(0, 10);
+        // Block 1 changes the account and a slot.
+        post_state.add_block(
+            ExecutionResult {
+                tx_changesets: vec![tx_changeset(
+                    address,
+                    AccountInfoChangeSet::Changed { old: original, new: first },
+                    [(1, 1, 5)],
+                    false,
+                )],
+                pre_block_changesets: BTreeMap::new(),
+                block_changesets: BTreeMap::new(),
+            },
+            true,
+        );
+        // Block 2 wipes the storage and writes another slot.
+        post_state.add_block(
+            ExecutionResult {
+                tx_changesets: vec![tx_changeset(
+                    address,
+                    AccountInfoChangeSet::Changed { old: first, new: second },
+                    [(3, 0, 7)],
+                    true,
+                )],
+                pre_block_changesets: BTreeMap::new(),
+                block_changesets: BTreeMap::new(),
+            },
+            true,
+        );
+        assert_eq!(post_state.block_number(), 2);
+        assert_eq!(post_state.transition_id(), 14);
+        assert_eq!(post_state.accounts().collect::<Vec<_>>(), vec![(address, Some(second))]);
+        assert_eq!(
+            post_state.storage()[&address],
+            Storage { wiped: true, slots: BTreeMap::from([(slot(3), U256::from(7))]) }
+        );
+
+        post_state.write_to_db(&tx).unwrap();
+
+        // The plain state holds the latest values.
+        assert_eq!(tx.get::<tables::PlainAccountState>(address), Ok(Some(second)));
+        assert_eq!(
+            plain_storage(&tx, address).unwrap(),
+            BTreeMap::from([(slot(3), U256::from(7))])
+        );
+
+        // The changesets hold the values before every transition.
+        let account_changes = tx
+            .cursor_read::<tables::AccountChangeSet>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            account_changes,
+            vec![
+                (10, AccountBeforeTx { address, info: Some(original) }),
+                (12, AccountBeforeTx { address, info: Some(first) }),
+            ]
+        );
+        let storage_changes = tx
+            .cursor_read::<tables::StorageChangeSet>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let entry = |key, value| StorageEntry { key: slot(key), value: U256::from(value) };
+        assert_eq!(
+            storage_changes,
+            vec![
+                (TransitionIdAddress((10, address)), entry(1, 1)),
+                (TransitionIdAddress((12, address)), entry(1, 5)),
+                (TransitionIdAddress((12, address)), entry(2, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn pre_block_changes_before_transactions() {
+        let db = create_test_rw_db();
+        let tx = db.tx_mut().unwrap();
+
+        let address = Address::random();
+        let original = Account { nonce: 1, ..Default::default() };
+        tx.put::<tables::PlainAccountState>(address, original).unwrap();
+        tx.put::<tables::PlainStorageState>(
+            address,
+            StorageEntry { key: slot(1), value: U256::from(1) },
+        )
+        .unwrap();
+
+        let changed = Account { nonce: 2, ..original };
+        let mut post_state = PostState::new(0, 10);
+        // The system call and the transaction change the same slot.
+        post_state.add_block(
+            ExecutionResult {
+                tx_changesets: vec![tx_changeset(
+                    address,
+                    AccountInfoChangeSet::Changed { old: original, new: changed },
+                    [(1, 2, 3)],
+                    false,
+                )],
+                pre_block_changesets: tx_changeset(
+                    address,
+                    AccountInfoChangeSet::NoChange,
+                    [(1, 1, 2), (2, 0, 4)],
+                    false,
+                )
+                .changeset,
+                block_changesets: BTreeMap::new(),
+            },
+            true,
+        );
+        assert_eq!(post_state.transition_id(), 12);
+        assert_eq!(
+            post_state.storage()[&address],
+            Storage {
+                wiped: false,
+                slots: BTreeMap::from([(slot(1), U256::from(3)), (slot(2), U256::from(4))])
+            }
+        );
+
+        post_state.write_to_db(&tx).unwrap();
+
+        // The changeset of the first transition holds the state before the block.
+        let account_changes = tx
+            .cursor_read::<tables::AccountChangeSet>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(account_changes, vec![(10, AccountBeforeTx { address, info: Some(original) })]);
+        let storage_changes = tx
+            .cursor_read::<tables::StorageChangeSet>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let entry = |key, value| StorageEntry { key: slot(key), value: U256::from(value) };
+        assert_eq!(
+            storage_changes,
+            vec![
+                (TransitionIdAddress((10, address)), entry(1, 1)),
+                (TransitionIdAddress((10, address)), entry(2, 0)),
+            ]
+        );
+    }
+
+    #[test]
+    fn write_hashed_state() {
+        let db = create_test_rw_db();
+        let tx = db.tx_mut().unwrap();
+
+        let (kept, destroyed) = (Address::random(), Address::random());
+        let original = Account { nonce: 1, ..Default::default() };
+        for address in [kept, destroyed] {
+            tx.put::<tables::HashedAccount>(keccak256(address), original).unwrap();
+        }
+        // Slots that are only in the hashed state, e.g. from a state download.
+        for key in [1, 2] {
+            tx.put::<tables::HashedStorage>(
+                keccak256(kept),
+                StorageEntry { key: keccak256(slot(key)), value: U256::from(key) },
+            )
+            .unwrap();
+        }
+        tx.put::<tables::StoragesTrie>(keccak256(kept), Default::default()).unwrap();
+
+        let changed = Account { nonce: 2, ..original };
+        let mut post_state = PostState::new(0, 0);
+        post_state.add_block(
+            ExecutionResult {
+                tx_changesets: vec![
+                    tx_changeset(
+                        kept,
+                        AccountInfoChangeSet::Changed { old: original, new: changed },
+                        [(3, 0, 7)],
+                        true,
+                    ),
+                    tx_changeset(
+                        destroyed,
+                        AccountInfoChangeSet::Destroyed { old: original },
+                        [],
+                        false,
+                    ),
+                ],
+                pre_block_changesets: BTreeMap::new(),
+                block_changesets: BTreeMap::new(),
+            },
+            true,
+        );
+        post_state.write_hashed_state(&tx).unwrap();
+
+        assert_eq!(tx.get::<tables::HashedAccount>(keccak256(kept)), Ok(Some(changed)));
+        assert_eq!(tx.get::<tables::HashedAccount>(keccak256(destroyed)), Ok(None));
+        let storage = tx
+            .cursor_dup_read::<tables::HashedStorage>()
+            .unwrap()
+            .walk_dup(Some(keccak256(kept)), None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(
+            storage,
+            vec![(keccak256(kept), StorageEntry { key: keccak256(slot(3)), value: U256::from(7) })]
+        );
+        assert_eq!(tx.get::<tables::StoragesTrie>(keccak256(kept)), Ok(None));
+    }
+}
@@ -1,28 +1,38 @@
 use crate::{
+    trie::{DBTrieLoader, EthAccount},
     BlockHashProvider, BlockIdProvider, BlockProvider, EvmEnvProvider, HeaderProvider,
-    ProviderError, StateProviderFactory, TransactionsProvider, WithdrawalsProvider,
+    ProviderError, ReceiptProvider, StateProviderFactory, StateRangeProvider, TransactionsProvider,
+    TrieRange, WithdrawalsProvider,
 };
 use reth_db::{
-    cursor::DbCursorRO,
+    cursor::{DbCursorRO, DbDupCursorRO},
     database::{Database, DatabaseGAT},
     tables,
     transaction::DbTx,
 };
 use reth_interfaces::Result;
 use reth_primitives::{
-    Block, BlockHash, BlockId, BlockNumber, ChainInfo, ChainSpec, Hardfork, Head, Header,
+    trie::Nibbles, Account, Block, BlockHash, BlockId, BlockNumber, Bytes, ChainInfo, ChainSpec,
+    Hardfork, Head, Header, PrunePart, Receipt, SnapSyncCheckpoint, StorageEntry,
     TransactionSigned, TxHash, TxNumber, Withdrawal, H256, U256,
 };
 use reth_revm_primitives::{
     config::revm_spec,
     env::{fill_block_env, fill_cfg_and_block_env, fill_cfg_env},
 };
+use reth_rlp::{encode_fixed_size, Encodable};
+use reth_static_files::{StaticFileError, StaticFileSegment, StaticFiles};
 use revm_primitives::{BlockEnv, CfgEnv, SpecId};
-use std::{ops::RangeBounds, sync::Arc};
+use std::{
+    ops::{Bound, Range, RangeBounds},
+    sync::Arc,
+};
 
 mod state;
 pub use state::{
+    cached::{CachedStateProvider, StateCache},
     chain::ChainState,
+    hashed::HashedStateProviderRef,
     historical::{HistoricalStateProvider, HistoricalStateProviderRef},
     latest::{LatestStateProvider, LatestStateProviderRef},
 };
@@ -35,33 +45,105 @@ pub struct ShareableDatabase<DB> {
     db: DB,
     /// Chain spec
     chain_spec: Arc<ChainSpec>,
+    /// Static files holding the ancient headers and transactions, if they were moved out of the
+    /// database.
+    static_files: Option<Arc<StaticFiles>>,
 }
 
 impl<DB> ShareableDatabase<DB> {
     /// create new database provider
     pub fn new(db: DB, chain_spec: ChainSpec) -> Self {
-        Self { db, chain_spec: Arc::new(chain_spec) }
+        Self { db, chain_spec: Arc::new(chain_spec), static_files: None }
+    }
+
+    /// Reads the data moved out of the database from the given static files.
+    pub fn with_static_files(mut self, static_files: Arc<StaticFiles>) -> Self {
+        self.static_files = Some(static_files);
+        self
+    }
+
+    /// Reads a value from the static files, if there are any.
+    fn from_static_files<T>(
+        &self,
+        read: impl FnOnce(&StaticFiles) -> std::result::Result<Option<T>, StaticFileError>,
+    ) -> Result<Option<T>> {
+        match &self.static_files {
+            Some(static_files) => Ok(read(static_files)?),
+            None => Ok(None),
+        }
+    }
+
+    /// Reads a range of transactions, the frozen ones from the static files and the rest from the
+    /// database.
+    fn read_transactions<'tx, C: DbCursorRO<'tx, tables::Transactions>>(
+        &self,
+        cursor: &mut C,
+        range: Range<TxNumber>,
+    ) -> Result<Vec<TransactionSigned>> {
+        let mut transactions = Vec::with_capacity(range.end.saturating_sub(range.start) as usize);
+        let mut next = range.start;
+        if let Some(static_files) = &self.static_files {
+            let frozen = static_files.next_key(StaticFileSegment::Transactions).min(range.end);
+            while next < frozen {
+                transactions.extend(static_files.transaction(next)?);
+                next += 1;
+            }
+        }
+        if next < range.end {
+            for entry in cursor.walk_range(next..range.end)? {
+                transactions.push(entry?.1);
+            }
+        }
+        Ok(transactions)
+    }
+
+    /// Reads the receipts of a range of transactions, the frozen ones from the static files and
+    /// the rest from the database.
+    fn read_receipts<'tx, C: DbCursorRO<'tx, tables::Receipts>>(
+        &self,
+        cursor: &mut C,
+        range: Range<TxNumber>,
+    ) -> Result<Vec<Receipt>> {
+        let mut receipts = Vec::with_capacity(range.end.saturating_sub(range.start) as usize);
+        let mut next = range.start;
+        if let Some(static_files) = &self.static_files {
+            let frozen = static_files.next_key(StaticFileSegment::Receipts).min(range.end);
+            while next < frozen {
+                receipts.extend(static_files.receipt(next)?);
+                next += 1;
+            }
+        }
+        if next < range.end {
+            for entry in cursor.walk_range(next..range.end)? {
+                receipts.push(entry?.1);
+            }
+        }
+        Ok(receipts)
     }
 }
 
 impl<DB: Clone> Clone for ShareableDatabase<DB> {
     fn clone(&self) -> Self {
-        Self { db: self.db.clone(), chain_spec: Arc::clone(&self.chain_spec) }
+        Self {
+            db: self.db.clone(),
+            chain_spec: Arc::clone(&self.chain_spec),
+            static_files: self.static_files.clone(),
+        }
     }
 }
 
 impl<DB: Database> HeaderProvider for ShareableDatabase<DB> {
     fn header(&self, block_hash: &BlockHash) -> Result<Option<Header>> {
-        self.db.view(|tx| {
-            if let Some(num) = tx.get::<tables::HeaderNumbers>(*block_hash)? {
-                Ok(tx.get::<tables::Headers>(num)?)
-            } else {
-                Ok(None)
-            }
-        })?
+        match self.db.view(|tx| tx.get::<tables::HeaderNumbers>(*block_hash))?? {
+            Some(num) => self.header_by_number(num),
+            None => Ok(None),
+        }
     }
 
     fn header_by_number(&self, num: BlockNumber) -> Result<Option<Header>> {
+        if let Some(header) = self.from_static_files(|static_files| static_files.header(num))? {
+            return Ok(Some(header))
+        }
         Ok(self.db.view(|tx| tx.get::<tables::Headers>(num))??)
     }
 
@@ -80,24 +162,44 @@ impl<DB: Database> HeaderProvider for ShareableDatabase<DB> {
     }
 
     fn headers_range(&self, range: impl RangeBounds<BlockNumber>) -> Result<Vec<Header>> {
-        self.db
-            .view(|tx| {
-                let mut cursor = tx.cursor_read::<tables::Headers>()?;
-                cursor
-                    .walk_range(range)?
-                    .map(|result| result.map(|(_, header)| header).map_err(Into::into))
-                    .collect::<Result<Vec<_>>>()
-            })?
-            .map_err(Into::into)
+        let mut headers = Vec::new();
+        let mut start = match range.start_bound() {
+            Bound::Included(number) => *number,
+            Bound::Excluded(number) => number + 1,
+            Bound::Unbounded => 0,
+        };
+
+        // The frozen headers are a prefix of the chain, read them from the static files first.
+        if let Some(static_files) = &self.static_files {
+            while range.contains(&start) {
+                let Some(header) = static_files.header(start)? else { break };
+                headers.push(header);
+                start += 1;
+            }
+        }
+
+        let end = range.end_bound().cloned();
+        self.db.view(|tx| {
+            let mut cursor = tx.cursor_read::<tables::Headers>()?;
+            for entry in cursor.walk_range((Bound::Included(start), end))? {
+                headers.push(entry?.1);
+            }
+            Ok::<_, reth_interfaces::db::Error>(())
+        })??;
+        Ok(headers)
     }
 }
 
 impl<DB: Database> BlockHashProvider for ShareableDatabase<DB> {
     fn block_hash(&self, number: U256) -> Result<Option<H256>> {
         // TODO: This unwrap is potentially unsafe
-        self.db
-            .view(|tx| tx.get::<tables::CanonicalHeaders>(number.try_into().unwrap()))?
-            .map_err(Into::into)
+        let number: BlockNumber = number.try_into().unwrap();
+        if let Some(hash) =
+            self.from_static_files(|static_files| static_files.canonical_hash(number))?
+        {
+            return Ok(Some(hash))
+        }
+        self.db.view(|tx| tx.get::<tables::CanonicalHeaders>(number))?.map_err(Into::into)
     }
 }
 
@@ -155,19 +257,21 @@ impl<DB: Database> BlockProvider for ShareableDatabase<DB> {
 
 impl<DB: Database> TransactionsProvider for ShareableDatabase<DB> {
     fn transaction_by_id(&self, id: TxNumber) -> Result<Option<TransactionSigned>> {
+        if let Some(transaction) =
+            self.from_static_files(|static_files| static_files.transaction(id))?
+        {
+            return Ok(Some(transaction))
+        }
         self.db.view(|tx| tx.get::<tables::Transactions>(id))?.map_err(Into::into)
     }
 
     fn transaction_by_hash(&self, hash: TxHash) -> Result<Option<TransactionSigned>> {
-        self.db
-            .view(|tx| {
-                if let Some(id) = tx.get::<tables::TxHashNumber>(hash)? {
-                    tx.get::<tables::Transactions>(id)
-                } else {
-                    Ok(None)
-                }
-            })?
-            .map_err(Into::into)
+        // Transactions of blocks whose lookup was pruned are not found either, telling them apart
+        // from unknown hashes would require hashing every transaction of these blocks.
+        match self.db.view(|tx| tx.get::<tables::TxHashNumber>(hash))?? {
+            Some(id) => self.transaction_by_id(id),
+            None => Ok(None),
+        }
     }
 
     fn transactions_by_block(&self, id: BlockId) -> Result<Option<Vec<TransactionSigned>>> {
@@ -179,11 +283,7 @@ impl<DB: Database> TransactionsProvider for ShareableDatabase<DB> {
                     Ok(Some(Vec::default()))
                 } else {
                     let mut tx_cursor = tx.cursor_read::<tables::Transactions>()?;
-                    let transactions = tx_cursor
-                        .walk_range(tx_range)?
-                        .map(|result| result.map(|(_, tx)| tx))
-                        .collect::<std::result::Result<Vec<_>, _>>()?;
-                    Ok(Some(transactions))
+                    Ok(Some(self.read_transactions(&mut tx_cursor, tx_range)?))
                 }
             } else {
                 Ok(None)
@@ -207,18 +307,37 @@ impl<DB: Database> TransactionsProvider for ShareableDatabase<DB> {
             if body.tx_id_range().is_empty() {
                 results.push(Vec::default());
             } else {
-                results.push(
-                    tx_cursor
-                        .walk_range(tx_range)?
-                        .map(|result| result.map(|(_, tx)| tx))
-                        .collect::<std::result::Result<Vec<_>, _>>()?,
-                );
+                results.push(self.read_transactions(&mut tx_cursor, tx_range)?);
             }
         }
         Ok(results)
     }
 }
 
+impl<DB: Database> ReceiptProvider for ShareableDatabase<DB> {
+    fn receipt(&self, id: TxNumber) -> Result<Option<Receipt>> {
+        if let Some(receipt) = self.from_static_files(|static_files| static_files.receipt(id))? {
+            return Ok(Some(receipt))
+        }
+        let tx = self.db.tx()?;
+        let receipt = tx.get::<tables::Receipts>(id)?;
+        if receipt.is_none() {
+            ensure_tx_history_available(&tx, PrunePart::Receipts, id)?;
+        }
+        Ok(receipt)
+    }
+
+    fn receipts_by_block(&self, block: BlockId) -> Result<Option<Vec<Receipt>>> {
+        let Some(number) = self.block_number_for_id(block)? else { return Ok(None) };
+        let tx = self.db.tx()?;
+        let Some(body) = tx.get::<tables::BlockBodies>(number)? else { return Ok(None) };
+        ensure_history_available(&tx, PrunePart::Receipts, number)?;
+
+        let mut cursor = tx.cursor_read::<tables::Receipts>()?;
+        Ok(Some(self.read_receipts(&mut cursor, body.tx_id_range())?))
+    }
+}
+
 impl<DB: Database> WithdrawalsProvider for ShareableDatabase<DB> {
     fn withdrawals_by_block(&self, id: BlockId, timestamp: u64) -> Result<Option<Vec<Withdrawal>>> {
         if self.chain_spec.fork(Hardfork::Shanghai).active_at_timestamp(timestamp) {
@@ -301,17 +420,25 @@ impl<DB: Database> StateProviderFactory for ShareableDatabase<DB> {
 
     /// Storage provider for latest block
     fn latest(&self) -> Result<Self::LatestSP<'_>> {
-        Ok(LatestStateProvider::new(self.db.tx()?))
+        let tx = self.db.tx()?;
+        if is_snap_synced(&tx)? {
+            return Ok(LatestStateProvider::new_hashed(tx))
+        }
+        Ok(LatestStateProvider::new(tx))
     }
 
     fn history_by_block_number(&self, block_number: BlockNumber) -> Result<Self::HistorySP<'_>> {
         let tx = self.db.tx()?;
+        ensure_state_history_available(&tx, block_number)?;
 
         // get transition id
         let transition = tx
             .get::<tables::BlockTransitionIndex>(block_number)?
             .ok_or(ProviderError::BlockTransition { block_number })?;
 
+        if is_snap_synced(&tx)? {
+            return Ok(HistoricalStateProvider::new_hashed(tx, transition))
+        }
         Ok(HistoricalStateProvider::new(tx, transition))
     }
 
@@ -321,22 +448,254 @@ impl<DB: Database> StateProviderFactory for ShareableDatabase<DB> {
         let block_number = tx
             .get::<tables::HeaderNumbers>(block_hash)?
             .ok_or(ProviderError::BlockHash { block_hash })?;
+        ensure_state_history_available(&tx, block_number)?;
 
         // get transition id
         let transition = tx
             .get::<tables::BlockTransitionIndex>(block_number)?
             .ok_or(ProviderError::BlockTransition { block_number })?;
 
+        if is_snap_synced(&tx)? {
+            return Ok(HistoricalStateProvider::new_hashed(tx, transition))
+        }
         Ok(HistoricalStateProvider::new(tx, transition))
     }
 }
 
+impl<DB: Database> StateRangeProvider for ShareableDatabase<DB> {
+    fn account_range(
+        &self,
+        root: H256,
+        start: H256,
+        limit: H256,
+        max_bytes: usize,
+    ) -> Result<Option<TrieRange<(H256, Account, H256)>>> {
+        let tx = self.db.tx()?;
+        let trie = DBTrieLoader::default();
+
+        let mut entries = Vec::new();
+        let mut size = 0;
+        let mut cursor = tx.cursor_read::<tables::HashedAccount>()?;
+        let mut entry = cursor.seek(start)?;
+        while let Some((hashed_address, account)) = entry {
+            if size >= max_bytes {
+                break
+            }
+            let (storage_root, _) = trie.storage_proof(&tx, hashed_address, &[])?;
+            size += H256::len_bytes() + EthAccount::from_with_root(account, storage_root).length();
+            entries.push((hashed_address, account, storage_root));
+            if hashed_address >= limit {
+                break
+            }
+            entry = cursor.next()?;
+        }
+
+        let mut targets = vec![Nibbles::unpack(start)];
+        targets.extend(entries.last().map(|(hashed_address, ..)| Nibbles::unpack(hashed_address)));
+        let (state_root, proof) = trie.state_proof(&tx, &targets)?;
+        if state_root != root {
+            return Ok(None)
+        }
+
+        Ok(Some(TrieRange {
+            entries,
+            proof: proof.into_iter().map(|(_, node)| node.into()).collect(),
+        }))
+    }
+
+    fn storage_ranges(
+        &self,
+        root: H256,
+        accounts: &[H256],
+        start: H256,
+        limit: H256,
+        max_bytes: usize,
+    ) -> Result<Option<TrieRange<Vec<(H256, U256)>>>> {
+        let tx = self.db.tx()?;
+        let trie = DBTrieLoader::default();
+        if trie.state_proof(&tx, &[])?.0 != root {
+            return Ok(None)
+        }
+
+        let mut range = TrieRange::default();
+        let mut size = 0;
+        let mut cursor = tx.cursor_dup_read::<tables::HashedStorage>()?;
+        for (idx, hashed_address) in accounts.iter().enumerate() {
+            if size >= max_bytes {
+                break
+            }
+            let start = if idx == 0 { start } else { H256::zero() };
+            let limit = if idx == accounts.len() - 1 { limit } else { H256::repeat_byte(0xff) };
+
+            let mut slots = Vec::new();
+            let mut complete = true;
+            let mut entry = cursor.seek_by_key_subkey(*hashed_address, start)?;
+            while let Some(StorageEntry { key, value }) = entry {
+                if size >= max_bytes {
+                    complete = false;
+                    break
+                }
+                size += H256::len_bytes() + encode_fixed_size(&value).len();
+                slots.push((key, value));
+                if key >= limit {
+                    complete = false;
+                    break
+                }
+                entry = cursor.next_dup()?.map(|(_, entry)| entry);
+            }
+
+            if start.is_zero() && complete {
+                range.entries.push(slots);
+                continue
+            }
+
+            // The range of the account is partial, so it ends the response.
+            let mut targets = vec![Nibbles::unpack(start)];
+            targets.extend(slots.last().map(|(key, _)| Nibbles::unpack(key)));
+            let (_, proof) = trie.storage_proof(&tx, *hashed_address, &targets)?;
+            range.proof = proof.into_iter().map(|(_, node)| node.into()).collect();
+            range.entries.push(slots);
+            break
+        }
+
+        Ok(Some(range))
+    }
+
+    fn trie_nodes(
+        &self,
+        root: H256,
+        paths: &[(Option<H256>, Vec<Nibbles>)],
+        max_bytes: usize,
+    ) -> Result<Option<Vec<Bytes>>> {
+        let tx = self.db.tx()?;
+        let trie = DBTrieLoader::default();
+        if trie.state_proof(&tx, &[])?.0 != root {
+            return Ok(None)
+        }
+
+        let mut nodes = Vec::new();
+        let mut size = 0;
+        'sets: for (hashed_address, paths) in paths {
+            let (_, proof) = match hashed_address {
+                Some(hashed_address) => trie.storage_proof(&tx, *hashed_address, paths)?,
+                None => trie.state_proof(&tx, paths)?,
+            };
+            for path in paths {
+                if size >= max_bytes {
+                    break 'sets
+                }
+                match proof.iter().find(|(node_path, _)| node_path == path) {
+                    Some((_, node)) => {
+                        size += node.len();
+                        nodes.push(node.clone().into());
+                    }
+                    None => break 'sets,
+                }
+            }
+        }
+
+        Ok(Some(nodes))
+    }
+}
+
+/// Returns whether the state was downloaded with a snap sync, which only downloads the hashed
+/// state, so the plain state is incomplete.
+///
+/// The snap sync saves its checkpoint under [SnapSyncCheckpoint::KEY] once it starts.
+fn is_snap_synced<'a>(tx: &impl DbTx<'a>) -> Result<bool> {
+    Ok(tx.get::<tables::SyncStageProgress>(SnapSyncCheckpoint::KEY.as_bytes().to_vec())?.is_some())
+}
+
+/// Returns an error if the changesets needed to recreate the state at the given block were pruned.
+fn ensure_state_history_available<'a>(tx: &impl DbTx<'a>, block_number: BlockNumber) -> Result<()> {
+    for part in [PrunePart::AccountHistory, PrunePart::StorageHistory] {
+        // The state of the highest pruned block is still available, as it only needs the
+        // changesets of later blocks.
+        if let Some(available_from) = tx.get::<tables::PruneCheckpoints>(part)? {
+            if block_number < available_from {
+                return Err(ProviderError::HistoryPruned {
+                    part,
+                    block_number: Some(block_number),
+                    available_from,
+                }
+                .into())
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Returns an error if the given part of the history of the block was pruned.
+fn ensure_history_available<'a>(
+    tx: &impl DbTx<'a>,
+    part: PrunePart,
+    block_number: BlockNumber,
+) -> Result<()> {
+    match tx.get::<tables::PruneCheckpoints>(part)? {
+        Some(pruned_to) if block_number <= pruned_to => Err(ProviderError::HistoryPruned {
+            part,
+            block_number: Some(block_number),
+            available_from: pruned_to + 1,
+        }
+        .into()),
+        _ => Ok(()),
+    }
+}
+
+/// Returns an error if the given part of the history of the transaction was pruned.
+///
+/// The block of a pruned transaction is found by a binary search over [tables::BlockBodies].
+fn ensure_tx_history_available<'a>(
+    tx: &impl DbTx<'a>,
+    part: PrunePart,
+    tx_number: TxNumber,
+) -> Result<()> {
+    let Some(pruned_to) = tx.get::<tables::PruneCheckpoints>(part)? else { return Ok(()) };
+
+    let next_tx_number = |number: BlockNumber| -> Result<TxNumber> {
+        let body =
+            tx.get::<tables::BlockBodies>(number)?.ok_or(ProviderError::BlockBody { number })?;
+        Ok(body.start_tx_id + body.tx_count)
+    };
+    if tx_number >= next_tx_number(pruned_to)? {
+        return Ok(())
+    }
+
+    let (mut low, mut high) = (0, pruned_to);
+    while low < high {
+        let mid = low + (high - low) / 2;
+        if tx_number < next_tx_number(mid)? {
+            high = mid;
+        } else {
+            low = mid + 1;
+        }
+    }
+    Err(ProviderError::HistoryPruned {
+        part,
+        block_number: Some(low),
+        available_from: pruned_to + 1,
+    }
+    .into())
+}
+
 #[cfg(test)]
 mod tests {
     use super::ShareableDatabase;
-    use crate::{BlockIdProvider, StateProviderFactory};
-    use reth_db::mdbx::{test_utils::create_test_db, EnvKind, WriteMap};
-    use reth_primitives::{ChainSpecBuilder, H256};
+    use crate::{
+        trie::DBTrieLoader, AccountProvider, BlockIdProvider, ProviderError, ReceiptProvider,
+        StateProvider, StateProviderFactory, StateRangeProvider, Transaction, TransactionsProvider,
+    };
+    use reth_db::{
+        database::Database,
+        mdbx::{test_utils::create_test_db, EnvKind, WriteMap},
+        models::StoredBlockBody,
+        tables,
+        transaction::DbTxMut,
+    };
+    use reth_primitives::{
+        keccak256, proofs::EMPTY_ROOT, trie::Nibbles, Account, Address, BlockNumberOrTag,
+        ChainSpecBuilder, PrunePart, Receipt, SnapSyncCheckpoint, StorageEntry, H256, U256,
+    };
 
     #[test]
     fn common_history_provider() {
@@ -358,4 +717,172 @@ mod tests {
         assert_eq!(chain_info.last_finalized, None);
         assert_eq!(chain_info.safe_finalized, None);
     }
+
+    #[test]
+    fn pruned_history() {
+        let chain_spec = ChainSpecBuilder::mainnet().build();
+        let db = create_test_db::<WriteMap>(EnvKind::RW);
+
+        // Blocks 0 to 3 with two transactions each, the history of blocks 0 to 2 is pruned.
+        let mut tx = Transaction::new(db.as_ref()).unwrap();
+        for number in 0..4 {
+            let body = StoredBlockBody { start_tx_id: number * 2, tx_count: 2 };
+            tx.put::<tables::BlockBodies>(number, body).unwrap();
+        }
+        for id in 6..8 {
+            tx.put::<tables::Receipts>(id, Receipt::default()).unwrap();
+        }
+        tx.put::<tables::PruneCheckpoints>(PrunePart::Receipts, 2).unwrap();
+        tx.put::<tables::PruneCheckpoints>(PrunePart::TransactionLookup, 2).unwrap();
+        tx.commit().unwrap();
+        drop(tx);
+
+        let provider = ShareableDatabase::new(db, chain_spec);
+        let pruned = |block_number, part| {
+            reth_interfaces::Error::from(ProviderError::HistoryPruned {
+                part,
+                block_number,
+                available_from: 3,
+            })
+        };
+
+        assert_eq!(provider.receipt(0).unwrap_err(), pruned(Some(0), PrunePart::Receipts));
+        assert_eq!(provider.receipt(3).unwrap_err(), pruned(Some(1), PrunePart::Receipts));
+        assert_eq!(provider.receipt(5).unwrap_err(), pruned(Some(2), PrunePart::Receipts));
+        assert_eq!(provider.receipt(6), Ok(Some(Receipt::default())));
+        assert_eq!(provider.receipt(8), Ok(None));
+
+        assert_eq!(
+            provider.receipts_by_block(BlockNumberOrTag::Number(2).into()).unwrap_err(),
+            pruned(Some(2), PrunePart::Receipts)
+        );
+        assert_eq!(
+            provider.receipts_by_block(BlockNumberOrTag::Number(3).into()),
+            Ok(Some(vec![Receipt::default(); 2]))
+        );
+
+        // unknown hashes are not reported as pruned
+        assert_eq!(provider.transaction_by_hash(H256::zero()), Ok(None));
+    }
+
+    #[test]
+    fn reads_hashed_state_after_snap_sync() {
+        let chain_spec = ChainSpecBuilder::mainnet().build();
+        let db = create_test_db::<WriteMap>(EnvKind::RW);
+
+        // An account that was downloaded by the snap sync and not touched since, so it is only in
+        // the hashed state.
+        let address = Address::random();
+        let account = Account { nonce: 1, balance: U256::from(2), bytecode_hash: None };
+        let (slot, value) = (H256::from_low_u64_be(1), U256::from(3));
+        let mut tx = Transaction::new(db.as_ref()).unwrap();
+        tx.put::<tables::HashedAccount>(keccak256(address), account).unwrap();
+        tx.put::<tables::HashedStorage>(
+            keccak256(address),
+            StorageEntry { key: keccak256(slot), value },
+        )
+        .unwrap();
+        tx.put::<tables::BlockTransitionIndex>(1, 1).unwrap();
+        tx.commit().unwrap();
+        drop(tx);
+
+        let provider = ShareableDatabase::new(db.clone(), chain_spec);
+        assert_eq!(provider.latest().unwrap().basic_account(address), Ok(None));
+
+        let tx = db.tx_mut().unwrap();
+        tx.put::<tables::SyncStageProgress>(SnapSyncCheckpoint::KEY.as_bytes().to_vec(), vec![])
+            .unwrap();
+        tx.commit().unwrap();
+
+        let latest = provider.latest().unwrap();
+        assert_eq!(latest.basic_account(address), Ok(Some(account)));
+        assert_eq!(latest.storage(address, slot), Ok(Some(value)));
+        let historical = provider.history_by_block_number(1).unwrap();
+        assert_eq!(historical.basic_account(address), Ok(Some(account)));
+        assert_eq!(historical.storage(address, slot), Ok(Some(value)));
+    }
+
+    #[test]
+    fn serves_state_ranges() {
+        let chain_spec = ChainSpecBuilder::mainnet().build();
+        let db = create_test_db::<WriteMap>(EnvKind::RW);
+
+        let mut tx = Transaction::new(db.as_ref()).unwrap();
+        let mut hashed_addresses = (0..50u64)
+            .map(|i| {
+                let hashed_address = keccak256(H256::from_low_u64_be(i));
+                let account = Account { nonce: i, balance: U256::from(i), bytecode_hash: None };
+                tx.put::<tables::HashedAccount>(hashed_address, account).unwrap();
+                hashed_address
+            })
+            .collect::<Vec<_>>();
+        hashed_addresses.sort();
+        let storage_address = hashed_addresses[10];
+        for slot in 0..20u64 {
+            let entry =
+                StorageEntry { key: keccak256(H256::from_low_u64_be(slot)), value: U256::from(1) };
+            tx.put::<tables::HashedStorage>(storage_address, entry).unwrap();
+        }
+        let root = DBTrieLoader::default().calculate_root(&tx).unwrap();
+        tx.commit().unwrap();
+        drop(tx);
+
+        let provider = ShareableDatabase::new(db, chain_spec);
+        assert_eq!(
+            provider.account_range(H256::zero(), H256::zero(), H256::zero(), usize::MAX).unwrap(),
+            None
+        );
+
+        // The range ends with the first account at or after the limit.
+        let range = provider
+            .account_range(root, H256::zero(), hashed_addresses[20], usize::MAX)
+            .unwrap()
+            .unwrap();
+        let keys = range.entries.iter().map(|(key, ..)| *key).collect::<Vec<_>>();
+        assert_eq!(keys, hashed_addresses[..=20]);
+        assert!(!range.proof.is_empty());
+        assert_eq!(range.entries[0].2, EMPTY_ROOT);
+        assert_ne!(range.entries[10].2, EMPTY_ROOT);
+
+        // The range is cut off at the byte limit, but holds at least one account.
+        let range = provider
+            .account_range(root, H256::zero(), H256::repeat_byte(0xff), 1)
+            .unwrap()
+            .unwrap();
+        assert_eq!(range.entries.len(), 1);
+
+        let slots = provider
+            .storage_ranges(
+                root,
+                &[storage_address],
+                H256::zero(),
+                H256::repeat_byte(0xff),
+                usize::MAX,
+            )
+            .unwrap()
+            .unwrap();
+        assert_eq!(slots.entries.len(), 1);
+        assert_eq!(slots.entries[0].len(), 20);
+        assert!(slots.proof.is_empty());
+
+        let slots = provider
+            .storage_ranges(
+                root,
+                &[storage_address],
+                H256::repeat_byte(0x80),
+                H256::repeat_byte(0xff),
+                usize::MAX,
+            )
+            .unwrap()
+            .unwrap();
+        assert!(slots.entries[0].iter().all(|(key, _)| *key >= H256::repeat_byte(0x80)));
+        assert!(!slots.proof.is_empty());
+
+        let nodes = provider
+            .trie_nodes(root, &[(None, vec![Nibbles::default()])], usize::MAX)
+            .unwrap()
+            .unwrap();
+        assert_eq!(nodes.len(), 1);
+        assert_eq!(keccak256(&nodes[0]), root);
+    }
 }
@@ -0,0 +1,282 @@
+use crate::{AccountProvider, BlockHashProvider, PostState, StateProvider};
+use parking_lot::Mutex;
+use reth_interfaces::Result;
+use reth_primitives::{
+    Account, Address, BlockNumber, Bytecode, StorageKey, StorageValue, H256, U256,
+};
+use schnellru::{ByLength, LruMap};
+use std::{
+    fmt::Debug,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+/// A bounded least recently used cache of the latest state: accounts, storage slots and
+/// bytecodes.
+///
+/// The cache is meant to live across the execution of many blocks. It holds the state at the end
+/// of the block it was last updated for, see [StateCache::block]. The owner has to apply every
+/// change to the state to the cache, and clear the cache whenever the state changes in another
+/// way, e.g. on unwind.
+pub struct StateCache {
+    /// The cached entries.
+    inner: Mutex<StateCacheInner>,
+    /// The number of reads served by the cache.
+    hits: AtomicU64,
+    /// The number of reads that missed the cache.
+    misses: AtomicU64,
+}
+
+struct StateCacheInner {
+    /// The block the cached state belongs to.
+    block: Option<BlockNumber>,
+    /// Accounts, `None` if the account does not exist.
+    accounts: LruMap<Address, Option<Account>, ByLength>,
+    /// Storage slots, `None` if the slot is empty.
+    storage: LruMap<(Address, StorageKey), Option<StorageValue>, ByLength>,
+    /// Bytecodes by their hash.
+    bytecodes: LruMap<H256, Bytecode, ByLength>,
+}
+
+impl StateCache {
+    /// Creates a cache that holds at most the given number of accounts, storage slots and
+    /// bytecodes.
+    pub fn new(max_accounts: u32, max_storage_slots: u32, max_bytecodes: u32) -> Self {
+        Self {
+            inner: Mutex::new(StateCacheInner {
+                block: None,
+                accounts: LruMap::new(ByLength::new(max_accounts)),
+                storage: LruMap::new(ByLength::new(max_storage_slots)),
+                bytecodes: LruMap::new(ByLength::new(max_bytecodes)),
+            }),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    /// Returns the block the cached state belongs to, or `None` if the cache is empty.
+    pub fn block(&self) -> Option<BlockNumber> {
+        self.inner.lock().block
+    }
+
+    /// Returns the number of reads served by the cache.
+    pub fn hits(&self) -> u64 {
+        self.hits.load(Ordering::Relaxed)
+    }
+
+    /// Returns the number of reads that missed the cache.
+    pub fn misses(&self) -> u64 {
+        self.misses.load(Ordering::Relaxed)
+    }
+
+    /// Removes all entries.
+    pub fn clear(&self) {
+        let mut inner = self.inner.lock();
+        inner.block = None;
+        inner.accounts.clear();
+        inner.storage.clear();
+        inner.bytecodes.clear();
+    }
+
+    /// Applies the changes of executed blocks and marks the cache as holding the state at the end
+    /// of the last block of the post state.
+    ///
+    /// The post state has to start at the block after [StateCache::block].
+    pub fn apply_post_state(&self, post_state: &PostState) {
+        let mut inner = self.inner.lock();
+        for (address, account) in post_state.accounts() {
+            inner.accounts.insert(address, account);
+        }
+        if post_state.storage().values().any(|storage| storage.wiped) {
+            // Slots are not indexed by account, wiping storage is rare.
+            inner.storage.clear();
+        }
+        for (address, storage) in post_state.storage() {
+            for (key, value) in &storage.slots {
+                let value = (*value != U256::ZERO).then_some(*value);
+                inner.storage.insert((*address, *key), value);
+            }
+        }
+        for (hash, bytecode) in post_state.bytecodes() {
+            inner.bytecodes.insert(*hash, bytecode.clone());
+        }
+        inner.block = Some(post_state.block_number());
+    }
+
+    /// Returns the cached entry and counts the read as hit or miss if `count` is set.
+    fn read<T>(&self, entry: Option<T>, count: bool) -> Option<T> {
+        if count {
+            let counter = if entry.is_some() { &self.hits } else { &self.misses };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+        entry
+    }
+}
+
+impl Debug for StateCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let inner = self.inner.lock();
+        f.debug_struct("StateCache")
+            .field("block", &inner.block)
+            .field("accounts", &inner.accounts.len())
+            .field("storage", &inner.storage.len())
+            .field("bytecodes", &inner.bytecodes.len())
+            .field("hits", &self.hits())
+            .field("misses", &self.misses())
+            .finish()
+    }
+}
+
+/// A [StateProvider] that serves reads from a [StateCache] and fills it on misses.
+///
+/// The underlying provider has to provide the state the cache holds.
+#[derive(Debug)]
+pub struct CachedStateProvider<'a, SP> {
+    /// The provider of the state on cache misses.
+    provider: SP,
+    /// The cache of the state.
+    cache: &'a StateCache,
+    /// Whether reads are counted as hits or misses of the cache.
+    count_reads: bool,
+}
+
+impl<'a, SP: StateProvider> CachedStateProvider<'a, SP> {
+    /// Wraps a provider of the latest state with the given cache.
+    pub fn new(provider: SP, cache: &'a StateCache) -> Self {
+        Self { provider, cache, count_reads: true }
+    }
+
+    /// Wraps a provider of the latest state with the given cache, without counting the reads as
+    /// hits or misses. Used to fill the cache ahead of the reads that are counted.
+    pub fn prefetching(provider: SP, cache: &'a StateCache) -> Self {
+        Self { provider, cache, count_reads: false }
+    }
+}
+
+impl<'a, SP: StateProvider> AccountProvider for CachedStateProvider<'a, SP> {
+    /// Get basic account information.
+    fn basic_account(&self, address: Address) -> Result<Option<Account>> {
+        let cached = self.cache.inner.lock().accounts.get(&address).copied();
+        if let Some(account) = self.cache.read(cached, self.count_reads) {
+            return Ok(account)
+        }
+        let account = self.provider.basic_account(address)?;
+        self.cache.inner.lock().accounts.insert(address, account);
+        Ok(account)
+    }
+}
+
+impl<'a, SP: StateProvider> BlockHashProvider for CachedStateProvider<'a, SP> {
+    /// Get block hash by number.
+    fn block_hash(&self, number: U256) -> Result<Option<H256>> {
+        self.provider.block_hash(number)
+    }
+}
+
+impl<'a, SP: StateProvider> StateProvider for CachedStateProvider<'a, SP> {
+    /// Get storage.
+    fn storage(&self, account: Address, storage_key: StorageKey) -> Result<Option<StorageValue>> {
+        let cached = self.cache.inner.lock().storage.get(&(account, storage_key)).copied();
+        if let Some(value) = self.cache.read(cached, self.count_reads) {
+            return Ok(value)
+        }
+        let value = self.provider.storage(account, storage_key)?;
+        self.cache.inner.lock().storage.insert((account, storage_key), value);
+        Ok(value)
+    }
+
+    /// Get account code by its hash
+    fn bytecode_by_hash(&self, code_hash: H256) -> Result<Option<Bytecode>> {
+        let cached = self.cache.inner.lock().bytecodes.get(&code_hash).cloned();
+        if let Some(bytecode) = self.cache.read(cached, self.count_reads) {
+            return Ok(Some(bytecode))
+        }
+        // Missing bytecodes are not cached, they can be inserted by a later block.
+        let bytecode = self.provider.bytecode_by_hash(code_hash)?;
+        if let Some(bytecode) = &bytecode {
+            self.cache.inner.lock().bytecodes.insert(code_hash, bytecode.clone());
+        }
+        Ok(bytecode)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        execution_result::{
+            AccountChangeSet, AccountInfoChangeSet, ExecutionResult, TransactionChangeSet,
+        },
+        LatestStateProviderRef,
+    };
+    use reth_db::{
+        database::Database,
+        mdbx::test_utils::create_test_rw_db,
+        tables,
+        transaction::{DbTx, DbTxMut},
+    };
+    use reth_primitives::{Receipt, StorageEntry};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn cache_reads_and_execution_results() {
+        let db = create_test_rw_db();
+        let tx = db.tx_mut().unwrap();
+        let address = Address::random();
+        let key = H256::from_low_u64_be(1);
+        let account = Account { nonce: 1, ..Default::default() };
+        tx.put::<tables::PlainAccountState>(address, account).unwrap();
+        tx.put::<tables::PlainStorageState>(address, StorageEntry { key, value: U256::from(2) })
+            .unwrap();
+
+        let cache = StateCache::new(10, 10, 10);
+        let provider = CachedStateProvider::new(LatestStateProviderRef::new(&tx), &cache);
+        assert_eq!(provider.basic_account(address), Ok(Some(account)));
+        assert_eq!(provider.basic_account(address), Ok(Some(account)));
+        assert_eq!(provider.storage(address, key), Ok(Some(U256::from(2))));
+        assert_eq!(provider.basic_account(Address::random()), Ok(None));
+        assert_eq!((cache.hits(), cache.misses()), (1, 3));
+
+        // The cached values are served even if the database changes.
+        tx.delete::<tables::PlainAccountState>(address, None).unwrap();
+        assert_eq!(provider.basic_account(address), Ok(Some(account)));
+
+        // Executed blocks update the cache.
+        let changed = Account { nonce: 2, ..account };
+        let result = ExecutionResult {
+            tx_changesets: vec![TransactionChangeSet {
+                receipt: Receipt::default(),
+                changeset: BTreeMap::from([(
+                    address,
+                    AccountChangeSet {
+                        account: AccountInfoChangeSet::Changed { new: changed, old: account },
+                        storage: BTreeMap::from([(U256::from(1), (U256::from(2), U256::ZERO))]),
+                        wipe_storage: false,
+                    },
+                )]),
+                new_bytecodes: BTreeMap::new(),
+            }],
+            pre_block_changesets: BTreeMap::new(),
+            block_changesets: BTreeMap::new(),
+        };
+        let mut post_state = PostState::new(0, 0);
+        post_state.add_block(result, true);
+        cache.apply_post_state(&post_state);
+        assert_eq!(cache.block(), Some(1));
+        assert_eq!(provider.basic_account(address), Ok(Some(changed)));
+        assert_eq!(provider.storage(address, key), Ok(None));
+
+        cache.clear();
+        assert_eq!(cache.block(), None);
+        assert_eq!(provider.basic_account(address), Ok(None));
+
+        // Prefetched reads fill the cache without being counted.
+        let (hits, misses) = (cache.hits(), cache.misses());
+        let other = Address::random();
+        tx.put::<tables::PlainAccountState>(other, account).unwrap();
+        let prefetcher = CachedStateProvider::prefetching(LatestStateProviderRef::new(&tx), &cache);
+        assert_eq!(prefetcher.basic_account(other), Ok(Some(account)));
+        assert_eq!((cache.hits(), cache.misses()), (hits, misses));
+        assert_eq!(provider.basic_account(other), Ok(Some(account)));
+        assert_eq!((cache.hits(), cache.misses()), (hits + 1, misses));
+    }
+}
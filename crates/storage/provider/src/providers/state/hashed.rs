@@ -0,0 +1,88 @@
+use crate::{AccountProvider, BlockHashProvider, StateProvider};
+use reth_db::{cursor::DbDupCursorRO, tables, transaction::DbTx};
+use reth_interfaces::Result;
+use reth_primitives::{
+    keccak256, Account, Address, Bytecode, StorageKey, StorageValue, H256, U256,
+};
+use std::marker::PhantomData;
+
+/// State provider over the latest hashed state that takes tx reference.
+///
+/// The accounts and storage slots are looked up by their hashed keys in [tables::HashedAccount]
+/// and [tables::HashedStorage], which hold the complete state even if the plain state does not,
+/// e.g. after the state was downloaded from peers, which only serve the hashed state.
+pub struct HashedStateProviderRef<'a, 'b, TX: DbTx<'a>> {
+    /// database transaction
+    db: &'b TX,
+    /// Phantom data over lifetime
+    phantom: PhantomData<&'a TX>,
+}
+
+impl<'a, 'b, TX: DbTx<'a>> HashedStateProviderRef<'a, 'b, TX> {
+    /// Create new state provider
+    pub fn new(db: &'b TX) -> Self {
+        Self { db, phantom: PhantomData {} }
+    }
+}
+
+impl<'a, 'b, TX: DbTx<'a>> AccountProvider for HashedStateProviderRef<'a, 'b, TX> {
+    /// Get basic account information.
+    fn basic_account(&self, address: Address) -> Result<Option<Account>> {
+        self.db.get::<tables::HashedAccount>(keccak256(address)).map_err(Into::into)
+    }
+}
+
+impl<'a, 'b, TX: DbTx<'a>> BlockHashProvider for HashedStateProviderRef<'a, 'b, TX> {
+    /// Get block hash by number.
+    fn block_hash(&self, number: U256) -> Result<Option<H256>> {
+        self.db.get::<tables::CanonicalHeaders>(number.to::<u64>()).map_err(Into::into)
+    }
+}
+
+impl<'a, 'b, TX: DbTx<'a>> StateProvider for HashedStateProviderRef<'a, 'b, TX> {
+    /// Get storage.
+    fn storage(&self, account: Address, storage_key: StorageKey) -> Result<Option<StorageValue>> {
+        let (hashed_address, hashed_key) = (keccak256(account), keccak256(storage_key));
+        let mut cursor = self.db.cursor_dup_read::<tables::HashedStorage>()?;
+        if let Some(entry) = cursor.seek_by_key_subkey(hashed_address, hashed_key)? {
+            if entry.key == hashed_key {
+                return Ok(Some(entry.value))
+            }
+        }
+        Ok(None)
+    }
+
+    /// Get account code by its hash
+    fn bytecode_by_hash(&self, code_hash: H256) -> Result<Option<Bytecode>> {
+        self.db.get::<tables::Bytecodes>(code_hash).map_err(Into::into)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_db::{database::Database, mdbx::test_utils::create_test_rw_db, transaction::DbTxMut};
+    use reth_primitives::StorageEntry;
+
+    #[test]
+    fn reads_hashed_state() {
+        let db = create_test_rw_db();
+        let tx = db.tx_mut().unwrap();
+
+        let address = Address::random();
+        let account = Account { nonce: 1, ..Default::default() };
+        let (slot, value) = (H256::from_low_u64_be(1), U256::from(2));
+        tx.put::<tables::HashedAccount>(keccak256(address), account).unwrap();
+        tx.put::<tables::HashedStorage>(
+            keccak256(address),
+            StorageEntry { key: keccak256(slot), value },
+        )
+        .unwrap();
+
+        let provider = HashedStateProviderRef::new(&tx);
+        assert_eq!(provider.basic_account(address).unwrap(), Some(account));
+        assert_eq!(provider.basic_account(Address::random()).unwrap(), None);
+        assert_eq!(provider.storage(address, slot).unwrap(), Some(value));
+        assert_eq!(provider.storage(address, H256::from_low_u64_be(2)).unwrap(), None);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::{
     providers::state::macros::delegate_provider_impls, AccountProvider, BlockHashProvider,
-    ProviderError, StateProvider,
+    HashedStateProviderRef, ProviderError, StateProvider,
 };
 use reth_db::{
     cursor::{DbCursorRO, DbDupCursorRO},
@@ -22,11 +22,17 @@ use std::marker::PhantomData;
 /// [tables::StorageHistory]
 /// [tables::AccountChangeSet]
 /// [tables::StorageChangeSet]
+///
+/// Values that did not change after the transition are read from the latest state, which is read
+/// from the hashed state tables if the state was downloaded with a snap sync, see
+/// [HistoricalStateProviderRef::new_hashed].
 pub struct HistoricalStateProviderRef<'a, 'b, TX: DbTx<'a>> {
     /// Transaction
     tx: &'b TX,
     /// Transition is main indexer of account and storage changes
     transition: TransitionId,
+    /// Whether the latest state is read from the hashed state tables.
+    hashed: bool,
     /// Phantom lifetime `'a`
     _phantom: PhantomData<&'a TX>,
 }
@@ -34,7 +40,13 @@ pub struct HistoricalStateProviderRef<'a, 'b, TX: DbTx<'a>> {
 impl<'a, 'b, TX: DbTx<'a>> HistoricalStateProviderRef<'a, 'b, TX> {
     /// Create new StateProvider from history transaction number
     pub fn new(tx: &'b TX, transition: TransitionId) -> Self {
-        Self { tx, transition, _phantom: PhantomData {} }
+        Self { tx, transition, hashed: false, _phantom: PhantomData {} }
+    }
+
+    /// Create new StateProvider from history transaction number that reads the latest state from
+    /// the hashed state tables.
+    pub fn new_hashed(tx: &'b TX, transition: TransitionId) -> Self {
+        Self { tx, transition, hashed: true, _phantom: PhantomData {} }
     }
 }
 impl<'a, 'b, TX: DbTx<'a>> AccountProvider for HistoricalStateProviderRef<'a, 'b, TX> {
@@ -43,12 +55,14 @@ impl<'a, 'b, TX: DbTx<'a>> AccountProvider for HistoricalStateProviderRef<'a, 'b
         // history key to search IntegerList of transition id changesets.
         let history_key = ShardedKey::new(address, self.transition);
 
-        let Some(changeset_transition_id) = self.tx.cursor_read::<tables::AccountHistory>()?
+        let changeset_transition_id = self
+            .tx
+            .cursor_read::<tables::AccountHistory>()?
             .seek(history_key)?
-            .filter(|(key,_)| key.key == address)
-            .map(|(_,list)| list.0.enable_rank().successor(self.transition as usize).map(|i| i as u64)) else {
-                return Ok(None)
-            };
+            .filter(|(key, _)| key.key == address)
+            .and_then(|(_, list)| {
+                list.0.enable_rank().successor(self.transition as usize).map(|i| i as u64)
+            });
 
         // if changeset transition id is present we are getting value from changeset
         if let Some(changeset_transition_id) = changeset_transition_id {
@@ -63,8 +77,11 @@ impl<'a, 'b, TX: DbTx<'a>> AccountProvider for HistoricalStateProviderRef<'a, 'b
                 })?;
             Ok(account.info)
         } else {
-            // if changeset is not present that means that there was history shard but we need to
-            // use newest value from plain state
+            // if changeset is not present that means that the account did not change after this
+            // transition, or its older history was pruned, so we use newest value from plain state
+            if self.hashed {
+                return HashedStateProviderRef::new(self.tx).basic_account(address)
+            }
             Ok(self.tx.get::<tables::PlainAccountState>(address)?)
         }
     }
@@ -83,12 +100,14 @@ impl<'a, 'b, TX: DbTx<'a>> StateProvider for HistoricalStateProviderRef<'a, 'b,
         // history key to search IntegerList of transition id changesets.
         let history_key = StorageShardedKey::new(address, storage_key, self.transition);
 
-        let Some(changeset_transition_id) = self.tx.cursor_read::<tables::StorageHistory>()?
+        let changeset_transition_id = self
+            .tx
+            .cursor_read::<tables::StorageHistory>()?
             .seek(history_key)?
-            .filter(|(key,_)| key.address == address && key.sharded_key.key == storage_key)
-            .map(|(_,list)| list.0.enable_rank().successor(self.transition as usize).map(|i| i as u64)) else {
-                return Ok(None)
-            };
+            .filter(|(key, _)| key.address == address && key.sharded_key.key == storage_key)
+            .and_then(|(_, list)| {
+                list.0.enable_rank().successor(self.transition as usize).map(|i| i as u64)
+            });
 
         // if changeset transition id is present we are getting value from changeset
         if let Some(changeset_transition_id) = changeset_transition_id {
@@ -104,8 +123,11 @@ impl<'a, 'b, TX: DbTx<'a>> StateProvider for HistoricalStateProviderRef<'a, 'b,
                 })?;
             Ok(Some(storage_entry.value))
         } else {
-            // if changeset is not present that means that there was history shard but we need to
-            // use newest value from plain state
+            // if changeset is not present that means that the slot did not change after this
+            // transition, or its older history was pruned, so we use newest value from plain state
+            if self.hashed {
+                return HashedStateProviderRef::new(self.tx).storage(address, storage_key)
+            }
             Ok(self
                 .tx
                 .cursor_dup_read::<tables::PlainStorageState>()?
@@ -127,6 +149,8 @@ pub struct HistoricalStateProvider<'a, TX: DbTx<'a>> {
     tx: TX,
     /// Transition is main indexer of account and storage changes
     transition: TransitionId,
+    /// Whether the latest state is read from the hashed state tables.
+    hashed: bool,
     /// Phantom lifetime `'a`
     _phantom: PhantomData<&'a TX>,
 }
@@ -134,13 +158,24 @@ pub struct HistoricalStateProvider<'a, TX: DbTx<'a>> {
 impl<'a, TX: DbTx<'a>> HistoricalStateProvider<'a, TX> {
     /// Create new StateProvider from history transaction number
     pub fn new(tx: TX, transition: TransitionId) -> Self {
-        Self { tx, transition, _phantom: PhantomData {} }
+        Self { tx, transition, hashed: false, _phantom: PhantomData {} }
+    }
+
+    /// Create new StateProvider from history transaction number that reads the latest state from
+    /// the hashed state tables, see [HistoricalStateProviderRef::new_hashed].
+    pub fn new_hashed(tx: TX, transition: TransitionId) -> Self {
+        Self { tx, transition, hashed: true, _phantom: PhantomData {} }
     }
 
     /// Returns a new provider that takes the `TX` as reference
     #[inline(always)]
     fn as_ref<'b>(&'b self) -> HistoricalStateProviderRef<'a, 'b, TX> {
-        HistoricalStateProviderRef::new(&self.tx, self.transition)
+        HistoricalStateProviderRef {
+            tx: &self.tx,
+            transition: self.transition,
+            hashed: self.hashed,
+            _phantom: PhantomData {},
+        }
     }
 }
 
@@ -163,6 +198,7 @@ mod tests {
     use reth_primitives::{hex_literal::hex, Account, StorageEntry, H160, H256, U256};
 
     const ADDRESS: H160 = H160(hex!("0000000000000000000000000000000000000001"));
+    const UNCHANGED_ADDRESS: H160 = H160(hex!("0000000000000000000000000000000000000002"));
     const STORAGE: H256 =
         H256(hex!("0000000000000000000000000000000000000000000000000000000000000001"));
 
@@ -218,6 +254,7 @@ mod tests {
 
         // setup plain state
         tx.put::<tables::PlainAccountState>(ADDRESS, acc_plain).unwrap();
+        tx.put::<tables::PlainAccountState>(UNCHANGED_ADDRESS, acc_plain).unwrap();
         tx.commit().unwrap();
 
         let tx = db.tx().unwrap();
@@ -255,6 +292,12 @@ mod tests {
             HistoricalStateProviderRef::new(&tx, 16).basic_account(ADDRESS),
             Ok(Some(acc_plain))
         );
+
+        // accounts without history, or whose history was pruned, did not change since
+        assert_eq!(
+            HistoricalStateProviderRef::new(&tx, 1).basic_account(UNCHANGED_ADDRESS),
+            Ok(Some(acc_plain))
+        );
     }
 
     #[test]
@@ -1,6 +1,6 @@
 use crate::{
     providers::state::macros::delegate_provider_impls, AccountProvider, BlockHashProvider,
-    StateProvider,
+    HashedStateProviderRef, StateProvider,
 };
 use reth_db::{cursor::DbDupCursorRO, tables, transaction::DbTx};
 use reth_interfaces::Result;
@@ -8,9 +8,14 @@ use reth_primitives::{Account, Address, Bytecode, StorageKey, StorageValue, H256
 use std::marker::PhantomData;
 
 /// State provider over latest state that takes tx reference.
+///
+/// If the state was downloaded with a snap sync, the plain state is incomplete and the state is
+/// read through [HashedStateProviderRef] instead, see [LatestStateProviderRef::new_hashed].
 pub struct LatestStateProviderRef<'a, 'b, TX: DbTx<'a>> {
     /// database transaction
     db: &'b TX,
+    /// Whether the state is read from the hashed state tables.
+    hashed: bool,
     /// Phantom data over lifetime
     phantom: PhantomData<&'a TX>,
 }
@@ -18,13 +23,21 @@ pub struct LatestStateProviderRef<'a, 'b, TX: DbTx<'a>> {
 impl<'a, 'b, TX: DbTx<'a>> LatestStateProviderRef<'a, 'b, TX> {
     /// Create new state provider
     pub fn new(db: &'b TX) -> Self {
-        Self { db, phantom: PhantomData {} }
+        Self { db, hashed: false, phantom: PhantomData {} }
+    }
+
+    /// Create new state provider that reads the hashed state tables.
+    pub fn new_hashed(db: &'b TX) -> Self {
+        Self { db, hashed: true, phantom: PhantomData {} }
     }
 }
 
 impl<'a, 'b, TX: DbTx<'a>> AccountProvider for LatestStateProviderRef<'a, 'b, TX> {
     /// Get basic account information.
     fn basic_account(&self, address: Address) -> Result<Option<Account>> {
+        if self.hashed {
+            return HashedStateProviderRef::new(self.db).basic_account(address)
+        }
         self.db.get::<tables::PlainAccountState>(address).map_err(Into::into)
     }
 }
@@ -39,6 +52,9 @@ impl<'a, 'b, TX: DbTx<'a>> BlockHashProvider for LatestStateProviderRef<'a, 'b,
 impl<'a, 'b, TX: DbTx<'a>> StateProvider for LatestStateProviderRef<'a, 'b, TX> {
     /// Get storage.
     fn storage(&self, account: Address, storage_key: StorageKey) -> Result<Option<StorageValue>> {
+        if self.hashed {
+            return HashedStateProviderRef::new(self.db).storage(account, storage_key)
+        }
         let mut cursor = self.db.cursor_dup_read::<tables::PlainStorageState>()?;
         if let Some(entry) = cursor.seek_by_key_subkey(account, storage_key)? {
             if entry.key == storage_key {
@@ -58,6 +74,8 @@ impl<'a, 'b, TX: DbTx<'a>> StateProvider for LatestStateProviderRef<'a, 'b, TX>
 pub struct LatestStateProvider<'a, TX: DbTx<'a>> {
     /// database transaction
     db: TX,
+    /// Whether the state is read from the hashed state tables.
+    hashed: bool,
     /// Phantom lifetime `'a`
     _phantom: PhantomData<&'a TX>,
 }
@@ -65,13 +83,19 @@ pub struct LatestStateProvider<'a, TX: DbTx<'a>> {
 impl<'a, TX: DbTx<'a>> LatestStateProvider<'a, TX> {
     /// Create new state provider
     pub fn new(db: TX) -> Self {
-        Self { db, _phantom: PhantomData {} }
+        Self { db, hashed: false, _phantom: PhantomData {} }
+    }
+
+    /// Create new state provider that reads the hashed state tables, see
+    /// [LatestStateProviderRef::new_hashed].
+    pub fn new_hashed(db: TX) -> Self {
+        Self { db, hashed: true, _phantom: PhantomData {} }
     }
 
     /// Returns a new provider that takes the `TX` as reference
     #[inline(always)]
     fn as_ref<'b>(&'b self) -> LatestStateProviderRef<'a, 'b, TX> {
-        LatestStateProviderRef::new(&self.db)
+        LatestStateProviderRef { db: &self.db, hashed: self.hashed, phantom: PhantomData {} }
     }
 }
 
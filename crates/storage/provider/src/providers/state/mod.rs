@@ -1,5 +1,7 @@
 //! [StateProvider](crate::StateProvider) implementations
+pub(crate) mod cached;
 pub(crate) mod chain;
+pub(crate) mod hashed;
 pub(crate) mod historical;
 pub(crate) mod latest;
 pub(crate) mod macros;
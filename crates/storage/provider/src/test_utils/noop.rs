@@ -1,11 +1,13 @@
 use crate::{
     AccountProvider, BlockHashProvider, BlockIdProvider, BlockProvider, EvmEnvProvider,
-    HeaderProvider, StateProvider, StateProviderFactory, TransactionsProvider,
+    HeaderProvider, ReceiptProvider, StateProvider, StateProviderFactory, StateRangeProvider,
+    TransactionsProvider, TrieRange,
 };
 use reth_interfaces::Result;
 use reth_primitives::{
-    Account, Address, Block, BlockHash, BlockId, BlockNumber, Bytecode, ChainInfo, Header,
-    StorageKey, StorageValue, TransactionSigned, TxHash, TxNumber, H256, U256,
+    trie::Nibbles, Account, Address, Block, BlockHash, BlockId, BlockNumber, Bytecode, Bytes,
+    ChainInfo, Header, Receipt, StorageKey, StorageValue, TransactionSigned, TxHash, TxNumber,
+    H256, U256,
 };
 use revm_primitives::{BlockEnv, CfgEnv};
 use std::ops::RangeBounds;
@@ -63,6 +65,16 @@ impl TransactionsProvider for NoopProvider {
     }
 }
 
+impl ReceiptProvider for NoopProvider {
+    fn receipt(&self, _id: TxNumber) -> Result<Option<Receipt>> {
+        Ok(None)
+    }
+
+    fn receipts_by_block(&self, _block: BlockId) -> Result<Option<Vec<Receipt>>> {
+        Ok(None)
+    }
+}
+
 impl HeaderProvider for NoopProvider {
     fn header(&self, _block_hash: &BlockHash) -> Result<Option<Header>> {
         Ok(None)
@@ -157,3 +169,35 @@ impl StateProviderFactory for NoopProvider {
         Ok(*self)
     }
 }
+
+impl StateRangeProvider for NoopProvider {
+    fn account_range(
+        &self,
+        _root: H256,
+        _start: H256,
+        _limit: H256,
+        _max_bytes: usize,
+    ) -> Result<Option<TrieRange<(H256, Account, H256)>>> {
+        Ok(None)
+    }
+
+    fn storage_ranges(
+        &self,
+        _root: H256,
+        _accounts: &[H256],
+        _start: H256,
+        _limit: H256,
+        _max_bytes: usize,
+    ) -> Result<Option<TrieRange<Vec<(H256, U256)>>>> {
+        Ok(None)
+    }
+
+    fn trie_nodes(
+        &self,
+        _root: H256,
+        _paths: &[(Option<H256>, Vec<Nibbles>)],
+        _max_bytes: usize,
+    ) -> Result<Option<Vec<Bytes>>> {
+        Ok(None)
+    }
+}
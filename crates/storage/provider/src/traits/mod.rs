@@ -18,9 +18,15 @@ pub use evm_env::EvmEnvProvider;
 mod header;
 pub use header::HeaderProvider;
 
+mod receipts;
+pub use receipts::ReceiptProvider;
+
 mod state;
 pub use state::{StateProvider, StateProviderFactory};
 
+mod state_range;
+pub use state_range::{StateRangeProvider, TrieRange};
+
 mod transactions;
 pub use transactions::TransactionsProvider;
 
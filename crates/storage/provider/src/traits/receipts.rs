@@ -0,0 +1,19 @@
+use reth_interfaces::Result;
+use reth_primitives::{BlockId, Receipt, TxNumber};
+
+///  Client trait for fetching [Receipt] data.
+#[auto_impl::auto_impl(&, Arc)]
+pub trait ReceiptProvider: Send + Sync {
+    /// Get receipt by transaction number.
+    ///
+    /// Returns [ProviderError::HistoryPruned](reth_interfaces::provider::ProviderError::HistoryPruned) if the
+    /// receipt was pruned.
+    fn receipt(&self, id: TxNumber) -> Result<Option<Receipt>>;
+
+    /// Get the receipts of all transactions of the block, in order.
+    ///
+    /// Returns `None` if the block is not found and
+    /// [ProviderError::HistoryPruned](reth_interfaces::provider::ProviderError::HistoryPruned) if
+    /// its receipts were pruned.
+    fn receipts_by_block(&self, block: BlockId) -> Result<Option<Vec<Receipt>>>;
+}
@@ -0,0 +1,63 @@
+use auto_impl::auto_impl;
+use reth_interfaces::Result;
+use reth_primitives::{trie::Nibbles, Account, Bytes, H256, U256};
+
+/// Consecutive entries of a trie together with the proof of the range's boundaries.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrieRange<T> {
+    /// The entries, ordered by their hashed key.
+    pub entries: Vec<T>,
+    /// The RLP of the trie nodes on the paths to the first and the last key of the range.
+    pub proof: Vec<Bytes>,
+}
+
+/// Client trait for serving ranges and nodes of the stored state trie, e.g. to peers that sync the
+/// state with the `snap` protocol.
+///
+/// Only the state whose trie was computed last can be served. All methods return `None` if the
+/// root of the stored state trie is not the requested `root`.
+///
+/// The responses are cut off once they reach about `max_bytes`, but always include at least one
+/// entry if there is any.
+#[auto_impl(&, Arc)]
+pub trait StateRangeProvider: Send + Sync {
+    /// Returns the accounts starting at the hashed address `start` and ending with the first
+    /// account at or after `limit`, each with its hashed address and storage root.
+    ///
+    /// The proof covers `start` and the last returned account.
+    fn account_range(
+        &self,
+        root: H256,
+        start: H256,
+        limit: H256,
+        max_bytes: usize,
+    ) -> Result<Option<TrieRange<(H256, Account, H256)>>>;
+
+    /// Returns the storage slots of the accounts with the given hashed addresses, in order.
+    ///
+    /// The slots of the first account start at `start`, the slots of the last account end with
+    /// the first slot at or after `limit`. If the slots of an account start after the zero hash
+    /// or are cut off, it is the last account of the response and the proof covers its first
+    /// requested and its last returned slot.
+    fn storage_ranges(
+        &self,
+        root: H256,
+        accounts: &[H256],
+        start: H256,
+        limit: H256,
+        max_bytes: usize,
+    ) -> Result<Option<TrieRange<Vec<(H256, U256)>>>>;
+
+    /// Returns the RLP of the trie nodes at the given paths, in order, ending at the first node
+    /// that is not available.
+    ///
+    /// Each entry holds paths in the state trie if the hashed address is `None`, or paths in the
+    /// storage trie of the account with the hashed address otherwise. Nodes that are embedded in
+    /// their parent are not available.
+    fn trie_nodes(
+        &self,
+        root: H256,
+        paths: &[(Option<H256>, Vec<Nibbles>)],
+        max_bytes: usize,
+    ) -> Result<Option<Vec<Bytes>>>;
+}
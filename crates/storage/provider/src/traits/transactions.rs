@@ -10,6 +10,9 @@ pub trait TransactionsProvider: BlockIdProvider + Send + Sync {
     fn transaction_by_id(&self, id: TxNumber) -> Result<Option<TransactionSigned>>;
 
     /// Get transaction by transaction hash.
+    ///
+    /// Returns `None` for transactions of blocks whose transaction lookup was pruned, since their
+    /// hashes can not be told apart from unknown ones.
     fn transaction_by_hash(&self, hash: TxHash) -> Result<Option<TransactionSigned>>;
 
     /// Get transactions by block id.
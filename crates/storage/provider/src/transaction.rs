@@ -14,14 +14,14 @@ use reth_db::{
 };
 use reth_interfaces::{db::Error as DbError, provider::ProviderError};
 use reth_primitives::{
-    keccak256, Account, Address, BlockHash, BlockNumber, Bytecode, ChainSpec, Hardfork, Header,
-    SealedBlock, StorageEntry, TransitionId, TxNumber, H256, U256,
+    keccak256, Account, Address, BlockHash, BlockNumber, ChainSpec, Hardfork, Header, SealedBlock,
+    StorageEntry, TransitionId, TxNumber, H256, U256,
 };
-use reth_tracing::tracing::{info, trace};
+use reth_tracing::tracing::info;
 use std::{
     collections::{BTreeMap, BTreeSet},
     fmt::Debug,
-    ops::{Deref, DerefMut},
+    ops::{Deref, DerefMut, Range},
 };
 
 use crate::{
@@ -29,7 +29,7 @@ use crate::{
     trie::{DBTrieLoader, TrieError},
 };
 
-use crate::execution_result::{AccountChangeSet, ExecutionResult};
+use crate::{execution_result::ExecutionResult, PostState};
 
 /// A container for any DB transaction that will open a new inner transaction when the current
 /// one is committed.
@@ -307,9 +307,8 @@ where
 
         // merkle tree
         {
-            let current_root = self.get_header(parent_block_number)?.state_root;
             let loader = DBTrieLoader::default();
-            let root = loader.update_root(self, current_root, from..to)?;
+            let root = loader.update_root(self, from..to)?;
             if root != block.state_root {
                 return Err(TransactionError::StateTrieRootMismatch {
                     got: root,
@@ -614,8 +613,116 @@ where
         Ok(())
     }
 
+    /// Delete the account changesets of the given transitions and remove them from the account
+    /// history index. Used inside the prune stage.
+    ///
+    /// Returns the number of deleted changesets.
+    pub fn prune_account_history(
+        &self,
+        transitions: Range<TransitionId>,
+    ) -> Result<usize, TransactionError> {
+        let account_transitions =
+            self.get_account_transition_ids_from_changeset(transitions.start, transitions.end)?;
+
+        let changesets = account_transitions.values().flatten().copied().collect::<BTreeSet<_>>();
+        for transition_id in &changesets {
+            self.delete::<tables::AccountChangeSet>(*transition_id, None)?;
+        }
+
+        for address in account_transitions.keys() {
+            self.prune_history_shards::<tables::AccountHistory>(
+                ShardedKey::new(*address, 0),
+                |key| key.key == *address,
+                transitions.end,
+            )?;
+        }
+
+        Ok(account_transitions.values().map(Vec::len).sum())
+    }
+
+    /// Delete the storage changesets of the given transitions and remove them from the storage
+    /// history index. Used inside the prune stage.
+    ///
+    /// Returns the number of deleted changesets.
+    pub fn prune_storage_history(
+        &self,
+        transitions: Range<TransitionId>,
+    ) -> Result<usize, TransactionError> {
+        let storage_transitions =
+            self.get_storage_transition_ids_from_changeset(transitions.start, transitions.end)?;
+
+        let changesets = storage_transitions
+            .iter()
+            .flat_map(|((address, _), indices)| indices.iter().map(|index| (*index, *address)))
+            .collect::<BTreeSet<_>>();
+        for changeset_key in changesets {
+            self.delete::<tables::StorageChangeSet>(changeset_key.into(), None)?;
+        }
+
+        for (address, storage_key) in storage_transitions.keys() {
+            self.prune_history_shards::<tables::StorageHistory>(
+                StorageShardedKey::new(*address, *storage_key, 0),
+                |key| key.address == *address && key.sharded_key.key == *storage_key,
+                transitions.end,
+            )?;
+        }
+
+        Ok(storage_transitions.values().map(Vec::len).sum())
+    }
+
+    /// Remove the transition ids below `below` from the history shards of a single key, starting
+    /// with the shard at `first_shard`. Shards left without any transition id are deleted.
+    fn prune_history_shards<T>(
+        &self,
+        first_shard: T::Key,
+        is_same_key: impl Fn(&T::Key) -> bool,
+        below: TransitionId,
+    ) -> Result<(), TransactionError>
+    where
+        T: Table<Value = TransitionList>,
+    {
+        // Shards are ordered by their highest transition id, so only the shards up to the first
+        // one reaching `below` hold transition ids to remove.
+        let mut shards = Vec::new();
+        let mut cursor = self.cursor_read::<T>()?;
+        let mut walker = cursor.walk(Some(first_shard))?;
+        while let Some((shard_key, list)) = walker.next().transpose()? {
+            if !is_same_key(&shard_key) {
+                break
+            }
+            let indices = list.iter(0).collect::<Vec<_>>();
+            let reached = indices.last().map_or(false, |index| *index as TransitionId >= below);
+            shards.push((shard_key, indices));
+            if reached {
+                break
+            }
+        }
+
+        for (shard_key, indices) in shards {
+            let kept = indices
+                .iter()
+                .copied()
+                .filter(|index| *index as TransitionId >= below)
+                .collect::<Vec<_>>();
+            if kept.len() == indices.len() {
+                break
+            }
+
+            self.delete::<T>(shard_key.clone(), None)?;
+            if !kept.is_empty() {
+                self.put::<T>(
+                    shard_key,
+                    TransitionList::new(kept).expect("Indices are presorted and not empty"),
+                )?;
+            }
+        }
+        Ok(())
+    }
+
     /// Used inside execution stage to commit created account storage changesets for transaction or
     /// block state change.
+    ///
+    /// The results are merged into a [PostState] first, which is then written in one pass.
     pub fn insert_execution_result(
         &self,
         changesets: Vec<ExecutionResult>,
@@ -623,114 +730,20 @@ where
         parent_block_number: u64,
     ) -> Result<(), TransactionError> {
         // Get last tx count so that we can know amount of transaction in the block.
-        let mut current_transition_id = self
+        let current_transition_id = self
             .get::<tables::BlockTransitionIndex>(parent_block_number)?
             .ok_or(ProviderError::BlockTransition { block_number: parent_block_number })?;
 
         info!(target: "sync::stages::execution", current_transition_id, blocks = changesets.len(), "Inserting execution results");
 
-        // apply changes to plain database.
-        let mut block_number = parent_block_number;
-        for results in changesets.into_iter() {
-            block_number += 1;
-            let spurious_dragon_active =
-                chain_spec.fork(Hardfork::SpuriousDragon).active_at_block(block_number);
-            // insert state change set
-            for result in results.tx_changesets.into_iter() {
-                for (address, account_change_set) in result.changeset.into_iter() {
-                    let AccountChangeSet { account, wipe_storage, storage } = account_change_set;
-                    // apply account change to db. Updates AccountChangeSet and PlainAccountState
-                    // tables.
-                    trace!(target: "sync::stages::execution", ?address, current_transition_id, ?account, wipe_storage, "Applying account changeset");
-                    account.apply_to_db(
-                        &**self,
-                        address,
-                        current_transition_id,
-                        spurious_dragon_active,
-                    )?;
-
-                    let storage_id = TransitionIdAddress((current_transition_id, address));
-
-                    // cast key to H256 and trace the change
-                    let storage = storage
-                                .into_iter()
-                                .map(|(key, (old_value,new_value))| {
-                                    let hkey = H256(key.to_be_bytes());
-                                    trace!(target: "sync::stages::execution", ?address, current_transition_id, ?hkey, ?old_value, ?new_value, "Applying storage changeset");
-                                    (hkey, old_value,new_value)
-                                })
-                                .collect::<Vec<_>>();
-
-                    let mut cursor_storage_changeset =
-                        self.cursor_write::<tables::StorageChangeSet>()?;
-                    cursor_storage_changeset.seek_exact(storage_id)?;
-
-                    if wipe_storage {
-                        // iterate over storage and save them before entry is deleted.
-                        self.cursor_read::<tables::PlainStorageState>()?
-                            .walk(Some(address))?
-                            .take_while(|res| {
-                                res.as_ref().map(|(k, _)| *k == address).unwrap_or_default()
-                            })
-                            .try_for_each(|entry| {
-                                let (_, old_value) = entry?;
-                                cursor_storage_changeset.append(storage_id, old_value)
-                            })?;
-
-                        // delete all entries
-                        self.delete::<tables::PlainStorageState>(address, None)?;
-
-                        // insert storage changeset
-                        for (key, _, new_value) in storage {
-                            // old values are already cleared.
-                            if new_value != U256::ZERO {
-                                self.put::<tables::PlainStorageState>(
-                                    address,
-                                    StorageEntry { key, value: new_value },
-                                )?;
-                            }
-                        }
-                    } else {
-                        // insert storage changeset
-                        for (key, old_value, new_value) in storage {
-                            let old_entry = StorageEntry { key, value: old_value };
-                            let new_entry = StorageEntry { key, value: new_value };
-                            // insert into StorageChangeSet
-                            cursor_storage_changeset.append(storage_id, old_entry)?;
-
-                            // Always delete old value as duplicate table, put will not override it
-                            self.delete::<tables::PlainStorageState>(address, Some(old_entry))?;
-                            if new_value != U256::ZERO {
-                                self.put::<tables::PlainStorageState>(address, new_entry)?;
-                            }
-                        }
-                    }
-                }
-                // insert bytecode
-                for (hash, bytecode) in result.new_bytecodes.into_iter() {
-                    // make different types of bytecode. Checked and maybe even analyzed (needs to
-                    // be packed). Currently save only raw bytes.
-                    let bytes = bytecode.bytes();
-                    trace!(target: "sync::stages::execution", ?hash, ?bytes, len = bytes.len(), "Inserting bytecode");
-                    self.put::<tables::Bytecodes>(hash, Bytecode(bytecode))?;
-                    // NOTE: bytecode bytes are not inserted in change set and can be found in
-                    // separate table
-                }
-                current_transition_id += 1;
-            }
-
-            // If there are any post block changes, we will add account changesets to db.
-            for (address, changeset) in results.block_changesets.into_iter() {
-                trace!(target: "sync::stages::execution", ?address, current_transition_id, "Applying block reward");
-                changeset.apply_to_db(
-                    &**self,
-                    address,
-                    current_transition_id,
-                    spurious_dragon_active,
-                )?;
-            }
-            current_transition_id += 1;
+        let mut post_state = PostState::new(parent_block_number, current_transition_id);
+        for result in changesets.into_iter() {
+            let spurious_dragon_active = chain_spec
+                .fork(Hardfork::SpuriousDragon)
+                .active_at_block(post_state.block_number() + 1);
+            post_state.add_block(result, spurious_dragon_active);
         }
+        post_state.write_to_db(&**self)?;
         Ok(())
     }
 }
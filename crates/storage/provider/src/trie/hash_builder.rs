@@ -0,0 +1,425 @@
+use super::PrefixSet;
+use reth_primitives::{
+    keccak256,
+    proofs::EMPTY_ROOT,
+    trie::{HashBuilderState, HashBuilderValue, Nibbles},
+    H256,
+};
+use reth_rlp::{BufMut, Encodable, Header, EMPTY_STRING_CODE};
+
+/// Computes the root of a Merkle Patricia Trie from a stream of items in ascending path order.
+///
+/// Items are either leaves, added with [HashBuilder::add_leaf], or subtrees that are known not to
+/// have changed, added with [HashBuilder::add_branch] and the hash of the branch node at their
+/// root. Nodes are built as soon as no later item can be part of them, so only the nodes along
+/// the path of the most recent item are kept in memory.
+///
+/// Every branch node that is referenced by its hash is recorded and can be collected with
+/// [HashBuilder::take_branch_nodes], so that it can be stored and later used in place of its
+/// subtree.
+///
+/// If proof targets are set with [HashBuilder::with_proof_targets], the RLP of every node on the
+/// path to one of the targets is retained as well, and can be collected with
+/// [HashBuilder::take_proof_nodes].
+///
+/// The builder can be converted to and from a [HashBuilderState] to continue adding items later,
+/// e.g. after the state was persisted.
+#[derive(Debug, Default)]
+pub struct HashBuilder {
+    /// Path of the most recently added item.
+    key: Nibbles,
+    /// Value of the most recently added item.
+    value: HashBuilderValue,
+    /// References to the nodes that are not yet part of their parent, as embedded in the parent:
+    /// the RLP of the node if it is shorter than 32 bytes, the RLP of its hash otherwise.
+    stack: Vec<Vec<u8>>,
+    /// Bitmask of the children of the branch node being built at each depth.
+    groups: Vec<u16>,
+    /// Paths and hashes of the branch nodes built since they were last collected.
+    branch_nodes: Vec<(Nibbles, H256)>,
+    /// The keys whose proofs are retained.
+    proof_targets: PrefixSet,
+    /// Paths and RLP of the retained proof nodes.
+    proof_nodes: Vec<(Nibbles, Vec<u8>)>,
+}
+
+impl HashBuilder {
+    /// Retains the nodes on the paths to the given keys while building the trie.
+    ///
+    /// Subtrees added with [HashBuilder::add_branch] must not contain any of the targets, since
+    /// their nodes are never built.
+    pub fn with_proof_targets(mut self, targets: PrefixSet) -> Self {
+        self.proof_targets = targets;
+        self
+    }
+
+    /// Adds a leaf with the given path, which must be greater than the path of the last item.
+    pub fn add_leaf(&mut self, key: Nibbles, value: &[u8]) {
+        debug_assert!(key > self.key, "items must be added in ascending order");
+        if !self.key.is_empty() {
+            self.update(&key);
+        }
+        self.key = key;
+        self.value = HashBuilderValue::Bytes(value.to_vec());
+    }
+
+    /// Adds the subtree below the given path, given the hash of the branch node at its root.
+    ///
+    /// The path must be greater than the path of the last item and no later item may start with
+    /// it.
+    pub fn add_branch(&mut self, key: Nibbles, hash: H256) {
+        debug_assert!(
+            key > self.key || (self.key.is_empty() && key.is_empty()),
+            "items must be added in ascending order"
+        );
+        if !self.key.is_empty() {
+            self.update(&key);
+        } else if key.is_empty() {
+            // The subtree is the whole trie.
+            self.stack.push(rlp_hash(hash));
+        }
+        self.key = key;
+        self.value = HashBuilderValue::Hash(hash);
+    }
+
+    /// Returns the branch nodes built since the last call, in the order they were completed.
+    pub fn take_branch_nodes(&mut self) -> Vec<(Nibbles, H256)> {
+        std::mem::take(&mut self.branch_nodes)
+    }
+
+    /// Returns the paths and RLP of the nodes on the paths to the proof targets, ordered by path.
+    ///
+    /// Nodes that are embedded in their parent instead of being referenced by their hash are
+    /// part of the parent's RLP and not returned separately, except for the root.
+    pub fn take_proof_nodes(&mut self) -> Vec<(Nibbles, Vec<u8>)> {
+        let mut nodes = std::mem::take(&mut self.proof_nodes);
+        nodes.sort_by(|(a, _), (b, _)| a.cmp(b));
+        nodes
+    }
+
+    /// Builds the remaining nodes and returns the root of the trie.
+    pub fn root(&mut self) -> H256 {
+        if !self.key.is_empty() {
+            self.update(&Nibbles::default());
+            self.key = Nibbles::default();
+            self.value = HashBuilderValue::default();
+        }
+
+        match self.stack.last() {
+            Some(node_ref) if node_ref.len() == H256::len_bytes() + 1 => {
+                H256::from_slice(&node_ref[1..])
+            }
+            Some(node_ref) => keccak256(node_ref),
+            None => EMPTY_ROOT,
+        }
+    }
+
+    /// Builds all nodes that end between the last item and the `succeeding` one.
+    ///
+    /// The last item becomes a child of the branch node at the depth where it diverges from its
+    /// neighbours. If the succeeding item diverges at a shallower depth than the previous one, the
+    /// branch nodes in between are complete and are folded into their parents, inserting
+    /// extension nodes where a branch node has no siblings below its parent.
+    fn update(&mut self, succeeding: &Nibbles) {
+        let mut build_extensions = false;
+        let mut current = self.key.clone();
+
+        loop {
+            let preceding_exists = !self.groups.is_empty();
+            let preceding_len = self.groups.len().saturating_sub(1);
+
+            let common_prefix_len = succeeding.common_prefix_length(&current);
+            let len = preceding_len.max(common_prefix_len);
+            debug_assert!(len < current.len(), "items must not be prefixes of each other");
+
+            // Register the current node as a child of the branch node at `len`.
+            let extra_digit = current[len];
+            if self.groups.len() <= len {
+                self.groups.resize(len + 1, 0);
+            }
+            self.groups[len] |= 1 << extra_digit;
+
+            let mut len_from = len;
+            if !succeeding.is_empty() || preceding_exists {
+                len_from += 1;
+            }
+
+            // The part of the path below the branch node.
+            let short_node_key = current.slice(len_from..);
+
+            if !build_extensions {
+                match &self.value {
+                    HashBuilderValue::Bytes(value) => {
+                        let leaf = leaf_node_rlp(&short_node_key, value);
+                        self.stack.push(rlp_node(&leaf));
+                        self.retain_proof_node(current.slice(..len_from), leaf);
+                    }
+                    HashBuilderValue::Hash(hash) => {
+                        self.stack.push(rlp_hash(*hash));
+                        build_extensions = true;
+                    }
+                }
+            }
+
+            if build_extensions && !short_node_key.is_empty() {
+                let child = self.stack.pop().expect("stack holds the current node");
+                let extension = extension_node_rlp(&short_node_key, &child);
+                self.stack.push(rlp_node(&extension));
+                self.retain_proof_node(current.slice(..len_from), extension);
+            }
+
+            // The branch node at `len` is still missing children.
+            if preceding_len <= common_prefix_len && !succeeding.is_empty() {
+                return
+            }
+
+            if !succeeding.is_empty() || preceding_exists {
+                self.push_branch_node(&current, len);
+            }
+
+            self.groups.truncate(len);
+
+            if preceding_len == 0 {
+                return
+            }
+
+            current.truncate(preceding_len);
+            while self.groups.last() == Some(&0) {
+                self.groups.pop();
+            }
+
+            build_extensions = true;
+        }
+    }
+
+    /// Replaces the children of the branch node at depth `len` on the stack with the node itself.
+    fn push_branch_node(&mut self, current: &Nibbles, len: usize) {
+        let state_mask = self.groups[len];
+        let first_child = self.stack.len() - state_mask.count_ones() as usize;
+        let children = self.stack.split_off(first_child);
+
+        let branch = branch_node_rlp(state_mask, &children);
+        if branch.len() >= H256::len_bytes() {
+            let hash = keccak256(&branch);
+            self.branch_nodes.push((current.slice(..len), hash));
+            self.stack.push(rlp_hash(hash));
+        } else {
+            self.stack.push(branch.clone());
+        }
+        self.retain_proof_node(current.slice(..len), branch);
+    }
+
+    /// Retains the node at the given path if it is on the path to a proof target and either
+    /// referenced by its hash or the root.
+    fn retain_proof_node(&mut self, path: Nibbles, rlp: Vec<u8>) {
+        if (rlp.len() >= H256::len_bytes() || path.is_empty()) && self.proof_targets.contains(&path)
+        {
+            self.proof_nodes.push((path, rlp));
+        }
+    }
+}
+
+impl From<HashBuilderState> for HashBuilder {
+    fn from(state: HashBuilderState) -> Self {
+        let HashBuilderState { key, value, stack, groups } = state;
+        Self { key, value, stack, groups, ..Default::default() }
+    }
+}
+
+impl From<HashBuilder> for HashBuilderState {
+    /// Converts the builder into its state, which does not include the branch nodes that were not
+    /// collected yet.
+    fn from(builder: HashBuilder) -> Self {
+        debug_assert!(builder.branch_nodes.is_empty(), "branch nodes must be collected first");
+        let HashBuilder { key, value, stack, groups, .. } = builder;
+        Self { key, value, stack, groups }
+    }
+}
+
+/// Returns the reference to a node that is embedded in its parent.
+fn rlp_node(rlp: &[u8]) -> Vec<u8> {
+    if rlp.len() < H256::len_bytes() {
+        rlp.to_vec()
+    } else {
+        rlp_hash(keccak256(rlp))
+    }
+}
+
+/// Returns the RLP encoding of a node hash.
+fn rlp_hash(hash: H256) -> Vec<u8> {
+    let mut out = Vec::with_capacity(H256::len_bytes() + 1);
+    hash.as_bytes().encode(&mut out);
+    out
+}
+
+/// Encodes a list from items that are already RLP encoded.
+fn encode_list(items: &[&[u8]]) -> Vec<u8> {
+    let payload_length = items.iter().map(|item| item.len()).sum();
+    let mut out = Vec::with_capacity(payload_length + 3);
+    Header { list: true, payload_length }.encode(&mut out);
+    for item in items {
+        out.put_slice(item);
+    }
+    out
+}
+
+/// Encodes a byte string.
+fn encode_bytes(bytes: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(bytes.len() + 3);
+    bytes.encode(&mut out);
+    out
+}
+
+fn leaf_node_rlp(key: &Nibbles, value: &[u8]) -> Vec<u8> {
+    encode_list(&[&encode_bytes(&key.encode_path_leaf(true)), &encode_bytes(value)])
+}
+
+fn extension_node_rlp(key: &Nibbles, child: &[u8]) -> Vec<u8> {
+    encode_list(&[&encode_bytes(&key.encode_path_leaf(false)), child])
+}
+
+/// Encodes a branch node from the references to its children, one per bit set in `state_mask`.
+fn branch_node_rlp(state_mask: u16, children: &[Vec<u8>]) -> Vec<u8> {
+    let mut children = children.iter();
+    let mut items = (0..16)
+        .map(|nibble| match state_mask & (1 << nibble) {
+            0 => &[EMPTY_STRING_CODE][..],
+            _ => children.next().expect("a child for every bit of the state mask").as_slice(),
+        })
+        .collect::<Vec<_>>();
+    // Branch nodes of keccak-keyed tries never hold a value.
+    items.push(&[EMPTY_STRING_CODE]);
+    encode_list(&items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::{collection::btree_map, prelude::*};
+    use reth_primitives::{hex_literal::hex, proofs::KeccakHasher};
+    use std::collections::BTreeMap;
+    use triehash::trie_root;
+
+    fn expected_root(leaves: &BTreeMap<Vec<u8>, Vec<u8>>) -> H256 {
+        H256(trie_root::<KeccakHasher, _, _, _>(leaves.clone()).0)
+    }
+
+    fn build(leaves: &BTreeMap<Vec<u8>, Vec<u8>>) -> (H256, Vec<(Nibbles, H256)>) {
+        let mut hb = HashBuilder::default();
+        for (key, value) in leaves {
+            hb.add_leaf(Nibbles::unpack(key), value);
+        }
+        let root = hb.root();
+        (root, hb.take_branch_nodes())
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(HashBuilder::default().root(), EMPTY_ROOT);
+    }
+
+    #[test]
+    fn keccak_keyed_trie() {
+        let leaves = BTreeMap::from([
+            (keccak256(b"doe").to_vec(), b"reindeer".to_vec()),
+            (keccak256(b"dog").to_vec(), b"puppy".to_vec()),
+            (keccak256(b"dogglesworth").to_vec(), b"cat".to_vec()),
+        ]);
+        assert_eq!(build(&leaves).0, expected_root(&leaves));
+    }
+
+    #[test]
+    fn single_leaf() {
+        let leaves = BTreeMap::from([(vec![0x12; 32], vec![0x01])]);
+        let (root, branch_nodes) = build(&leaves);
+        assert_eq!(root, expected_root(&leaves));
+        assert!(branch_nodes.is_empty());
+    }
+
+    #[test]
+    fn records_hashed_branch_nodes() {
+        let leaves = BTreeMap::from([
+            (hex!("1100").to_vec(), vec![0xaa; 32]),
+            (hex!("1200").to_vec(), vec![0xbb; 32]),
+            (hex!("2000").to_vec(), vec![0xcc; 32]),
+        ]);
+        let (root, branch_nodes) = build(&leaves);
+        assert_eq!(root, expected_root(&leaves));
+
+        let paths = branch_nodes.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>();
+        assert_eq!(paths, vec![Nibbles::from_hex(vec![1]), Nibbles::default()]);
+        assert_eq!(branch_nodes.last().unwrap().1, root);
+    }
+
+    #[test]
+    fn retains_proof_nodes() {
+        let leaves = BTreeMap::from([
+            (hex!("1100").to_vec(), vec![0xaa; 32]),
+            (hex!("1200").to_vec(), vec![0xbb; 32]),
+            (hex!("2000").to_vec(), vec![0xcc; 32]),
+        ]);
+        let target = Nibbles::unpack(hex!("1100"));
+        let mut hb = HashBuilder::default().with_proof_targets([target].into_iter().collect());
+        for (key, value) in &leaves {
+            hb.add_leaf(Nibbles::unpack(key), value);
+        }
+        let root = hb.root();
+
+        let nodes = hb.take_proof_nodes();
+        let paths = nodes.iter().map(|(path, _)| path.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            paths,
+            vec![Nibbles::default(), Nibbles::from_hex(vec![1]), Nibbles::from_hex(vec![1, 1])]
+        );
+        assert_eq!(keccak256(&nodes[0].1), root);
+        assert_eq!(nodes[2].1, leaf_node_rlp(&Nibbles::from_hex(vec![0, 0]), &[0xaa; 32]));
+    }
+
+    #[test]
+    fn subtrees_replace_their_leaves() {
+        let leaves = BTreeMap::from([
+            (hex!("1100").to_vec(), vec![0xaa; 32]),
+            (hex!("1200").to_vec(), vec![0xbb; 32]),
+            (hex!("2000").to_vec(), vec![0xcc; 32]),
+        ]);
+        let (root, branch_nodes) = build(&leaves);
+        let (path, hash) = branch_nodes[0].clone();
+
+        let mut hb = HashBuilder::default();
+        hb.add_branch(path, hash);
+        hb.add_leaf(Nibbles::unpack(hex!("2000")), &[0xcc; 32]);
+        assert_eq!(hb.root(), root);
+        // Only the root was rebuilt.
+        assert_eq!(hb.take_branch_nodes(), vec![(Nibbles::default(), root)]);
+    }
+
+    #[test]
+    fn resumes_from_state() {
+        let leaves = (0..50u64)
+            .map(|i| (keccak256(i.to_be_bytes()).to_vec(), vec![0xaa; (i % 40 + 1) as usize]))
+            .collect::<BTreeMap<_, _>>();
+        let (root, branch_nodes) = build(&leaves);
+
+        let mut hb = HashBuilder::default();
+        let mut resumed_nodes = vec![];
+        for (key, value) in &leaves {
+            hb.add_leaf(Nibbles::unpack(key), value);
+            resumed_nodes.extend(hb.take_branch_nodes());
+            hb = HashBuilderState::from(hb).into();
+        }
+        assert_eq!(hb.root(), root);
+        resumed_nodes.extend(hb.take_branch_nodes());
+        assert_eq!(resumed_nodes, branch_nodes);
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+        #[test]
+        fn arbitrary_keccak_keyed_tries(
+            leaves in btree_map(any::<[u8; 32]>(), proptest::collection::vec(any::<u8>(), 1..64), 0..64)
+        ) {
+            let leaves = leaves.into_iter().map(|(k, v)| (k.to_vec(), v)).collect();
+            prop_assert_eq!(build(&leaves).0, expected_root(&leaves));
+        }
+    }
+}
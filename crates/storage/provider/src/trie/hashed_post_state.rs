@@ -0,0 +1,170 @@
+use super::{TrieError, TrieLeaves};
+use crate::execution_result::{AccountChangeSet, AccountInfoChangeSet};
+use reth_primitives::{keccak256, Account, Address, H256, U256};
+use std::{
+    collections::BTreeMap,
+    ops::Bound::{self, Excluded, Included, Unbounded},
+};
+
+/// State changes that are not written to the hashed state yet, keyed by hashed address and slot.
+///
+/// The root of the state with the changes applied is computed by
+/// [DBTrieLoader::state_root_with_changes](super::DBTrieLoader::state_root_with_changes).
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct HashedPostState {
+    /// The changed accounts, `None` if they are destroyed.
+    pub(super) accounts: BTreeMap<H256, Option<Account>>,
+    /// The changed storage of every account.
+    pub(super) storages: BTreeMap<H256, HashedStorage>,
+}
+
+/// The changed storage of an account in a [HashedPostState].
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(super) struct HashedStorage {
+    /// Whether the storage was wiped, the slots in the database are then ignored.
+    pub(super) wiped: bool,
+    /// The changed slots, `None` if they are empty.
+    pub(super) slots: BTreeMap<H256, Option<U256>>,
+}
+
+impl HashedPostState {
+    /// Returns true if nothing changed.
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty() && self.storages.is_empty()
+    }
+
+    /// Applies the changes of a transaction or system call.
+    ///
+    /// `has_state_clear_eip` tells whether [EIP-158](https://eips.ethereum.org/EIPS/eip-158) is
+    /// active, empty accounts are then not created.
+    pub fn apply_changeset(
+        &mut self,
+        changeset: &BTreeMap<Address, AccountChangeSet>,
+        has_state_clear_eip: bool,
+    ) {
+        for (address, AccountChangeSet { account, storage, wipe_storage }) in changeset {
+            self.change_account(*address, account, has_state_clear_eip);
+
+            if storage.is_empty() && !wipe_storage {
+                continue
+            }
+            let hashed_storage = self.storages.entry(keccak256(address)).or_default();
+            if *wipe_storage {
+                hashed_storage.wiped = true;
+                hashed_storage.slots.clear();
+            }
+            for (key, (_, value)) in storage {
+                let value = (*value != U256::ZERO).then_some(*value);
+                hashed_storage.slots.insert(keccak256(H256(key.to_be_bytes())), value);
+            }
+        }
+    }
+
+    /// Applies the account changes made after the transactions of a block, like block rewards.
+    pub fn apply_account_changes(
+        &mut self,
+        changes: &BTreeMap<Address, AccountInfoChangeSet>,
+        has_state_clear_eip: bool,
+    ) {
+        for (address, change) in changes {
+            self.change_account(*address, change, has_state_clear_eip);
+        }
+    }
+
+    fn change_account(
+        &mut self,
+        address: Address,
+        change: &AccountInfoChangeSet,
+        has_state_clear_eip: bool,
+    ) {
+        let account = match change {
+            AccountInfoChangeSet::Changed { new, .. } => Some(*new),
+            AccountInfoChangeSet::Created { new } => {
+                if has_state_clear_eip && new.is_empty() {
+                    return
+                }
+                Some(*new)
+            }
+            AccountInfoChangeSet::Destroyed { .. } => None,
+            AccountInfoChangeSet::NoChange => return,
+        };
+        self.accounts.insert(keccak256(address), account);
+    }
+}
+
+/// The leaves of a trie in the database with changes applied on top, a changed leaf replaces the
+/// stored one with the same key.
+pub(super) struct ChangedLeaves<'a, L: TrieLeaves> {
+    /// The stored leaves, `None` if they are all replaced.
+    stored: Option<L>,
+    /// The changed leaves, `None` if they are removed.
+    changes: &'a BTreeMap<H256, Option<L::Value>>,
+    /// The next stored leaf.
+    next_stored: Option<(H256, L::Value)>,
+    /// The lower bound of the changed leaves that were not returned yet.
+    changed_from: Bound<H256>,
+}
+
+impl<'a, L: TrieLeaves> ChangedLeaves<'a, L>
+where
+    L::Value: Clone,
+{
+    pub(super) fn new(stored: Option<L>, changes: &'a BTreeMap<H256, Option<L::Value>>) -> Self {
+        Self { stored, changes, next_stored: None, changed_from: Unbounded }
+    }
+
+    fn next_leaf(&mut self) -> Result<Option<(H256, L::Value)>, TrieError> {
+        let changes = self.changes;
+        let mut changed = changes.range((self.changed_from, Unbounded));
+
+        loop {
+            let next_changed = changed.next();
+            let take_changed = match (next_changed, &self.next_stored) {
+                (Some((changed_key, _)), Some((stored_key, _))) => changed_key <= stored_key,
+                (next_changed, _) => next_changed.is_some(),
+            };
+
+            if !take_changed {
+                let Some(leaf) = self.next_stored.take() else { return Ok(None) };
+                self.next_stored = self.advance_stored()?;
+                return Ok(Some(leaf))
+            }
+
+            let (key, value) = next_changed.expect("changed leaf exists");
+            self.changed_from = Excluded(*key);
+            if self.next_stored.as_ref().map_or(false, |(stored_key, _)| stored_key == key) {
+                self.next_stored = self.advance_stored()?;
+            }
+            if let Some(value) = value {
+                return Ok(Some((*key, value.clone())))
+            }
+        }
+    }
+
+    fn advance_stored(&mut self) -> Result<Option<(H256, L::Value)>, TrieError> {
+        match self.stored.as_mut() {
+            Some(stored) => stored.next(),
+            None => Ok(None),
+        }
+    }
+}
+
+impl<'a, L: TrieLeaves> TrieLeaves for ChangedLeaves<'a, L>
+where
+    L::Value: Clone,
+{
+    type Value = L::Value;
+
+    fn seek(&mut self, key: H256) -> Result<Option<(H256, L::Value)>, TrieError> {
+        self.next_stored = match self.stored.as_mut() {
+            Some(stored) => stored.seek(key)?,
+            None => None,
+        };
+        self.changed_from = Included(key);
+        self.next_leaf()
+    }
+
+    fn next(&mut self) -> Result<Option<(H256, L::Value)>, TrieError> {
+        self.next_leaf()
+    }
+}
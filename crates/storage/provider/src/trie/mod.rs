@@ -1,6 +1,4 @@
 use crate::Transaction;
-use cita_trie::{PatriciaTrie, Trie};
-use hasher::HasherKeccak;
 use reth_db::{
     cursor::{DbCursorRO, DbCursorRW, DbDupCursorRO},
     database::Database,
@@ -9,197 +7,354 @@ use reth_db::{
     transaction::{DbTx, DbTxMut},
 };
 use reth_primitives::{
-    keccak256, proofs::EMPTY_ROOT, Account, Address, StorageEntry, StorageTrieEntry, TransitionId,
-    H256, KECCAK_EMPTY, U256,
+    keccak256,
+    proofs::EMPTY_ROOT,
+    trie::{HashBuilderState, Nibbles, StoredNibbles, StoredNibblesSubKey},
+    Account, Address, StorageEntry, StorageTrieEntry, TransitionId, H256, KECCAK_EMPTY, U256,
 };
-use reth_rlp::{
-    encode_fixed_size, Decodable, DecodeError, Encodable, RlpDecodable, RlpEncodable,
-    EMPTY_STRING_CODE,
-};
-use reth_tracing::tracing::*;
+use reth_rlp::{encode_fixed_size, DecodeError, Encodable, RlpDecodable, RlpEncodable};
 use std::{
     collections::{BTreeMap, BTreeSet},
     ops::Range,
-    sync::Arc,
 };
 
+mod hash_builder;
+pub use hash_builder::HashBuilder;
+
+mod hashed_post_state;
+pub use hashed_post_state::HashedPostState;
+use hashed_post_state::{ChangedLeaves, HashedStorage};
+
+mod node;
+pub use node::{ChildRef, TrieNode};
+
+mod prefix_set;
+pub use prefix_set::PrefixSet;
+
+mod range_proof;
+pub use range_proof::{verify_range_proof, RangeProofError};
+
 /// Merkle Trie error types
 #[allow(missing_docs)]
 #[derive(Debug, thiserror::Error)]
 pub enum TrieError {
-    #[error("Some error occurred: {0}")]
-    InternalError(#[from] cita_trie::TrieError),
-    #[error("The root node wasn't found in the DB")]
-    MissingRoot(H256),
     #[error("{0:?}")]
     DatabaseError(#[from] reth_db::Error),
     #[error("{0:?}")]
     DecodeError(#[from] DecodeError),
 }
 
-/// Database wrapper implementing HashDB trait.
-struct HashDatabase<'tx, 'itx, DB: Database> {
-    tx: &'tx Transaction<'itx, DB>,
+impl From<TrieError> for reth_interfaces::Error {
+    fn from(err: TrieError) -> Self {
+        match err {
+            TrieError::DatabaseError(err) => err.into(),
+            TrieError::DecodeError(_) => reth_db::Error::DecodeError.into(),
+        }
+    }
+}
+
+/// The stored branch nodes of a trie.
+trait TrieNodes {
+    /// Returns the first node with a path greater than or equal to `path`.
+    fn seek(&mut self, path: Nibbles) -> Result<Option<(Nibbles, H256)>, TrieError>;
+}
+
+/// The stored branch nodes of a trie that can be updated.
+trait TrieNodesMut: TrieNodes {
+    /// Removes the node at `path`, if any.
+    fn remove(&mut self, path: Nibbles) -> Result<(), TrieError>;
+
+    /// Stores the hash of the node at `path`, replacing any previous one.
+    fn insert(&mut self, path: Nibbles, hash: H256) -> Result<(), TrieError>;
+}
+
+/// The leaves of a trie, ordered by their hashed key.
+trait TrieLeaves {
+    /// The value of a leaf, before it is encoded into the trie.
+    type Value;
+
+    /// Returns the first leaf with a key greater than or equal to `key`.
+    fn seek(&mut self, key: H256) -> Result<Option<(H256, Self::Value)>, TrieError>;
+
+    /// Returns the leaf following the last one returned.
+    fn next(&mut self) -> Result<Option<(H256, Self::Value)>, TrieError>;
+}
+
+/// The branch nodes of the accounts trie, stored in [tables::AccountsTrie].
+struct AccountTrieNodes<C>(C);
+
+impl<'tx, C: DbCursorRO<'tx, tables::AccountsTrie>> TrieNodes for AccountTrieNodes<C> {
+    fn seek(&mut self, path: Nibbles) -> Result<Option<(Nibbles, H256)>, TrieError> {
+        Ok(self.0.seek(StoredNibbles(path))?.map(|(path, hash)| (path.0, hash)))
+    }
 }
 
-impl<'tx, 'itx, DB> cita_trie::DB for HashDatabase<'tx, 'itx, DB>
+impl<'tx, C> TrieNodesMut for AccountTrieNodes<C>
 where
-    DB: Database,
+    C: DbCursorRO<'tx, tables::AccountsTrie> + DbCursorRW<'tx, tables::AccountsTrie>,
 {
-    type Error = TrieError;
-
-    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
-        Ok(self.tx.get::<tables::AccountsTrie>(H256::from_slice(key))?)
+    fn remove(&mut self, path: Nibbles) -> Result<(), TrieError> {
+        if self.0.seek_exact(StoredNibbles(path))?.is_some() {
+            self.0.delete_current()?;
+        }
+        Ok(())
     }
 
-    fn contains(&self, key: &[u8]) -> Result<bool, Self::Error> {
-        Ok(<Self as cita_trie::DB>::get(self, key)?.is_some())
+    fn insert(&mut self, path: Nibbles, hash: H256) -> Result<(), TrieError> {
+        Ok(self.0.upsert(StoredNibbles(path), hash)?)
     }
+}
 
-    fn insert(&self, _key: Vec<u8>, _value: Vec<u8>) -> Result<(), Self::Error> {
-        unreachable!("Use batch instead.");
+/// The branch nodes of the storage trie of a single account, stored in [tables::StoragesTrie].
+struct StorageTrieNodes<C> {
+    cursor: C,
+    hashed_address: H256,
+}
+
+impl<'tx, C: DbDupCursorRO<'tx, tables::StoragesTrie>> TrieNodes for StorageTrieNodes<C> {
+    fn seek(&mut self, path: Nibbles) -> Result<Option<(Nibbles, H256)>, TrieError> {
+        Ok(self
+            .cursor
+            .seek_by_key_subkey(self.hashed_address, StoredNibblesSubKey(path))?
+            .map(|entry| (entry.nibbles.0, entry.hash)))
     }
+}
 
-    // Insert a batch of data into the cache.
-    fn insert_batch(&self, keys: Vec<Vec<u8>>, values: Vec<Vec<u8>>) -> Result<(), Self::Error> {
-        let mut cursor = self.tx.cursor_write::<tables::AccountsTrie>()?;
-        for (key, value) in keys.into_iter().zip(values.into_iter()) {
-            cursor.upsert(H256::from_slice(key.as_slice()), value)?;
+impl<'tx, C> TrieNodesMut for StorageTrieNodes<C>
+where
+    C: DbDupCursorRO<'tx, tables::StoragesTrie> + DbCursorRW<'tx, tables::StoragesTrie>,
+{
+    fn remove(&mut self, path: Nibbles) -> Result<(), TrieError> {
+        if self
+            .cursor
+            .seek_by_key_subkey(self.hashed_address, StoredNibblesSubKey(path.clone()))?
+            .filter(|entry| entry.nibbles.0 == path)
+            .is_some()
+        {
+            self.cursor.delete_current()?;
         }
         Ok(())
     }
 
-    fn remove_batch(&self, keys: &[Vec<u8>]) -> Result<(), Self::Error> {
-        let mut cursor = self.tx.cursor_write::<tables::AccountsTrie>()?;
-        for key in keys {
-            if cursor.seek_exact(H256::from_slice(key.as_slice()))?.is_some() {
-                cursor.delete_current()?;
-            }
-        }
-        Ok(())
+    fn insert(&mut self, path: Nibbles, hash: H256) -> Result<(), TrieError> {
+        self.remove(path.clone())?;
+        let entry = StorageTrieEntry { nibbles: StoredNibblesSubKey(path), hash };
+        Ok(self.cursor.upsert(self.hashed_address, entry)?)
     }
+}
+
+/// Stored branch nodes that are only read, ignoring all updates.
+struct Unchanged<N>(N);
 
-    fn remove(&self, _key: &[u8]) -> Result<(), Self::Error> {
-        unreachable!("Use batch instead.");
+impl<N: TrieNodes> TrieNodes for Unchanged<N> {
+    fn seek(&mut self, path: Nibbles) -> Result<Option<(Nibbles, H256)>, TrieError> {
+        self.0.seek(path)
     }
+}
 
-    fn flush(&self) -> Result<(), Self::Error> {
+impl<N: TrieNodes> TrieNodesMut for Unchanged<N> {
+    fn remove(&mut self, _path: Nibbles) -> Result<(), TrieError> {
+        Ok(())
+    }
+
+    fn insert(&mut self, _path: Nibbles, _hash: H256) -> Result<(), TrieError> {
         Ok(())
     }
 }
 
-impl<'tx, 'itx, DB: Database> HashDatabase<'tx, 'itx, DB> {
-    /// Instantiates a new Database for the accounts trie, with an empty root
-    fn new(tx: &'tx Transaction<'itx, DB>) -> Result<Self, TrieError> {
-        let root = EMPTY_ROOT;
-        if tx.get::<tables::AccountsTrie>(root)?.is_none() {
-            tx.put::<tables::AccountsTrie>(root, [EMPTY_STRING_CODE].to_vec())?;
+/// Stored branch nodes that may be missing, like those of a wiped storage trie.
+impl<N: TrieNodes> TrieNodes for Option<N> {
+    fn seek(&mut self, path: Nibbles) -> Result<Option<(Nibbles, H256)>, TrieError> {
+        match self {
+            Some(nodes) => nodes.seek(path),
+            None => Ok(None),
         }
-        Ok(Self { tx })
     }
+}
 
-    /// Instantiates a new Database for the accounts trie, with an existing root
-    fn from_root(tx: &'tx Transaction<'itx, DB>, root: H256) -> Result<Self, TrieError> {
-        if root == EMPTY_ROOT {
-            return Self::new(tx)
-        }
-        tx.get::<tables::AccountsTrie>(root)?.ok_or(TrieError::MissingRoot(root))?;
-        Ok(Self { tx })
+/// The accounts in [tables::HashedAccount].
+struct AccountLeaves<C>(C);
+
+impl<'tx, C: DbCursorRO<'tx, tables::HashedAccount>> TrieLeaves for AccountLeaves<C> {
+    type Value = Account;
+
+    fn seek(&mut self, key: H256) -> Result<Option<(H256, Account)>, TrieError> {
+        Ok(self.0.seek(key)?)
+    }
+
+    fn next(&mut self) -> Result<Option<(H256, Account)>, TrieError> {
+        Ok(self.0.next()?)
     }
 }
 
-/// Database wrapper implementing HashDB trait.
-struct DupHashDatabase<'tx, 'itx, DB: Database> {
-    tx: &'tx Transaction<'itx, DB>,
-    key: H256,
+/// The storage of a single account in [tables::HashedStorage].
+struct StorageLeaves<C> {
+    cursor: C,
+    hashed_address: H256,
 }
 
-impl<'tx, 'itx, DB> cita_trie::DB for DupHashDatabase<'tx, 'itx, DB>
-where
-    DB: Database,
-{
-    type Error = TrieError;
+impl<'tx, C: DbDupCursorRO<'tx, tables::HashedStorage>> TrieLeaves for StorageLeaves<C> {
+    type Value = U256;
 
-    fn get(&self, key: &[u8]) -> Result<Option<Vec<u8>>, Self::Error> {
-        let mut cursor = self.tx.cursor_dup_read::<tables::StoragesTrie>()?;
-        let subkey = H256::from_slice(key);
-        Ok(cursor
-            .seek_by_key_subkey(self.key, subkey)?
-            .filter(|entry| entry.hash == subkey)
-            .map(|entry| entry.node))
+    fn seek(&mut self, key: H256) -> Result<Option<(H256, U256)>, TrieError> {
+        Ok(self
+            .cursor
+            .seek_by_key_subkey(self.hashed_address, key)?
+            .map(|StorageEntry { key, value }| (key, value)))
     }
 
-    fn contains(&self, key: &[u8]) -> Result<bool, Self::Error> {
-        Ok(<Self as cita_trie::DB>::get(self, key)?.is_some())
+    fn next(&mut self) -> Result<Option<(H256, U256)>, TrieError> {
+        // Should be able to use walk_dup, but any call to next() causes an assert fail in mdbx.c
+        Ok(self.cursor.next_dup()?.map(|(_, StorageEntry { key, value })| (key, value)))
     }
+}
 
-    fn insert(&self, _key: Vec<u8>, _value: Vec<u8>) -> Result<(), Self::Error> {
-        unreachable!("Use batch instead.");
+/// The progress of a root computation that is limited in the number of leaves it may add.
+enum RootProgress {
+    /// All leaves were added and the root was computed.
+    Complete(H256),
+    /// The computation stopped after adding the leaf with the given key, and can be continued
+    /// with the same hash builder.
+    Paused(H256),
+}
+
+/// Computes the root of a trie, updating its stored branch nodes along the way.
+///
+/// The leaves are walked in order. Whenever the walk reaches a stored branch node whose subtree
+/// contains none of the `changes`, the node's hash is used in place of the subtree, and the walk
+/// continues after it. Stored nodes whose subtree changed are removed, and all branch nodes built
+/// from the leaves are stored.
+fn compute_root<N: TrieNodesMut, L: TrieLeaves>(
+    nodes: &mut N,
+    leaves: &mut L,
+    changes: &PrefixSet,
+    encode_leaf: impl FnMut(H256, L::Value) -> Result<Vec<u8>, TrieError>,
+) -> Result<H256, TrieError> {
+    let mut hash_builder = HashBuilder::default();
+    match compute_root_with_progress(
+        nodes,
+        leaves,
+        changes,
+        &mut hash_builder,
+        None,
+        u64::MAX,
+        encode_leaf,
+    )? {
+        RootProgress::Complete(root) => Ok(root),
+        RootProgress::Paused(..) => unreachable!("the number of leaves is not limited"),
     }
+}
 
-    /// Insert a batch of data into the cache.
-    fn insert_batch(&self, keys: Vec<Vec<u8>>, values: Vec<Vec<u8>>) -> Result<(), Self::Error> {
-        let mut cursor = self.tx.cursor_dup_write::<tables::StoragesTrie>()?;
-        for (key, node) in keys.into_iter().zip(values.into_iter()) {
-            let hash = H256::from_slice(key.as_slice());
-            if cursor.seek_by_key_subkey(self.key, hash)?.filter(|e| e.hash == hash).is_some() {
-                cursor.delete_current()?;
-            }
-            cursor.upsert(self.key, StorageTrieEntry { hash, node })?;
-        }
-        Ok(())
+/// Computes the root of a trie like [compute_root] without updating its stored branch nodes, and
+/// returns the nodes on the paths to the `targets`, see [HashBuilder::take_proof_nodes].
+///
+/// Only the stored nodes above the targets are expanded, so the walk is cheap as long as the
+/// stored nodes are up to date. Without targets, it returns the root of the stored trie.
+fn compute_proof<N: TrieNodes, L: TrieLeaves>(
+    nodes: N,
+    leaves: &mut L,
+    targets: PrefixSet,
+    encode_leaf: impl FnMut(H256, L::Value) -> Result<Vec<u8>, TrieError>,
+) -> Result<(H256, Vec<(Nibbles, Vec<u8>)>), TrieError> {
+    let mut hash_builder = HashBuilder::default().with_proof_targets(targets.clone());
+    match compute_root_with_progress(
+        &mut Unchanged(nodes),
+        leaves,
+        &targets,
+        &mut hash_builder,
+        None,
+        u64::MAX,
+        encode_leaf,
+    )? {
+        RootProgress::Complete(root) => Ok((root, hash_builder.take_proof_nodes())),
+        RootProgress::Paused(..) => unreachable!("the number of leaves is not limited"),
     }
+}
 
-    fn remove_batch(&self, keys: &[Vec<u8>]) -> Result<(), Self::Error> {
-        let mut cursor = self.tx.cursor_dup_write::<tables::StoragesTrie>()?;
-        for key in keys {
-            let hash = H256::from_slice(key.as_slice());
-            if cursor.seek_by_key_subkey(self.key, hash)?.filter(|e| e.hash == hash).is_some() {
-                cursor.delete_current()?;
+/// Like [compute_root], but stops once `max_leaves` leaves were added, and can continue with the
+/// hash builder of a previous call after the last leaf it added.
+///
+/// All branch nodes built before the computation stops lie before the last added leaf, so they
+/// are stored and are not visited again when the walk continues after it.
+fn compute_root_with_progress<N: TrieNodesMut, L: TrieLeaves>(
+    nodes: &mut N,
+    leaves: &mut L,
+    changes: &PrefixSet,
+    hash_builder: &mut HashBuilder,
+    resume_after: Option<H256>,
+    max_leaves: u64,
+    mut encode_leaf: impl FnMut(H256, L::Value) -> Result<Vec<u8>, TrieError>,
+) -> Result<RootProgress, TrieError> {
+    // Everything left of `next` has been added to the hash builder.
+    let mut next = match resume_after {
+        Some(last_key) => Nibbles::unpack(last_key).increment(),
+        None => Some(Nibbles::default()),
+    };
+    let mut added_leaves = 0;
+
+    while let Some(from) = next.take() {
+        // Find the next stored node that can stand in for its subtree.
+        let mut subtree = None;
+        let mut node = nodes.seek(from.clone())?;
+        while let Some((path, hash)) = node {
+            if !changes.contains(&path) {
+                subtree = Some((path, hash));
+                break
             }
+            nodes.remove(path.clone())?;
+            let mut first_child = path;
+            first_child.push(0);
+            node = nodes.seek(first_child)?;
         }
-        Ok(())
-    }
 
-    fn remove(&self, _key: &[u8]) -> Result<(), Self::Error> {
-        unreachable!("Use batch instead.");
-    }
+        // Add the leaves left of it.
+        let mut leaf = leaves.seek(nibbles_to_key(&from))?;
+        while let Some((key, value)) = leaf {
+            let path = Nibbles::unpack(key);
+            if subtree.as_ref().map_or(false, |(subtree_path, _)| path >= *subtree_path) {
+                break
+            }
+            hash_builder.add_leaf(path, &encode_leaf(key, value)?);
+
+            added_leaves += 1;
+            if added_leaves >= max_leaves {
+                for (path, hash) in hash_builder.take_branch_nodes() {
+                    nodes.insert(path, hash)?;
+                }
+                return Ok(RootProgress::Paused(key))
+            }
 
-    fn flush(&self) -> Result<(), Self::Error> {
-        Ok(())
-    }
-}
+            leaf = leaves.next()?;
+        }
 
-impl<'tx, 'itx, DB: Database> DupHashDatabase<'tx, 'itx, DB> {
-    /// Instantiates a new Database for the storage trie, with an empty root
-    fn new(tx: &'tx Transaction<'itx, DB>, key: H256) -> Result<Self, TrieError> {
-        let root = EMPTY_ROOT;
-        let mut cursor = tx.cursor_dup_write::<tables::StoragesTrie>()?;
-        if cursor.seek_by_key_subkey(key, root)?.filter(|entry| entry.hash == root).is_none() {
-            tx.put::<tables::StoragesTrie>(
-                key,
-                StorageTrieEntry { hash: root, node: [EMPTY_STRING_CODE].to_vec() },
-            )?;
+        if let Some((path, hash)) = subtree {
+            next = path.increment();
+            hash_builder.add_branch(path, hash);
         }
-        Ok(Self { tx, key })
-    }
 
-    /// Instantiates a new Database for the storage trie, with an existing root
-    fn from_root(tx: &'tx Transaction<'itx, DB>, key: H256, root: H256) -> Result<Self, TrieError> {
-        if root == EMPTY_ROOT {
-            return Self::new(tx, key)
+        for (path, hash) in hash_builder.take_branch_nodes() {
+            nodes.insert(path, hash)?;
         }
-        tx.cursor_dup_read::<tables::StoragesTrie>()?
-            .seek_by_key_subkey(key, root)?
-            .filter(|entry| entry.hash == root)
-            .ok_or(TrieError::MissingRoot(root))?;
-        Ok(Self { tx, key })
     }
+
+    let root = hash_builder.root();
+    for (path, hash) in hash_builder.take_branch_nodes() {
+        nodes.insert(path, hash)?;
+    }
+    Ok(RootProgress::Complete(root))
+}
+
+/// Returns the smallest key starting with the given path.
+fn nibbles_to_key(path: &Nibbles) -> H256 {
+    let mut key = H256::zero();
+    let packed = path.pack();
+    key.0[..packed.len()].copy_from_slice(&packed);
+    key
 }
 
 /// An Ethereum account, for RLP encoding traits deriving.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Default, RlpEncodable, RlpDecodable)]
-pub(crate) struct EthAccount {
+pub struct EthAccount {
     /// Account nonce.
     nonce: u64,
     /// Account balance.
@@ -222,13 +377,49 @@ impl From<Account> for EthAccount {
 }
 
 impl EthAccount {
-    pub(crate) fn from_with_root(acc: Account, storage_root: H256) -> EthAccount {
+    /// Creates the trie representation of the account with the given storage root.
+    pub fn from_with_root(acc: Account, storage_root: H256) -> EthAccount {
         Self { storage_root, ..Self::from(acc) }
     }
+
+    /// Returns the storage root of the account.
+    pub fn storage_root(&self) -> H256 {
+        self.storage_root
+    }
+}
+
+impl From<EthAccount> for Account {
+    fn from(acc: EthAccount) -> Self {
+        Account {
+            nonce: acc.nonce,
+            balance: acc.balance,
+            bytecode_hash: (acc.code_hash != KECCAK_EMPTY).then_some(acc.code_hash),
+        }
+    }
+}
+
+/// The progress of a state root computation that is limited in the number of accounts it may
+/// process, see [DBTrieLoader::calculate_root_with_progress].
+#[derive(Debug)]
+pub enum TrieProgress {
+    /// The state root was computed.
+    Complete(H256),
+    /// The computation stopped after the account with the hashed address `last_account_key`.
+    InProgress {
+        /// The hashed address of the last account added to the trie.
+        last_account_key: H256,
+        /// The state of the hash builder, to continue the computation with.
+        state: HashBuilderState,
+    },
 }
 
 /// Struct for calculating the root of a merkle patricia tree,
 /// while populating the database with intermediate hashes.
+///
+/// The hashes of the branch nodes of the state trie and of all storage tries are stored keyed by
+/// their path, see [tables::AccountsTrie] and [tables::StoragesTrie]. When the root is updated,
+/// only the branch nodes above changed keys are recomputed, all other subtrees are represented by
+/// their stored hash.
 #[derive(Debug, Default)]
 pub struct DBTrieLoader;
 
@@ -238,156 +429,255 @@ impl DBTrieLoader {
         &self,
         tx: &Transaction<'_, DB>,
     ) -> Result<H256, TrieError> {
-        tx.clear::<tables::AccountsTrie>()?;
-        tx.clear::<tables::StoragesTrie>()?;
-
-        let mut accounts_cursor = tx.cursor_read::<tables::HashedAccount>()?;
-        let mut walker = accounts_cursor.walk(None)?;
-
-        let db = Arc::new(HashDatabase::new(tx)?);
-
-        let hasher = Arc::new(HasherKeccak::new());
-
-        let mut trie = PatriciaTrie::new(Arc::clone(&db), Arc::clone(&hasher));
-
-        while let Some((hashed_address, account)) = walker.next().transpose()? {
-            let value = EthAccount::from_with_root(
-                account,
-                self.calculate_storage_root(tx, hashed_address)?,
-            );
-
-            let mut out = Vec::new();
-            Encodable::encode(&value, &mut out);
-            trie.insert(hashed_address.as_bytes().to_vec(), out)?;
+        match self.calculate_root_with_progress(tx, None, u64::MAX)? {
+            TrieProgress::Complete(root) => Ok(root),
+            TrieProgress::InProgress { .. } => {
+                unreachable!("the number of accounts is not limited")
+            }
         }
-        let root = H256::from_slice(trie.root()?.as_slice());
-
-        Ok(root)
     }
 
-    fn calculate_storage_root<DB: Database>(
+    /// Calculates the root of the state trie like [DBTrieLoader::calculate_root], but stops after
+    /// `max_accounts` accounts were added to the trie.
+    ///
+    /// If `resume` holds the last account key and hash builder state of a previous call that
+    /// stopped, the computation continues from there. The hashed state must not have changed in
+    /// between, and the intermediate hashes stored by the previous call must have been committed.
+    pub fn calculate_root_with_progress<DB: Database>(
         &self,
         tx: &Transaction<'_, DB>,
-        address: H256,
-    ) -> Result<H256, TrieError> {
-        let db = Arc::new(DupHashDatabase::new(tx, address)?);
-
-        let hasher = Arc::new(HasherKeccak::new());
-
-        let mut trie = PatriciaTrie::new(Arc::clone(&db), Arc::clone(&hasher));
-
-        let mut storage_cursor = tx.cursor_dup_read::<tables::HashedStorage>()?;
-
-        // Should be able to use walk_dup, but any call to next() causes an assert fail in mdbx.c
-        // let mut walker = storage_cursor.walk_dup(address, H256::zero())?;
-        let mut current = storage_cursor.seek_by_key_subkey(address, H256::zero())?;
-
-        while let Some(StorageEntry { key: storage_key, value }) = current {
-            let out = encode_fixed_size(&value).to_vec();
-            trie.insert(storage_key.to_vec(), out)?;
-            current = storage_cursor.next_dup()?.map(|(_, v)| v);
-        }
-
-        let root = H256::from_slice(trie.root()?.as_slice());
-
-        // if root is empty remove it from db
-        if root == EMPTY_ROOT {
-            tx.delete::<tables::StoragesTrie>(address, None)?;
+        resume: Option<(H256, HashBuilderState)>,
+        max_accounts: u64,
+    ) -> Result<TrieProgress, TrieError> {
+        if resume.is_none() {
+            tx.clear::<tables::AccountsTrie>()?;
+            tx.clear::<tables::StoragesTrie>()?;
         }
+        let (mut hash_builder, resume_after) = match resume {
+            Some((last_account_key, state)) => (state.into(), Some(last_account_key)),
+            None => (HashBuilder::default(), None),
+        };
 
-        Ok(root)
+        let progress = match self.state_root(
+            tx,
+            &PrefixSet::default(),
+            &BTreeMap::new(),
+            &mut hash_builder,
+            resume_after,
+            max_accounts,
+        )? {
+            RootProgress::Complete(root) => TrieProgress::Complete(root),
+            RootProgress::Paused(last_account_key) => {
+                TrieProgress::InProgress { last_account_key, state: hash_builder.into() }
+            }
+        };
+        Ok(progress)
     }
 
-    /// Calculates the root of the state trie by updating an existing trie.
+    /// Calculates the root of the state trie by updating the stored intermediate hashes with the
+    /// state changes in the given transition range.
     pub fn update_root<DB: Database>(
         &self,
         tx: &Transaction<'_, DB>,
-        root: H256,
         tid_range: Range<TransitionId>,
     ) -> Result<H256, TrieError> {
-        let mut accounts_cursor = tx.cursor_read::<tables::HashedAccount>()?;
-
         let changed_accounts = self.gather_changes(tx, tid_range)?;
 
-        let db = Arc::new(HashDatabase::from_root(tx, root)?);
+        let mut accounts_cursor = tx.cursor_read::<tables::HashedAccount>()?;
+        for hashed_address in changed_accounts.keys() {
+            if accounts_cursor.seek_exact(*hashed_address)?.is_none() {
+                tx.delete::<tables::StoragesTrie>(*hashed_address, None)?;
+            }
+        }
 
-        let hasher = Arc::new(HasherKeccak::new());
+        let account_changes = changed_accounts.keys().map(Nibbles::unpack).collect();
+        let storage_changes = changed_accounts
+            .into_iter()
+            .map(|(hashed_address, storage)| {
+                (hashed_address, storage.into_iter().map(Nibbles::unpack).collect())
+            })
+            .collect();
 
-        let mut trie = PatriciaTrie::from(Arc::clone(&db), Arc::clone(&hasher), root.as_bytes())?;
+        let mut hash_builder = HashBuilder::default();
+        match self.state_root(
+            tx,
+            &account_changes,
+            &storage_changes,
+            &mut hash_builder,
+            None,
+            u64::MAX,
+        )? {
+            RootProgress::Complete(root) => Ok(root),
+            RootProgress::Paused(..) => unreachable!("the number of accounts is not limited"),
+        }
+    }
 
-        for (address, changed_storages) in changed_accounts {
-            let storage_root = if let Some(account) = trie.get(address.as_slice())? {
-                trie.remove(address.as_bytes())?;
+    /// Returns the root of the state trie and the nodes on the paths to the given targets, which
+    /// are hashed addresses or the paths of nodes, without updating the stored intermediate
+    /// hashes.
+    ///
+    /// The nodes are RLP encoded and ordered by their path. Nodes that are embedded in their
+    /// parent are only part of the parent's encoding. Without targets, only the root is computed,
+    /// which is cheap as long as the intermediate hashes are up to date with the hashed state.
+    pub fn state_proof<'tx, TX: DbTx<'tx>>(
+        &self,
+        tx: &TX,
+        targets: &[Nibbles],
+    ) -> Result<(H256, Vec<(Nibbles, Vec<u8>)>), TrieError> {
+        let nodes = AccountTrieNodes(tx.cursor_read::<tables::AccountsTrie>()?);
+        let mut leaves = AccountLeaves(tx.cursor_read::<tables::HashedAccount>()?);
+
+        compute_proof(
+            nodes,
+            &mut leaves,
+            targets.iter().cloned().collect(),
+            |hashed_address, account| {
+                let (storage_root, _) = self.storage_proof(tx, hashed_address, &[])?;
 
-                let storage_root = EthAccount::decode(&mut account.as_slice())?.storage_root;
-                self.update_storage_root(tx, storage_root, address, changed_storages)?
-            } else {
-                self.calculate_storage_root(tx, address)?
-            };
+                let mut out = Vec::new();
+                EthAccount::from_with_root(account, storage_root).encode(&mut out);
+                Ok(out)
+            },
+        )
+    }
+
+    /// Returns the root of the storage trie of the account with the given hashed address and the
+    /// nodes on the paths to the given targets, like [DBTrieLoader::state_proof].
+    pub fn storage_proof<'tx, TX: DbTx<'tx>>(
+        &self,
+        tx: &TX,
+        hashed_address: H256,
+        targets: &[Nibbles],
+    ) -> Result<(H256, Vec<(Nibbles, Vec<u8>)>), TrieError> {
+        let nodes = StorageTrieNodes {
+            cursor: tx.cursor_dup_read::<tables::StoragesTrie>()?,
+            hashed_address,
+        };
+        let mut leaves = StorageLeaves {
+            cursor: tx.cursor_dup_read::<tables::HashedStorage>()?,
+            hashed_address,
+        };
 
-            if let Some((_, account)) = accounts_cursor.seek_exact(address)? {
-                let value = EthAccount::from_with_root(account, storage_root);
+        compute_proof(nodes, &mut leaves, targets.iter().cloned().collect(), |_, value| {
+            Ok(encode_fixed_size(&value).to_vec())
+        })
+    }
 
-                let mut out = Vec::new();
-                Encodable::encode(&value, &mut out);
-                trie.insert(address.as_bytes().to_vec(), out)?;
-            }
-        }
+    /// Returns the root of the state trie with the given changes applied on top of the hashed
+    /// state, without updating the stored intermediate hashes.
+    ///
+    /// Only the stored nodes above the changed accounts and slots are expanded, so the root is
+    /// cheap to compute as long as there are few changes and the intermediate hashes are up to
+    /// date with the hashed state.
+    pub fn state_root_with_changes<'tx, TX: DbTx<'tx>>(
+        &self,
+        tx: &TX,
+        changes: &HashedPostState,
+    ) -> Result<H256, TrieError> {
+        let mut nodes = Unchanged(AccountTrieNodes(tx.cursor_read::<tables::AccountsTrie>()?));
+        let mut leaves = ChangedLeaves::new(
+            Some(AccountLeaves(tx.cursor_read::<tables::HashedAccount>()?)),
+            &changes.accounts,
+        );
+        let account_changes =
+            changes.accounts.keys().chain(changes.storages.keys()).map(Nibbles::unpack).collect();
 
-        let new_root = H256::from_slice(trie.root()?.as_slice());
-        if new_root != root {
-            let mut cursor = tx.cursor_write::<tables::AccountsTrie>()?;
-            if cursor.seek_exact(root)?.is_some() {
-                cursor.delete_current()?;
-            }
-        }
+        compute_root(&mut nodes, &mut leaves, &account_changes, |hashed_address, account| {
+            let storage_root = match changes.storages.get(&hashed_address) {
+                Some(storage) => self.storage_root_with_changes(tx, hashed_address, storage)?,
+                None => self.storage_proof(tx, hashed_address, &[])?.0,
+            };
 
-        Ok(new_root)
+            let mut out = Vec::new();
+            EthAccount::from_with_root(account, storage_root).encode(&mut out);
+            Ok(out)
+        })
     }
 
-    fn update_storage_root<DB: Database>(
+    fn storage_root_with_changes<'tx, TX: DbTx<'tx>>(
         &self,
-        tx: &Transaction<'_, DB>,
-        root: H256,
-        address: H256,
-        changed_storages: BTreeSet<H256>,
+        tx: &TX,
+        hashed_address: H256,
+        storage: &HashedStorage,
     ) -> Result<H256, TrieError> {
-        let db = Arc::new(DupHashDatabase::from_root(tx, address, root)?);
+        // The stored nodes and slots of a wiped storage are all replaced.
+        let (nodes, leaves) = if storage.wiped {
+            (None, None)
+        } else {
+            let nodes = StorageTrieNodes {
+                cursor: tx.cursor_dup_read::<tables::StoragesTrie>()?,
+                hashed_address,
+            };
+            let leaves = StorageLeaves {
+                cursor: tx.cursor_dup_read::<tables::HashedStorage>()?,
+                hashed_address,
+            };
+            (Some(nodes), Some(leaves))
+        };
+        let mut leaves = ChangedLeaves::new(leaves, &storage.slots);
+        let changes = storage.slots.keys().map(Nibbles::unpack).collect();
 
-        let hasher = Arc::new(HasherKeccak::new());
+        compute_root(&mut Unchanged(nodes), &mut leaves, &changes, |_, value| {
+            Ok(encode_fixed_size(&value).to_vec())
+        })
+    }
 
-        let mut trie = PatriciaTrie::from(Arc::clone(&db), Arc::clone(&hasher), root.as_bytes())?;
-        let mut storage_cursor = tx.cursor_dup_read::<tables::HashedStorage>()?;
+    fn state_root<DB: Database>(
+        &self,
+        tx: &Transaction<'_, DB>,
+        account_changes: &PrefixSet,
+        storage_changes: &BTreeMap<H256, PrefixSet>,
+        hash_builder: &mut HashBuilder,
+        resume_after: Option<H256>,
+        max_accounts: u64,
+    ) -> Result<RootProgress, TrieError> {
+        let mut nodes = AccountTrieNodes(tx.cursor_write::<tables::AccountsTrie>()?);
+        let mut leaves = AccountLeaves(tx.cursor_read::<tables::HashedAccount>()?);
+        let unchanged = PrefixSet::default();
+
+        compute_root_with_progress(
+            &mut nodes,
+            &mut leaves,
+            account_changes,
+            hash_builder,
+            resume_after,
+            max_accounts,
+            |hashed_address, account| {
+                let changes = storage_changes.get(&hashed_address).unwrap_or(&unchanged);
+                let storage_root = self.storage_root(tx, hashed_address, changes)?;
 
-        for key in changed_storages {
-            if let Some(StorageEntry { value, .. }) =
-                storage_cursor.seek_by_key_subkey(address, key)?.filter(|e| e.key == key)
-            {
-                let out = encode_fixed_size(&value).to_vec();
-                trie.insert(key.as_bytes().to_vec(), out)?;
-            } else {
-                trie.remove(key.as_bytes())?;
-            }
-        }
+                let mut out = Vec::new();
+                EthAccount::from_with_root(account, storage_root).encode(&mut out);
+                Ok(out)
+            },
+        )
+    }
 
-        let new_root = H256::from_slice(trie.root()?.as_slice());
-        if new_root != root {
-            let mut cursor = tx.cursor_dup_write::<tables::StoragesTrie>()?;
-            if cursor
-                .seek_by_key_subkey(address, root)?
-                .filter(|entry| entry.hash == root)
-                .is_some()
-            {
-                cursor.delete_current()?;
-            }
-        }
+    fn storage_root<DB: Database>(
+        &self,
+        tx: &Transaction<'_, DB>,
+        hashed_address: H256,
+        changes: &PrefixSet,
+    ) -> Result<H256, TrieError> {
+        let mut nodes = StorageTrieNodes {
+            cursor: tx.cursor_dup_write::<tables::StoragesTrie>()?,
+            hashed_address,
+        };
+        let mut leaves = StorageLeaves {
+            cursor: tx.cursor_dup_read::<tables::HashedStorage>()?,
+            hashed_address,
+        };
+
+        let root = compute_root(&mut nodes, &mut leaves, changes, |_, value| {
+            Ok(encode_fixed_size(&value).to_vec())
+        })?;
 
         // if root is empty remove it from db
-        if new_root == EMPTY_ROOT {
-            tx.delete::<tables::StoragesTrie>(address, None)?;
+        if root == EMPTY_ROOT {
+            tx.delete::<tables::StoragesTrie>(hashed_address, None)?;
         }
 
-        Ok(new_root)
+        Ok(root)
     }
 
     fn gather_changes<DB: Database>(
@@ -431,6 +721,7 @@ impl DBTrieLoader {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::execution_result::{AccountChangeSet, AccountInfoChangeSet};
     use assert_matches::assert_matches;
     use proptest::{prelude::ProptestConfig, proptest};
     use reth_db::{mdbx::test_utils::create_test_rw_db, tables, transaction::DbTxMut};
@@ -522,7 +813,7 @@ mod tests {
         });
         let expected = H256(sec_trie_root::<KeccakHasher, _, _, _>(encoded_storage).0);
         assert_matches!(
-            trie.calculate_storage_root(&tx, hashed_address),
+            trie.storage_root(&tx, hashed_address, &PrefixSet::default()),
             Ok(got) if got == expected
         );
     }
@@ -648,6 +939,337 @@ mod tests {
         );
     }
 
+    type State = BTreeMap<Address, (Account, BTreeMap<H256, U256>)>;
+
+    fn expected_state_root(state: &State) -> H256 {
+        let encoded_accounts = state.iter().map(|(address, (account, storage))| {
+            let encoded_storage = storage.iter().map(|(k, v)| (*k, encode_fixed_size(v).to_vec()));
+            let storage_root = H256(sec_trie_root::<KeccakHasher, _, _, _>(encoded_storage).0);
+            let mut out = Vec::new();
+            EthAccount::from_with_root(*account, storage_root).encode(&mut out);
+            (*address, out)
+        });
+        H256(sec_trie_root::<KeccakHasher, _, _, _>(encoded_accounts).0)
+    }
+
+    fn stored_nodes<DB: Database>(
+        tx: &Transaction<'_, DB>,
+    ) -> (Vec<(StoredNibbles, H256)>, Vec<(H256, StorageTrieEntry)>) {
+        let accounts = tx
+            .cursor_read::<tables::AccountsTrie>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        let storages = tx
+            .cursor_dup_read::<tables::StoragesTrie>()
+            .unwrap()
+            .walk(None)
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        (accounts, storages)
+    }
+
+    #[test]
+    fn update_root_matches_rebuild() {
+        let trie = DBTrieLoader::default();
+        let db = create_test_rw_db();
+        let tx = Transaction::new(db.as_ref()).unwrap();
+
+        let mut state = (0..200u64)
+            .map(|i| {
+                let account = Account { nonce: i, balance: U256::from(i), bytecode_hash: None };
+                let storage = (0..(i % 5) * 10)
+                    .map(|j| (H256::from_low_u64_be(j), U256::from(i * 100 + j + 1)))
+                    .collect();
+                (Address::from_low_u64_be(i), (account, storage))
+            })
+            .collect::<State>();
+        for (address, (account, storage)) in &state {
+            let hashed_address = keccak256(address);
+            tx.put::<tables::HashedAccount>(hashed_address, *account).unwrap();
+            for (key, value) in storage {
+                tx.put::<tables::HashedStorage>(
+                    hashed_address,
+                    StorageEntry { key: keccak256(key), value: *value },
+                )
+                .unwrap();
+            }
+        }
+        assert_eq!(trie.calculate_root(&tx).unwrap(), expected_state_root(&state));
+
+        // Applies an account change in transition 1, recording the previous value.
+        let set_account = |state: &mut State, address: Address, account: Option<Account>| {
+            let previous = match account {
+                Some(account) => {
+                    tx.put::<tables::HashedAccount>(keccak256(address), account).unwrap();
+                    state.entry(address).or_default().0 = account;
+                    Some(account)
+                }
+                None => {
+                    tx.delete::<tables::HashedAccount>(keccak256(address), None).unwrap();
+                    state.remove(&address).map(|(account, _)| account)
+                }
+            };
+            tx.put::<tables::AccountChangeSet>(1, AccountBeforeTx { address, info: previous })
+                .unwrap();
+        };
+        // Applies a storage change in transition 1, recording the previous value.
+        let set_storage = |state: &mut State, address: Address, key: H256, value: U256| {
+            let hashed_address = keccak256(address);
+            let hashed_key = keccak256(key);
+            let mut cursor = tx.cursor_dup_write::<tables::HashedStorage>().unwrap();
+            if cursor
+                .seek_by_key_subkey(hashed_address, hashed_key)
+                .unwrap()
+                .filter(|entry| entry.key == hashed_key)
+                .is_some()
+            {
+                cursor.delete_current().unwrap();
+            }
+            let storage = &mut state.get_mut(&address).unwrap().1;
+            let previous = if value == U256::ZERO {
+                storage.remove(&key)
+            } else {
+                cursor.upsert(hashed_address, StorageEntry { key: hashed_key, value }).unwrap();
+                storage.insert(key, value)
+            };
+            tx.put::<tables::StorageChangeSet>(
+                (1, address).into(),
+                StorageEntry { key, value: previous.unwrap_or_default() },
+            )
+            .unwrap();
+        };
+
+        let account = |nonce| Account { nonce, balance: U256::from(1), bytecode_hash: None };
+        set_account(&mut state, Address::from_low_u64_be(3), Some(account(42)));
+        set_account(&mut state, Address::from_low_u64_be(5), None);
+        set_account(&mut state, Address::from_low_u64_be(1000), Some(account(1)));
+        set_storage(&mut state, Address::from_low_u64_be(1000), H256::zero(), U256::from(1));
+        for (i, slot) in [(4, 0), (4, 1), (4, 100), (38, 7), (199, 39)] {
+            let value = if slot == 1 { U256::ZERO } else { U256::from(slot + 7) };
+            set_storage(
+                &mut state,
+                Address::from_low_u64_be(i),
+                H256::from_low_u64_be(slot),
+                value,
+            );
+        }
+
+        let root = trie.update_root(&tx, 1..2).unwrap();
+        assert_eq!(root, expected_state_root(&state));
+
+        // The stored nodes are the same as if the trie was built from scratch.
+        let updated = stored_nodes(&tx);
+        assert_eq!(trie.calculate_root(&tx).unwrap(), root);
+        assert_eq!(stored_nodes(&tx), updated);
+    }
+
+    #[test]
+    fn calculate_root_with_progress_matches_rebuild() {
+        let trie = DBTrieLoader::default();
+        let db = create_test_rw_db();
+        let tx = Transaction::new(db.as_ref()).unwrap();
+
+        let state = (0..100u64)
+            .map(|i| {
+                let account = Account { nonce: i, balance: U256::from(i), bytecode_hash: None };
+                let storage = (0..i % 3 * 5)
+                    .map(|j| (H256::from_low_u64_be(j), U256::from(i * 100 + j + 1)))
+                    .collect();
+                (Address::from_low_u64_be(i), (account, storage))
+            })
+            .collect::<State>();
+        for (address, (account, storage)) in &state {
+            let hashed_address = keccak256(address);
+            tx.put::<tables::HashedAccount>(hashed_address, *account).unwrap();
+            for (key, value) in storage {
+                tx.put::<tables::HashedStorage>(
+                    hashed_address,
+                    StorageEntry { key: keccak256(key), value: *value },
+                )
+                .unwrap();
+            }
+        }
+
+        let mut resume = None;
+        let mut calls = 0;
+        let root = loop {
+            calls += 1;
+            match trie.calculate_root_with_progress(&tx, resume.take(), 7).unwrap() {
+                TrieProgress::Complete(root) => break root,
+                TrieProgress::InProgress { last_account_key, state } => {
+                    resume = Some((last_account_key, state))
+                }
+            }
+        };
+        assert_eq!(calls, 100 / 7 + 1);
+        assert_eq!(root, expected_state_root(&state));
+
+        let resumed = stored_nodes(&tx);
+        assert_eq!(trie.calculate_root(&tx).unwrap(), root);
+        assert_eq!(stored_nodes(&tx), resumed);
+    }
+
+    #[test]
+    fn state_root_with_changes_matches_rebuild() {
+        let trie = DBTrieLoader::default();
+        let db = create_test_rw_db();
+        let tx = Transaction::new(db.as_ref()).unwrap();
+
+        let mut state = (0..100u64)
+            .map(|i| {
+                let account = Account { nonce: i, balance: U256::from(i), bytecode_hash: None };
+                let storage = (0..i % 4 * 10)
+                    .map(|j| (H256::from_low_u64_be(j), U256::from(i * 100 + j + 1)))
+                    .collect();
+                (Address::from_low_u64_be(i), (account, storage))
+            })
+            .collect::<State>();
+        for (address, (account, storage)) in &state {
+            let hashed_address = keccak256(address);
+            tx.put::<tables::HashedAccount>(hashed_address, *account).unwrap();
+            for (key, value) in storage {
+                tx.put::<tables::HashedStorage>(
+                    hashed_address,
+                    StorageEntry { key: keccak256(key), value: *value },
+                )
+                .unwrap();
+            }
+        }
+        let root = trie.calculate_root(&tx).unwrap();
+        assert_eq!(trie.state_root_with_changes(&tx, &HashedPostState::default()).unwrap(), root);
+        let stored = stored_nodes(&tx);
+
+        let account = |nonce| Account { nonce, balance: U256::from(1), bytecode_hash: None };
+        let changed = |account| AccountInfoChangeSet::Changed { new: account, old: account };
+        let slot = |slot: u64| U256::from(slot);
+        let changeset = BTreeMap::from([
+            (
+                Address::from_low_u64_be(3),
+                AccountChangeSet {
+                    account: changed(account(42)),
+                    storage: BTreeMap::from([
+                        (slot(0), (U256::ZERO, U256::from(7))),
+                        (slot(1), (U256::ZERO, U256::ZERO)),
+                        (slot(100), (U256::ZERO, U256::from(8))),
+                    ]),
+                    wipe_storage: false,
+                },
+            ),
+            (
+                Address::from_low_u64_be(5),
+                AccountChangeSet {
+                    account: AccountInfoChangeSet::Destroyed { old: account(5) },
+                    storage: BTreeMap::new(),
+                    wipe_storage: true,
+                },
+            ),
+            (
+                Address::from_low_u64_be(7),
+                AccountChangeSet {
+                    account: AccountInfoChangeSet::NoChange,
+                    storage: BTreeMap::from([(slot(1000), (U256::ZERO, U256::from(9)))]),
+                    wipe_storage: true,
+                },
+            ),
+            (
+                Address::from_low_u64_be(1000),
+                AccountChangeSet {
+                    account: AccountInfoChangeSet::Created { new: account(1) },
+                    storage: BTreeMap::from([(slot(0), (U256::ZERO, U256::from(1)))]),
+                    wipe_storage: false,
+                },
+            ),
+        ]);
+        let mut changes = HashedPostState::default();
+        changes.apply_changeset(&changeset, true);
+
+        state.get_mut(&Address::from_low_u64_be(3)).unwrap().0 = account(42);
+        let storage = &mut state.get_mut(&Address::from_low_u64_be(3)).unwrap().1;
+        storage.insert(H256::from_low_u64_be(0), U256::from(7));
+        storage.remove(&H256::from_low_u64_be(1));
+        storage.insert(H256::from_low_u64_be(100), U256::from(8));
+        state.remove(&Address::from_low_u64_be(5));
+        state.get_mut(&Address::from_low_u64_be(7)).unwrap().1 =
+            BTreeMap::from([(H256::from_low_u64_be(1000), U256::from(9))]);
+        state.insert(
+            Address::from_low_u64_be(1000),
+            (account(1), BTreeMap::from([(H256::zero(), U256::from(1))])),
+        );
+
+        assert_eq!(
+            trie.state_root_with_changes(&tx, &changes).unwrap(),
+            expected_state_root(&state)
+        );
+        // The stored nodes are not updated.
+        assert_eq!(stored_nodes(&tx), stored);
+    }
+
+    #[test]
+    fn proofs_of_stored_trie() {
+        let trie = DBTrieLoader::default();
+        let db = create_test_rw_db();
+        let tx = Transaction::new(db.as_ref()).unwrap();
+
+        let state = (0..100u64)
+            .map(|i| {
+                let account = Account { nonce: i, balance: U256::from(i), bytecode_hash: None };
+                let storage = (0..i % 3 * 10)
+                    .map(|j| (H256::from_low_u64_be(j), U256::from(i * 100 + j + 1)))
+                    .collect();
+                (Address::from_low_u64_be(i), (account, storage))
+            })
+            .collect::<State>();
+        for (address, (account, storage)) in &state {
+            let hashed_address = keccak256(address);
+            tx.put::<tables::HashedAccount>(hashed_address, *account).unwrap();
+            for (key, value) in storage {
+                tx.put::<tables::HashedStorage>(
+                    hashed_address,
+                    StorageEntry { key: keccak256(key), value: *value },
+                )
+                .unwrap();
+            }
+        }
+        let root = trie.calculate_root(&tx).unwrap();
+
+        // Every node of the proof is referenced by the hash in its parent.
+        let assert_proof = |root: H256, nodes: &[(Nibbles, Vec<u8>)], target: &Nibbles| {
+            assert_eq!(nodes[0].0, Nibbles::default());
+            assert_eq!(keccak256(&nodes[0].1), root);
+            for pair in nodes.windows(2) {
+                assert!(target.starts_with(&pair[1].0));
+                let hash = keccak256(&pair[1].1);
+                assert!(pair[0].1.windows(32).any(|window| window == hash.as_bytes()));
+            }
+        };
+
+        assert_eq!(trie.state_proof(&*tx, &[]).unwrap(), (root, vec![]));
+        let address = Address::from_low_u64_be(5);
+        let (storage_root, _) = trie.storage_proof(&*tx, keccak256(address), &[]).unwrap();
+        let target = Nibbles::unpack(keccak256(address));
+        let (proof_root, nodes) = trie.state_proof(&*tx, &[target.clone()]).unwrap();
+        assert_eq!(proof_root, root);
+        assert_proof(root, &nodes, &target);
+        let mut account = Vec::new();
+        EthAccount::from_with_root(state[&address].0, storage_root).encode(&mut account);
+        assert!(nodes.last().unwrap().1.windows(account.len()).any(|window| window == account));
+
+        let target = Nibbles::unpack(keccak256(H256::from_low_u64_be(3)));
+        let (proof_root, nodes) =
+            trie.storage_proof(&*tx, keccak256(address), &[target.clone()]).unwrap();
+        assert_eq!(proof_root, storage_root);
+        assert_proof(storage_root, &nodes, &target);
+
+        // Proving does not change the stored nodes.
+        let stored = stored_nodes(&tx);
+        trie.state_proof(&*tx, &[Nibbles::from_hex(vec![1, 2])]).unwrap();
+        assert_eq!(stored_nodes(&tx), stored);
+    }
+
     fn test_with_accounts(accounts: BTreeMap<Address, (Account, BTreeSet<StorageEntry>)>) {
         let trie = DBTrieLoader::default();
         let db = create_test_rw_db();
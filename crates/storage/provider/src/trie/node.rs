@@ -0,0 +1,159 @@
+use reth_primitives::{trie::Nibbles, H256};
+use reth_rlp::{DecodeError, Header};
+
+/// The reference to a node in its parent.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChildRef {
+    /// The hash of a node whose RLP is at least 32 bytes long.
+    Hash(H256),
+    /// The RLP of a node that is shorter than 32 bytes, embedded in its parent.
+    Embedded(Vec<u8>),
+}
+
+/// A node of a keccak-keyed Merkle Patricia Trie, decoded from its RLP.
+///
+/// All keys of such a trie have the same length, so branch nodes never hold a value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrieNode {
+    /// A branch node with the references to its children, indexed by their nibble.
+    Branch(Vec<Option<ChildRef>>),
+    /// An extension node with the shared part of the paths below it.
+    Extension {
+        /// The path from the node to its child.
+        key: Nibbles,
+        /// The child, which is a branch node.
+        child: ChildRef,
+    },
+    /// A leaf node with the remaining part of its key.
+    Leaf {
+        /// The path from the node to the end of the key.
+        key: Nibbles,
+        /// The value of the leaf.
+        value: Vec<u8>,
+    },
+}
+
+impl TrieNode {
+    /// Decodes a node from its RLP.
+    pub fn decode(rlp: &[u8]) -> Result<Self, DecodeError> {
+        let mut buf = rlp;
+        let header = Header::decode(&mut buf)?;
+        if !header.list {
+            return Err(DecodeError::UnexpectedString)
+        }
+        if buf.len() != header.payload_length {
+            return Err(DecodeError::UnexpectedLength)
+        }
+
+        let mut items = Vec::with_capacity(17);
+        while !buf.is_empty() {
+            items.push(RlpItem::decode(&mut buf)?);
+        }
+
+        match items.as_slice() {
+            [path, item] => {
+                if path.list {
+                    return Err(DecodeError::UnexpectedList)
+                }
+                let (key, is_leaf) = Nibbles::decode_path_leaf(path.payload)
+                    .ok_or(DecodeError::Custom("invalid hex-prefix path"))?;
+                if is_leaf {
+                    if item.list {
+                        return Err(DecodeError::UnexpectedList)
+                    }
+                    Ok(TrieNode::Leaf { key, value: item.payload.to_vec() })
+                } else {
+                    let child = item.child_ref()?.ok_or(DecodeError::Custom("empty extension"))?;
+                    Ok(TrieNode::Extension { key, child })
+                }
+            }
+            [children @ .., _value] if children.len() == 16 => Ok(TrieNode::Branch(
+                children.iter().map(RlpItem::child_ref).collect::<Result<_, _>>()?,
+            )),
+            _ => Err(DecodeError::Custom("invalid number of trie node items")),
+        }
+    }
+}
+
+/// An item of the RLP list of a node.
+struct RlpItem<'a> {
+    /// Whether the item is a list.
+    list: bool,
+    /// The payload of the item.
+    payload: &'a [u8],
+    /// The whole encoding of the item, including its header.
+    raw: &'a [u8],
+}
+
+impl<'a> RlpItem<'a> {
+    fn decode(buf: &mut &'a [u8]) -> Result<Self, DecodeError> {
+        let start = *buf;
+        let header = Header::decode(buf)?;
+        if buf.len() < header.payload_length {
+            return Err(DecodeError::InputTooShort)
+        }
+        let payload = &buf[..header.payload_length];
+        *buf = &buf[header.payload_length..];
+        let raw = &start[..start.len() - buf.len()];
+        Ok(Self { list: header.list, payload, raw })
+    }
+
+    /// Returns the child referenced by the item, or `None` if the item is empty.
+    fn child_ref(&self) -> Result<Option<ChildRef>, DecodeError> {
+        if self.list {
+            return Ok(Some(ChildRef::Embedded(self.raw.to_vec())))
+        }
+        match self.payload.len() {
+            0 => Ok(None),
+            32 => Ok(Some(ChildRef::Hash(H256::from_slice(self.payload)))),
+            _ => Err(DecodeError::UnexpectedLength),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trie::HashBuilder;
+    use reth_primitives::keccak256;
+
+    #[test]
+    fn decodes_built_nodes() {
+        // Two leaves that share their first nibble, below an extension node.
+        let mut hb =
+            HashBuilder::default().with_proof_targets([Nibbles::default()].into_iter().collect());
+        let (first, second) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+        hb.add_leaf(Nibbles::unpack(first), &[0x01; 32]);
+        hb.add_leaf(Nibbles::unpack(second), &[0x02; 32]);
+        let root = hb.root();
+
+        let nodes = hb.take_proof_nodes();
+        let (_, root_rlp) = &nodes[0];
+        assert_eq!(keccak256(root_rlp), root);
+
+        let TrieNode::Extension { key, child: ChildRef::Hash(_) } =
+            TrieNode::decode(root_rlp).unwrap()
+        else {
+            panic!("expected an extension node")
+        };
+        // The keys diverge at their last nibble.
+        assert_eq!(key.len(), 63);
+    }
+
+    #[test]
+    fn decodes_leaves() {
+        let rlp = [0xc4, 0x82, 0x20, 0x12, 0x03];
+        assert_eq!(
+            TrieNode::decode(&rlp),
+            Ok(TrieNode::Leaf { key: Nibbles::from_hex(vec![1, 2]), value: vec![0x03] })
+        );
+
+        // The list is longer than its items.
+        assert_eq!(
+            TrieNode::decode(&[0xc5, 0x82, 0x20, 0x12, 0x03]),
+            Err(DecodeError::UnexpectedLength)
+        );
+        // Invalid hex-prefix flag.
+        assert!(TrieNode::decode(&[0xc4, 0x82, 0x40, 0x12, 0x03]).is_err());
+    }
+}
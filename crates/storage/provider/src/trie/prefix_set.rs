@@ -0,0 +1,53 @@
+use reth_primitives::trie::Nibbles;
+
+/// The set of keys that changed since the stored branch nodes of a trie were computed.
+///
+/// A stored branch node can only be reused if none of the keys below it changed.
+#[derive(Debug, Default, Clone)]
+pub struct PrefixSet {
+    /// Sorted and deduplicated keys.
+    keys: Vec<Nibbles>,
+}
+
+impl PrefixSet {
+    /// Returns true if any of the keys starts with the given prefix.
+    pub fn contains(&self, prefix: &Nibbles) -> bool {
+        let idx = self.keys.partition_point(|key| key < prefix);
+        self.keys.get(idx).map_or(false, |key| key.starts_with(prefix))
+    }
+
+    /// Returns true if no key changed.
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+}
+
+impl FromIterator<Nibbles> for PrefixSet {
+    fn from_iter<T: IntoIterator<Item = Nibbles>>(iter: T) -> Self {
+        let mut keys = iter.into_iter().collect::<Vec<_>>();
+        keys.sort_unstable();
+        keys.dedup();
+        Self { keys }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn contains_prefixes_of_keys() {
+        let set = [vec![1, 2, 3], vec![1, 4], vec![5, 0, 0]]
+            .into_iter()
+            .map(Nibbles::from_hex)
+            .collect::<PrefixSet>();
+
+        for prefix in [vec![], vec![1], vec![1, 2], vec![1, 4], vec![5, 0], vec![5, 0, 0]] {
+            assert!(set.contains(&Nibbles::from_hex(prefix)));
+        }
+        for prefix in [vec![0], vec![1, 3], vec![1, 2, 3, 4], vec![5, 1], vec![6]] {
+            assert!(!set.contains(&Nibbles::from_hex(prefix)));
+        }
+        assert!(!PrefixSet::default().contains(&Nibbles::default()));
+    }
+}
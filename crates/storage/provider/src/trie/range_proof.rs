@@ -0,0 +1,345 @@
+use super::{
+    node::{ChildRef, TrieNode},
+    HashBuilder,
+};
+use reth_primitives::{
+    keccak256,
+    trie::{HashBuilderValue, Nibbles},
+    Bytes, H256,
+};
+use reth_rlp::DecodeError;
+use std::{cmp::Ordering, collections::HashMap};
+
+/// Errors of [verify_range_proof].
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum RangeProofError {
+    /// The keys of the leaves are not ascending, or start before the range.
+    #[error("Leaves are not ordered or start before the range")]
+    UnorderedLeaves,
+    /// The proof misses a node on the path to a boundary of the range.
+    #[error("Proof node {0:?} is missing")]
+    MissingNode(H256),
+    /// A node of the proof could not be decoded.
+    #[error("Invalid proof node: {0}")]
+    InvalidNode(#[from] DecodeError),
+    /// The leaves and the proof do not form the expected trie.
+    #[error("Root mismatch, got {got:?}, expected {expected:?}")]
+    RootMismatch {
+        /// The root of the trie formed by the leaves and the proof.
+        got: H256,
+        /// The expected root.
+        expected: H256,
+    },
+}
+
+/// Verifies that the `leaves` are all the leaves of the trie with the given `root`, starting at
+/// the key `start` and ending with the last of them.
+///
+/// Without a proof, the leaves have to be the whole trie. Otherwise the proof holds the nodes on
+/// the paths to `start` and to the last leaf, and the subtrees outside of the range are taken
+/// from it, so the leaves have to fill the range for the root to match. With a proof but no
+/// leaves, the proof has to show that there are no leaves at or after `start`.
+///
+/// The values of the leaves are encoded as in the trie. Returns whether the trie holds leaves
+/// after the last one.
+pub fn verify_range_proof(
+    root: H256,
+    start: H256,
+    leaves: &[(H256, Vec<u8>)],
+    proof: &[Bytes],
+) -> Result<bool, RangeProofError> {
+    if leaves.first().map_or(false, |(key, _)| *key < start) ||
+        leaves.windows(2).any(|pair| pair[0].0 >= pair[1].0)
+    {
+        return Err(RangeProofError::UnorderedLeaves)
+    }
+
+    let mut items = leaves
+        .iter()
+        .map(|(key, value)| (Nibbles::unpack(key), HashBuilderValue::Bytes(value.clone())))
+        .collect::<Vec<_>>();
+
+    let mut more = false;
+    if !proof.is_empty() {
+        let nodes = proof.iter().map(|node| (keccak256(node), &node[..])).collect();
+        let mut outside = Vec::new();
+        collect_outside(&nodes, root, &Nibbles::unpack(start), Boundary::Left, &mut outside)?;
+        if let Some((last, _)) = leaves.last() {
+            let left = outside.len();
+            collect_outside(&nodes, root, &Nibbles::unpack(last), Boundary::Right, &mut outside)?;
+            more = outside.len() > left;
+        }
+        items.extend(outside);
+        items.sort_by(|(a, _), (b, _)| a.cmp(b));
+    }
+
+    let mut hash_builder = HashBuilder::default();
+    for (path, value) in items {
+        match value {
+            HashBuilderValue::Bytes(value) => hash_builder.add_leaf(path, &value),
+            HashBuilderValue::Hash(hash) => hash_builder.add_branch(path, hash),
+        }
+    }
+    let got = hash_builder.root();
+    if got != root {
+        return Err(RangeProofError::RootMismatch { got, expected: root })
+    }
+
+    Ok(more)
+}
+
+/// A boundary of the range of leaves.
+#[derive(Debug, Clone, Copy)]
+enum Boundary {
+    /// The start of the range, everything before it is outside.
+    Left,
+    /// The last leaf of the range, everything after it is outside.
+    Right,
+}
+
+impl Boundary {
+    /// Returns whether a path that compares to the boundary like `ordering` is outside the range.
+    fn is_outside(self, ordering: Ordering) -> bool {
+        match self {
+            Boundary::Left => ordering == Ordering::Less,
+            Boundary::Right => ordering == Ordering::Greater,
+        }
+    }
+}
+
+/// Walks the proof from the root along the path to `target`, and collects the subtrees and leaves
+/// next to the path that are outside of the range.
+fn collect_outside(
+    nodes: &HashMap<H256, &[u8]>,
+    root: H256,
+    target: &Nibbles,
+    boundary: Boundary,
+    outside: &mut Vec<(Nibbles, HashBuilderValue)>,
+) -> Result<(), RangeProofError> {
+    let mut path = Nibbles::default();
+    let mut node = ChildRef::Hash(root);
+    loop {
+        let decoded = match &node {
+            ChildRef::Hash(hash) => {
+                TrieNode::decode(nodes.get(hash).ok_or(RangeProofError::MissingNode(*hash))?)?
+            }
+            ChildRef::Embedded(rlp) => TrieNode::decode(rlp)?,
+        };
+
+        match decoded {
+            TrieNode::Branch(children) => {
+                let nibble = *target
+                    .get(path.len())
+                    .ok_or(DecodeError::Custom("branch node below the key length"))?;
+                let mut next = None;
+                for (child_nibble, child) in (0u8..).zip(children) {
+                    let Some(child) = child else { continue };
+                    if child_nibble == nibble {
+                        next = Some(child);
+                    } else if boundary.is_outside(child_nibble.cmp(&nibble)) {
+                        let mut child_path = path.clone();
+                        child_path.push(child_nibble);
+                        collect_subtree(child_path, child, outside)?;
+                    }
+                }
+                match next {
+                    Some(child) => {
+                        path.push(nibble);
+                        node = child;
+                    }
+                    None => return Ok(()),
+                }
+            }
+            TrieNode::Extension { key, child } => {
+                path.extend(&key);
+                let ordering = compare_prefix(&path, target);
+                if ordering != Ordering::Equal {
+                    if boundary.is_outside(ordering) {
+                        collect_subtree(path, child, outside)?;
+                    }
+                    return Ok(())
+                }
+                node = child;
+            }
+            TrieNode::Leaf { key, value } => {
+                path.extend(&key);
+                if boundary.is_outside(path.cmp(target)) {
+                    outside.push((path, HashBuilderValue::Bytes(value)));
+                }
+                return Ok(())
+            }
+        }
+    }
+}
+
+/// Collects the subtree at `path`: its hash if it is referenced by hash, its leaves otherwise.
+fn collect_subtree(
+    path: Nibbles,
+    node: ChildRef,
+    outside: &mut Vec<(Nibbles, HashBuilderValue)>,
+) -> Result<(), RangeProofError> {
+    let rlp = match node {
+        ChildRef::Hash(hash) => {
+            outside.push((path, HashBuilderValue::Hash(hash)));
+            return Ok(())
+        }
+        ChildRef::Embedded(rlp) => rlp,
+    };
+
+    match TrieNode::decode(&rlp)? {
+        TrieNode::Branch(children) => {
+            for (nibble, child) in (0u8..).zip(children) {
+                if let Some(child) = child {
+                    let mut child_path = path.clone();
+                    child_path.push(nibble);
+                    collect_subtree(child_path, child, outside)?;
+                }
+            }
+        }
+        TrieNode::Extension { key, child } => {
+            let mut child_path = path;
+            child_path.extend(&key);
+            collect_subtree(child_path, child, outside)?;
+        }
+        TrieNode::Leaf { key, value } => {
+            let mut leaf_path = path;
+            leaf_path.extend(&key);
+            outside.push((leaf_path, HashBuilderValue::Bytes(value)));
+        }
+    }
+    Ok(())
+}
+
+/// Compares a path with the prefix of the same length of `target`.
+fn compare_prefix(path: &Nibbles, target: &Nibbles) -> Ordering {
+    let len = path.len().min(target.len());
+    path.as_slice().cmp(&target[..len])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::trie::PrefixSet;
+    use assert_matches::assert_matches;
+    use proptest::{collection::btree_map, prelude::*};
+    use std::collections::BTreeMap;
+
+    /// Returns the root of the trie and the proof of the given keys.
+    fn prove(leaves: &BTreeMap<H256, Vec<u8>>, targets: &[H256]) -> (H256, Vec<Bytes>) {
+        let targets = targets.iter().map(Nibbles::unpack).collect::<PrefixSet>();
+        let mut hash_builder = HashBuilder::default().with_proof_targets(targets);
+        for (key, value) in leaves {
+            hash_builder.add_leaf(Nibbles::unpack(key), value);
+        }
+        let root = hash_builder.root();
+        let proof =
+            hash_builder.take_proof_nodes().into_iter().map(|(_, rlp)| rlp.into()).collect();
+        (root, proof)
+    }
+
+    fn leaves(count: u64) -> BTreeMap<H256, Vec<u8>> {
+        (0..count).map(|i| (keccak256(i.to_be_bytes()), vec![i as u8 + 1; 33])).collect()
+    }
+
+    #[test]
+    fn whole_trie_without_proof() {
+        let leaves = leaves(20);
+        let (root, _) = prove(&leaves, &[]);
+        let range = leaves.into_iter().collect::<Vec<_>>();
+
+        assert_eq!(verify_range_proof(root, H256::zero(), &range, &[]), Ok(false));
+        assert_matches!(
+            verify_range_proof(root, H256::zero(), &range[1..], &[]),
+            Err(RangeProofError::RootMismatch { .. })
+        );
+    }
+
+    #[test]
+    fn partial_ranges() {
+        let leaves = leaves(50);
+        let all = leaves.clone().into_iter().collect::<Vec<_>>();
+        let start = H256::from_low_u64_be(1);
+        let (first, last) = (&all[10], &all[20]);
+        let (root, proof) = prove(&leaves, &[first.0, last.0]);
+
+        // The range starts at a leaf.
+        assert_eq!(verify_range_proof(root, first.0, &all[10..=20], &proof), Ok(true));
+        // The range is missing a leaf.
+        let mut gap = all[10..=20].to_vec();
+        gap.remove(5);
+        assert_matches!(
+            verify_range_proof(root, first.0, &gap, &proof),
+            Err(RangeProofError::RootMismatch { .. })
+        );
+        // A value was changed.
+        let mut changed = all[10..=20].to_vec();
+        changed[3].1 = vec![0xff; 33];
+        assert_matches!(
+            verify_range_proof(root, first.0, &changed, &proof),
+            Err(RangeProofError::RootMismatch { .. })
+        );
+        // The leaves are not ordered.
+        assert_eq!(
+            verify_range_proof(root, last.0, &all[10..=20], &proof),
+            Err(RangeProofError::UnorderedLeaves)
+        );
+
+        // The range reaches the end of the trie.
+        let (root, proof) = prove(&leaves, &[start, all[49].0]);
+        let from_start = all.iter().filter(|(key, _)| *key >= start).cloned().collect::<Vec<_>>();
+        assert_eq!(verify_range_proof(root, start, &from_start, &proof), Ok(false));
+        assert_matches!(
+            verify_range_proof(root, start, &from_start[..from_start.len() - 1], &[]),
+            Err(RangeProofError::RootMismatch { .. })
+        );
+    }
+
+    #[test]
+    fn empty_ranges() {
+        let leaves = leaves(30);
+
+        // There is nothing after the last leaf.
+        let after = H256::repeat_byte(0xff);
+        assert!(leaves.keys().all(|key| *key < after));
+        let (root, proof) = prove(&leaves, &[after]);
+        assert_eq!(verify_range_proof(root, after, &[], &proof), Ok(false));
+
+        // The leaves at and after the start are withheld.
+        let first = *leaves.keys().next().unwrap();
+        let (root, proof) = prove(&leaves, &[first]);
+        assert_matches!(
+            verify_range_proof(root, first, &[], &proof),
+            Err(RangeProofError::RootMismatch { .. })
+        );
+
+        // The proof is missing.
+        assert_matches!(
+            verify_range_proof(root, first, &[], &proof[1..]),
+            Err(RangeProofError::MissingNode(_))
+        );
+    }
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(100))]
+        #[test]
+        fn random_ranges(
+            leaves in btree_map(any::<[u8; 32]>(), any::<u8>(), 1..100),
+            bounds in (any::<prop::sample::Index>(), any::<prop::sample::Index>()),
+        ) {
+            // Short values are embedded in their parent.
+            let leaves = leaves
+                .into_iter()
+                .map(|(key, value)| (H256(key), vec![value; value as usize % 40 + 1]))
+                .collect::<BTreeMap<_, _>>();
+            let all = leaves.clone().into_iter().collect::<Vec<_>>();
+            let (a, b) = (bounds.0.index(all.len()), bounds.1.index(all.len()));
+            let (from, to) = (a.min(b), a.max(b));
+
+            let (root, proof) = prove(&leaves, &[all[from].0, all[to].0]);
+            prop_assert_eq!(
+                verify_range_proof(root, all[from].0, &all[from..=to], &proof),
+                Ok(to + 1 < all.len())
+            );
+        }
+    }
+}
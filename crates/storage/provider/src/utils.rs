@@ -90,7 +90,8 @@ pub fn insert_block<'a, TX: DbTxMut<'a> + DbTx<'a>>(
         }
     }
 
-    if has_block_reward || has_withdrawals {
+    // The beacon root update of EIP-4788 is written with the post block transition.
+    if has_block_reward || has_withdrawals || block.parent_beacon_block_root.is_some() {
         transition_id += 1;
     }
     tx.put::<tables::BlockTransitionIndex>(block.number, transition_id)?;
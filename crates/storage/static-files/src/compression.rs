@@ -0,0 +1,47 @@
+use crate::StaticFileError;
+use serde::{Deserialize, Serialize};
+
+/// The compression of the rows of a static file.
+///
+/// Every row is compressed on its own, so that a single row can be read without decompressing
+/// its neighbours.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[repr(u8)]
+pub enum Compression {
+    /// The rows are stored as they are.
+    #[default]
+    Uncompressed = 0,
+    /// The rows are compressed with raw [Snappy](https://github.com/google/snappy).
+    Snappy = 1,
+}
+
+impl Compression {
+    /// Compresses a row.
+    pub(crate) fn compress(&self, row: &[u8]) -> Result<Vec<u8>, StaticFileError> {
+        match self {
+            Compression::Uncompressed => Ok(row.to_vec()),
+            Compression::Snappy => Ok(snap::raw::Encoder::new().compress_vec(row)?),
+        }
+    }
+
+    /// Decompresses a row compressed by [Compression::compress].
+    pub(crate) fn decompress(&self, row: &[u8]) -> Result<Vec<u8>, StaticFileError> {
+        match self {
+            Compression::Uncompressed => Ok(row.to_vec()),
+            Compression::Snappy => Ok(snap::raw::Decoder::new().decompress_vec(row)?),
+        }
+    }
+}
+
+impl TryFrom<u8> for Compression {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(Compression::Uncompressed),
+            1 => Ok(Compression::Snappy),
+            _ => Err(value),
+        }
+    }
+}
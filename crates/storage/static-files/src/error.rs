@@ -0,0 +1,45 @@
+use crate::StaticFileSegment;
+use reth_interfaces::provider::ProviderError;
+use reth_primitives::BlockNumber;
+use std::path::PathBuf;
+
+/// Errors that can occur when reading or writing static files.
+#[derive(Debug, thiserror::Error)]
+pub enum StaticFileError {
+    /// Accessing a static file failed.
+    #[error("Static file I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    /// A static file does not have the expected layout.
+    #[error("Static file {path:?} is corrupted: {reason}")]
+    Corrupted {
+        /// The path of the file.
+        path: PathBuf,
+        /// What is wrong with the file.
+        reason: &'static str,
+    },
+    /// A row could not be decompressed.
+    #[error("Failed to decompress static file row: {0}")]
+    Decompress(#[from] snap::Error),
+    /// A new static file does not continue the block range of the existing files.
+    #[error("{segment} static file must start at block #{expected}, got #{got}")]
+    NonContiguous {
+        /// The segment of the file.
+        segment: StaticFileSegment,
+        /// The first block after the existing files.
+        expected: BlockNumber,
+        /// The first block of the new file.
+        got: BlockNumber,
+    },
+}
+
+impl From<StaticFileError> for ProviderError {
+    fn from(error: StaticFileError) -> Self {
+        ProviderError::StaticFile(error.to_string())
+    }
+}
+
+impl From<StaticFileError> for reth_interfaces::Error {
+    fn from(error: StaticFileError) -> Self {
+        ProviderError::from(error).into()
+    }
+}
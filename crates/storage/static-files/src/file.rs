@@ -0,0 +1,131 @@
+use crate::{Compression, StaticFileError, StaticFileSegment};
+use memmap2::Mmap;
+use reth_primitives::BlockNumber;
+use std::{
+    fs::{self, File},
+    ops::{Range, RangeInclusive},
+    path::{Path, PathBuf},
+};
+
+/// The extension of the file holding the rows.
+pub(crate) const DATA_EXTENSION: &str = "dat";
+/// The extension of the file holding the row offsets.
+pub(crate) const INDEX_EXTENSION: &str = "idx";
+/// The version of the index layout.
+pub(crate) const INDEX_VERSION: u8 = 1;
+/// The length of the index header: version, compression, first key and number of rows.
+pub(crate) const INDEX_HEADER_LEN: usize = 1 + 1 + 8 + 8;
+
+/// Returns the path of a static file with the given extension.
+pub(crate) fn file_path(
+    dir: &Path,
+    segment: StaticFileSegment,
+    blocks: &RangeInclusive<BlockNumber>,
+    extension: &str,
+) -> PathBuf {
+    dir.join(segment.file_name(blocks)).with_extension(extension)
+}
+
+/// An immutable static file with the rows of one segment for a range of blocks.
+///
+/// A static file consists of a data file with the concatenated rows and an index file with the
+/// offset of every row in the data file, followed by the end of the last row. Rows are read from a
+/// memory map of the data file. An empty row stands for a key without a value.
+#[derive(Debug)]
+pub struct StaticFile {
+    /// The segment of the rows.
+    segment: StaticFileSegment,
+    /// The blocks whose rows are in the file.
+    blocks: RangeInclusive<BlockNumber>,
+    /// The compression of the rows.
+    compression: Compression,
+    /// The key of the first row.
+    first_key: u64,
+    /// The offsets of the rows in the data file, followed by its length.
+    offsets: Vec<u64>,
+    /// The memory map of the data file, or `None` if it is empty and can not be mapped.
+    data: Option<Mmap>,
+}
+
+impl StaticFile {
+    /// Opens the static file of a segment holding the given blocks.
+    pub fn open(
+        dir: &Path,
+        segment: StaticFileSegment,
+        blocks: RangeInclusive<BlockNumber>,
+    ) -> Result<Self, StaticFileError> {
+        let index_path = file_path(dir, segment, &blocks, INDEX_EXTENSION);
+        let index = fs::read(&index_path)?;
+        let corrupted = |reason| StaticFileError::Corrupted { path: index_path.clone(), reason };
+
+        if index.len() < INDEX_HEADER_LEN || index[0] != INDEX_VERSION {
+            return Err(corrupted("unsupported index header"))
+        }
+        let compression =
+            Compression::try_from(index[1]).map_err(|_| corrupted("unknown compression"))?;
+        let first_key = u64::from_le_bytes(index[2..10].try_into().expect("8 bytes"));
+        let rows = u64::from_le_bytes(index[10..18].try_into().expect("8 bytes"));
+
+        let offsets = &index[INDEX_HEADER_LEN..];
+        if offsets.len() % 8 != 0 || (offsets.len() / 8) as u64 != rows + 1 {
+            return Err(corrupted("offsets do not match the number of rows"))
+        }
+        let offsets = offsets
+            .chunks_exact(8)
+            .map(|offset| u64::from_le_bytes(offset.try_into().expect("8 bytes")))
+            .collect::<Vec<_>>();
+        if offsets[0] != 0 || offsets.windows(2).any(|pair| pair[0] > pair[1]) {
+            return Err(corrupted("offsets are not ordered"))
+        }
+
+        let data_path = file_path(dir, segment, &blocks, DATA_EXTENSION);
+        let file = File::open(&data_path)?;
+        let len = file.metadata()?.len();
+        if Some(&len) != offsets.last() {
+            return Err(StaticFileError::Corrupted {
+                path: data_path,
+                reason: "data file does not match its index",
+            })
+        }
+        let data = if len == 0 {
+            None
+        } else {
+            // SAFETY: Static files are never modified once written.
+            Some(unsafe { Mmap::map(&file)? })
+        };
+
+        Ok(Self { segment, blocks, compression, first_key, offsets, data })
+    }
+
+    /// Returns the segment of the rows.
+    pub fn segment(&self) -> StaticFileSegment {
+        self.segment
+    }
+
+    /// Returns the blocks whose rows are in the file.
+    pub fn blocks(&self) -> &RangeInclusive<BlockNumber> {
+        &self.blocks
+    }
+
+    /// Returns the keys of the rows in the file.
+    pub fn keys(&self) -> Range<u64> {
+        self.first_key..self.first_key + self.offsets.len() as u64 - 1
+    }
+
+    /// Returns the decompressed row of the given key, or `None` if the key is not in the file or
+    /// has no value.
+    pub fn row(&self, key: u64) -> Result<Option<Vec<u8>>, StaticFileError> {
+        if !self.keys().contains(&key) {
+            return Ok(None)
+        }
+
+        let index = (key - self.first_key) as usize;
+        let (start, end) = (self.offsets[index] as usize, self.offsets[index + 1] as usize);
+        if start == end {
+            return Ok(None)
+        }
+
+        let data = self.data.as_ref().expect("data file is not empty");
+        Ok(Some(self.compression.decompress(&data[start..end])?))
+    }
+}
@@ -0,0 +1,26 @@
+#![warn(missing_docs, unreachable_pub, unused_crate_dependencies)]
+#![deny(unused_must_use, rust_2018_idioms)]
+#![doc(test(
+    no_crate_inject,
+    attr(deny(warnings, rust_2018_idioms), allow(dead_code, unused_variables))
+))]
+
+//! Immutable static files for ancient chain data.
+//!
+//! Finalized headers, transactions and receipts never change, so there is no need to keep them in
+//! the database. They are moved to append-only files, split by [StaticFileSegment] into files
+//! covering consecutive block ranges, see [StaticFiles].
+
+mod compression;
+mod error;
+mod file;
+mod segment;
+mod static_files;
+mod writer;
+
+pub use compression::Compression;
+pub use error::StaticFileError;
+pub use file::StaticFile;
+pub use segment::StaticFileSegment;
+pub use static_files::StaticFiles;
+pub use writer::StaticFileWriter;
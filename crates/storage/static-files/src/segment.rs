@@ -0,0 +1,82 @@
+use reth_primitives::BlockNumber;
+use serde::{Deserialize, Serialize};
+use std::{fmt, ops::RangeInclusive};
+
+/// A kind of chain data that is moved to static files.
+///
+/// Every segment is split into files covering consecutive block ranges. The rows of the
+/// [Headers][StaticFileSegment::Headers] segment are keyed by block number, the rows of the other
+/// segments by transaction number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StaticFileSegment {
+    /// The canonical headers and their hashes, moved from the `Headers` and `CanonicalHeaders`
+    /// tables.
+    Headers,
+    /// The transactions, moved from the `Transactions` table.
+    Transactions,
+    /// The transaction receipts, moved from the `Receipts` table.
+    Receipts,
+}
+
+impl StaticFileSegment {
+    /// All segments.
+    pub const ALL: [StaticFileSegment; 3] =
+        [StaticFileSegment::Headers, StaticFileSegment::Transactions, StaticFileSegment::Receipts];
+
+    /// Returns the name of the segment, used as the prefix of its file names.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            StaticFileSegment::Headers => "headers",
+            StaticFileSegment::Transactions => "transactions",
+            StaticFileSegment::Receipts => "receipts",
+        }
+    }
+
+    /// Returns the file name, without extension, of the file holding the given blocks.
+    pub fn file_name(&self, blocks: &RangeInclusive<BlockNumber>) -> String {
+        format!("{}_{}_{}", self.as_str(), blocks.start(), blocks.end())
+    }
+
+    /// Parses a file name created by [StaticFileSegment::file_name].
+    pub fn parse_file_name(name: &str) -> Option<(Self, RangeInclusive<BlockNumber>)> {
+        let mut parts = name.split('_');
+        let segment = match parts.next()? {
+            "headers" => StaticFileSegment::Headers,
+            "transactions" => StaticFileSegment::Transactions,
+            "receipts" => StaticFileSegment::Receipts,
+            _ => return None,
+        };
+        let start = parts.next()?.parse().ok()?;
+        let end = parts.next()?.parse().ok()?;
+        if parts.next().is_some() || start > end {
+            return None
+        }
+        Some((segment, start..=end))
+    }
+}
+
+impl fmt::Display for StaticFileSegment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn file_name_roundtrip() {
+        for segment in StaticFileSegment::ALL {
+            let name = segment.file_name(&(500_000..=999_999));
+            assert_eq!(
+                StaticFileSegment::parse_file_name(&name),
+                Some((segment, 500_000..=999_999))
+            );
+        }
+        assert_eq!(StaticFileSegment::parse_file_name("headers_10_5"), None);
+        assert_eq!(StaticFileSegment::parse_file_name("headers_0_5_7"), None);
+        assert_eq!(StaticFileSegment::parse_file_name("logs_0_5"), None);
+    }
+}
@@ -0,0 +1,269 @@
+use crate::{
+    file::INDEX_EXTENSION, Compression, StaticFile, StaticFileError, StaticFileSegment,
+    StaticFileWriter,
+};
+use parking_lot::RwLock;
+use reth_codecs::Compact;
+use reth_primitives::{BlockHash, BlockNumber, Header, Receipt, TransactionSigned, TxNumber, H256};
+use std::{
+    collections::BTreeMap,
+    fs,
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+use tracing::debug;
+
+/// The static files of all segments in a directory.
+///
+/// Static files are only ever appended: a new file of a segment has to start right after the
+/// highest block of the existing files, so every segment holds all blocks up to
+/// [StaticFiles::highest_block].
+#[derive(Debug)]
+pub struct StaticFiles {
+    /// The directory of the static files.
+    dir: PathBuf,
+    /// The compression of new static files.
+    compression: Compression,
+    /// The static files of every segment, ordered by block range.
+    files: RwLock<BTreeMap<StaticFileSegment, Vec<Arc<StaticFile>>>>,
+}
+
+impl StaticFiles {
+    /// Opens the static files in the given directory, creating it if it does not exist.
+    ///
+    /// New static files are written with the given compression, existing files are read with the
+    /// compression they were written with.
+    pub fn open(
+        dir: impl Into<PathBuf>,
+        compression: Compression,
+    ) -> Result<Self, StaticFileError> {
+        let dir = dir.into();
+        fs::create_dir_all(&dir)?;
+
+        let mut files = BTreeMap::<_, Vec<Arc<StaticFile>>>::new();
+        for entry in fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some(INDEX_EXTENSION) {
+                continue
+            }
+            let Some((segment, blocks)) = path
+                .file_stem()
+                .and_then(|name| name.to_str())
+                .and_then(StaticFileSegment::parse_file_name)
+            else {
+                continue
+            };
+            files
+                .entry(segment)
+                .or_default()
+                .push(Arc::new(StaticFile::open(&dir, segment, blocks)?));
+        }
+
+        for (segment, files) in files.iter_mut() {
+            files.sort_by_key(|file| *file.blocks().start());
+            let mut expected = 0;
+            for file in files.iter() {
+                if *file.blocks().start() != expected {
+                    return Err(StaticFileError::NonContiguous {
+                        segment: *segment,
+                        expected,
+                        got: *file.blocks().start(),
+                    })
+                }
+                expected = file.blocks().end() + 1;
+            }
+        }
+
+        Ok(Self { dir, compression, files: RwLock::new(files) })
+    }
+
+    /// Returns the directory of the static files.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns the highest block in the static files of a segment, or `None` if there are none.
+    pub fn highest_block(&self, segment: StaticFileSegment) -> Option<BlockNumber> {
+        self.files.read().get(&segment)?.last().map(|file| *file.blocks().end())
+    }
+
+    /// Returns the key after the last row in the static files of a segment. All lower keys are
+    /// read from the static files.
+    pub fn next_key(&self, segment: StaticFileSegment) -> u64 {
+        self.files
+            .read()
+            .get(&segment)
+            .and_then(|files| files.last())
+            .map_or(0, |file| file.keys().end)
+    }
+
+    /// Creates a writer for the next static file of a segment.
+    ///
+    /// The file has to start right after the highest block of the segment, and its rows start at
+    /// the given key. The file is only visible to readers once it is passed to
+    /// [StaticFiles::commit].
+    pub fn writer(
+        &self,
+        segment: StaticFileSegment,
+        blocks: RangeInclusive<BlockNumber>,
+        first_key: u64,
+    ) -> Result<StaticFileWriter, StaticFileError> {
+        let expected = self.highest_block(segment).map_or(0, |block| block + 1);
+        if *blocks.start() != expected {
+            return Err(StaticFileError::NonContiguous { segment, expected, got: *blocks.start() })
+        }
+        StaticFileWriter::new(&self.dir, segment, blocks, first_key, self.compression)
+    }
+
+    /// Completes a static file and makes its rows visible to readers.
+    pub fn commit(&self, writer: StaticFileWriter) -> Result<(), StaticFileError> {
+        let file = writer.finish()?;
+        debug!(
+            target: "static_files",
+            segment = %file.segment(),
+            blocks = ?file.blocks(),
+            rows = file.keys().end - file.keys().start,
+            "Committed static file"
+        );
+        self.files.write().entry(file.segment()).or_default().push(Arc::new(file));
+        Ok(())
+    }
+
+    /// Returns the row of a key in a segment, or `None` if it is not in the static files.
+    pub fn row(
+        &self,
+        segment: StaticFileSegment,
+        key: u64,
+    ) -> Result<Option<Vec<u8>>, StaticFileError> {
+        let files = self.files.read();
+        let Some(files) = files.get(&segment) else { return Ok(None) };
+        match files.partition_point(|file| file.keys().start <= key).checked_sub(1) {
+            Some(index) => files[index].row(key),
+            None => Ok(None),
+        }
+    }
+
+    /// Returns the canonical header and hash of a block.
+    pub fn header_with_hash(
+        &self,
+        number: BlockNumber,
+    ) -> Result<Option<(Header, BlockHash)>, StaticFileError> {
+        let Some(row) = self.header_row(number)? else { return Ok(None) };
+        let (hash, header) = row.split_at(H256::len_bytes());
+        Ok(Some((decode(header), H256::from_slice(hash))))
+    }
+
+    /// Returns the canonical header of a block.
+    pub fn header(&self, number: BlockNumber) -> Result<Option<Header>, StaticFileError> {
+        Ok(self.header_with_hash(number)?.map(|(header, _)| header))
+    }
+
+    /// Returns the canonical hash of a block.
+    pub fn canonical_hash(
+        &self,
+        number: BlockNumber,
+    ) -> Result<Option<BlockHash>, StaticFileError> {
+        Ok(self.header_row(number)?.map(|row| H256::from_slice(&row[..H256::len_bytes()])))
+    }
+
+    /// Returns a transaction by its number.
+    pub fn transaction(&self, id: TxNumber) -> Result<Option<TransactionSigned>, StaticFileError> {
+        Ok(self.row(StaticFileSegment::Transactions, id)?.map(|row| decode(&row)))
+    }
+
+    /// Returns the receipt of a transaction by its number.
+    pub fn receipt(&self, id: TxNumber) -> Result<Option<Receipt>, StaticFileError> {
+        Ok(self.row(StaticFileSegment::Receipts, id)?.map(|row| decode(&row)))
+    }
+
+    /// Returns the row of a block in the [Headers][StaticFileSegment::Headers] segment.
+    fn header_row(&self, number: BlockNumber) -> Result<Option<Vec<u8>>, StaticFileError> {
+        let Some(row) = self.row(StaticFileSegment::Headers, number)? else { return Ok(None) };
+        if row.len() < H256::len_bytes() {
+            return Err(StaticFileError::Corrupted {
+                path: self.dir.clone(),
+                reason: "header row is shorter than a hash",
+            })
+        }
+        Ok(Some(row))
+    }
+}
+
+/// Decodes a [Compact] encoded row.
+fn decode<T: Compact>(row: &[u8]) -> T {
+    T::from_compact(row, row.len()).0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reth_primitives::{Transaction, TransactionKind, TxLegacy};
+
+    fn transaction(nonce: u64) -> TransactionSigned {
+        TransactionSigned::from_transaction_and_signature(
+            Transaction::Legacy(TxLegacy {
+                nonce,
+                to: TransactionKind::Create,
+                ..Default::default()
+            }),
+            Default::default(),
+        )
+    }
+
+    #[test]
+    fn write_and_reopen() {
+        let dir = tempfile::tempdir().unwrap();
+        let static_files = StaticFiles::open(dir.path(), Compression::Snappy).unwrap();
+
+        let mut writer = static_files.writer(StaticFileSegment::Headers, 0..=1, 0).unwrap();
+        for number in 0..=1 {
+            let header = Header { number, ..Default::default() };
+            writer.append_header(header, H256::from_low_u64_be(number)).unwrap();
+        }
+        static_files.commit(writer).unwrap();
+
+        // block 2 has one transaction, block 3 has none and block 4 has two
+        let mut writer = static_files.writer(StaticFileSegment::Transactions, 0..=4, 5).unwrap();
+        for nonce in 5..8 {
+            writer.append_value(transaction(nonce)).unwrap();
+        }
+        static_files.commit(writer).unwrap();
+
+        let mut writer = static_files.writer(StaticFileSegment::Receipts, 0..=4, 5).unwrap();
+        writer.append_empty().unwrap();
+        writer.append_value(Receipt { cumulative_gas_used: 21_000, ..Default::default() }).unwrap();
+        static_files.commit(writer).unwrap();
+
+        let static_files = StaticFiles::open(dir.path(), Compression::Uncompressed).unwrap();
+        assert_eq!(static_files.highest_block(StaticFileSegment::Headers), Some(1));
+        assert_eq!(static_files.highest_block(StaticFileSegment::Transactions), Some(4));
+        assert_eq!(
+            static_files.header_with_hash(1).unwrap(),
+            Some((Header { number: 1, ..Default::default() }, H256::from_low_u64_be(1)))
+        );
+        assert_eq!(static_files.canonical_hash(0).unwrap(), Some(H256::from_low_u64_be(0)));
+        assert_eq!(static_files.header(2).unwrap(), None);
+
+        assert_eq!(static_files.transaction(4).unwrap(), None);
+        assert_eq!(static_files.transaction(6).unwrap(), Some(transaction(6)));
+        assert_eq!(static_files.transaction(8).unwrap(), None);
+
+        assert_eq!(static_files.receipt(5).unwrap(), None);
+        assert_eq!(static_files.receipt(6).unwrap().unwrap().cumulative_gas_used, 21_000);
+    }
+
+    #[test]
+    fn reject_non_contiguous_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let static_files = StaticFiles::open(dir.path(), Compression::Uncompressed).unwrap();
+
+        let writer = static_files.writer(StaticFileSegment::Headers, 0..=9, 0).unwrap();
+        static_files.commit(writer).unwrap();
+
+        assert!(matches!(
+            static_files.writer(StaticFileSegment::Headers, 11..=20, 11),
+            Err(StaticFileError::NonContiguous { expected: 10, got: 11, .. })
+        ));
+    }
+}
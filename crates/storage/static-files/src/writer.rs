@@ -0,0 +1,148 @@
+use crate::{
+    file::{file_path, DATA_EXTENSION, INDEX_EXTENSION, INDEX_HEADER_LEN, INDEX_VERSION},
+    Compression, StaticFile, StaticFileError, StaticFileSegment,
+};
+use reth_codecs::Compact;
+use reth_primitives::{BlockHash, BlockNumber, Header};
+use std::{
+    fs::{self, File},
+    io::{BufWriter, Write},
+    ops::RangeInclusive,
+    path::{Path, PathBuf},
+};
+
+/// The extension appended to the files while they are written.
+const TEMP_EXTENSION: &str = "tmp";
+
+/// Writes the rows of a new [StaticFile].
+///
+/// Rows are appended in key order, starting at the first key of the file. The files are written
+/// under temporary names and only renamed once complete, so an interrupted write never leaves a
+/// partial static file behind.
+///
+/// Created by [StaticFiles::writer][crate::StaticFiles::writer] and completed by
+/// [StaticFiles::commit][crate::StaticFiles::commit].
+#[derive(Debug)]
+pub struct StaticFileWriter {
+    /// The directory of the static files.
+    dir: PathBuf,
+    /// The segment of the rows.
+    segment: StaticFileSegment,
+    /// The blocks whose rows are written.
+    blocks: RangeInclusive<BlockNumber>,
+    /// The compression of the rows.
+    compression: Compression,
+    /// The key of the first row.
+    first_key: u64,
+    /// The offsets of the written rows, followed by the end of the last row.
+    offsets: Vec<u64>,
+    /// The data file.
+    data: BufWriter<File>,
+}
+
+impl StaticFileWriter {
+    /// Creates the temporary data file of a new static file.
+    pub(crate) fn new(
+        dir: &Path,
+        segment: StaticFileSegment,
+        blocks: RangeInclusive<BlockNumber>,
+        first_key: u64,
+        compression: Compression,
+    ) -> Result<Self, StaticFileError> {
+        let data = File::create(temp_path(dir, segment, &blocks, DATA_EXTENSION))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            segment,
+            blocks,
+            compression,
+            first_key,
+            offsets: vec![0],
+            data: BufWriter::new(data),
+        })
+    }
+
+    /// Returns the segment of the rows.
+    pub fn segment(&self) -> StaticFileSegment {
+        self.segment
+    }
+
+    /// Returns the key of the next row.
+    pub fn next_key(&self) -> u64 {
+        self.first_key + self.offsets.len() as u64 - 1
+    }
+
+    /// Appends the next row. An empty row stands for a key without a value.
+    pub fn append(&mut self, row: &[u8]) -> Result<(), StaticFileError> {
+        let row = if row.is_empty() { Vec::new() } else { self.compression.compress(row)? };
+        self.data.write_all(&row)?;
+
+        let end = self.offsets.last().expect("offsets are not empty") + row.len() as u64;
+        self.offsets.push(end);
+        Ok(())
+    }
+
+    /// Appends a row for a key without a value.
+    pub fn append_empty(&mut self) -> Result<(), StaticFileError> {
+        self.append(&[])
+    }
+
+    /// Appends the [Compact] encoding of a value as the next row.
+    pub fn append_value<T: Compact>(&mut self, value: T) -> Result<(), StaticFileError> {
+        let mut row = Vec::new();
+        value.to_compact(&mut row);
+        self.append(&row)
+    }
+
+    /// Appends a canonical header and its hash as the next row of the
+    /// [Headers][StaticFileSegment::Headers] segment.
+    pub fn append_header(
+        &mut self,
+        header: Header,
+        hash: BlockHash,
+    ) -> Result<(), StaticFileError> {
+        let mut row = hash.as_bytes().to_vec();
+        header.to_compact(&mut row);
+        self.append(&row)
+    }
+
+    /// Writes the index, renames the files to their final names and opens the static file.
+    pub(crate) fn finish(self) -> Result<StaticFile, StaticFileError> {
+        let data = self.data.into_inner().map_err(|error| error.into_error())?;
+        data.sync_all()?;
+
+        let rows = self.offsets.len() as u64 - 1;
+        let mut index = Vec::with_capacity(INDEX_HEADER_LEN + self.offsets.len() * 8);
+        index.push(INDEX_VERSION);
+        index.push(self.compression as u8);
+        index.extend_from_slice(&self.first_key.to_le_bytes());
+        index.extend_from_slice(&rows.to_le_bytes());
+        for offset in &self.offsets {
+            index.extend_from_slice(&offset.to_le_bytes());
+        }
+
+        let mut index_file =
+            File::create(temp_path(&self.dir, self.segment, &self.blocks, INDEX_EXTENSION))?;
+        index_file.write_all(&index)?;
+        index_file.sync_all()?;
+
+        // Static files are discovered by their index, so it is renamed last.
+        for extension in [DATA_EXTENSION, INDEX_EXTENSION] {
+            fs::rename(
+                temp_path(&self.dir, self.segment, &self.blocks, extension),
+                file_path(&self.dir, self.segment, &self.blocks, extension),
+            )?;
+        }
+
+        StaticFile::open(&self.dir, self.segment, self.blocks)
+    }
+}
+
+/// Returns the path of a static file with the given extension while it is written.
+fn temp_path(
+    dir: &Path,
+    segment: StaticFileSegment,
+    blocks: &RangeInclusive<BlockNumber>,
+    extension: &str,
+) -> PathBuf {
+    dir.join(format!("{}.{extension}.{TEMP_EXTENSION}", segment.file_name(blocks)))
+}
@@ -0,0 +1,178 @@
+//! A blob store that persists sidecars as individual files.
+
+use crate::blobstore::{BlobStore, BlobStoreError};
+use parking_lot::Mutex;
+use reth_primitives::{BlobTransactionSidecar, H256};
+use reth_rlp::{Decodable, Encodable};
+use std::{
+    ffi::OsStr,
+    fs,
+    io::ErrorKind,
+    path::{Path, PathBuf},
+};
+use tracing::debug;
+
+/// A [`BlobStore`] that writes every sidecar to its own file in a directory.
+///
+/// Files are named after the hex encoded transaction hash and contain the RLP encoded sidecar.
+/// Blob sidecars are only relevant while the transaction is in the pool, so these files are
+/// removed when the store is opened. Other files in the directory are left untouched.
+#[derive(Debug)]
+pub struct DiskFileBlobStore {
+    /// The directory the sidecars are stored in.
+    blob_dir: PathBuf,
+    /// Total size of all stored files, in bytes.
+    size: Mutex<usize>,
+}
+
+// === impl DiskFileBlobStore ===
+
+impl DiskFileBlobStore {
+    /// Opens the store in the given directory, creating it if it does not exist yet.
+    ///
+    /// Any sidecars left over from a previous run are removed.
+    pub fn open(blob_dir: impl Into<PathBuf>) -> Result<Self, BlobStoreError> {
+        let blob_dir = blob_dir.into();
+        fs::create_dir_all(&blob_dir)?;
+
+        debug!(target: "txpool::blob", ?blob_dir, "Removing leftover blob sidecars");
+        for entry in fs::read_dir(&blob_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() && is_blob_file_name(&entry.file_name()) {
+                fs::remove_file(entry.path())?;
+            }
+        }
+        Ok(Self { blob_dir, size: Mutex::new(0) })
+    }
+
+    /// Returns the directory the sidecars are stored in.
+    pub fn blob_dir(&self) -> &Path {
+        &self.blob_dir
+    }
+
+    /// Returns the path of the file for the given transaction.
+    fn blob_path(&self, tx: H256) -> PathBuf {
+        self.blob_dir.join(format!("{tx:x}"))
+    }
+
+    /// Writes the sidecar to disk, returns the number of written bytes.
+    fn write_one(&self, tx: H256, data: &BlobTransactionSidecar) -> Result<usize, BlobStoreError> {
+        let path = self.blob_path(tx);
+        let previous = fs::metadata(&path).map(|meta| meta.len() as usize).unwrap_or_default();
+        let mut buf = Vec::with_capacity(data.length());
+        data.encode(&mut buf);
+        fs::write(path, &buf)?;
+
+        let mut size = self.size.lock();
+        *size = size.saturating_sub(previous) + buf.len();
+        Ok(buf.len())
+    }
+
+    /// Removes the file of the given transaction, if it exists.
+    fn delete_one(&self, tx: H256) -> Result<(), BlobStoreError> {
+        let path = self.blob_path(tx);
+        let len = match fs::metadata(&path) {
+            Ok(meta) => meta.len() as usize,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(()),
+            Err(err) => return Err(err.into()),
+        };
+        fs::remove_file(path)?;
+        let mut size = self.size.lock();
+        *size = size.saturating_sub(len);
+        Ok(())
+    }
+
+    /// Reads the sidecar of the given transaction, if it exists.
+    fn read_one(&self, tx: H256) -> Result<Option<BlobTransactionSidecar>, BlobStoreError> {
+        let data = match fs::read(self.blob_path(tx)) {
+            Ok(data) => data,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Some(BlobTransactionSidecar::decode(&mut data.as_slice())?))
+    }
+}
+
+/// Returns true if the file name is a hex encoded transaction hash, as written by
+/// [`DiskFileBlobStore`].
+fn is_blob_file_name(name: &OsStr) -> bool {
+    name.to_str().map_or(false, |name| {
+        name.len() == 64 && name.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f'))
+    })
+}
+
+impl BlobStore for DiskFileBlobStore {
+    fn insert(&self, tx: H256, data: BlobTransactionSidecar) -> Result<(), BlobStoreError> {
+        self.write_one(tx, &data)?;
+        Ok(())
+    }
+
+    fn insert_all(&self, txs: Vec<(H256, BlobTransactionSidecar)>) -> Result<(), BlobStoreError> {
+        for (tx, data) in txs {
+            self.write_one(tx, &data)?;
+        }
+        Ok(())
+    }
+
+    fn delete(&self, tx: H256) -> Result<(), BlobStoreError> {
+        self.delete_one(tx)
+    }
+
+    fn delete_all(&self, txs: Vec<H256>) -> Result<(), BlobStoreError> {
+        for tx in txs {
+            self.delete_one(tx)?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, tx: H256) -> Result<Option<BlobTransactionSidecar>, BlobStoreError> {
+        self.read_one(tx)
+    }
+
+    fn get_all(
+        &self,
+        txs: Vec<H256>,
+    ) -> Result<Vec<(H256, BlobTransactionSidecar)>, BlobStoreError> {
+        let mut items = Vec::with_capacity(txs.len());
+        for tx in txs {
+            if let Some(data) = self.read_one(tx)? {
+                items.push((tx, data));
+            }
+        }
+        Ok(items)
+    }
+
+    fn data_size_hint(&self) -> Option<usize> {
+        Some(*self.size.lock())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blobstore::test_sidecar;
+
+    #[test]
+    fn disk_insert_get_delete() {
+        let dir = tempfile::tempdir().unwrap();
+        let store = DiskFileBlobStore::open(dir.path().join("blobs")).unwrap();
+
+        let (a, b) = (H256::random(), H256::random());
+        store.insert_all(vec![(a, test_sidecar(1)), (b, test_sidecar(2))]).unwrap();
+        assert_eq!(store.get(a).unwrap(), Some(test_sidecar(1)));
+        assert_eq!(store.get_all(vec![a, b, H256::zero()]).unwrap().len(), 2);
+        assert!(store.data_size_hint().unwrap() > 0);
+
+        store.delete_all(vec![a, b]).unwrap();
+        assert!(store.get(a).unwrap().is_none());
+        assert_eq!(store.data_size_hint(), Some(0));
+
+        // leftovers are cleared on reopen, other files are kept
+        store.insert(a, test_sidecar(1)).unwrap();
+        let other = store.blob_dir().join("other");
+        fs::write(&other, b"other").unwrap();
+        let store = DiskFileBlobStore::open(store.blob_dir()).unwrap();
+        assert!(store.get(a).unwrap().is_none());
+        assert!(other.exists());
+    }
+}
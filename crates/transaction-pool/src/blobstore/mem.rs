@@ -0,0 +1,210 @@
+//! An in-memory blob store with optional spillover to disk.
+
+use crate::blobstore::{sidecar_size, BlobStore, BlobStoreError, DiskFileBlobStore};
+use parking_lot::RwLock;
+use reth_primitives::{BlobTransactionSidecar, H256};
+use std::collections::{HashMap, VecDeque};
+
+/// An in-memory [`BlobStore`].
+///
+/// If configured with [`InMemoryBlobStore::with_spillover`], the oldest sidecars are moved to a
+/// [`DiskFileBlobStore`] once the in-memory data exceeds the configured size.
+#[derive(Debug, Default)]
+pub struct InMemoryBlobStore {
+    inner: RwLock<InMemoryBlobStoreInner>,
+    /// Where to move sidecars once the memory limit is reached.
+    spillover: Option<Spillover>,
+}
+
+#[derive(Debug, Default)]
+struct InMemoryBlobStoreInner {
+    /// All sidecars that are currently kept in memory.
+    store: HashMap<H256, BlobTransactionSidecar>,
+    /// Insertion order of the in-memory sidecars, oldest first.
+    ///
+    /// This may contain hashes of sidecars that were already deleted, these are skipped.
+    order: VecDeque<H256>,
+    /// Total size of all in-memory sidecars, in bytes.
+    data_size: usize,
+}
+
+#[derive(Debug)]
+struct Spillover {
+    /// Max total size of the in-memory sidecars, in bytes.
+    max_memory_size: usize,
+    /// The store the oldest sidecars are moved to.
+    disk: DiskFileBlobStore,
+}
+
+// === impl InMemoryBlobStore ===
+
+impl InMemoryBlobStore {
+    /// Creates a new store that keeps at most `max_memory_size` bytes of sidecars in memory and
+    /// moves the oldest ones to the given disk store.
+    pub fn with_spillover(max_memory_size: usize, disk: DiskFileBlobStore) -> Self {
+        Self { inner: Default::default(), spillover: Some(Spillover { max_memory_size, disk }) }
+    }
+
+    /// Inserts the sidecar while holding the lock.
+    fn insert_one(inner: &mut InMemoryBlobStoreInner, tx: H256, data: BlobTransactionSidecar) {
+        inner.data_size += sidecar_size(&data);
+        if let Some(previous) = inner.store.insert(tx, data) {
+            inner.data_size -= sidecar_size(&previous);
+        } else {
+            inner.order.push_back(tx);
+        }
+        // amortized cleanup of hashes of sidecars that were already deleted
+        if inner.order.len() > inner.store.len() * 2 {
+            let InMemoryBlobStoreInner { store, order, .. } = inner;
+            order.retain(|tx| store.contains_key(tx));
+        }
+    }
+
+    /// Removes the sidecar while holding the lock.
+    fn remove_one(inner: &mut InMemoryBlobStoreInner, tx: H256) -> Option<BlobTransactionSidecar> {
+        let data = inner.store.remove(&tx)?;
+        inner.data_size -= sidecar_size(&data);
+        Some(data)
+    }
+
+    /// Moves the oldest sidecars to disk until the in-memory size is within limits.
+    fn maybe_spill(&self, inner: &mut InMemoryBlobStoreInner) -> Result<(), BlobStoreError> {
+        let Some(spillover) = &self.spillover else { return Ok(()) };
+        while inner.data_size > spillover.max_memory_size {
+            let Some(tx) = inner.order.pop_front() else { break };
+            if let Some(data) = Self::remove_one(inner, tx) {
+                spillover.disk.insert(tx, data)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl BlobStore for InMemoryBlobStore {
+    fn insert(&self, tx: H256, data: BlobTransactionSidecar) -> Result<(), BlobStoreError> {
+        let mut inner = self.inner.write();
+        Self::insert_one(&mut inner, tx, data);
+        self.maybe_spill(&mut inner)
+    }
+
+    fn insert_all(&self, txs: Vec<(H256, BlobTransactionSidecar)>) -> Result<(), BlobStoreError> {
+        if txs.is_empty() {
+            return Ok(())
+        }
+        let mut inner = self.inner.write();
+        for (tx, data) in txs {
+            Self::insert_one(&mut inner, tx, data);
+        }
+        self.maybe_spill(&mut inner)
+    }
+
+    fn delete(&self, tx: H256) -> Result<(), BlobStoreError> {
+        Self::remove_one(&mut self.inner.write(), tx);
+        // a sidecar that was reinserted after it was spilled is in memory and on disk
+        if let Some(spillover) = &self.spillover {
+            spillover.disk.delete(tx)?;
+        }
+        Ok(())
+    }
+
+    fn delete_all(&self, txs: Vec<H256>) -> Result<(), BlobStoreError> {
+        {
+            let mut inner = self.inner.write();
+            for tx in &txs {
+                Self::remove_one(&mut inner, *tx);
+            }
+        }
+        if let Some(spillover) = &self.spillover {
+            spillover.disk.delete_all(txs)?;
+        }
+        Ok(())
+    }
+
+    fn get(&self, tx: H256) -> Result<Option<BlobTransactionSidecar>, BlobStoreError> {
+        if let Some(data) = self.inner.read().store.get(&tx).cloned() {
+            return Ok(Some(data))
+        }
+        match &self.spillover {
+            Some(spillover) => spillover.disk.get(tx),
+            None => Ok(None),
+        }
+    }
+
+    fn get_all(
+        &self,
+        txs: Vec<H256>,
+    ) -> Result<Vec<(H256, BlobTransactionSidecar)>, BlobStoreError> {
+        let mut items = Vec::with_capacity(txs.len());
+        let mut on_disk = Vec::new();
+        {
+            let inner = self.inner.read();
+            for tx in txs {
+                match inner.store.get(&tx) {
+                    Some(data) => items.push((tx, data.clone())),
+                    None => on_disk.push(tx),
+                }
+            }
+        }
+        if let Some(spillover) = &self.spillover {
+            items.extend(spillover.disk.get_all(on_disk)?);
+        }
+        Ok(items)
+    }
+
+    fn data_size_hint(&self) -> Option<usize> {
+        let in_memory = self.inner.read().data_size;
+        let on_disk = self
+            .spillover
+            .as_ref()
+            .and_then(|spillover| spillover.disk.data_size_hint())
+            .unwrap_or_default();
+        Some(in_memory + on_disk)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blobstore::test_sidecar;
+
+    #[test]
+    fn mem_insert_get_delete() {
+        let store = InMemoryBlobStore::default();
+        let tx = H256::random();
+        store.insert(tx, test_sidecar(1)).unwrap();
+        assert_eq!(store.get(tx).unwrap(), Some(test_sidecar(1)));
+        assert_eq!(store.data_size_hint(), Some(sidecar_size(&test_sidecar(1))));
+
+        store.delete(tx).unwrap();
+        assert!(store.get(tx).unwrap().is_none());
+        assert_eq!(store.data_size_hint(), Some(0));
+    }
+
+    #[test]
+    fn spills_oldest_to_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        let disk = DiskFileBlobStore::open(dir.path()).unwrap();
+        let limit = sidecar_size(&test_sidecar(0));
+        let store = InMemoryBlobStore::with_spillover(limit, disk);
+
+        let (a, b) = (H256::random(), H256::random());
+        store.insert(a, test_sidecar(1)).unwrap();
+        store.insert(b, test_sidecar(2)).unwrap();
+
+        // `a` was moved to disk but is still retrievable
+        assert!(!store.inner.read().store.contains_key(&a));
+        assert_eq!(store.get(a).unwrap(), Some(test_sidecar(1)));
+        assert_eq!(store.get_all(vec![a, b]).unwrap().len(), 2);
+
+        // reinserting `a` keeps it in memory, deleting it also removes the spilled copy
+        store.insert(a, test_sidecar(3)).unwrap();
+        assert!(store.inner.read().store.contains_key(&a));
+        store.delete(a).unwrap();
+        assert!(store.get(a).unwrap().is_none());
+
+        store.insert(a, test_sidecar(1)).unwrap();
+        store.delete_all(vec![a, b]).unwrap();
+        assert!(store.get(a).unwrap().is_none());
+        assert!(store.get(b).unwrap().is_none());
+    }
+}
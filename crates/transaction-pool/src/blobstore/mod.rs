@@ -0,0 +1,77 @@
+//! Storage for blob transaction sidecars.
+//!
+//! Blob transactions ([EIP-4844](https://eips.ethereum.org/EIPS/eip-4844)) are accompanied by a
+//! sidecar of up to [`MAX_BLOBS_PER_BLOCK`](reth_primitives::constants::MAX_BLOBS_PER_BLOCK) blobs
+//! of 128KiB each. Keeping these inside the pool's transaction types would make every clone and
+//! every sub-pool move expensive, so the pool only tracks the canonical transaction and stores the
+//! sidecar separately in a [`BlobStore`].
+
+pub use disk::DiskFileBlobStore;
+pub use mem::InMemoryBlobStore;
+use reth_primitives::{constants::BYTES_PER_BLOB, BlobTransactionSidecar, H256};
+use std::fmt;
+
+mod disk;
+mod mem;
+
+/// A blob store that can be used to store blob sidecars of transactions in the pool.
+///
+/// Sidecars are keyed by the hash of the transaction they belong to.
+#[auto_impl::auto_impl(Arc)]
+pub trait BlobStore: fmt::Debug + Send + Sync + 'static {
+    /// Inserts the blob sidecar into the store
+    fn insert(&self, tx: H256, data: BlobTransactionSidecar) -> Result<(), BlobStoreError>;
+
+    /// Inserts multiple blob sidecars into the store
+    fn insert_all(&self, txs: Vec<(H256, BlobTransactionSidecar)>) -> Result<(), BlobStoreError>;
+
+    /// Deletes the blob sidecar from the store
+    fn delete(&self, tx: H256) -> Result<(), BlobStoreError>;
+
+    /// Deletes multiple blob sidecars from the store
+    fn delete_all(&self, txs: Vec<H256>) -> Result<(), BlobStoreError>;
+
+    /// Retrieves the decoded blob data for the given transaction hash.
+    fn get(&self, tx: H256) -> Result<Option<BlobTransactionSidecar>, BlobStoreError>;
+
+    /// Retrieves all decoded blob data for the given transaction hashes.
+    ///
+    /// This only returns the blobs that were found in the store.
+    /// If there's no blob it will not be returned.
+    fn get_all(
+        &self,
+        txs: Vec<H256>,
+    ) -> Result<Vec<(H256, BlobTransactionSidecar)>, BlobStoreError>;
+
+    /// Data size of all transactions in the blob store, in bytes.
+    fn data_size_hint(&self) -> Option<usize>;
+}
+
+/// Error variants that can occur when interacting with a [`BlobStore`].
+#[derive(Debug, thiserror::Error)]
+pub enum BlobStoreError {
+    /// Failed to decode the stored blob data.
+    #[error("failed to decode blob data: {0}")]
+    DecodeError(#[from] reth_rlp::DecodeError),
+    /// Failed to read or write blob data.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Returns the approximate in-memory size of the sidecar, in bytes.
+#[inline]
+pub(crate) fn sidecar_size(sidecar: &BlobTransactionSidecar) -> usize {
+    sidecar.blobs.len() * BYTES_PER_BLOB +
+        (sidecar.commitments.len() + sidecar.proofs.len()) *
+            reth_primitives::constants::BYTES_PER_COMMITMENT
+}
+
+#[cfg(test)]
+pub(crate) fn test_sidecar(fill: u8) -> BlobTransactionSidecar {
+    use reth_primitives::constants::BYTES_PER_COMMITMENT;
+    BlobTransactionSidecar {
+        blobs: vec![vec![fill; BYTES_PER_BLOB].into()],
+        commitments: vec![[fill; BYTES_PER_COMMITMENT]],
+        proofs: vec![[fill; BYTES_PER_COMMITMENT]],
+    }
+}
@@ -1,6 +1,9 @@
 /// Guarantees max transactions for one sender, compatible with geth/erigon
 pub(crate) const MAX_ACCOUNT_SLOTS_PER_SENDER: usize = 16;
 
+/// Guarantees max blob transactions for one sender, compatible with geth's blobpool
+pub(crate) const MAX_ACCOUNT_BLOB_SLOTS_PER_SENDER: usize = 16;
+
 ///! Configuration options for the Transaction pool.
 #[derive(Debug, Clone)]
 pub struct PoolConfig {
@@ -10,8 +13,12 @@ pub struct PoolConfig {
     pub basefee_limit: SubPoolLimit,
     /// Max number of transaction in the queued sub-pool
     pub queued_limit: SubPoolLimit,
+    /// Max number of transaction in the blob sub-pool
+    pub blob_limit: SubPoolLimit,
     /// Max number of executable transaction slots guaranteed per account
     pub max_account_slots: usize,
+    /// Max number of blob transactions per account
+    pub max_account_blob_slots: usize,
 }
 
 impl Default for PoolConfig {
@@ -20,7 +27,9 @@ impl Default for PoolConfig {
             pending_limit: Default::default(),
             basefee_limit: Default::default(),
             queued_limit: Default::default(),
+            blob_limit: Default::default(),
             max_account_slots: MAX_ACCOUNT_SLOTS_PER_SENDER,
+            max_account_blob_slots: MAX_ACCOUNT_BLOB_SLOTS_PER_SENDER,
         }
     }
 }
@@ -1,5 +1,6 @@
 //! Transaction pool errors
 
+use crate::blobstore::BlobStoreError;
 use reth_primitives::{Address, TxHash};
 
 /// Transaction pool result type.
@@ -29,6 +30,31 @@ pub enum PoolError {
     /// respect the max_init_code_size.
     #[error("[{0:?}] Transaction's size {1} exceeds max_init_code_size {2}.")]
     TxExceedsMaxInitCodeSize(TxHash, usize, usize),
+    /// Thrown if the transaction's nonce is lower than the current on chain nonce of the sender.
+    #[error("[{0:?}] Transaction nonce {1} is lower than the sender's nonce {2}.")]
+    NonceTooLow(TxHash, u64, u64),
+    /// Thrown if the sender's state could not be looked up while validating the transaction.
+    #[error("[{0:?}] Failed to look up the sender's state: {1}")]
+    Provider(TxHash, reth_interfaces::Error),
+    /// Thrown if a blob transaction is submitted without its sidecar.
+    #[error("[{0:?}] Blob transaction is missing its sidecar.")]
+    MissingBlobSidecar(TxHash),
+    /// Thrown if a blob transaction carries no blobs or more blobs than fit into a block.
+    #[error("[{0:?}] Blob transaction has {1} blobs, expected between 1 and {2}.")]
+    InvalidBlobCount(TxHash, usize, usize),
+    /// Thrown if the sidecar of a blob transaction does not match its versioned hashes.
+    #[error("[{0:?}] Blob sidecar does not match the transaction's versioned hashes.")]
+    InvalidBlobSidecar(TxHash),
+    /// Thrown if a KZG proof of a blob transaction's sidecar is invalid or malformed.
+    #[error("[{0:?}] Blob sidecar has an invalid KZG proof.")]
+    InvalidBlobProof(TxHash),
+    /// Thrown if a blob transaction is submitted but no KZG trusted setup is configured to verify
+    /// its proofs.
+    #[error("[{0:?}] Blob transactions are not accepted without a KZG trusted setup.")]
+    MissingKzgSettings(TxHash),
+    /// Thrown if the sidecar of a blob transaction could not be stored.
+    #[error("[{0:?}] Failed to store blob sidecar: {1}")]
+    BlobStore(TxHash, BlobStoreError),
 }
 
 // === impl PoolError ===
@@ -43,6 +69,14 @@ impl PoolError {
             PoolError::DiscardedOnInsert(hash) => hash,
             PoolError::TxExceedsGasLimit(hash, _, _) => hash,
             PoolError::TxExceedsMaxInitCodeSize(hash, _, _) => hash,
+            PoolError::NonceTooLow(hash, _, _) => hash,
+            PoolError::Provider(hash, _) => hash,
+            PoolError::MissingBlobSidecar(hash) => hash,
+            PoolError::InvalidBlobCount(hash, _, _) => hash,
+            PoolError::InvalidBlobSidecar(hash) => hash,
+            PoolError::InvalidBlobProof(hash) => hash,
+            PoolError::MissingKzgSettings(hash) => hash,
+            PoolError::BlobStore(hash, _) => hash,
         }
     }
 }
@@ -79,24 +79,29 @@
 //! [`Pool`](crate::Pool) type is just an `Arc` wrapper around `PoolInner`. This is the usable type
 //! that provides the `TransactionPool` interface.
 
+use crate::{
+    blobstore::{BlobStore, BlobStoreError, InMemoryBlobStore},
+    error::PoolResult,
+    pool::PoolInner,
+    traits::{NewTransactionEvent, PoolSize},
+};
 pub use crate::{
     config::PoolConfig,
-    ordering::TransactionOrdering,
+    ordering::{CostOrdering, TransactionOrdering},
     traits::{
         BestTransactions, OnNewBlockEvent, PoolTransaction, PooledTransaction, PropagateKind,
-        PropagatedTransactions, TransactionOrigin, TransactionPool,
+        PropagatedTransactions, StateDiff, TransactionOrigin, TransactionPool,
+    },
+    validate::{
+        EthTransactionValidator, TransactionValidationOutcome, TransactionValidator,
+        ValidPoolTransaction,
     },
-    validate::{TransactionValidationOutcome, TransactionValidator, ValidPoolTransaction},
-};
-use crate::{
-    error::PoolResult,
-    pool::PoolInner,
-    traits::{NewTransactionEvent, PoolSize},
 };
-use reth_primitives::{TxHash, U256};
+use reth_primitives::{BlobTransactionSidecar, TxHash, U256};
 use std::{collections::HashMap, sync::Arc};
 use tokio::sync::mpsc::Receiver;
 
+pub mod blobstore;
 mod config;
 pub mod error;
 mod identifier;
@@ -125,8 +130,21 @@ where
     T: TransactionOrdering<Transaction = <V as TransactionValidator>::Transaction>,
 {
     /// Create a new transaction pool instance.
+    ///
+    /// Blob sidecars are kept in an [`InMemoryBlobStore`].
     pub fn new(client: Arc<V>, ordering: Arc<T>, config: PoolConfig) -> Self {
-        Self { pool: Arc::new(PoolInner::new(client, ordering, config)) }
+        Self::with_blob_store(client, ordering, Arc::new(InMemoryBlobStore::default()), config)
+    }
+
+    /// Create a new transaction pool instance that stores blob sidecars in the given
+    /// [`BlobStore`].
+    pub fn with_blob_store(
+        client: Arc<V>,
+        ordering: Arc<T>,
+        blob_store: Arc<dyn BlobStore>,
+        config: PoolConfig,
+    ) -> Self {
+        Self { pool: Arc::new(PoolInner::new(client, ordering, blob_store, config)) }
     }
 
     /// Returns the wrapped pool.
@@ -263,6 +281,17 @@ where
     fn on_propagated(&self, txs: PropagatedTransactions) {
         self.inner().on_propagated(txs)
     }
+
+    fn get_blob(&self, tx_hash: TxHash) -> Result<Option<BlobTransactionSidecar>, BlobStoreError> {
+        self.inner().get_blob(tx_hash)
+    }
+
+    fn get_all_blobs(
+        &self,
+        tx_hashes: Vec<TxHash>,
+    ) -> Result<Vec<(TxHash, BlobTransactionSidecar)>, BlobStoreError> {
+        self.inner().get_all_blobs(tx_hashes)
+    }
 }
 
 impl<V: TransactionValidator, O: TransactionOrdering> Clone for Pool<V, O> {
@@ -45,6 +45,12 @@
 //!  - Basefee Pool: To account for the dynamic base fee requirement (3. b) which could render
 //! an EIP-1559 and all subsequent transactions of the sender currently invalid.
 //!
+//!  - Blob Pool: Contains EIP-4844 blob transactions that are otherwise ready but whose max fee per
+//! blob gas is currently below the blob gas price of the pending block.
+//!
+//! The sidecars of blob transactions are not kept in the sub-pools but in a separate
+//! [`BlobStore`](crate::blobstore::BlobStore).
+//!
 //! The classification of transactions is always dependent on the current state that is changed as
 //! soon as a new block is mined. Once a new block is mined, the account changeset must be applied
 //! to the transaction pool.
@@ -68,6 +74,7 @@
 #![allow(dead_code)] // TODO(mattsse): remove once remaining checks implemented
 
 use crate::{
+    blobstore::{BlobStore, BlobStoreError},
     error::{PoolError, PoolResult},
     identifier::{SenderId, SenderIdentifiers, TransactionId},
     pool::{listener::PoolEventBroadcast, state::SubPool, txpool::TxPool},
@@ -80,7 +87,7 @@ use crate::{
 use best::BestTransactions;
 pub use events::TransactionEvent;
 use parking_lot::{Mutex, RwLock};
-use reth_primitives::{Address, TxHash, H256};
+use reth_primitives::{Address, BlobTransactionSidecar, TxHash, H256};
 use std::{collections::HashSet, fmt, sync::Arc, time::Instant};
 use tokio::sync::mpsc;
 use tracing::warn;
@@ -103,6 +110,8 @@ pub struct PoolInner<V: TransactionValidator, T: TransactionOrdering> {
     validator: Arc<V>,
     /// The internal pool that manages all transactions.
     pool: RwLock<TxPool<T>>,
+    /// Storage for the sidecars of blob transactions.
+    blob_store: Arc<dyn BlobStore>,
     /// Pool settings.
     config: PoolConfig,
     /// Manages listeners for transaction state change events.
@@ -121,12 +130,18 @@ where
     T: TransactionOrdering<Transaction = <V as TransactionValidator>::Transaction>,
 {
     /// Create a new transaction pool instance.
-    pub(crate) fn new(validator: Arc<V>, ordering: Arc<T>, config: PoolConfig) -> Self {
+    pub(crate) fn new(
+        validator: Arc<V>,
+        ordering: Arc<T>,
+        blob_store: Arc<dyn BlobStore>,
+        config: PoolConfig,
+    ) -> Self {
         Self {
             identifiers: Default::default(),
             validator,
             event_listener: Default::default(),
             pool: RwLock::new(TxPool::new(ordering, config.clone())),
+            blob_store,
             pending_transaction_listener: Default::default(),
             transaction_listener: Default::default(),
             config,
@@ -153,6 +168,23 @@ where
         &self.validator
     }
 
+    /// Returns the store that holds the sidecars of blob transactions.
+    pub fn blob_store(&self) -> &Arc<dyn BlobStore> {
+        &self.blob_store
+    }
+
+    /// Deletes the sidecars of the given transactions from the blob store.
+    ///
+    /// Transactions that are not blob transactions are ignored by the store.
+    fn delete_blobs(&self, txs: Vec<TxHash>) {
+        if txs.is_empty() {
+            return
+        }
+        if let Err(err) = self.blob_store.delete_all(txs) {
+            warn!(target: "txpool", ?err, "failed to delete blob sidecars");
+        }
+    }
+
     /// Adds a new transaction listener to the pool that gets notified about every new _pending_
     /// transaction.
     pub fn add_pending_listener(&self) -> mpsc::Receiver<TxHash> {
@@ -185,6 +217,8 @@ where
     /// Updates the entire pool after a new block was executed.
     pub(crate) fn on_new_block(&self, block: OnNewBlockEvent) {
         let outcome = self.pool.write().on_new_block(block);
+        // sidecars of mined and discarded transactions are no longer needed
+        self.delete_blobs(outcome.mined.iter().chain(&outcome.discarded).copied().collect());
         self.notify_on_new_block(outcome);
     }
 
@@ -198,11 +232,23 @@ where
         tx: TransactionValidationOutcome<T::Transaction>,
     ) -> PoolResult<TxHash> {
         match tx {
-            TransactionValidationOutcome::Valid { balance, state_nonce, transaction } => {
+            TransactionValidationOutcome::Valid { balance, state_nonce, mut transaction } => {
                 let sender_id = self.get_sender_id(transaction.sender());
                 let transaction_id = TransactionId::new(sender_id, transaction.nonce());
+                // the length must be computed while the sidecar is still attached
                 let encoded_length = transaction.encoded_length();
 
+                // move the sidecar to the blob store
+                let hash = *transaction.hash();
+                let has_sidecar = if let Some(sidecar) = transaction.take_blob_sidecar() {
+                    self.blob_store
+                        .insert(hash, sidecar)
+                        .map_err(|err| PoolError::BlobStore(hash, err))?;
+                    true
+                } else {
+                    false
+                };
+
                 let tx = ValidPoolTransaction {
                     cost: transaction.cost(),
                     transaction,
@@ -213,8 +259,22 @@ where
                     encoded_length,
                 };
 
-                let added = self.pool.write().add_transaction(tx, balance, state_nonce)?;
-                let hash = *added.hash();
+                let added = match self.pool.write().add_transaction(tx, balance, state_nonce) {
+                    Ok(added) => added,
+                    Err(err) => {
+                        if has_sidecar {
+                            self.delete_blobs(vec![hash]);
+                        }
+                        return Err(err)
+                    }
+                };
+
+                // the sidecar of a replaced transaction is no longer needed
+                if let Some(replaced) = added.replaced() {
+                    if replaced.transaction.is_eip4844() {
+                        self.delete_blobs(vec![*replaced.hash()]);
+                    }
+                }
 
                 // Notify about new pending transactions
                 if let Some(pending_hash) = added.as_pending() {
@@ -348,6 +408,10 @@ where
     ) -> Vec<Arc<ValidPoolTransaction<T::Transaction>>> {
         let removed = self.pool.write().remove_invalid(hashes);
 
+        self.delete_blobs(
+            removed.iter().filter(|tx| tx.transaction.is_eip4844()).map(|tx| *tx.hash()).collect(),
+        );
+
         let mut listener = self.event_listener.write();
 
         removed.iter().for_each(|tx| listener.discarded(tx.hash()));
@@ -379,6 +443,22 @@ where
         self.pool.read().get_all(txs).collect()
     }
 
+    /// Returns the sidecar of the blob transaction with the given hash.
+    pub(crate) fn get_blob(
+        &self,
+        tx_hash: TxHash,
+    ) -> Result<Option<BlobTransactionSidecar>, BlobStoreError> {
+        self.blob_store.get(tx_hash)
+    }
+
+    /// Returns the sidecars of all blob transactions for the given hashes.
+    pub(crate) fn get_all_blobs(
+        &self,
+        tx_hashes: Vec<TxHash>,
+    ) -> Result<Vec<(TxHash, BlobTransactionSidecar)>, BlobStoreError> {
+        self.blob_store.get_all(tx_hashes)
+    }
+
     /// Notify about propagated transactions.
     pub(crate) fn on_propagated(&self, txs: PropagatedTransactions) {
         let mut listener = self.event_listener.write();
@@ -398,7 +478,15 @@ where
 
     /// Enforces the size limits of pool and returns the discarded transactions if violated.
     pub(crate) fn discard_worst(&self) -> HashSet<TxHash> {
-        self.pool.write().discard_worst().into_iter().map(|tx| *tx.hash()).collect()
+        let discarded = self.pool.write().discard_worst();
+        self.delete_blobs(
+            discarded
+                .iter()
+                .filter(|tx| tx.transaction.is_eip4844())
+                .map(|tx| *tx.hash())
+                .collect(),
+        );
+        discarded.into_iter().map(|tx| *tx.hash()).collect()
     }
 }
 
@@ -419,6 +507,8 @@ pub struct AddedPendingTransaction<T: PoolTransaction> {
     discarded: Vec<TxHash>,
     /// Transactions removed from the Ready pool
     removed: Vec<Arc<ValidPoolTransaction<T>>>,
+    /// The transaction that was replaced by this transaction, if any.
+    replaced: Option<Arc<ValidPoolTransaction<T>>>,
 }
 
 impl<T: PoolTransaction> AddedPendingTransaction<T> {
@@ -429,6 +519,7 @@ impl<T: PoolTransaction> AddedPendingTransaction<T> {
             promoted: Default::default(),
             discarded: Default::default(),
             removed: Default::default(),
+            replaced: None,
         }
     }
 }
@@ -445,6 +536,8 @@ pub enum AddedTransaction<T: PoolTransaction> {
         transaction: Arc<ValidPoolTransaction<T>>,
         /// The subpool it was moved to.
         subpool: SubPool,
+        /// The transaction that was replaced by this transaction, if any.
+        replaced: Option<Arc<ValidPoolTransaction<T>>>,
     },
 }
 
@@ -458,6 +551,14 @@ impl<T: PoolTransaction> AddedTransaction<T> {
         }
     }
 
+    /// Returns the transaction that was replaced by the added transaction, if any.
+    pub(crate) fn replaced(&self) -> Option<&Arc<ValidPoolTransaction<T>>> {
+        match self {
+            AddedTransaction::Pending(tx) => tx.replaced.as_ref(),
+            AddedTransaction::Parked { replaced, .. } => replaced.as_ref(),
+        }
+    }
+
     /// Returns the hash of the transaction
     pub(crate) fn hash(&self) -> &TxHash {
         match self {
@@ -472,7 +573,7 @@ impl<T: PoolTransaction> AddedTransaction<T> {
             AddedTransaction::Pending(tx) => {
                 NewTransactionEvent { subpool: SubPool::Pending, transaction: tx.transaction }
             }
-            AddedTransaction::Parked { transaction, subpool } => {
+            AddedTransaction::Parked { transaction, subpool, .. } => {
                 NewTransactionEvent { transaction, subpool }
             }
         }
@@ -251,3 +251,24 @@ impl<T: PoolTransaction> Ord for QueuedOrd<T> {
             other.timestamp.cmp(&self.timestamp))
     }
 }
+
+/// A new type wrapper for [`ValidPoolTransaction`]
+///
+/// This sorts blob transactions by their blob fee cap first and their fee cap second.
+///
+/// `Blob` transactions are EIP-4844 transactions that are otherwise ready but currently violate
+/// the blob fee or the dynamic fee requirement.
+#[derive(Debug)]
+pub(crate) struct BlobOrd<T: PoolTransaction>(Arc<ValidPoolTransaction<T>>);
+
+impl_ord_wrapper!(BlobOrd);
+
+impl<T: PoolTransaction> Ord for BlobOrd<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let this = &self.0.transaction;
+        let other = &other.0.transaction;
+        this.max_fee_per_blob_gas()
+            .cmp(&other.max_fee_per_blob_gas())
+            .then_with(|| this.max_fee_per_gas().cmp(&other.max_fee_per_gas()))
+    }
+}
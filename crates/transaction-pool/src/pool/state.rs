@@ -19,6 +19,12 @@ bitflags::bitflags! {
         ///
         /// Set to 1 if `feeCap` of the transaction meets the requirement of the pending block.
         const ENOUGH_FEE_CAP_BLOCK = 0b000010;
+        /// Covers the blob fee requirement of EIP-4844 transactions.
+        ///
+        /// Set to 1 if `maxFeePerBlobGas` of the transaction meets the blob gas price of the pending block.
+        const ENOUGH_BLOB_FEE_CAP_BLOCK = 0b000001;
+        /// Marks EIP-4844 transactions, those are kept in the `Blob` sub-pool while parked.
+        const BLOB_TRANSACTION = 0b1000000;
 
         const PENDING_POOL_BITS = Self::NO_PARKED_ANCESTORS.bits | Self::NO_NONCE_GAPS.bits | Self::ENOUGH_BALANCE.bits | Self::NOT_TOO_MUCH_GAS.bits |  Self::ENOUGH_FEE_CAP_BLOCK.bits;
 
@@ -36,9 +42,17 @@ impl TxState {
     ///   - _No_ parked ancestors
     ///   - enough balance
     ///   - enough fee cap
+    ///   - enough blob fee cap, if it's a blob transaction
     #[inline]
     pub(crate) fn is_pending(&self) -> bool {
-        *self >= TxState::PENDING_POOL_BITS
+        self.contains(TxState::PENDING_POOL_BITS) &&
+            (!self.is_blob() || self.contains(TxState::ENOUGH_BLOB_FEE_CAP_BLOCK))
+    }
+
+    /// Returns `true` if the transaction is an EIP-4844 transaction.
+    #[inline]
+    pub(crate) fn is_blob(&self) -> bool {
+        self.contains(TxState::BLOB_TRANSACTION)
     }
 
     /// Returns `true` if the `ENOUGH_FEE_CAP_BLOCK` bit is set.
@@ -61,6 +75,7 @@ pub enum SubPool {
     Queued = 0,
     Pending,
     BaseFee,
+    Blob,
 }
 
 // === impl PoolDestination ===
@@ -82,9 +97,13 @@ impl From<TxState> for SubPool {
         if value.is_pending() {
             return SubPool::Pending
         }
-        if value < TxState::BASE_FEE_POOL_BITS {
+        if !value.contains(TxState::BASE_FEE_POOL_BITS) {
             return SubPool::Queued
         }
+        // blob transactions that only lack the fee requirements are parked separately
+        if value.is_blob() {
+            return SubPool::Blob
+        }
         SubPool::BaseFee
     }
 }
@@ -128,4 +147,23 @@ mod tests {
         assert_eq!(SubPool::Pending, state.into());
         assert!(state.is_pending());
     }
+
+    #[test]
+    fn test_tx_blob() {
+        let state = TxState::PENDING_POOL_BITS | TxState::BLOB_TRANSACTION;
+        assert_eq!(SubPool::Blob, state.into());
+        assert!(!state.is_pending());
+
+        let state = state | TxState::ENOUGH_BLOB_FEE_CAP_BLOCK;
+        assert_eq!(SubPool::Pending, state.into());
+        assert!(state.is_pending());
+
+        let state = TxState::BASE_FEE_POOL_BITS |
+            TxState::BLOB_TRANSACTION |
+            TxState::ENOUGH_BLOB_FEE_CAP_BLOCK;
+        assert_eq!(SubPool::Blob, state.into());
+
+        let state = TxState::NO_PARKED_ANCESTORS | TxState::BLOB_TRANSACTION;
+        assert_eq!(SubPool::Queued, state.into());
+    }
 }
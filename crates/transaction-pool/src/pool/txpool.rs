@@ -1,12 +1,12 @@
 //! The internal transaction pool implementation.
 use crate::{
-    config::MAX_ACCOUNT_SLOTS_PER_SENDER,
+    config::{MAX_ACCOUNT_BLOB_SLOTS_PER_SENDER, MAX_ACCOUNT_SLOTS_PER_SENDER},
     error::PoolError,
     identifier::{SenderId, TransactionId},
     metrics::TxPoolMetrics,
     pool::{
         best::BestTransactions,
-        parked::{BasefeeOrd, ParkedPool, QueuedOrd},
+        parked::{BasefeeOrd, BlobOrd, ParkedPool, QueuedOrd},
         pending::PendingPool,
         state::{SubPool, TxState},
         update::{Destination, PoolUpdate},
@@ -17,7 +17,7 @@ use crate::{
     ValidPoolTransaction, U256,
 };
 use fnv::FnvHashMap;
-use reth_primitives::{TxHash, H256};
+use reth_primitives::{constants::BLOB_TX_MIN_BLOB_GASPRICE, TxHash, H256};
 use std::{
     cmp::Ordering,
     collections::{btree_map::Entry, hash_map, BTreeMap, HashMap},
@@ -48,6 +48,7 @@ pub(crate) const MIN_PROTOCOL_BASE_FEE: u128 = 7;
 ///         B3[(Queued)]
 ///         B1[(Pending)]
 ///         B2[(Basefee)]
+///         B4[(Blob)]
 ///     end
 ///   end
 ///   discard([discard])
@@ -57,10 +58,13 @@ pub(crate) const MIN_PROTOCOL_BASE_FEE: u128 = 7;
 ///   pool --> |if ready| B1
 ///   pool --> |if ready + basfee too low| B2
 ///   pool --> |nonce gap or lack of funds| B3
+///   pool --> |if ready + blob fee too low| B4
 ///   pool --> |update| pool
 ///   B1 --> |best| production
 ///   B2 --> |worst| discard
 ///   B3 --> |worst| discard
+///   B4 --> |worst| discard
+///   B4 --> |decreased blob fee| B1
 ///   B1 --> |increased fee| B2
 ///   B2 --> |decreased fee| B1
 ///   B3 --> |promote| B1
@@ -88,6 +92,11 @@ pub struct TxPool<T: TransactionOrdering> {
     /// Holds all parked transactions that currently violate the dynamic fee requirement but could
     /// be moved to pending if the base fee changes in their favor (decreases) in future blocks.
     basefee_pool: ParkedPool<BasefeeOrd<T::Transaction>>,
+    /// blob subpool
+    ///
+    /// Holds all blob transactions that are otherwise ready but currently violate the blob fee
+    /// requirement, see [EIP-4844](https://eips.ethereum.org/EIPS/eip-4844).
+    blob_pool: ParkedPool<BlobOrd<T::Transaction>>,
     /// All transactions in the pool.
     all_transactions: AllTransactions<T::Transaction>,
     /// Transaction pool metrics
@@ -104,7 +113,11 @@ impl<T: TransactionOrdering> TxPool<T> {
             pending_pool: PendingPool::new(ordering),
             queued_pool: Default::default(),
             basefee_pool: Default::default(),
-            all_transactions: AllTransactions::new(config.max_account_slots),
+            blob_pool: Default::default(),
+            all_transactions: AllTransactions::new(
+                config.max_account_slots,
+                config.max_account_blob_slots,
+            ),
             config,
             metrics: Default::default(),
         }
@@ -124,6 +137,8 @@ impl<T: TransactionOrdering> TxPool<T> {
             basefee_size: self.basefee_pool.size(),
             queued: self.queued_pool.len(),
             queued_size: self.queued_pool.size(),
+            blob: self.blob_pool.len(),
+            blob_size: self.blob_pool.size(),
         }
     }
 
@@ -174,8 +189,11 @@ impl<T: TransactionOrdering> TxPool<T> {
         }
 
         // Apply the state changes to the total set of transactions which triggers sub-pool updates.
-        let updates =
-            self.all_transactions.update(event.pending_block_base_fee, &event.state_changes);
+        let updates = self.all_transactions.update(
+            event.pending_block_base_fee,
+            event.pending_block_blob_fee,
+            &event.state_changes,
+        );
 
         // Process the sub-pool updates
         let UpdateOutcome { promoted, discarded, .. } = self.process_updates(updates);
@@ -190,7 +208,7 @@ impl<T: TransactionOrdering> TxPool<T> {
 
     /// Adds the transaction into the pool.
     ///
-    /// This pool consists of two three-pools: `Queued`, `Pending` and `BaseFee`.
+    /// This pool consists of four sub-pools: `Queued`, `Pending`, `BaseFee` and `Blob`.
     ///
     /// The `Queued` pool contains transactions with gaps in its dependency tree: It requires
     /// additional transactions that are note yet present in the pool. And transactions that the
@@ -208,6 +226,9 @@ impl<T: TransactionOrdering> TxPool<T> {
     /// the sender's balance or nonce and instead their `feeCap` determines whether the
     /// transaction is _currently_ (on the current state) ready or needs to be parked until the
     /// `feeCap` satisfies the block's `baseFee`.
+    ///
+    /// The `Blob` pool contains blob transactions that are otherwise ready but whose
+    /// `maxFeePerBlobGas` is currently below the pending block's blob gas price.
     pub(crate) fn add_transaction(
         &mut self,
         tx: ValidPoolTransaction<T::Transaction>,
@@ -224,6 +245,7 @@ impl<T: TransactionOrdering> TxPool<T> {
 
         match self.all_transactions.insert_tx(tx, on_chain_balance, on_chain_nonce) {
             Ok(InsertOk { transaction, move_to, replaced_tx, updates, .. }) => {
+                let replaced = replaced_tx.as_ref().map(|(tx, _)| tx.clone());
                 self.add_new_transaction(transaction.clone(), replaced_tx, move_to);
                 // Update inserted transactions metric
                 self.metrics.inserted_transactions.increment(1);
@@ -236,9 +258,10 @@ impl<T: TransactionOrdering> TxPool<T> {
                         promoted,
                         discarded,
                         removed,
+                        replaced,
                     })
                 } else {
-                    AddedTransaction::Parked { transaction, subpool: move_to }
+                    AddedTransaction::Parked { transaction, subpool: move_to, replaced }
                 };
 
                 Ok(res)
@@ -253,7 +276,8 @@ impl<T: TransactionOrdering> TxPool<T> {
                     InsertErr::ProtocolFeeCapTooLow { transaction, fee_cap } => {
                         Err(PoolError::ProtocolFeeCapTooLow(*transaction.hash(), fee_cap))
                     }
-                    InsertErr::ExceededSenderTransactionsCapacity { transaction } => {
+                    InsertErr::ExceededSenderTransactionsCapacity { transaction } |
+                    InsertErr::ExceededSenderBlobTransactionsCapacity { transaction } => {
                         Err(PoolError::SpammerExceededCapacity(
                             transaction.sender(),
                             *transaction.hash(),
@@ -348,6 +372,7 @@ impl<T: TransactionOrdering> TxPool<T> {
             SubPool::Queued => self.queued_pool.remove_transaction(tx),
             SubPool::Pending => self.pending_pool.remove_transaction(tx),
             SubPool::BaseFee => self.basefee_pool.remove_transaction(tx),
+            SubPool::Blob => self.blob_pool.remove_transaction(tx),
         }
     }
 
@@ -392,6 +417,9 @@ impl<T: TransactionOrdering> TxPool<T> {
             SubPool::BaseFee => {
                 self.basefee_pool.add_transaction(tx);
             }
+            SubPool::Blob => {
+                self.blob_pool.add_transaction(tx);
+            }
         }
     }
 
@@ -442,6 +470,7 @@ impl<T: TransactionOrdering> TxPool<T> {
             self, removed, [
                 pending_limit  => pending_pool,
                 basefee_limit  => basefee_pool,
+                blob_limit  => blob_pool,
                 queued_limit  => queued_pool
             ]
         );
@@ -475,6 +504,10 @@ impl<T: TransactionOrdering> TxPool<T> {
     pub(crate) fn queued(&self) -> &ParkedPool<QueuedOrd<T::Transaction>> {
         &self.queued_pool
     }
+
+    pub(crate) fn blob(&self) -> &ParkedPool<BlobOrd<T::Transaction>> {
+        &self.blob_pool
+    }
 }
 
 impl<T: TransactionOrdering> fmt::Debug for TxPool<T> {
@@ -490,6 +523,8 @@ impl<T: TransactionOrdering> fmt::Debug for TxPool<T> {
 pub(crate) struct AllTransactions<T: PoolTransaction> {
     /// Expected base fee for the pending block.
     pending_basefee: u128,
+    /// Expected blob gas price for the pending block.
+    pending_blob_fee: u128,
     /// Minimum base fee required by the protocol.
     ///
     /// Transactions with a lower base fee will never be included by the chain
@@ -498,18 +533,22 @@ pub(crate) struct AllTransactions<T: PoolTransaction> {
     block_gas_limit: u64,
     /// Max number of executable transaction slots guaranteed per account
     max_account_slots: usize,
+    /// Max number of blob transactions per account
+    max_account_blob_slots: usize,
     /// _All_ transactions identified by their hash.
     by_hash: HashMap<TxHash, Arc<ValidPoolTransaction<T>>>,
     /// _All_ transaction in the pool sorted by their sender and nonce pair.
     txs: BTreeMap<TransactionId, PoolInternalTransaction<T>>,
     /// Tracks the number of transactions by sender that are currently in the pool.
     tx_counter: FnvHashMap<SenderId, usize>,
+    /// Tracks the number of blob transactions by sender that are currently in the pool.
+    blob_tx_counter: FnvHashMap<SenderId, usize>,
 }
 
 impl<T: PoolTransaction> AllTransactions<T> {
     /// Create a new instance
-    fn new(max_account_slots: usize) -> Self {
-        Self { max_account_slots, ..Default::default() }
+    fn new(max_account_slots: usize, max_account_blob_slots: usize) -> Self {
+        Self { max_account_slots, max_account_blob_slots, ..Default::default() }
     }
 
     /// Returns an iterator over all _unique_ hashes in the pool
@@ -543,7 +582,23 @@ impl<T: PoolTransaction> AllTransactions<T> {
 
     /// Decrements the transaction counter for the sender
     pub(crate) fn tx_decr(&mut self, sender: SenderId) {
-        if let hash_map::Entry::Occupied(mut entry) = self.tx_counter.entry(sender) {
+        Self::counter_decr(&mut self.tx_counter, sender)
+    }
+
+    /// Increments the blob transaction counter for the sender
+    pub(crate) fn blob_tx_inc(&mut self, sender: SenderId) {
+        let count = self.blob_tx_counter.entry(sender).or_default();
+        *count += 1;
+    }
+
+    /// Decrements the blob transaction counter for the sender
+    pub(crate) fn blob_tx_decr(&mut self, sender: SenderId) {
+        Self::counter_decr(&mut self.blob_tx_counter, sender)
+    }
+
+    /// Decrements the sender's entry in the given counter, removing it if it drops to zero.
+    fn counter_decr(counter: &mut FnvHashMap<SenderId, usize>, sender: SenderId) {
+        if let hash_map::Entry::Occupied(mut entry) = counter.entry(sender) {
             let count = entry.get_mut();
             if *count == 1 {
                 entry.remove();
@@ -553,6 +608,14 @@ impl<T: PoolTransaction> AllTransactions<T> {
         }
     }
 
+    /// Decrements all counters that track the given removed transaction.
+    fn on_removed(&mut self, tx: &ValidPoolTransaction<T>) {
+        self.tx_decr(tx.sender_id());
+        if tx.transaction.is_eip4844() {
+            self.blob_tx_decr(tx.sender_id());
+        }
+    }
+
     /// Rechecks all transactions in the pool against the changes.
     ///
     /// Possible changes are:
@@ -565,16 +628,24 @@ impl<T: PoolTransaction> AllTransactions<T> {
     ///   - increased sender allowance: promote from `queued` to
     ///       - `pending` if basefee condition is met.
     ///       - `basefee` if basefee condition is _not_ met.
+    /// For blob transactions:
+    ///   - decreased blob fee: promotes from `blob` to `pending` sub-pool.
+    ///   - increased blob fee: demotes from `pending` to `blob` sub-pool.
     ///
     /// Additionally, this will also update the `cumulative_gas_used` for transactions of a sender
     /// that got transaction included in the block.
     pub(crate) fn update(
         &mut self,
         pending_block_base_fee: u128,
+        pending_block_blob_fee: Option<u128>,
         _state_diffs: &StateDiff,
     ) -> Vec<PoolUpdate> {
         // update new basefee
         self.pending_basefee = pending_block_base_fee;
+        if let Some(pending_block_blob_fee) = pending_block_blob_fee {
+            self.pending_blob_fee = pending_block_blob_fee;
+        }
+        let pending_blob_fee = self.pending_blob_fee;
 
         // TODO(mattsse): probably good idea to allocate some capacity here.
         let mut updates = Vec::new();
@@ -616,6 +687,7 @@ impl<T: PoolTransaction> AllTransactions<T> {
 
             // Update the first transaction of this sender.
             Self::update_base_fee(&pending_block_base_fee, tx);
+            Self::update_blob_fee(&pending_blob_fee, tx);
             // Track if the transaction's sub-pool changed.
             Self::record_subpool_update(&mut updates, tx);
 
@@ -644,6 +716,7 @@ impl<T: PoolTransaction> AllTransactions<T> {
 
                 // Update and record sub-pool changes.
                 Self::update_base_fee(&pending_block_base_fee, tx);
+                Self::update_blob_fee(&pending_blob_fee, tx);
                 Self::record_subpool_update(&mut updates, tx);
 
                 // Advance iterator
@@ -686,6 +759,17 @@ impl<T: PoolTransaction> AllTransactions<T> {
         }
     }
 
+    /// Rechecks the blob transaction's blob fee condition.
+    fn update_blob_fee(pending_blob_fee: &u128, tx: &mut PoolInternalTransaction<T>) {
+        if let Some(blob_fee_cap) = tx.transaction.transaction.max_fee_per_blob_gas() {
+            if blob_fee_cap >= *pending_blob_fee {
+                tx.state.insert(TxState::ENOUGH_BLOB_FEE_CAP_BLOCK);
+            } else {
+                tx.state.remove(TxState::ENOUGH_BLOB_FEE_CAP_BLOCK);
+            }
+        }
+    }
+
     /// Returns an iterator over all transactions for the given sender, starting with the lowest
     /// nonce
     #[cfg(test)]
@@ -763,8 +847,8 @@ impl<T: PoolTransaction> AllTransactions<T> {
     ) -> Option<(Arc<ValidPoolTransaction<T>>, SubPool)> {
         let tx = self.by_hash.remove(tx_hash)?;
         let internal = self.txs.remove(&tx.transaction_id)?;
-        // decrement the counter for the sender.
-        self.tx_decr(tx.sender_id());
+        // decrement the counters for the sender.
+        self.on_removed(&tx);
         Some((tx, internal.subpool))
     }
 
@@ -779,8 +863,8 @@ impl<T: PoolTransaction> AllTransactions<T> {
     ) -> Option<(Arc<ValidPoolTransaction<T>>, SubPool)> {
         let internal = self.txs.remove(id)?;
 
-        // decrement the counter for the sender.
-        self.tx_decr(internal.transaction.sender_id());
+        // decrement the counters for the sender.
+        self.on_removed(&internal.transaction);
 
         self.by_hash.remove(internal.transaction.hash()).map(|tx| (tx, internal.subpool))
     }
@@ -790,6 +874,8 @@ impl<T: PoolTransaction> AllTransactions<T> {
     /// This will enforce all additional rules in the context of this pool, such as:
     ///   - Spam protection: reject new non-local transaction from a sender that exhausted its slot
     ///     capacity.
+    ///   - Blob spam protection: reject new non-local blob transactions from a sender that
+    ///     exhausted its blob slot capacity.
     ///   - Gas limit: reject transactions if they exceed a block's maximum gas.
     fn ensure_valid(
         &self,
@@ -803,6 +889,15 @@ impl<T: PoolTransaction> AllTransactions<T> {
                     transaction: Arc::new(transaction),
                 })
             }
+            if transaction.transaction.is_eip4844() {
+                let current_blob_txs =
+                    self.blob_tx_counter.get(&transaction.sender_id()).copied().unwrap_or_default();
+                if current_blob_txs >= self.max_account_blob_slots {
+                    return Err(InsertErr::ExceededSenderBlobTransactionsCapacity {
+                        transaction: Arc::new(transaction),
+                    })
+                }
+            }
         }
         if transaction.gas_limit() > self.block_gas_limit {
             return Err(InsertErr::TxGasLimitMoreThanAvailableBlockGas {
@@ -860,6 +955,14 @@ impl<T: PoolTransaction> AllTransactions<T> {
             state.insert(TxState::ENOUGH_FEE_CAP_BLOCK);
         }
 
+        // Check blob fee
+        if let Some(blob_fee_cap) = transaction.transaction.max_fee_per_blob_gas() {
+            state.insert(TxState::BLOB_TRANSACTION);
+            if blob_fee_cap >= self.pending_blob_fee {
+                state.insert(TxState::ENOUGH_BLOB_FEE_CAP_BLOCK);
+            }
+        }
+
         // Ensure tx does not exceed block gas limit
         if transaction.gas_limit() < self.block_gas_limit {
             state.insert(TxState::NOT_TOO_MUCH_GAS);
@@ -981,6 +1084,15 @@ impl<T: PoolTransaction> AllTransactions<T> {
         if replaced_tx.is_none() {
             self.tx_inc(tx_id.sender);
         }
+        // The blob counter can change on replacement if the transaction type changes.
+        if let Some((replaced, _)) = &replaced_tx {
+            if replaced.transaction.is_eip4844() {
+                self.blob_tx_decr(tx_id.sender);
+            }
+        }
+        if transaction.transaction.is_eip4844() {
+            self.blob_tx_inc(tx_id.sender);
+        }
 
         Ok(InsertOk { transaction, move_to: state.into(), state, replaced_tx, updates })
     }
@@ -1002,18 +1114,25 @@ impl<T: PoolTransaction> AllTransactions<T> {
     pub(crate) fn tx_count(&self, sender: SenderId) -> usize {
         self.tx_counter.get(&sender).copied().unwrap_or_default()
     }
+
+    pub(crate) fn blob_tx_count(&self, sender: SenderId) -> usize {
+        self.blob_tx_counter.get(&sender).copied().unwrap_or_default()
+    }
 }
 
 impl<T: PoolTransaction> Default for AllTransactions<T> {
     fn default() -> Self {
         Self {
             max_account_slots: MAX_ACCOUNT_SLOTS_PER_SENDER,
+            max_account_blob_slots: MAX_ACCOUNT_BLOB_SLOTS_PER_SENDER,
             pending_basefee: Default::default(),
+            pending_blob_fee: BLOB_TX_MIN_BLOB_GASPRICE,
             minimal_protocol_basefee: MIN_PROTOCOL_BASE_FEE,
             block_gas_limit: 30_000_000,
             by_hash: Default::default(),
             txs: Default::default(),
             tx_counter: Default::default(),
+            blob_tx_counter: Default::default(),
         }
     }
 }
@@ -1034,6 +1153,8 @@ pub(crate) enum InsertErr<T: PoolTransaction> {
     ///
     /// The sender can be considered a spammer at this point.
     ExceededSenderTransactionsCapacity { transaction: Arc<ValidPoolTransaction<T>> },
+    /// Sender currently exceeds the configured limit for blob transactions.
+    ExceededSenderBlobTransactionsCapacity { transaction: Arc<ValidPoolTransaction<T>> },
     /// Transaction gas limit exceeds block's gas limit
     TxGasLimitMoreThanAvailableBlockGas {
         transaction: Arc<ValidPoolTransaction<T>>,
@@ -1343,6 +1464,57 @@ mod tests {
         assert!(matches!(err, InsertErr::ExceededSenderTransactionsCapacity { .. }));
     }
 
+    #[test]
+    fn rejects_blob_spammer() {
+        let on_chain_balance = U256::from(1_000);
+        let on_chain_nonce = 0;
+        let mut f = MockTransactionFactory::default();
+        let mut pool = AllTransactions::default();
+        pool.max_account_blob_slots = 2;
+
+        let mut tx = MockTransaction::eip4844();
+        for _ in 0..pool.max_account_blob_slots {
+            tx = tx.next();
+            pool.insert_tx(f.validated(tx.clone()), on_chain_balance, on_chain_nonce).unwrap();
+        }
+        let sender = f.ids.sender_id(&tx.get_sender()).unwrap();
+        assert_eq!(pool.max_account_blob_slots, pool.blob_tx_count(sender));
+
+        let err =
+            pool.insert_tx(f.validated(tx.next()), on_chain_balance, on_chain_nonce).unwrap_err();
+        assert!(matches!(err, InsertErr::ExceededSenderBlobTransactionsCapacity { .. }));
+
+        // non-blob transactions are not affected by the blob limit
+        let eip1559 = MockTransaction::eip1559().with_sender(tx.get_sender()).with_nonce(4);
+        pool.insert_tx(f.validated(eip1559), on_chain_balance, on_chain_nonce).unwrap();
+
+        let first = TransactionId::new(sender, 1);
+        pool.remove_transaction(&first).unwrap();
+        assert_eq!(pool.blob_tx_count(sender), 1);
+    }
+
+    #[test]
+    fn insert_blob_below_blob_fee() {
+        let on_chain_balance = U256::from(10_000);
+        let on_chain_nonce = 0;
+        let mut f = MockTransactionFactory::default();
+        let mut pool = AllTransactions::default();
+        pool.pending_blob_fee = 10;
+
+        let tx = f.validated(MockTransaction::eip4844().with_blob_fee(5));
+        let InsertOk { state, move_to, .. } =
+            pool.insert_tx(tx.clone(), on_chain_balance, on_chain_nonce).unwrap();
+        assert!(state.contains(TxState::BLOB_TRANSACTION));
+        assert!(!state.contains(TxState::ENOUGH_BLOB_FEE_CAP_BLOCK));
+        assert_eq!(move_to, SubPool::Blob);
+
+        // the blob fee drops, the transaction becomes pending
+        let updates = pool.update(0, Some(5), &StateDiff {});
+        assert_eq!(updates.len(), 1);
+        assert!(matches!(updates[0].destination, Destination::Pool(SubPool::Pending)));
+        assert_eq!(pool.get(tx.id()).unwrap().subpool, SubPool::Pending);
+    }
+
     #[test]
     fn allow_local_spamming() {
         let on_chain_balance = U256::from(1_000);
@@ -12,8 +12,9 @@ use rand::{
     prelude::Distribution,
 };
 use reth_primitives::{
-    Address, FromRecoveredTransaction, IntoRecoveredTransaction, Transaction, TransactionKind,
-    TransactionSignedEcRecovered, TxEip1559, TxHash, TxLegacy, TxType, H256, U128, U256,
+    constants::BLOB_TX_MIN_BLOB_GASPRICE, Address, BlobTransactionSidecar,
+    FromRecoveredTransaction, IntoRecoveredTransaction, Transaction, TransactionKind,
+    TransactionSignedEcRecovered, TxEip1559, TxEip4844, TxHash, TxLegacy, TxType, H256, U128, U256,
 };
 use std::{ops::Range, sync::Arc, time::Instant};
 
@@ -37,6 +38,9 @@ macro_rules! set_value {
             MockTransaction::Eip1559 { ref mut $field, .. } => {
                 *$field = new_value;
             }
+            MockTransaction::Eip4844 { ref mut $field, .. } => {
+                *$field = new_value;
+            }
         }
     };
 }
@@ -47,6 +51,7 @@ macro_rules! get_value {
         match $this {
             MockTransaction::Legacy { $field, .. } => $field,
             MockTransaction::Eip1559 { $field, .. } => $field,
+            MockTransaction::Eip4844 { $field, .. } => $field,
         }
     };
 }
@@ -98,6 +103,17 @@ pub enum MockTransaction {
         to: TransactionKind,
         value: U256,
     },
+    Eip4844 {
+        hash: H256,
+        sender: Address,
+        nonce: u64,
+        max_fee_per_gas: u128,
+        max_priority_fee_per_gas: u128,
+        max_fee_per_blob_gas: u128,
+        gas_limit: u64,
+        to: TransactionKind,
+        value: U256,
+    },
 }
 
 // === impl MockTransaction ===
@@ -138,22 +154,55 @@ impl MockTransaction {
         }
     }
 
+    /// Returns a new EIP4844 transaction with random address and hash and empty values
+    pub fn eip4844() -> Self {
+        MockTransaction::Eip4844 {
+            hash: H256::random(),
+            sender: Address::random(),
+            nonce: 0,
+            max_fee_per_gas: MIN_PROTOCOL_BASE_FEE,
+            max_priority_fee_per_gas: MIN_PROTOCOL_BASE_FEE,
+            max_fee_per_blob_gas: BLOB_TX_MIN_BLOB_GASPRICE,
+            gas_limit: 0,
+            to: TransactionKind::Call(Address::random()),
+            value: Default::default(),
+        }
+    }
+
+    pub fn set_blob_fee(&mut self, val: u128) -> &mut Self {
+        if let MockTransaction::Eip4844 { max_fee_per_blob_gas, .. } = self {
+            *max_fee_per_blob_gas = val;
+        }
+        self
+    }
+
+    pub fn with_blob_fee(mut self, val: u128) -> Self {
+        self.set_blob_fee(val);
+        self
+    }
+
     pub fn set_priority_fee(&mut self, val: u128) -> &mut Self {
-        if let MockTransaction::Eip1559 { max_priority_fee_per_gas, .. } = self {
+        if let MockTransaction::Eip1559 { max_priority_fee_per_gas, .. } |
+        MockTransaction::Eip4844 { max_priority_fee_per_gas, .. } = self
+        {
             *max_priority_fee_per_gas = val;
         }
         self
     }
 
     pub fn with_priority_fee(mut self, val: u128) -> Self {
-        if let MockTransaction::Eip1559 { ref mut max_priority_fee_per_gas, .. } = self {
+        if let MockTransaction::Eip1559 { ref mut max_priority_fee_per_gas, .. } |
+        MockTransaction::Eip4844 { ref mut max_priority_fee_per_gas, .. } = self
+        {
             *max_priority_fee_per_gas = val;
         }
         self
     }
 
     pub fn get_priority_fee(&self) -> Option<u128> {
-        if let MockTransaction::Eip1559 { max_priority_fee_per_gas, .. } = self {
+        if let MockTransaction::Eip1559 { max_priority_fee_per_gas, .. } |
+        MockTransaction::Eip4844 { max_priority_fee_per_gas, .. } = self
+        {
             Some(*max_priority_fee_per_gas)
         } else {
             None
@@ -161,21 +210,27 @@ impl MockTransaction {
     }
 
     pub fn set_max_fee(&mut self, val: u128) -> &mut Self {
-        if let MockTransaction::Eip1559 { max_fee_per_gas, .. } = self {
+        if let MockTransaction::Eip1559 { max_fee_per_gas, .. } |
+        MockTransaction::Eip4844 { max_fee_per_gas, .. } = self
+        {
             *max_fee_per_gas = val;
         }
         self
     }
 
     pub fn with_max_fee(mut self, val: u128) -> Self {
-        if let MockTransaction::Eip1559 { ref mut max_fee_per_gas, .. } = self {
+        if let MockTransaction::Eip1559 { ref mut max_fee_per_gas, .. } |
+        MockTransaction::Eip4844 { ref mut max_fee_per_gas, .. } = self
+        {
             *max_fee_per_gas = val;
         }
         self
     }
 
     pub fn get_max_fee(&self) -> Option<u128> {
-        if let MockTransaction::Eip1559 { max_fee_per_gas, .. } = self {
+        if let MockTransaction::Eip1559 { max_fee_per_gas, .. } |
+        MockTransaction::Eip4844 { max_fee_per_gas, .. } = self
+        {
             Some(*max_fee_per_gas)
         } else {
             None
@@ -187,7 +242,8 @@ impl MockTransaction {
             MockTransaction::Legacy { gas_price, .. } => {
                 *gas_price = val;
             }
-            MockTransaction::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas, .. } => {
+            MockTransaction::Eip1559 { max_fee_per_gas, max_priority_fee_per_gas, .. } |
+            MockTransaction::Eip4844 { max_fee_per_gas, max_priority_fee_per_gas, .. } => {
                 *max_fee_per_gas = val;
                 *max_priority_fee_per_gas = val;
             }
@@ -204,6 +260,11 @@ impl MockTransaction {
                 ref mut max_fee_per_gas,
                 ref mut max_priority_fee_per_gas,
                 ..
+            } |
+            MockTransaction::Eip4844 {
+                ref mut max_fee_per_gas,
+                ref mut max_priority_fee_per_gas,
+                ..
             } => {
                 *max_fee_per_gas = val;
                 *max_priority_fee_per_gas = val;
@@ -215,7 +276,8 @@ impl MockTransaction {
     pub fn get_gas_price(&self) -> u128 {
         match self {
             MockTransaction::Legacy { gas_price, .. } => *gas_price,
-            MockTransaction::Eip1559 { max_fee_per_gas, .. } => *max_fee_per_gas,
+            MockTransaction::Eip1559 { max_fee_per_gas, .. } |
+            MockTransaction::Eip4844 { max_fee_per_gas, .. } => *max_fee_per_gas,
         }
     }
 
@@ -276,6 +338,10 @@ impl MockTransaction {
     pub fn is_eip1559(&self) -> bool {
         matches!(self, MockTransaction::Eip1559 { .. })
     }
+
+    pub fn is_eip4844(&self) -> bool {
+        matches!(self, MockTransaction::Eip4844 { .. })
+    }
 }
 
 impl PoolTransaction for MockTransaction {
@@ -283,6 +349,7 @@ impl PoolTransaction for MockTransaction {
         match self {
             MockTransaction::Legacy { hash, .. } => hash,
             MockTransaction::Eip1559 { hash, .. } => hash,
+            MockTransaction::Eip4844 { hash, .. } => hash,
         }
     }
 
@@ -290,6 +357,7 @@ impl PoolTransaction for MockTransaction {
         match self {
             MockTransaction::Legacy { sender, .. } => *sender,
             MockTransaction::Eip1559 { sender, .. } => *sender,
+            MockTransaction::Eip4844 { sender, .. } => *sender,
         }
     }
 
@@ -297,6 +365,7 @@ impl PoolTransaction for MockTransaction {
         match self {
             MockTransaction::Legacy { nonce, .. } => *nonce,
             MockTransaction::Eip1559 { nonce, .. } => *nonce,
+            MockTransaction::Eip4844 { nonce, .. } => *nonce,
         }
     }
 
@@ -308,6 +377,9 @@ impl PoolTransaction for MockTransaction {
             MockTransaction::Eip1559 { max_fee_per_gas, value, gas_limit, .. } => {
                 U256::from(*gas_limit) * U256::from(*max_fee_per_gas) + *value
             }
+            MockTransaction::Eip4844 { max_fee_per_gas, value, gas_limit, .. } => {
+                U256::from(*gas_limit) * U256::from(*max_fee_per_gas) + *value
+            }
         }
     }
 
@@ -322,23 +394,52 @@ impl PoolTransaction for MockTransaction {
     fn max_fee_per_gas(&self) -> Option<u128> {
         match self {
             MockTransaction::Legacy { .. } => None,
-            MockTransaction::Eip1559 { max_fee_per_gas, .. } => Some(*max_fee_per_gas),
+            MockTransaction::Eip1559 { max_fee_per_gas, .. } |
+            MockTransaction::Eip4844 { max_fee_per_gas, .. } => Some(*max_fee_per_gas),
         }
     }
 
     fn max_priority_fee_per_gas(&self) -> Option<u128> {
         match self {
             MockTransaction::Legacy { .. } => None,
-            MockTransaction::Eip1559 { max_priority_fee_per_gas, .. } => {
+            MockTransaction::Eip1559 { max_priority_fee_per_gas, .. } |
+            MockTransaction::Eip4844 { max_priority_fee_per_gas, .. } => {
                 Some(*max_priority_fee_per_gas)
             }
         }
     }
 
+    fn max_fee_per_blob_gas(&self) -> Option<u128> {
+        match self {
+            MockTransaction::Eip4844 { max_fee_per_blob_gas, .. } => Some(*max_fee_per_blob_gas),
+            _ => None,
+        }
+    }
+
+    fn blob_versioned_hashes(&self) -> Option<&[H256]> {
+        None
+    }
+
+    fn blob_sidecar(&self) -> Option<&BlobTransactionSidecar> {
+        None
+    }
+
+    fn take_blob_sidecar(&mut self) -> Option<BlobTransactionSidecar> {
+        None
+    }
+
+    fn from_recovered_blob_transaction(
+        tx: TransactionSignedEcRecovered,
+        _sidecar: BlobTransactionSidecar,
+    ) -> Self {
+        Self::from_recovered_transaction(tx)
+    }
+
     fn kind(&self) -> &TransactionKind {
         match self {
             MockTransaction::Legacy { to, .. } => to,
             MockTransaction::Eip1559 { to, .. } => to,
+            MockTransaction::Eip4844 { to, .. } => to,
         }
     }
 
@@ -350,6 +451,7 @@ impl PoolTransaction for MockTransaction {
         match self {
             MockTransaction::Legacy { .. } => TxType::Legacy.into(),
             MockTransaction::Eip1559 { .. } => TxType::EIP1559.into(),
+            MockTransaction::Eip4844 { .. } => TxType::EIP4844.into(),
         }
     }
 
@@ -401,6 +503,26 @@ impl FromRecoveredTransaction for MockTransaction {
                 to,
                 value: U256::from(value),
             },
+            Transaction::Eip4844(TxEip4844 {
+                nonce,
+                gas_limit,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                max_fee_per_blob_gas,
+                to,
+                value,
+                ..
+            }) => MockTransaction::Eip4844 {
+                hash,
+                sender,
+                nonce,
+                max_fee_per_gas,
+                max_priority_fee_per_gas,
+                max_fee_per_blob_gas,
+                gas_limit,
+                to,
+                value: U256::from(value),
+            },
             Transaction::Eip2930 { .. } => {
                 unimplemented!()
             }
@@ -1,7 +1,11 @@
-use crate::{error::PoolResult, pool::state::SubPool, validate::ValidPoolTransaction};
+use crate::{
+    blobstore::BlobStoreError, error::PoolResult, pool::state::SubPool,
+    validate::ValidPoolTransaction,
+};
 use reth_primitives::{
-    Address, FromRecoveredTransaction, IntoRecoveredTransaction, PeerId, Transaction,
-    TransactionKind, TransactionSignedEcRecovered, TxHash, H256, U256,
+    Address, BlobTransaction, BlobTransactionSidecar, FromRecoveredTransaction,
+    IntoRecoveredTransaction, PeerId, Transaction, TransactionKind, TransactionSignedEcRecovered,
+    TxHash, EIP4844_TX_TYPE_ID, H256, U256,
 };
 use reth_rlp::Encodable;
 use std::{collections::HashMap, fmt, sync::Arc};
@@ -132,6 +136,21 @@ pub trait TransactionPool: Send + Sync + Clone {
     ///
     /// Consumer: P2P
     fn on_propagated(&self, txs: PropagatedTransactions);
+
+    /// Returns the sidecar of the blob transaction with the given hash, if it exists.
+    ///
+    /// Consumer: P2P
+    fn get_blob(&self, tx_hash: TxHash) -> Result<Option<BlobTransactionSidecar>, BlobStoreError>;
+
+    /// Returns the sidecars of all blob transactions for the given hashes.
+    ///
+    /// Hashes without a sidecar are skipped.
+    ///
+    /// Consumer: P2P
+    fn get_all_blobs(
+        &self,
+        tx_hashes: Vec<TxHash>,
+    ) -> Result<Vec<(TxHash, BlobTransactionSidecar)>, BlobStoreError>;
 }
 
 /// Represents a transaction that was propagated over the network.
@@ -219,6 +238,8 @@ pub struct OnNewBlockEvent {
     ///
     /// The base fee of a block depends on the utilization of the last block and its base fee.
     pub pending_block_base_fee: u128,
+    /// EIP-4844 blob gas price of the _next_ (pending) block, if Cancun is active.
+    pub pending_block_blob_fee: Option<u128>,
     /// Provides a set of state changes that affected the accounts.
     pub state_changes: StateDiff,
     /// All mined transactions in the block
@@ -283,6 +304,30 @@ pub trait PoolTransaction: fmt::Debug + Send + Sync + FromRecoveredTransaction {
     /// This will return `None` for non-EIP1559 transactions
     fn max_priority_fee_per_gas(&self) -> Option<u128>;
 
+    /// Returns the EIP-4844 max fee per blob gas the caller is willing to pay.
+    ///
+    /// This will return `None` for non-EIP4844 transactions
+    fn max_fee_per_blob_gas(&self) -> Option<u128>;
+
+    /// Returns the versioned hashes of the blobs the transaction carries.
+    ///
+    /// This will return `None` for non-EIP4844 transactions
+    fn blob_versioned_hashes(&self) -> Option<&[H256]>;
+
+    /// Returns the sidecar of a blob transaction, if it is (still) attached.
+    fn blob_sidecar(&self) -> Option<&BlobTransactionSidecar>;
+
+    /// Detaches the sidecar of a blob transaction.
+    ///
+    /// Sidecars are not kept in the pool but in a [`BlobStore`](crate::blobstore::BlobStore).
+    fn take_blob_sidecar(&mut self) -> Option<BlobTransactionSidecar>;
+
+    /// Converts to this type from a recovered blob transaction and its sidecar.
+    fn from_recovered_blob_transaction(
+        tx: TransactionSignedEcRecovered,
+        sidecar: BlobTransactionSidecar,
+    ) -> Self;
+
     /// Returns the transaction's [`TransactionKind`], which is the address of the recipient or
     /// [`TransactionKind::Create`] if the transaction is a contract creation.
     fn kind(&self) -> &TransactionKind;
@@ -293,6 +338,11 @@ pub trait PoolTransaction: fmt::Debug + Send + Sync + FromRecoveredTransaction {
     /// Returns the transaction type
     fn tx_type(&self) -> u8;
 
+    /// Returns `true` if this is an EIP-4844 blob transaction.
+    fn is_eip4844(&self) -> bool {
+        self.tx_type() == EIP4844_TX_TYPE_ID
+    }
+
     /// Returns the length of the rlp encoded object
     fn encoded_length(&self) -> usize;
 }
@@ -311,6 +361,9 @@ pub struct PooledTransaction {
 
     /// This is `priority + basefee`for EIP-1559 and `gasPrice` for legacy transactions.
     pub(crate) effective_gas_price: u128,
+
+    /// The sidecar of a blob transaction, until it is moved to the blob store.
+    pub(crate) blob_sidecar: Option<BlobTransactionSidecar>,
 }
 
 impl PooledTransaction {
@@ -363,6 +416,7 @@ impl PoolTransaction for PooledTransaction {
             Transaction::Legacy(_) => None,
             Transaction::Eip2930(_) => None,
             Transaction::Eip1559(tx) => Some(tx.max_fee_per_gas),
+            Transaction::Eip4844(tx) => Some(tx.max_fee_per_gas),
         }
     }
 
@@ -374,9 +428,42 @@ impl PoolTransaction for PooledTransaction {
             Transaction::Legacy(_) => None,
             Transaction::Eip2930(_) => None,
             Transaction::Eip1559(tx) => Some(tx.max_priority_fee_per_gas),
+            Transaction::Eip4844(tx) => Some(tx.max_priority_fee_per_gas),
         }
     }
 
+    /// Returns the EIP-4844 max fee per blob gas the caller is willing to pay.
+    ///
+    /// This will return `None` for non-EIP4844 transactions
+    fn max_fee_per_blob_gas(&self) -> Option<u128> {
+        self.transaction.max_fee_per_blob_gas()
+    }
+
+    /// Returns the versioned hashes of the blobs the transaction carries.
+    fn blob_versioned_hashes(&self) -> Option<&[H256]> {
+        self.transaction.blob_versioned_hashes()
+    }
+
+    /// Returns the attached sidecar of a blob transaction.
+    fn blob_sidecar(&self) -> Option<&BlobTransactionSidecar> {
+        self.blob_sidecar.as_ref()
+    }
+
+    /// Detaches the sidecar of a blob transaction.
+    fn take_blob_sidecar(&mut self) -> Option<BlobTransactionSidecar> {
+        self.blob_sidecar.take()
+    }
+
+    /// Converts to this type from a recovered blob transaction and its sidecar.
+    fn from_recovered_blob_transaction(
+        tx: TransactionSignedEcRecovered,
+        sidecar: BlobTransactionSidecar,
+    ) -> Self {
+        let mut pooled = Self::from_recovered_transaction(tx);
+        pooled.blob_sidecar = Some(sidecar);
+        pooled
+    }
+
     /// Returns the transaction's [`TransactionKind`], which is the address of the recipient or
     /// [`TransactionKind::Create`] if the transaction is a contract creation.
     fn kind(&self) -> &TransactionKind {
@@ -394,8 +481,20 @@ impl PoolTransaction for PooledTransaction {
     }
 
     /// Returns the length of the rlp encoded object
+    ///
+    /// For blob transactions with an attached sidecar this is the length of the network form.
     fn encoded_length(&self) -> usize {
-        self.transaction.length()
+        match &self.blob_sidecar {
+            Some(sidecar) => {
+                let length = BlobTransaction {
+                    transaction: self.transaction.clone().into_signed(),
+                    sidecar: sidecar.clone(),
+                }
+                .enveloped_length();
+                length + reth_rlp::length_of_length(length)
+            }
+            None => self.transaction.length(),
+        }
     }
 }
 
@@ -418,9 +517,17 @@ impl FromRecoveredTransaction for PooledTransaction {
                 let effective_gas_price = t.max_priority_fee_per_gas;
                 (cost, effective_gas_price)
             }
+            Transaction::Eip4844(t) => {
+                // the blob fee is paid upfront as well
+                let cost = U256::from(t.max_fee_per_gas) * U256::from(t.gas_limit) +
+                    U256::from(t.max_fee_per_blob_gas) * U256::from(t.blob_gas()) +
+                    U256::from(t.value);
+                let effective_gas_price = t.max_priority_fee_per_gas;
+                (cost, effective_gas_price)
+            }
         };
 
-        PooledTransaction { transaction: tx, cost, effective_gas_price }
+        PooledTransaction { transaction: tx, cost, effective_gas_price, blob_sidecar: None }
     }
 }
 
@@ -445,4 +552,8 @@ pub struct PoolSize {
     pub queued: usize,
     /// Reported size of transactions in the _queued_ sub-pool.
     pub queued_size: usize,
+    /// Number of transactions in the _blob_ sub-pool.
+    pub blob: usize,
+    /// Reported size of transactions in the _blob_ sub-pool.
+    pub blob_size: usize,
 }
@@ -5,8 +5,11 @@ use crate::{
     identifier::{SenderId, TransactionId},
     traits::{PoolTransaction, TransactionOrigin},
 };
-use reth_primitives::{Address, TransactionKind, TxHash, U256};
-use std::{fmt, time::Instant};
+use reth_primitives::{
+    constants::MAX_BLOBS_PER_BLOCK, eip4844::KzgSettings, Address, TransactionKind, TxHash, U256,
+};
+use reth_provider::{AccountProvider, StateProviderFactory};
+use std::{fmt, marker::PhantomData, sync::Arc, time::Instant};
 
 /// A Result type returned after checking a transaction's validity.
 #[derive(Debug)]
@@ -80,6 +83,127 @@ pub trait TransactionValidator: Send + Sync {
     }
 }
 
+/// A [TransactionValidator] implementation that validates ethereum transaction against the
+/// latest state of the chain.
+///
+/// This looks up the sender's account to determine its on chain nonce and balance. Transactions
+/// with a nonce lower than the on chain nonce of the sender are rejected.
+///
+/// Blob transactions are only accepted if a KZG trusted setup is configured via
+/// [`EthTransactionValidator::with_kzg_settings`] to verify the proofs of their sidecar.
+#[derive(Clone)]
+pub struct EthTransactionValidator<Client, T> {
+    /// This type fetches account info from the db
+    client: Client,
+    /// The KZG trusted setup used to verify the proofs of blob sidecars
+    kzg_settings: Option<Arc<KzgSettings>>,
+    /// Marker for the transaction type
+    _marker: PhantomData<T>,
+}
+
+// === impl EthTransactionValidator ===
+
+impl<Client, T> EthTransactionValidator<Client, T> {
+    /// Creates a new validator that looks up account state via the given client.
+    pub fn new(client: Client) -> Self {
+        Self { client, kzg_settings: None, _marker: Default::default() }
+    }
+
+    /// Sets the KZG trusted setup used to verify blob transactions.
+    pub fn with_kzg_settings(mut self, kzg_settings: Arc<KzgSettings>) -> Self {
+        self.kzg_settings = Some(kzg_settings);
+        self
+    }
+}
+
+impl<Client: fmt::Debug, T> fmt::Debug for EthTransactionValidator<Client, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EthTransactionValidator")
+            .field("client", &self.client)
+            .field("has_kzg_settings", &self.kzg_settings.is_some())
+            .finish_non_exhaustive()
+    }
+}
+
+#[async_trait::async_trait]
+impl<Client, T> TransactionValidator for EthTransactionValidator<Client, T>
+where
+    Client: StateProviderFactory + 'static,
+    T: PoolTransaction + 'static,
+{
+    type Transaction = T;
+
+    async fn validate_transaction(
+        &self,
+        _origin: TransactionOrigin,
+        transaction: Self::Transaction,
+    ) -> TransactionValidationOutcome<Self::Transaction> {
+        if transaction.is_eip4844() {
+            if let Err(err) = ensure_valid_blob_sidecar(&transaction, self.kzg_settings.as_deref())
+            {
+                return TransactionValidationOutcome::Invalid(transaction, err)
+            }
+        }
+
+        let account = match self
+            .client
+            .latest()
+            .and_then(|state| state.basic_account(transaction.sender()))
+        {
+            Ok(account) => account.unwrap_or_default(),
+            Err(err) => {
+                let hash = *transaction.hash();
+                return TransactionValidationOutcome::Invalid(
+                    transaction,
+                    PoolError::Provider(hash, err),
+                )
+            }
+        };
+
+        // Checks for nonce
+        if transaction.nonce() < account.nonce {
+            let hash = *transaction.hash();
+            let nonce = transaction.nonce();
+            return TransactionValidationOutcome::Invalid(
+                transaction,
+                PoolError::NonceTooLow(hash, nonce, account.nonce),
+            )
+        }
+
+        TransactionValidationOutcome::Valid {
+            balance: account.balance,
+            state_nonce: account.nonce,
+            transaction,
+        }
+    }
+}
+
+/// Ensures that a blob transaction is accompanied by a sidecar that matches its versioned hashes
+/// and whose KZG proofs are valid.
+fn ensure_valid_blob_sidecar<T: PoolTransaction>(
+    transaction: &T,
+    kzg_settings: Option<&KzgSettings>,
+) -> Result<(), PoolError> {
+    let hash = *transaction.hash();
+    let sidecar = transaction.blob_sidecar().ok_or(PoolError::MissingBlobSidecar(hash))?;
+    let versioned_hashes = transaction.blob_versioned_hashes().unwrap_or_default();
+
+    let max_blobs = MAX_BLOBS_PER_BLOCK as usize;
+    if versioned_hashes.is_empty() || versioned_hashes.len() > max_blobs {
+        return Err(PoolError::InvalidBlobCount(hash, versioned_hashes.len(), max_blobs))
+    }
+
+    if !sidecar.matches_versioned_hashes(versioned_hashes) {
+        return Err(PoolError::InvalidBlobSidecar(hash))
+    }
+
+    let kzg_settings = kzg_settings.ok_or(PoolError::MissingKzgSettings(hash))?;
+    if !sidecar.verify_kzg_proofs(kzg_settings).unwrap_or_default() {
+        return Err(PoolError::InvalidBlobProof(hash))
+    }
+    Ok(())
+}
+
 /// A valid transaction in the pool.
 pub struct ValidPoolTransaction<T: PoolTransaction> {
     /// The transaction